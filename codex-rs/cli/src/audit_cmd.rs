@@ -0,0 +1,211 @@
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use codex_common::CliConfigOverrides;
+use codex_core::audit::ApprovalSubject;
+use codex_core::audit::AuditEvent;
+use codex_core::audit::AuditQuery;
+use codex_core::audit::AuditRecord;
+use codex_core::audit::read_audit_log;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_protocol::mcp_protocol::ConversationId;
+use serde::Serialize;
+
+const AUDIT_EVENT_TYPES: [&str; 6] = [
+    "policy",
+    "approval",
+    "exec",
+    "patch",
+    "mcp_tool_call",
+    "escalation",
+];
+
+/// Query the audit log (`~/.codex/audit.jsonl`) of commands, patches, and
+/// approvals.
+#[derive(Debug, clap::Parser)]
+pub struct AuditCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Only show records at or after this time (RFC 3339 or YYYY-MM-DD).
+    #[arg(long, value_parser = parse_since, value_name = "TIME")]
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only show records of this session.
+    #[arg(long = "session", value_parser = parse_session_id, value_name = "SESSION_ID")]
+    pub session_id: Option<ConversationId>,
+
+    /// Only show records of this type. May be repeated.
+    #[arg(
+        long = "type",
+        value_name = "TYPE",
+        value_parser = clap::builder::PossibleValuesParser::new(AUDIT_EVENT_TYPES)
+    )]
+    pub types: Vec<String>,
+
+    /// Only show the most recent N matching records.
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+
+    /// Output the matching records as JSON Lines.
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl AuditCli {
+    pub fn run(self) -> Result<()> {
+        let overrides = self
+            .config_overrides
+            .parse_overrides()
+            .map_err(|e| anyhow!(e))?;
+        let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+            .context("failed to load configuration")?;
+
+        let query = AuditQuery {
+            since: self.since,
+            conversation_id: self.session_id,
+            kinds: self.types,
+        };
+        let mut records =
+            read_audit_log(&config.codex_home, &query).context("failed to read audit log")?;
+        if let Some(limit) = self.limit {
+            records.drain(..records.len().saturating_sub(limit));
+        }
+
+        if self.json {
+            for record in &records {
+                println!("{}", serde_json::to_string(record)?);
+            }
+            return Ok(());
+        }
+
+        if records.is_empty() {
+            println!("No matching audit records.");
+            return Ok(());
+        }
+
+        let rows: Vec<[String; 4]> = records.iter().map(format_row).collect();
+        let mut widths = ["Time".len(), "Session".len(), "Type".len()];
+        for row in &rows {
+            for (i, width) in widths.iter_mut().enumerate() {
+                *width = (*width).max(row[i].len());
+            }
+        }
+
+        println!(
+            "{:<time_w$}  {:<session_w$}  {:<type_w$}  Details",
+            "Time",
+            "Session",
+            "Type",
+            time_w = widths[0],
+            session_w = widths[1],
+            type_w = widths[2],
+        );
+        for row in rows {
+            println!(
+                "{:<time_w$}  {:<session_w$}  {:<type_w$}  {}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                time_w = widths[0],
+                session_w = widths[1],
+                type_w = widths[2],
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn format_row(record: &AuditRecord) -> [String; 4] {
+    let details = match &record.event {
+        AuditEvent::Policy {
+            cwd,
+            approval_policy,
+            sandbox_policy,
+        } => format!(
+            "approval={approval_policy} sandbox={sandbox_policy} cwd={}",
+            cwd.display()
+        ),
+        AuditEvent::Approval {
+            subject, decision, ..
+        } => {
+            let subject = match subject {
+                ApprovalSubject::Exec { command, .. } => join_command(command),
+                ApprovalSubject::Patch { files } => join_files(files),
+            };
+            format!("{}: {subject}", serialized_name(decision))
+        }
+        AuditEvent::Exec {
+            command,
+            sandbox,
+            exit_code,
+            ..
+        } => format!(
+            "{} (exit {exit_code}, sandbox {})",
+            join_command(command),
+            serialized_name(sandbox)
+        ),
+        AuditEvent::Patch { files, success, .. } => {
+            let status = if *success { "applied" } else { "failed" };
+            format!("{status}: {}", join_files(files))
+        }
+        AuditEvent::McpToolCall {
+            server,
+            tool,
+            success,
+            ..
+        } => {
+            let status = if *success { "ok" } else { "error" };
+            format!("{server}.{tool} ({status})")
+        }
+        AuditEvent::Escalation {
+            command, reason, ..
+        } => format!("{} ({reason})", join_command(command)),
+    };
+    [
+        record.ts.to_rfc3339_opts(SecondsFormat::Secs, true),
+        record.conversation_id.to_string(),
+        record.event.kind().to_string(),
+        details,
+    ]
+}
+
+fn join_command(command: &[String]) -> String {
+    shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "))
+}
+
+fn join_files(files: &[std::path::PathBuf]) -> String {
+    files
+        .iter()
+        .map(|f| f.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Name of a unit enum variant as it appears in the JSON records.
+fn serialized_name(value: &impl Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        .map_err(|_| format!("invalid time `{value}`; expected RFC 3339 or YYYY-MM-DD"))
+}
+
+fn parse_session_id(value: &str) -> Result<ConversationId, String> {
+    ConversationId::from_string(value).map_err(|e| format!("invalid session id `{value}`: {e}"))
+}
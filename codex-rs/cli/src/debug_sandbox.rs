@@ -105,6 +105,7 @@ async fn run_command_under_sandbox(
                 .expect("codex-linux-sandbox executable not found");
             spawn_command_under_linux_sandbox(
                 codex_linux_sandbox_exe,
+                config.linux_sandbox_backend,
                 command,
                 cwd,
                 &config.sandbox_policy,
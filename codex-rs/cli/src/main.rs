@@ -20,10 +20,16 @@ use owo_colors::OwoColorize;
 use std::path::PathBuf;
 use supports_color::Stream;
 
+mod audit_cmd;
 mod mcp_cmd;
+mod review_cmd;
+mod sessions_cmd;
 
+use crate::audit_cmd::AuditCli;
 use crate::mcp_cmd::McpCli;
 use crate::proto::ProtoCli;
+use crate::review_cmd::ReviewCli;
+use crate::sessions_cmd::SessionsCli;
 
 /// Codex CLI
 ///
@@ -82,6 +88,16 @@ enum Subcommand {
     /// Resume a previous interactive session (picker by default; use --last to continue the most recent).
     Resume(ResumeCommand),
 
+    /// Query the audit log of commands, patches, and approvals.
+    Audit(AuditCli),
+
+    /// Search, export, diff and prune recorded sessions.
+    Sessions(SessionsCli),
+
+    /// Review changes and report prioritized findings; exits with status 1
+    /// when a finding reaches `--fail-on-priority`.
+    Review(ReviewCli),
+
     /// Internal: generate TypeScript protocol bindings.
     #[clap(hide = true)]
     GenerateTs(GenerateTsCommand),
@@ -309,6 +325,27 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             );
             run_apply_command(apply_cli, None).await?;
         }
+        Some(Subcommand::Audit(mut audit_cli)) => {
+            prepend_config_flags(
+                &mut audit_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            audit_cli.run()?;
+        }
+        Some(Subcommand::Sessions(mut sessions_cli)) => {
+            prepend_config_flags(
+                &mut sessions_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            sessions_cli.run().await?;
+        }
+        Some(Subcommand::Review(mut review_cli)) => {
+            prepend_config_flags(
+                &mut review_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            review_cli.run(codex_linux_sandbox_exe).await?;
+        }
         Some(Subcommand::GenerateTs(gen_cli)) => {
             codex_protocol_ts::generate_ts(&gen_cli.out_dir, gen_cli.prettier.as_deref())?;
         }
@@ -0,0 +1,216 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
+use codex_core::NewConversation;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::git_info::get_git_repo_root;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewOutputEvent;
+use codex_core::protocol::ReviewTarget;
+use codex_core::review_format::format_review_findings_block;
+use codex_core::review_report::review_output_to_rdjson;
+use codex_core::review_report::review_output_to_sarif;
+use codex_core::review_target::review_request_for_target;
+use codex_protocol::config_types::SandboxMode;
+
+/// Exit status when a finding is at or above the `--fail-on-priority`
+/// threshold.
+const FINDINGS_EXIT_CODE: i32 = 1;
+
+/// Review changes with Codex and report prioritized findings. Reviews the
+/// uncommitted changes unless another target is given.
+#[derive(Debug, clap::Parser)]
+pub struct ReviewCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Review only the staged changes.
+    #[arg(long, conflicts_with_all = ["commit", "range", "base", "files"])]
+    pub staged: bool,
+
+    /// Review the changes introduced by this commit.
+    #[arg(long, value_name = "SHA", conflicts_with_all = ["range", "base", "files"])]
+    pub commit: Option<String>,
+
+    /// Review the changes between two revisions.
+    #[arg(long, value_name = "A..B", value_parser = parse_range, conflicts_with_all = ["base", "files"])]
+    pub range: Option<(String, String)>,
+
+    /// Review the changes on the current branch since it diverged from
+    /// this branch.
+    #[arg(long, value_name = "BRANCH", conflicts_with = "files")]
+    pub base: Option<String>,
+
+    /// Review the full contents of these files.
+    #[arg(value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    /// Directory to review; defaults to the current directory.
+    #[arg(long = "cd", short = 'C', value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Additional instructions for the reviewer.
+    #[arg(long, value_name = "TEXT")]
+    pub prompt: Option<String>,
+
+    /// Exit with status 1 when a finding has this priority or a more urgent
+    /// one (0 is P0, the most urgent, through 3 for P3).
+    #[arg(
+        long,
+        value_name = "PRIORITY",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(i32).range(0..=3)
+    )]
+    pub fail_on_priority: i32,
+
+    /// How to print the findings. The SARIF and rdjson reports are only
+    /// available here; `codex exec` does not run reviews or produce them.
+    #[arg(long, value_enum, default_value_t = ReviewOutputFormat::Text)]
+    pub format: ReviewOutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReviewOutputFormat {
+    /// Findings with `path:start-end` anchors.
+    Text,
+    /// The review output as JSON.
+    Json,
+    /// A SARIF 2.1.0 log for code scanning dashboards.
+    Sarif,
+    /// reviewdog's Diagnostic Format (rdjson).
+    Rdjson,
+}
+
+impl ReviewCli {
+    pub async fn run(self, codex_linux_sandbox_exe: Option<PathBuf>) -> Result<()> {
+        let overrides = self
+            .config_overrides
+            .parse_overrides()
+            .map_err(|e| anyhow!(e))?;
+        let config = Config::load_with_cli_overrides(
+            overrides,
+            ConfigOverrides {
+                // The reviewer only reads the code and nobody is around to
+                // approve anything.
+                approval_policy: Some(AskForApproval::Never),
+                sandbox_mode: Some(SandboxMode::ReadOnly),
+                cwd: self.cwd.clone(),
+                codex_linux_sandbox_exe,
+                ..Default::default()
+            },
+        )
+        .context("failed to load configuration")?;
+
+        let mut review_request = review_request_for_target(self.target());
+        if let Some(prompt) = &self.prompt {
+            review_request.prompt = format!("{}\n\n{prompt}", review_request.prompt);
+        }
+
+        // Reports use paths relative to the repository root.
+        let root = get_git_repo_root(&config.cwd).unwrap_or_else(|| config.cwd.clone());
+        let conversation_manager =
+            ConversationManager::new(AuthManager::shared(config.codex_home.clone()));
+        let NewConversation { conversation, .. } =
+            conversation_manager.new_conversation(config).await?;
+        conversation.submit(Op::Review { review_request }).await?;
+        let review_output = loop {
+            let event = conversation.next_event().await?;
+            match event.msg {
+                EventMsg::ExitedReviewMode(ev) => break ev.review_output,
+                EventMsg::Error(ev) => bail!(ev.message),
+                EventMsg::TurnAborted(ev) => bail!("review aborted: {:?}", ev.reason),
+                _ => {}
+            }
+        };
+        conversation.submit(Op::Shutdown).await?;
+        while let Ok(event) = conversation.next_event().await {
+            if matches!(event.msg, EventMsg::ShutdownComplete) {
+                break;
+            }
+        }
+
+        let review_output = review_output.context("the reviewer did not produce a review")?;
+        match self.format {
+            ReviewOutputFormat::Text => print_review(&review_output),
+            ReviewOutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&review_output)?);
+            }
+            ReviewOutputFormat::Sarif => {
+                let sarif = review_output_to_sarif(&review_output, &root);
+                println!("{}", serde_json::to_string_pretty(&sarif)?);
+            }
+            ReviewOutputFormat::Rdjson => {
+                let rdjson = review_output_to_rdjson(&review_output, &root);
+                println!("{}", serde_json::to_string_pretty(&rdjson)?);
+            }
+        }
+
+        if review_output
+            .findings
+            .iter()
+            .any(|finding| finding.priority <= self.fail_on_priority)
+        {
+            std::process::exit(FINDINGS_EXIT_CODE);
+        }
+        Ok(())
+    }
+
+    fn target(&self) -> ReviewTarget {
+        if self.staged {
+            ReviewTarget::Staged
+        } else if let Some(sha) = &self.commit {
+            ReviewTarget::Commit { sha: sha.clone() }
+        } else if let Some((base, head)) = &self.range {
+            ReviewTarget::CommitRange {
+                base: base.clone(),
+                head: head.clone(),
+            }
+        } else if let Some(branch) = &self.base {
+            ReviewTarget::BaseBranch {
+                branch: branch.clone(),
+            }
+        } else if !self.files.is_empty() {
+            ReviewTarget::Files {
+                paths: self.files.clone(),
+            }
+        } else {
+            ReviewTarget::WorkingTree
+        }
+    }
+}
+
+fn print_review(review_output: &ReviewOutputEvent) {
+    let explanation = review_output.overall_explanation.trim();
+    if !explanation.is_empty() {
+        println!("{explanation}");
+    }
+    if review_output.findings.is_empty() {
+        println!("\nNo findings.");
+    } else {
+        println!(
+            "{}",
+            format_review_findings_block(&review_output.findings, None)
+        );
+    }
+}
+
+/// Parse `A..B` into its two revisions.
+fn parse_range(range: &str) -> Result<(String, String), String> {
+    match range.split_once("..") {
+        Some((base, head)) if !base.is_empty() && !head.is_empty() && !head.starts_with('.') => {
+            Ok((base.to_string(), head.to_string()))
+        }
+        _ => Err(format!(
+            "expected a range like main..feature, got `{range}`"
+        )),
+    }
+}
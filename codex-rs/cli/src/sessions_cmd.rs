@@ -0,0 +1,327 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use chrono::DateTime;
+use chrono::Utc;
+use codex_common::CliConfigOverrides;
+use codex_core::ExportFormat;
+use codex_core::ExportOptions;
+use codex_core::PruneReason;
+use codex_core::RolloutRecorder;
+use codex_core::SessionHit;
+use codex_core::SessionQuery;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::diff_sessions;
+use codex_core::export_session;
+use codex_core::find_conversation_path_by_id_str;
+use codex_core::parse_date_bound;
+use codex_core::prune_sessions;
+
+/// Maximum number of characters of a preview shown per row.
+const PREVIEW_MAX_CHARS: usize = 80;
+
+/// Search, export, diff and prune recorded sessions (`~/.codex/sessions`).
+#[derive(Debug, clap::Parser)]
+pub struct SessionsCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: SessionsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SessionsSubcommand {
+    /// Search recorded sessions by text, directory, branch, model, and date.
+    Search(SearchArgs),
+
+    /// Export a session as Markdown, HTML, or a shareable JSON bundle.
+    Export(ExportArgs),
+
+    /// Show how the workspaces left by two sessions, such as two branches
+    /// of a forked conversation, differ.
+    Diff(DiffArgs),
+
+    /// Delete and compress sessions according to `[session_retention]`.
+    Prune(PruneArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct SearchArgs {
+    /// Words that must all appear in the user or agent messages.
+    #[arg(value_name = "TEXT")]
+    pub text: Vec<String>,
+
+    /// Only show sessions started in this directory or below it.
+    #[arg(long, value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Only show sessions started on this git branch.
+    #[arg(long, value_name = "BRANCH")]
+    pub branch: Option<String>,
+
+    /// Only show sessions that used this model.
+    #[arg(long, value_name = "MODEL")]
+    pub model: Option<String>,
+
+    /// Only show sessions started at or after this time (RFC 3339 or YYYY-MM-DD).
+    #[arg(long, value_parser = parse_date_bound, value_name = "TIME")]
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only show sessions started before this time (RFC 3339 or YYYY-MM-DD).
+    #[arg(long, value_parser = parse_date_bound, value_name = "TIME")]
+    pub until: Option<DateTime<Utc>>,
+
+    /// Maximum number of sessions to show.
+    #[arg(long, value_name = "N", default_value_t = 25)]
+    pub limit: usize,
+
+    /// Output the matching sessions as JSON Lines.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct ExportArgs {
+    /// Session id (UUID) or path to a rollout file.
+    #[arg(value_name = "SESSION")]
+    pub session: String,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = ExportFormatArg::Md)]
+    pub format: ExportFormatArg,
+
+    /// Write the export to this file instead of stdout.
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Replace the output of commands and tool calls with a placeholder.
+    #[arg(long)]
+    pub redact_outputs: bool,
+
+    /// Rewrite absolute paths relative to the session directory and home.
+    #[arg(long)]
+    pub redact_paths: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct DiffArgs {
+    /// Session id (UUID) or path to a rollout file to diff from.
+    #[arg(value_name = "FROM")]
+    pub from: String,
+
+    /// Session id (UUID) or path to a rollout file to diff to.
+    #[arg(value_name = "TO")]
+    pub to: String,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct PruneArgs {
+    /// Only list the sessions that would be deleted or compressed.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ExportFormatArg {
+    Md,
+    Html,
+    Json,
+}
+
+impl From<ExportFormatArg> for ExportFormat {
+    fn from(value: ExportFormatArg) -> Self {
+        match value {
+            ExportFormatArg::Md => ExportFormat::Markdown,
+            ExportFormatArg::Html => ExportFormat::Html,
+            ExportFormatArg::Json => ExportFormat::Json,
+        }
+    }
+}
+
+impl SessionsCli {
+    pub async fn run(self) -> Result<()> {
+        let overrides = self
+            .config_overrides
+            .parse_overrides()
+            .map_err(|e| anyhow!(e))?;
+        let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+            .context("failed to load configuration")?;
+
+        match self.cmd {
+            SessionsSubcommand::Search(args) => run_search(&config, args).await,
+            SessionsSubcommand::Export(args) => run_export(&config, args).await,
+            SessionsSubcommand::Diff(args) => run_diff(&config, args).await,
+            SessionsSubcommand::Prune(args) => run_prune(&config, args),
+        }
+    }
+}
+
+async fn run_search(config: &Config, args: SearchArgs) -> Result<()> {
+    let text = args.text.join(" ");
+    let query = SessionQuery {
+        text: (!text.trim().is_empty()).then_some(text),
+        cwd: args.cwd,
+        git_branch: args.branch,
+        model: args.model,
+        since: args.since,
+        until: args.until,
+        limit: args.limit,
+        offset: 0,
+    };
+    let hits = RolloutRecorder::search_conversations(&config.codex_home, query)
+        .await
+        .context("failed to search sessions")?;
+
+    if args.json {
+        for hit in &hits {
+            println!("{}", serde_json::to_string(hit)?);
+        }
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        println!("No matching sessions.");
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 4]> = hits.iter().map(format_row).collect();
+    let mut widths = ["Started".len(), "Session".len(), "Branch".len()];
+    for row in &rows {
+        for (i, width) in widths.iter_mut().enumerate() {
+            *width = (*width).max(row[i].len());
+        }
+    }
+
+    println!(
+        "{:<started_w$}  {:<session_w$}  {:<branch_w$}  Preview",
+        "Started",
+        "Session",
+        "Branch",
+        started_w = widths[0],
+        session_w = widths[1],
+        branch_w = widths[2],
+    );
+    for (row, hit) in rows.iter().zip(&hits) {
+        println!(
+            "{:<started_w$}  {:<session_w$}  {:<branch_w$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            started_w = widths[0],
+            session_w = widths[1],
+            branch_w = widths[2],
+        );
+        if let Some(snippet) = &hit.snippet {
+            let indent = widths.iter().sum::<usize>() + 6;
+            println!("{:indent$}{}", "", single_line(snippet));
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve a session given as a rollout path or a session id.
+async fn resolve_session(config: &Config, session: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(session);
+    if path.is_file() {
+        return Ok(path);
+    }
+    match find_conversation_path_by_id_str(&config.codex_home, session).await? {
+        Some(path) => Ok(path),
+        None => bail!("no recorded session found for `{session}`"),
+    }
+}
+
+async fn run_export(config: &Config, args: ExportArgs) -> Result<()> {
+    let path = resolve_session(config, &args.session).await?;
+
+    let options = ExportOptions {
+        redact_outputs: args.redact_outputs,
+        redact_paths: args.redact_paths,
+    };
+    let rendered = export_session(&path, args.format.into(), options)
+        .await
+        .with_context(|| format!("failed to export {}", path.display()))?;
+
+    match args.output {
+        Some(output) => std::fs::write(&output, rendered)
+            .with_context(|| format!("failed to write {}", output.display()))?,
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+async fn run_diff(config: &Config, args: DiffArgs) -> Result<()> {
+    let from = resolve_session(config, &args.from).await?;
+    let to = resolve_session(config, &args.to).await?;
+    let diff = diff_sessions(&config.codex_home, &from, &to)
+        .await
+        .context("failed to diff sessions")?;
+    if diff.is_empty() {
+        println!("The sessions left identical workspaces.");
+    } else {
+        print!("{diff}");
+    }
+    Ok(())
+}
+
+fn run_prune(config: &Config, args: PruneArgs) -> Result<()> {
+    let retention = &config.session_retention;
+    if retention.is_unlimited() {
+        println!("No retention limits are configured; see `[session_retention]` in config.toml.");
+        return Ok(());
+    }
+
+    let report = prune_sessions(&config.codex_home, retention, args.dry_run)
+        .context("failed to prune sessions")?;
+    let (delete_verb, compress_verb) = if args.dry_run {
+        ("Would delete", "Would compress")
+    } else {
+        ("Deleted", "Compressed")
+    };
+    for session in &report.deleted {
+        let reason = match session.reason {
+            PruneReason::MaxAge => "older than max_age_days",
+            PruneReason::KeepLast => "beyond keep_last",
+            PruneReason::MaxTotalBytes => "over max_total_bytes",
+        };
+        println!("{delete_verb} {} ({reason})", session.path.display());
+    }
+    for path in &report.compressed {
+        println!("{compress_verb} {}", path.display());
+    }
+    println!(
+        "{delete_verb} {} session(s) ({} bytes); {} {} session(s).",
+        report.deleted.len(),
+        report.deleted_bytes(),
+        compress_verb.to_lowercase(),
+        report.compressed.len()
+    );
+    Ok(())
+}
+
+fn format_row(hit: &SessionHit) -> [String; 4] {
+    [
+        hit.timestamp.clone().unwrap_or_default(),
+        hit.conversation_id.to_string(),
+        hit.git_branch.clone().unwrap_or_else(|| "-".to_string()),
+        single_line(&hit.preview),
+    ]
+}
+
+/// Collapse whitespace so each entry fits on one line, and cap its length.
+fn single_line(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= PREVIEW_MAX_CHARS {
+        return line;
+    }
+    let mut truncated: String = line.chars().take(PREVIEW_MAX_CHARS - 1).collect();
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,123 @@
+use std::path::Path;
+
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use serde_json::Value as JsonValue;
+use tempfile::TempDir;
+
+const SESSION_A: &str = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+const SESSION_B: &str = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+
+fn codex_command(codex_home: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("codex")?;
+    cmd.env("CODEX_HOME", codex_home);
+    Ok(cmd)
+}
+
+fn write_audit_log(codex_home: &Path) -> Result<()> {
+    let lines = [
+        serde_json::json!({
+            "ts": "2025-01-01T12:00:00Z",
+            "conversation_id": SESSION_A,
+            "user": "alice",
+            "type": "exec",
+            "call_id": "call_1",
+            "command": ["ls", "-la"],
+            "cwd": "/repo",
+            "sandbox": "linux-seccomp",
+            "exit_code": 0,
+        }),
+        serde_json::json!({
+            "ts": "2025-01-02T12:00:00Z",
+            "conversation_id": SESSION_B,
+            "type": "approval",
+            "call_id": "call_2",
+            "subject": {"kind": "exec", "command": ["git", "push"], "cwd": "/repo"},
+            "decision": "approved",
+            "source": "tui",
+        }),
+        serde_json::json!({
+            "ts": "2025-01-03T12:00:00Z",
+            "conversation_id": SESSION_B,
+            "type": "escalation",
+            "call_id": "call_2",
+            "command": ["git", "push"],
+            "reason": "command failed in sandbox",
+        }),
+    ];
+    let contents = lines
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    std::fs::write(codex_home.join("audit.jsonl"), contents)?;
+    Ok(())
+}
+
+#[test]
+fn audit_shows_empty_state() -> Result<()> {
+    let codex_home = TempDir::new()?;
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd.arg("audit").output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("No matching audit records."));
+
+    Ok(())
+}
+
+#[test]
+fn audit_renders_table() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    write_audit_log(codex_home.path())?;
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd.arg("audit").output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("Time"));
+    assert!(lines[1].contains("ls -la (exit 0, sandbox linux-seccomp)"));
+    assert!(lines[2].contains("approved: git push"));
+    assert!(lines[3].contains("escalation"));
+
+    Ok(())
+}
+
+#[test]
+fn audit_filters_records() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    write_audit_log(codex_home.path())?;
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd
+        .args([
+            "audit",
+            "--json",
+            "--session",
+            SESSION_B,
+            "--since",
+            "2025-01-02",
+        ])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let types = stdout
+        .lines()
+        .map(|line| serde_json::from_str::<JsonValue>(line).map(|v| v["type"].clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(types, vec!["approval", "escalation"]);
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd
+        .args(["audit", "--json", "--type", "exec", "--type", "escalation"])
+        .args(["--limit", "1"])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let record: JsonValue = serde_json::from_str(stdout.trim())?;
+    assert_eq!(record["type"], "escalation");
+
+    Ok(())
+}
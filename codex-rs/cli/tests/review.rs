@@ -0,0 +1,164 @@
+#![cfg(not(target_os = "windows"))]
+
+use std::path::Path;
+
+use anyhow::Result;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn git(cwd: &Path, args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(cwd)
+        .status()?;
+    anyhow::ensure!(status.success(), "git {args:?} failed");
+    Ok(())
+}
+
+fn review_command(codex_home: &Path, server_uri: &str, repo: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("codex")?;
+    cmd.env("CODEX_HOME", codex_home)
+        .env("OPENAI_API_KEY", "dummy")
+        .env("OPENAI_BASE_URL", format!("{server_uri}/v1"))
+        .args(["review", "-C"])
+        .arg(repo);
+    Ok(cmd)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn review_exits_non_zero_when_findings_reach_threshold() -> Result<()> {
+    non_sandbox_test!(result);
+    let codex_home = TempDir::new()?;
+    let repo = TempDir::new()?;
+    git(repo.path(), &["init", "-q"])?;
+    std::fs::write(repo.path().join("notes.txt"), "one\n")?;
+    git(repo.path(), &["add", "notes.txt"])?;
+    git(repo.path(), &["commit", "-q", "-m", "init"])?;
+    std::fs::write(repo.path().join("notes.txt"), "one\ntwo\n")?;
+
+    let review = serde_json::json!({
+        "findings": [{
+            "title": "[P1] Second line is wrong",
+            "body": "It should say three.",
+            "confidence_score": 0.7,
+            "priority": 1,
+            "code_location": {
+                "absolute_file_path": repo.path().join("notes.txt"),
+                "line_range": {"start": 2, "end": 2}
+            }
+        }],
+        "overall_correctness": "patch is incorrect",
+        "overall_explanation": "One line needs fixing.",
+        "overall_confidence_score": 0.7
+    })
+    .to_string();
+    let server = responses::start_mock_server().await;
+    wiremock::Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(responses::sse_response(responses::sse(vec![
+            responses::ev_assistant_message("m1", &review),
+            responses::ev_completed("r1"),
+        ])))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let output = review_command(codex_home.path(), &server.uri(), repo.path())?.output()?;
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("One line needs fixing."), "{stdout}");
+    let anchor = format!(
+        "- [P1] Second line is wrong — {}:2-2",
+        repo.path().join("notes.txt").display()
+    );
+    assert!(stdout.contains(&anchor), "{stdout}");
+
+    // The reviewer saw the working tree diff with line anchors.
+    let requests = server.received_requests().await.unwrap_or_default();
+    let body = String::from_utf8_lossy(&requests[0].body).into_owned();
+    assert!(body.contains("     2 +two"), "{body}");
+
+    review_command(codex_home.path(), &server.uri(), repo.path())?
+        .args(["--fail-on-priority", "0"])
+        .assert()
+        .success();
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn review_prints_sarif_and_rdjson_reports() -> Result<()> {
+    non_sandbox_test!(result);
+    let codex_home = TempDir::new()?;
+    let repo = TempDir::new()?;
+    git(repo.path(), &["init", "-q"])?;
+    git(
+        repo.path(),
+        &["commit", "-q", "--allow-empty", "-m", "init"],
+    )?;
+    std::fs::create_dir(repo.path().join("src"))?;
+    std::fs::write(repo.path().join("src/lib.rs"), "fn a() {}\n")?;
+
+    let review = serde_json::json!({
+        "findings": [{
+            "title": "[P2] Missing docs",
+            "body": "Document `a`.",
+            "confidence_score": 0.5,
+            "priority": 2,
+            "code_location": {
+                "absolute_file_path": repo.path().join("src/lib.rs"),
+                "line_range": {"start": 1, "end": 1}
+            }
+        }],
+        "overall_correctness": "patch is correct",
+        "overall_explanation": "Looks fine.",
+        "overall_confidence_score": 0.5
+    })
+    .to_string();
+    let server = responses::start_mock_server().await;
+    wiremock::Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(responses::sse_response(responses::sse(vec![
+            responses::ev_assistant_message("m1", &review),
+            responses::ev_completed("r1"),
+        ])))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    // A P2 finding is below the default threshold, so both runs succeed.
+    let output = review_command(codex_home.path(), &server.uri(), repo.path())?
+        .args(["--format", "sarif"])
+        .output()?;
+    assert!(output.status.success());
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(sarif["version"], "2.1.0");
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["ruleId"], "P2");
+    assert_eq!(result["level"], "warning");
+    assert_eq!(
+        result["locations"][0]["physicalLocation"],
+        serde_json::json!({
+            "artifactLocation": { "uri": "src/lib.rs", "uriBaseId": "%SRCROOT%" },
+            "region": { "startLine": 1, "endLine": 1 },
+        })
+    );
+    assert_eq!(result["properties"]["confidence_score"], 0.5);
+
+    let output = review_command(codex_home.path(), &server.uri(), repo.path())?
+        .args(["--format", "rdjson"])
+        .output()?;
+    assert!(output.status.success());
+    let rdjson: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let diagnostic = &rdjson["diagnostics"][0];
+    assert_eq!(diagnostic["severity"], "WARNING");
+    assert_eq!(diagnostic["location"]["path"], "src/lib.rs");
+    assert_eq!(diagnostic["location"]["range"]["end"]["line"], 1);
+
+    Ok(())
+}
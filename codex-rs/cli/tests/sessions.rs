@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use serde_json::Value as JsonValue;
+use tempfile::TempDir;
+
+const SESSION_A: &str = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+const SESSION_B: &str = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+
+fn codex_command(codex_home: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("codex")?;
+    cmd.env("CODEX_HOME", codex_home);
+    Ok(cmd)
+}
+
+fn write_rollout(
+    codex_home: &Path,
+    filename_ts: &str,
+    id: &str,
+    branch: &str,
+    message: &str,
+) -> Result<()> {
+    let dir = codex_home
+        .join("sessions")
+        .join(&filename_ts[0..4])
+        .join(&filename_ts[5..7])
+        .join(&filename_ts[8..10]);
+    std::fs::create_dir_all(&dir)?;
+    let lines = [
+        serde_json::json!({
+            "timestamp": filename_ts,
+            "type": "session_meta",
+            "payload": {
+                "id": id,
+                "timestamp": filename_ts,
+                "cwd": "/repo",
+                "originator": "codex",
+                "cli_version": "0.0.0",
+                "instructions": null,
+                "git": { "branch": branch }
+            }
+        }),
+        serde_json::json!({
+            "timestamp": filename_ts,
+            "type": "event_msg",
+            "payload": { "type": "user_message", "message": message, "kind": "plain" }
+        }),
+    ];
+    let contents = lines
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    std::fs::write(
+        dir.join(format!("rollout-{filename_ts}-{id}.jsonl")),
+        contents,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn sessions_search_shows_empty_state() -> Result<()> {
+    let codex_home = TempDir::new()?;
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd.args(["sessions", "search", "anything"]).output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("No matching sessions."));
+
+    Ok(())
+}
+
+#[test]
+fn sessions_search_filters_by_text_and_branch() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    write_rollout(
+        codex_home.path(),
+        "2025-01-01T12-00-00",
+        SESSION_A,
+        "fix-auth",
+        "Fix the auth bug in the login flow",
+    )?;
+    write_rollout(
+        codex_home.path(),
+        "2025-01-02T12-00-00",
+        SESSION_B,
+        "main",
+        "Write release notes",
+    )?;
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd
+        .args(["sessions", "search", "--json", "auth", "bug"])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let ids = stdout
+        .lines()
+        .map(|line| serde_json::from_str::<JsonValue>(line).map(|v| v["conversation_id"].clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(ids, vec![SESSION_A]);
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd
+        .args(["sessions", "search", "--branch", "main"])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("Started"));
+    assert!(lines[1].contains(SESSION_B));
+    assert!(lines[1].contains("Write release notes"));
+
+    Ok(())
+}
+
+#[test]
+fn sessions_export_by_id_writes_markdown() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    write_rollout(
+        codex_home.path(),
+        "2025-01-01T12-00-00",
+        SESSION_A,
+        "fix-auth",
+        "Fix the auth bug in the login flow",
+    )?;
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd.args(["sessions", "export", SESSION_A]).output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with(&format!("# Codex session {SESSION_A}")));
+    assert!(stdout.contains("- **Branch:** fix-auth"));
+
+    let out_file = codex_home.path().join("export.json");
+    let mut cmd = codex_command(codex_home.path())?;
+    cmd.args(["sessions", "export", SESSION_A, "--format", "json", "-o"])
+        .arg(&out_file)
+        .assert()
+        .success();
+    let bundle: JsonValue = serde_json::from_str(&std::fs::read_to_string(&out_file)?)?;
+    assert_eq!(bundle["session"]["id"], SESSION_A);
+
+    Ok(())
+}
+
+#[test]
+fn sessions_export_unknown_session_fails() -> Result<()> {
+    let codex_home = TempDir::new()?;
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd.args(["sessions", "export", SESSION_B]).output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("no recorded session found"));
+
+    Ok(())
+}
+
+#[test]
+fn sessions_prune_dry_run_keeps_files() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    write_rollout(
+        codex_home.path(),
+        "2025-01-01T12-00-00",
+        SESSION_A,
+        "main",
+        "old session",
+    )?;
+    let rollout = codex_home
+        .path()
+        .join("sessions/2025/01/01")
+        .join(format!("rollout-2025-01-01T12-00-00-{SESSION_A}.jsonl"));
+    let last_week = std::time::SystemTime::now() - std::time::Duration::from_secs(7 * 24 * 3600);
+    std::fs::File::options()
+        .write(true)
+        .open(&rollout)?
+        .set_modified(last_week)?;
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd
+        .args([
+            "-c",
+            "session_retention.max_age_days=1",
+            "sessions",
+            "prune",
+            "--dry-run",
+        ])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("Would delete"), "{stdout}");
+    assert!(stdout.contains(SESSION_A), "{stdout}");
+    assert!(rollout.exists());
+
+    let mut cmd = codex_command(codex_home.path())?;
+    let output = cmd
+        .args([
+            "-c",
+            "session_retention.max_age_days=1",
+            "sessions",
+            "prune",
+        ])
+        .output()?;
+    assert!(output.status.success());
+    assert!(!rollout.exists());
+
+    Ok(())
+}
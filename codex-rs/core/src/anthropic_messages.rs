@@ -0,0 +1,814 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+use tracing::warn;
+
+use crate::ModelProviderInfo;
+use crate::client::read_sse_fixture;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::client_common::local_shell_call_arguments;
+use crate::client_common::split_base64_data_url;
+use crate::client_common::thinking_budget_tokens;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::STREAM_IDLE_TIMEOUT_MESSAGE;
+use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_anthropic_messages_api;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ResponseItem;
+
+/// Value of the required `anthropic-version` header.
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` is mandatory for the Messages API; used when neither the
+/// config nor the model info provide `model_max_output_tokens`.
+const DEFAULT_MAX_TOKENS: u64 = 32_000;
+
+/// Anthropic rejects thinking budgets below this value.
+const MIN_THINKING_BUDGET_TOKENS: u64 = 1_024;
+
+/// Implementation for Anthropic's Messages API.
+pub(crate) async fn stream_anthropic_messages(
+    prompt: &Prompt,
+    model: &str,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for Anthropic Messages API".to_string(),
+        ));
+    }
+
+    if let Some(path) = &*CODEX_RS_SSE_FIXTURE {
+        // short circuit for tests
+        warn!(path, "Streaming from fixture");
+        let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+        tokio::spawn(process_anthropic_sse(
+            read_sse_fixture(path)?,
+            tx_event,
+            provider.stream_idle_timeout(),
+        ));
+        return Ok(ResponseStream { rx_event });
+    }
+
+    let payload =
+        build_anthropic_messages_request(prompt, model, model_family, effort, max_output_tokens)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(&None, model),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let req_builder = provider
+            .create_request_builder(client, &None, model)
+            .await?;
+
+        let res = req_builder
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&payload)
+            .send()
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_anthropic_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Builds the JSON body of a streaming `/v1/messages` request.
+///
+/// Reasoning items that carry a signature (stored in `encrypted_content`) are
+/// sent back as `thinking` blocks, which Anthropic requires in order to
+/// continue a turn that used tools while thinking was enabled.
+pub(crate) fn build_anthropic_messages_request(
+    prompt: &Prompt,
+    model: &str,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+) -> Result<Value> {
+    let mut messages = MessageList::default();
+
+    for item in prompt.get_formatted_input() {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for c in content {
+                    let block = match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            // Empty text blocks are rejected by the API.
+                            if text.is_empty() {
+                                continue;
+                            }
+                            json!({"type": "text", "text": text})
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            match split_base64_data_url(&image_url) {
+                                Some((media_type, data)) => json!({
+                                    "type": "image",
+                                    "source": {
+                                        "type": "base64",
+                                        "media_type": media_type,
+                                        "data": data,
+                                    },
+                                }),
+                                None => json!({
+                                    "type": "image",
+                                    "source": {"type": "url", "url": image_url},
+                                }),
+                            }
+                        }
+                    };
+                    messages.push(role, block);
+                }
+            }
+            ResponseItem::Reasoning {
+                content,
+                encrypted_content: Some(signature),
+                ..
+            } => {
+                let text = content
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|c| match c {
+                        ReasoningItemContent::ReasoningText { text }
+                        | ReasoningItemContent::Text { text } => text,
+                    })
+                    .collect::<String>();
+                let block = if text.is_empty() {
+                    json!({"type": "redacted_thinking", "data": signature})
+                } else {
+                    json!({"type": "thinking", "thinking": text, "signature": signature})
+                };
+                messages.push("assistant", block);
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(&arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                messages.push(
+                    "assistant",
+                    json!({"type": "tool_use", "id": call_id, "name": name, "input": input}),
+                );
+            }
+            // Calls to the built-in `local_shell` tool and to freeform tools
+            // are replayed as calls to the equivalent function tools.
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                messages.push(
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": call_id.or(id).unwrap_or_default(),
+                        "name": "shell",
+                        "input": local_shell_call_arguments(&action),
+                    }),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                messages.push(
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": call_id,
+                        "name": name,
+                        "input": {"input": input},
+                    }),
+                );
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                messages.push(
+                    "user",
+                    json!({"type": "tool_result", "tool_use_id": call_id, "content": output}),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let mut block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": call_id,
+                    "content": output.content,
+                });
+                if output.success == Some(false) {
+                    block["is_error"] = json!(true);
+                }
+                messages.push("user", block);
+            }
+            // Unsigned reasoning cannot be replayed, and web searches run on
+            // OpenAI's side have no equivalent in the Messages API.
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => continue,
+        }
+    }
+
+    let max_tokens = max_output_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let tools_json = create_tools_json_for_anthropic_messages_api(&prompt.tools)?;
+    let mut payload = json!({
+        "model": model,
+        "max_tokens": max_tokens,
+        "system": prompt.get_full_instructions(model_family),
+        "messages": messages.0,
+        "stream": true,
+    });
+    if !tools_json.is_empty() {
+        payload["tools"] = json!(tools_json);
+    }
+    // The thinking budget must leave room for the answer within `max_tokens`.
+    if let Some(budget) = thinking_budget_tokens(model_family, effort)
+        .map(|budget| budget.min(max_tokens / 2))
+        .filter(|budget| *budget >= MIN_THINKING_BUDGET_TOKENS)
+    {
+        payload["thinking"] = json!({"type": "enabled", "budget_tokens": budget});
+    }
+
+    Ok(payload)
+}
+
+/// Messages with consecutive blocks of the same role merged, as required for
+/// e.g. the `tool_result` blocks of parallel tool calls.
+#[derive(Default)]
+struct MessageList(Vec<Value>);
+
+impl MessageList {
+    fn push(&mut self, role: &str, block: Value) {
+        if let Some(last) = self.0.last_mut()
+            && last["role"] == role
+            && let Some(content) = last["content"].as_array_mut()
+        {
+            content.push(block);
+            return;
+        }
+        self.0.push(json!({"role": role, "content": [block]}));
+    }
+}
+
+/// A content block that is still being streamed.
+enum ContentBlock {
+    Text(String),
+    Thinking {
+        text: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input: String,
+    },
+}
+
+impl ContentBlock {
+    fn from_start(block: &Value) -> Option<Self> {
+        let str_field = |name: &str| block[name].as_str().unwrap_or_default().to_string();
+        match block["type"].as_str()? {
+            "text" => Some(Self::Text(str_field("text"))),
+            "thinking" => Some(Self::Thinking {
+                text: str_field("thinking"),
+                signature: str_field("signature"),
+            }),
+            "redacted_thinking" => Some(Self::RedactedThinking(str_field("data"))),
+            "tool_use" => Some(Self::ToolUse {
+                id: str_field("id"),
+                name: str_field("name"),
+                // The input is streamed as `input_json_delta`s; the start
+                // event only carries a placeholder.
+                input: String::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn into_response_item(self) -> Option<ResponseItem> {
+        match self {
+            Self::Text(text) if text.is_empty() => None,
+            Self::Text(text) => Some(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text }],
+            }),
+            Self::Thinking { text, signature } => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: Some(vec![ReasoningItemContent::ReasoningText { text }]),
+                encrypted_content: Some(signature).filter(|s| !s.is_empty()),
+            }),
+            Self::RedactedThinking(data) => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some(data),
+            }),
+            Self::ToolUse { id, name, input } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments: if input.is_empty() {
+                    "{}".to_string()
+                } else {
+                    input
+                },
+                call_id: id,
+            }),
+        }
+    }
+}
+
+/// SSE processor for the Messages API streaming format. Every content block
+/// is forwarded as a single [`ResponseEvent::OutputItemDone`] once its
+/// `content_block_stop` arrives, with text and thinking also streamed as
+/// deltas.
+pub(crate) async fn process_anthropic_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut response_id = String::new();
+    let mut blocks: HashMap<u64, ContentBlock> = HashMap::new();
+    let mut input_tokens = 0;
+    let mut cached_input_tokens = 0;
+    let mut output_tokens = 0;
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before message_stop".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        STREAM_IDLE_TIMEOUT_MESSAGE.into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let event: Value = match serde_json::from_str(&sse.data) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        trace!("anthropic_messages received SSE event: {event:?}");
+
+        match event["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                let message = &event["message"];
+                response_id = message["id"].as_str().unwrap_or_default().to_string();
+                let usage = &message["usage"];
+                let cache_read = usage["cache_read_input_tokens"].as_u64().unwrap_or(0);
+                // Anthropic reports cache reads and writes separately from
+                // `input_tokens`; Codex counts cached tokens as part of the input.
+                input_tokens = usage["input_tokens"].as_u64().unwrap_or(0)
+                    + cache_read
+                    + usage["cache_creation_input_tokens"].as_u64().unwrap_or(0);
+                cached_input_tokens = cache_read;
+                output_tokens = usage["output_tokens"].as_u64().unwrap_or(0);
+            }
+            "content_block_start" => {
+                if let (Some(index), Some(block)) = (
+                    event["index"].as_u64(),
+                    ContentBlock::from_start(&event["content_block"]),
+                ) {
+                    blocks.insert(index, block);
+                }
+            }
+            "content_block_delta" => {
+                let Some(block) = event["index"].as_u64().and_then(|i| blocks.get_mut(&i)) else {
+                    continue;
+                };
+                let delta = &event["delta"];
+                match (block, delta["type"].as_str().unwrap_or_default()) {
+                    (ContentBlock::Text(text), "text_delta") => {
+                        let chunk = delta["text"].as_str().unwrap_or_default();
+                        if !chunk.is_empty() {
+                            text.push_str(chunk);
+                            let _ = tx_event
+                                .send(Ok(ResponseEvent::OutputTextDelta(chunk.to_string())))
+                                .await;
+                        }
+                    }
+                    (ContentBlock::Thinking { text, .. }, "thinking_delta") => {
+                        let chunk = delta["thinking"].as_str().unwrap_or_default();
+                        if !chunk.is_empty() {
+                            text.push_str(chunk);
+                            let _ = tx_event
+                                .send(Ok(ResponseEvent::ReasoningContentDelta(chunk.to_string())))
+                                .await;
+                        }
+                    }
+                    (ContentBlock::Thinking { signature, .. }, "signature_delta") => {
+                        signature.push_str(delta["signature"].as_str().unwrap_or_default());
+                    }
+                    (ContentBlock::ToolUse { input, .. }, "input_json_delta") => {
+                        input.push_str(delta["partial_json"].as_str().unwrap_or_default());
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_stop" => {
+                if let Some(item) = event["index"]
+                    .as_u64()
+                    .and_then(|i| blocks.remove(&i))
+                    .and_then(ContentBlock::into_response_item)
+                {
+                    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                }
+            }
+            "message_delta" => {
+                // `output_tokens` in `message_delta` is cumulative.
+                if let Some(tokens) = event["usage"]["output_tokens"].as_u64() {
+                    output_tokens = tokens;
+                }
+            }
+            "message_stop" => {
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id,
+                        token_usage: Some(TokenUsage {
+                            input_tokens,
+                            cached_input_tokens,
+                            output_tokens,
+                            reasoning_output_tokens: 0,
+                            total_tokens: input_tokens + output_tokens,
+                        }),
+                    }))
+                    .await;
+                return;
+            }
+            "error" => {
+                let message = event["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+                    .to_string();
+                let _ = tx_event.send(Err(CodexErr::Stream(message, None))).await;
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::derive_default_model_family;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use codex_protocol::models::LocalShellAction;
+    use codex_protocol::models::LocalShellExecAction;
+    use codex_protocol::models::LocalShellStatus;
+    use pretty_assertions::assert_eq;
+
+    async fn collect_fixture_events(fixture: &str) -> Vec<Result<ResponseEvent>> {
+        let path = format!("{}/tests/fixtures/{fixture}", env!("CARGO_MANIFEST_DIR"));
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(64);
+        let stream = read_sse_fixture(path).expect("read fixture");
+        tokio::spawn(process_anthropic_sse(stream, tx, Duration::from_secs(5)));
+        let mut events = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            events.push(ev);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn parses_recorded_stream() {
+        let events = collect_fixture_events("anthropic_messages.sse").await;
+        let events = events
+            .into_iter()
+            .map(|ev| ev.expect("stream event"))
+            .collect::<Vec<_>>();
+
+        let deltas = events
+            .iter()
+            .filter_map(|ev| match ev {
+                ResponseEvent::OutputTextDelta(delta) => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+        assert_eq!(deltas, "Let me list the files.");
+
+        let items = events
+            .iter()
+            .filter_map(|ev| match ev {
+                ResponseEvent::OutputItemDone(item) => Some(item.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            vec![
+                ResponseItem::Reasoning {
+                    id: String::new(),
+                    summary: Vec::new(),
+                    content: Some(vec![ReasoningItemContent::ReasoningText {
+                        text: "The user wants the file list. I should run ls.".to_string(),
+                    }]),
+                    encrypted_content: Some(
+                        "EqQBCgIYAhIM1gbcDa9GJwZA2b3hGgxBdjrkzLoky3dl1pkiMOYds".to_string()
+                    ),
+                },
+                ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText {
+                        text: "Let me list the files.".to_string(),
+                    }],
+                },
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: r#"{"command": ["ls", "-la"]}"#.to_string(),
+                    call_id: "toolu_01T1x1fJ34qAmk2tNTrN7Up6".to_string(),
+                },
+            ]
+        );
+
+        let Some(ResponseEvent::Completed {
+            response_id,
+            token_usage,
+        }) = events.last()
+        else {
+            panic!("expected Completed as the last event, got {events:?}");
+        };
+        assert_eq!(response_id, "msg_01XFDUDYJgAACzvnptvVoYEL");
+        assert_eq!(
+            token_usage,
+            &Some(TokenUsage {
+                input_tokens: 1_496,
+                cached_input_tokens: 1_024,
+                output_tokens: 89,
+                reasoning_output_tokens: 0,
+                total_tokens: 1_585,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn error_event_fails_the_stream() {
+        let body = "data: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n";
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(8);
+        let stream = futures::stream::iter(vec![Ok(Bytes::from(body))]);
+        tokio::spawn(process_anthropic_sse(stream, tx, Duration::from_secs(5)));
+
+        match rx.recv().await {
+            Some(Err(CodexErr::Stream(message, None))) => assert_eq!(message, "Overloaded"),
+            other => panic!("expected stream error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn request_replays_thinking_and_merges_tool_results() {
+        let mut model_family = derive_default_model_family("claude-sonnet-4-20250514");
+        model_family.supports_reasoning_summaries = true;
+
+        let prompt = Prompt {
+            input: vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputText {
+                        text: "list files".to_string(),
+                    }],
+                },
+                ResponseItem::Reasoning {
+                    id: String::new(),
+                    summary: Vec::new(),
+                    content: Some(vec![ReasoningItemContent::ReasoningText {
+                        text: "run ls".to_string(),
+                    }]),
+                    encrypted_content: Some("sig".to_string()),
+                },
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: r#"{"command":["ls"]}"#.to_string(),
+                    call_id: "toolu_1".to_string(),
+                },
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: "not json".to_string(),
+                    call_id: "toolu_2".to_string(),
+                },
+                ResponseItem::FunctionCallOutput {
+                    call_id: "toolu_1".to_string(),
+                    output: FunctionCallOutputPayload {
+                        content: "a.txt".to_string(),
+                        success: Some(true),
+                    },
+                },
+                ResponseItem::FunctionCallOutput {
+                    call_id: "toolu_2".to_string(),
+                    output: FunctionCallOutputPayload {
+                        content: "failed".to_string(),
+                        success: Some(false),
+                    },
+                },
+            ],
+            ..Default::default()
+        };
+
+        let payload = build_anthropic_messages_request(
+            &prompt,
+            "claude-sonnet-4-20250514",
+            &model_family,
+            Some(ReasoningEffortConfig::Low),
+            Some(16_000),
+        )
+        .expect("build request");
+
+        assert_eq!(payload["model"], "claude-sonnet-4-20250514");
+        assert_eq!(payload["max_tokens"], 16_000);
+        assert_eq!(
+            payload["thinking"],
+            json!({"type": "enabled", "budget_tokens": 4_096})
+        );
+        assert!(payload.get("tools").is_none());
+        assert_eq!(
+            payload["messages"],
+            json!([
+                {"role": "user", "content": [{"type": "text", "text": "list files"}]},
+                {"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "run ls", "signature": "sig"},
+                    {"type": "tool_use", "id": "toolu_1", "name": "shell", "input": {"command": ["ls"]}},
+                    {"type": "tool_use", "id": "toolu_2", "name": "shell", "input": {}},
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": "a.txt"},
+                    {"type": "tool_result", "tool_use_id": "toolu_2", "content": "failed", "is_error": true},
+                ]},
+            ])
+        );
+    }
+
+    #[test]
+    fn request_replays_local_shell_and_custom_tool_calls_as_tool_use() {
+        let model_family = derive_default_model_family("claude-sonnet-4-20250514");
+        let prompt = Prompt {
+            input: vec![
+                ResponseItem::LocalShellCall {
+                    id: None,
+                    call_id: Some("call_1".to_string()),
+                    status: LocalShellStatus::Completed,
+                    action: LocalShellAction::Exec(LocalShellExecAction {
+                        command: vec!["ls".to_string()],
+                        timeout_ms: Some(1_000),
+                        working_directory: Some("/repo".to_string()),
+                        env: None,
+                        user: None,
+                    }),
+                },
+                ResponseItem::FunctionCallOutput {
+                    call_id: "call_1".to_string(),
+                    output: FunctionCallOutputPayload {
+                        content: "a.txt".to_string(),
+                        success: Some(true),
+                    },
+                },
+                ResponseItem::CustomToolCall {
+                    id: None,
+                    status: None,
+                    call_id: "call_2".to_string(),
+                    name: "apply_patch".to_string(),
+                    input: "*** Begin Patch".to_string(),
+                },
+                ResponseItem::CustomToolCallOutput {
+                    call_id: "call_2".to_string(),
+                    output: "Done!".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let payload =
+            build_anthropic_messages_request(&prompt, "claude-custom", &model_family, None, None)
+                .expect("build request");
+
+        assert_eq!(payload["model"], "claude-custom");
+        assert_eq!(
+            payload["messages"],
+            json!([
+                {"role": "assistant", "content": [{
+                    "type": "tool_use",
+                    "id": "call_1",
+                    "name": "shell",
+                    "input": {"command": ["ls"], "workdir": "/repo", "timeout_ms": 1_000},
+                }]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "call_1", "content": "a.txt"},
+                ]},
+                {"role": "assistant", "content": [{
+                    "type": "tool_use",
+                    "id": "call_2",
+                    "name": "apply_patch",
+                    "input": {"input": "*** Begin Patch"},
+                }]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "call_2", "content": "Done!"},
+                ]},
+            ])
+        );
+    }
+}
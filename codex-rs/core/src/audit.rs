@@ -0,0 +1,401 @@
+//! Append-only audit log of the privileged actions taken by the agent.
+//!
+//! The log is stored at `~/.codex/audit.jsonl` with **one JSON object per
+//! line**. Unlike rollouts it holds no conversation content, only what was
+//! run on the machine, under which sandbox, and who approved it:
+//!
+//! ````text
+//! {"ts":"2025-01-01T12:00:00Z","conversation_id":"<uuid>","user":"alice","type":"exec","call_id":"call_1","command":["ls"],"cwd":"/repo","sandbox":"linux-seccomp","exit_code":0}
+//! ````
+//!
+//! Records are written the same way as the message history: the full line is
+//! prepared up front and written with a single `write(2)` to a file opened
+//! with `O_APPEND`, under an advisory lock. Records can optionally be
+//! forwarded to the local syslog daemon (and thereby to journald).
+
+use std::fs::OpenOptions;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Result;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use codex_protocol::mcp_protocol::ConversationId;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::config::Config;
+use crate::exec::SandboxType;
+use crate::protocol::ApprovalSource;
+use crate::protocol::AskForApproval;
+use crate::protocol::ReviewDecision;
+use crate::protocol::SandboxPolicy;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+/// Filename that stores the audit log inside `~/.codex`.
+pub const AUDIT_LOG_FILENAME: &str = "audit.jsonl";
+
+const MAX_RETRIES: usize = 10;
+const RETRY_SLEEP: Duration = Duration::from_millis(100);
+
+/// A single line of the audit log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    pub ts: DateTime<Utc>,
+    pub conversation_id: ConversationId,
+    /// OS user that ran Codex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// Policies in effect when a session starts, and whenever a client
+    /// changes them.
+    Policy {
+        cwd: PathBuf,
+        approval_policy: AskForApproval,
+        sandbox_policy: SandboxPolicy,
+    },
+    /// A decision on an approval request.
+    Approval {
+        call_id: String,
+        subject: ApprovalSubject,
+        decision: ReviewDecision,
+        source: ApprovalSource,
+    },
+    /// A command run on behalf of the model, or input written to one that is
+    /// still running.
+    Exec {
+        call_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        sandbox: SandboxType,
+        /// `-1` if the command could not be run or its exit code is unknown,
+        /// e.g. because it was still running when the tool call returned.
+        exit_code: i32,
+    },
+    /// A patch applied on behalf of the model.
+    Patch {
+        call_id: String,
+        files: Vec<PathBuf>,
+        sandbox: SandboxType,
+        success: bool,
+    },
+    /// A call to a tool of an MCP server.
+    McpToolCall {
+        call_id: String,
+        server: String,
+        tool: String,
+        success: bool,
+    },
+    /// A command that was approved to run outside of the sandbox.
+    Escalation {
+        call_id: String,
+        command: Vec<String>,
+        reason: String,
+    },
+}
+
+impl AuditEvent {
+    /// The value of the `type` field of the serialized record.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuditEvent::Policy { .. } => "policy",
+            AuditEvent::Approval { .. } => "approval",
+            AuditEvent::Exec { .. } => "exec",
+            AuditEvent::Patch { .. } => "patch",
+            AuditEvent::McpToolCall { .. } => "mcp_tool_call",
+            AuditEvent::Escalation { .. } => "escalation",
+        }
+    }
+}
+
+/// What an approval request was about.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ApprovalSubject {
+    Exec { command: Vec<String>, cwd: PathBuf },
+    Patch { files: Vec<PathBuf> },
+}
+
+/// Writes the audit records of one session.
+#[derive(Debug)]
+pub(crate) struct AuditLogger {
+    path: PathBuf,
+    conversation_id: ConversationId,
+    user: Option<String>,
+    syslog: bool,
+}
+
+impl AuditLogger {
+    /// Returns `None` if the audit log is disabled in `config`.
+    pub(crate) fn new(config: &Config, conversation_id: ConversationId) -> Option<Self> {
+        if !config.audit.enabled {
+            return None;
+        }
+        Some(Self {
+            path: audit_log_path(&config.codex_home),
+            conversation_id,
+            user: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .ok(),
+            syslog: config.audit.syslog,
+        })
+    }
+
+    /// Appends a record for `event`. Failures are logged rather than
+    /// propagated so that a full disk does not stop the agent mid-turn.
+    pub(crate) async fn record(&self, event: AuditEvent) {
+        let record = AuditRecord {
+            ts: Utc::now(),
+            conversation_id: self.conversation_id,
+            user: self.user.clone(),
+            event,
+        };
+        let line = match serde_json::to_string(&record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("failed to serialize audit record: {e}");
+                return;
+            }
+        };
+        if self.syslog
+            && let Err(e) = forward_to_syslog(&line)
+        {
+            warn!("failed to forward audit record to syslog: {e}");
+        }
+        if let Err(e) = append_line(self.path.clone(), line).await {
+            warn!("failed to write audit record: {e}");
+        }
+    }
+}
+
+pub fn audit_log_path(codex_home: &Path) -> PathBuf {
+    codex_home.join(AUDIT_LOG_FILENAME)
+}
+
+async fn append_line(path: PathBuf, mut line: String) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    line.push('\n');
+
+    // Open in append-only mode.
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    {
+        options.mode(0o600);
+    }
+
+    // Perform a blocking write under an advisory write lock using std::fs.
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut audit_file = options.open(&path)?;
+        for _ in 0..MAX_RETRIES {
+            match audit_file.try_lock() {
+                Ok(()) => {
+                    audit_file.write_all(line.as_bytes())?;
+                    audit_file.flush()?;
+                    return Ok(());
+                }
+                Err(std::fs::TryLockError::WouldBlock) => {
+                    std::thread::sleep(RETRY_SLEEP);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(std::io::Error::new(
+            std::io::ErrorKind::WouldBlock,
+            "could not acquire exclusive lock on audit log after multiple attempts",
+        ))
+    })
+    .await?
+}
+
+/// Sends `line` to the local syslog daemon as an `authpriv.info` message.
+/// systemd-journald listens on `/dev/log` as well, so this covers journald.
+#[cfg(unix)]
+fn forward_to_syslog(line: &str) -> Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    const SYSLOG_SOCKETS: &[&str] = &["/dev/log", "/var/run/syslog", "/var/run/log"];
+    const LOG_AUTHPRIV: u8 = 10;
+    const LOG_INFO: u8 = 6;
+
+    let message = format!(
+        "<{}>codex[{}]: {line}",
+        LOG_AUTHPRIV * 8 + LOG_INFO,
+        std::process::id()
+    );
+    let socket = UnixDatagram::unbound()?;
+    let mut last_err = None;
+    for path in SYSLOG_SOCKETS {
+        match socket.send_to(message.as_bytes(), path) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| std::io::Error::other("no syslog socket found")))
+}
+
+#[cfg(not(unix))]
+fn forward_to_syslog(_line: &str) -> Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "syslog forwarding is only supported on Unix",
+    ))
+}
+
+/// Filters applied by [`read_audit_log`].
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// Only records at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only records of this session.
+    pub conversation_id: Option<ConversationId>,
+    /// Only records whose `type` is one of these; all types if empty.
+    pub kinds: Vec<String>,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.since.is_none_or(|since| record.ts >= since)
+            && self
+                .conversation_id
+                .is_none_or(|id| record.conversation_id == id)
+            && (self.kinds.is_empty() || self.kinds.iter().any(|k| k == record.event.kind()))
+    }
+}
+
+/// Reads the records in `~/.codex/audit.jsonl` that match `query`, oldest
+/// first. A missing log is treated as an empty one; lines that cannot be
+/// parsed are skipped with a warning.
+pub fn read_audit_log(codex_home: &Path, query: &AuditQuery) -> Result<Vec<AuditRecord>> {
+    let file = match std::fs::File::open(audit_log_path(codex_home)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<AuditRecord>(&line) {
+            Ok(record) if query.matches(&record) => records.push(record),
+            Ok(_) => {}
+            Err(e) => warn!("skipping malformed audit record on line {}: {e}", index + 1),
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn logger(codex_home: &Path, conversation_id: ConversationId) -> AuditLogger {
+        AuditLogger {
+            path: audit_log_path(codex_home),
+            conversation_id,
+            user: Some("alice".to_string()),
+            syslog: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn records_are_appended_and_filtered() {
+        let codex_home = TempDir::new().unwrap();
+        let first = ConversationId::new();
+        let second = ConversationId::new();
+
+        logger(codex_home.path(), first)
+            .record(AuditEvent::Exec {
+                call_id: "call_1".to_string(),
+                command: vec!["ls".to_string()],
+                cwd: PathBuf::from("/repo"),
+                sandbox: SandboxType::LinuxSeccomp,
+                exit_code: 0,
+            })
+            .await;
+        logger(codex_home.path(), second)
+            .record(AuditEvent::Approval {
+                call_id: "call_2".to_string(),
+                subject: ApprovalSubject::Patch {
+                    files: vec![PathBuf::from("/repo/a.txt")],
+                },
+                decision: ReviewDecision::Approved,
+                source: ApprovalSource::Tui,
+            })
+            .await;
+
+        let all = read_audit_log(codex_home.path(), &AuditQuery::default()).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].conversation_id, first);
+        assert_eq!(all[0].user.as_deref(), Some("alice"));
+
+        let approvals = read_audit_log(
+            codex_home.path(),
+            &AuditQuery {
+                kinds: vec!["approval".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(approvals.len(), 1);
+        assert_eq!(approvals[0].conversation_id, second);
+
+        let future = read_audit_log(
+            codex_home.path(),
+            &AuditQuery {
+                since: Some(Utc::now() + chrono::Duration::hours(1)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(future.is_empty());
+    }
+
+    #[test]
+    fn record_serialization_is_flat() {
+        let record = AuditRecord {
+            ts: DateTime::parse_from_rfc3339("2025-01-01T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            conversation_id: ConversationId::from_string("67e55044-10b1-426f-9247-bb680e5fe0c8")
+                .unwrap(),
+            user: None,
+            event: AuditEvent::Escalation {
+                call_id: "call_1".to_string(),
+                command: vec!["git".to_string(), "push".to_string()],
+                reason: "command failed in sandbox".to_string(),
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&record).unwrap(),
+            serde_json::json!({
+                "ts": "2025-01-01T12:00:00Z",
+                "conversation_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+                "type": "escalation",
+                "call_id": "call_1",
+                "command": ["git", "push"],
+                "reason": "command failed in sandbox",
+            })
+        );
+    }
+}
@@ -134,6 +134,234 @@ fn parse_plain_command_from_node(cmd: tree_sitter::Node, src: &str) -> Option<Ve
     Some(words)
 }
 
+/// A word of a shell command as seen by [`try_analyze_bash_script`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellWord {
+    /// A word whose value is known statically, with quotes removed.
+    Literal(String),
+    /// A word whose value depends on expansions, substitutions, or escapes
+    /// that are only resolved when the script runs. Holds the source text.
+    Dynamic(String),
+}
+
+/// The simple commands a script may run and the files it may write to
+/// through output redirections.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BashScriptAnalysis {
+    /// Every simple command, including those nested in pipelines, subshells,
+    /// `{ …; }` groups, and `$(…)` / backtick substitutions.
+    pub commands: Vec<Vec<ShellWord>>,
+    /// Targets of redirections that write or append to a file (`>`, `>>`,
+    /// `&>`, `>|`, `<>`, `>&file`). Input redirections, here-documents,
+    /// here-strings, and file descriptor duplication such as `2>&1` only read
+    /// and are not recorded.
+    pub write_targets: Vec<ShellWord>,
+}
+
+/// Walk the parse tree of a script and collect every simple command it may
+/// run, together with the targets of its output redirections.
+///
+/// Returns `None` if the script does not parse cleanly or uses constructs the
+/// analysis does not model: background jobs (`&`), control flow, function
+/// definitions, variable assignments, process substitution, and so on.
+pub fn try_analyze_bash_script(tree: &Tree, src: &str) -> Option<BashScriptAnalysis> {
+    let root = tree.root_node();
+    if root.has_error() {
+        return None;
+    }
+    let mut walker = ScriptWalker {
+        src,
+        analysis: BashScriptAnalysis::default(),
+    };
+    walker.statement(root)?;
+    Some(walker.analysis)
+}
+
+struct ScriptWalker<'a> {
+    src: &'a str,
+    analysis: BashScriptAnalysis,
+}
+
+impl ScriptWalker<'_> {
+    fn text(&self, node: Node) -> Option<&str> {
+        node.utf8_text(self.src.as_bytes()).ok()
+    }
+
+    fn statement(&mut self, node: Node) -> Option<()> {
+        match node.kind() {
+            "program"
+            | "list"
+            | "pipeline"
+            | "subshell"
+            | "compound_statement"
+            | "negated_command"
+            | "redirected_statement" => {
+                let mut cursor = node.walk();
+                for child in node.children(&mut cursor) {
+                    if child.is_named() {
+                        self.statement(child)?;
+                    } else if child.kind() == "&" {
+                        // Background jobs outlive the command we are asked
+                        // to approve.
+                        return None;
+                    }
+                }
+                Some(())
+            }
+            "command" => self.command(node),
+            "file_redirect" | "heredoc_redirect" | "herestring_redirect" => self.redirect(node),
+            "comment" => Some(()),
+            _ => None,
+        }
+    }
+
+    fn command(&mut self, node: Node) -> Option<()> {
+        let mut words = Vec::new();
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            match child.kind() {
+                "command_name" => words.push(self.word(child.named_child(0)?)?),
+                "file_redirect" | "herestring_redirect" => self.redirect(child)?,
+                // Rejects environment assignments (`FOO=bar cmd`) as well.
+                _ => words.push(self.word(child)?),
+            }
+        }
+        self.analysis.commands.push(words);
+        Some(())
+    }
+
+    fn redirect(&mut self, node: Node) -> Option<()> {
+        let mut cursor = node.walk();
+        match node.kind() {
+            "file_redirect" => {
+                let mut operator = None;
+                let mut destinations = Vec::new();
+                for child in node.children(&mut cursor) {
+                    if !child.is_named() {
+                        operator = Some(child.kind());
+                    } else if child.kind() != "file_descriptor" {
+                        destinations.push(child);
+                    }
+                }
+                let destination = match destinations.as_slice() {
+                    [] => None,
+                    [destination] => Some(self.word(*destination)?),
+                    _ => return None,
+                };
+                match (operator?, destination) {
+                    ("<" | "<&" | "<&-" | ">&-", _) => Some(()),
+                    (">&", Some(ShellWord::Literal(fd)))
+                        if fd == "-" || fd.chars().all(|c| c.is_ascii_digit()) =>
+                    {
+                        Some(())
+                    }
+                    (">" | ">>" | "&>" | "&>>" | ">|" | "<>" | ">&", Some(target)) => {
+                        self.analysis.write_targets.push(target);
+                        Some(())
+                    }
+                    _ => None,
+                }
+            }
+            "heredoc_redirect" => {
+                for child in node.named_children(&mut cursor) {
+                    match child.kind() {
+                        "heredoc_start" | "heredoc_end" => {}
+                        "heredoc_body" => self.nested_commands(child)?,
+                        "file_redirect" | "herestring_redirect" => self.redirect(child)?,
+                        // A pipeline or `&&` / `||` chain following the
+                        // here-document operator.
+                        _ => self.statement(child)?,
+                    }
+                }
+                Some(())
+            }
+            "herestring_redirect" => {
+                for child in node.named_children(&mut cursor) {
+                    if child.kind() != "file_descriptor" {
+                        self.word(child)?;
+                    }
+                }
+                Some(())
+            }
+            _ => None,
+        }
+    }
+
+    fn word(&mut self, node: Node) -> Option<ShellWord> {
+        if let Some(value) = self.literal_value(node) {
+            return Some(ShellWord::Literal(value));
+        }
+        match node.kind() {
+            "word"
+            | "number"
+            | "string"
+            | "raw_string"
+            | "concatenation"
+            | "simple_expansion"
+            | "expansion"
+            | "arithmetic_expansion"
+            | "command_substitution"
+            | "ansi_c_string"
+            | "translated_string" => {
+                self.nested_commands(node)?;
+                Some(ShellWord::Dynamic(self.text(node)?.to_owned()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The value of `node` if it can be determined without running the
+    /// script. Words with backslash escapes or a leading `~` are treated as
+    /// dynamic rather than re-implementing the shell's unescaping rules.
+    fn literal_value(&self, node: Node) -> Option<String> {
+        let text = self.text(node)?;
+        match node.kind() {
+            "word" | "number" if !text.contains('\\') && !text.starts_with('~') => {
+                Some(text.to_owned())
+            }
+            "raw_string" => Some(text.strip_prefix('\'')?.strip_suffix('\'')?.to_owned()),
+            "string" | "concatenation" => {
+                let mut value = String::new();
+                let mut cursor = node.walk();
+                for child in node.named_children(&mut cursor) {
+                    if child.kind() == "string_content" {
+                        let content = self.text(child)?;
+                        if content.contains('\\') {
+                            return None;
+                        }
+                        value.push_str(content);
+                    } else {
+                        value.push_str(&self.literal_value(child)?);
+                    }
+                }
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Analyze the commands run by `$(…)` and backtick substitutions nested
+    /// anywhere inside `node`.
+    fn nested_commands(&mut self, node: Node) -> Option<()> {
+        let mut cursor = node.walk();
+        match node.kind() {
+            "command_substitution" => {
+                for child in node.named_children(&mut cursor) {
+                    self.statement(child)?;
+                }
+                Some(())
+            }
+            "process_substitution" => None,
+            _ => {
+                for child in node.named_children(&mut cursor) {
+                    self.nested_commands(child)?;
+                }
+                Some(())
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,4 +447,101 @@ mod tests {
     fn rejects_trailing_operator_parse_error() {
         assert!(parse_seq("ls &&").is_none());
     }
+
+    fn analyze(src: &str) -> Option<BashScriptAnalysis> {
+        let tree = try_parse_bash(src)?;
+        try_analyze_bash_script(&tree, src)
+    }
+
+    fn literal(s: &str) -> ShellWord {
+        ShellWord::Literal(s.to_string())
+    }
+
+    #[test]
+    fn analyze_collects_commands_from_pipelines_and_groups() {
+        let analysis = analyze("(ls -1 | wc -l) && { pwd; ! false; }").unwrap();
+        assert_eq!(
+            analysis.commands,
+            vec![
+                vec![literal("ls"), literal("-1")],
+                vec![literal("wc"), literal("-l")],
+                vec![literal("pwd")],
+                vec![literal("false")],
+            ]
+        );
+        assert!(analysis.write_targets.is_empty());
+    }
+
+    #[test]
+    fn analyze_descends_into_command_substitutions() {
+        let analysis = analyze("echo \"dir: $(pwd)\" `whoami`").unwrap();
+        assert_eq!(
+            analysis.commands,
+            vec![
+                vec![literal("pwd")],
+                vec![literal("whoami")],
+                vec![
+                    literal("echo"),
+                    ShellWord::Dynamic("\"dir: $(pwd)\"".to_string()),
+                    ShellWord::Dynamic("`whoami`".to_string()),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn analyze_marks_expansions_and_escapes_as_dynamic() {
+        let analysis = analyze("cat $HOME/notes ~/todo a\\ b 'x'\"y\"z").unwrap();
+        assert_eq!(
+            analysis.commands,
+            vec![vec![
+                literal("cat"),
+                ShellWord::Dynamic("$HOME/notes".to_string()),
+                ShellWord::Dynamic("~/todo".to_string()),
+                ShellWord::Dynamic("a\\ b".to_string()),
+                literal("xyz"),
+            ]]
+        );
+    }
+
+    #[test]
+    fn analyze_records_only_writing_redirects() {
+        let analysis =
+            analyze("grep foo src 2>/dev/null 2>&1 < input.txt > out.txt >> log.txt &> all.txt")
+                .unwrap();
+        assert_eq!(
+            analysis.write_targets,
+            vec![
+                literal("/dev/null"),
+                literal("out.txt"),
+                literal("log.txt"),
+                literal("all.txt"),
+            ]
+        );
+
+        let analysis = analyze("cat <<EOF > notes.md\nhello $(date)\nEOF\n").unwrap();
+        assert_eq!(
+            analysis.commands,
+            vec![vec![literal("cat")], vec![literal("date")]]
+        );
+        assert_eq!(analysis.write_targets, vec![literal("notes.md")]);
+
+        let analysis = analyze("wc -c <<< \"$(ls)\" >&2").unwrap();
+        assert_eq!(
+            analysis.commands,
+            vec![vec![literal("ls")], vec![literal("wc"), literal("-c")]]
+        );
+        assert!(analysis.write_targets.is_empty());
+    }
+
+    #[test]
+    fn analyze_rejects_unmodeled_constructs() {
+        assert!(analyze("ls &").is_none());
+        assert!(analyze("echo hi & echo bye").is_none());
+        assert!(analyze("FOO=bar ls").is_none());
+        assert!(analyze("diff <(ls a) <(ls b)").is_none());
+        assert!(analyze("for f in *; do rm $f; done").is_none());
+        assert!(analyze("f() { ls; }; f").is_none());
+        assert!(analyze("ls &&").is_none());
+    }
 }
@@ -21,6 +21,7 @@ use crate::client_common::ResponseEvent;
 use crate::client_common::ResponseStream;
 use crate::error::CodexErr;
 use crate::error::Result;
+use crate::error::STREAM_IDLE_TIMEOUT_MESSAGE;
 use crate::model_family::ModelFamily;
 use crate::openai_tools::create_tools_json_for_chat_completions_api;
 use crate::util::backoff;
@@ -31,20 +32,25 @@ use codex_protocol::models::ResponseItem;
 /// Implementation for the classic Chat Completions API.
 pub(crate) async fn stream_chat_completions(
     prompt: &Prompt,
+    model: &str,
     model_family: &ModelFamily,
+    parallel_tool_calls: bool,
     client: &reqwest::Client,
     provider: &ModelProviderInfo,
 ) -> Result<ResponseStream> {
-    if prompt.output_schema.is_some() {
-        return Err(CodexErr::UnsupportedOperation(
-            "output_schema is not supported for Chat Completions API".to_string(),
-        ));
-    }
-
     // Build messages array
     let mut messages = Vec::<serde_json::Value>::new();
 
-    let full_instructions = prompt.get_full_instructions(model_family);
+    let mut full_instructions = prompt.get_full_instructions(model_family).into_owned();
+    // Providers that cannot enforce the schema learn about it from the
+    // instructions; the final message is validated after the turn.
+    if let Some(schema) = &prompt.output_schema
+        && !provider.supports_json_schema
+    {
+        full_instructions.push_str(&format!(
+            "\n\nYour final message must be a single JSON value, without any surrounding text, that matches this JSON Schema:\n{schema}"
+        ));
+    }
     messages.push(json!({"role": "system", "content": full_instructions}));
 
     let input = prompt.get_formatted_input();
@@ -274,16 +280,33 @@ pub(crate) async fn stream_chat_completions(
     }
 
     let tools_json = create_tools_json_for_chat_completions_api(&prompt.tools)?;
-    let payload = json!({
-        "model": model_family.slug,
+    let mut payload = json!({
+        "model": model,
         "messages": messages,
         "stream": true,
         "tools": tools_json,
     });
+    // Providers reject `parallel_tool_calls` without tools, and many
+    // OpenAI-compatible servers do not know it, so only send it to enable it.
+    if parallel_tool_calls && !prompt.tools.is_empty() {
+        payload["parallel_tool_calls"] = json!(true);
+    }
+    if let Some(schema) = &prompt.output_schema
+        && provider.supports_json_schema
+    {
+        payload["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "codex_output_schema",
+                "strict": true,
+                "schema": schema,
+            },
+        });
+    }
 
     debug!(
         "POST to {}: {}",
-        provider.get_full_url(&None),
+        provider.get_full_url(&None, model),
         serde_json::to_string_pretty(&payload).unwrap_or_default()
     );
 
@@ -292,7 +315,9 @@ pub(crate) async fn stream_chat_completions(
     loop {
         attempt += 1;
 
-        let req_builder = provider.create_request_builder(client, &None).await?;
+        let req_builder = provider
+            .create_request_builder(client, &None, model)
+            .await?;
 
         let res = req_builder
             .header(reqwest::header::ACCEPT, "text/event-stream")
@@ -395,7 +420,7 @@ async fn process_chat_sse<S>(
             Err(_) => {
                 let _ = tx_event
                     .send(Err(CodexErr::Stream(
-                        "idle timeout waiting for SSE".into(),
+                        STREAM_IDLE_TIMEOUT_MESSAGE.into(),
                         None,
                     )))
                     .await;
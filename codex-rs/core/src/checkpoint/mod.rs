@@ -0,0 +1,491 @@
+//! Workspace checkpoints taken before each turn, so that `Op::Undo` and
+//! `Op::RestoreCheckpoint` can roll back the changes the agent made.
+//!
+//! Inside a git repository a checkpoint is a ghost commit: a commit of the
+//! working tree that no ref points to, created without touching the index
+//! (see `codex_git_tooling`). Other directories are copied into the
+//! content-addressed [`SnapshotStore`] under `~/.codex/checkpoints`.
+//!
+//! The two kinds restore differently: checking out a ghost commit leaves
+//! files created after the checkpoint in place, while restoring a snapshot
+//! deletes them so the directory matches the snapshot exactly.
+//!
+//! Every checkpoint is recorded in the rollout as a
+//! [`RolloutItem::Checkpoint`], so a resumed session can still undo the turns
+//! of its earlier runs. A second checkpoint taken once each turn completes
+//! records the workspace the session left behind, which is what
+//! [`diff_sessions`] compares between two branches of a conversation.
+//! Snapshots that no recorded session refers to any more are removed by
+//! [`collect_garbage`] when session retention prunes rollouts.
+
+mod store;
+
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use codex_git_tooling::CreateGhostCommitOptions;
+use codex_git_tooling::GitToolingError;
+use codex_git_tooling::create_ghost_commit;
+use codex_git_tooling::restore_to_commit;
+use codex_protocol::protocol::CheckpointItem;
+use codex_protocol::protocol::CheckpointKind;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use thiserror::Error;
+
+pub(crate) use store::SnapshotStore;
+
+use crate::git_info::get_git_repo_root;
+use crate::git_info::resolve_root_git_project_for_trust;
+use crate::rollout::RolloutRecorder;
+use crate::rollout::compression::read_rollout_bytes;
+
+/// Snapshots younger than this may belong to a turn that has not recorded
+/// its checkpoint yet, so garbage collection leaves them alone.
+const GC_MIN_AGE: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Error)]
+pub(crate) enum CheckpointError {
+    #[error(transparent)]
+    Git(#[from] GitToolingError),
+
+    #[error("the working directory holds more than {limit} bytes, the checkpoint size limit")]
+    TooLarge { limit: u64 },
+
+    #[error("cannot compare a git checkpoint with a directory snapshot")]
+    MixedKinds,
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Snapshot the working tree at `cwd` before the turn `turn_id` changes it,
+/// or after it completed.
+pub(crate) fn create_checkpoint(
+    codex_home: &Path,
+    cwd: &Path,
+    max_snapshot_bytes: u64,
+    turn_id: String,
+    after_turn: bool,
+) -> Result<CheckpointItem, CheckpointError> {
+    if get_git_repo_root(cwd).is_some() {
+        let commit = create_ghost_commit(&CreateGhostCommitOptions::new(cwd))?;
+        return Ok(CheckpointItem {
+            id: commit.id().to_string(),
+            kind: CheckpointKind::GhostCommit,
+            turn_id,
+            after_turn,
+        });
+    }
+
+    let id = SnapshotStore::new(codex_home).create(cwd, max_snapshot_bytes)?;
+    Ok(CheckpointItem {
+        id,
+        kind: CheckpointKind::Snapshot,
+        turn_id,
+        after_turn,
+    })
+}
+
+/// Make the working tree at `cwd` match `checkpoint` again.
+pub(crate) fn restore_checkpoint(
+    codex_home: &Path,
+    cwd: &Path,
+    checkpoint: &CheckpointItem,
+) -> Result<(), CheckpointError> {
+    match checkpoint.kind {
+        CheckpointKind::GhostCommit => restore_to_commit(cwd, &checkpoint.id)?,
+        CheckpointKind::Snapshot => SnapshotStore::new(codex_home).restore(cwd, &checkpoint.id)?,
+    }
+    Ok(())
+}
+
+/// The checkpoints of a recorded session that can still be restored: every
+/// checkpoint taken before a turn, minus those consumed by a later restore.
+pub(crate) fn checkpoints_from_rollout(items: &[RolloutItem]) -> Vec<CheckpointItem> {
+    let mut checkpoints = Vec::new();
+    for item in items {
+        match item {
+            RolloutItem::Checkpoint(checkpoint) if !checkpoint.after_turn => {
+                checkpoints.push(checkpoint.clone())
+            }
+            RolloutItem::EventMsg(EventMsg::CheckpointRestored(ev)) => {
+                let keep = checkpoints.len().saturating_sub(ev.turns_undone);
+                checkpoints.truncate(keep);
+            }
+            _ => {}
+        }
+    }
+    checkpoints
+}
+
+/// The checkpoint matching the workspace a recorded session left behind:
+/// the most recent checkpoint, or the one restored last if no turn ran
+/// since.
+pub(crate) fn final_checkpoint(items: &[RolloutItem]) -> Option<&CheckpointItem> {
+    let mut seen: Vec<&CheckpointItem> = Vec::new();
+    let mut last = None;
+    for item in items {
+        match item {
+            RolloutItem::Checkpoint(checkpoint) => {
+                seen.push(checkpoint);
+                last = Some(checkpoint);
+            }
+            RolloutItem::EventMsg(EventMsg::CheckpointRestored(ev)) => {
+                last = seen
+                    .iter()
+                    .rev()
+                    .find(|checkpoint| checkpoint.id == ev.checkpoint_id)
+                    .copied()
+                    .or(last);
+            }
+            _ => {}
+        }
+    }
+    last
+}
+
+/// Remove the snapshots, and the objects only they hold, that none of
+/// `rollouts` records as a checkpoint. `rollouts` must list every recorded
+/// session, archived ones included. Returns how many files were removed.
+pub(crate) fn collect_garbage(codex_home: &Path, rollouts: &[PathBuf]) -> io::Result<usize> {
+    let store = SnapshotStore::new(codex_home);
+    if !store.exists() {
+        return Ok(0);
+    }
+
+    let mut live = HashSet::new();
+    for path in rollouts {
+        let bytes = match read_rollout_bytes(path) {
+            Ok(bytes) => bytes,
+            // Deleted since it was listed.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for line in String::from_utf8_lossy(&bytes).lines() {
+            if !line.contains("\"checkpoint\"") {
+                continue;
+            }
+            if let Ok(RolloutLine {
+                item: RolloutItem::Checkpoint(checkpoint),
+                ..
+            }) = serde_json::from_str::<RolloutLine>(line)
+                && checkpoint.kind == CheckpointKind::Snapshot
+            {
+                live.insert(checkpoint.id);
+            }
+        }
+    }
+    store.gc(&live, GC_MIN_AGE)
+}
+
+/// Unified diff between two checkpoints of the working tree at `cwd`.
+pub(crate) fn diff_checkpoints(
+    codex_home: &Path,
+    cwd: &Path,
+    from: &CheckpointItem,
+    to: &CheckpointItem,
+) -> Result<String, CheckpointError> {
+    match (from.kind, to.kind) {
+        (CheckpointKind::GhostCommit, CheckpointKind::GhostCommit) => {
+            let output = Command::new("git")
+                .args([
+                    "diff",
+                    "--no-color",
+                    "--no-ext-diff",
+                    &from.id,
+                    &to.id,
+                    "--",
+                    ".",
+                ])
+                .current_dir(cwd)
+                .output()?;
+            if !output.status.success() {
+                return Err(CheckpointError::Io(io::Error::other(format!(
+                    "git diff failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        (CheckpointKind::Snapshot, CheckpointKind::Snapshot) => {
+            SnapshotStore::new(codex_home).diff(&from.id, &to.id)
+        }
+        _ => Err(CheckpointError::MixedKinds),
+    }
+}
+
+/// Unified diff between the workspaces two recorded sessions, typically
+/// two branches of the same conversation, left behind. Both sessions must
+/// have been recorded with checkpoints enabled, and in the same git
+/// repository (or worktrees of it) if they ran in one.
+pub async fn diff_sessions(codex_home: &Path, from: &Path, to: &Path) -> io::Result<String> {
+    let (from_cwd, from_items) = session_checkpoint_items(from).await?;
+    let (to_cwd, to_items) = session_checkpoint_items(to).await?;
+    let no_checkpoint = |path: &Path| {
+        io::Error::other(format!(
+            "{} has no checkpoints; sessions are only snapshotted when `checkpoints.enabled` is set",
+            path.display()
+        ))
+    };
+    let from_checkpoint = final_checkpoint(&from_items)
+        .cloned()
+        .ok_or_else(|| no_checkpoint(from))?;
+    let to_checkpoint = final_checkpoint(&to_items)
+        .cloned()
+        .ok_or_else(|| no_checkpoint(to))?;
+
+    let codex_home = codex_home.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        // Ghost commits only exist in the repository they were created in,
+        // and the diff runs in the first session's.
+        if from_checkpoint.kind == CheckpointKind::GhostCommit
+            && to_checkpoint.kind == CheckpointKind::GhostCommit
+            && resolve_root_git_project_for_trust(&from_cwd)
+                != resolve_root_git_project_for_trust(&to_cwd)
+        {
+            return Err(io::Error::other(format!(
+                "cannot compare sessions recorded in different git repositories ({} and {})",
+                from_cwd.display(),
+                to_cwd.display()
+            )));
+        }
+        diff_checkpoints(&codex_home, &from_cwd, &from_checkpoint, &to_checkpoint)
+            .map_err(io::Error::other)
+    })
+    .await
+    .map_err(|e| io::Error::other(format!("checkpoint diff task failed: {e}")))?
+}
+
+/// The working directory and rollout items of the session recorded at `path`.
+async fn session_checkpoint_items(path: &Path) -> io::Result<(PathBuf, Vec<RolloutItem>)> {
+    let items = RolloutRecorder::get_rollout_history(path)
+        .await?
+        .get_rollout_items();
+    let cwd = items
+        .iter()
+        .find_map(|item| match item {
+            RolloutItem::SessionMeta(meta_line) => Some(meta_line.meta.cwd.clone()),
+            _ => None,
+        })
+        .ok_or_else(|| io::Error::other(format!("{} has no session metadata", path.display())))?;
+    Ok((cwd, items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::CheckpointRestoredEvent;
+    use pretty_assertions::assert_eq;
+
+    fn checkpoint(id: &str) -> CheckpointItem {
+        CheckpointItem {
+            id: id.to_string(),
+            kind: CheckpointKind::Snapshot,
+            turn_id: id.to_string(),
+            after_turn: false,
+        }
+    }
+
+    #[test]
+    fn restores_consume_later_checkpoints() {
+        let items = vec![
+            RolloutItem::Checkpoint(checkpoint("a")),
+            RolloutItem::Checkpoint(checkpoint("b")),
+            RolloutItem::Checkpoint(checkpoint("c")),
+            RolloutItem::EventMsg(EventMsg::CheckpointRestored(CheckpointRestoredEvent {
+                checkpoint_id: "b".to_string(),
+                turns_undone: 2,
+            })),
+            RolloutItem::Checkpoint(checkpoint("d")),
+        ];
+
+        assert_eq!(
+            checkpoints_from_rollout(&items),
+            vec![checkpoint("a"), checkpoint("d")]
+        );
+    }
+
+    #[test]
+    fn snapshot_checkpoint_round_trip() {
+        let codex_home = tempfile::TempDir::new().unwrap();
+        let workspace = tempfile::TempDir::new().unwrap();
+        let cwd = workspace.path();
+        std::fs::write(cwd.join("kept.txt"), "original").unwrap();
+        std::fs::create_dir(cwd.join("src")).unwrap();
+        std::fs::write(cwd.join("src/lib.rs"), "fn main() {}").unwrap();
+
+        let item =
+            create_checkpoint(codex_home.path(), cwd, u64::MAX, "1".to_string(), false).unwrap();
+        assert_eq!(item.kind, CheckpointKind::Snapshot);
+
+        std::fs::write(cwd.join("kept.txt"), "changed").unwrap();
+        std::fs::remove_file(cwd.join("src/lib.rs")).unwrap();
+        std::fs::create_dir(cwd.join("new")).unwrap();
+        std::fs::write(cwd.join("new/file.txt"), "added").unwrap();
+
+        restore_checkpoint(codex_home.path(), cwd, &item).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(cwd.join("kept.txt")).unwrap(),
+            "original"
+        );
+        assert_eq!(
+            std::fs::read_to_string(cwd.join("src/lib.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert!(!cwd.join("new").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn snapshot_restore_replaces_symlinks_instead_of_following_them() {
+        let codex_home = tempfile::TempDir::new().unwrap();
+        let workspace = tempfile::TempDir::new().unwrap();
+        let outside = tempfile::TempDir::new().unwrap();
+        let cwd = workspace.path();
+        let victim = outside.path().join("victim.txt");
+        std::fs::write(&victim, "outside").unwrap();
+        std::fs::write(cwd.join("file.txt"), "original").unwrap();
+        std::os::unix::fs::symlink("file.txt", cwd.join("link")).unwrap();
+
+        let item =
+            create_checkpoint(codex_home.path(), cwd, u64::MAX, "1".to_string(), false).unwrap();
+
+        std::fs::remove_file(cwd.join("file.txt")).unwrap();
+        std::os::unix::fs::symlink(&victim, cwd.join("file.txt")).unwrap();
+        std::fs::remove_file(cwd.join("link")).unwrap();
+        std::os::unix::fs::symlink(&victim, cwd.join("new-link")).unwrap();
+
+        restore_checkpoint(codex_home.path(), cwd, &item).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&victim).unwrap(), "outside");
+        let metadata = std::fs::symlink_metadata(cwd.join("file.txt")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(
+            std::fs::read_to_string(cwd.join("file.txt")).unwrap(),
+            "original"
+        );
+        assert_eq!(
+            std::fs::read_link(cwd.join("link")).unwrap(),
+            PathBuf::from("file.txt")
+        );
+        assert!(std::fs::symlink_metadata(cwd.join("new-link")).is_err());
+    }
+
+    #[test]
+    fn gc_removes_unreferenced_snapshots_and_objects() {
+        let codex_home = tempfile::TempDir::new().unwrap();
+        let workspace = tempfile::TempDir::new().unwrap();
+        let cwd = workspace.path();
+        std::fs::write(cwd.join("shared.txt"), "shared").unwrap();
+        std::fs::write(cwd.join("file.txt"), "old").unwrap();
+        let old = SnapshotStore::new(codex_home.path())
+            .create(cwd, u64::MAX)
+            .unwrap();
+        std::fs::write(cwd.join("file.txt"), "new").unwrap();
+        let new = SnapshotStore::new(codex_home.path())
+            .create(cwd, u64::MAX)
+            .unwrap();
+
+        let store = SnapshotStore::new(codex_home.path());
+        let live = HashSet::from([new.clone()]);
+        assert_eq!(store.gc(&live, Duration::from_secs(3600)).unwrap(), 0);
+        // The old snapshot and the object only it refers to.
+        assert_eq!(store.gc(&live, Duration::ZERO).unwrap(), 2);
+
+        let err = store.restore(cwd, &old).unwrap_err();
+        assert!(matches!(err, CheckpointError::Io(_)));
+        std::fs::write(cwd.join("file.txt"), "changed").unwrap();
+        store.restore(cwd, &new).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(cwd.join("file.txt")).unwrap(),
+            "new"
+        );
+        assert_eq!(
+            std::fs::read_to_string(cwd.join("shared.txt")).unwrap(),
+            "shared"
+        );
+    }
+
+    #[test]
+    fn snapshot_respects_size_limit() {
+        let codex_home = tempfile::TempDir::new().unwrap();
+        let workspace = tempfile::TempDir::new().unwrap();
+        std::fs::write(workspace.path().join("big.bin"), vec![0u8; 64]).unwrap();
+
+        let err = create_checkpoint(
+            codex_home.path(),
+            workspace.path(),
+            16,
+            "1".to_string(),
+            false,
+        )
+        .unwrap_err();
+        assert!(matches!(err, CheckpointError::TooLarge { limit: 16 }));
+    }
+
+    #[test]
+    fn final_checkpoint_follows_restores() {
+        let after = CheckpointItem {
+            after_turn: true,
+            ..checkpoint("b-after")
+        };
+        let mut items = vec![
+            RolloutItem::Checkpoint(checkpoint("a")),
+            RolloutItem::Checkpoint(checkpoint("b")),
+            RolloutItem::Checkpoint(after.clone()),
+        ];
+        assert_eq!(final_checkpoint(&items), Some(&after));
+        assert_eq!(
+            checkpoints_from_rollout(&items),
+            vec![checkpoint("a"), checkpoint("b")]
+        );
+
+        items.push(RolloutItem::EventMsg(EventMsg::CheckpointRestored(
+            CheckpointRestoredEvent {
+                checkpoint_id: "a".to_string(),
+                turns_undone: 2,
+            },
+        )));
+        assert_eq!(final_checkpoint(&items), Some(&checkpoint("a")));
+    }
+
+    #[test]
+    fn snapshot_diff_lists_changed_files() {
+        let codex_home = tempfile::TempDir::new().unwrap();
+        let workspace = tempfile::TempDir::new().unwrap();
+        let cwd = workspace.path();
+        std::fs::write(cwd.join("same.txt"), "same\n").unwrap();
+        std::fs::write(cwd.join("edited.txt"), "one\ntwo\n").unwrap();
+        let from =
+            create_checkpoint(codex_home.path(), cwd, u64::MAX, "1".to_string(), true).unwrap();
+
+        std::fs::write(cwd.join("edited.txt"), "one\nthree\n").unwrap();
+        std::fs::write(cwd.join("added.txt"), "new\n").unwrap();
+        let to =
+            create_checkpoint(codex_home.path(), cwd, u64::MAX, "2".to_string(), true).unwrap();
+
+        let diff = diff_checkpoints(codex_home.path(), cwd, &from, &to).unwrap();
+        assert_eq!(
+            diff,
+            "diff --git a/added.txt b/added.txt\n\
+             --- /dev/null\n\
+             +++ b/added.txt\n\
+             @@ -0,0 +1 @@\n\
+             +new\n\
+             diff --git a/edited.txt b/edited.txt\n\
+             --- a/edited.txt\n\
+             +++ b/edited.txt\n\
+             @@ -1,2 +1,2 @@\n \
+             one\n\
+             -two\n\
+             +three\n"
+        );
+    }
+}
@@ -0,0 +1,441 @@
+//! Content-addressed snapshots of working directories that are not git
+//! repositories.
+//!
+//! File contents are stored once under `objects/<sha1>`, however many
+//! snapshots contain them. A snapshot is a manifest of the directories and
+//! files under the working directory, stored as `snapshots/<id>.json` where
+//! `id` is the SHA-1 of the manifest itself.
+//!
+//! Snapshots and objects are never rewritten, only garbage collected once
+//! no recorded session refers to them any more (see [`SnapshotStore::gc`]).
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use serde::Serialize;
+use sha1::Digest;
+use sha1::Sha1;
+use similar::TextDiff;
+use walkdir::WalkDir;
+
+use super::CheckpointError;
+
+/// Directory inside `~/.codex` that holds the snapshot store.
+pub(crate) const CHECKPOINTS_DIRNAME: &str = "checkpoints";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// Directories, relative to the snapshot root, with `/` separators.
+    dirs: BTreeSet<String>,
+    /// Regular files, keyed like `dirs`.
+    files: BTreeMap<String, FileEntry>,
+    /// Symlinks, keyed like `dirs`, and the path each one points to.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    symlinks: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FileEntry {
+    object: String,
+    executable: bool,
+}
+
+pub(crate) struct SnapshotStore {
+    codex_home: PathBuf,
+    root: PathBuf,
+}
+
+impl SnapshotStore {
+    pub(crate) fn new(codex_home: &Path) -> Self {
+        Self {
+            codex_home: codex_home.to_path_buf(),
+            root: codex_home.join(CHECKPOINTS_DIRNAME),
+        }
+    }
+
+    /// Whether any snapshot was ever taken.
+    pub(crate) fn exists(&self) -> bool {
+        self.root.exists()
+    }
+
+    /// Copy the regular files under `dir` into the store and return the id
+    /// of the snapshot. Symlinks are recorded as links, not followed.
+    pub(crate) fn create(&self, dir: &Path, max_bytes: u64) -> Result<String, CheckpointError> {
+        let mut manifest = Manifest::default();
+        let mut total_bytes: u64 = 0;
+        for entry in self.walk(dir, false) {
+            let entry = entry.map_err(io::Error::from)?;
+            let file_type = entry.file_type();
+            if !file_type.is_dir() && !file_type.is_file() && !file_type.is_symlink() {
+                continue;
+            }
+            let rel = relative_key(dir, entry.path())?;
+            if file_type.is_symlink() {
+                let target = fs::read_link(entry.path())?;
+                let target = target.to_str().ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "cannot snapshot non-UTF-8 symlink {}",
+                            entry.path().display()
+                        ),
+                    )
+                })?;
+                manifest.symlinks.insert(rel, target.to_string());
+                continue;
+            }
+            if file_type.is_dir() {
+                manifest.dirs.insert(rel);
+                continue;
+            }
+
+            let metadata = entry.metadata().map_err(io::Error::from)?;
+            total_bytes = total_bytes.saturating_add(metadata.len());
+            if total_bytes > max_bytes {
+                return Err(CheckpointError::TooLarge { limit: max_bytes });
+            }
+            let contents = fs::read(entry.path())?;
+            let object = self.write_object(&contents)?;
+            manifest.files.insert(
+                rel,
+                FileEntry {
+                    object,
+                    executable: is_executable(&metadata),
+                },
+            );
+        }
+
+        let json = serde_json::to_vec(&manifest).map_err(io::Error::other)?;
+        let id = sha1_hex(&json);
+        let path = self.snapshot_path(&id);
+        if !path.exists() {
+            write_atomically(&path, &json)?;
+        }
+        Ok(id)
+    }
+
+    /// Make `dir` match snapshot `id`: files, symlinks and directories
+    /// created since are removed, and modified or deleted ones are written
+    /// back. Unlike restoring a ghost commit, which leaves files created
+    /// after the checkpoint in place, this deletes them.
+    ///
+    /// Symlinks that are not part of the snapshot are removed before
+    /// anything is written, and files are written through a temporary file
+    /// renamed over the path, so a restore never writes outside `dir`.
+    pub(crate) fn restore(&self, dir: &Path, id: &str) -> Result<(), CheckpointError> {
+        let manifest = self.read_manifest(id)?;
+
+        // Children come before their parents, so directories are empty by
+        // the time they are visited.
+        for entry in self.walk(dir, true) {
+            let entry = entry.map_err(io::Error::from)?;
+            let rel = relative_key(dir, entry.path())?;
+            let file_type = entry.file_type();
+            if file_type.is_symlink() {
+                let target = fs::read_link(entry.path())?;
+                if manifest.symlinks.get(&rel).map(Path::new) != Some(target.as_path()) {
+                    fs::remove_file(entry.path())?;
+                }
+            } else if file_type.is_file() && !manifest.files.contains_key(&rel) {
+                fs::remove_file(entry.path())?;
+            } else if file_type.is_dir() && !manifest.dirs.contains(&rel) {
+                // Directories that hold `CODEX_HOME` are left alone.
+                if fs::read_dir(entry.path())?.next().is_none() {
+                    fs::remove_dir(entry.path())?;
+                }
+            }
+        }
+
+        for rel in &manifest.dirs {
+            let path = dir.join(rel);
+            if let Ok(metadata) = fs::symlink_metadata(&path)
+                && !metadata.is_dir()
+            {
+                fs::remove_file(&path)?;
+            }
+            fs::create_dir_all(&path)?;
+        }
+        for (rel, file) in &manifest.files {
+            let path = dir.join(rel);
+            let unchanged = fs::symlink_metadata(&path).is_ok_and(|m| m.is_file())
+                && fs::read(&path).is_ok_and(|contents| sha1_hex(&contents) == file.object);
+            if !unchanged {
+                self.copy_object_to(&file.object, &path)?;
+            }
+            set_executable(&path, file.executable)?;
+        }
+        for (rel, target) in &manifest.symlinks {
+            let path = dir.join(rel);
+            match fs::symlink_metadata(&path) {
+                Ok(metadata) if metadata.is_symlink() => continue,
+                Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&path)?,
+                Ok(_) => fs::remove_file(&path)?,
+                Err(_) => {}
+            }
+            create_symlink(Path::new(target), &path)?;
+        }
+        Ok(())
+    }
+
+    /// Delete the snapshots not in `live`, then the objects no remaining
+    /// snapshot refers to, and return how many files were removed.
+    /// Snapshots and objects younger than `min_age` are kept, since a
+    /// session may have just written them without recording the snapshot
+    /// in its rollout yet.
+    pub(crate) fn gc(&self, live: &HashSet<String>, min_age: Duration) -> io::Result<usize> {
+        let now = SystemTime::now();
+        let is_recent = |metadata: &fs::Metadata| {
+            metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_none_or(|age| age < min_age)
+        };
+
+        let mut removed = 0;
+        let mut referenced: HashSet<String> = HashSet::new();
+        let snapshots_dir = self.root.join("snapshots");
+        if snapshots_dir.exists() {
+            for entry in fs::read_dir(&snapshots_dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                let Some(id) = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_suffix(".json"))
+                else {
+                    continue;
+                };
+                if !live.contains(id) && !is_recent(&entry.metadata()?) {
+                    fs::remove_file(&path)?;
+                    removed += 1;
+                    continue;
+                }
+                let manifest = self.read_manifest(id).map_err(io::Error::other)?;
+                referenced.extend(manifest.files.into_values().map(|file| file.object));
+            }
+        }
+
+        let objects_dir = self.root.join("objects");
+        if objects_dir.exists() {
+            for prefix in fs::read_dir(&objects_dir)? {
+                let prefix = prefix?;
+                if !prefix.file_type()?.is_dir() {
+                    continue;
+                }
+                let prefix_name = prefix.file_name().to_string_lossy().into_owned();
+                for entry in fs::read_dir(prefix.path())? {
+                    let entry = entry?;
+                    let object = format!("{prefix_name}{}", entry.file_name().to_string_lossy());
+                    if !referenced.contains(&object) && !is_recent(&entry.metadata()?) {
+                        fs::remove_file(entry.path())?;
+                        removed += 1;
+                    }
+                }
+                // Only succeeds once the prefix directory is empty.
+                let _ = fs::remove_dir(prefix.path());
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Unified diff from snapshot `from` to snapshot `to`, in the format of
+    /// `git diff`. Files that are not UTF-8 are reported as binary.
+    pub(crate) fn diff(&self, from: &str, to: &str) -> Result<String, CheckpointError> {
+        let from_manifest = self.read_manifest(from)?;
+        let to_manifest = self.read_manifest(to)?;
+        let paths: BTreeSet<&String> = from_manifest
+            .files
+            .keys()
+            .chain(to_manifest.files.keys())
+            .collect();
+
+        let mut out = String::new();
+        for path in paths {
+            let old = from_manifest.files.get(path);
+            let new = to_manifest.files.get(path);
+            if old.map(|f| &f.object) == new.map(|f| &f.object) {
+                continue;
+            }
+            let old_contents = old
+                .map(|f| fs::read(self.object_path(&f.object)))
+                .transpose()?;
+            let new_contents = new
+                .map(|f| fs::read(self.object_path(&f.object)))
+                .transpose()?;
+            let old_header = match old {
+                Some(_) => format!("a/{path}"),
+                None => "/dev/null".to_string(),
+            };
+            let new_header = match new {
+                Some(_) => format!("b/{path}"),
+                None => "/dev/null".to_string(),
+            };
+
+            out.push_str(&format!("diff --git a/{path} b/{path}\n"));
+            let old_text = old_contents.as_deref().map(std::str::from_utf8);
+            let new_text = new_contents.as_deref().map(std::str::from_utf8);
+            match (old_text, new_text) {
+                (Some(Err(_)), _) | (_, Some(Err(_))) => {
+                    out.push_str(&format!(
+                        "Binary files {old_header} and {new_header} differ\n"
+                    ));
+                }
+                (old_text, new_text) => {
+                    let old_text = old_text.and_then(Result::ok).unwrap_or("");
+                    let new_text = new_text.and_then(Result::ok).unwrap_or("");
+                    let unified = TextDiff::from_lines(old_text, new_text)
+                        .unified_diff()
+                        .context_radius(3)
+                        .header(&old_header, &new_header)
+                        .to_string();
+                    out.push_str(&unified);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn read_manifest(&self, id: &str) -> Result<Manifest, CheckpointError> {
+        let json = fs::read(self.snapshot_path(id))
+            .map_err(|e| io::Error::new(e.kind(), format!("failed to read snapshot {id}: {e}")))?;
+        Ok(serde_json::from_slice(&json).map_err(io::Error::other)?)
+    }
+
+    /// Entries under `dir`, skipping `CODEX_HOME` when the working
+    /// directory contains it (e.g. a session started in `~`).
+    fn walk(
+        &self,
+        dir: &Path,
+        contents_first: bool,
+    ) -> impl Iterator<Item = walkdir::Result<walkdir::DirEntry>> + use<> {
+        let codex_home = self.codex_home.clone();
+        WalkDir::new(dir)
+            .min_depth(1)
+            .follow_links(false)
+            .contents_first(contents_first)
+            .into_iter()
+            .filter_entry(move |entry| !entry.path().starts_with(&codex_home))
+    }
+
+    fn write_object(&self, contents: &[u8]) -> io::Result<String> {
+        let object = sha1_hex(contents);
+        let path = self.object_path(&object);
+        if !path.exists() {
+            write_atomically(&path, contents)?;
+        }
+        Ok(object)
+    }
+
+    /// Write object `object` to `path` through a temporary file in the same
+    /// directory, replacing whatever is at `path` instead of writing
+    /// through it when it is a symlink.
+    fn copy_object_to(&self, object: &str, path: &Path) -> io::Result<()> {
+        let tmp = path.with_extension(format!("{}.codex-restore.tmp", std::process::id()));
+        fs::copy(self.object_path(object), &tmp)?;
+        if let Ok(metadata) = fs::symlink_metadata(path)
+            && metadata.is_dir()
+        {
+            fs::remove_dir_all(path)?;
+        }
+        fs::rename(&tmp, path)
+    }
+
+    fn object_path(&self, object: &str) -> PathBuf {
+        let (prefix, rest) = object.split_at(2.min(object.len()));
+        self.root.join("objects").join(prefix).join(rest)
+    }
+
+    fn snapshot_path(&self, id: &str) -> PathBuf {
+        self.root.join("snapshots").join(format!("{id}.json"))
+    }
+}
+
+/// `path` relative to `root`, with `/` separators.
+fn relative_key(root: &Path, path: &Path) -> io::Result<String> {
+    let rel = path.strip_prefix(root).map_err(io::Error::other)?;
+    let parts = rel
+        .components()
+        .map(|component| {
+            component.as_os_str().to_str().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("cannot snapshot non-UTF-8 path {}", path.display()),
+                )
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok(parts.join("/"))
+}
+
+fn sha1_hex(contents: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(contents))
+}
+
+/// Writes through a temporary file so a concurrent reader never sees a
+/// partial object.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let Some(dir) = path.parent() else {
+        return Err(io::Error::other(format!(
+            "{} has no parent directory",
+            path.display()
+        )));
+    };
+    fs::create_dir_all(dir)?;
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// Creating symlinks needs extra privileges on Windows, so recorded
+/// symlinks are not recreated there.
+#[cfg(not(unix))]
+fn create_symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    let mode = permissions.mode();
+    let wanted = if executable {
+        mode | ((mode & 0o444) >> 2)
+    } else {
+        mode & !0o111
+    };
+    if wanted != mode {
+        permissions.set_mode(wanted);
+        fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path, _executable: bool) -> io::Result<()> {
+    Ok(())
+}
@@ -23,6 +23,7 @@ use tracing::debug;
 use tracing::trace;
 use tracing::warn;
 
+use crate::anthropic_messages::stream_anthropic_messages;
 use crate::chat_completions::AggregateStreamExt;
 use crate::chat_completions::stream_chat_completions;
 use crate::client_common::Prompt;
@@ -32,18 +33,25 @@ use crate::client_common::ResponsesApiRequest;
 use crate::client_common::create_reasoning_param_for_request;
 use crate::client_common::create_text_param_for_request;
 use crate::config::Config;
+use crate::config_types::ModelFallback;
+use crate::config_types::ResponseCacheMode;
 use crate::default_client::create_client;
 use crate::error::CodexErr;
 use crate::error::Result;
+use crate::error::STREAM_IDLE_TIMEOUT_MESSAGE;
 use crate::error::UsageLimitReachedError;
 use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::gemini::stream_gemini;
 use crate::model_family::ModelFamily;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::WireApi;
 use crate::openai_model_info::get_model_info;
 use crate::openai_tools::create_tools_json_for_responses_api;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::TokenUsage;
+use crate::response_cache;
 use crate::token_data::PlanType;
 use crate::util::backoff;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
@@ -113,17 +121,74 @@ impl ModelClient {
         })
     }
 
-    /// Dispatches to either the Responses or Chat implementation depending on
-    /// the provider config.  Public callers always invoke `stream()` – the
+    /// Dispatches to the Responses, Chat, Anthropic Messages or Gemini
+    /// implementation depending on the provider config, going through the
+    /// response cache when one is configured.  Public callers always invoke `stream()` – the
     /// specialised helpers are private to avoid accidental misuse.
     pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
+        let Some(mode) = self.config.response_cache else {
+            return self.stream_from_provider(prompt).await;
+        };
+
+        let settings = response_cache::RequestSettings {
+            provider_id: &self.config.model_provider_id,
+            model: &self.config.model,
+            model_family: &self.config.model_family,
+            reasoning_effort: self.effort,
+            reasoning_summary: self.summary,
+            verbosity: self.config.model_verbosity,
+        };
+        let path = response_cache::transcript_path(
+            &self.config.codex_home,
+            &self.config.cwd,
+            settings,
+            prompt,
+        )?;
+        if mode != ResponseCacheMode::Record && path.exists() {
+            debug!(path = %path.display(), "Replaying cached response");
+            return stream_from_fixture(&path, self.provider.clone()).await;
+        }
+        if mode == ResponseCacheMode::Replay {
+            return Err(CodexErr::ResponseCacheMiss(path));
+        }
+        let stream = self.stream_from_provider(prompt).await?;
+        Ok(response_cache::record(stream, path))
+    }
+
+    async fn stream_from_provider(&self, prompt: &Prompt) -> Result<ResponseStream> {
         match self.provider.wire_api {
             WireApi::Responses => self.stream_responses(prompt).await,
+            WireApi::AnthropicMessages => {
+                stream_anthropic_messages(
+                    prompt,
+                    &self.config.model,
+                    &self.config.model_family,
+                    self.effort,
+                    self.config.model_max_output_tokens,
+                    &self.client,
+                    &self.provider,
+                )
+                .await
+            }
+            WireApi::Gemini => {
+                stream_gemini(
+                    prompt,
+                    &self.config.model,
+                    &self.config.model_family,
+                    self.effort,
+                    self.config.model_max_output_tokens,
+                    &self.client,
+                    &self.provider,
+                )
+                .await
+            }
             WireApi::Chat => {
                 // Create the raw streaming connection first.
                 let response_stream = stream_chat_completions(
                     prompt,
+                    &self.config.model,
                     &self.config.model_family,
+                    self.parallel_tool_calls(),
                     &self.client,
                     &self.provider,
                 )
@@ -211,7 +276,8 @@ impl ModelClient {
             input: &input_with_instructions,
             tools: &tools_json,
             tool_choice: "auto",
-            parallel_tool_calls: false,
+            // Like on the Chat Completions path, only with tools to call.
+            parallel_tool_calls: self.parallel_tool_calls() && !tools_json.is_empty(),
             reasoning,
             store: azure_workaround,
             stream: true,
@@ -237,13 +303,13 @@ impl ModelClient {
 
             trace!(
                 "POST to {}: {}",
-                self.provider.get_full_url(&auth),
+                self.provider.get_full_url(&auth, &self.config.model),
                 payload_body.as_str()
             );
 
             let mut req_builder = self
                 .provider
-                .create_request_builder(&self.client, &auth)
+                .create_request_builder(&self.client, &auth, &self.config.model)
                 .await?;
 
             req_builder = req_builder
@@ -385,6 +451,12 @@ impl ModelClient {
         self.config.model.clone()
     }
 
+    /// Whether requests let the model emit several tool calls at once. Off
+    /// unless enabled in the config.
+    pub fn parallel_tool_calls(&self) -> bool {
+        self.config.parallel_tool_calls.unwrap_or(false)
+    }
+
     /// Returns the currently configured model family.
     pub fn get_model_family(&self) -> ModelFamily {
         self.config.model_family.clone()
@@ -403,6 +475,36 @@ impl ModelClient {
     pub fn get_auth_manager(&self) -> Option<Arc<AuthManager>> {
         self.auth_manager.clone()
     }
+
+    pub(crate) fn get_config(&self) -> Arc<Config> {
+        Arc::clone(&self.config)
+    }
+
+    /// Returns a client that sends the same requests to `fallback`'s model
+    /// and provider instead.
+    pub(crate) fn with_fallback(&self, fallback: &ModelFallback) -> Self {
+        let mut config = (*self.config).clone();
+        config.model = fallback.model.clone();
+        config.model_family = find_family_for_model(&fallback.model)
+            .unwrap_or_else(|| derive_default_model_family(&fallback.model));
+        let model_info = get_model_info(&config.model_family);
+        config.model_context_window = model_info.as_ref().map(|info| info.context_window);
+        config.model_max_output_tokens = model_info.as_ref().map(|info| info.max_output_tokens);
+        config.model_auto_compact_token_limit =
+            model_info.and_then(|info| info.auto_compact_token_limit);
+        config.model_provider_id = fallback.model_provider_id.clone();
+        config.model_provider = fallback.model_provider.clone();
+
+        Self {
+            config: Arc::new(config),
+            auth_manager: self.auth_manager.clone(),
+            client: self.client.clone(),
+            provider: fallback.model_provider.clone(),
+            conversation_id: self.conversation_id,
+            effort: self.effort,
+            summary: self.summary,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -568,7 +670,7 @@ async fn process_sse<S>(
             Err(_) => {
                 let _ = tx_event
                     .send(Err(CodexErr::Stream(
-                        "idle timeout waiting for SSE".into(),
+                        STREAM_IDLE_TIMEOUT_MESSAGE.into(),
                         None,
                     )))
                     .await;
@@ -727,6 +829,20 @@ async fn stream_from_fixture(
     provider: ModelProviderInfo,
 ) -> Result<ResponseStream> {
     let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+    let stream = read_sse_fixture(path)?;
+    tokio::spawn(process_sse(
+        stream,
+        tx_event,
+        provider.stream_idle_timeout(),
+    ));
+    Ok(ResponseStream { rx_event })
+}
+
+/// Reads an SSE fixture (see `CODEX_RS_SSE_FIXTURE`) into a byte stream that
+/// can be fed to any of the SSE processors.
+pub(crate) fn read_sse_fixture(
+    path: impl AsRef<Path>,
+) -> Result<impl Stream<Item = Result<Bytes>> + Unpin + Send + 'static> {
     let f = std::fs::File::open(path.as_ref())?;
     let lines = std::io::BufReader::new(f).lines();
 
@@ -738,13 +854,7 @@ async fn stream_from_fixture(
     }
 
     let rdr = std::io::Cursor::new(content);
-    let stream = ReaderStream::new(rdr).map_err(CodexErr::Io);
-    tokio::spawn(process_sse(
-        stream,
-        tx_event,
-        provider.stream_idle_timeout(),
-    ));
-    Ok(ResponseStream { rx_event })
+    Ok(ReaderStream::new(rdr).map_err(CodexErr::Io))
 }
 
 fn rate_limit_regex() -> &'static Regex {
@@ -895,6 +1005,7 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            supports_json_schema: false,
         };
 
         let events = collect_events(
@@ -955,6 +1066,7 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            supports_json_schema: false,
         };
 
         let events = collect_events(&[sse1.as_bytes()], provider).await;
@@ -989,6 +1101,7 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            supports_json_schema: false,
         };
 
         let events = collect_events(&[sse1.as_bytes()], provider).await;
@@ -1094,6 +1207,7 @@ mod tests {
                 stream_max_retries: Some(0),
                 stream_idle_timeout_ms: Some(1000),
                 requires_openai_auth: false,
+                supports_json_schema: false,
             };
 
             let out = run_sse(evs, provider).await;
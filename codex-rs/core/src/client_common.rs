@@ -7,10 +7,12 @@ use codex_apply_patch::APPLY_PATCH_TOOL_INSTRUCTIONS;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use codex_protocol::config_types::Verbosity as VerbosityConfig;
+use codex_protocol::models::LocalShellAction;
 use codex_protocol::models::ResponseItem;
 use futures::Stream;
 use serde::Serialize;
 use serde_json::Value;
+use serde_json::json;
 use std::borrow::Cow;
 use std::ops::Deref;
 use std::pin::Pin;
@@ -175,6 +177,46 @@ pub(crate) fn create_reasoning_param_for_request(
     })
 }
 
+/// Token budget for extended thinking on the Anthropic Messages and Gemini
+/// APIs, which take a budget rather than an effort level. `None` disables
+/// thinking.
+pub(crate) fn thinking_budget_tokens(
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+) -> Option<u64> {
+    if !model_family.supports_reasoning_summaries {
+        return None;
+    }
+
+    match effort.unwrap_or_default() {
+        ReasoningEffortConfig::Minimal => None,
+        ReasoningEffortConfig::Low => Some(4_096),
+        ReasoningEffortConfig::Medium => Some(10_240),
+        ReasoningEffortConfig::High => Some(24_576),
+    }
+}
+
+/// Splits a `data:<mime>;base64,<data>` image URL (as produced for local
+/// images) into its media type and payload.
+pub(crate) fn split_base64_data_url(url: &str) -> Option<(&str, &str)> {
+    let (media_type, data) = url.strip_prefix("data:")?.split_once(";base64,")?;
+    Some((media_type, data))
+}
+
+/// Arguments for the `shell` function tool equivalent to a `local_shell`
+/// call, so the call can be replayed to providers without that built-in tool.
+pub(crate) fn local_shell_call_arguments(action: &LocalShellAction) -> Value {
+    let LocalShellAction::Exec(exec) = action;
+    let mut arguments = json!({"command": exec.command});
+    if let Some(workdir) = &exec.working_directory {
+        arguments["workdir"] = json!(workdir);
+    }
+    if let Some(timeout_ms) = exec.timeout_ms {
+        arguments["timeout_ms"] = json!(timeout_ms);
+    }
+    arguments
+}
+
 pub(crate) fn create_text_param_for_request(
     verbosity: Option<VerbosityConfig>,
     output_schema: &Option<Value>,
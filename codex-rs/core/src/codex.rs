@@ -10,7 +10,10 @@ use std::time::Duration;
 use crate::AuthManager;
 use crate::client_common::REVIEW_PROMPT;
 use crate::event_mapping::map_response_item_to_event_messages;
+use crate::git_info::get_git_repo_root;
 use crate::review_format::format_review_findings_block;
+use crate::review_target::review_prompt_with_diff;
+use crate::review_target::review_target_diff;
 use crate::user_notification::UserNotifier;
 use async_channel::Receiver;
 use async_channel::Sender;
@@ -18,6 +21,10 @@ use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::MaybeApplyPatchVerified;
 use codex_apply_patch::maybe_parse_apply_patch_verified;
 use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::protocol::BudgetKind;
+use codex_protocol::protocol::BudgetScope;
+use codex_protocol::protocol::CheckpointItem;
+use codex_protocol::protocol::CheckpointRestoredEvent;
 use codex_protocol::protocol::ConversationPathResponseEvent;
 use codex_protocol::protocol::ExitedReviewModeEvent;
 use codex_protocol::protocol::ReviewRequest;
@@ -47,12 +54,27 @@ use crate::apply_patch::ApplyPatchExec;
 use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::audit::ApprovalSubject;
+use crate::audit::AuditEvent;
+use crate::audit::AuditLogger;
+use crate::checkpoint::CheckpointError;
+use crate::checkpoint::checkpoints_from_rollout;
+use crate::checkpoint::create_checkpoint;
+use crate::checkpoint::restore_checkpoint;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config_types::Budget;
+use crate::config_types::LinuxSandboxBackend;
+use crate::config_types::ModelFallback;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
+use crate::cost;
+use crate::cost::CostRecord;
+use crate::cost::DailyCost;
+use crate::delegate_tool::DELEGATE_TOOL_NAME;
+use crate::delegate_tool::handle_delegate;
 use crate::environment_context::EnvironmentContext;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
@@ -65,11 +87,17 @@ use crate::exec::StdoutStream;
 use crate::exec::StreamOutput;
 use crate::exec::process_exec_tool_call;
 use crate::exec_command::EXEC_COMMAND_TOOL_NAME;
+use crate::exec_command::ExecCommandOutput;
 use crate::exec_command::ExecCommandParams;
 use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
 use crate::exec_env::create_env;
+use crate::hooks::HookOutcome;
+use crate::hooks::HookPayload;
+use crate::hooks::HookRunner;
+use crate::is_safe_command::CommandSafetyVerdict;
+use crate::is_safe_command::analyze_command_safety;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::mcp_tool_call::handle_mcp_tool_call;
 use crate::model_family::find_family_for_model;
@@ -78,6 +106,8 @@ use crate::openai_tools::ApplyPatchToolArgs;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
 use crate::openai_tools::get_openai_tools;
+use crate::output_schema::retry_prompt;
+use crate::output_schema::validate_final_message;
 use crate::parse_command::parse_command;
 use crate::plan_tool::handle_update_plan;
 use crate::project_doc::get_user_instructions;
@@ -86,8 +116,10 @@ use crate::protocol::AgentReasoningDeltaEvent;
 use crate::protocol::AgentReasoningRawContentDeltaEvent;
 use crate::protocol::AgentReasoningSectionBreakEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
+use crate::protocol::ApprovalSource;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::CompactedItem;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -98,13 +130,16 @@ use crate::protocol::FileChange;
 use crate::protocol::InputItem;
 use crate::protocol::ListCustomPromptsResponseEvent;
 use crate::protocol::Op;
+use crate::protocol::OutputSchemaMismatchEvent;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::ReviewDecision;
 use crate::protocol::ReviewOutputEvent;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SandboxViolation;
 use crate::protocol::SessionConfiguredEvent;
+use crate::protocol::SessionFork;
 use crate::protocol::StreamErrorEvent;
 use crate::protocol::Submission;
 use crate::protocol::TaskCompleteEvent;
@@ -113,12 +148,16 @@ use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
 use crate::protocol::TurnDiffEvent;
 use crate::protocol::WebSearchBeginEvent;
+use crate::redact::SecretRedactor;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
 use crate::safety::SafetyCheck;
+use crate::safety::apply_linux_sandbox_backend;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
 use crate::shell;
+use crate::tokenizer::Tokenizer;
+use crate::truncate::truncate_middle;
 use crate::turn_diff_tracker::TurnDiffTracker;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_instructions::UserInstructions;
@@ -135,9 +174,11 @@ use codex_protocol::models::ResponseItem;
 use codex_protocol::models::ShellToolCallParams;
 use codex_protocol::protocol::InitialHistory;
 
+mod auto_commit;
 pub mod compact;
-use self::compact::build_compacted_history;
-use self::compact::collect_user_messages;
+mod pull_request;
+use self::auto_commit::commit_turn_changes;
+use self::compact::compact_history;
 
 /// The high-level interface to the Codex system.
 /// It operates as a queue pair where you send submissions and receive events.
@@ -158,12 +199,8 @@ pub struct CodexSpawnOk {
 pub(crate) const INITIAL_SUBMIT_ID: &str = "";
 pub(crate) const SUBMISSION_CHANNEL_CAPACITY: usize = 64;
 
-// Model-formatting limits: clients get full streams; oonly content sent to the model is truncated.
-pub(crate) const MODEL_FORMAT_MAX_BYTES: usize = 10 * 1024; // 10 KiB
-pub(crate) const MODEL_FORMAT_MAX_LINES: usize = 256; // lines
-pub(crate) const MODEL_FORMAT_HEAD_LINES: usize = MODEL_FORMAT_MAX_LINES / 2;
-pub(crate) const MODEL_FORMAT_TAIL_LINES: usize = MODEL_FORMAT_MAX_LINES - MODEL_FORMAT_HEAD_LINES; // 128
-pub(crate) const MODEL_FORMAT_HEAD_BYTES: usize = MODEL_FORMAT_MAX_BYTES / 2;
+// Model-formatting limit: clients get full streams; only content sent to the model is truncated.
+pub(crate) const MODEL_FORMAT_MAX_TOKENS: u64 = 2_560;
 
 impl Codex {
     /// Spawn a new [`Codex`] and initialize the session.
@@ -171,6 +208,7 @@ impl Codex {
         config: Config,
         auth_manager: Arc<AuthManager>,
         conversation_history: InitialHistory,
+        fork: Option<SessionFork>,
     ) -> CodexResult<CodexSpawnOk> {
         let (tx_sub, rx_sub) = async_channel::bounded(SUBMISSION_CHANNEL_CAPACITY);
         let (tx_event, rx_event) = async_channel::unbounded();
@@ -199,6 +237,7 @@ impl Codex {
             auth_manager.clone(),
             tx_event.clone(),
             conversation_history,
+            fork,
         )
         .await
         .map_err(|e| {
@@ -257,11 +296,43 @@ impl Codex {
 struct State {
     approved_commands: HashSet<Vec<String>>,
     current_task: Option<AgentTask>,
-    pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
+    pending_approvals: HashMap<String, PendingApproval>,
     pending_input: Vec<ResponseInputItem>,
     history: ConversationHistory,
     token_info: Option<TokenUsageInfo>,
+    /// Tokens `history` took up in the context window as of the last
+    /// response, reported by the provider or estimated. Unknown again once
+    /// compaction replaces the history.
+    context_tokens: Option<u64>,
     latest_rate_limits: Option<RateLimitSnapshot>,
+    /// Scopes whose soft budget has already paused a task.
+    soft_budgets_reached: HashSet<BudgetScope>,
+    /// Workspace checkpoints that can still be restored, oldest first.
+    checkpoints: Vec<CheckpointItem>,
+    /// Set after a checkpoint fails, so later turns do not retry.
+    checkpoints_disabled: bool,
+}
+
+/// Which checkpoint `Op::Undo` or `Op::RestoreCheckpoint` restores.
+enum CheckpointTarget {
+    /// The checkpoint taken before the n-th most recent turn.
+    TurnsBack(usize),
+    Id(String),
+}
+
+/// How a running task must react to the session's spend.
+#[derive(Debug, PartialEq)]
+enum BudgetAction {
+    Continue,
+    Pause,
+    Abort,
+}
+
+/// An approval request that is waiting for the user's decision.
+struct PendingApproval {
+    tx_approve: oneshot::Sender<(ReviewDecision, ApprovalSource)>,
+    call_id: String,
+    subject: ApprovalSubject,
 }
 
 /// Context for an initialized model agent
@@ -283,8 +354,16 @@ pub(crate) struct Session {
     rollout: Mutex<Option<RolloutRecorder>>,
     state: Mutex<State>,
     codex_linux_sandbox_exe: Option<PathBuf>,
+    linux_sandbox_backend: LinuxSandboxBackend,
     user_shell: shell::Shell,
     show_raw_agent_reasoning: bool,
+    /// Scrubs secrets from tool output; `None` when `redact_secrets` is off.
+    secret_redactor: Option<Arc<SecretRedactor>>,
+    /// Appends to `~/.codex/audit.jsonl`; `None` when `audit.enabled` is off.
+    audit: Option<AuditLogger>,
+    hooks: HookRunner,
+    /// Spend recorded by every session today, for the daily budgets.
+    daily_cost: Mutex<DailyCost>,
     next_internal_sub_id: AtomicU64,
 }
 
@@ -307,11 +386,72 @@ pub(crate) struct TurnContext {
 }
 
 impl TurnContext {
+    /// The tokenizer matching the turn's model.
+    pub(crate) fn tokenizer(&self) -> Tokenizer {
+        Tokenizer::for_model_family(&self.client.get_model_family())
+    }
+
+    /// Prompt size that triggers auto-compaction: the configured or known
+    /// auto-compact limit, or the context window when there is none.
+    pub(crate) fn auto_compact_token_limit(&self) -> Option<u64> {
+        self.client
+            .get_auto_compact_token_limit()
+            .and_then(|limit| u64::try_from(limit).ok())
+            .or_else(|| self.client.get_model_context_window())
+    }
+
     fn resolve_path(&self, path: Option<String>) -> PathBuf {
         path.as_ref()
             .map(PathBuf::from)
             .map_or_else(|| self.cwd.clone(), |p| self.cwd.join(p))
     }
+
+    /// Returns the entry of `model_fallbacks` to switch to when the current
+    /// model's provider is unavailable: the one after the current model, or
+    /// the first one while the primary model is in use.
+    fn next_model_fallback(&self) -> Option<ModelFallback> {
+        if self.is_review_mode {
+            return None;
+        }
+        let config = self.client.get_config();
+        let next = config
+            .model_fallbacks
+            .iter()
+            .position(|fallback| {
+                fallback.model == config.model
+                    && fallback.model_provider_id == config.model_provider_id
+            })
+            .map_or(0, |i| i + 1);
+        config.model_fallbacks.get(next).cloned()
+    }
+
+    /// Copy of this context that sends requests to `fallback` instead.
+    fn with_model_fallback(&self, fallback: &ModelFallback) -> Self {
+        let client = self.client.with_fallback(fallback);
+        let config = client.get_config();
+        let tools_config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &config.model_family,
+            include_plan_tool: config.include_plan_tool,
+            include_apply_patch_tool: config.include_apply_patch_tool,
+            include_web_search_request: config.tools_web_search_request,
+            use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
+            include_view_image_tool: config.include_view_image_tool,
+            include_delegate_tool: config.include_delegate_tool,
+            experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        });
+        Self {
+            client,
+            cwd: self.cwd.clone(),
+            base_instructions: self.base_instructions.clone(),
+            user_instructions: self.user_instructions.clone(),
+            approval_policy: self.approval_policy,
+            sandbox_policy: self.sandbox_policy.clone(),
+            shell_environment_policy: self.shell_environment_policy.clone(),
+            tools_config,
+            is_review_mode: self.is_review_mode,
+            final_output_json_schema: self.final_output_json_schema.clone(),
+        }
+    }
 }
 
 /// Configure the model session.
@@ -355,6 +495,7 @@ impl Session {
         auth_manager: Arc<AuthManager>,
         tx_event: Sender<Event>,
         initial_history: InitialHistory,
+        fork: Option<SessionFork>,
     ) -> anyhow::Result<(Arc<Self>, TurnContext)> {
         let ConfigureSession {
             provider,
@@ -376,10 +517,15 @@ impl Session {
         let (conversation_id, rollout_params) = match &initial_history {
             InitialHistory::New | InitialHistory::Forked(_) => {
                 let conversation_id = ConversationId::default();
-                (
-                    conversation_id,
-                    RolloutRecorderParams::new(conversation_id, user_instructions.clone()),
-                )
+                let params = match fork {
+                    Some(fork) => RolloutRecorderParams::forked(
+                        conversation_id,
+                        user_instructions.clone(),
+                        fork,
+                    ),
+                    None => RolloutRecorderParams::new(conversation_id, user_instructions.clone()),
+                };
+                (conversation_id, params)
             }
             InitialHistory::Resumed(resumed_history) => (
                 resumed_history.conversation_id,
@@ -462,6 +608,7 @@ impl Session {
                 include_web_search_request: config.tools_web_search_request,
                 use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                 include_view_image_tool: config.include_view_image_tool,
+                include_delegate_tool: config.include_delegate_tool,
                 experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
             }),
             user_instructions,
@@ -483,10 +630,26 @@ impl Session {
             state: Mutex::new(state),
             rollout: Mutex::new(Some(rollout_recorder)),
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
+            linux_sandbox_backend: config.linux_sandbox_backend,
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            secret_redactor: config.redact_secrets.then(|| {
+                Arc::new(SecretRedactor::new(
+                    &config.shell_environment_policy,
+                    config.redact_high_entropy_strings,
+                ))
+            }),
+            audit: AuditLogger::new(&config, conversation_id),
+            hooks: HookRunner::new(config.hooks.clone(), conversation_id),
+            daily_cost: Mutex::new(DailyCost::default()),
             next_internal_sub_id: AtomicU64::new(0),
         });
+        sess.record_audit(AuditEvent::Policy {
+            cwd: turn_context.cwd.clone(),
+            approval_policy: turn_context.approval_policy,
+            sandbox_policy: turn_context.sandbox_policy.clone(),
+        })
+        .await;
 
         // Dispatch the SessionConfiguredEvent first and then report any errors.
         // If resuming, include converted initial messages in the payload so UIs can render them immediately.
@@ -564,6 +727,19 @@ impl Session {
                 if persist && !rollout_items.is_empty() {
                     self.persist_rollout_items(&rollout_items).await;
                 }
+
+                // A resumed session keeps counting tokens and cost from where
+                // it left off, so that session budgets span resumes.
+                if !persist {
+                    let token_info = rollout_items.iter().rev().find_map(|item| match item {
+                        RolloutItem::EventMsg(EventMsg::TokenCount(ev)) => ev.info.clone(),
+                        _ => None,
+                    });
+                    self.state.lock().await.token_info = token_info;
+                }
+
+                // Turns recorded before the resume or fork can still be undone.
+                self.state.lock().await.checkpoints = checkpoints_from_rollout(&rollout_items);
             }
         }
     }
@@ -585,13 +761,21 @@ impl Session {
         command: Vec<String>,
         cwd: PathBuf,
         reason: Option<String>,
-    ) -> ReviewDecision {
+    ) -> (ReviewDecision, ApprovalSource) {
         // Add the tx_approve callback to the map before sending the request.
         let (tx_approve, rx_approve) = oneshot::channel();
         let event_id = sub_id.clone();
+        let pending = PendingApproval {
+            tx_approve,
+            call_id: call_id.clone(),
+            subject: ApprovalSubject::Exec {
+                command: command.clone(),
+                cwd: cwd.clone(),
+            },
+        };
         let prev_entry = {
             let mut state = self.state.lock().await;
-            state.pending_approvals.insert(sub_id, tx_approve)
+            state.pending_approvals.insert(sub_id, pending)
         };
         if prev_entry.is_some() {
             warn!("Overwriting existing pending approval for sub_id: {event_id}");
@@ -617,13 +801,35 @@ impl Session {
         action: &ApplyPatchAction,
         reason: Option<String>,
         grant_root: Option<PathBuf>,
-    ) -> oneshot::Receiver<ReviewDecision> {
+    ) -> oneshot::Receiver<(ReviewDecision, ApprovalSource)> {
+        let changes = convert_apply_patch_to_protocol(action);
+        self.request_changes_approval(sub_id, call_id, changes, reason, grant_root)
+            .await
+    }
+
+    /// Like [`Session::request_patch_approval`], for changes that were
+    /// already converted to their protocol form.
+    pub(crate) async fn request_changes_approval(
+        &self,
+        sub_id: String,
+        call_id: String,
+        changes: HashMap<PathBuf, FileChange>,
+        reason: Option<String>,
+        grant_root: Option<PathBuf>,
+    ) -> oneshot::Receiver<(ReviewDecision, ApprovalSource)> {
         // Add the tx_approve callback to the map before sending the request.
         let (tx_approve, rx_approve) = oneshot::channel();
         let event_id = sub_id.clone();
+        let pending = PendingApproval {
+            tx_approve,
+            call_id: call_id.clone(),
+            subject: ApprovalSubject::Patch {
+                files: changes.keys().cloned().collect(),
+            },
+        };
         let prev_entry = {
             let mut state = self.state.lock().await;
-            state.pending_approvals.insert(sub_id, tx_approve)
+            state.pending_approvals.insert(sub_id, pending)
         };
         if prev_entry.is_some() {
             warn!("Overwriting existing pending approval for sub_id: {event_id}");
@@ -633,7 +839,7 @@ impl Session {
             id: event_id,
             msg: EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
                 call_id,
-                changes: convert_apply_patch_to_protocol(action),
+                changes,
                 reason,
                 grant_root,
             }),
@@ -642,14 +848,19 @@ impl Session {
         rx_approve
     }
 
-    pub async fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
+    pub async fn notify_approval(
+        &self,
+        sub_id: &str,
+        decision: ReviewDecision,
+        source: ApprovalSource,
+    ) {
         let entry = {
             let mut state = self.state.lock().await;
             state.pending_approvals.remove(sub_id)
         };
         match entry {
-            Some(tx_approve) => {
-                tx_approve.send(decision).ok();
+            Some(PendingApproval { tx_approve, .. }) => {
+                tx_approve.send((decision, source)).ok();
             }
             None => {
                 warn!("No pending approval found for sub_id: {sub_id}");
@@ -657,6 +868,34 @@ impl Session {
         }
     }
 
+    /// Records the user's decision on a pending approval in the audit log.
+    async fn audit_approval(&self, sub_id: &str, decision: ReviewDecision, source: ApprovalSource) {
+        if self.audit.is_none() {
+            return;
+        }
+        let event = {
+            let state = self.state.lock().await;
+            state
+                .pending_approvals
+                .get(sub_id)
+                .map(|pending| AuditEvent::Approval {
+                    call_id: pending.call_id.clone(),
+                    subject: pending.subject.clone(),
+                    decision,
+                    source,
+                })
+        };
+        if let Some(event) = event {
+            self.record_audit(event).await;
+        }
+    }
+
+    pub(crate) async fn record_audit(&self, event: AuditEvent) {
+        if let Some(audit) = &self.audit {
+            audit.record(event).await;
+        }
+    }
+
     pub async fn add_approved_command(&self, cmd: Vec<String>) {
         let mut state = self.state.lock().await;
         state.approved_commands.insert(cmd);
@@ -680,13 +919,20 @@ impl Session {
                 RolloutItem::ResponseItem(response_item) => {
                     history.record_items(std::iter::once(response_item));
                 }
+                RolloutItem::Compacted(CompactedItem {
+                    replacement_history: Some(replacement_history),
+                    ..
+                }) => {
+                    history.replace(replacement_history.clone());
+                }
                 RolloutItem::Compacted(compacted) => {
                     let snapshot = history.contents();
-                    let user_messages = collect_user_messages(&snapshot);
-                    let rebuilt = build_compacted_history(
+                    let rebuilt = compact_history(
                         self.build_initial_context(turn_context),
-                        &user_messages,
+                        &snapshot,
+                        compacted.retained_turns.unwrap_or_default(),
                         &compacted.message,
+                        turn_context.tokenizer(),
                     );
                     history.replace(rebuilt);
                 }
@@ -742,21 +988,132 @@ impl Session {
         sub_id: &str,
         turn_context: &TurnContext,
         token_usage: Option<&TokenUsage>,
+        estimated: bool,
     ) {
+        let config = turn_context.client.get_config();
+        let cost_usd = token_usage.and_then(|usage| {
+            cost::model_pricing(&config).map(|pricing| cost::cost_usd(&pricing, usage))
+        });
         {
             let mut state = self.state.lock().await;
             if let Some(token_usage) = token_usage {
-                let info = TokenUsageInfo::new_or_append(
+                let mut info = TokenUsageInfo::new_or_append(
                     &state.token_info,
                     &Some(token_usage.clone()),
                     turn_context.client.get_model_context_window(),
                 );
+                if let Some(info) = info.as_mut() {
+                    info.estimated |= estimated;
+                    if let Some(cost_usd) = cost_usd {
+                        info.add_cost(cost_usd);
+                    }
+                }
                 state.token_info = info;
+                if !turn_context.is_review_mode {
+                    state.context_tokens = Some(token_usage.tokens_in_context_window());
+                }
+            }
+        }
+        if let Some(cost_usd) = cost_usd {
+            let record = CostRecord {
+                ts: chrono::Utc::now(),
+                conversation_id: self.conversation_id,
+                model: config.model.clone(),
+                cost_usd,
+            };
+            if let Err(e) = cost::record_cost(&config.codex_home, &record).await {
+                warn!("failed to record cost: {e}");
             }
         }
         self.send_token_count_event(sub_id).await;
     }
 
+    /// Adds usage reported by a delegated child conversation to this
+    /// session's totals, so that it counts against the session budget. The
+    /// child records its cost in the daily ledger itself.
+    pub(crate) async fn add_delegate_usage(
+        &self,
+        sub_id: &str,
+        turn_context: &TurnContext,
+        token_usage: &TokenUsage,
+        cost_usd: f64,
+        estimated: bool,
+    ) {
+        {
+            let mut state = self.state.lock().await;
+            let info = state.token_info.get_or_insert_with(|| TokenUsageInfo {
+                total_token_usage: TokenUsage::default(),
+                last_token_usage: TokenUsage::default(),
+                model_context_window: turn_context.client.get_model_context_window(),
+                total_cost_usd: None,
+                estimated: false,
+            });
+            info.total_token_usage.add_assign(token_usage);
+            if cost_usd > 0.0 {
+                info.add_cost(cost_usd);
+            }
+            info.estimated |= estimated;
+        }
+        self.send_token_count_event(sub_id).await;
+    }
+
+    /// Emits `BudgetExceeded` for each budget the spend has newly reached.
+    /// Soft budgets pause a task once per scope; hard budgets abort every
+    /// task until the limit is raised.
+    async fn enforce_budgets(&self, sub_id: &str, config: &Config) -> BudgetAction {
+        let budget = &config.budget;
+        if budget == &Budget::default() {
+            return BudgetAction::Continue;
+        }
+        let daily_usd = if budget.daily_soft_usd.is_some() || budget.daily_hard_usd.is_some() {
+            self.daily_cost
+                .lock()
+                .await
+                .total_usd(&config.codex_home, chrono::Local::now())
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("failed to read cost ledger: {e}");
+                    0.0
+                })
+        } else {
+            0.0
+        };
+
+        let mut events = Vec::new();
+        let action = {
+            let mut state = self.state.lock().await;
+            let session_usd = state
+                .token_info
+                .as_ref()
+                .and_then(|info| info.total_cost_usd)
+                .unwrap_or(0.0);
+            let exceeded = cost::exceeded_budgets(budget, session_usd, daily_usd);
+            if let Some(hard) = exceeded.iter().find(|e| e.kind == BudgetKind::Hard) {
+                events.push(hard.clone());
+                BudgetAction::Abort
+            } else {
+                for soft in exceeded {
+                    if state.soft_budgets_reached.insert(soft.scope) {
+                        events.push(soft);
+                    }
+                }
+                if events.is_empty() {
+                    BudgetAction::Continue
+                } else {
+                    BudgetAction::Pause
+                }
+            }
+        };
+        for event in events {
+            self.send_event(Event {
+                id: sub_id.to_string(),
+                msg: EventMsg::BudgetExceeded(event),
+            })
+            .await;
+        }
+        action
+    }
+
     async fn update_rate_limits(&self, sub_id: &str, new_rate_limits: RateLimitSnapshot) {
         {
             let mut state = self.state.lock().await;
@@ -811,6 +1168,7 @@ impl Session {
             command_for_display,
             cwd,
             apply_patch,
+            tokenizer: _,
         } = exec_command_context;
         let msg = match apply_patch {
             Some(ApplyPatchCommandContext {
@@ -849,6 +1207,7 @@ impl Session {
         call_id: &str,
         output: &ExecToolCallOutput,
         is_apply_patch: bool,
+        tokenizer: Tokenizer,
     ) {
         let ExecToolCallOutput {
             stdout,
@@ -857,11 +1216,12 @@ impl Session {
             duration,
             exit_code,
             timed_out: _,
+            sandbox_violations,
         } = output;
         // Send full stdout/stderr to clients; do not truncate.
         let stdout = stdout.text.clone();
         let stderr = stderr.text.clone();
-        let formatted_output = format_exec_output_str(output);
+        let formatted_output = format_exec_output_str(output, tokenizer);
         let aggregated_output: String = aggregated_output.text.clone();
 
         let msg = if is_apply_patch {
@@ -880,6 +1240,7 @@ impl Session {
                 exit_code: *exit_code,
                 duration: *duration,
                 formatted_output,
+                sandbox_violations: sandbox_violations.clone(),
             })
         };
 
@@ -914,13 +1275,29 @@ impl Session {
         exec_args: ExecInvokeArgs<'a>,
     ) -> crate::error::Result<ExecToolCallOutput> {
         let is_apply_patch = begin_ctx.apply_patch.is_some();
+        let tokenizer = begin_ctx.tokenizer;
         let sub_id = begin_ctx.sub_id.clone();
         let call_id = begin_ctx.call_id.clone();
+        let audit_event = self.audit.as_ref().map(|_| match &begin_ctx.apply_patch {
+            Some(ApplyPatchCommandContext { changes, .. }) => AuditEvent::Patch {
+                call_id: call_id.clone(),
+                files: changes.keys().cloned().collect(),
+                sandbox: exec_args.sandbox_type,
+                success: false,
+            },
+            None => AuditEvent::Exec {
+                call_id: call_id.clone(),
+                command: exec_args.params.command.clone(),
+                cwd: exec_args.params.cwd.clone(),
+                sandbox: exec_args.sandbox_type,
+                exit_code: -1,
+            },
+        });
 
         self.on_exec_command_begin(turn_diff_tracker, begin_ctx.clone())
             .await;
 
-        let result = process_exec_tool_call(
+        let mut result = process_exec_tool_call(
             exec_args.params,
             exec_args.sandbox_type,
             exec_args.sandbox_policy,
@@ -929,6 +1306,15 @@ impl Session {
             exec_args.stdout_stream,
         )
         .await;
+        if let Some(redactor) = &self.secret_redactor {
+            match &mut result {
+                Ok(output) => redactor.redact_exec_output(output),
+                Err(CodexErr::Sandbox(
+                    SandboxErr::Timeout { output } | SandboxErr::Denied { output },
+                )) => redactor.redact_exec_output(output),
+                Err(_) => {}
+            }
+        }
 
         let output_stderr;
         let borrowed: &ExecToolCallOutput = match &result {
@@ -942,6 +1328,12 @@ impl Session {
                     aggregated_output: StreamOutput::new(get_error_message_ui(e)),
                     duration: Duration::default(),
                     timed_out: false,
+                    sandbox_violations: match e {
+                        CodexErr::Sandbox(SandboxErr::Denied { output }) => {
+                            output.sandbox_violations.clone()
+                        }
+                        _ => Vec::new(),
+                    },
                 };
                 &output_stderr
             }
@@ -952,12 +1344,151 @@ impl Session {
             &call_id,
             borrowed,
             is_apply_patch,
+            tokenizer,
         )
         .await;
 
+        if let Some(mut event) = audit_event {
+            match &mut event {
+                AuditEvent::Patch { success, .. } => *success = borrowed.exit_code == 0,
+                AuditEvent::Exec { exit_code, .. } => *exit_code = borrowed.exit_code,
+                _ => {}
+            }
+            self.record_audit(event).await;
+        }
+
         result
     }
 
+    /// Returns `text` with secrets replaced by placeholders, or unchanged if
+    /// redaction is disabled.
+    pub(crate) fn redact_secrets<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match &self.secret_redactor {
+            Some(redactor) => redactor.redact(text),
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    pub(crate) fn secret_redactor(&self) -> Option<&SecretRedactor> {
+        self.secret_redactor.as_deref()
+    }
+
+    /// Snapshot the workspace before the turn `sub_id` changes it, or once
+    /// it completed when `after_turn` is set, if `checkpoints.enabled` is
+    /// set. Only the former can be undone to; the latter records the final
+    /// state of the session for comparing branches. The first failure
+    /// disables checkpoints for the rest of the session.
+    async fn capture_checkpoint(&self, sub_id: &str, turn_context: &TurnContext, after_turn: bool) {
+        let config = turn_context.client.get_config();
+        if !config.checkpoints.enabled || self.state.lock().await.checkpoints_disabled {
+            return;
+        }
+
+        let codex_home = config.codex_home.clone();
+        let cwd = turn_context.cwd.clone();
+        let max_snapshot_bytes = config.checkpoints.max_snapshot_bytes;
+        let turn_id = sub_id.to_string();
+        let result = tokio::task::spawn_blocking(move || {
+            create_checkpoint(&codex_home, &cwd, max_snapshot_bytes, turn_id, after_turn)
+        })
+        .await
+        .unwrap_or_else(|e| Err(CheckpointError::Io(std::io::Error::other(e))));
+
+        match result {
+            Ok(checkpoint) => {
+                if !after_turn {
+                    self.state.lock().await.checkpoints.push(checkpoint.clone());
+                }
+                self.persist_rollout_items(&[RolloutItem::Checkpoint(checkpoint)])
+                    .await;
+            }
+            Err(e) => {
+                warn!("failed to create checkpoint: {e}");
+                self.state.lock().await.checkpoints_disabled = true;
+                self.notify_background_event(
+                    sub_id,
+                    format!("Checkpoints disabled for this session: {e}"),
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Restore the workspace to a checkpoint and forget the checkpoints of
+    /// the turns it undoes. Replies with `CheckpointRestored` or `Error`.
+    async fn restore_to_checkpoint(
+        &self,
+        sub_id: &str,
+        turn_context: &TurnContext,
+        target: CheckpointTarget,
+    ) {
+        let selected = {
+            let state = self.state.lock().await;
+            let available = state.checkpoints.len();
+            let index = if state.current_task.is_some() {
+                Err("Cannot restore a checkpoint while a task is running.".to_string())
+            } else {
+                match target {
+                    CheckpointTarget::TurnsBack(0) => {
+                        Err("Nothing to undo: zero turns requested.".to_string())
+                    }
+                    CheckpointTarget::TurnsBack(turns) if turns > available => Err(format!(
+                        "Cannot undo {turns} turn(s): only {available} checkpoint(s) available."
+                    )),
+                    CheckpointTarget::TurnsBack(turns) => Ok(available - turns),
+                    CheckpointTarget::Id(id) => state
+                        .checkpoints
+                        .iter()
+                        .rposition(|checkpoint| checkpoint.id == id)
+                        .ok_or_else(|| format!("Unknown checkpoint {id}.")),
+                }
+            };
+            index.map(|index| (index, state.checkpoints[index].clone()))
+        };
+        let (index, checkpoint) = match selected {
+            Ok(selected) => selected,
+            Err(message) => {
+                self.send_event(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::Error(ErrorEvent { message }),
+                })
+                .await;
+                return;
+            }
+        };
+
+        let codex_home = turn_context.client.get_config().codex_home.clone();
+        let cwd = turn_context.cwd.clone();
+        let to_restore = checkpoint.clone();
+        let result =
+            tokio::task::spawn_blocking(move || restore_checkpoint(&codex_home, &cwd, &to_restore))
+                .await
+                .unwrap_or_else(|e| Err(CheckpointError::Io(std::io::Error::other(e))));
+
+        let msg = match result {
+            Ok(()) => {
+                let turns_undone = {
+                    let mut state = self.state.lock().await;
+                    let turns_undone = state.checkpoints.len() - index;
+                    state.checkpoints.truncate(index);
+                    turns_undone
+                };
+                EventMsg::CheckpointRestored(CheckpointRestoredEvent {
+                    checkpoint_id: checkpoint.id,
+                    turns_undone,
+                })
+            }
+            Err(e) => EventMsg::Error(ErrorEvent {
+                message: format!("Failed to restore checkpoint {}: {e}", checkpoint.id),
+            }),
+        };
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg,
+        })
+        .await;
+    }
+
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
@@ -1002,6 +1533,30 @@ impl Session {
         }
     }
 
+    /// Like [`Session::inject_input`], preceded by `context`, a message that
+    /// did not come from the user. Without a running task, `context` is
+    /// recorded in the history before `input` is handed back to start one.
+    async fn inject_input_with_context(
+        &self,
+        context: ResponseInputItem,
+        input: Vec<InputItem>,
+    ) -> Result<(), Vec<InputItem>> {
+        {
+            let mut state = self.state.lock().await;
+            if state.current_task.is_some() {
+                state.pending_input.push(context);
+                state.pending_input.push(input.into());
+                return Ok(());
+            }
+        }
+        self.record_conversation_items(&[context.into()]).await;
+        Err(input)
+    }
+
+    async fn context_tokens(&self) -> Option<u64> {
+        self.state.lock().await.context_tokens
+    }
+
     pub async fn get_pending_input(&self) -> Vec<ResponseInputItem> {
         let mut state = self.state.lock().await;
         if state.pending_input.is_empty() {
@@ -1062,6 +1617,8 @@ pub(crate) struct ExecCommandContext {
     pub(crate) command_for_display: Vec<String>,
     pub(crate) cwd: PathBuf,
     pub(crate) apply_patch: Option<ApplyPatchCommandContext>,
+    /// Counts the tokens of the output formatted for the model.
+    pub(crate) tokenizer: Tokenizer,
 }
 
 #[derive(Clone, Debug)]
@@ -1238,6 +1795,7 @@ async fn submission_loop(
                     include_web_search_request: config.tools_web_search_request,
                     use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                     include_view_image_tool: config.include_view_image_tool,
+                    include_delegate_tool: config.include_delegate_tool,
                     experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
                 });
 
@@ -1257,6 +1815,15 @@ async fn submission_loop(
                 // Install the new persistent context for subsequent tasks/turns.
                 turn_context = Arc::new(new_turn_context);
 
+                if approval_policy.is_some() || sandbox_policy.is_some() {
+                    sess.record_audit(AuditEvent::Policy {
+                        cwd: new_cwd,
+                        approval_policy: new_approval_policy,
+                        sandbox_policy: new_sandbox_policy,
+                    })
+                    .await;
+                }
+
                 // Optionally persist changes to model / effort
                 if cwd.is_some() || approval_policy.is_some() || sandbox_policy.is_some() {
                     sess.record_conversation_items(&[ResponseItem::from(EnvironmentContext::new(
@@ -1327,6 +1894,7 @@ async fn submission_loop(
                             use_streamable_shell_tool: config
                                 .use_experimental_streamable_shell_tool,
                             include_view_image_tool: config.include_view_image_tool,
+                            include_delegate_tool: config.include_delegate_tool,
                             experimental_unified_exec_tool: config
                                 .use_experimental_unified_exec_tool,
                         }),
@@ -1347,6 +1915,16 @@ async fn submission_loop(
                         sess.record_conversation_items(&[ResponseItem::from(new_env_context)])
                             .await;
                     }
+                    if fresh_turn_context.approval_policy != turn_context.approval_policy
+                        || fresh_turn_context.sandbox_policy != turn_context.sandbox_policy
+                    {
+                        sess.record_audit(AuditEvent::Policy {
+                            cwd: fresh_turn_context.cwd.clone(),
+                            approval_policy: fresh_turn_context.approval_policy,
+                            sandbox_policy: fresh_turn_context.sandbox_policy.clone(),
+                        })
+                        .await;
+                    }
 
                     // Install the new persistent context for subsequent tasks/turns.
                     turn_context = Arc::new(fresh_turn_context);
@@ -1357,18 +1935,32 @@ async fn submission_loop(
                     sess.set_task(task).await;
                 }
             }
-            Op::ExecApproval { id, decision } => match decision {
-                ReviewDecision::Abort => {
-                    sess.interrupt_task().await;
+            Op::ExecApproval {
+                id,
+                decision,
+                source,
+            } => {
+                sess.audit_approval(&id, decision, source).await;
+                match decision {
+                    ReviewDecision::Abort => {
+                        sess.interrupt_task().await;
+                    }
+                    other => sess.notify_approval(&id, other, source).await,
                 }
-                other => sess.notify_approval(&id, other).await,
-            },
-            Op::PatchApproval { id, decision } => match decision {
-                ReviewDecision::Abort => {
-                    sess.interrupt_task().await;
+            }
+            Op::PatchApproval {
+                id,
+                decision,
+                source,
+            } => {
+                sess.audit_approval(&id, decision, source).await;
+                match decision {
+                    ReviewDecision::Abort => {
+                        sess.interrupt_task().await;
+                    }
+                    other => sess.notify_approval(&id, other, source).await,
                 }
-                other => sess.notify_approval(&id, other).await,
-            },
+            }
             Op::AddToHistory { text } => {
                 let id = sess.conversation_id;
                 let config = config.clone();
@@ -1527,6 +2119,35 @@ async fn submission_loop(
                 )
                 .await;
             }
+            Op::Undo { turns } => {
+                sess.restore_to_checkpoint(
+                    &sub.id,
+                    &turn_context,
+                    CheckpointTarget::TurnsBack(turns),
+                )
+                .await;
+            }
+            Op::RestoreCheckpoint { id } => {
+                sess.restore_to_checkpoint(&sub.id, &turn_context, CheckpointTarget::Id(id))
+                    .await;
+            }
+            Op::OpenPullRequest { base, draft } => {
+                pull_request::spawn_open_pull_request(
+                    sess.clone(),
+                    Arc::clone(&turn_context),
+                    sub.id,
+                    base,
+                    draft,
+                );
+            }
+            Op::AddressReviewComments { number } => {
+                pull_request::spawn_address_review_comments(
+                    sess.clone(),
+                    Arc::clone(&turn_context),
+                    sub.id,
+                    number,
+                );
+            }
             _ => {
                 // Ignore unknown ops; enum is non_exhaustive to allow extensions.
             }
@@ -1553,11 +2174,44 @@ async fn spawn_review_thread(
         include_web_search_request: false,
         use_streamable_shell_tool: false,
         include_view_image_tool: false,
+        include_delegate_tool: false,
         experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
     });
 
     let base_instructions = REVIEW_PROMPT.to_string();
-    let review_prompt = review_request.prompt.clone();
+    let review_prompt = match &review_request.target {
+        Some(target) => {
+            let cwd = &parent_turn_context.cwd;
+            let message = match review_target_diff(cwd, target).await {
+                Ok(diff) if !diff.trim().is_empty() => {
+                    let root = get_git_repo_root(cwd).unwrap_or_else(|| cwd.clone());
+                    Ok(review_prompt_with_diff(
+                        &review_request.prompt,
+                        &diff,
+                        &root,
+                        Tokenizer::for_model_family(&review_model_family),
+                    ))
+                }
+                Ok(_) => Err(format!(
+                    "There are no changes to review in {}.",
+                    review_request.user_facing_hint
+                )),
+                Err(e) => Err(format!("Failed to compute the changes to review: {e}")),
+            };
+            match message {
+                Ok(prompt) => prompt,
+                Err(message) => {
+                    sess.send_event(Event {
+                        id: sub_id,
+                        msg: EventMsg::Error(ErrorEvent { message }),
+                    })
+                    .await;
+                    return;
+                }
+            }
+        }
+        None => review_request.prompt.clone(),
+    };
     let provider = parent_turn_context.client.get_provider();
     let auth_manager = parent_turn_context.client.get_auth_manager();
     let model_family = review_model_family.clone();
@@ -1633,7 +2287,7 @@ async fn spawn_review_thread(
 /// user_instructions. Emits ExitedReviewMode upon final review message.
 async fn run_task(
     sess: Arc<Session>,
-    turn_context: Arc<TurnContext>,
+    mut turn_context: Arc<TurnContext>,
     sub_id: String,
     input: Vec<InputItem>,
 ) {
@@ -1648,11 +2302,39 @@ async fn run_task(
     };
     sess.send_event(event).await;
 
+    let is_review_mode = turn_context.is_review_mode;
+    let input = if is_review_mode {
+        input
+    } else {
+        match run_user_prompt_hooks(&sess, &turn_context, &sub_id, input).await {
+            Some(input) => input,
+            None => {
+                sess.remove_task(&sub_id).await;
+                let event = Event {
+                    id: sub_id,
+                    msg: EventMsg::TaskComplete(TaskCompleteEvent {
+                        last_agent_message: None,
+                    }),
+                };
+                sess.send_event(event).await;
+                return;
+            }
+        }
+    };
+
+    if !is_review_mode {
+        sess.capture_checkpoint(&sub_id, &turn_context, false).await;
+    }
+
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
+    // Tokens added to the history since the last response, which the next
+    // request carries on top of the context that response reported.
+    let mut unsent_tokens = turn_context
+        .tokenizer()
+        .count_item(&initial_input_for_turn.clone().into());
     // For review threads, keep an isolated in-memory history so the
     // model sees a fresh conversation without the parent session's history.
     // For normal turns, continue recording to the session history as before.
-    let is_review_mode = turn_context.is_review_mode;
     let mut review_thread_history: Vec<ResponseItem> = Vec::new();
     if is_review_mode {
         // Seed review threads with environment context so the model knows the working directory.
@@ -1668,8 +2350,35 @@ async fn run_task(
     // many turns, from the perspective of the user, it is a single turn.
     let mut turn_diff_tracker = TurnDiffTracker::new();
     let mut auto_compact_recently_attempted = false;
+    let mut is_first_request = true;
+    let mut output_schema_attempts: u32 = 0;
 
     loop {
+        match sess
+            .enforce_budgets(&sub_id, &turn_context.client.get_config())
+            .await
+        {
+            BudgetAction::Abort => {
+                sess.remove_task(&sub_id).await;
+                if turn_context.is_review_mode {
+                    exit_review_mode(sess.clone(), sub_id.clone(), None).await;
+                }
+                let event = Event {
+                    id: sub_id,
+                    msg: EventMsg::TurnAborted(TurnAbortedEvent {
+                        reason: TurnAbortReason::BudgetExceeded,
+                    }),
+                };
+                sess.send_event(event).await;
+                return;
+            }
+            // Pausing before the first request would ignore the message the
+            // user just sent, so a soft budget only warns then.
+            BudgetAction::Pause if !is_first_request => break,
+            BudgetAction::Pause | BudgetAction::Continue => {}
+        }
+        is_first_request = false;
+
         // Note that pending_input would be something like a message the user
         // submitted through the UI while the model was running. Though the UI
         // may support this, the model might not.
@@ -1679,6 +2388,11 @@ async fn run_task(
             .into_iter()
             .map(ResponseItem::from)
             .collect::<Vec<ResponseItem>>();
+        let tokenizer = turn_context.tokenizer();
+        unsent_tokens += pending_input
+            .iter()
+            .map(|item| tokenizer.count_item(item))
+            .sum::<u64>();
 
         // Construct the input that we will send to the model.
         //
@@ -1700,6 +2414,20 @@ async fn run_task(
             sess.turn_input_with_history(pending_input).await
         };
 
+        // Providers only report usage after the fact, so add what was
+        // recorded since the last response to the context it reported, and
+        // compact before a request that would exceed the auto-compact limit.
+        if !is_review_mode
+            && !auto_compact_recently_attempted
+            && let Some(limit) = turn_context.auto_compact_token_limit()
+            && let Some(context_tokens) = sess.context_tokens().await
+            && context_tokens + unsent_tokens >= limit
+        {
+            auto_compact_recently_attempted = true;
+            compact::run_inline_auto_compact_task(sess.clone(), turn_context.clone()).await;
+            continue;
+        }
+
         let turn_input_messages: Vec<String> = turn_input
             .iter()
             .filter_map(|item| match item {
@@ -1715,7 +2443,7 @@ async fn run_task(
             .collect();
         match run_turn(
             &sess,
-            turn_context.as_ref(),
+            &mut turn_context,
             &mut turn_diff_tracker,
             sub_id.clone(),
             turn_input,
@@ -1829,6 +2557,11 @@ async fn run_task(
                         responses.push(response);
                     }
                 }
+                let tokenizer = turn_context.tokenizer();
+                unsent_tokens = responses
+                    .iter()
+                    .map(|response| tokenizer.count_item(&response.clone().into()))
+                    .sum();
 
                 // Only attempt to take the lock if there is something to record.
                 if !items_to_record_in_conversation_history.is_empty() {
@@ -1869,10 +2602,52 @@ async fn run_task(
                     last_agent_message = get_last_assistant_message_from_turn(
                         &items_to_record_in_conversation_history,
                     );
-                    sess.notifier()
-                        .notify(&UserNotification::AgentTurnComplete {
-                            turn_id: sub_id.clone(),
-                            input_messages: turn_input_messages,
+                    if let Some(schema) = &turn_context.final_output_json_schema
+                        && !turn_context.client.get_provider().enforces_output_schema()
+                    {
+                        output_schema_attempts += 1;
+                        let errors = validate_final_message(schema, last_agent_message.as_deref());
+                        if !errors.is_empty() {
+                            let max_retries =
+                                turn_context.client.get_config().output_schema_max_retries;
+                            if output_schema_attempts <= max_retries {
+                                let retry = ResponseItem::Message {
+                                    id: None,
+                                    role: "user".to_string(),
+                                    content: vec![ContentItem::InputText {
+                                        text: retry_prompt(&errors),
+                                    }],
+                                };
+                                unsent_tokens += turn_context.tokenizer().count_item(&retry);
+                                if is_review_mode {
+                                    review_thread_history.push(retry);
+                                } else {
+                                    sess.record_conversation_items(&[retry]).await;
+                                }
+                                continue;
+                            }
+                            sess.send_event(Event {
+                                id: sub_id.clone(),
+                                msg: EventMsg::OutputSchemaMismatch(OutputSchemaMismatchEvent {
+                                    attempts: output_schema_attempts,
+                                    errors,
+                                }),
+                            })
+                            .await;
+                        }
+                    }
+                    sess.hooks.spawn(
+                        sub_id.clone(),
+                        turn_context.cwd.clone(),
+                        HookPayload::TurnComplete {
+                            input_messages: turn_input_messages.clone(),
+                            last_assistant_message: last_agent_message.clone(),
+                        },
+                    );
+                    sess.notifier()
+                        .notify(&UserNotification::AgentTurnComplete {
+                            turn_id: sub_id.clone(),
+                            input_messages: turn_input_messages,
                             last_assistant_message: last_agent_message.clone(),
                         });
                     break;
@@ -1902,12 +2677,18 @@ async fn run_task(
     //
     // Emits an ExitedReviewMode event with the parsed review output.
     if turn_context.is_review_mode {
+        let root = get_git_repo_root(&turn_context.cwd).unwrap_or_else(|| turn_context.cwd.clone());
         exit_review_mode(
             sess.clone(),
             sub_id.clone(),
-            last_agent_message.as_deref().map(parse_review_output_event),
+            last_agent_message
+                .as_deref()
+                .map(|text| parse_review_output_event(text, &root)),
         )
         .await;
+    } else {
+        commit_turn_changes(&sess, &turn_context, &sub_id, &mut turn_diff_tracker).await;
+        sess.capture_checkpoint(&sub_id, &turn_context, true).await;
     }
 
     sess.remove_task(&sub_id).await;
@@ -1918,14 +2699,74 @@ async fn run_task(
     sess.send_event(event).await;
 }
 
+/// Runs the `user_prompt_submit` hooks on the text of `input` and appends
+/// their messages to it. Returns `None` when a hook vetoes the prompt.
+async fn run_user_prompt_hooks(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    mut input: Vec<InputItem>,
+) -> Option<Vec<InputItem>> {
+    let prompt = input
+        .iter()
+        .filter_map(|item| match item {
+            InputItem::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let payload = HookPayload::UserPromptSubmit {
+        prompt: prompt.clone(),
+    };
+    if !sess.hooks.has_hooks_for(&payload) {
+        return Some(input);
+    }
+
+    let outcome = sess.hooks.run(sub_id, &turn_context.cwd, payload).await;
+    if let Some(reason) = outcome.veto {
+        sess.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::Error(ErrorEvent {
+                message: format!("prompt blocked by hook: {reason}"),
+            }),
+        })
+        .await;
+        return None;
+    }
+    if let HookPayload::UserPromptSubmit { prompt: rewritten } = outcome.payload
+        && rewritten != prompt
+    {
+        input.retain(|item| !matches!(item, InputItem::Text { .. }));
+        input.insert(0, InputItem::Text { text: rewritten });
+    }
+    input.extend(
+        outcome
+            .messages
+            .into_iter()
+            .map(|text| InputItem::Text { text }),
+    );
+    Some(input)
+}
+
 /// Parse the review output; when not valid JSON, build a structured
 /// fallback that carries the plain text as the overall explanation.
+/// Finding paths the reviewer left relative, as they appear in the diff,
+/// are resolved against the repository `root`.
 ///
 /// Returns: a ReviewOutputEvent parsed from JSON or a fallback populated from text.
-fn parse_review_output_event(text: &str) -> ReviewOutputEvent {
+fn parse_review_output_event(text: &str, root: &Path) -> ReviewOutputEvent {
+    let resolve_paths = |mut ev: ReviewOutputEvent| {
+        for finding in &mut ev.findings {
+            let path = &mut finding.code_location.absolute_file_path;
+            if path.is_relative() {
+                *path = root.join(&*path);
+            }
+        }
+        ev
+    };
     // Try direct parse first
     if let Ok(ev) = serde_json::from_str::<ReviewOutputEvent>(text) {
-        return ev;
+        return resolve_paths(ev);
     }
     // If wrapped in markdown fences or extra prose, attempt to extract the first JSON object
     if let (Some(start), Some(end)) = (text.find('{'), text.rfind('}'))
@@ -1933,7 +2774,7 @@ fn parse_review_output_event(text: &str) -> ReviewOutputEvent {
         && let Some(slice) = text.get(start..=end)
         && let Ok(ev) = serde_json::from_str::<ReviewOutputEvent>(slice)
     {
-        return ev;
+        return resolve_paths(ev);
     }
     // Not JSON – return a structured ReviewOutputEvent that carries
     // the plain text as the overall explanation.
@@ -1943,9 +2784,37 @@ fn parse_review_output_event(text: &str) -> ReviewOutputEvent {
     }
 }
 
+/// Usage of a response whose provider did not report any, estimated locally.
+fn estimate_token_usage(
+    turn_context: &TurnContext,
+    prompt: &Prompt,
+    output: &[ProcessedResponseItem],
+) -> TokenUsage {
+    let model_family = turn_context.client.get_model_family();
+    let tokenizer = Tokenizer::for_model_family(&model_family);
+    let input_tokens = tokenizer.count_prompt(
+        &prompt.get_full_instructions(&model_family),
+        &prompt.get_formatted_input(),
+    );
+    let output_tokens = output
+        .iter()
+        .map(|processed| tokenizer.count_item(&processed.item))
+        .sum();
+    TokenUsage {
+        input_tokens,
+        cached_input_tokens: 0,
+        output_tokens,
+        reasoning_output_tokens: 0,
+        total_tokens: input_tokens + output_tokens,
+    }
+}
+
+/// Runs a turn, retrying transient stream failures. When the provider stays
+/// unavailable after all retries, `turn_context` is replaced with one for the
+/// next configured model fallback, which the rest of the task keeps using.
 async fn run_turn(
     sess: &Session,
-    turn_context: &TurnContext,
+    turn_context: &mut Arc<TurnContext>,
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: String,
     input: Vec<ResponseItem>,
@@ -1955,7 +2824,7 @@ async fn run_turn(
         Some(sess.mcp_connection_manager.list_all_tools()),
     );
 
-    let prompt = Prompt {
+    let mut prompt = Prompt {
         input,
         tools,
         base_instructions_override: turn_context.base_instructions.clone(),
@@ -1976,6 +2845,7 @@ async fn run_turn(
                 return Err(CodexErr::UsageLimitReached(e));
             }
             Err(CodexErr::UsageNotIncluded) => return Err(CodexErr::UsageNotIncluded),
+            Err(e @ CodexErr::ResponseCacheMiss(_)) => return Err(e),
             Err(e) => {
                 // Use the configured provider-specific stream retry budget.
                 let max_retries = turn_context.client.get_provider().stream_max_retries();
@@ -2001,6 +2871,30 @@ async fn run_turn(
                     .await;
 
                     tokio::time::sleep(delay).await;
+                } else if e.is_provider_unavailable()
+                    && let Some(fallback) = turn_context.next_model_fallback()
+                {
+                    warn!(
+                        "provider unavailable ({e}); falling back to {} via {}",
+                        fallback.model, fallback.model_provider_id
+                    );
+                    sess.notify_background_event(
+                        &sub_id,
+                        format!(
+                            "{} is unavailable ({e}); switching to {} via {}",
+                            turn_context.client.get_model(),
+                            fallback.model,
+                            fallback.model_provider_id
+                        ),
+                    )
+                    .await;
+
+                    *turn_context = Arc::new(turn_context.with_model_fallback(&fallback));
+                    prompt.tools = get_openai_tools(
+                        &turn_context.tools_config,
+                        Some(sess.mcp_connection_manager.list_all_tools()),
+                    );
+                    retries = 0;
                 } else {
                     return Err(e);
                 }
@@ -2099,6 +2993,8 @@ async fn try_run_turn(
     let mut stream = turn_context.client.clone().stream(&prompt).await?;
 
     let mut output = Vec::new();
+    // Indices into `output` of parallel-safe tool calls that have not run yet.
+    let mut pending_parallel_calls = Vec::new();
 
     loop {
         // Poll the next item from the model stream. We must inspect *both* Ok and Err
@@ -2126,6 +3022,24 @@ async fn try_run_turn(
         match event {
             ResponseEvent::Created => {}
             ResponseEvent::OutputItemDone(item) => {
+                if is_parallel_safe_call(sess, &item) {
+                    pending_parallel_calls.push(output.len());
+                    output.push(ProcessedResponseItem {
+                        item,
+                        response: None,
+                    });
+                    continue;
+                }
+                if is_tool_call(&item) {
+                    run_parallel_calls(
+                        sess,
+                        turn_context,
+                        sub_id,
+                        &mut output,
+                        &mut pending_parallel_calls,
+                    )
+                    .await?;
+                }
                 let response = handle_response_item(
                     sess,
                     turn_context,
@@ -2154,7 +3068,19 @@ async fn try_run_turn(
                 response_id: _,
                 token_usage,
             } => {
-                sess.update_token_usage_info(sub_id, turn_context, token_usage.as_ref())
+                run_parallel_calls(
+                    sess,
+                    turn_context,
+                    sub_id,
+                    &mut output,
+                    &mut pending_parallel_calls,
+                )
+                .await?;
+                // Chat Completions providers often omit usage.
+                let estimated = token_usage.is_none();
+                let token_usage = token_usage
+                    .unwrap_or_else(|| estimate_token_usage(turn_context, &prompt, &output));
+                sess.update_token_usage_info(sub_id, turn_context, Some(&token_usage), estimated)
                     .await;
 
                 let unified_diff = turn_diff_tracker.get_unified_diff();
@@ -2169,7 +3095,7 @@ async fn try_run_turn(
 
                 let result = TurnRunResult {
                     processed_items: output,
-                    total_token_usage: token_usage.clone(),
+                    total_token_usage: Some(token_usage),
                 };
 
                 return Ok(result);
@@ -2216,6 +3142,74 @@ async fn try_run_turn(
     }
 }
 
+fn is_tool_call(item: &ResponseItem) -> bool {
+    matches!(
+        item,
+        ResponseItem::FunctionCall { .. }
+            | ResponseItem::LocalShellCall { .. }
+            | ResponseItem::CustomToolCall { .. }
+    )
+}
+
+/// Whether `item` is a tool call without side effects, which may run
+/// concurrently with its neighbours: a shell command known to be safe or an
+/// MCP tool annotated as read-only.
+fn is_parallel_safe_call(sess: &Session, item: &ResponseItem) -> bool {
+    // Hooks see, and may rewrite or veto, one call at a time.
+    if sess.hooks.has_tool_call_hooks() {
+        return false;
+    }
+    let is_known_safe =
+        |command: &[String]| analyze_command_safety(command) == CommandSafetyVerdict::KnownSafe;
+    match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => match name.as_str() {
+            "container.exec" | "shell" => serde_json::from_str::<ShellToolCallParams>(arguments)
+                .is_ok_and(|params| {
+                    params.with_escalated_permissions != Some(true)
+                        && is_known_safe(&params.command)
+                }),
+            _ => sess.mcp_connection_manager.is_read_only_tool(name),
+        },
+        ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(action),
+            ..
+        } => is_known_safe(&action.command),
+        _ => false,
+    }
+}
+
+/// Runs the `pending` parallel-safe calls concurrently and stores their
+/// responses in `output`, so the history keeps the order of the calls.
+async fn run_parallel_calls(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    output: &mut [ProcessedResponseItem],
+    pending: &mut Vec<usize>,
+) -> CodexResult<()> {
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let calls = pending.drain(..).map(|idx| {
+        let item = output[idx].item.clone();
+        async move {
+            // Safe calls never modify files, so they have nothing to report
+            // to the turn's diff tracker.
+            let mut turn_diff_tracker = TurnDiffTracker::new();
+            let response =
+                handle_response_item(sess, turn_context, &mut turn_diff_tracker, sub_id, item)
+                    .await;
+            (idx, response)
+        }
+    });
+    for (idx, response) in future::join_all(calls).await {
+        output[idx].response = response?;
+    }
+    Ok(())
+}
+
 async fn handle_response_item(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2346,6 +3340,7 @@ async fn handle_unified_exec_tool_call(
     session_id: Option<String>,
     arguments: Vec<String>,
     timeout_ms: Option<u64>,
+    tokenizer: Tokenizer,
 ) -> ResponseInputItem {
     let parsed_session_id = if let Some(session_id) = session_id {
         match session_id.parse::<i32>() {
@@ -2368,6 +3363,7 @@ async fn handle_unified_exec_tool_call(
         session_id: parsed_session_id,
         input_chunks: &arguments,
         timeout_ms,
+        tokenizer,
     };
 
     let result = sess.unified_exec_manager.handle_request(request).await;
@@ -2382,7 +3378,7 @@ async fn handle_unified_exec_tool_call(
 
             match serde_json::to_string(&SerializedUnifiedExecResult {
                 session_id: value.session_id.map(|id| id.to_string()),
-                output: &value.output,
+                output: &sess.redact_secrets(&value.output),
             }) {
                 Ok(serialized) => FunctionCallOutputPayload {
                     content: serialized,
@@ -2456,14 +3452,33 @@ async fn handle_function_call(
                 }
             };
 
-            handle_unified_exec_tool_call(
+            // Hooks see the commands that start a session, not the input
+            // later written to it.
+            let (input, pre) = if args.session_id.is_some() {
+                (args.input, None)
+            } else {
+                match run_pre_exec_hooks(sess, &sub_id, &turn_context.cwd, &call_id, args.input)
+                    .await
+                {
+                    Ok(hooked) => hooked,
+                    Err(output) => return output,
+                }
+            };
+            let mut result = handle_unified_exec_tool_call(
                 sess,
-                call_id,
+                call_id.clone(),
                 args.session_id,
-                args.input,
+                input.clone(),
                 args.timeout_ms,
+                turn_context.tokenizer(),
             )
-            .await
+            .await;
+            // The unified exec manager does not expose exit codes.
+            record_streamable_exec_audit(sess, turn_context, &call_id, input, None).await;
+            if let ResponseInputItem::FunctionCallOutput { output, .. } = &mut result {
+                run_post_exec_hooks(sess, &sub_id, &turn_context.cwd, pre, output).await;
+            }
+            result
         }
         "view_image" => {
             #[derive(serde::Deserialize)]
@@ -2530,9 +3545,10 @@ async fn handle_function_call(
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        DELEGATE_TOOL_NAME => handle_delegate(sess, turn_context, arguments, sub_id, call_id).await,
         EXEC_COMMAND_TOOL_NAME => {
             // TODO(mbolin): Sandbox check.
-            let exec_params = match serde_json::from_str::<ExecCommandParams>(&arguments) {
+            let mut exec_params = match serde_json::from_str::<ExecCommandParams>(&arguments) {
                 Ok(params) => params,
                 Err(e) => {
                     return ResponseInputItem::FunctionCallOutput {
@@ -2544,11 +3560,42 @@ async fn handle_function_call(
                     };
                 }
             };
+            let shell_mode = if exec_params.login { "-lc" } else { "-c" };
+            let command = vec![
+                exec_params.shell.clone(),
+                shell_mode.to_string(),
+                exec_params.cmd.clone(),
+            ];
+            let (command, pre) =
+                match run_pre_exec_hooks(sess, &sub_id, &turn_context.cwd, &call_id, command).await
+                {
+                    Ok(hooked) => hooked,
+                    Err(output) => return output,
+                };
+            apply_hooked_exec_command(&mut exec_params, command);
+            let command = vec![
+                exec_params.shell.clone(),
+                if exec_params.login { "-lc" } else { "-c" }.to_string(),
+                exec_params.cmd.clone(),
+            ];
             let result = sess
                 .session_manager
-                .handle_exec_command_request(exec_params)
+                .handle_exec_command_request(exec_params, turn_context.tokenizer())
                 .await;
-            let function_call_output = crate::exec_command::result_into_payload(result);
+            let exit_code = result.as_ref().ok().and_then(ExecCommandOutput::exit_code);
+            record_streamable_exec_audit(sess, turn_context, &call_id, command, exit_code).await;
+            let mut function_call_output = crate::exec_command::result_into_payload(result);
+            if let Cow::Owned(redacted) = sess.redact_secrets(&function_call_output.content) {
+                function_call_output.content = redacted;
+            }
+            run_post_exec_hooks(
+                sess,
+                &sub_id,
+                &turn_context.cwd,
+                pre,
+                &mut function_call_output,
+            )
+            .await;
             ResponseInputItem::FunctionCallOutput {
                 call_id,
                 output: function_call_output,
@@ -2567,12 +3614,18 @@ async fn handle_function_call(
                     };
                 }
             };
+            let input = vec![write_stdin_params.chars.clone()];
             let result = sess
                 .session_manager
-                .handle_write_stdin_request(write_stdin_params)
+                .handle_write_stdin_request(write_stdin_params, turn_context.tokenizer())
                 .await;
-            let function_call_output: FunctionCallOutputPayload =
+            let exit_code = result.as_ref().ok().and_then(ExecCommandOutput::exit_code);
+            record_streamable_exec_audit(sess, turn_context, &call_id, input, exit_code).await;
+            let mut function_call_output: FunctionCallOutputPayload =
                 crate::exec_command::result_into_payload(result);
+            if let Cow::Owned(redacted) = sess.redact_secrets(&function_call_output.content) {
+                function_call_output.content = redacted;
+            }
             ResponseInputItem::FunctionCallOutput {
                 call_id,
                 output: function_call_output,
@@ -2711,6 +3764,223 @@ fn maybe_translate_shell_command(
 }
 
 async fn handle_container_exec_with_params(
+    mut params: ExecParams,
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    call_id: String,
+) -> ResponseInputItem {
+    if !sess.hooks.has_tool_call_hooks() {
+        return run_container_exec(
+            params,
+            sess,
+            turn_context,
+            turn_diff_tracker,
+            sub_id,
+            call_id,
+        )
+        .await;
+    }
+
+    let pre = tool_call_hook_payload(&call_id, &params.command, &params.cwd);
+    let original = pre.clone();
+    let pre = sess.hooks.run(&sub_id, &params.cwd, pre).await;
+    let action = match pre.payload {
+        HookPayload::PrePatch { .. } => "patch",
+        _ => "exec command",
+    };
+    if let Some(reason) = &pre.veto {
+        sess.notify_background_event(&sub_id, format!("hook blocked {action}: {reason}"))
+            .await;
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: format!("{action} rejected by hook: {reason}{}", pre.feedback()),
+                success: Some(false),
+            },
+        };
+    }
+
+    // Work out what the post-hooks will see before a patch changes the files
+    // it is verified against.
+    let post = if pre.payload == original {
+        original
+    } else {
+        match &pre.payload {
+            HookPayload::PreExec { command, .. } => params.command = command.clone(),
+            HookPayload::PrePatch { patch, .. } => {
+                params.command = vec!["apply_patch".to_string(), patch.clone()];
+            }
+            _ => {}
+        }
+        tool_call_hook_payload(&call_id, &params.command, &params.cwd)
+    };
+    let cwd = params.cwd.clone();
+
+    let mut result = run_container_exec(
+        params,
+        sess,
+        turn_context,
+        turn_diff_tracker,
+        sub_id.clone(),
+        call_id,
+    )
+    .await;
+    if let ResponseInputItem::FunctionCallOutput { output, .. } = &mut result {
+        let success = output.success.unwrap_or(false);
+        let post = match post {
+            HookPayload::PreExec { call_id, command } => HookPayload::PostExec {
+                call_id,
+                command,
+                output: output.content.clone(),
+                success,
+            },
+            HookPayload::PrePatch {
+                call_id,
+                patch,
+                files,
+            } => HookPayload::PostPatch {
+                call_id,
+                patch,
+                files,
+                output: output.content.clone(),
+                success,
+            },
+            other => other,
+        };
+        let post = sess.hooks.run(&sub_id, &cwd, post).await;
+        output.content.push_str(&pre.feedback());
+        output.content.push_str(&post.feedback());
+    }
+    result
+}
+
+/// Describes a shell call to the `pre_exec` or `pre_patch` hooks, depending
+/// on whether it applies a patch.
+fn tool_call_hook_payload(call_id: &str, command: &[String], cwd: &Path) -> HookPayload {
+    match maybe_parse_apply_patch_verified(command, cwd) {
+        MaybeApplyPatchVerified::Body(action) => {
+            let mut files: Vec<PathBuf> = action.changes().keys().cloned().collect();
+            files.sort();
+            HookPayload::PrePatch {
+                call_id: call_id.to_string(),
+                patch: action.patch,
+                files,
+            }
+        }
+        _ => HookPayload::PreExec {
+            call_id: call_id.to_string(),
+            command: command.to_vec(),
+        },
+    }
+}
+
+/// Runs the `pre_exec` hooks for `command`, which the `exec_command` or
+/// `unified_exec` tool is about to run in a new session. Input written to a
+/// running session is not shown to the hooks, so they cannot rewrite it.
+/// Returns the command as rewritten by the hooks along with their
+/// outcome, which is `None` when no hooks run around tool calls. A veto
+/// becomes the output of the call.
+async fn run_pre_exec_hooks(
+    sess: &Session,
+    sub_id: &str,
+    cwd: &Path,
+    call_id: &str,
+    command: Vec<String>,
+) -> Result<(Vec<String>, Option<HookOutcome>), ResponseInputItem> {
+    if !sess.hooks.has_tool_call_hooks() {
+        return Ok((command, None));
+    }
+    let pre = HookPayload::PreExec {
+        call_id: call_id.to_string(),
+        command,
+    };
+    let pre = sess.hooks.run(sub_id, cwd, pre).await;
+    if let Some(reason) = &pre.veto {
+        sess.notify_background_event(sub_id, format!("hook blocked exec command: {reason}"))
+            .await;
+        return Err(ResponseInputItem::FunctionCallOutput {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: format!("exec command rejected by hook: {reason}{}", pre.feedback()),
+                success: Some(false),
+            },
+        });
+    }
+    let command = match &pre.payload {
+        HookPayload::PreExec { command, .. } => command.clone(),
+        _ => Vec::new(),
+    };
+    Ok((command, Some(pre)))
+}
+
+/// Runs the `post_exec` hooks for a command that [`run_pre_exec_hooks`]
+/// let through, and appends the messages of both to `output`.
+async fn run_post_exec_hooks(
+    sess: &Session,
+    sub_id: &str,
+    cwd: &Path,
+    pre: Option<HookOutcome>,
+    output: &mut FunctionCallOutputPayload,
+) {
+    let Some(pre) = pre else {
+        return;
+    };
+    let HookPayload::PreExec { call_id, command } = pre.payload.clone() else {
+        return;
+    };
+    let post = HookPayload::PostExec {
+        call_id,
+        command,
+        output: output.content.clone(),
+        success: output.success.unwrap_or(false),
+    };
+    let post = sess.hooks.run(sub_id, cwd, post).await;
+    output.content.push_str(&pre.feedback());
+    output.content.push_str(&post.feedback());
+}
+
+/// Records the `Exec` audit event for a command that the `exec_command` or
+/// `unified_exec` tool started, or for input that `write_stdin` or
+/// `unified_exec` wrote to a running one. These tools run commands outside of
+/// the sandbox, in Codex's own working directory. `exit_code` is `None` when
+/// it is not known yet.
+async fn record_streamable_exec_audit(
+    sess: &Session,
+    turn_context: &TurnContext,
+    call_id: &str,
+    command: Vec<String>,
+    exit_code: Option<i32>,
+) {
+    sess.record_audit(AuditEvent::Exec {
+        call_id: call_id.to_string(),
+        command,
+        cwd: std::env::current_dir().unwrap_or_else(|_| turn_context.cwd.clone()),
+        sandbox: SandboxType::None,
+        exit_code: exit_code.unwrap_or(-1),
+    })
+    .await;
+}
+
+/// Applies the command the hooks saw for an `exec_command` call, a shell
+/// invocation of its script, to `params`. A command of that form replaces
+/// the shell and the script; any other command becomes the script.
+fn apply_hooked_exec_command(params: &mut ExecCommandParams, command: Vec<String>) {
+    match command.as_slice() {
+        [shell, mode, script] if mode == "-c" || mode == "-lc" => {
+            params.shell = shell.clone();
+            params.login = mode == "-lc";
+            params.cmd = script.clone();
+        }
+        _ => {
+            params.cmd = shlex::try_join(command.iter().map(String::as_str))
+                .unwrap_or_else(|_| command.join(" "));
+        }
+    }
+}
+
+async fn run_container_exec(
     params: ExecParams,
     sess: &Session,
     turn_context: &TurnContext,
@@ -2818,6 +4088,7 @@ async fn handle_container_exec_with_params(
                     &turn_context.sandbox_policy,
                     &state.approved_commands,
                     params.with_escalated_permissions.unwrap_or(false),
+                    &params.cwd,
                 )
             };
             let command_for_display = params.command.clone();
@@ -2826,9 +4097,11 @@ async fn handle_container_exec_with_params(
     };
 
     let sandbox_type = match safety {
-        SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
+        SafetyCheck::AutoApprove { sandbox_type } => {
+            apply_linux_sandbox_backend(sandbox_type, sess.linux_sandbox_backend)
+        }
         SafetyCheck::AskUser => {
-            let decision = sess
+            let (decision, _) = sess
                 .request_command_approval(
                     sub_id.clone(),
                     call_id.clone(),
@@ -2852,6 +4125,17 @@ async fn handle_container_exec_with_params(
                     };
                 }
             }
+            if params.with_escalated_permissions.unwrap_or(false) {
+                sess.record_audit(AuditEvent::Escalation {
+                    call_id: call_id.clone(),
+                    command: params.command.clone(),
+                    reason: params
+                        .justification
+                        .clone()
+                        .unwrap_or_else(|| "model requested escalated permissions".to_string()),
+                })
+                .await;
+            }
             // No sandboxing is applied because the user has given
             // explicit approval. Often, we end up in this case because
             // the command cannot be run in a sandbox, such as
@@ -2883,6 +4167,7 @@ async fn handle_container_exec_with_params(
                 changes: convert_apply_patch_to_protocol(&action),
             },
         ),
+        tokenizer: turn_context.tokenizer(),
     };
 
     let params = maybe_translate_shell_command(params, sess, turn_context);
@@ -2903,6 +4188,7 @@ async fn handle_container_exec_with_params(
                         sub_id: sub_id.clone(),
                         call_id: call_id.clone(),
                         tx_event: sess.tx_event.clone(),
+                        redactor: sess.secret_redactor.clone(),
                     })
                 },
             },
@@ -2914,7 +4200,7 @@ async fn handle_container_exec_with_params(
             let ExecToolCallOutput { exit_code, .. } = &output;
 
             let is_success = *exit_code == 0;
-            let content = format_exec_output(&output);
+            let content = format_exec_output(&output, turn_context.tokenizer());
             ResponseInputItem::FunctionCallOutput {
                 call_id: call_id.clone(),
                 output: FunctionCallOutputPayload {
@@ -2959,7 +4245,7 @@ async fn handle_sandbox_error(
     let cwd = exec_command_context.cwd.clone();
 
     if let SandboxErr::Timeout { output } = &error {
-        let content = format_exec_output(output);
+        let content = format_exec_output(output, turn_context.tokenizer());
         return ResponseInputItem::FunctionCallOutput {
             call_id,
             output: FunctionCallOutputPayload {
@@ -2969,16 +4255,24 @@ async fn handle_sandbox_error(
         };
     }
 
+    let violations_summary = match &error {
+        SandboxErr::Denied { output } => summarize_sandbox_violations(&output.sandbox_violations),
+        _ => None,
+    };
+
     // Early out if either the user never wants to be asked for approval, or
     // we're letting the model manage escalation requests. Otherwise, continue
     match turn_context.approval_policy {
         AskForApproval::Never | AskForApproval::OnRequest => {
+            let mut content =
+                format!("failed in sandbox {sandbox_type:?} with execution error: {error}");
+            if let Some(summary) = &violations_summary {
+                content.push_str(&format!("\n{summary}"));
+            }
             return ResponseInputItem::FunctionCallOutput {
                 call_id,
                 output: FunctionCallOutputPayload {
-                    content: format!(
-                        "failed in sandbox {sandbox_type:?} with execution error: {error}"
-                    ),
+                    content,
                     success: Some(false),
                 },
             };
@@ -2994,17 +4288,22 @@ async fn handle_sandbox_error(
     // exit codes merit a retry.
 
     // For now, we categorically ask the user to retry without sandbox and
-    // emit the raw error as a background event.
+    // emit the raw error as a background event. When the sandbox reported
+    // what it blocked, we use that to explain why escalation is needed.
     sess.notify_background_event(&sub_id, format!("Execution failed: {error}"))
         .await;
 
-    let decision = sess
+    let reason = match &violations_summary {
+        Some(summary) => format!("command failed; retry without sandbox? ({summary})"),
+        None => "command failed; retry without sandbox?".to_string(),
+    };
+    let (decision, _) = sess
         .request_command_approval(
             sub_id.clone(),
             call_id.clone(),
             params.command.clone(),
             cwd.clone(),
-            Some("command failed; retry without sandbox?".to_string()),
+            Some(reason),
         )
         .await;
 
@@ -3015,6 +4314,12 @@ async fn handle_sandbox_error(
             // executions skip the sandbox directly.
             // TODO(ragona): Isn't this a bug? It always saves the command in an | fork?
             sess.add_approved_command(params.command.clone()).await;
+            sess.record_audit(AuditEvent::Escalation {
+                call_id: call_id.clone(),
+                command: params.command.clone(),
+                reason: format!("command failed in sandbox: {error}"),
+            })
+            .await;
             // Inform UI we are retrying without sandbox.
             sess.notify_background_event(&sub_id, "retrying command without sandbox")
                 .await;
@@ -3038,6 +4343,7 @@ async fn handle_sandbox_error(
                                 sub_id: sub_id.clone(),
                                 call_id: call_id.clone(),
                                 tx_event: sess.tx_event.clone(),
+                                redactor: sess.secret_redactor.clone(),
                             })
                         },
                     },
@@ -3049,7 +4355,7 @@ async fn handle_sandbox_error(
                     let ExecToolCallOutput { exit_code, .. } = &retry_output;
 
                     let is_success = *exit_code == 0;
-                    let content = format_exec_output(&retry_output);
+                    let content = format_exec_output(&retry_output, turn_context.tokenizer());
 
                     ResponseInputItem::FunctionCallOutput {
                         call_id: call_id.clone(),
@@ -3081,7 +4387,21 @@ async fn handle_sandbox_error(
     }
 }
 
-fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
+/// Summarizes the operations blocked by the sandbox in a single line, e.g.,
+/// `sandbox blocked: write to /etc/hosts (openat), network access (connect)`.
+fn summarize_sandbox_violations(violations: &[SandboxViolation]) -> Option<String> {
+    if violations.is_empty() {
+        return None;
+    }
+    let blocked = violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!("sandbox blocked: {blocked}"))
+}
+
+fn format_exec_output_str(exec_output: &ExecToolCallOutput, tokenizer: Tokenizer) -> String {
     let ExecToolCallOutput {
         aggregated_output, ..
     } = exec_output;
@@ -3100,101 +4420,17 @@ fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
         s = &prefixed_str;
     }
 
-    let total_lines = s.lines().count();
-    if s.len() <= MODEL_FORMAT_MAX_BYTES && total_lines <= MODEL_FORMAT_MAX_LINES {
-        return s.to_string();
+    let violations_str: String;
+    if let Some(summary) = summarize_sandbox_violations(&exec_output.sandbox_violations) {
+        violations_str = format!("{summary}\n{s}");
+        s = &violations_str;
     }
 
-    let lines: Vec<&str> = s.lines().collect();
-    let head_take = MODEL_FORMAT_HEAD_LINES.min(lines.len());
-    let tail_take = MODEL_FORMAT_TAIL_LINES.min(lines.len().saturating_sub(head_take));
-    let omitted = lines.len().saturating_sub(head_take + tail_take);
-
-    // Join head and tail blocks (lines() strips newlines; reinsert them)
-    let head_block = lines
-        .iter()
-        .take(head_take)
-        .cloned()
-        .collect::<Vec<_>>()
-        .join("\n");
-    let tail_block = if tail_take > 0 {
-        lines[lines.len() - tail_take..].join("\n")
-    } else {
-        String::new()
-    };
-    let marker = format!("\n[... omitted {omitted} of {total_lines} lines ...]\n\n");
-
-    // Byte budgets for head/tail around the marker
-    let mut head_budget = MODEL_FORMAT_HEAD_BYTES.min(MODEL_FORMAT_MAX_BYTES);
-    let tail_budget = MODEL_FORMAT_MAX_BYTES.saturating_sub(head_budget + marker.len());
-    if tail_budget == 0 && marker.len() >= MODEL_FORMAT_MAX_BYTES {
-        // Degenerate case: marker alone exceeds budget; return a clipped marker
-        return take_bytes_at_char_boundary(&marker, MODEL_FORMAT_MAX_BYTES).to_string();
-    }
-    if tail_budget == 0 {
-        // Make room for the marker by shrinking head
-        head_budget = MODEL_FORMAT_MAX_BYTES.saturating_sub(marker.len());
-    }
-
-    // Enforce line-count cap by trimming head/tail lines
-    let head_lines_text = head_block;
-    let tail_lines_text = tail_block;
-    // Build final string respecting byte budgets
-    let head_part = take_bytes_at_char_boundary(&head_lines_text, head_budget);
-    let mut result = String::with_capacity(MODEL_FORMAT_MAX_BYTES.min(s.len()));
-
-    result.push_str(head_part);
-    result.push_str(&marker);
-
-    let remaining = MODEL_FORMAT_MAX_BYTES.saturating_sub(result.len());
-    let tail_budget_final = remaining;
-    let tail_part = take_last_bytes_at_char_boundary(&tail_lines_text, tail_budget_final);
-    result.push_str(tail_part);
-
-    result
-}
-
-// Truncate a &str to a byte budget at a char boundary (prefix)
-#[inline]
-fn take_bytes_at_char_boundary(s: &str, maxb: usize) -> &str {
-    if s.len() <= maxb {
-        return s;
-    }
-    let mut last_ok = 0;
-    for (i, ch) in s.char_indices() {
-        let nb = i + ch.len_utf8();
-        if nb > maxb {
-            break;
-        }
-        last_ok = nb;
-    }
-    &s[..last_ok]
-}
-
-// Take a suffix of a &str within a byte budget at a char boundary
-#[inline]
-fn take_last_bytes_at_char_boundary(s: &str, maxb: usize) -> &str {
-    if s.len() <= maxb {
-        return s;
-    }
-    let mut start = s.len();
-    let mut used = 0usize;
-    for (i, ch) in s.char_indices().rev() {
-        let nb = ch.len_utf8();
-        if used + nb > maxb {
-            break;
-        }
-        start = i;
-        used += nb;
-        if start == 0 {
-            break;
-        }
-    }
-    &s[start..]
+    truncate_middle(s, MODEL_FORMAT_MAX_TOKENS, tokenizer).0
 }
 
 /// Exec output is a pre-serialized JSON payload
-fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
+fn format_exec_output(exec_output: &ExecToolCallOutput, tokenizer: Tokenizer) -> String {
     let ExecToolCallOutput {
         exit_code,
         duration,
@@ -3216,7 +4452,7 @@ fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
     // round to 1 decimal place
     let duration_seconds = ((duration.as_secs_f32()) * 10.0).round() / 10.0;
 
-    let formatted_output = format_exec_output_str(exec_output);
+    let formatted_output = format_exec_output_str(exec_output, tokenizer);
 
     let payload = ExecOutput {
         output: &formatted_output,
@@ -3230,6 +4466,43 @@ fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
     serde_json::to_string(&payload).expect("serialize ExecOutput")
 }
 
+/// Send `input` to the model as a single user message, outside of the
+/// conversation, with `instructions` in place of the base instructions.
+/// Returns the text of the reply, if any.
+async fn request_one_shot_reply(
+    turn_context: &TurnContext,
+    instructions: &str,
+    input: String,
+) -> CodexResult<Option<String>> {
+    let prompt = Prompt {
+        input: vec![ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText { text: input }],
+        }],
+        base_instructions_override: Some(instructions.to_string()),
+        ..Default::default()
+    };
+
+    let mut stream = turn_context.client.clone().stream(&prompt).await?;
+    let mut items = Vec::new();
+    loop {
+        match stream.next().await {
+            Some(Ok(ResponseEvent::OutputItemDone(item))) => items.push(item),
+            Some(Ok(ResponseEvent::Completed { .. })) => break,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(e),
+            None => {
+                return Err(CodexErr::Stream(
+                    "stream closed before response.completed".into(),
+                    None,
+                ));
+            }
+        }
+    }
+    Ok(get_last_assistant_message_from_turn(&items))
+}
+
 pub(super) fn get_last_assistant_message_from_turn(responses: &[ResponseItem]) -> Option<String> {
     responses.iter().rev().find_map(|item| {
         if let ResponseItem::Message { role, content, .. } = item {
@@ -3345,9 +4618,10 @@ pub(crate) use tests::make_session_and_context;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codex::compact::build_compacted_history;
+    use crate::codex::compact::collect_user_messages;
     use crate::config::ConfigOverrides;
     use crate::config::ConfigToml;
-    use crate::protocol::CompactedItem;
     use crate::protocol::InitialHistory;
     use crate::protocol::ResumedHistory;
     use codex_protocol::models::ContentItem;
@@ -3360,6 +4634,33 @@ mod tests {
     use std::sync::Arc;
     use std::time::Duration as StdDuration;
 
+    #[cfg(unix)]
+    #[test]
+    fn review_output_paths_are_resolved_against_the_repo_root() {
+        let text = r#"```json
+{"findings": [
+  {"title": "[P1] a", "body": "", "confidence_score": 0.5, "priority": 1,
+   "code_location": {"absolute_file_path": "src/lib.rs", "line_range": {"start": 1, "end": 2}}},
+  {"title": "[P2] b", "body": "", "confidence_score": 0.5, "priority": 2,
+   "code_location": {"absolute_file_path": "/elsewhere/notes.txt", "line_range": {"start": 3, "end": 3}}}
+],
+ "overall_correctness": "patch is incorrect", "overall_explanation": "", "overall_confidence_score": 0.5}
+```"#;
+        let ev = parse_review_output_event(text, Path::new("/repo"));
+        let paths: Vec<&Path> = ev
+            .findings
+            .iter()
+            .map(|finding| finding.code_location.absolute_file_path.as_path())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("/repo/src/lib.rs"),
+                Path::new("/elsewhere/notes.txt")
+            ]
+        );
+    }
+
     #[test]
     fn reconstruct_history_matches_live_compactions() {
         let (session, turn_context) = make_session_and_context();
@@ -3427,9 +4728,8 @@ mod tests {
     }
 
     #[test]
-    fn model_truncation_head_tail_by_lines() {
-        // Build 400 short lines so line-count limit, not byte budget, triggers truncation
-        let lines: Vec<String> = (1..=400).map(|i| format!("line{i}")).collect();
+    fn model_truncation_keeps_head_and_tail_lines() {
+        let lines: Vec<String> = (1..=4_000).map(|i| format!("line{i}")).collect();
         let full = lines.join("\n");
 
         let exec = ExecToolCallOutput {
@@ -3439,37 +4739,21 @@ mod tests {
             aggregated_output: StreamOutput::new(full),
             duration: StdDuration::from_secs(1),
             timed_out: false,
+            sandbox_violations: Vec::new(),
         };
 
-        let out = format_exec_output_str(&exec);
-
-        // Expect elision marker with correct counts
-        let omitted = 400 - MODEL_FORMAT_MAX_LINES; // 144
-        let marker = format!("\n[... omitted {omitted} of 400 lines ...]\n\n");
-        assert!(out.contains(&marker), "missing marker: {out}");
+        let tokenizer = Tokenizer::Heuristic;
+        let out = format_exec_output_str(&exec, tokenizer);
 
-        // Validate head and tail
-        let parts: Vec<&str> = out.split(&marker).collect();
-        assert_eq!(parts.len(), 2, "expected one marker split");
-        let head = parts[0];
-        let tail = parts[1];
-
-        let expected_head: String = (1..=MODEL_FORMAT_HEAD_LINES)
-            .map(|i| format!("line{i}"))
-            .collect::<Vec<_>>()
-            .join("\n");
-        assert!(head.starts_with(&expected_head), "head mismatch");
-
-        let expected_tail: String = ((400 - MODEL_FORMAT_TAIL_LINES + 1)..=400)
-            .map(|i| format!("line{i}"))
-            .collect::<Vec<_>>()
-            .join("\n");
-        assert!(tail.ends_with(&expected_tail), "tail mismatch");
+        assert!(out.contains("tokens truncated"), "missing marker: {out}");
+        assert!(tokenizer.count(&out) <= MODEL_FORMAT_MAX_TOKENS);
+        assert!(out.starts_with("line1\nline2\n"), "head mismatch");
+        assert!(out.ends_with("line3999\nline4000"), "tail mismatch");
     }
 
     #[test]
-    fn model_truncation_respects_byte_budget() {
-        // Construct a large output (about 100kB) so byte budget dominates
+    fn model_truncation_respects_token_budget() {
+        // Construct a large output (about 100kB) so the token budget is exceeded.
         let big_line = "x".repeat(100);
         let full = std::iter::repeat_n(big_line, 1000)
             .collect::<Vec<_>>()
@@ -3482,11 +4766,19 @@ mod tests {
             aggregated_output: StreamOutput::new(full.clone()),
             duration: StdDuration::from_secs(1),
             timed_out: false,
+            sandbox_violations: Vec::new(),
         };
 
-        let out = format_exec_output_str(&exec);
-        assert!(out.len() <= MODEL_FORMAT_MAX_BYTES, "exceeds byte budget");
-        assert!(out.contains("omitted"), "should contain elision marker");
+        let tokenizer = Tokenizer::default();
+        let out = format_exec_output_str(&exec, tokenizer);
+        assert!(
+            tokenizer.count(&out) <= MODEL_FORMAT_MAX_TOKENS,
+            "exceeds token budget"
+        );
+        assert!(
+            out.contains("tokens truncated"),
+            "should contain elision marker"
+        );
 
         // Ensure head and tail are drawn from the original
         assert!(full.starts_with(out.chars().take(8).collect::<String>().as_str()));
@@ -3513,9 +4805,10 @@ mod tests {
             aggregated_output: StreamOutput::new("Command output".to_string()),
             duration: StdDuration::from_secs(1),
             timed_out: true,
+            sandbox_violations: Vec::new(),
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, Tokenizer::default());
 
         assert_eq!(
             out,
@@ -3523,6 +4816,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn includes_sandbox_violations() {
+        let exec = ExecToolCallOutput {
+            exit_code: 1,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new("Permission denied".to_string()),
+            duration: StdDuration::from_secs(1),
+            timed_out: false,
+            sandbox_violations: vec![
+                SandboxViolation::FileWrite {
+                    syscall: "openat".to_string(),
+                    path: PathBuf::from("/etc/hosts"),
+                },
+                SandboxViolation::Network {
+                    syscall: "connect".to_string(),
+                },
+            ],
+        };
+
+        let out = format_exec_output_str(&exec, Tokenizer::default());
+
+        assert_eq!(
+            out,
+            "sandbox blocked: write to /etc/hosts (openat), network access (connect)\nPermission denied"
+        );
+    }
+
     #[test]
     fn falls_back_to_content_when_structured_is_null() {
         let ctr = CallToolResult {
@@ -3609,6 +4930,7 @@ mod tests {
             include_web_search_request: config.tools_web_search_request,
             use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
             include_view_image_tool: config.include_view_image_tool,
+            include_delegate_tool: config.include_delegate_tool,
             experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
         });
         let turn_context = TurnContext {
@@ -3636,8 +4958,13 @@ mod tests {
                 ..Default::default()
             }),
             codex_linux_sandbox_exe: None,
+            linux_sandbox_backend: config.linux_sandbox_backend,
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            secret_redactor: None,
+            audit: None,
+            hooks: HookRunner::new(config.hooks.clone(), conversation_id),
+            daily_cost: Mutex::new(DailyCost::default()),
             next_internal_sub_id: AtomicU64::new(0),
         };
         (session, turn_context)
@@ -3683,10 +5010,13 @@ mod tests {
             session.build_initial_context(turn_context),
             &user_messages1,
             summary1,
+            turn_context.tokenizer(),
         );
         live_history.replace(rebuilt1);
         rollout_items.push(RolloutItem::Compacted(CompactedItem {
             message: summary1.to_string(),
+            retained_turns: None,
+            replacement_history: None,
         }));
 
         let user2 = ResponseItem::Message {
@@ -3716,10 +5046,13 @@ mod tests {
             session.build_initial_context(turn_context),
             &user_messages2,
             summary2,
+            turn_context.tokenizer(),
         );
         live_history.replace(rebuilt2);
         rollout_items.push(RolloutItem::Compacted(CompactedItem {
             message: summary2.to_string(),
+            retained_turns: None,
+            replacement_history: None,
         }));
 
         let user3 = ResponseItem::Message {
@@ -0,0 +1,168 @@
+//! Commits the files a turn changed when `auto_commit` is enabled.
+
+use codex_git_tooling::CommitPathsOptions;
+use codex_git_tooling::GitToolingError;
+use codex_git_tooling::commit_paths;
+use codex_git_tooling::committable_paths;
+use tracing::warn;
+
+use super::Session;
+use super::TurnContext;
+use super::request_one_shot_reply;
+use crate::error::Result as CodexResult;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::TurnCommittedEvent;
+use crate::truncate::truncate_middle;
+use crate::turn_diff_tracker::TurnDiffTracker;
+
+const COMMIT_MESSAGE_PROMPT: &str = include_str!("../../templates/auto_commit/prompt.md");
+/// Largest part of the turn diff shown to the model.
+const COMMIT_DIFF_MAX_TOKENS: u64 = 8_000;
+/// Used when the model does not produce a commit message.
+const FALLBACK_COMMIT_MESSAGE: &str = "chore: apply changes from Codex turn";
+
+/// Commit the files touched by the patches of this turn, and only those,
+/// with a message written by the model. Files outside the repository of the
+/// turn's working directory and ignored files are left alone. Emits
+/// `TurnCommitted` on success, which records the commit in the rollout.
+pub(super) async fn commit_turn_changes(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    turn_diff_tracker: &mut TurnDiffTracker,
+) {
+    let config = turn_context.client.get_config();
+    if !config.auto_commit.enabled {
+        return;
+    }
+    let touched = turn_diff_tracker.touched_paths();
+    let Ok(Some(diff)) = turn_diff_tracker.get_unified_diff() else {
+        return;
+    };
+
+    let cwd = turn_context.cwd.clone();
+    let paths = {
+        let touched = touched.clone();
+        let cwd = cwd.clone();
+        tokio::task::spawn_blocking(move || {
+            committable_paths(&CommitPathsOptions::new(&cwd, &touched, ""))
+        })
+        .await
+        .unwrap_or_else(|e| Err(GitToolingError::Io(std::io::Error::other(e))))
+    };
+    let paths = match paths {
+        Ok(paths) if paths.is_empty() => return,
+        Ok(paths) => paths,
+        Err(e) => {
+            warn!("auto-commit skipped: {e}");
+            sess.notify_background_event(sub_id, format!("Auto-commit skipped: {e}"))
+                .await;
+            return;
+        }
+    };
+
+    let subject = match generate_commit_message(turn_context, &diff).await {
+        Ok(Some(message)) => message,
+        Ok(None) => FALLBACK_COMMIT_MESSAGE.to_string(),
+        Err(e) => {
+            warn!("failed to generate commit message: {e}");
+            FALLBACK_COMMIT_MESSAGE.to_string()
+        }
+    };
+    let message = with_trailer(&subject, config.auto_commit.trailer.as_deref());
+
+    let result = {
+        let message = message.clone();
+        tokio::task::spawn_blocking(move || {
+            commit_paths(&CommitPathsOptions::new(&cwd, &touched, &message))
+        })
+        .await
+        .unwrap_or_else(|e| Err(GitToolingError::Io(std::io::Error::other(e))))
+    };
+    match result {
+        Ok(Some(sha)) => {
+            sess.send_event(Event {
+                id: sub_id.to_string(),
+                msg: EventMsg::TurnCommitted(TurnCommittedEvent {
+                    sha,
+                    message,
+                    paths,
+                }),
+            })
+            .await;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            warn!("auto-commit failed: {e}");
+            sess.notify_background_event(sub_id, format!("Auto-commit failed: {e}"))
+                .await;
+        }
+    }
+}
+
+/// Ask the model for a conventional commit message for `diff`. Returns
+/// `None` when the reply has no usable text.
+async fn generate_commit_message(
+    turn_context: &TurnContext,
+    diff: &str,
+) -> CodexResult<Option<String>> {
+    let (diff, _) = truncate_middle(diff, COMMIT_DIFF_MAX_TOKENS, turn_context.tokenizer());
+    let reply = request_one_shot_reply(turn_context, COMMIT_MESSAGE_PROMPT, diff).await?;
+    Ok(reply.and_then(|text| clean_commit_message(&text)))
+}
+
+/// Strip the code fences and surrounding whitespace models sometimes add.
+fn clean_commit_message(text: &str) -> Option<String> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(|inner| inner.trim_start_matches(|c: char| c.is_ascii_alphabetic()))
+        .unwrap_or(text)
+        .trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+fn with_trailer(message: &str, trailer: Option<&str>) -> String {
+    match trailer.map(str::trim).filter(|trailer| !trailer.is_empty()) {
+        Some(trailer) => format!("{message}\n\n{trailer}"),
+        None => message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn clean_commit_message_strips_fences() {
+        assert_eq!(
+            clean_commit_message("```text\nfix: handle empty input\n```\n"),
+            Some("fix: handle empty input".to_string())
+        );
+        assert_eq!(
+            clean_commit_message("  feat(cli): add flag\n\nBody.\n"),
+            Some("feat(cli): add flag\n\nBody.".to_string())
+        );
+        assert_eq!(clean_commit_message("```\n```"), None);
+    }
+
+    #[test]
+    fn trailer_is_separated_by_a_blank_line() {
+        assert_eq!(
+            with_trailer(
+                "fix: typo",
+                Some("Co-authored-by: Codex <codex@example.com>")
+            ),
+            "fix: typo\n\nCo-authored-by: Codex <codex@example.com>"
+        );
+        assert_eq!(with_trailer("fix: typo", Some("  ")), "fix: typo");
+        assert_eq!(with_trailer("fix: typo", None), "fix: typo");
+    }
+}
@@ -18,17 +18,21 @@ use crate::protocol::InputMessageKind;
 use crate::protocol::TaskCompleteEvent;
 use crate::protocol::TaskStartedEvent;
 use crate::protocol::TurnContextItem;
+use crate::tokenizer::Tokenizer;
 use crate::truncate::truncate_middle;
 use crate::util::backoff;
 use askama::Template;
 use codex_protocol::models::ContentItem;
+use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 use codex_protocol::models::ResponseItem;
 use codex_protocol::protocol::RolloutItem;
 use futures::prelude::*;
 
 pub const SUMMARIZATION_PROMPT: &str = include_str!("../../templates/compact/prompt.md");
-const COMPACT_USER_MESSAGE_MAX_TOKENS: usize = 20_000;
+const COMPACT_USER_MESSAGE_MAX_TOKENS: u64 = 20_000;
+/// Stands in for tool output that is pruned before summarizing.
+const PRUNED_TOOL_OUTPUT: &str = "[output pruned]";
 
 #[derive(Template)]
 #[template(path = "compact/history_bridge.md", escape = "none")]
@@ -89,9 +93,24 @@ async fn run_compact_task_inner(
     remove_task_on_completion: bool,
 ) {
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
-    let turn_input = sess
-        .turn_input_with_history(vec![initial_input_for_turn.clone().into()])
-        .await;
+    let compaction = turn_context.client.get_config().compaction;
+    let history_before = {
+        let state = sess.state.lock().await;
+        state.history.contents()
+    };
+    let retained_turns = retained_turn_count(
+        &history_before,
+        compaction.keep_recent_turns,
+        turn_context.tokenizer(),
+        retained_turns_token_budget(turn_context.as_ref()),
+    );
+    let (summarized, _) = split_recent_turns(&history_before, retained_turns);
+    let mut turn_input = if compaction.prune_tool_outputs {
+        prune_tool_outputs(summarized)
+    } else {
+        summarized.to_vec()
+    };
+    turn_input.push(initial_input_for_turn.into());
 
     let prompt = Prompt {
         input: turn_input,
@@ -156,16 +175,24 @@ async fn run_compact_task_inner(
         state.history.contents()
     };
     let summary_text = get_last_assistant_message_from_turn(&history_snapshot).unwrap_or_default();
-    let user_messages = collect_user_messages(&history_snapshot);
     let initial_context = sess.build_initial_context(turn_context.as_ref());
-    let new_history = build_compacted_history(initial_context, &user_messages, &summary_text);
+    let new_history = compact_history(
+        initial_context,
+        &history_before,
+        retained_turns,
+        &summary_text,
+        turn_context.tokenizer(),
+    );
     {
         let mut state = sess.state.lock().await;
-        state.history.replace(new_history);
+        state.history.replace(new_history.clone());
+        state.context_tokens = None;
     }
 
     let rollout_item = RolloutItem::Compacted(CompactedItem {
         message: summary_text.clone(),
+        retained_turns: (retained_turns > 0).then_some(retained_turns),
+        replacement_history: Some(new_history),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
 
@@ -217,10 +244,122 @@ pub fn is_session_prefix_message(text: &str) -> bool {
     )
 }
 
+/// Tokens the turns kept verbatim may take up: half of the limit that
+/// triggers auto-compaction, or of the context window when there is none,
+/// so that compacting frees room even when the recent turns are large.
+fn retained_turns_token_budget(turn_context: &TurnContext) -> Option<u64> {
+    Some(turn_context.auto_compact_token_limit()? / 2)
+}
+
+/// Number of turns to keep verbatim when compacting `history` with
+/// `keep_recent_turns`. The oldest turn is always summarized, otherwise
+/// compaction would have nothing to shrink, and turns are dropped from the
+/// oldest kept one until the rest fit in `max_tokens`. This also covers an
+/// auto-compaction in the middle of a turn whose own tool output is what
+/// overflowed the context window.
+fn retained_turn_count(
+    history: &[ResponseItem],
+    keep_recent_turns: usize,
+    tokenizer: Tokenizer,
+    max_tokens: Option<u64>,
+) -> usize {
+    let turns = history.iter().filter(|item| is_turn_start(item)).count();
+    let mut retained_turns = keep_recent_turns.min(turns.saturating_sub(1));
+    let Some(max_tokens) = max_tokens else {
+        return retained_turns;
+    };
+    while retained_turns > 0 {
+        let (_, retained) = split_recent_turns(history, retained_turns);
+        let tokens: u64 = retained.iter().map(|item| tokenizer.count_item(item)).sum();
+        if tokens <= max_tokens {
+            break;
+        }
+        retained_turns -= 1;
+    }
+    retained_turns
+}
+
+/// Splits `history` right before the user message that starts the
+/// `retained_turns`-th most recent turn. Splitting on user messages keeps
+/// every tool call in the same half as its output.
+pub(crate) fn split_recent_turns(
+    history: &[ResponseItem],
+    retained_turns: usize,
+) -> (&[ResponseItem], &[ResponseItem]) {
+    if retained_turns == 0 {
+        return (history, &[]);
+    }
+    let split = history
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, item)| is_turn_start(item))
+        .nth(retained_turns - 1)
+        .map_or(0, |(idx, _)| idx);
+    history.split_at(split)
+}
+
+fn is_turn_start(item: &ResponseItem) -> bool {
+    match item {
+        ResponseItem::Message { role, content, .. } if role == "user" => {
+            content_items_to_text(content).is_some_and(|text| !is_session_prefix_message(&text))
+        }
+        _ => false,
+    }
+}
+
+/// Replaces the body of every tool output in `items` with a short stub.
+fn prune_tool_outputs(items: &[ResponseItem]) -> Vec<ResponseItem> {
+    items
+        .iter()
+        .map(|item| match item {
+            ResponseItem::FunctionCallOutput { call_id, output }
+                if output.content.len() > PRUNED_TOOL_OUTPUT.len() =>
+            {
+                ResponseItem::FunctionCallOutput {
+                    call_id: call_id.clone(),
+                    output: FunctionCallOutputPayload {
+                        content: PRUNED_TOOL_OUTPUT.to_string(),
+                        success: output.success,
+                    },
+                }
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output }
+                if output.len() > PRUNED_TOOL_OUTPUT.len() =>
+            {
+                ResponseItem::CustomToolCallOutput {
+                    call_id: call_id.clone(),
+                    output: PRUNED_TOOL_OUTPUT.to_string(),
+                }
+            }
+            other => other.clone(),
+        })
+        .collect()
+}
+
+/// History after compacting `history`: the initial context, the bridge
+/// message with the summary and the user messages of the summarized turns,
+/// then the last `retained_turns` turns verbatim.
+pub(crate) fn compact_history(
+    initial_context: Vec<ResponseItem>,
+    history: &[ResponseItem],
+    retained_turns: usize,
+    summary_text: &str,
+    tokenizer: Tokenizer,
+) -> Vec<ResponseItem> {
+    let (summarized, retained) = split_recent_turns(history, retained_turns);
+    let user_messages = collect_user_messages(summarized);
+    let mut new_history =
+        build_compacted_history(initial_context, &user_messages, summary_text, tokenizer);
+    new_history.extend_from_slice(retained);
+    new_history
+}
+
 pub(crate) fn build_compacted_history(
     initial_context: Vec<ResponseItem>,
     user_messages: &[String],
     summary_text: &str,
+    tokenizer: Tokenizer,
 ) -> Vec<ResponseItem> {
     let mut history = initial_context;
     let mut user_messages_text = if user_messages.is_empty() {
@@ -229,11 +368,13 @@ pub(crate) fn build_compacted_history(
         user_messages.join("\n\n")
     };
     // Truncate the concatenated prior user messages so the bridge message
-    // stays well under the context window (approx. 4 bytes/token).
-    let max_bytes = COMPACT_USER_MESSAGE_MAX_TOKENS * 4;
-    if user_messages_text.len() > max_bytes {
-        user_messages_text = truncate_middle(&user_messages_text, max_bytes).0;
-    }
+    // stays well under the context window.
+    user_messages_text = truncate_middle(
+        &user_messages_text,
+        COMPACT_USER_MESSAGE_MAX_TOKENS,
+        tokenizer,
+    )
+    .0;
     let summary_text = if summary_text.is_empty() {
         "(no summary available)".to_string()
     } else {
@@ -382,9 +523,14 @@ mod tests {
     fn build_compacted_history_truncates_overlong_user_messages() {
         // Prepare a very large prior user message so the aggregated
         // `user_messages_text` exceeds the truncation threshold used by
-        // `build_compacted_history` (80k bytes).
-        let big = "X".repeat(200_000);
-        let history = build_compacted_history(Vec::new(), std::slice::from_ref(&big), "SUMMARY");
+        // `build_compacted_history` (20k tokens).
+        let big = "The quick brown fox jumps over the lazy dog. ".repeat(5_000);
+        let history = build_compacted_history(
+            Vec::new(),
+            std::slice::from_ref(&big),
+            "SUMMARY",
+            Tokenizer::default(),
+        );
 
         // Expect exactly one bridge message added to history (plus any initial context we provided, which is none).
         assert_eq!(history.len(), 1);
@@ -411,4 +557,102 @@ mod tests {
             "bridge should include the provided summary text"
         );
     }
+
+    fn user(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn output(call_id: &str, content: &str) -> ResponseItem {
+        ResponseItem::FunctionCallOutput {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: content.to_string(),
+                success: Some(true),
+            },
+        }
+    }
+
+    #[test]
+    fn split_recent_turns_splits_before_retained_user_messages() {
+        let history = vec![
+            user("<environment_context>cwd=/tmp</environment_context>"),
+            user("one"),
+            output("call-1", "first output"),
+            user("two"),
+            output("call-2", "second output"),
+            user("three"),
+        ];
+
+        let count = |keep_recent_turns| {
+            retained_turn_count(&history, keep_recent_turns, Tokenizer::Heuristic, None)
+        };
+        assert_eq!(count(0), 0);
+        assert_eq!(count(2), 2);
+        // The oldest turn is always summarized.
+        assert_eq!(count(5), 2);
+
+        let (summarized, retained) = split_recent_turns(&history, 2);
+        assert_eq!(summarized, &history[..3]);
+        assert_eq!(retained, &history[3..]);
+        assert_eq!(split_recent_turns(&history, 0), (&history[..], &[][..]));
+    }
+
+    #[test]
+    fn retained_turns_are_capped_by_tokens() {
+        let history = vec![
+            user("one"),
+            user("two"),
+            output("call-2", &"x".repeat(4_000)),
+            user("three"),
+            output("call-3", &"x".repeat(400)),
+        ];
+        let count = |max_tokens| retained_turn_count(&history, 2, Tokenizer::Heuristic, max_tokens);
+
+        assert_eq!(count(None), 2);
+        assert_eq!(count(Some(10_000)), 2);
+        // The second turn's output does not fit, so only the last turn is kept.
+        assert_eq!(count(Some(500)), 1);
+        // Not even the current turn fits; everything is summarized.
+        assert_eq!(count(Some(50)), 0);
+    }
+
+    #[test]
+    fn compact_history_appends_retained_turns_after_bridge() {
+        let history = vec![user("one"), output("call-1", "out"), user("two")];
+
+        let compacted = compact_history(Vec::new(), &history, 1, "SUMMARY", Tokenizer::default());
+
+        assert_eq!(compacted.len(), 2);
+        let ResponseItem::Message { content, .. } = &compacted[0] else {
+            panic!("expected bridge message, got {:?}", compacted[0]);
+        };
+        let bridge = content_items_to_text(content).unwrap_or_default();
+        assert!(bridge.contains("one") && bridge.contains("SUMMARY"));
+        assert!(!bridge.contains("two"));
+        assert_eq!(compacted[1], user("two"));
+    }
+
+    #[test]
+    fn prune_tool_outputs_replaces_long_bodies() {
+        let pruned = prune_tool_outputs(&[
+            user("one"),
+            output("call-1", &"x".repeat(1_000)),
+            output("call-2", "ok"),
+        ]);
+
+        assert_eq!(
+            pruned,
+            vec![
+                user("one"),
+                output("call-1", PRUNED_TOOL_OUTPUT),
+                output("call-2", "ok"),
+            ]
+        );
+    }
 }
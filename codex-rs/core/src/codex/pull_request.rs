@@ -0,0 +1,239 @@
+//! Opens pull requests for the session's branch and turns their review
+//! comments into a follow-up turn. Both talk to the network, so they run in
+//! the background instead of holding up the submission loop.
+
+use std::sync::Arc;
+
+use tracing::warn;
+
+use super::AgentTask;
+use super::Session;
+use super::TurnContext;
+use super::request_one_shot_reply;
+use crate::forge::DEFAULT_REMOTE;
+use crate::forge::ForgeError;
+use crate::forge::NewPullRequest;
+use crate::forge::current_branch;
+use crate::forge::forge_for_repo;
+use crate::forge::pull_request_changes;
+use crate::forge::push_branch;
+use crate::forge::review_comments_context;
+use crate::forge::review_comments_request;
+use crate::git_info::get_default_branch_of_remote;
+use crate::protocol::ErrorEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::InputItem;
+use crate::protocol::PullRequestOpenedEvent;
+use crate::truncate::truncate_middle;
+use codex_protocol::models::ResponseInputItem;
+
+const PULL_REQUEST_PROMPT: &str = include_str!("../../templates/pull_request/prompt.md");
+/// Largest part of the branch diff shown to the model.
+const PULL_REQUEST_DIFF_MAX_TOKENS: u64 = 16_000;
+
+/// Push the current branch and open a pull request for it into `base`, or
+/// the remote's default branch. Replies with `PullRequestOpened`, or an
+/// error event when any step fails.
+pub(super) fn spawn_open_pull_request(
+    sess: Arc<Session>,
+    turn_context: Arc<TurnContext>,
+    sub_id: String,
+    base: Option<String>,
+    draft: bool,
+) {
+    tokio::spawn(async move {
+        let msg = match open_pull_request(&turn_context, base, draft).await {
+            Ok(event) => EventMsg::PullRequestOpened(event),
+            Err(e) => EventMsg::Error(ErrorEvent {
+                message: format!("Failed to open pull request: {e}"),
+            }),
+        };
+        sess.send_event(Event { id: sub_id, msg }).await;
+    });
+}
+
+/// Fetch the review comments on pull request `number`, or on the open pull
+/// request for the current branch, and ask the agent to address them. The
+/// comments are passed as a separate developer message, never as the user's.
+pub(super) fn spawn_address_review_comments(
+    sess: Arc<Session>,
+    turn_context: Arc<TurnContext>,
+    sub_id: String,
+    number: Option<u64>,
+) {
+    tokio::spawn(async move {
+        let (context, request) = match review_comments(&turn_context, number).await {
+            Ok(Some(review)) => review,
+            Ok(None) => {
+                sess.notify_background_event(&sub_id, "The pull request has no review comments.")
+                    .await;
+                return;
+            }
+            Err(e) => {
+                sess.send_event(Event {
+                    id: sub_id,
+                    msg: EventMsg::Error(ErrorEvent {
+                        message: format!("Failed to fetch review comments: {e}"),
+                    }),
+                })
+                .await;
+                return;
+            }
+        };
+        if let Err(items) = sess.inject_input_with_context(context, vec![request]).await {
+            let task = AgentTask::spawn(Arc::clone(&sess), turn_context, sub_id, items);
+            sess.set_task(task).await;
+        }
+    });
+}
+
+async fn open_pull_request(
+    turn_context: &TurnContext,
+    base: Option<String>,
+    draft: bool,
+) -> Result<PullRequestOpenedEvent, ForgeError> {
+    let config = turn_context.client.get_config();
+    let cwd = turn_context.cwd.as_path();
+    let forge = forge_for_repo(&config.forge, cwd).await?;
+    let remote = config.forge.remote.as_deref().unwrap_or(DEFAULT_REMOTE);
+    let branch = current_branch(cwd).await?;
+    let base = match base {
+        Some(base) => base,
+        None => get_default_branch_of_remote(cwd, remote)
+            .await
+            .ok_or_else(|| {
+                ForgeError::InvalidBranch(
+                    "cannot tell the default branch; pass the base branch explicitly".to_string(),
+                )
+            })?,
+    };
+    if branch == base {
+        return Err(ForgeError::InvalidBranch(format!(
+            "`{branch}` is the base branch; commit the changes on another branch first"
+        )));
+    }
+    let (log, diff) = pull_request_changes(cwd, remote, &base).await;
+    if log.is_empty() {
+        return Err(ForgeError::InvalidBranch(format!(
+            "`{branch}` has no commits that are not on `{base}`"
+        )));
+    }
+
+    let (diff, _) = truncate_middle(
+        &diff,
+        PULL_REQUEST_DIFF_MAX_TOKENS,
+        turn_context.tokenizer(),
+    );
+    let input = format!("Commits:\n{log}\n\nDiff:\n{diff}");
+    let description = match request_one_shot_reply(turn_context, PULL_REQUEST_PROMPT, input).await {
+        Ok(reply) => reply.and_then(|text| split_description(&text)),
+        Err(e) => {
+            warn!("failed to generate pull request description: {e}");
+            None
+        }
+    };
+    let (title, body) = description.unwrap_or_else(|| fallback_description(&log));
+
+    push_branch(cwd, remote, &branch).await?;
+    let pull_request = forge
+        .open_pull_request(&NewPullRequest {
+            title,
+            body,
+            head: branch.clone(),
+            base,
+            draft,
+        })
+        .await?;
+    Ok(PullRequestOpenedEvent {
+        number: pull_request.number,
+        url: pull_request.url,
+        title: pull_request.title,
+        branch,
+    })
+}
+
+/// The review comments on the pull request as a developer message, along
+/// with the request to address them, or `None` when there are none.
+async fn review_comments(
+    turn_context: &TurnContext,
+    number: Option<u64>,
+) -> Result<Option<(ResponseInputItem, InputItem)>, ForgeError> {
+    let config = turn_context.client.get_config();
+    let cwd = turn_context.cwd.as_path();
+    let forge = forge_for_repo(&config.forge, cwd).await?;
+    let pull_request = match number {
+        Some(number) => forge.pull_request(number).await?,
+        None => {
+            let branch = current_branch(cwd).await?;
+            forge
+                .find_pull_request(&branch)
+                .await?
+                .ok_or(ForgeError::NoPullRequest(branch))?
+        }
+    };
+    let comments = forge.review_comments(pull_request.number).await?;
+    if comments.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((
+        review_comments_context(&pull_request, &comments),
+        review_comments_request(&pull_request),
+    )))
+}
+
+/// Split the model's reply into a title and a body, dropping the Markdown
+/// heading marker models sometimes put on the title.
+fn split_description(text: &str) -> Option<(String, String)> {
+    let text = text.trim();
+    let (title, body) = text.split_once('\n').unwrap_or((text, ""));
+    let title = title.trim_start_matches('#').trim();
+    if title.is_empty() {
+        return None;
+    }
+    Some((title.to_string(), body.trim().to_string()))
+}
+
+/// Title from the oldest commit and the commit list as the body, for when
+/// the model does not describe the pull request.
+fn fallback_description(log: &str) -> (String, String) {
+    let title = log
+        .lines()
+        .next_back()
+        .map(|line| line.trim_start_matches("- ").to_string())
+        .unwrap_or_default();
+    (title, log.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn split_description_takes_the_first_line_as_title() {
+        assert_eq!(
+            split_description("# Add widget cache\n\nCaches widgets.\n\n- Adds `Cache`\n"),
+            Some((
+                "Add widget cache".to_string(),
+                "Caches widgets.\n\n- Adds `Cache`".to_string()
+            ))
+        );
+        assert_eq!(
+            split_description("Fix typo"),
+            Some(("Fix typo".to_string(), String::new()))
+        );
+        assert_eq!(split_description("  \n"), None);
+    }
+
+    #[test]
+    fn fallback_description_uses_the_oldest_commit() {
+        assert_eq!(
+            fallback_description("- Add tests\n- Add widget cache"),
+            (
+                "Add widget cache".to_string(),
+                "- Add tests\n- Add widget cache".to_string()
+            )
+        );
+    }
+}
@@ -1,13 +1,27 @@
 use crate::config_profile::ConfigProfile;
+use crate::config_types::Audit;
+use crate::config_types::AutoCommit;
+use crate::config_types::Budget;
+use crate::config_types::Checkpoints;
+use crate::config_types::Compaction;
+use crate::config_types::ForgeConfig;
 use crate::config_types::History;
+use crate::config_types::Hooks;
+use crate::config_types::LinuxSandboxBackend;
 use crate::config_types::McpServerConfig;
+use crate::config_types::ModelFallback;
+use crate::config_types::ModelFallbackToml;
+use crate::config_types::ModelPricing;
 use crate::config_types::Notifications;
 use crate::config_types::ReasoningSummaryFormat;
+use crate::config_types::ResponseCacheMode;
 use crate::config_types::SandboxWorkspaceWrite;
+use crate::config_types::SessionRetention;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
 use crate::config_types::Tui;
 use crate::config_types::UriBasedFileOpener;
+use crate::config_types::Worktree;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
 use crate::model_family::derive_default_model_family;
@@ -46,6 +60,8 @@ pub const GPT_5_CODEX_MEDIUM_MODEL: &str = "gpt-5-codex";
 /// the context window.
 pub(crate) const PROJECT_DOC_MAX_BYTES: usize = 32 * 1024; // 32 KiB
 
+pub(crate) const DEFAULT_OUTPUT_SCHEMA_MAX_RETRIES: u32 = 2;
+
 pub(crate) const CONFIG_TOML_FILE: &str = "config.toml";
 
 /// Application configuration loaded from disk and merged with overrides.
@@ -68,12 +84,27 @@ pub struct Config {
     /// Token usage threshold triggering auto-compaction of conversation history.
     pub model_auto_compact_token_limit: Option<i64>,
 
+    /// Whether requests allow the model to emit several tool calls at once.
+    /// Defaults to false.
+    pub parallel_tool_calls: Option<bool>,
+
+    /// How many times the model is asked to fix a final message that does
+    /// not match the turn's output schema.
+    pub output_schema_max_retries: u32,
+
+    /// How the history is compacted.
+    pub compaction: Compaction,
+
     /// Key into the model_providers map that specifies which provider to use.
     pub model_provider_id: String,
 
     /// Info needed to make an API request to the model.
     pub model_provider: ModelProviderInfo,
 
+    /// Ordered list of models to switch to when requests to `model` keep
+    /// failing after all retries.
+    pub model_fallbacks: Vec<ModelFallback>,
+
     /// Approval policy for executing commands.
     pub approval_policy: AskForApproval,
 
@@ -81,6 +112,19 @@ pub struct Config {
 
     pub shell_environment_policy: ShellEnvironmentPolicy,
 
+    /// When `true` (the default), secrets detected in command and MCP tool
+    /// output are replaced with placeholders before the model sees them or
+    /// they are written to the rollout.
+    pub redact_secrets: bool,
+
+    /// When `true`, secret redaction also replaces long random-looking
+    /// tokens. Off by default: it catches hashes and fixtures too.
+    pub redact_high_entropy_strings: bool,
+
+    /// Record model responses to, or replay them from, the local response
+    /// cache. `None` (the default) disables the cache.
+    pub response_cache: Option<ResponseCacheMode>,
+
     /// When `true`, `AgentReasoning` events emitted by the backend will be
     /// suppressed from the frontend output. This can reduce visual noise when
     /// users are only interested in the final agent responses.
@@ -133,6 +177,12 @@ pub struct Config {
     /// Combined provider map (defaults merged with user-defined overrides).
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
+    /// User-defined prices keyed by model slug, overriding the built-in ones.
+    pub model_pricing: HashMap<String, ModelPricing>,
+
+    /// Spending limits on the estimated cost of model requests.
+    pub budget: Budget,
+
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: usize,
 
@@ -143,6 +193,27 @@ pub struct Config {
     /// Settings that govern if and what will be written to `~/.codex/history.jsonl`.
     pub history: History,
 
+    /// Settings for the audit log written to `~/.codex/audit.jsonl`.
+    pub audit: Audit,
+
+    /// External commands run before and after agent actions.
+    pub hooks: Hooks,
+
+    /// Snapshots of the workspace taken before each turn, for undo.
+    pub checkpoints: Checkpoints,
+
+    /// Limits on the recorded sessions kept under `~/.codex/sessions`.
+    pub session_retention: SessionRetention,
+
+    /// Running sessions in a separate git worktree.
+    pub worktree: Worktree,
+
+    /// Committing the changes of every turn.
+    pub auto_commit: AutoCommit,
+
+    /// Opening pull requests and reading their review comments.
+    pub forge: ForgeConfig,
+
     /// Optional URI-based file opener. If set, citations to files in the model
     /// output will be hyperlinked using the specified URI scheme.
     pub file_opener: UriBasedFileOpener,
@@ -155,6 +226,9 @@ pub struct Config {
     /// When this program is invoked, arg0 will be set to `codex-linux-sandbox`.
     pub codex_linux_sandbox_exe: Option<PathBuf>,
 
+    /// Mechanism used by `codex-linux-sandbox` to enforce the sandbox policy.
+    pub linux_sandbox_backend: LinuxSandboxBackend,
+
     /// Value to use for `reasoning.effort` when making a request using the
     /// Responses API.
     pub model_reasoning_effort: Option<ReasoningEffort>,
@@ -187,6 +261,10 @@ pub struct Config {
     /// Include the `view_image` tool that lets the agent attach a local image path to context.
     pub include_view_image_tool: bool,
 
+    /// Include the `delegate` tool that lets the agent hand a focused task to
+    /// a child conversation.
+    pub include_delegate_tool: bool,
+
     /// The active profile name used to derive this `Config` (if any).
     pub active_profile: Option<String>,
 
@@ -609,6 +687,9 @@ pub struct ConfigToml {
     /// Provider to use from the model_providers map.
     pub model_provider: Option<String>,
 
+    /// Ordered list of models to fall back to when `model` keeps failing.
+    pub model_fallbacks: Option<Vec<ModelFallbackToml>>,
+
     /// Size of the context window for the model, in tokens.
     pub model_context_window: Option<u64>,
 
@@ -618,6 +699,18 @@ pub struct ConfigToml {
     /// Token usage threshold triggering auto-compaction of conversation history.
     pub model_auto_compact_token_limit: Option<i64>,
 
+    /// Whether requests allow the model to emit several tool calls at once.
+    /// Defaults to false.
+    pub parallel_tool_calls: Option<bool>,
+
+    /// How many times the model is asked to fix a final message that does
+    /// not match the turn's output schema. Defaults to 2.
+    pub output_schema_max_retries: Option<u32>,
+
+    /// How the history is compacted when the context window fills up or
+    /// `/compact` is run.
+    pub compaction: Option<Compaction>,
+
     /// Default approval policy for executing commands.
     pub approval_policy: Option<AskForApproval>,
 
@@ -630,6 +723,18 @@ pub struct ConfigToml {
     /// Sandbox configuration to apply if `sandbox` is `WorkspaceWrite`.
     pub sandbox_workspace_write: Option<SandboxWorkspaceWrite>,
 
+    /// Mechanism used to enforce the sandbox on Linux. Defaults to Landlock.
+    pub linux_sandbox_backend: Option<LinuxSandboxBackend>,
+
+    /// Redact secrets from command and MCP tool output. Defaults to `true`.
+    pub redact_secrets: Option<bool>,
+
+    /// Also redact long high-entropy strings. Defaults to `false`.
+    pub redact_high_entropy_strings: Option<bool>,
+
+    /// Record/replay model responses through `~/.codex/response_cache`.
+    pub response_cache: Option<ResponseCacheMode>,
+
     /// Optional external command to spawn for end-user notifications.
     #[serde(default)]
     pub notify: Option<Vec<String>>,
@@ -645,6 +750,13 @@ pub struct ConfigToml {
     #[serde(default)]
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
+    /// Prices in USD per million tokens, keyed by model slug.
+    #[serde(default)]
+    pub model_pricing: HashMap<String, ModelPricing>,
+
+    /// Spending limits on the estimated cost of model requests.
+    pub budget: Option<Budget>,
+
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: Option<usize>,
 
@@ -659,6 +771,34 @@ pub struct ConfigToml {
     #[serde(default)]
     pub history: Option<History>,
 
+    /// Settings for the audit log written to `~/.codex/audit.jsonl`.
+    #[serde(default)]
+    pub audit: Option<Audit>,
+
+    /// External commands run before and after agent actions.
+    #[serde(default)]
+    pub hooks: Option<Hooks>,
+
+    /// Snapshots of the workspace taken before each turn, for undo.
+    #[serde(default)]
+    pub checkpoints: Option<Checkpoints>,
+
+    /// Limits on the recorded sessions kept under `~/.codex/sessions`.
+    #[serde(default)]
+    pub session_retention: Option<SessionRetention>,
+
+    /// Running sessions in a separate git worktree.
+    #[serde(default)]
+    pub worktree: Option<Worktree>,
+
+    /// Committing the changes of every turn.
+    #[serde(default)]
+    pub auto_commit: Option<AutoCommit>,
+
+    /// Opening pull requests and reading their review comments.
+    #[serde(default)]
+    pub forge: Option<ForgeConfig>,
+
     /// Optional URI-based file opener. If set, citations to files in the model
     /// output will be hyperlinked using the specified URI scheme.
     pub file_opener: Option<UriBasedFileOpener>,
@@ -741,6 +881,10 @@ pub struct ToolsToml {
     /// Enable the `view_image` tool that lets the agent attach local images.
     #[serde(default)]
     pub view_image: Option<bool>,
+
+    /// Enable the `delegate` tool that runs sub-tasks in child conversations.
+    #[serde(default)]
+    pub delegate: Option<bool>,
 }
 
 impl From<ToolsToml> for Tools {
@@ -748,6 +892,7 @@ impl From<ToolsToml> for Tools {
         Self {
             web_search: tools_toml.web_search,
             view_image: tools_toml.view_image,
+            delegate: tools_toml.delegate,
         }
     }
 }
@@ -913,6 +1058,32 @@ impl Config {
             })?
             .clone();
 
+        let model_fallbacks = config_profile
+            .model_fallbacks
+            .or(cfg.model_fallbacks)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|fallback| {
+                let model_provider_id = fallback
+                    .model_provider
+                    .unwrap_or_else(|| model_provider_id.clone());
+                let model_provider = model_providers
+                    .get(&model_provider_id)
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::NotFound,
+                            format!("Model provider `{model_provider_id}` not found"),
+                        )
+                    })?
+                    .clone();
+                Ok(ModelFallback {
+                    model: fallback.model,
+                    model_provider_id,
+                    model_provider,
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+
         let shell_environment_policy = cfg.shell_environment_policy.into();
 
         let resolved_cwd = {
@@ -997,8 +1168,17 @@ impl Config {
             model_context_window,
             model_max_output_tokens,
             model_auto_compact_token_limit,
+            parallel_tool_calls: cfg.parallel_tool_calls,
+            output_schema_max_retries: cfg
+                .output_schema_max_retries
+                .unwrap_or(DEFAULT_OUTPUT_SCHEMA_MAX_RETRIES),
+            compaction: config_profile
+                .compaction
+                .or(cfg.compaction)
+                .unwrap_or_default(),
             model_provider_id,
             model_provider,
+            model_fallbacks,
             cwd: resolved_cwd,
             approval_policy: approval_policy
                 .or(config_profile.approval_policy)
@@ -1011,11 +1191,24 @@ impl Config {
             base_instructions,
             mcp_servers: cfg.mcp_servers,
             model_providers,
+            model_pricing: cfg.model_pricing,
+            budget: cfg.budget.unwrap_or_default(),
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
             codex_home,
             history,
+            audit: cfg.audit.unwrap_or_default(),
+            hooks: cfg.hooks.unwrap_or_default(),
+            checkpoints: cfg.checkpoints.unwrap_or_default(),
+            session_retention: cfg.session_retention.unwrap_or_default(),
+            worktree: cfg.worktree.unwrap_or_default(),
+            auto_commit: cfg.auto_commit.unwrap_or_default(),
+            forge: cfg.forge.unwrap_or_default(),
             file_opener: cfg.file_opener.unwrap_or(UriBasedFileOpener::VsCode),
             codex_linux_sandbox_exe,
+            linux_sandbox_backend: cfg.linux_sandbox_backend.unwrap_or_default(),
+            redact_secrets: cfg.redact_secrets.unwrap_or(true),
+            redact_high_entropy_strings: cfg.redact_high_entropy_strings.unwrap_or(false),
+            response_cache: cfg.response_cache,
 
             hide_agent_reasoning: cfg.hide_agent_reasoning.unwrap_or(false),
             show_raw_agent_reasoning: cfg
@@ -1044,6 +1237,7 @@ impl Config {
                 .experimental_use_unified_exec_tool
                 .unwrap_or(false),
             include_view_image_tool,
+            include_delegate_tool: cfg.tools.as_ref().and_then(|t| t.delegate).unwrap_or(false),
             active_profile: active_profile_name,
             disable_paste_burst: cfg.disable_paste_burst.unwrap_or(false),
             tui_notifications: cfg
@@ -1270,6 +1464,19 @@ exclude_slash_tmp = true
         );
     }
 
+    #[test]
+    fn test_linux_sandbox_backend_parsing() {
+        let cfg = toml::from_str::<ConfigToml>(r#"linux_sandbox_backend = "namespaces""#)
+            .expect("TOML deserialization should succeed");
+        assert_eq!(
+            Some(LinuxSandboxBackend::Namespaces),
+            cfg.linux_sandbox_backend
+        );
+
+        let cfg = toml::from_str::<ConfigToml>("").expect("TOML deserialization should succeed");
+        assert_eq!(None, cfg.linux_sandbox_backend);
+    }
+
     #[test]
     fn load_global_mcp_servers_returns_empty_if_missing() -> anyhow::Result<()> {
         let codex_home = TempDir::new()?;
@@ -1565,6 +1772,7 @@ model_verbosity = "high"
             stream_max_retries: Some(10),
             stream_idle_timeout_ms: Some(300_000),
             requires_openai_auth: false,
+            supports_json_schema: false,
         };
         let model_provider_map = {
             let mut model_provider_map = built_in_model_providers();
@@ -1624,8 +1832,12 @@ model_verbosity = "high"
                 model_context_window: Some(200_000),
                 model_max_output_tokens: Some(100_000),
                 model_auto_compact_token_limit: None,
+                parallel_tool_calls: None,
+                output_schema_max_retries: DEFAULT_OUTPUT_SCHEMA_MAX_RETRIES,
+                compaction: Compaction::default(),
                 model_provider_id: "openai".to_string(),
                 model_provider: fixture.openai_provider.clone(),
+                model_fallbacks: Vec::new(),
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
@@ -1634,11 +1846,24 @@ model_verbosity = "high"
                 cwd: fixture.cwd(),
                 mcp_servers: HashMap::new(),
                 model_providers: fixture.model_provider_map.clone(),
+                model_pricing: HashMap::new(),
+                budget: Budget::default(),
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
                 codex_home: fixture.codex_home(),
                 history: History::default(),
+                audit: Audit::default(),
+                hooks: Hooks::default(),
+                checkpoints: Checkpoints::default(),
+                session_retention: SessionRetention::default(),
+                worktree: Worktree::default(),
+                auto_commit: AutoCommit::default(),
+                forge: ForgeConfig::default(),
                 file_opener: UriBasedFileOpener::VsCode,
                 codex_linux_sandbox_exe: None,
+                linux_sandbox_backend: LinuxSandboxBackend::default(),
+                redact_secrets: true,
+                redact_high_entropy_strings: false,
+                response_cache: None,
                 hide_agent_reasoning: false,
                 show_raw_agent_reasoning: false,
                 model_reasoning_effort: Some(ReasoningEffort::High),
@@ -1652,6 +1877,7 @@ model_verbosity = "high"
                 use_experimental_streamable_shell_tool: false,
                 use_experimental_unified_exec_tool: false,
                 include_view_image_tool: true,
+                include_delegate_tool: false,
                 active_profile: Some("o3".to_string()),
                 disable_paste_burst: false,
                 tui_notifications: Default::default(),
@@ -1682,8 +1908,12 @@ model_verbosity = "high"
             model_context_window: Some(16_385),
             model_max_output_tokens: Some(4_096),
             model_auto_compact_token_limit: None,
+            parallel_tool_calls: None,
+            output_schema_max_retries: DEFAULT_OUTPUT_SCHEMA_MAX_RETRIES,
+            compaction: Compaction::default(),
             model_provider_id: "openai-chat-completions".to_string(),
             model_provider: fixture.openai_chat_completions_provider.clone(),
+            model_fallbacks: Vec::new(),
             approval_policy: AskForApproval::UnlessTrusted,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
@@ -1692,11 +1922,24 @@ model_verbosity = "high"
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
+            model_pricing: HashMap::new(),
+            budget: Budget::default(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
             history: History::default(),
+            audit: Audit::default(),
+            hooks: Hooks::default(),
+            checkpoints: Checkpoints::default(),
+            session_retention: SessionRetention::default(),
+            worktree: Worktree::default(),
+            auto_commit: AutoCommit::default(),
+            forge: ForgeConfig::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
+            linux_sandbox_backend: LinuxSandboxBackend::default(),
+            redact_secrets: true,
+            redact_high_entropy_strings: false,
+            response_cache: None,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
             model_reasoning_effort: None,
@@ -1710,6 +1953,7 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             active_profile: Some("gpt3".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
@@ -1755,8 +1999,12 @@ model_verbosity = "high"
             model_context_window: Some(200_000),
             model_max_output_tokens: Some(100_000),
             model_auto_compact_token_limit: None,
+            parallel_tool_calls: None,
+            output_schema_max_retries: DEFAULT_OUTPUT_SCHEMA_MAX_RETRIES,
+            compaction: Compaction::default(),
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            model_fallbacks: Vec::new(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
@@ -1765,11 +2013,24 @@ model_verbosity = "high"
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
+            model_pricing: HashMap::new(),
+            budget: Budget::default(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
             history: History::default(),
+            audit: Audit::default(),
+            hooks: Hooks::default(),
+            checkpoints: Checkpoints::default(),
+            session_retention: SessionRetention::default(),
+            worktree: Worktree::default(),
+            auto_commit: AutoCommit::default(),
+            forge: ForgeConfig::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
+            linux_sandbox_backend: LinuxSandboxBackend::default(),
+            redact_secrets: true,
+            redact_high_entropy_strings: false,
+            response_cache: None,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
             model_reasoning_effort: None,
@@ -1783,6 +2044,7 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             active_profile: Some("zdr".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
@@ -1814,8 +2076,12 @@ model_verbosity = "high"
             model_context_window: Some(272_000),
             model_max_output_tokens: Some(128_000),
             model_auto_compact_token_limit: None,
+            parallel_tool_calls: None,
+            output_schema_max_retries: DEFAULT_OUTPUT_SCHEMA_MAX_RETRIES,
+            compaction: Compaction::default(),
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            model_fallbacks: Vec::new(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
@@ -1824,11 +2090,24 @@ model_verbosity = "high"
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
+            model_pricing: HashMap::new(),
+            budget: Budget::default(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
             history: History::default(),
+            audit: Audit::default(),
+            hooks: Hooks::default(),
+            checkpoints: Checkpoints::default(),
+            session_retention: SessionRetention::default(),
+            worktree: Worktree::default(),
+            auto_commit: AutoCommit::default(),
+            forge: ForgeConfig::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
+            linux_sandbox_backend: LinuxSandboxBackend::default(),
+            redact_secrets: true,
+            redact_high_entropy_strings: false,
+            response_cache: None,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
             model_reasoning_effort: Some(ReasoningEffort::High),
@@ -1842,6 +2121,7 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             active_profile: Some("gpt5".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
@@ -1852,6 +2132,91 @@ model_verbosity = "high"
         Ok(())
     }
 
+    #[test]
+    fn model_fallbacks_resolve_providers() -> std::io::Result<()> {
+        let fixture = create_test_fixture()?;
+        let mut cfg = fixture.cfg.clone();
+        cfg.model_fallbacks = Some(vec![ModelFallbackToml {
+            model: "gpt-4.1".to_string(),
+            model_provider: None,
+        }]);
+        cfg.profiles.insert(
+            "failover".to_string(),
+            toml::from_str(
+                r#"
+model = "gpt-5"
+model_provider = "openai"
+model_fallbacks = [
+    { model = "gpt-4.1", model_provider = "openai-chat-completions" },
+    { model = "o3" },
+]
+"#,
+            )
+            .expect("profile should deserialize"),
+        );
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg.clone(),
+            ConfigOverrides {
+                config_profile: Some("failover".to_string()),
+                cwd: Some(fixture.cwd()),
+                ..Default::default()
+            },
+            fixture.codex_home(),
+        )?;
+        assert_eq!(
+            config.model_fallbacks,
+            vec![
+                ModelFallback {
+                    model: "gpt-4.1".to_string(),
+                    model_provider_id: "openai-chat-completions".to_string(),
+                    model_provider: fixture.openai_chat_completions_provider.clone(),
+                },
+                ModelFallback {
+                    model: "o3".to_string(),
+                    model_provider_id: "openai".to_string(),
+                    model_provider: fixture.openai_provider.clone(),
+                },
+            ]
+        );
+
+        // Without a profile override, the top-level list applies and entries
+        // default to the active provider.
+        let config = Config::load_from_base_config_with_overrides(
+            cfg.clone(),
+            ConfigOverrides {
+                cwd: Some(fixture.cwd()),
+                ..Default::default()
+            },
+            fixture.codex_home(),
+        )?;
+        assert_eq!(
+            config.model_fallbacks,
+            vec![ModelFallback {
+                model: "gpt-4.1".to_string(),
+                model_provider_id: "openai-chat-completions".to_string(),
+                model_provider: fixture.openai_chat_completions_provider.clone(),
+            }]
+        );
+
+        cfg.model_fallbacks = Some(vec![ModelFallbackToml {
+            model: "gpt-4.1".to_string(),
+            model_provider: Some("missing".to_string()),
+        }]);
+        let err = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                cwd: Some(fixture.cwd()),
+                ..Default::default()
+            },
+            fixture.codex_home(),
+        )
+        .expect_err("unknown fallback provider should be rejected");
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+        Ok(())
+    }
+
     #[test]
     fn test_set_project_trusted_writes_explicit_tables() -> anyhow::Result<()> {
         let project_dir = Path::new("/some/path");
@@ -8,6 +8,9 @@ use std::path::PathBuf;
 use std::time::Duration;
 use wildmatch::WildMatchPattern;
 
+use crate::model_provider_info::ModelProviderInfo;
+use codex_protocol::config_types::WorktreeAction;
+
 use serde::Deserialize;
 use serde::Deserializer;
 use serde::Serialize;
@@ -140,8 +143,8 @@ pub struct History {
     /// If true, history entries will not be written to disk.
     pub persistence: HistoryPersistence,
 
-    /// If set, the maximum size of the history file in bytes.
-    /// TODO(mbolin): Not currently honored.
+    /// If set, the maximum size of the history file in bytes. When an append
+    /// makes the file larger, the oldest entries are dropped.
     pub max_bytes: Option<usize>,
 }
 
@@ -155,6 +158,244 @@ pub enum HistoryPersistence {
     None,
 }
 
+/// Settings for the audit log of privileged agent actions written to
+/// `~/.codex/audit.jsonl`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Audit {
+    /// If false, no audit records are written.
+    pub enabled: bool,
+
+    /// If true, every record is also forwarded to the local syslog daemon
+    /// (which is journald on most systemd-based Linux distributions).
+    pub syslog: bool,
+}
+
+impl Default for Audit {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            syslog: false,
+        }
+    }
+}
+
+/// External commands run around agent actions. Each hook receives a JSON
+/// description of the action on stdin; see `hooks.rs` for the protocol.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Hooks {
+    /// Run before a shell command. May veto it or rewrite the command.
+    pub pre_exec: Vec<HookCommand>,
+
+    /// Run after a shell command with its output.
+    pub post_exec: Vec<HookCommand>,
+
+    /// Run before a patch is applied. May veto it or rewrite the patch.
+    pub pre_patch: Vec<HookCommand>,
+
+    /// Run after a patch is applied.
+    pub post_patch: Vec<HookCommand>,
+
+    /// Run when the user submits a prompt. May veto it or rewrite the prompt.
+    pub user_prompt_submit: Vec<HookCommand>,
+
+    /// Run when a turn completes. Its output is ignored.
+    pub turn_complete: Vec<HookCommand>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct HookCommand {
+    /// Program and arguments to run.
+    pub command: Vec<String>,
+
+    /// How long the hook may run before it is killed. Defaults to 60 seconds.
+    pub timeout_ms: Option<u64>,
+}
+
+/// Snapshots of the workspace taken before each turn so that its changes
+/// can be undone. Git repositories are snapshotted as ghost commits; other
+/// directories are copied into `~/.codex/checkpoints`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Checkpoints {
+    pub enabled: bool,
+
+    /// Largest working directory, in bytes, that is copied into
+    /// `~/.codex/checkpoints`. Checkpoints are disabled for the session when
+    /// a directory outside git exceeds it.
+    pub max_snapshot_bytes: u64,
+}
+
+impl Default for Checkpoints {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_snapshot_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// Limits on the rollout files kept under `~/.codex/sessions`. Sessions
+/// that exceed any limit are deleted when a new session starts or when
+/// `codex sessions prune` runs.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SessionRetention {
+    /// Delete sessions that were last written more than this many days ago.
+    pub max_age_days: Option<u64>,
+
+    /// Delete the oldest sessions once the rollout files take up more than
+    /// this many bytes in total.
+    pub max_total_bytes: Option<u64>,
+
+    /// Keep at most this many sessions, deleting the oldest ones.
+    pub keep_last: Option<usize>,
+
+    /// If true, archived sessions (`~/.codex/archived_sessions`) are never
+    /// deleted and do not count towards the limits above.
+    pub keep_archived: bool,
+
+    /// Compress rollouts with zstd once they have not been written to for
+    /// this many days.
+    pub compress_after_days: Option<u64>,
+}
+
+impl Default for SessionRetention {
+    fn default() -> Self {
+        Self {
+            max_age_days: None,
+            max_total_bytes: None,
+            keep_last: None,
+            keep_archived: true,
+            compress_after_days: None,
+        }
+    }
+}
+
+impl SessionRetention {
+    /// True if no limit is configured, so there is nothing to enforce.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_age_days.is_none()
+            && self.max_total_bytes.is_none()
+            && self.keep_last.is_none()
+            && self.compress_after_days.is_none()
+    }
+}
+
+/// Run sessions in a fresh `git worktree` on a new branch instead of the
+/// current checkout, so that the agent's edits do not collide with the
+/// user's.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Worktree {
+    pub enabled: bool,
+
+    /// Directory the worktrees are created in. Defaults to
+    /// `~/.codex/worktrees`.
+    pub dir: Option<PathBuf>,
+
+    /// Prefix of the branch created for each worktree.
+    pub branch_prefix: String,
+
+    /// What to do with the worktree when the session ends. When unset, the
+    /// user is asked if possible and the branch is kept otherwise.
+    pub on_exit: Option<WorktreeAction>,
+}
+
+impl Default for Worktree {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            branch_prefix: "codex/".to_string(),
+            on_exit: None,
+        }
+    }
+}
+
+/// Commit the files changed by each turn, with a commit message written by
+/// the model.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct AutoCommit {
+    pub enabled: bool,
+
+    /// Trailer appended to every commit message, e.g.
+    /// `Co-authored-by: Codex <codex@example.com>`.
+    pub trailer: Option<String>,
+}
+
+/// Where pull requests for the session's repository are opened.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ForgeConfig {
+    /// Forge API to use. Detected from the remote's host when unset.
+    pub kind: Option<ForgeKind>,
+
+    /// Git remote to push to and to read the repository from. Defaults to
+    /// `origin`.
+    pub remote: Option<String>,
+
+    /// Base URL of the REST API, for self-hosted instances. Defaults to
+    /// `https://api.github.com` or `https://<host>/api/v4`.
+    pub api_base_url: Option<String>,
+
+    /// Environment variable holding the API token. Defaults to
+    /// `GITHUB_TOKEN` or `GITLAB_TOKEN`.
+    pub token_env_var: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    Github,
+    Gitlab,
+}
+
+/// Price of a model in USD per million tokens.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+
+    /// Price of input tokens served from the prompt cache. Defaults to the
+    /// regular input price.
+    pub cached_input: Option<f64>,
+
+    pub output: f64,
+}
+
+/// Spending limits in USD, enforced against the estimated cost of model
+/// requests. Soft limits pause the running task; hard limits abort it.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Budget {
+    pub session_soft_usd: Option<f64>,
+    pub session_hard_usd: Option<f64>,
+
+    /// Daily limits count every session started from the same `CODEX_HOME`
+    /// since local midnight.
+    pub daily_soft_usd: Option<f64>,
+    pub daily_hard_usd: Option<f64>,
+}
+
+/// Entry of the `model_fallbacks` list as written in `config.toml`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ModelFallbackToml {
+    pub model: String,
+
+    /// Key in the `model_providers` map. Defaults to the active provider.
+    pub model_provider: Option<String>,
+}
+
+/// Model (and provider) that a turn switches to when the active provider
+/// keeps failing with rate limits, server errors, or stream idle timeouts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelFallback {
+    pub model: String,
+    pub model_provider_id: String,
+    pub model_provider: ModelProviderInfo,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(untagged)]
 pub enum Notifications {
@@ -189,6 +430,51 @@ pub struct SandboxWorkspaceWrite {
     pub exclude_slash_tmp: bool,
 }
 
+/// How the conversation history is compacted, either by `/compact` or
+/// automatically when the context window fills up. The default replaces the
+/// whole history with a summary.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Compaction {
+    /// Number of most recent user turns kept verbatim after the summary.
+    /// The oldest turn is always summarized.
+    #[serde(default)]
+    pub keep_recent_turns: usize,
+
+    /// Replace the output of tool calls that are about to be summarized with
+    /// a short stub, so the summarization request stays small.
+    #[serde(default)]
+    pub prune_tool_outputs: bool,
+}
+
+/// How [`ModelClient::stream`](crate::ModelClient::stream) uses the local
+/// response cache in `~/.codex/response_cache`.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResponseCacheMode {
+    /// Always query the model and (over)write the transcript for the prompt.
+    Record,
+    /// Only replay recorded transcripts; a prompt without one is an error.
+    Replay,
+    /// Replay the transcript for the prompt if there is one, otherwise query
+    /// the model and record it.
+    Auto,
+}
+
+/// Mechanism used to enforce the sandbox policy on Linux.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinuxSandboxBackend {
+    /// Landlock filesystem rules combined with a seccomp network filter.
+    #[default]
+    Landlock,
+
+    /// Unprivileged user, mount and PID namespaces. Only the writable roots
+    /// are mounted read-write, `/tmp` and `/proc` are private to the command,
+    /// and the whole process tree is killed when the command exits or times
+    /// out. Sandbox violations are not reported with this backend.
+    Namespaces,
+}
+
 impl From<SandboxWorkspaceWrite> for codex_protocol::mcp_protocol::SandboxSettings {
     fn from(sandbox_workspace_write: SandboxWorkspaceWrite) -> Self {
         Self {
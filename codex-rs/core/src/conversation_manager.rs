@@ -17,6 +17,7 @@ use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::models::ResponseItem;
 use codex_protocol::protocol::InitialHistory;
 use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::SessionFork;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -64,7 +65,7 @@ impl ConversationManager {
         let CodexSpawnOk {
             codex,
             conversation_id,
-        } = Codex::spawn(config, auth_manager, InitialHistory::New).await?;
+        } = Codex::spawn(config, auth_manager, InitialHistory::New, None).await?;
         self.finalize_spawn(codex, conversation_id).await
     }
 
@@ -121,7 +122,7 @@ impl ConversationManager {
         let CodexSpawnOk {
             codex,
             conversation_id,
-        } = Codex::spawn(config, auth_manager, initial_history).await?;
+        } = Codex::spawn(config, auth_manager, initial_history, None).await?;
         self.finalize_spawn(codex, conversation_id).await
     }
 
@@ -139,23 +140,37 @@ impl ConversationManager {
     /// Fork an existing conversation by taking messages up to the given position
     /// (not including the message at the given position) and starting a new
     /// conversation with identical configuration (unless overridden by the
-    /// caller's `config`). The new conversation will have a fresh id.
+    /// caller's `config`). The new conversation will have a fresh id, and its
+    /// session metadata records the parent, the fork point and `branch_name`.
     pub async fn fork_conversation(
         &self,
         nth_user_message: usize,
+        branch_name: Option<String>,
         config: Config,
         path: PathBuf,
     ) -> CodexResult<NewConversation> {
         // Compute the prefix up to the cut point.
         let history = RolloutRecorder::get_rollout_history(&path).await?;
+        let parent_id = match &history {
+            InitialHistory::Resumed(resumed) => Some(resumed.conversation_id),
+            InitialHistory::New | InitialHistory::Forked(_) => None,
+        };
         let history = truncate_before_nth_user_message(history, nth_user_message);
+        let fork = match (&history, parent_id) {
+            (InitialHistory::Forked(_), Some(parent_id)) => Some(SessionFork {
+                parent_id,
+                nth_user_message,
+                name: branch_name,
+            }),
+            _ => None,
+        };
 
         // Spawn a new conversation with the computed initial history.
         let auth_manager = self.auth_manager.clone();
         let CodexSpawnOk {
             codex,
             conversation_id,
-        } = Codex::spawn(config, auth_manager, history).await?;
+        } = Codex::spawn(config, auth_manager, history, fork).await?;
 
         self.finalize_spawn(codex, conversation_id).await
     }
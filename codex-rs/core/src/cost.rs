@@ -0,0 +1,386 @@
+//! Estimated cost of model requests and the spending budgets enforced on it.
+//!
+//! The cost of a session is carried in [`TokenUsageInfo::total_cost_usd`] and
+//! is therefore persisted in rollouts along with the `TokenCount` events.
+//! Daily budgets need the spend of every session, so each priced request is
+//! also appended to a ledger with one file per local day,
+//! `~/.codex/costs/2025-01-01.jsonl`:
+//!
+//! ````text
+//! {"ts":"2025-01-01T12:00:00Z","conversation_id":"<uuid>","model":"gpt-5","cost_usd":0.0123}
+//! ````
+//!
+//! Ledger files are append-only, so [`DailyCost`] keeps a running total and
+//! only reads what was appended since it last looked. Files older than
+//! [`COST_LEDGER_RETENTION_DAYS`] are deleted when a new day starts.
+//!
+//! [`TokenUsageInfo::total_cost_usd`]: crate::protocol::TokenUsageInfo::total_cost_usd
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Result;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDate;
+use chrono::Utc;
+use codex_protocol::mcp_protocol::ConversationId;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::config::Config;
+use crate::config_types::Budget;
+use crate::config_types::ModelPricing;
+use crate::openai_model_info::get_model_pricing;
+use crate::protocol::BudgetExceededEvent;
+use crate::protocol::BudgetKind;
+use crate::protocol::BudgetScope;
+use crate::protocol::TokenUsage;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+/// Directory that stores the daily cost ledger files inside `~/.codex`.
+pub const COST_LEDGER_DIRNAME: &str = "costs";
+
+/// Number of days, including today, whose ledger files are kept.
+pub const COST_LEDGER_RETENTION_DAYS: u64 = 30;
+
+/// A single line of the cost ledger.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CostRecord {
+    pub ts: DateTime<Utc>,
+    pub conversation_id: ConversationId,
+    pub model: String,
+    pub cost_usd: f64,
+}
+
+/// Pricing for the model `config` talks to: the `model_pricing` entry for
+/// its slug if there is one, otherwise the built-in price list.
+pub(crate) fn model_pricing(config: &Config) -> Option<ModelPricing> {
+    config
+        .model_pricing
+        .get(&config.model)
+        .copied()
+        .or_else(|| get_model_pricing(&config.model_family))
+}
+
+/// Estimated cost in USD of a request that used `usage` tokens.
+pub(crate) fn cost_usd(pricing: &ModelPricing, usage: &TokenUsage) -> f64 {
+    let cached_input = pricing.cached_input.unwrap_or(pricing.input);
+    (usage.non_cached_input() as f64 * pricing.input
+        + usage.cached_input() as f64 * cached_input
+        + usage.output_tokens as f64 * pricing.output)
+        / 1_000_000.0
+}
+
+/// Ledger file holding the records of the local day `day`.
+pub fn cost_ledger_path(codex_home: &Path, day: NaiveDate) -> PathBuf {
+    codex_home
+        .join(COST_LEDGER_DIRNAME)
+        .join(format!("{}.jsonl", day.format("%Y-%m-%d")))
+}
+
+/// Appends `record` to the ledger file of its local day. Records are far
+/// smaller than `PIPE_BUF`, so a single `O_APPEND` write cannot interleave
+/// with writes from concurrent sessions.
+pub(crate) async fn record_cost(codex_home: &Path, record: &CostRecord) -> Result<()> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    tokio::fs::create_dir_all(codex_home.join(COST_LEDGER_DIRNAME)).await?;
+
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    {
+        options.mode(0o600);
+    }
+    let path = cost_ledger_path(codex_home, record.ts.with_timezone(&Local).date_naive());
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut file = options.open(path)?;
+        file.write_all(line.as_bytes())
+    })
+    .await?
+}
+
+/// Running total of the spend recorded by every session on the current
+/// local day.
+#[derive(Debug, Default)]
+pub(crate) struct DailyCost {
+    day: Option<NaiveDate>,
+    total_usd: f64,
+    /// Bytes of the day's ledger file already added to `total_usd`.
+    offset: u64,
+}
+
+impl DailyCost {
+    /// Total cost in USD recorded since local midnight of `now`. Only the
+    /// complete lines appended since the previous call are read.
+    pub(crate) async fn total_usd(
+        &mut self,
+        codex_home: &Path,
+        now: DateTime<Local>,
+    ) -> Result<f64> {
+        let today = now.date_naive();
+        if self.day != Some(today) {
+            *self = DailyCost {
+                day: Some(today),
+                ..Default::default()
+            };
+            if let Err(e) = prune_cost_ledger(codex_home, today).await {
+                warn!("failed to prune cost ledger: {e}");
+            }
+        }
+
+        let path = cost_ledger_path(codex_home, today);
+        let offset = self.offset;
+        let appended =
+            tokio::task::spawn_blocking(move || read_complete_lines(&path, offset)).await??;
+        self.offset += appended.len() as u64;
+        self.total_usd += appended
+            .split(|&byte| byte == b'\n')
+            .filter_map(|line| serde_json::from_slice::<CostRecord>(line).ok())
+            .filter(|record| record.ts.with_timezone(&Local).date_naive() == today)
+            .map(|record| record.cost_usd)
+            .sum::<f64>();
+        Ok(self.total_usd)
+    }
+}
+
+/// The bytes of `path` past `offset` up to and including the last newline,
+/// so that a record still being appended is picked up by the next read.
+fn read_complete_lines(path: &Path, offset: u64) -> Result<Vec<u8>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    file.seek(SeekFrom::Start(offset))?;
+    let mut appended = Vec::new();
+    file.read_to_end(&mut appended)?;
+    let complete = appended
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |newline| newline + 1);
+    appended.truncate(complete);
+    Ok(appended)
+}
+
+/// Deletes the ledger files of days that fell out of the retention window.
+async fn prune_cost_ledger(codex_home: &Path, today: NaiveDate) -> Result<()> {
+    let Some(oldest_kept) =
+        today.checked_sub_days(chrono::Days::new(COST_LEDGER_RETENTION_DAYS - 1))
+    else {
+        return Ok(());
+    };
+    let mut entries = match tokio::fs::read_dir(codex_home.join(COST_LEDGER_DIRNAME)).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name();
+        let Some(day) = file_name
+            .to_str()
+            .and_then(|name| name.strip_suffix(".jsonl"))
+            .and_then(|stem| NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        if day < oldest_kept {
+            tokio::fs::remove_file(entry.path()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Budgets that `session_usd` and `daily_usd` have reached, hard limits
+/// first.
+pub(crate) fn exceeded_budgets(
+    budget: &Budget,
+    session_usd: f64,
+    daily_usd: f64,
+) -> Vec<BudgetExceededEvent> {
+    let limits = [
+        (
+            BudgetScope::Session,
+            BudgetKind::Hard,
+            budget.session_hard_usd,
+        ),
+        (BudgetScope::Day, BudgetKind::Hard, budget.daily_hard_usd),
+        (
+            BudgetScope::Session,
+            BudgetKind::Soft,
+            budget.session_soft_usd,
+        ),
+        (BudgetScope::Day, BudgetKind::Soft, budget.daily_soft_usd),
+    ];
+    limits
+        .into_iter()
+        .filter_map(|(scope, kind, limit_usd)| {
+            let limit_usd = limit_usd?;
+            let spent_usd = match scope {
+                BudgetScope::Session => session_usd,
+                BudgetScope::Day => daily_usd,
+            };
+            (spent_usd >= limit_usd).then_some(BudgetExceededEvent {
+                scope,
+                kind,
+                limit_usd,
+                spent_usd,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn cost_bills_cached_input_at_its_own_price() {
+        let pricing = ModelPricing {
+            input: 1.25,
+            cached_input: Some(0.125),
+            output: 10.0,
+        };
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            cached_input_tokens: 400_000,
+            output_tokens: 100_000,
+            reasoning_output_tokens: 50_000,
+            total_tokens: 1_100_000,
+        };
+        // 0.6M * 1.25 + 0.4M * 0.125 + 0.1M * 10
+        assert!((cost_usd(&pricing, &usage) - 1.8).abs() < 1e-9);
+
+        let uncached = ModelPricing {
+            cached_input: None,
+            ..pricing
+        };
+        // 1M * 1.25 + 0.1M * 10
+        assert!((cost_usd(&uncached, &usage) - 2.25).abs() < 1e-9);
+    }
+
+    fn record(ts: DateTime<Local>, cost_usd: f64) -> CostRecord {
+        CostRecord {
+            ts: ts.with_timezone(&Utc),
+            conversation_id: ConversationId::new(),
+            model: "gpt-5".to_string(),
+            cost_usd,
+        }
+    }
+
+    #[tokio::test]
+    async fn daily_cost_only_counts_today() {
+        let codex_home = TempDir::new().unwrap();
+        let now = Local.with_ymd_and_hms(2025, 3, 10, 15, 0, 0).unwrap();
+        for (ts, cost_usd) in [
+            (now - chrono::Duration::days(1), 5.0),
+            (now - chrono::Duration::hours(2), 1.5),
+            (now, 0.25),
+        ] {
+            record_cost(codex_home.path(), &record(ts, cost_usd))
+                .await
+                .unwrap();
+        }
+
+        let mut daily = DailyCost::default();
+        let total = daily.total_usd(codex_home.path(), now).await.unwrap();
+        assert!((total - 1.75).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn daily_cost_reads_only_appended_records() {
+        let codex_home = TempDir::new().unwrap();
+        let now = Local.with_ymd_and_hms(2025, 3, 10, 15, 0, 0).unwrap();
+        let path = cost_ledger_path(codex_home.path(), now.date_naive());
+        let mut daily = DailyCost::default();
+
+        record_cost(codex_home.path(), &record(now, 1.0))
+            .await
+            .unwrap();
+        assert!((daily.total_usd(codex_home.path(), now).await.unwrap() - 1.0).abs() < 1e-9);
+
+        // A record that is still being written is left for the next read.
+        let mut partial = serde_json::to_string(&record(now, 2.0)).unwrap();
+        partial.push('\n');
+        let (head, tail) = partial.split_at(10);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(head.as_bytes()).unwrap();
+        assert!((daily.total_usd(codex_home.path(), now).await.unwrap() - 1.0).abs() < 1e-9);
+        file.write_all(tail.as_bytes()).unwrap();
+        assert!((daily.total_usd(codex_home.path(), now).await.unwrap() - 3.0).abs() < 1e-9);
+
+        // The total starts over at midnight.
+        let tomorrow = now + chrono::Duration::days(1);
+        assert_eq!(
+            daily.total_usd(codex_home.path(), tomorrow).await.unwrap(),
+            0.0
+        );
+    }
+
+    #[tokio::test]
+    async fn new_day_prunes_ledger_files_past_retention() {
+        let codex_home = TempDir::new().unwrap();
+        let now = Local.with_ymd_and_hms(2025, 3, 31, 15, 0, 0).unwrap();
+        let kept = now - chrono::Duration::days(29);
+        let pruned = now - chrono::Duration::days(30);
+        for ts in [kept, pruned] {
+            record_cost(codex_home.path(), &record(ts, 1.0))
+                .await
+                .unwrap();
+        }
+
+        DailyCost::default()
+            .total_usd(codex_home.path(), now)
+            .await
+            .unwrap();
+        assert!(cost_ledger_path(codex_home.path(), kept.date_naive()).exists());
+        assert!(!cost_ledger_path(codex_home.path(), pruned.date_naive()).exists());
+    }
+
+    #[test]
+    fn exceeded_budgets_lists_hard_limits_first() {
+        let budget = Budget {
+            session_soft_usd: Some(1.0),
+            session_hard_usd: Some(5.0),
+            daily_soft_usd: Some(10.0),
+            daily_hard_usd: Some(20.0),
+        };
+        assert_eq!(exceeded_budgets(&budget, 0.5, 0.5), vec![]);
+
+        let scopes = |session_usd, daily_usd| {
+            exceeded_budgets(&budget, session_usd, daily_usd)
+                .into_iter()
+                .map(|event| (event.scope, event.kind))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            scopes(1.0, 12.0),
+            vec![
+                (BudgetScope::Session, BudgetKind::Soft),
+                (BudgetScope::Day, BudgetKind::Soft),
+            ]
+        );
+        assert_eq!(
+            scopes(6.0, 25.0),
+            vec![
+                (BudgetScope::Session, BudgetKind::Hard),
+                (BudgetScope::Day, BudgetKind::Hard),
+                (BudgetScope::Session, BudgetKind::Soft),
+                (BudgetScope::Day, BudgetKind::Soft),
+            ]
+        );
+    }
+}
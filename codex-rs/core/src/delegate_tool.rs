@@ -0,0 +1,458 @@
+//! The `delegate` tool, which hands a focused task to a child conversation.
+//!
+//! The child starts from the parent's configuration with its own
+//! instructions, model, sandbox policy and token budget. Its sandbox can only
+//! be as permissive as the parent's and it cannot delegate further. It uses
+//! the parent's approval policy, and its approval requests are asked of the
+//! parent's user. Its events are forwarded to the parent nested under the
+//! tool call id, its usage is added to the parent's, and its final message
+//! becomes the tool output.
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use serde::Deserialize;
+
+use crate::AuthManager;
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config::Config;
+use crate::conversation_manager::ConversationManager;
+use crate::conversation_manager::NewConversation;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_family_for_model;
+use crate::openai_model_info::get_model_info;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::DelegateBeginEvent;
+use crate::protocol::DelegateEndEvent;
+use crate::protocol::DelegateNestedEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::InputItem;
+use crate::protocol::Op;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::TokenUsage;
+use crate::protocol::TokenUsageInfo;
+use crate::protocol_config_types::SandboxMode;
+use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::models::ResponseInputItem;
+
+pub(crate) const DELEGATE_TOOL_NAME: &str = "delegate";
+
+pub(crate) static DELEGATE_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "task".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The task for the helper, with everything it needs to know to complete it."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "instructions".to_string(),
+        JsonSchema::String {
+            description: Some("Additional instructions for the helper.".to_string()),
+        },
+    );
+    properties.insert(
+        "model".to_string(),
+        JsonSchema::String {
+            description: Some("Model for the helper. Defaults to the current model.".to_string()),
+        },
+    );
+    properties.insert(
+        "sandbox".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "One of: read-only, workspace-write, danger-full-access. Cannot exceed the current sandbox, which is also the default."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "token_budget".to_string(),
+        JsonSchema::Number {
+            description: Some("Total tokens the helper may use before it is stopped.".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: DELEGATE_TOOL_NAME.to_string(),
+        description: r#"Hands a focused task to a helper agent that works in the same directory and returns its final message.
+Use it for self-contained work, such as investigating a failing test, whose intermediate steps you do not need to see.
+"#
+        .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["task".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+#[derive(Debug, Deserialize)]
+struct DelegateArgs {
+    task: String,
+    #[serde(default)]
+    instructions: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    sandbox: Option<SandboxMode>,
+    #[serde(default)]
+    token_budget: Option<u64>,
+}
+
+/// Runs the delegated task to completion and returns the child's final
+/// message as the output of the `delegate` call.
+pub(crate) async fn handle_delegate(
+    sess: &Session,
+    turn_context: &TurnContext,
+    arguments: String,
+    sub_id: String,
+    call_id: String,
+) -> ResponseInputItem {
+    let args = match serde_json::from_str::<DelegateArgs>(&arguments) {
+        Ok(args) => args,
+        Err(e) => {
+            return failure(call_id, format!("failed to parse function arguments: {e}"));
+        }
+    };
+
+    let config = child_config(turn_context, &args);
+    let model = config.model.clone();
+    let auth_manager = turn_context
+        .client
+        .get_auth_manager()
+        .unwrap_or_else(|| AuthManager::shared(config.codex_home.clone()));
+    let conversation_manager = ConversationManager::new(auth_manager);
+    let NewConversation {
+        conversation: child,
+        ..
+    } = match conversation_manager.new_conversation(config).await {
+        Ok(conversation) => conversation,
+        Err(e) => return failure(call_id, format!("failed to start delegate: {e}")),
+    };
+
+    sess.send_event(Event {
+        id: sub_id.clone(),
+        msg: EventMsg::DelegateBegin(DelegateBeginEvent {
+            call_id: call_id.clone(),
+            task: args.task.clone(),
+            model,
+        }),
+    })
+    .await;
+
+    let outcome = run_child(sess, turn_context, &child, &args, &sub_id, &call_id).await;
+    let _ = child.submit(Op::Shutdown).await;
+
+    sess.send_event(Event {
+        id: sub_id,
+        msg: EventMsg::DelegateEnd(DelegateEndEvent {
+            call_id: call_id.clone(),
+            last_agent_message: outcome.last_agent_message.clone(),
+            success: outcome.error.is_none(),
+        }),
+    })
+    .await;
+
+    let message = outcome
+        .last_agent_message
+        .unwrap_or_else(|| "(the delegate returned no message)".to_string());
+    let (content, success) = match outcome.error {
+        Some(error) => (format!("{error}\n\n{message}"), false),
+        None => (message, true),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+struct DelegateOutcome {
+    last_agent_message: Option<String>,
+    /// Why the child did not complete its task normally, if it did not.
+    error: Option<String>,
+}
+
+/// Submits the task and forwards the child's events until its task ends,
+/// interrupting it once it spends its token budget.
+async fn run_child(
+    sess: &Session,
+    turn_context: &TurnContext,
+    child: &crate::CodexConversation,
+    args: &DelegateArgs,
+    sub_id: &str,
+    call_id: &str,
+) -> DelegateOutcome {
+    let mut outcome = DelegateOutcome {
+        last_agent_message: None,
+        error: None,
+    };
+    if let Err(e) = child
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: args.task.clone(),
+            }],
+        })
+        .await
+    {
+        outcome.error = Some(format!("failed to submit the task to the delegate: {e}"));
+        return outcome;
+    }
+
+    let mut charged = ChargedUsage::default();
+    loop {
+        let event = match child.next_event().await {
+            Ok(event) => event,
+            Err(e) => {
+                outcome.error = Some(format!("delegate stopped unexpectedly: {e}"));
+                return outcome;
+            }
+        };
+        match &event.msg {
+            EventMsg::AgentMessage(message) => {
+                outcome.last_agent_message = Some(message.message.clone());
+            }
+            EventMsg::Error(error) => {
+                outcome.error = Some(format!("delegate failed: {}", error.message));
+            }
+            EventMsg::ExecApprovalRequest(request) => {
+                let (decision, source) = sess
+                    .request_command_approval(
+                        sub_id.to_string(),
+                        request.call_id.clone(),
+                        request.command.clone(),
+                        request.cwd.clone(),
+                        request.reason.clone(),
+                    )
+                    .await;
+                let _ = child
+                    .submit(Op::ExecApproval {
+                        id: event.id,
+                        decision,
+                        source,
+                    })
+                    .await;
+                continue;
+            }
+            EventMsg::ApplyPatchApprovalRequest(request) => {
+                let (decision, source) = sess
+                    .request_changes_approval(
+                        sub_id.to_string(),
+                        request.call_id.clone(),
+                        request.changes.clone(),
+                        request.reason.clone(),
+                        request.grant_root.clone(),
+                    )
+                    .await
+                    .await
+                    .unwrap_or_default();
+                let _ = child
+                    .submit(Op::PatchApproval {
+                        id: event.id,
+                        decision,
+                        source,
+                    })
+                    .await;
+                continue;
+            }
+            EventMsg::TokenCount(token_count) => {
+                if let Some(info) = &token_count.info {
+                    charged.charge(sess, turn_context, sub_id, info).await;
+                }
+                if let (Some(budget), Some(info)) = (args.token_budget, &token_count.info)
+                    && info.total_token_usage.total_tokens >= budget
+                    && outcome.error.is_none()
+                {
+                    outcome.error = Some(format!(
+                        "delegate stopped after exceeding its budget of {budget} tokens"
+                    ));
+                    let _ = child.submit(Op::Interrupt).await;
+                }
+            }
+            _ => {}
+        }
+        let done = match &event.msg {
+            EventMsg::TaskComplete(complete) => {
+                if complete.last_agent_message.is_some() {
+                    outcome
+                        .last_agent_message
+                        .clone_from(&complete.last_agent_message);
+                }
+                true
+            }
+            EventMsg::TurnAborted(_) => {
+                if outcome.error.is_none() {
+                    outcome.error = Some("delegate was interrupted".to_string());
+                }
+                true
+            }
+            _ => false,
+        };
+        sess.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::DelegateEvent(DelegateNestedEvent {
+                call_id: call_id.to_string(),
+                msg: Box::new(event.msg),
+            }),
+        })
+        .await;
+        if done {
+            return outcome;
+        }
+    }
+}
+
+/// The part of the child's usage already added to the parent's. The child
+/// reports running totals, and repeats them when only its rate limits change.
+#[derive(Default)]
+struct ChargedUsage {
+    token_usage: TokenUsage,
+    cost_usd: f64,
+}
+
+impl ChargedUsage {
+    /// Adds the usage in `info` that was not charged yet to the parent.
+    async fn charge(
+        &mut self,
+        sess: &Session,
+        turn_context: &TurnContext,
+        sub_id: &str,
+        info: &TokenUsageInfo,
+    ) {
+        let total = &info.total_token_usage;
+        let charged = &self.token_usage;
+        let token_usage = TokenUsage {
+            input_tokens: total.input_tokens.saturating_sub(charged.input_tokens),
+            cached_input_tokens: total
+                .cached_input_tokens
+                .saturating_sub(charged.cached_input_tokens),
+            output_tokens: total.output_tokens.saturating_sub(charged.output_tokens),
+            reasoning_output_tokens: total
+                .reasoning_output_tokens
+                .saturating_sub(charged.reasoning_output_tokens),
+            total_tokens: total.total_tokens.saturating_sub(charged.total_tokens),
+        };
+        let total_cost_usd = info.total_cost_usd.unwrap_or(0.0);
+        let cost_usd = (total_cost_usd - self.cost_usd).max(0.0);
+        if token_usage == TokenUsage::default() && cost_usd == 0.0 {
+            return;
+        }
+        sess.add_delegate_usage(sub_id, turn_context, &token_usage, cost_usd, info.estimated)
+            .await;
+        self.token_usage = total.clone();
+        self.cost_usd = total_cost_usd;
+    }
+}
+
+/// The parent's configuration, adjusted for a child that runs `args`.
+fn child_config(turn_context: &TurnContext, args: &DelegateArgs) -> Config {
+    let mut config = (*turn_context.client.get_config()).clone();
+    config.cwd = turn_context.cwd.clone();
+    config.base_instructions = turn_context.base_instructions.clone();
+    config.approval_policy = turn_context.approval_policy;
+    config.sandbox_policy = clamp_sandbox_policy(args.sandbox, &turn_context.sandbox_policy);
+    config.include_delegate_tool = false;
+    config.notify = None;
+
+    if let Some(instructions) = &args.instructions {
+        config.user_instructions = Some(match config.user_instructions.take() {
+            Some(existing) => format!("{existing}\n\n{instructions}"),
+            None => instructions.clone(),
+        });
+    }
+
+    if let Some(model) = &args.model
+        && *model != config.model
+    {
+        let model_family =
+            find_family_for_model(model).unwrap_or_else(|| derive_default_model_family(model));
+        let model_info = get_model_info(&model_family);
+        config.model_context_window = model_info.as_ref().map(|info| info.context_window);
+        config.model_max_output_tokens = model_info.as_ref().map(|info| info.max_output_tokens);
+        config.model_auto_compact_token_limit =
+            model_info.and_then(|info| info.auto_compact_token_limit);
+        config.model = model.clone();
+        config.model_family = model_family;
+    }
+    config
+}
+
+/// The sandbox policy for `requested`, which may not be more permissive than
+/// `ceiling`.
+fn clamp_sandbox_policy(requested: Option<SandboxMode>, ceiling: &SandboxPolicy) -> SandboxPolicy {
+    fn permissiveness(policy: &SandboxPolicy) -> u8 {
+        match policy {
+            SandboxPolicy::ReadOnly => 0,
+            SandboxPolicy::WorkspaceWrite { .. } => 1,
+            SandboxPolicy::DangerFullAccess => 2,
+        }
+    }
+
+    let requested = match requested {
+        None => return ceiling.clone(),
+        Some(SandboxMode::ReadOnly) => SandboxPolicy::new_read_only_policy(),
+        Some(SandboxMode::WorkspaceWrite) => SandboxPolicy::new_workspace_write_policy(),
+        Some(SandboxMode::DangerFullAccess) => SandboxPolicy::DangerFullAccess,
+    };
+    if permissiveness(&requested) < permissiveness(ceiling) {
+        requested
+    } else {
+        ceiling.clone()
+    }
+}
+
+fn failure(call_id: String, content: String) -> ResponseInputItem {
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(false),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sandbox_never_exceeds_the_parent() {
+        let workspace_write = SandboxPolicy::new_workspace_write_policy();
+
+        assert_eq!(
+            clamp_sandbox_policy(None, &workspace_write),
+            workspace_write
+        );
+        assert_eq!(
+            clamp_sandbox_policy(Some(SandboxMode::ReadOnly), &workspace_write),
+            SandboxPolicy::ReadOnly
+        );
+        assert_eq!(
+            clamp_sandbox_policy(Some(SandboxMode::DangerFullAccess), &workspace_write),
+            workspace_write
+        );
+        assert_eq!(
+            clamp_sandbox_policy(
+                Some(SandboxMode::WorkspaceWrite),
+                &SandboxPolicy::DangerFullAccess
+            ),
+            workspace_write
+        );
+        assert_eq!(
+            clamp_sandbox_policy(Some(SandboxMode::WorkspaceWrite), &SandboxPolicy::ReadOnly),
+            SandboxPolicy::ReadOnly
+        );
+    }
+}
@@ -6,12 +6,17 @@ use codex_protocol::protocol::RateLimitSnapshot;
 use reqwest::StatusCode;
 use serde_json;
 use std::io;
+use std::path::PathBuf;
 use std::time::Duration;
 use thiserror::Error;
 use tokio::task::JoinError;
 
 pub type Result<T> = std::result::Result<T, CodexErr>;
 
+/// Message of the [`CodexErr::Stream`] raised when a provider stops sending
+/// SSE events for longer than its `stream_idle_timeout_ms`.
+pub(crate) const STREAM_IDLE_TIMEOUT_MESSAGE: &str = "idle timeout waiting for SSE";
+
 #[derive(Error, Debug)]
 pub enum SandboxErr {
     /// Error from sandbox execution
@@ -108,6 +113,14 @@ pub enum CodexErr {
     #[error("unsupported operation: {0}")]
     UnsupportedOperation(String),
 
+    /// Returned in `response_cache = "replay"` mode when no transcript was
+    /// recorded for the prompt.
+    #[error(
+        "no recorded response at {}; record one with response_cache = \"record\" or \"auto\"",
+        .0.display()
+    )]
+    ResponseCacheMiss(PathBuf),
+
     // -----------------------------------------------------------------
     // Automatic conversions for common external error types
     // -----------------------------------------------------------------
@@ -250,6 +263,20 @@ impl CodexErr {
     pub fn downcast_ref<T: std::any::Any>(&self) -> Option<&T> {
         (self as &dyn std::any::Any).downcast_ref::<T>()
     }
+
+    /// Whether this error means the provider is rate limited or unavailable,
+    /// so that switching to a fallback model may help.
+    pub(crate) fn is_provider_unavailable(&self) -> bool {
+        match self {
+            CodexErr::RetryLimit(status) => {
+                *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }
+            CodexErr::InternalServerError => true,
+            CodexErr::Stream(message, _) => message == STREAM_IDLE_TIMEOUT_MESSAGE,
+            CodexErr::Reqwest(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        }
+    }
 }
 
 pub fn get_error_message_ui(e: &CodexErr) -> String {
@@ -6,24 +6,32 @@ use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::ExitStatus;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
 
 use async_channel::Sender;
+use serde::Deserialize;
+use serde::Serialize;
 use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
 use tokio::io::BufReader;
 use tokio::process::Child;
 
+use crate::config_types::LinuxSandboxBackend;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
+use crate::landlock::CODEX_SANDBOX_VIOLATION_REPORT_ENV_VAR;
 use crate::landlock::spawn_command_under_linux_sandbox;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::ExecCommandOutputDeltaEvent;
 use crate::protocol::ExecOutputStream;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SandboxViolation;
+use crate::redact::SecretRedactor;
+use crate::redact::StreamRedactor;
 use crate::seatbelt::spawn_command_under_seatbelt;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
@@ -61,7 +69,8 @@ impl ExecParams {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum SandboxType {
     None,
 
@@ -70,6 +79,10 @@ pub enum SandboxType {
 
     /// Only available on Linux.
     LinuxSeccomp,
+
+    /// Only available on Linux. Runs the command in fresh user, mount and PID
+    /// namespaces created by `codex-linux-sandbox`.
+    LinuxNamespaces,
 }
 
 #[derive(Clone)]
@@ -77,6 +90,9 @@ pub struct StdoutStream {
     pub sub_id: String,
     pub call_id: String,
     pub tx_event: Sender<Event>,
+    /// Redacts secrets from the streamed output, which is then emitted a
+    /// line at a time so that a secret straddling two reads is still caught.
+    pub redactor: Option<Arc<SecretRedactor>>,
 }
 
 pub async fn process_exec_tool_call(
@@ -90,6 +106,7 @@ pub async fn process_exec_tool_call(
     let start = Instant::now();
 
     let timeout_duration = params.timeout_duration();
+    let mut sandbox_violations = Vec::new();
 
     let raw_output_result: std::result::Result<RawExecToolCallOutput, CodexErr> = match sandbox_type
     {
@@ -112,19 +129,40 @@ pub async fn process_exec_tool_call(
             .await?;
             consume_truncated_output(child, timeout_duration, stdout_stream.clone()).await
         }
-        SandboxType::LinuxSeccomp => {
+        SandboxType::LinuxSeccomp | SandboxType::LinuxNamespaces => {
             let ExecParams {
                 command,
                 cwd: command_cwd,
-                env,
+                mut env,
                 ..
             } = params;
 
             let codex_linux_sandbox_exe = codex_linux_sandbox_exe
                 .as_ref()
                 .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
+
+            // Ask the Landlock helper to report what it blocked; the
+            // namespaces backend cannot. Failing to create the report file
+            // only costs us the report.
+            let violation_report = if sandbox_type == SandboxType::LinuxSeccomp {
+                tempfile::NamedTempFile::new().ok()
+            } else {
+                None
+            };
+            if let Some(report) = &violation_report {
+                env.insert(
+                    CODEX_SANDBOX_VIOLATION_REPORT_ENV_VAR.to_string(),
+                    report.path().to_string_lossy().into_owned(),
+                );
+            }
+
             let child = spawn_command_under_linux_sandbox(
                 codex_linux_sandbox_exe,
+                if sandbox_type == SandboxType::LinuxNamespaces {
+                    LinuxSandboxBackend::Namespaces
+                } else {
+                    LinuxSandboxBackend::Landlock
+                },
                 command,
                 command_cwd,
                 sandbox_policy,
@@ -134,7 +172,11 @@ pub async fn process_exec_tool_call(
             )
             .await?;
 
-            consume_truncated_output(child, timeout_duration, stdout_stream).await
+            let output = consume_truncated_output(child, timeout_duration, stdout_stream).await;
+            if let Some(report) = violation_report {
+                sandbox_violations = read_sandbox_violation_report(report.path());
+            }
+            output
         }
     };
     let duration = start.elapsed();
@@ -169,6 +211,7 @@ pub async fn process_exec_tool_call(
                 aggregated_output,
                 duration,
                 timed_out,
+                sandbox_violations,
             };
 
             if timed_out {
@@ -177,7 +220,10 @@ pub async fn process_exec_tool_call(
                 }));
             }
 
-            if exit_code != 0 && is_likely_sandbox_denied(sandbox_type, exit_code) {
+            if exit_code != 0
+                && (!exec_output.sandbox_violations.is_empty()
+                    || is_likely_sandbox_denied(sandbox_type, exit_code))
+            {
                 return Err(CodexErr::Sandbox(SandboxErr::Denied {
                     output: Box::new(exec_output),
                 }));
@@ -212,6 +258,21 @@ fn is_likely_sandbox_denied(sandbox_type: SandboxType, exit_code: i32) -> bool {
     true
 }
 
+/// Reads the list of [`SandboxViolation`]s written by `codex-linux-sandbox`.
+/// A missing or malformed report (e.g., because the helper could not monitor
+/// the command on this kernel) is treated as an empty one.
+fn read_sandbox_violation_report(path: &Path) -> Vec<SandboxViolation> {
+    match std::fs::read(path) {
+        Ok(contents) if !contents.is_empty() => {
+            serde_json::from_slice(&contents).unwrap_or_else(|e| {
+                tracing::warn!("failed to parse sandbox violation report: {e}");
+                Vec::new()
+            })
+        }
+        _ => Vec::new(),
+    }
+}
+
 #[derive(Debug)]
 pub struct StreamOutput<T> {
     pub text: T,
@@ -257,6 +318,9 @@ pub struct ExecToolCallOutput {
     pub aggregated_output: StreamOutput<String>,
     pub duration: Duration,
     pub timed_out: bool,
+    /// Operations that the sandbox reported as blocked, if the sandbox in use
+    /// supports reporting them.
+    pub sandbox_violations: Vec<SandboxViolation>,
 }
 
 async fn exec(
@@ -379,6 +443,10 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     let mut buf = Vec::with_capacity(AGGREGATE_BUFFER_INITIAL_CAPACITY);
     let mut tmp = [0u8; READ_CHUNK_SIZE];
     let mut emitted_deltas: usize = 0;
+    let mut stream_redactor = stream
+        .as_ref()
+        .and_then(|stream| stream.redactor.as_deref())
+        .map(StreamRedactor::new);
 
     // No caps: append all bytes
 
@@ -391,23 +459,14 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
         if let Some(stream) = &stream
             && emitted_deltas < MAX_EXEC_OUTPUT_DELTAS_PER_CALL
         {
-            let chunk = tmp[..n].to_vec();
-            let msg = EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
-                call_id: stream.call_id.clone(),
-                stream: if is_stderr {
-                    ExecOutputStream::Stderr
-                } else {
-                    ExecOutputStream::Stdout
-                },
-                chunk,
-            });
-            let event = Event {
-                id: stream.sub_id.clone(),
-                msg,
+            let chunk = match &mut stream_redactor {
+                Some(redactor) => redactor.push(&tmp[..n]),
+                None => tmp[..n].to_vec(),
             };
-            #[allow(clippy::let_unit_value)]
-            let _ = stream.tx_event.send(event).await;
-            emitted_deltas += 1;
+            if !chunk.is_empty() {
+                send_output_delta(stream, is_stderr, chunk).await;
+                emitted_deltas += 1;
+            }
         }
 
         if let Some(tx) = &aggregate_tx {
@@ -418,12 +477,39 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
         // Continue reading to EOF to avoid back-pressure
     }
 
+    if let (Some(stream), Some(redactor)) = (&stream, stream_redactor)
+        && emitted_deltas < MAX_EXEC_OUTPUT_DELTAS_PER_CALL
+    {
+        let chunk = redactor.finish();
+        if !chunk.is_empty() {
+            send_output_delta(stream, is_stderr, chunk).await;
+        }
+    }
+
     Ok(StreamOutput {
         text: buf,
         truncated_after_lines: None,
     })
 }
 
+async fn send_output_delta(stream: &StdoutStream, is_stderr: bool, chunk: Vec<u8>) {
+    let msg = EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
+        call_id: stream.call_id.clone(),
+        stream: if is_stderr {
+            ExecOutputStream::Stderr
+        } else {
+            ExecOutputStream::Stdout
+        },
+        chunk,
+    });
+    let event = Event {
+        id: stream.sub_id.clone(),
+        msg,
+    };
+    #[allow(clippy::let_unit_value)]
+    let _ = stream.tx_event.send(event).await;
+}
+
 #[cfg(unix)]
 fn synthetic_exit_status(code: i32) -> ExitStatus {
     use std::os::unix::process::ExitStatusExt;
@@ -11,5 +11,6 @@ pub use responses_api::EXEC_COMMAND_TOOL_NAME;
 pub use responses_api::WRITE_STDIN_TOOL_NAME;
 pub use responses_api::create_exec_command_tool_for_responses_api;
 pub use responses_api::create_write_stdin_tool_for_responses_api;
+pub(crate) use session_manager::ExecCommandOutput;
 pub use session_manager::SessionManager as ExecSessionManager;
 pub use session_manager::result_into_payload;
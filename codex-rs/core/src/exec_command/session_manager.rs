@@ -20,6 +20,7 @@ use crate::exec_command::exec_command_params::ExecCommandParams;
 use crate::exec_command::exec_command_params::WriteStdinParams;
 use crate::exec_command::exec_command_session::ExecCommandSession;
 use crate::exec_command::session_id::SessionId;
+use crate::tokenizer::Tokenizer;
 use crate::truncate::truncate_middle;
 use codex_protocol::models::FunctionCallOutputPayload;
 
@@ -38,6 +39,14 @@ pub struct ExecCommandOutput {
 }
 
 impl ExecCommandOutput {
+    /// The exit code of the process, or `None` if it is still running.
+    pub(crate) fn exit_code(&self) -> Option<i32> {
+        match self.exit_status {
+            ExitStatus::Exited(code) => Some(code),
+            ExitStatus::Ongoing(_) => None,
+        }
+    }
+
     fn to_text_output(&self) -> String {
         let wall_time_secs = self.wall_time.as_secs_f32();
         let termination_status = match self.exit_status {
@@ -83,9 +92,11 @@ pub fn result_into_payload(result: Result<ExecCommandOutput, String>) -> Functio
 
 impl SessionManager {
     /// Processes the request and is required to send a response via `outgoing`.
+    /// `tokenizer` counts tokens against `max_output_tokens`.
     pub async fn handle_exec_command_request(
         &self,
         params: ExecCommandParams,
+        tokenizer: Tokenizer,
     ) -> Result<ExecCommandOutput, String> {
         // Allocate a session id.
         let session_id = SessionId(
@@ -108,8 +119,6 @@ impl SessionManager {
         // Collect output until either timeout expires or process exits.
         // Do not cap during collection; truncate at the end if needed.
         // Use a modest initial capacity to avoid large preallocation.
-        let cap_bytes_u64 = params.max_output_tokens.saturating_mul(4);
-        let cap_bytes: usize = cap_bytes_u64.min(usize::MAX as u64) as usize;
         let mut collected: Vec<u8> = Vec::with_capacity(4096);
 
         let start_time = Instant::now();
@@ -166,7 +175,8 @@ impl SessionManager {
         };
 
         // If output exceeds cap, truncate the middle and record original token estimate.
-        let (output, original_token_count) = truncate_middle(&output, cap_bytes);
+        let (output, original_token_count) =
+            truncate_middle(&output, params.max_output_tokens, tokenizer);
         Ok(ExecCommandOutput {
             wall_time: Instant::now().duration_since(start_time),
             exit_status,
@@ -179,6 +189,7 @@ impl SessionManager {
     pub async fn handle_write_stdin_request(
         &self,
         params: WriteStdinParams,
+        tokenizer: Tokenizer,
     ) -> Result<ExecCommandOutput, String> {
         let WriteStdinParams {
             session_id,
@@ -203,7 +214,7 @@ impl SessionManager {
             return Err("failed to write to stdin".to_string());
         }
 
-        // Collect output up to yield_time_ms, truncating to max_output_tokens bytes.
+        // Collect output up to yield_time_ms, truncating to max_output_tokens tokens.
         let mut collected: Vec<u8> = Vec::with_capacity(4096);
         let start_time = Instant::now();
         let deadline = start_time + Duration::from_millis(yield_time_ms);
@@ -228,9 +239,7 @@ impl SessionManager {
 
         // Return structured output, truncating middle if over cap.
         let output = String::from_utf8_lossy(&collected).to_string();
-        let cap_bytes_u64 = max_output_tokens.saturating_mul(4);
-        let cap_bytes: usize = cap_bytes_u64.min(usize::MAX as u64) as usize;
-        let (output, original_token_count) = truncate_middle(&output, cap_bytes);
+        let (output, original_token_count) = truncate_middle(&output, max_output_tokens, tokenizer);
         Ok(ExecCommandOutput {
             wall_time: Instant::now().duration_since(start_time),
             exit_status: ExitStatus::Ongoing(session_id),
@@ -394,7 +403,7 @@ PY"#
             login: false,
         };
         let initial_output = match session_manager
-            .handle_exec_command_request(params.clone())
+            .handle_exec_command_request(params.clone(), Tokenizer::default())
             .await
         {
             Ok(v) => v,
@@ -432,17 +441,16 @@ PY"#
             session_id,
             chars: String::new(),
             yield_time_ms: 3_000,
-            max_output_tokens: 16, // 16 tokens ~= 64 bytes -> likely truncation
+            max_output_tokens: 32, // ~3s of counter output is roughly 90 tokens
         };
         let second = session_manager
-            .handle_write_stdin_request(write_params)
+            .handle_write_stdin_request(write_params, Tokenizer::default())
             .await
             .expect("write stdin should succeed");
 
-        // Verify truncation metadata and size bound (cap is tokens*4 bytes).
+        // Verify truncation metadata and size bound.
         assert!(second.original_token_count.is_some());
-        let cap_bytes = (16u64 * 4) as usize;
-        assert!(second.output.len() <= cap_bytes);
+        assert!(Tokenizer::default().count(&second.output) <= 32);
         // New middle marker should be present.
         assert!(
             second.output.contains("tokens truncated") && second.output.contains('…'),
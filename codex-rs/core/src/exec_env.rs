@@ -15,6 +15,44 @@ pub fn create_env(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
     populate_env(std::env::vars(), policy)
 }
 
+/// Returns the `(name, value)` pairs of the variables in the current process
+/// environment that `policy` excludes by name (the default `*KEY*`,
+/// `*SECRET*`, and `*TOKEN*` patterns and any custom `exclude` patterns).
+/// These are the likely secrets that commands must not see, so their values
+/// are also redacted from command output.
+pub fn excluded_env_vars(policy: &ShellEnvironmentPolicy) -> Vec<(String, String)> {
+    find_excluded_vars(std::env::vars(), policy)
+}
+
+fn find_excluded_vars<I>(vars: I, policy: &ShellEnvironmentPolicy) -> Vec<(String, String)>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let default_excludes = if policy.ignore_default_excludes {
+        Vec::new()
+    } else {
+        default_excludes()
+    };
+    vars.into_iter()
+        .filter(|(k, _)| {
+            default_excludes
+                .iter()
+                .chain(&policy.exclude)
+                .any(|pattern| pattern.matches(k))
+        })
+        // Variables the user explicitly sets are meant to be visible.
+        .filter(|(k, _)| !policy.r#set.contains_key(k))
+        .collect()
+}
+
+fn default_excludes() -> Vec<EnvironmentVariablePattern> {
+    vec![
+        EnvironmentVariablePattern::new_case_insensitive("*KEY*"),
+        EnvironmentVariablePattern::new_case_insensitive("*SECRET*"),
+        EnvironmentVariablePattern::new_case_insensitive("*TOKEN*"),
+    ]
+}
+
 fn populate_env<I>(vars: I, policy: &ShellEnvironmentPolicy) -> HashMap<String, String>
 where
     I: IntoIterator<Item = (String, String)>,
@@ -42,12 +80,7 @@ where
 
     // Step 2 – Apply the default exclude if not disabled.
     if !policy.ignore_default_excludes {
-        let default_excludes = vec![
-            EnvironmentVariablePattern::new_case_insensitive("*KEY*"),
-            EnvironmentVariablePattern::new_case_insensitive("*SECRET*"),
-            EnvironmentVariablePattern::new_case_insensitive("*TOKEN*"),
-        ];
-        env_map.retain(|k, _| !matches_any(k, &default_excludes));
+        env_map.retain(|k, _| !matches_any(k, &default_excludes()));
     }
 
     // Step 3 – Apply custom excludes.
@@ -191,4 +224,30 @@ mod tests {
         };
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_excluded_vars() {
+        let vars = make_vars(&[
+            ("PATH", "/usr/bin"),
+            ("API_KEY", "secret"),
+            ("DB_PASSWORD", "hunter2"),
+            ("GITHUB_TOKEN", "ghp_x"),
+        ]);
+
+        let mut policy = ShellEnvironmentPolicy {
+            exclude: vec![EnvironmentVariablePattern::new_case_insensitive(
+                "*PASSWORD",
+            )],
+            ..Default::default()
+        };
+        policy
+            .r#set
+            .insert("GITHUB_TOKEN".to_string(), "ghp_x".to_string());
+
+        let result = find_excluded_vars(vars, &policy);
+        assert_eq!(
+            result,
+            make_vars(&[("API_KEY", "secret"), ("DB_PASSWORD", "hunter2")])
+        );
+    }
 }
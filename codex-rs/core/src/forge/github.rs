@@ -0,0 +1,226 @@
+//! GitHub REST API: <https://docs.github.com/en/rest/pulls>.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::Forge;
+use super::ForgeError;
+use super::MAX_PAGES;
+use super::NewPullRequest;
+use super::PullRequest;
+use super::ReviewComment;
+use super::check_response;
+use crate::default_client::create_client;
+
+/// Environment variable holding the token when `forge.token_env_var` is
+/// not set.
+pub(super) const TOKEN_ENV_VAR: &str = "GITHUB_TOKEN";
+/// Largest page GitHub returns; further pages are followed through the
+/// `Link` header.
+const PAGE_SIZE: u32 = 100;
+
+/// A repository on github.com or GitHub Enterprise.
+pub struct GitHub {
+    client: reqwest::Client,
+    api_base_url: String,
+    /// `owner/name`.
+    repo: String,
+    token_env_var: String,
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    number: u64,
+    html_url: String,
+    title: String,
+}
+
+impl From<PullResponse> for PullRequest {
+    fn from(value: PullResponse) -> Self {
+        Self {
+            number: value.number,
+            url: value.html_url,
+            title: value.title,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct CommentResponse {
+    user: User,
+    body: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    line: Option<u64>,
+    #[serde(default)]
+    original_line: Option<u64>,
+    created_at: String,
+}
+
+impl GitHub {
+    pub fn new(api_base_url: String, repo: String, token_env_var: String) -> Self {
+        Self {
+            client: create_client(),
+            api_base_url: api_base_url.trim_end_matches('/').to_string(),
+            repo,
+            token_env_var,
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/repos/{}/{path}", self.api_base_url, self.repo);
+        self.request_url(method, url)
+    }
+
+    fn request_url(&self, method: reqwest::Method, url: String) -> reqwest::RequestBuilder {
+        let builder = self
+            .client
+            .request(method, url)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        match std::env::var(&self.token_env_var) {
+            Ok(token) if !token.is_empty() => builder.bearer_auth(token),
+            _ => builder,
+        }
+    }
+
+    /// Every page of the comments listed at `path`.
+    async fn comments(&self, path: &str) -> Result<Vec<CommentResponse>, ForgeError> {
+        let mut request = self
+            .request(reqwest::Method::GET, path)
+            .query(&[("per_page", PAGE_SIZE)]);
+        let mut comments = Vec::new();
+        for _ in 0..MAX_PAGES {
+            let response = request.send().await?;
+            let response = check_response("GET", response, &self.token_env_var).await?;
+            let next = next_page_url(response.headers())
+                // The token is only ever sent to the configured API.
+                .filter(|url| url.starts_with(&self.api_base_url));
+            comments.extend(response.json::<Vec<CommentResponse>>().await?);
+            match next {
+                Some(url) => request = self.request_url(reqwest::Method::GET, url),
+                None => break,
+            }
+        }
+        Ok(comments)
+    }
+
+    fn owner(&self) -> &str {
+        self.repo
+            .split_once('/')
+            .map_or(self.repo.as_str(), |(owner, _)| owner)
+    }
+}
+
+#[async_trait]
+impl Forge for GitHub {
+    async fn open_pull_request(&self, request: &NewPullRequest) -> Result<PullRequest, ForgeError> {
+        let response = self
+            .request(reqwest::Method::POST, "pulls")
+            .json(&json!({
+                "title": request.title,
+                "body": request.body,
+                "head": request.head,
+                "base": request.base,
+                "draft": request.draft,
+            }))
+            .send()
+            .await?;
+        let response = check_response("POST", response, &self.token_env_var).await?;
+        Ok(response.json::<PullResponse>().await?.into())
+    }
+
+    async fn pull_request(&self, number: u64) -> Result<PullRequest, ForgeError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("pulls/{number}"))
+            .send()
+            .await?;
+        let response = check_response("GET", response, &self.token_env_var).await?;
+        Ok(response.json::<PullResponse>().await?.into())
+    }
+
+    async fn find_pull_request(&self, head: &str) -> Result<Option<PullRequest>, ForgeError> {
+        let response = self
+            .request(reqwest::Method::GET, "pulls")
+            .query(&[
+                ("head", format!("{}:{head}", self.owner())),
+                ("state", "open".to_string()),
+            ])
+            .send()
+            .await?;
+        let response = check_response("GET", response, &self.token_env_var).await?;
+        let pulls: Vec<PullResponse> = response.json().await?;
+        Ok(pulls.into_iter().next().map(PullRequest::from))
+    }
+
+    async fn review_comments(&self, number: u64) -> Result<Vec<ReviewComment>, ForgeError> {
+        // Line comments live on the pull request, conversation comments on
+        // the issue behind it.
+        let mut comments = self.comments(&format!("pulls/{number}/comments")).await?;
+        comments.extend(self.comments(&format!("issues/{number}/comments")).await?);
+        // Timestamps are RFC 3339 in UTC, so they sort as strings.
+        comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(comments
+            .into_iter()
+            .map(|comment| ReviewComment {
+                author: comment.user.login,
+                body: comment.body,
+                path: comment.path,
+                line: comment.line.or(comment.original_line),
+            })
+            .collect())
+    }
+}
+
+/// The `rel="next"` URL of a `Link` header such as
+/// `<https://api.github.com/...&page=2>; rel="next", <...>; rel="last"`.
+fn next_page_url(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|entry| {
+        let (url, params) = entry.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use reqwest::header::HeaderMap;
+    use reqwest::header::HeaderValue;
+    use reqwest::header::LINK;
+
+    #[test]
+    fn finds_the_next_page_in_link_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(next_page_url(&headers), None);
+
+        headers.insert(
+            LINK,
+            HeaderValue::from_static(
+                "<https://api.github.com/repositories/1/pulls/2/comments?page=1>; rel=\"prev\", \
+                 <https://api.github.com/repositories/1/pulls/2/comments?page=3>; rel=\"next\", \
+                 <https://api.github.com/repositories/1/pulls/2/comments?page=5>; rel=\"last\"",
+            ),
+        );
+        assert_eq!(
+            next_page_url(&headers).as_deref(),
+            Some("https://api.github.com/repositories/1/pulls/2/comments?page=3")
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! GitLab REST API: <https://docs.gitlab.com/ee/api/merge_requests.html>.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::Forge;
+use super::ForgeError;
+use super::MAX_PAGES;
+use super::NewPullRequest;
+use super::PullRequest;
+use super::ReviewComment;
+use super::check_response;
+use crate::default_client::create_client;
+
+/// Environment variable holding the token when `forge.token_env_var` is
+/// not set.
+pub(super) const TOKEN_ENV_VAR: &str = "GITLAB_TOKEN";
+/// Largest page GitLab returns; further pages are read as long as the
+/// `X-Next-Page` header names one.
+const PAGE_SIZE: u32 = 100;
+
+/// A project on gitlab.com or a self-hosted GitLab.
+pub struct GitLab {
+    client: reqwest::Client,
+    api_base_url: String,
+    /// `group/subgroup/name`.
+    project: String,
+    token_env_var: String,
+}
+
+#[derive(Deserialize)]
+struct MergeRequestResponse {
+    iid: u64,
+    web_url: String,
+    title: String,
+}
+
+impl From<MergeRequestResponse> for PullRequest {
+    fn from(value: MergeRequestResponse) -> Self {
+        Self {
+            number: value.iid,
+            url: value.web_url,
+            title: value.title,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Author {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct Position {
+    #[serde(default)]
+    new_path: Option<String>,
+    #[serde(default)]
+    new_line: Option<u64>,
+    #[serde(default)]
+    old_line: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct NoteResponse {
+    author: Author,
+    body: String,
+    #[serde(default)]
+    system: bool,
+    #[serde(default)]
+    position: Option<Position>,
+}
+
+impl GitLab {
+    pub fn new(api_base_url: String, project: String, token_env_var: String) -> Self {
+        Self {
+            client: create_client(),
+            api_base_url: api_base_url.trim_end_matches('/').to_string(),
+            project,
+            token_env_var,
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        // The project path is a single, encoded path segment.
+        let project = self.project.replace('/', "%2F");
+        let url = format!("{}/projects/{project}/{path}", self.api_base_url);
+        let builder = self.client.request(method, url);
+        match std::env::var(&self.token_env_var) {
+            Ok(token) if !token.is_empty() => builder.header("PRIVATE-TOKEN", token),
+            _ => builder,
+        }
+    }
+}
+
+#[async_trait]
+impl Forge for GitLab {
+    async fn open_pull_request(&self, request: &NewPullRequest) -> Result<PullRequest, ForgeError> {
+        // GitLab marks drafts by their title.
+        let title = if request.draft {
+            format!("Draft: {}", request.title)
+        } else {
+            request.title.clone()
+        };
+        let response = self
+            .request(reqwest::Method::POST, "merge_requests")
+            .json(&json!({
+                "title": title,
+                "description": request.body,
+                "source_branch": request.head,
+                "target_branch": request.base,
+            }))
+            .send()
+            .await?;
+        let response = check_response("POST", response, &self.token_env_var).await?;
+        Ok(response.json::<MergeRequestResponse>().await?.into())
+    }
+
+    async fn pull_request(&self, number: u64) -> Result<PullRequest, ForgeError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("merge_requests/{number}"))
+            .send()
+            .await?;
+        let response = check_response("GET", response, &self.token_env_var).await?;
+        Ok(response.json::<MergeRequestResponse>().await?.into())
+    }
+
+    async fn find_pull_request(&self, head: &str) -> Result<Option<PullRequest>, ForgeError> {
+        let response = self
+            .request(reqwest::Method::GET, "merge_requests")
+            .query(&[("source_branch", head), ("state", "opened")])
+            .send()
+            .await?;
+        let response = check_response("GET", response, &self.token_env_var).await?;
+        let merge_requests: Vec<MergeRequestResponse> = response.json().await?;
+        Ok(merge_requests.into_iter().next().map(PullRequest::from))
+    }
+
+    async fn review_comments(&self, number: u64) -> Result<Vec<ReviewComment>, ForgeError> {
+        let mut notes: Vec<NoteResponse> = Vec::new();
+        let mut page = "1".to_string();
+        for _ in 0..MAX_PAGES {
+            let response = self
+                .request(
+                    reqwest::Method::GET,
+                    &format!("merge_requests/{number}/notes"),
+                )
+                .query(&[
+                    ("sort", "asc".to_string()),
+                    ("order_by", "created_at".to_string()),
+                    ("per_page", PAGE_SIZE.to_string()),
+                    ("page", page),
+                ])
+                .send()
+                .await?;
+            let response = check_response("GET", response, &self.token_env_var).await?;
+            // Empty on the last page.
+            let next_page = response
+                .headers()
+                .get("x-next-page")
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string);
+            notes.extend(response.json::<Vec<NoteResponse>>().await?);
+            match next_page {
+                Some(next_page) => page = next_page,
+                None => break,
+            }
+        }
+        Ok(notes
+            .into_iter()
+            .filter(|note| !note.system)
+            .map(|note| {
+                let (path, line) = match note.position {
+                    Some(position) => (position.new_path, position.new_line.or(position.old_line)),
+                    None => (None, None),
+                };
+                ReviewComment {
+                    author: note.author.username,
+                    body: note.body,
+                    path,
+                    line,
+                }
+            })
+            .collect())
+    }
+}
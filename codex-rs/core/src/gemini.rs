@@ -0,0 +1,714 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::ModelProviderInfo;
+use crate::client::read_sse_fixture;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::client_common::local_shell_call_arguments;
+use crate::client_common::split_base64_data_url;
+use crate::client_common::thinking_budget_tokens;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::STREAM_IDLE_TIMEOUT_MESSAGE;
+use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_gemini_api;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ResponseItem;
+
+/// Implementation for Google's Gemini API (`streamGenerateContent`).
+pub(crate) async fn stream_gemini(
+    prompt: &Prompt,
+    model: &str,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for Gemini API".to_string(),
+        ));
+    }
+
+    if let Some(path) = &*CODEX_RS_SSE_FIXTURE {
+        // short circuit for tests
+        warn!(path, "Streaming from fixture");
+        let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+        tokio::spawn(process_gemini_sse(
+            read_sse_fixture(path)?,
+            tx_event,
+            provider.stream_idle_timeout(),
+        ));
+        return Ok(ResponseStream { rx_event });
+    }
+
+    let payload = build_gemini_request(prompt, model_family, effort, max_output_tokens)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(&None, model),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let req_builder = provider
+            .create_request_builder(client, &None, model)
+            .await?;
+
+        let res = req_builder
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&payload)
+            .send()
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_gemini_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Builds the JSON body of a `streamGenerateContent` request.
+///
+/// Gemini attaches a `thoughtSignature` to the first part it emits after
+/// thinking. The parser stores it in the `encrypted_content` of a reasoning
+/// item, and here it is re-attached to the next part of the model turn, as
+/// required to keep the model's reasoning across function calls.
+pub(crate) fn build_gemini_request(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+) -> Result<Value> {
+    let mut contents = ContentList::default();
+    // `functionResponse` parts are matched by name, which the output items
+    // do not carry.
+    let mut function_names: HashMap<String, String> = HashMap::new();
+    let mut pending_signature: Option<String> = None;
+
+    for item in prompt.get_formatted_input() {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" { "model" } else { "user" };
+                for c in content {
+                    let mut part = match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if text.is_empty() {
+                                continue;
+                            }
+                            json!({"text": text})
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            match split_base64_data_url(&image_url) {
+                                Some((mime_type, data)) => json!({
+                                    "inlineData": {"mimeType": mime_type, "data": data},
+                                }),
+                                None => json!({"fileData": {"fileUri": image_url}}),
+                            }
+                        }
+                    };
+                    if role == "model"
+                        && let Some(signature) = pending_signature.take()
+                    {
+                        part["thoughtSignature"] = json!(signature);
+                    }
+                    contents.push(role, part);
+                }
+            }
+            ResponseItem::Reasoning {
+                encrypted_content: Some(signature),
+                ..
+            } => {
+                pending_signature = Some(signature);
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let args = serde_json::from_str::<Value>(&arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                let mut part = json!({"functionCall": {"name": name, "args": args}});
+                if let Some(signature) = pending_signature.take() {
+                    part["thoughtSignature"] = json!(signature);
+                }
+                function_names.insert(call_id, name);
+                contents.push("model", part);
+            }
+            // Calls to the built-in `local_shell` tool and to freeform tools
+            // are replayed as calls to the equivalent function tools.
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                let args = local_shell_call_arguments(&action);
+                let mut part = json!({"functionCall": {"name": "shell", "args": args}});
+                if let Some(signature) = pending_signature.take() {
+                    part["thoughtSignature"] = json!(signature);
+                }
+                function_names.insert(call_id.or(id).unwrap_or_default(), "shell".to_string());
+                contents.push("model", part);
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                let mut part = json!({"functionCall": {"name": name, "args": {"input": input}}});
+                if let Some(signature) = pending_signature.take() {
+                    part["thoughtSignature"] = json!(signature);
+                }
+                function_names.insert(call_id, name);
+                contents.push("model", part);
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let Some(name) = function_names.get(&call_id) else {
+                    warn!("dropping output of unknown function call {call_id}");
+                    continue;
+                };
+                let key = if output.success == Some(false) {
+                    "error"
+                } else {
+                    "output"
+                };
+                contents.push(
+                    "user",
+                    json!({"functionResponse": {
+                        "name": name,
+                        "response": {key: output.content},
+                    }}),
+                );
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                let Some(name) = function_names.get(&call_id) else {
+                    warn!("dropping output of unknown function call {call_id}");
+                    continue;
+                };
+                contents.push(
+                    "user",
+                    json!({"functionResponse": {
+                        "name": name,
+                        "response": {"output": output},
+                    }}),
+                );
+            }
+            // Thought summaries are not sent back, and web searches run on
+            // OpenAI's side have no equivalent in the Gemini API.
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => continue,
+        }
+    }
+
+    let mut generation_config = json!({});
+    if let Some(max_output_tokens) = max_output_tokens {
+        generation_config["maxOutputTokens"] = json!(max_output_tokens);
+    }
+    if let Some(budget) = thinking_budget_tokens(model_family, effort) {
+        generation_config["thinkingConfig"] = json!({
+            "includeThoughts": true,
+            "thinkingBudget": budget,
+        });
+    }
+
+    let tools_json = create_tools_json_for_gemini_api(&prompt.tools)?;
+    let mut payload = json!({
+        "systemInstruction": {"parts": [{"text": prompt.get_full_instructions(model_family)}]},
+        "contents": contents.0,
+        "generationConfig": generation_config,
+    });
+    if !tools_json.is_empty() {
+        payload["tools"] = json!([{"functionDeclarations": tools_json}]);
+    }
+
+    Ok(payload)
+}
+
+/// Contents with consecutive parts of the same role merged, as Gemini expects
+/// all `functionResponse` parts of a turn in a single content.
+#[derive(Default)]
+struct ContentList(Vec<Value>);
+
+impl ContentList {
+    fn push(&mut self, role: &str, part: Value) {
+        if let Some(last) = self.0.last_mut()
+            && last["role"] == role
+            && let Some(parts) = last["parts"].as_array_mut()
+        {
+            parts.push(part);
+            return;
+        }
+        self.0.push(json!({"role": role, "parts": [part]}));
+    }
+}
+
+/// SSE processor for the `streamGenerateContent?alt=sse` format. Each event is
+/// a full `GenerateContentResponse` chunk and the stream simply ends after
+/// the chunk carrying `finishReason`, so the accumulated items are emitted
+/// when the stream closes.
+pub(crate) async fn process_gemini_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut response_id = String::new();
+    let mut assistant_text = String::new();
+    let mut reasoning_text = String::new();
+    let mut thought_signature: Option<String> = None;
+    let mut function_calls: Vec<ResponseItem> = Vec::new();
+    let mut token_usage: Option<TokenUsage> = None;
+    let mut finished = false;
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        STREAM_IDLE_TIMEOUT_MESSAGE.into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let chunk: Value = match serde_json::from_str(&sse.data) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        trace!("gemini received SSE chunk: {chunk:?}");
+
+        if let Some(error) = chunk.get("error") {
+            let message = error["message"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_string();
+            let _ = tx_event.send(Err(CodexErr::Stream(message, None))).await;
+            return;
+        }
+
+        if let Some(id) = chunk["responseId"].as_str() {
+            response_id = id.to_string();
+        }
+        if let Some(usage) = chunk.get("usageMetadata") {
+            token_usage = Some(parse_usage(usage));
+        }
+
+        let candidate = &chunk["candidates"][0];
+        for part in candidate["content"]["parts"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            if let Some(signature) = part["thoughtSignature"].as_str() {
+                thought_signature = Some(signature.to_string());
+            }
+
+            if let Some(call) = part.get("functionCall") {
+                function_calls.push(ResponseItem::FunctionCall {
+                    id: None,
+                    name: call["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: call
+                        .get("args")
+                        .map(Value::to_string)
+                        .unwrap_or_else(|| "{}".to_string()),
+                    // Gemini only sometimes assigns ids to function calls.
+                    call_id: call["id"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("call_{}", Uuid::new_v4().simple())),
+                });
+            } else if let Some(text) = part["text"].as_str().filter(|t| !t.is_empty()) {
+                if part["thought"].as_bool().unwrap_or(false) {
+                    reasoning_text.push_str(text);
+                    let _ = tx_event
+                        .send(Ok(ResponseEvent::ReasoningContentDelta(text.to_string())))
+                        .await;
+                } else {
+                    assistant_text.push_str(text);
+                    let _ = tx_event
+                        .send(Ok(ResponseEvent::OutputTextDelta(text.to_string())))
+                        .await;
+                }
+            }
+        }
+
+        if candidate.get("finishReason").is_some() {
+            finished = true;
+        }
+    }
+
+    if !finished {
+        let _ = tx_event
+            .send(Err(CodexErr::Stream(
+                "stream closed before finishReason".into(),
+                None,
+            )))
+            .await;
+        return;
+    }
+
+    let mut items = Vec::new();
+    if !reasoning_text.is_empty() || thought_signature.is_some() {
+        items.push(ResponseItem::Reasoning {
+            id: String::new(),
+            summary: Vec::new(),
+            content: Some(vec![ReasoningItemContent::ReasoningText {
+                text: reasoning_text,
+            }]),
+            encrypted_content: thought_signature,
+        });
+    }
+    if !assistant_text.is_empty() {
+        items.push(ResponseItem::Message {
+            id: None,
+            role: "assistant".to_string(),
+            content: vec![ContentItem::OutputText {
+                text: assistant_text,
+            }],
+        });
+    }
+    items.extend(function_calls);
+
+    for item in items {
+        let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+    }
+    let _ = tx_event
+        .send(Ok(ResponseEvent::Completed {
+            response_id,
+            token_usage,
+        }))
+        .await;
+}
+
+fn parse_usage(usage: &Value) -> TokenUsage {
+    let count = |name: &str| usage[name].as_u64().unwrap_or(0);
+    let input_tokens = count("promptTokenCount");
+    let reasoning_output_tokens = count("thoughtsTokenCount");
+    // Like the Responses API, output tokens include the reasoning tokens.
+    let output_tokens = count("candidatesTokenCount") + reasoning_output_tokens;
+    TokenUsage {
+        input_tokens,
+        cached_input_tokens: count("cachedContentTokenCount"),
+        output_tokens,
+        reasoning_output_tokens,
+        total_tokens: input_tokens + output_tokens,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::derive_default_model_family;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use codex_protocol::models::LocalShellAction;
+    use codex_protocol::models::LocalShellExecAction;
+    use codex_protocol::models::LocalShellStatus;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn parses_recorded_stream() {
+        let path = format!("{}/tests/fixtures/gemini.sse", env!("CARGO_MANIFEST_DIR"));
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(64);
+        let stream = read_sse_fixture(path).expect("read fixture");
+        tokio::spawn(process_gemini_sse(stream, tx, Duration::from_secs(5)));
+        let mut events = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            events.push(ev.expect("stream event"));
+        }
+
+        let items = events
+            .iter()
+            .filter_map(|ev| match ev {
+                ResponseEvent::OutputItemDone(item) => Some(item.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let [reasoning, message, call] = items.as_slice() else {
+            panic!("expected reasoning, message and function call, got {items:?}");
+        };
+        assert_eq!(
+            reasoning,
+            &ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: Some(vec![ReasoningItemContent::ReasoningText {
+                    text: "**Listing files**\n\nThe user wants the file list, so I will run ls."
+                        .to_string(),
+                }]),
+                encrypted_content: Some(
+                    "CiQB0e2Kb7Bx3BqvTvyZx9k0JbDbTQ1t+bGvHv7w3w2LbF4eAX0".to_string()
+                ),
+            }
+        );
+        assert_eq!(
+            message,
+            &ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "Let me list the files.".to_string(),
+                }],
+            }
+        );
+        let ResponseItem::FunctionCall {
+            name,
+            arguments,
+            call_id,
+            ..
+        } = call
+        else {
+            panic!("expected function call, got {call:?}");
+        };
+        assert_eq!(name, "shell");
+        assert_eq!(
+            serde_json::from_str::<Value>(arguments).unwrap(),
+            json!({"command": ["ls", "-la"]})
+        );
+        assert!(call_id.starts_with("call_"));
+
+        let Some(ResponseEvent::Completed {
+            response_id,
+            token_usage,
+        }) = events.last()
+        else {
+            panic!("expected Completed as the last event, got {events:?}");
+        };
+        assert_eq!(response_id, "8bLPaPvXN5WZ1dkP0c2e-Qk");
+        assert_eq!(
+            token_usage,
+            &Some(TokenUsage {
+                input_tokens: 1_496,
+                cached_input_tokens: 1_024,
+                output_tokens: 115,
+                reasoning_output_tokens: 88,
+                total_tokens: 1_611,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn truncated_stream_is_an_error() {
+        let body = "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hel\"}],\"role\": \"model\"}}]}\n\n";
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(8);
+        let stream = futures::stream::iter(vec![Ok(Bytes::from(body))]);
+        tokio::spawn(process_gemini_sse(stream, tx, Duration::from_secs(5)));
+
+        assert!(matches!(
+            rx.recv().await,
+            Some(Ok(ResponseEvent::OutputTextDelta(delta))) if delta == "Hel"
+        ));
+        assert!(matches!(
+            rx.recv().await,
+            Some(Err(CodexErr::Stream(_, None)))
+        ));
+    }
+
+    #[test]
+    fn request_reattaches_thought_signature_to_function_call() {
+        let mut model_family = derive_default_model_family("gemini-2.5-pro");
+        model_family.supports_reasoning_summaries = true;
+
+        let prompt = Prompt {
+            input: vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputText {
+                        text: "list files".to_string(),
+                    }],
+                },
+                ResponseItem::Reasoning {
+                    id: String::new(),
+                    summary: Vec::new(),
+                    content: Some(vec![ReasoningItemContent::ReasoningText {
+                        text: "run ls".to_string(),
+                    }]),
+                    encrypted_content: Some("sig".to_string()),
+                },
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: r#"{"command":["ls"]}"#.to_string(),
+                    call_id: "call_1".to_string(),
+                },
+                ResponseItem::FunctionCallOutput {
+                    call_id: "call_1".to_string(),
+                    output: FunctionCallOutputPayload {
+                        content: "a.txt".to_string(),
+                        success: Some(true),
+                    },
+                },
+            ],
+            ..Default::default()
+        };
+
+        let payload = build_gemini_request(
+            &prompt,
+            &model_family,
+            Some(ReasoningEffortConfig::High),
+            None,
+        )
+        .expect("build request");
+
+        assert_eq!(
+            payload["generationConfig"],
+            json!({"thinkingConfig": {"includeThoughts": true, "thinkingBudget": 24_576}})
+        );
+        assert_eq!(
+            payload["contents"],
+            json!([
+                {"role": "user", "parts": [{"text": "list files"}]},
+                {"role": "model", "parts": [{
+                    "functionCall": {"name": "shell", "args": {"command": ["ls"]}},
+                    "thoughtSignature": "sig",
+                }]},
+                {"role": "user", "parts": [{
+                    "functionResponse": {"name": "shell", "response": {"output": "a.txt"}},
+                }]},
+            ])
+        );
+    }
+
+    #[test]
+    fn request_replays_local_shell_and_custom_tool_calls_as_function_calls() {
+        let model_family = derive_default_model_family("gemini-2.5-pro");
+        let prompt = Prompt {
+            input: vec![
+                ResponseItem::LocalShellCall {
+                    id: None,
+                    call_id: Some("call_1".to_string()),
+                    status: LocalShellStatus::Completed,
+                    action: LocalShellAction::Exec(LocalShellExecAction {
+                        command: vec!["ls".to_string()],
+                        timeout_ms: None,
+                        working_directory: None,
+                        env: None,
+                        user: None,
+                    }),
+                },
+                ResponseItem::FunctionCallOutput {
+                    call_id: "call_1".to_string(),
+                    output: FunctionCallOutputPayload {
+                        content: "a.txt".to_string(),
+                        success: Some(true),
+                    },
+                },
+                ResponseItem::CustomToolCall {
+                    id: None,
+                    status: None,
+                    call_id: "call_2".to_string(),
+                    name: "apply_patch".to_string(),
+                    input: "*** Begin Patch".to_string(),
+                },
+                ResponseItem::CustomToolCallOutput {
+                    call_id: "call_2".to_string(),
+                    output: "Done!".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let payload =
+            build_gemini_request(&prompt, &model_family, None, None).expect("build request");
+
+        assert_eq!(
+            payload["contents"],
+            json!([
+                {"role": "model", "parts": [{
+                    "functionCall": {"name": "shell", "args": {"command": ["ls"]}},
+                }]},
+                {"role": "user", "parts": [{
+                    "functionResponse": {"name": "shell", "response": {"output": "a.txt"}},
+                }]},
+                {"role": "model", "parts": [{
+                    "functionCall": {"name": "apply_patch", "args": {"input": "*** Begin Patch"}},
+                }]},
+                {"role": "user", "parts": [{
+                    "functionResponse": {"name": "apply_patch", "response": {"output": "Done!"}},
+                }]},
+            ])
+        );
+    }
+}
@@ -215,49 +215,65 @@ async fn get_git_remotes(cwd: &Path) -> Option<Vec<String>> {
 /// 1) The symbolic ref at `refs/remotes/<remote>/HEAD` for the first remote (origin prioritized)
 /// 2) `git remote show <remote>` parsed for "HEAD branch: <name>"
 /// 3) Local fallback to existing `main` or `master` if present
-async fn get_default_branch(cwd: &Path) -> Option<String> {
+pub(crate) async fn get_default_branch(cwd: &Path) -> Option<String> {
     // Prefer the first remote (with origin prioritized)
     let remotes = get_git_remotes(cwd).await.unwrap_or_default();
     for remote in remotes {
-        // Try symbolic-ref, which returns something like: refs/remotes/origin/main
-        if let Some(symref_output) = run_git_command_with_timeout(
-            &[
-                "symbolic-ref",
-                "--quiet",
-                &format!("refs/remotes/{remote}/HEAD"),
-            ],
-            cwd,
-        )
-        .await
-            && symref_output.status.success()
-            && let Ok(sym) = String::from_utf8(symref_output.stdout)
-        {
-            let trimmed = sym.trim();
-            if let Some((_, name)) = trimmed.rsplit_once('/') {
-                return Some(name.to_string());
-            }
+        if let Some(branch) = get_remote_head_branch(cwd, &remote).await {
+            return Some(branch);
         }
+    }
 
-        // Fall back to parsing `git remote show <remote>` output
-        if let Some(show_output) =
-            run_git_command_with_timeout(&["remote", "show", &remote], cwd).await
-            && show_output.status.success()
-            && let Ok(text) = String::from_utf8(show_output.stdout)
-        {
-            for line in text.lines() {
-                let line = line.trim();
-                if let Some(rest) = line.strip_prefix("HEAD branch:") {
-                    let name = rest.trim();
-                    if !name.is_empty() {
-                        return Some(name.to_string());
-                    }
+    // No remote-derived default; try common local defaults if they exist
+    get_default_branch_local(cwd).await
+}
+
+/// Like [`get_default_branch`], but only asks `remote` for its default
+/// branch before falling back to `main` or `master`.
+pub(crate) async fn get_default_branch_of_remote(cwd: &Path, remote: &str) -> Option<String> {
+    match get_remote_head_branch(cwd, remote).await {
+        Some(branch) => Some(branch),
+        None => get_default_branch_local(cwd).await,
+    }
+}
+
+/// The branch `remote`'s HEAD points to.
+async fn get_remote_head_branch(cwd: &Path, remote: &str) -> Option<String> {
+    // Try symbolic-ref, which returns something like: refs/remotes/origin/main
+    if let Some(symref_output) = run_git_command_with_timeout(
+        &[
+            "symbolic-ref",
+            "--quiet",
+            &format!("refs/remotes/{remote}/HEAD"),
+        ],
+        cwd,
+    )
+    .await
+        && symref_output.status.success()
+        && let Ok(sym) = String::from_utf8(symref_output.stdout)
+    {
+        let trimmed = sym.trim();
+        if let Some((_, name)) = trimmed.rsplit_once('/') {
+            return Some(name.to_string());
+        }
+    }
+
+    // Fall back to parsing `git remote show <remote>` output
+    if let Some(show_output) = run_git_command_with_timeout(&["remote", "show", remote], cwd).await
+        && show_output.status.success()
+        && let Ok(text) = String::from_utf8(show_output.stdout)
+    {
+        for line in text.lines() {
+            let line = line.trim();
+            if let Some(rest) = line.strip_prefix("HEAD branch:") {
+                let name = rest.trim();
+                if !name.is_empty() {
+                    return Some(name.to_string());
                 }
             }
         }
     }
-
-    // No remote-derived default; try common local defaults if they exist
-    get_default_branch_local(cwd).await
+    None
 }
 
 /// Attempt to determine the repository's default branch name from local branches.
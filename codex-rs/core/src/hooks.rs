@@ -0,0 +1,444 @@
+//! User-configured hooks that run external commands around agent actions.
+//!
+//! Each hook receives a single JSON object on stdin describing the action,
+//! for example:
+//!
+//! ````text
+//! {"hook":"pre_exec","conversation_id":"<uuid>","turn_id":"1","cwd":"/repo","call_id":"call_1","command":["git","commit","-m","wip"]}
+//! ````
+//!
+//! A hook may print a JSON object on stdout:
+//!
+//! ````text
+//! {"decision":"deny","reason":"commits are not allowed here","message":"..."}
+//! ````
+//!
+//! - `decision` is `allow` (the default) or `deny`. Pre-hooks (`pre_exec`,
+//!   `pre_patch`, `user_prompt_submit`) veto the action when they deny it,
+//!   exit with a non-zero status, cannot be started or time out.
+//! - `command`, `patch` and `prompt` rewrite the action of the matching
+//!   pre-hook. Later hooks see the rewritten action.
+//! - `message` is passed back to the model alongside the result of the
+//!   action. Output that is not JSON is used as the message as is.
+//!
+//! Post-hooks cannot veto anything, and the output of `turn_complete` hooks
+//! is ignored.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::config_types::HookCommand;
+use crate::config_types::Hooks;
+
+const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The action a hook is run for.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "hook", rename_all = "snake_case")]
+pub(crate) enum HookPayload {
+    PreExec {
+        call_id: String,
+        command: Vec<String>,
+    },
+    PostExec {
+        call_id: String,
+        command: Vec<String>,
+        output: String,
+        success: bool,
+    },
+    PrePatch {
+        call_id: String,
+        patch: String,
+        files: Vec<PathBuf>,
+    },
+    PostPatch {
+        call_id: String,
+        patch: String,
+        files: Vec<PathBuf>,
+        output: String,
+        success: bool,
+    },
+    UserPromptSubmit {
+        prompt: String,
+    },
+    TurnComplete {
+        input_messages: Vec<String>,
+        last_assistant_message: Option<String>,
+    },
+}
+
+impl HookPayload {
+    fn commands<'a>(&self, hooks: &'a Hooks) -> &'a [HookCommand] {
+        match self {
+            HookPayload::PreExec { .. } => &hooks.pre_exec,
+            HookPayload::PostExec { .. } => &hooks.post_exec,
+            HookPayload::PrePatch { .. } => &hooks.pre_patch,
+            HookPayload::PostPatch { .. } => &hooks.post_patch,
+            HookPayload::UserPromptSubmit { .. } => &hooks.user_prompt_submit,
+            HookPayload::TurnComplete { .. } => &hooks.turn_complete,
+        }
+    }
+
+    fn can_veto(&self) -> bool {
+        matches!(
+            self,
+            HookPayload::PreExec { .. }
+                | HookPayload::PrePatch { .. }
+                | HookPayload::UserPromptSubmit { .. }
+        )
+    }
+
+    fn rewrite(&mut self, output: HookOutput) {
+        match self {
+            HookPayload::PreExec { command, .. } => {
+                if let Some(rewritten) = output.command {
+                    *command = rewritten;
+                }
+            }
+            HookPayload::PrePatch { patch, .. } => {
+                if let Some(rewritten) = output.patch {
+                    *patch = rewritten;
+                }
+            }
+            HookPayload::UserPromptSubmit { prompt } => {
+                if let Some(rewritten) = output.prompt {
+                    *prompt = rewritten;
+                }
+            }
+            HookPayload::PostExec { .. }
+            | HookPayload::PostPatch { .. }
+            | HookPayload::TurnComplete { .. } => {}
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HookInput<'a> {
+    conversation_id: ConversationId,
+    turn_id: &'a str,
+    cwd: &'a Path,
+    #[serde(flatten)]
+    payload: &'a HookPayload,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct HookOutput {
+    #[serde(default)]
+    decision: HookDecision,
+    reason: Option<String>,
+    message: Option<String>,
+    command: Option<Vec<String>>,
+    patch: Option<String>,
+    prompt: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum HookDecision {
+    #[default]
+    Allow,
+    Deny,
+}
+
+/// What came of running the hooks for an action.
+#[derive(Debug)]
+pub(crate) struct HookOutcome {
+    /// The action, as rewritten by the hooks.
+    pub(crate) payload: HookPayload,
+    /// Why a pre-hook vetoed the action, if one did.
+    pub(crate) veto: Option<String>,
+    /// Messages for the model, in the order the hooks ran.
+    pub(crate) messages: Vec<String>,
+}
+
+impl HookOutcome {
+    /// The hooks' messages formatted to follow the output of the action, or
+    /// an empty string when there are none.
+    pub(crate) fn feedback(&self) -> String {
+        self.messages
+            .iter()
+            .map(|message| format!("\n\nhook: {message}"))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct HookRunner {
+    hooks: Hooks,
+    conversation_id: ConversationId,
+}
+
+impl HookRunner {
+    pub(crate) fn new(hooks: Hooks, conversation_id: ConversationId) -> Self {
+        Self {
+            hooks,
+            conversation_id,
+        }
+    }
+
+    /// Whether any hooks run around shell commands or patches.
+    pub(crate) fn has_tool_call_hooks(&self) -> bool {
+        !(self.hooks.pre_exec.is_empty()
+            && self.hooks.post_exec.is_empty()
+            && self.hooks.pre_patch.is_empty()
+            && self.hooks.post_patch.is_empty())
+    }
+
+    pub(crate) fn has_hooks_for(&self, payload: &HookPayload) -> bool {
+        !payload.commands(&self.hooks).is_empty()
+    }
+
+    /// Runs the hooks configured for `payload` one after the other, stopping
+    /// at the first veto.
+    pub(crate) async fn run(&self, turn_id: &str, cwd: &Path, payload: HookPayload) -> HookOutcome {
+        let mut outcome = HookOutcome {
+            payload,
+            veto: None,
+            messages: Vec::new(),
+        };
+        for hook in outcome.payload.commands(&self.hooks) {
+            let input = HookInput {
+                conversation_id: self.conversation_id,
+                turn_id,
+                cwd,
+                payload: &outcome.payload,
+            };
+            let result = match serde_json::to_vec(&input) {
+                Ok(stdin) => run_hook(hook, cwd, stdin).await,
+                Err(e) => Err(format!("failed to serialize hook input: {e}")),
+            };
+            let output = match result {
+                Ok(output) => output,
+                Err(reason) => {
+                    warn!("{reason}");
+                    if outcome.payload.can_veto() {
+                        outcome.veto = Some(reason);
+                        return outcome;
+                    }
+                    continue;
+                }
+            };
+
+            if let Some(message) = &output.message {
+                outcome.messages.push(message.clone());
+            }
+            if output.decision == HookDecision::Deny && outcome.payload.can_veto() {
+                outcome.veto = Some(
+                    output
+                        .reason
+                        .unwrap_or_else(|| format!("denied by hook `{}`", hook.command[0])),
+                );
+                return outcome;
+            }
+            outcome.payload.rewrite(output);
+        }
+        outcome
+    }
+
+    /// Runs the hooks for `payload` in the background.
+    pub(crate) fn spawn(&self, turn_id: String, cwd: PathBuf, payload: HookPayload) {
+        if !self.has_hooks_for(&payload) {
+            return;
+        }
+        let runner = self.clone();
+        tokio::spawn(async move {
+            runner.run(&turn_id, &cwd, payload).await;
+        });
+    }
+}
+
+/// Runs a single hook and parses what it printed. A hook that cannot be run,
+/// times out or exits unsuccessfully yields an error describing why.
+async fn run_hook(hook: &HookCommand, cwd: &Path, stdin: Vec<u8>) -> Result<HookOutput, String> {
+    let Some((program, args)) = hook.command.split_first() else {
+        return Err("hook has an empty command".to_string());
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to run hook `{program}`: {e}"))?;
+
+    let child_stdin = child.stdin.take();
+    let run = async move {
+        if let Some(mut child_stdin) = child_stdin {
+            // A hook that does not read its input closes the pipe early, which
+            // is fine. Dropping the handle closes the pipe so the hook sees EOF.
+            let _ = child_stdin.write_all(&stdin).await;
+        }
+        child.wait_with_output().await
+    };
+
+    // The timeout also covers writing the input, which blocks once the pipe
+    // is full if the hook never reads it.
+    let timeout = hook
+        .timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_HOOK_TIMEOUT);
+    let output = match tokio::time::timeout(timeout, run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Err(format!("failed to run hook `{program}`: {e}")),
+        Err(_) => {
+            return Err(format!(
+                "hook `{program}` timed out after {}ms",
+                timeout.as_millis()
+            ));
+        }
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        return Err(if stderr.is_empty() {
+            format!("hook `{program}` failed with {}", output.status)
+        } else {
+            stderr.to_string()
+        });
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stdout = stdout.trim();
+    if stdout.is_empty() {
+        return Ok(HookOutput::default());
+    }
+    Ok(
+        serde_json::from_str::<HookOutput>(stdout).unwrap_or_else(|_| HookOutput {
+            message: Some(stdout.to_string()),
+            ..Default::default()
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn hook(script: &str) -> HookCommand {
+        HookCommand {
+            command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            timeout_ms: None,
+        }
+    }
+
+    fn pre_exec(command: &[&str]) -> HookPayload {
+        HookPayload::PreExec {
+            call_id: "call_1".to_string(),
+            command: command.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn pre_hooks_rewrite_in_order_and_collect_messages() {
+        let runner = HookRunner::new(
+            Hooks {
+                pre_exec: vec![
+                    hook(r#"echo '{"command":["echo","rewritten"],"message":"first"}'"#),
+                    hook("grep -q rewritten && echo second"),
+                ],
+                ..Default::default()
+            },
+            ConversationId::new(),
+        );
+        let cwd = std::env::temp_dir();
+
+        let outcome = runner.run("1", &cwd, pre_exec(&["ls"])).await;
+
+        assert_eq!(outcome.payload, pre_exec(&["echo", "rewritten"]));
+        assert_eq!(outcome.veto, None);
+        assert_eq!(outcome.messages, vec!["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn pre_hooks_veto_on_deny_or_failure() {
+        let cwd = std::env::temp_dir();
+        let deny = HookRunner::new(
+            Hooks {
+                pre_exec: vec![
+                    hook(r#"echo '{"decision":"deny","reason":"no git"}'"#),
+                    hook("echo unreachable"),
+                ],
+                ..Default::default()
+            },
+            ConversationId::new(),
+        );
+        let outcome = deny.run("1", &cwd, pre_exec(&["git", "commit"])).await;
+        assert_eq!(outcome.veto.as_deref(), Some("no git"));
+        assert!(outcome.messages.is_empty());
+
+        let fail = HookRunner::new(
+            Hooks {
+                pre_exec: vec![hook("echo blocked >&2; exit 2")],
+                ..Default::default()
+            },
+            ConversationId::new(),
+        );
+        let outcome = fail.run("1", &cwd, pre_exec(&["ls"])).await;
+        assert_eq!(outcome.veto.as_deref(), Some("blocked"));
+    }
+
+    #[tokio::test]
+    async fn timeout_covers_a_hook_that_never_reads_its_input() {
+        let runner = HookRunner::new(
+            Hooks {
+                pre_exec: vec![HookCommand {
+                    timeout_ms: Some(200),
+                    ..hook("sleep 5")
+                }],
+                ..Default::default()
+            },
+            ConversationId::new(),
+        );
+        // Far more than a pipe buffer, so writing it blocks.
+        let input = "x".repeat(1024 * 1024);
+        let command = [input.as_str()];
+
+        let outcome = runner
+            .run("1", &std::env::temp_dir(), pre_exec(&command))
+            .await;
+
+        assert_eq!(
+            outcome.veto.as_deref(),
+            Some("hook `sh` timed out after 200ms")
+        );
+    }
+
+    #[tokio::test]
+    async fn post_hooks_cannot_veto() {
+        let runner = HookRunner::new(
+            Hooks {
+                post_exec: vec![hook("exit 1"), hook("echo formatted")],
+                ..Default::default()
+            },
+            ConversationId::new(),
+        );
+        let outcome = runner
+            .run(
+                "1",
+                &std::env::temp_dir(),
+                HookPayload::PostExec {
+                    call_id: "call_1".to_string(),
+                    command: vec!["ls".to_string()],
+                    output: String::new(),
+                    success: true,
+                },
+            )
+            .await;
+
+        assert_eq!(outcome.veto, None);
+        assert_eq!(outcome.feedback(), "\n\nhook: formatted");
+    }
+}
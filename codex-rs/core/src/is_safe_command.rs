@@ -1,31 +1,100 @@
+use std::path::PathBuf;
+
+use crate::bash::ShellWord;
+use crate::bash::try_analyze_bash_script;
 use crate::bash::try_parse_bash;
-use crate::bash::try_parse_word_only_commands_sequence;
 
-pub fn is_known_safe_command(command: &[String]) -> bool {
+/// Result of statically analyzing a command before it is run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandSafetyVerdict {
+    /// Every command that may run is known to be safe and nothing is written.
+    KnownSafe,
+    /// Every command that may run is known to be safe, but the script writes
+    /// to these files through output redirections. Relative paths are
+    /// relative to the command's working directory.
+    WritesFiles { paths: Vec<PathBuf> },
+    /// At least one command is not known to be safe, or the script uses shell
+    /// constructs that the analysis does not model.
+    NotKnownSafe,
+}
+
+/// Commands that are safe whatever arguments they receive, so an argument
+/// whose value is only known at runtime (e.g. `cat "$(ls | head -1)"`) does
+/// not change the verdict.
+const ARGUMENT_INSENSITIVE_COMMANDS: &[&str] = &[
+    "cat", "cd", "echo", "false", "grep", "head", "ls", "nl", "pwd", "tail", "true", "wc", "which",
+];
+
+/// Redirect targets that never persist anything.
+const NON_PERSISTENT_WRITE_TARGETS: &[&str] = &["/dev/null", "/dev/stdout", "/dev/stderr"];
+
+pub fn analyze_command_safety(command: &[String]) -> CommandSafetyVerdict {
     if is_safe_to_call_with_exec(command) {
-        return true;
+        return CommandSafetyVerdict::KnownSafe;
     }
 
-    // Support `bash -lc "..."` where the script consists solely of one or
-    // more "plain" commands (only bare words / quoted strings) combined with
-    // a conservative allow‑list of shell operators that themselves do not
-    // introduce side effects ( "&&", "||", ";", and "|" ). If every
-    // individual command in the script is itself a known‑safe command, then
-    // the composite expression is considered safe.
+    // Support `bash -lc "..."` by walking the parse tree of the script: every
+    // simple command it may run (including those in pipelines, subshells, and
+    // `$(…)` substitutions) must itself be known-safe, and any redirection
+    // that writes to a file is reported so the caller can check the target
+    // against the sandbox policy.
     if let [bash, flag, script] = command
         && bash == "bash"
         && flag == "-lc"
         && let Some(tree) = try_parse_bash(script)
-        && let Some(all_commands) = try_parse_word_only_commands_sequence(&tree, script)
-        && !all_commands.is_empty()
-        && all_commands
+        && let Some(analysis) = try_analyze_bash_script(&tree, script)
+        && !analysis.commands.is_empty()
+        && analysis
+            .commands
             .iter()
-            .all(|cmd| is_safe_to_call_with_exec(cmd))
+            .all(|words| is_safe_shell_command(words))
     {
-        return true;
+        let mut paths = Vec::new();
+        for target in analysis.write_targets {
+            match target {
+                ShellWord::Literal(path)
+                    if NON_PERSISTENT_WRITE_TARGETS.contains(&path.as_str()) => {}
+                // Globs and brace expansions in a redirect target are
+                // expanded by the shell.
+                ShellWord::Literal(path) if !path.contains(['*', '?', '[', '{']) => {
+                    paths.push(PathBuf::from(path));
+                }
+                _ => return CommandSafetyVerdict::NotKnownSafe,
+            }
+        }
+        if paths.is_empty() {
+            return CommandSafetyVerdict::KnownSafe;
+        }
+        // A `cd` earlier in the script changes what relative targets
+        // resolve to.
+        let changes_directory = analysis
+            .commands
+            .iter()
+            .any(|words| words.first() == Some(&ShellWord::Literal("cd".to_string())));
+        if changes_directory {
+            return CommandSafetyVerdict::NotKnownSafe;
+        }
+        return CommandSafetyVerdict::WritesFiles { paths };
     }
 
-    false
+    CommandSafetyVerdict::NotKnownSafe
+}
+
+fn is_safe_shell_command(words: &[ShellWord]) -> bool {
+    let mut literal_words = Vec::with_capacity(words.len());
+    for word in words {
+        match word {
+            ShellWord::Literal(word) => literal_words.push(word.clone()),
+            ShellWord::Dynamic(_) => {
+                return matches!(
+                    words.first(),
+                    Some(ShellWord::Literal(cmd))
+                        if ARGUMENT_INSENSITIVE_COMMANDS.contains(&cmd.as_str())
+                );
+            }
+        }
+    }
+    is_safe_to_call_with_exec(&literal_words)
 }
 
 fn is_safe_to_call_with_exec(command: &[String]) -> bool {
@@ -166,6 +235,10 @@ mod tests {
         args.iter().map(ToString::to_string).collect()
     }
 
+    fn is_known_safe_command(command: &[String]) -> bool {
+        analyze_command_safety(command) == CommandSafetyVerdict::KnownSafe
+    }
+
     #[test]
     fn known_safe_examples() {
         assert!(is_safe_to_call_with_exec(&vec_str(&["ls"])));
@@ -326,14 +399,18 @@ mod tests {
             "Sequence containing unsafe command must be rejected"
         );
 
-        // Disallowed because of parentheses / subshell.
+        // Disallowed because of unsafe command in a subshell or substitution.
+        assert!(
+            !is_known_safe_command(&vec_str(&["bash", "-lc", "ls || (pwd && rm -rf /)"])),
+            "Subshell containing unsafe command must be rejected"
+        );
         assert!(
-            !is_known_safe_command(&vec_str(&["bash", "-lc", "(ls)"])),
-            "Parentheses (subshell) are not provably safe with the current parser"
+            !is_known_safe_command(&vec_str(&["bash", "-lc", "echo $(rm -rf /)"])),
+            "Command substitution running an unsafe command must be rejected"
         );
         assert!(
-            !is_known_safe_command(&vec_str(&["bash", "-lc", "ls || (pwd && echo hi)"])),
-            "Nested parentheses are not provably safe with the current parser"
+            !is_known_safe_command(&vec_str(&["bash", "-lc", "find $DIR -name file.txt"])),
+            "Dynamic arguments are only allowed for argument-insensitive commands"
         );
 
         // Disallowed redirection.
@@ -342,4 +419,51 @@ mod tests {
             "> redirection should be rejected"
         );
     }
+
+    #[test]
+    fn bash_lc_safe_examples_with_subshells_substitutions_and_redirects() {
+        for script in [
+            "(ls)",
+            "ls || (pwd && echo hi)",
+            "cat \"$(ls | head -1)\"",
+            "grep -rn foo src 2>/dev/null | head -20",
+            "rg foo 2>&1 | wc -l",
+            "wc -l < Cargo.toml",
+        ] {
+            assert!(
+                is_known_safe_command(&vec_str(&["bash", "-lc", script])),
+                "expected {script:?} to be known safe"
+            );
+        }
+    }
+
+    #[test]
+    fn bash_lc_verdict_reports_write_targets() {
+        assert_eq!(
+            analyze_command_safety(&vec_str(&[
+                "bash",
+                "-lc",
+                "git diff > changes.patch && ls >> /tmp/listing.txt 2>/dev/null"
+            ])),
+            CommandSafetyVerdict::WritesFiles {
+                paths: vec![
+                    PathBuf::from("changes.patch"),
+                    PathBuf::from("/tmp/listing.txt")
+                ],
+            }
+        );
+
+        for script in [
+            "ls > $OUT",
+            "ls > *.txt",
+            "cd /etc && echo hi > passwd",
+            "rm -rf / > /dev/null",
+        ] {
+            assert_eq!(
+                analyze_command_safety(&vec_str(&["bash", "-lc", script])),
+                CommandSafetyVerdict::NotKnownSafe,
+                "expected {script:?} to be not known safe"
+            );
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use crate::config_types::LinuxSandboxBackend;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
@@ -6,15 +7,26 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::process::Child;
 
+/// When set for `codex-linux-sandbox`, the helper monitors the sandboxed
+/// command and writes the [`crate::protocol::SandboxViolation`]s it observed
+/// as a JSON list to the file at this path. The variable is not passed on to
+/// the sandboxed command. Only the [`LinuxSandboxBackend::Landlock`] backend
+/// produces reports; the helper refuses to run with
+/// [`LinuxSandboxBackend::Namespaces`] when one is requested.
+pub const CODEX_SANDBOX_VIOLATION_REPORT_ENV_VAR: &str = "CODEX_SANDBOX_VIOLATION_REPORT";
+
 /// Spawn a shell tool command under the Linux Landlock+seccomp sandbox helper
-/// (codex-linux-sandbox).
+/// (codex-linux-sandbox). With [`LinuxSandboxBackend::Namespaces`], the helper
+/// isolates the command in user, mount and PID namespaces instead.
 ///
 /// Unlike macOS Seatbelt where we directly embed the policy text, the Linux
 /// helper accepts a list of `--sandbox-permission`/`-s` flags mirroring the
 /// public CLI. We convert the internal [`SandboxPolicy`] representation into
 /// the equivalent CLI options.
+#[allow(clippy::too_many_arguments)]
 pub async fn spawn_command_under_linux_sandbox<P>(
     codex_linux_sandbox_exe: P,
+    backend: LinuxSandboxBackend,
     command: Vec<String>,
     command_cwd: PathBuf,
     sandbox_policy: &SandboxPolicy,
@@ -25,7 +37,8 @@ pub async fn spawn_command_under_linux_sandbox<P>(
 where
     P: AsRef<Path>,
 {
-    let args = create_linux_sandbox_command_args(command, sandbox_policy, sandbox_policy_cwd);
+    let args =
+        create_linux_sandbox_command_args(command, backend, sandbox_policy, sandbox_policy_cwd);
     let arg0 = Some("codex-linux-sandbox");
     spawn_child_async(
         codex_linux_sandbox_exe.as_ref().to_path_buf(),
@@ -42,6 +55,7 @@ where
 /// Converts the sandbox policy into the CLI invocation for `codex-linux-sandbox`.
 fn create_linux_sandbox_command_args(
     command: Vec<String>,
+    backend: LinuxSandboxBackend,
     sandbox_policy: &SandboxPolicy,
     sandbox_policy_cwd: &Path,
 ) -> Vec<String> {
@@ -55,13 +69,17 @@ fn create_linux_sandbox_command_args(
     let sandbox_policy_json =
         serde_json::to_string(sandbox_policy).expect("Failed to serialize SandboxPolicy to JSON");
 
-    let mut linux_cmd: Vec<String> = vec![
+    let mut linux_cmd: Vec<String> = Vec::new();
+    if backend == LinuxSandboxBackend::Namespaces {
+        linux_cmd.push("--namespaces".to_string());
+    }
+    linux_cmd.extend([
         sandbox_policy_cwd,
         sandbox_policy_json,
         // Separator so that command arguments starting with `-` are not parsed as
         // options of the helper itself.
         "--".to_string(),
-    ];
+    ]);
 
     // Append the original tool command.
     linux_cmd.extend(command);
@@ -5,10 +5,14 @@
 // the TUI or the tracing stack).
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
+mod anthropic_messages;
 mod apply_patch;
+pub mod audit;
 pub mod auth;
 pub mod bash;
 mod chat_completions;
+mod checkpoint;
+pub use checkpoint::diff_sessions;
 mod client;
 mod client_common;
 pub mod codex;
@@ -20,14 +24,19 @@ pub mod config_edit;
 pub mod config_profile;
 pub mod config_types;
 mod conversation_history;
+pub mod cost;
 pub mod custom_prompts;
+mod delegate_tool;
 mod environment_context;
 pub mod error;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
 mod flags;
+pub mod forge;
+mod gemini;
 pub mod git_info;
+mod hooks;
 pub mod internal_storage;
 mod is_safe_command;
 pub mod landlock;
@@ -36,6 +45,7 @@ mod mcp_tool_call;
 mod message_history;
 mod model_provider_info;
 pub mod parse_command;
+mod tokenizer;
 mod truncate;
 mod unified_exec;
 mod user_instructions;
@@ -47,6 +57,8 @@ pub use model_provider_info::create_oss_provider_with_base_url;
 mod conversation_manager;
 mod event_mapping;
 pub mod review_format;
+pub mod review_report;
+pub mod review_target;
 pub use codex_protocol::protocol::InitialHistory;
 pub use conversation_manager::ConversationManager;
 pub use conversation_manager::NewConversation;
@@ -57,8 +69,11 @@ pub mod default_client;
 pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
+mod output_schema;
 pub mod plan_tool;
 pub mod project_doc;
+pub mod redact;
+pub mod response_cache;
 mod rollout;
 pub(crate) mod safety;
 pub mod seatbelt;
@@ -70,15 +85,26 @@ pub mod turn_diff_tracker;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::RolloutRecorder;
 pub use rollout::SESSIONS_SUBDIR;
+pub use rollout::SessionHit;
 pub use rollout::SessionMeta;
+pub use rollout::SessionQuery;
+pub use rollout::export::ExportFormat;
+pub use rollout::export::ExportOptions;
+pub use rollout::export::export_session;
 pub use rollout::find_conversation_path_by_id_str;
+pub use rollout::index::parse_date_bound;
 pub use rollout::list::ConversationItem;
 pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
+pub use rollout::retention::PruneReason;
+pub use rollout::retention::PruneReport;
+pub use rollout::retention::prune_sessions;
 mod user_notification;
 pub mod util;
+pub mod worktree;
 
 pub use apply_patch::CODEX_APPLY_PATCH_ARG1;
+pub use safety::apply_linux_sandbox_backend;
 pub use safety::get_platform_sandbox;
 // Re-export the protocol types from the standalone `codex-protocol` crate so existing
 // `codex_core::protocol::...` references continue to work across the workspace.
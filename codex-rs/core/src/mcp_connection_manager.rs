@@ -266,6 +266,16 @@ impl McpConnectionManager {
             .get(tool_name)
             .map(|tool| (tool.server_name.clone(), tool.tool_name.clone()))
     }
+
+    /// Whether the server annotates the tool with the fully-qualified name
+    /// `tool_name` as not modifying its environment.
+    pub fn is_read_only_tool(&self, tool_name: &str) -> bool {
+        self.tools
+            .get(tool_name)
+            .and_then(|tool| tool.tool.annotations.as_ref())
+            .and_then(|annotations| annotations.read_only_hint)
+            .unwrap_or(false)
+    }
 }
 
 /// Query every server for its available tools and return a single map that
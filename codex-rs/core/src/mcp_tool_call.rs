@@ -2,6 +2,7 @@ use std::time::Instant;
 
 use tracing::error;
 
+use crate::audit::AuditEvent;
 use crate::codex::Session;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -55,10 +56,22 @@ pub(crate) async fn handle_mcp_tool_call(
 
     let start = Instant::now();
     // Perform the tool call.
-    let result = sess
+    let mut result = sess
         .call_tool(&server, &tool_name, arguments_value.clone())
         .await
         .map_err(|e| format!("tool call error: {e}"));
+    if let (Some(redactor), Ok(result)) = (sess.secret_redactor(), &mut result) {
+        redactor.redact_call_tool_result(result);
+    }
+    sess.record_audit(AuditEvent::McpToolCall {
+        call_id: call_id.clone(),
+        server,
+        tool: tool_name,
+        success: result
+            .as_ref()
+            .is_ok_and(|result| !result.is_error.unwrap_or(false)),
+    })
+    .await;
     let tool_call_end_event = EventMsg::McpToolCallEnd(McpToolCallEndEvent {
         call_id: call_id.clone(),
         invocation,
@@ -13,11 +13,23 @@
 //! trailing `\n`) and write it with a **single `write(2)` system call** while
 //! the file descriptor is opened with the `O_APPEND` flag. POSIX guarantees
 //! that writes up to `PIPE_BUF` bytes are atomic in that case.
+//!
+//! When `history.max_bytes` is set, an append that grows the file past the
+//! limit drops the oldest entries until the file is back under
+//! [`HISTORY_SOFT_CAP_RATIO`] of the limit, so that the file is not
+//! rewritten on every subsequent append. The remaining entries are written
+//! to a new file that replaces the old one, which gives the history a new
+//! `log_id`: offsets handed out before the trim would point at different
+//! entries, so [`lookup`] no longer resolves them.
 
 use std::fs::File;
 use std::fs::OpenOptions;
+use std::io::Read;
 use std::io::Result;
+use std::io::Seek;
+use std::io::SeekFrom;
 use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
 
 use serde::Deserialize;
@@ -39,6 +51,9 @@ use std::os::unix::fs::PermissionsExt;
 /// Filename that stores the message history inside `~/.codex`.
 const HISTORY_FILENAME: &str = "history.jsonl";
 
+/// Fraction of `history.max_bytes` the file is trimmed down to.
+const HISTORY_SOFT_CAP_RATIO: f64 = 0.8;
+
 const MAX_RETRIES: usize = 10;
 const RETRY_SLEEP: Duration = Duration::from_millis(100);
 
@@ -105,20 +120,33 @@ pub(crate) async fn append_entry(
         options.mode(0o600);
     }
 
-    let mut history_file = options.open(&path)?;
+    let history_file = options.open(&path)?;
 
     // Ensure permissions.
     ensure_owner_only_permissions(&history_file).await?;
 
+    let max_bytes = config.history.max_bytes;
+
     // Perform a blocking write under an advisory write lock using std::fs.
     tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut history_file = history_file;
         // Retry a few times to avoid indefinite blocking when contended.
         for _ in 0..MAX_RETRIES {
             match history_file.try_lock() {
                 Ok(()) => {
+                    // A concurrent trim may have replaced the file since it
+                    // was opened; appending to the old one would lose the
+                    // entry.
+                    if was_replaced(&history_file, &path)? {
+                        history_file = options.open(&path)?;
+                        continue;
+                    }
                     // While holding the exclusive lock, write the full line.
                     history_file.write_all(line.as_bytes())?;
                     history_file.flush()?;
+                    if let Some(max_bytes) = max_bytes {
+                        enforce_history_limit(&mut history_file, &path, max_bytes)?;
+                    }
                     return Ok(());
                 }
                 Err(std::fs::TryLockError::WouldBlock) => {
@@ -138,6 +166,70 @@ pub(crate) async fn append_entry(
     Ok(())
 }
 
+/// Drop the oldest entries of the locked history file at `path` if it is
+/// larger than `max_bytes`. The entries kept are written to a new file that
+/// is renamed over `path`, so the trimmed history has a new `log_id`.
+fn enforce_history_limit(file: &mut File, path: &Path, max_bytes: usize) -> Result<()> {
+    let len = file.metadata()?.len();
+    if len <= max_bytes as u64 {
+        return Ok(());
+    }
+
+    let mut contents = Vec::with_capacity(len as usize);
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut contents)?;
+    let keep_from = trim_start_offset(&contents, max_bytes);
+    if keep_from == 0 {
+        return Ok(());
+    }
+
+    let tmp = path.with_extension(format!("jsonl.{}.tmp", std::process::id()));
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        options.mode(0o600);
+    }
+    let mut trimmed = options.open(&tmp)?;
+    trimmed.write_all(&contents[keep_from..])?;
+    trimmed.flush()?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Whether `path` no longer refers to the open `file`, because a trim
+/// replaced it.
+#[cfg(unix)]
+fn was_replaced(file: &File, path: &Path) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    let open = file.metadata()?;
+    let current = std::fs::metadata(path)?;
+    Ok(open.dev() != current.dev() || open.ino() != current.ino())
+}
+
+/// Without inode numbers to compare, entries appended during a concurrent
+/// trim may be lost.
+#[cfg(not(unix))]
+fn was_replaced(_file: &File, _path: &Path) -> Result<bool> {
+    Ok(false)
+}
+
+/// Offset of the first line to keep so that what remains of `contents` fits
+/// in the soft cap derived from `max_bytes`. The newest entry is always
+/// kept, even if it is larger than the cap on its own.
+fn trim_start_offset(contents: &[u8], max_bytes: usize) -> usize {
+    let soft_cap = (max_bytes as f64 * HISTORY_SOFT_CAP_RATIO) as usize;
+    let body = contents.strip_suffix(b"\n").unwrap_or(contents);
+    let newest_start = body.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    let mut start = 0;
+    while contents.len() - start > soft_cap && start < newest_start {
+        match contents[start..].iter().position(|b| *b == b'\n') {
+            Some(i) => start += i + 1,
+            None => break,
+        }
+    }
+    start.min(newest_start)
+}
+
 /// Asynchronously fetch the history file's *identifier* (inode on Unix) and
 /// the current number of entries by counting newline characters.
 pub(crate) async fn history_metadata(config: &Config) -> (u64, usize) {
@@ -284,3 +376,45 @@ async fn ensure_owner_only_permissions(_file: &File) -> Result<()> {
     // For now, on non-Unix, simply succeed.
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn trim_keeps_newest_entries_under_soft_cap() {
+        let contents = b"aaaa\nbbbb\ncccc\ndddd\n";
+        // A soft cap of 8 bytes keeps the last 8 bytes worth of entries.
+        let start = trim_start_offset(contents, 10);
+        assert_eq!(&contents[start..], b"dddd\n");
+
+        let start = trim_start_offset(contents, 20);
+        assert_eq!(&contents[start..], b"bbbb\ncccc\ndddd\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn trimming_replaces_the_history_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(HISTORY_FILENAME);
+        std::fs::write(&path, "aaaa\nbbbb\ncccc\ndddd\n").unwrap();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .read(true)
+            .open(&path)
+            .unwrap();
+
+        enforce_history_limit(&mut file, &path, 10).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "dddd\n");
+        assert!(was_replaced(&file, &path).unwrap());
+    }
+
+    #[test]
+    fn trim_never_drops_the_newest_entry() {
+        let contents = b"short\na much longer entry\n";
+        let start = trim_start_offset(contents, 4);
+        assert_eq!(&contents[start..], b"a much longer entry\n");
+    }
+}
@@ -25,9 +25,11 @@ const MAX_REQUEST_MAX_RETRIES: u64 = 100;
 /// Wire protocol that the provider speaks. Most third-party services only
 /// implement the classic OpenAI Chat Completions JSON schema, whereas OpenAI
 /// itself (and a handful of others) additionally expose the more modern
-/// *Responses* API. The two protocols use different request/response shapes
-/// and *cannot* be auto-detected at runtime, therefore each provider entry
-/// must declare which one it expects.
+/// *Responses* API. Anthropic and Google models can also be reached through
+/// their native APIs, which preserve thinking blocks and tool calls that get
+/// lost when going through an OpenAI-compatible proxy. The protocols use
+/// different request/response shapes and *cannot* be auto-detected at
+/// runtime, therefore each provider entry must declare which one it expects.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum WireApi {
@@ -37,6 +39,13 @@ pub enum WireApi {
     /// Regular Chat Completions compatible with `/v1/chat/completions`.
     #[default]
     Chat,
+
+    /// Anthropic's Messages API at `/v1/messages`.
+    #[serde(rename = "anthropic")]
+    AnthropicMessages,
+
+    /// Google's Gemini API at `/v1beta/models/{model}:streamGenerateContent`.
+    Gemini,
 }
 
 /// Serializable representation of a provider definition.
@@ -86,13 +95,22 @@ pub struct ModelProviderInfo {
     /// and API key (if needed) comes from the "env_key" environment variable.
     #[serde(default)]
     pub requires_openai_auth: bool,
+
+    /// Whether the provider's Chat Completions endpoint accepts
+    /// `response_format: {"type": "json_schema"}`. If false, an output schema
+    /// is described to the model in its instructions and its final message
+    /// is validated locally instead.
+    #[serde(default)]
+    pub supports_json_schema: bool,
 }
 
 impl ModelProviderInfo {
     /// Construct a `POST` RequestBuilder for the given URL using the provided
     /// reqwest Client applying:
     ///   • provider-specific headers (static + env based)
-    ///   • Bearer auth header when an API key is available.
+    ///   • Bearer auth header when an API key is available (or the
+    ///     `x-api-key`/`x-goog-api-key` header for the Anthropic and Gemini
+    ///     wire APIs).
     ///   • Auth token for OAuth.
     ///
     /// If the provider declares an `env_key` but the variable is missing/empty, returns an [`Err`] identical to the
@@ -101,6 +119,7 @@ impl ModelProviderInfo {
         &'a self,
         client: &'a reqwest::Client,
         auth: &Option<CodexAuth>,
+        model: &str,
     ) -> crate::error::Result<reqwest::RequestBuilder> {
         let effective_auth = match self.api_key() {
             Ok(Some(key)) => Some(CodexAuth::from_api_key(&key)),
@@ -114,12 +133,17 @@ impl ModelProviderInfo {
             }
         };
 
-        let url = self.get_full_url(&effective_auth);
+        let url = self.get_full_url(&effective_auth, model);
 
         let mut builder = client.post(url);
 
         if let Some(auth) = effective_auth.as_ref() {
-            builder = builder.bearer_auth(auth.get_token().await?);
+            let token = auth.get_token().await?;
+            builder = match self.wire_api {
+                WireApi::AnthropicMessages => builder.header("x-api-key", token),
+                WireApi::Gemini => builder.header("x-goog-api-key", token),
+                WireApi::Responses | WireApi::Chat => builder.bearer_auth(token),
+            };
         }
 
         Ok(self.apply_http_headers(builder))
@@ -138,17 +162,22 @@ impl ModelProviderInfo {
             })
     }
 
-    pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>) -> String {
-        let default_base_url = if matches!(
-            auth,
-            Some(CodexAuth {
-                mode: AuthMode::ChatGPT,
-                ..
-            })
-        ) {
-            "https://chatgpt.com/backend-api/codex"
-        } else {
-            "https://api.openai.com/v1"
+    pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>, model: &str) -> String {
+        let default_base_url = match self.wire_api {
+            WireApi::AnthropicMessages => "https://api.anthropic.com/v1",
+            WireApi::Gemini => "https://generativelanguage.googleapis.com/v1beta",
+            WireApi::Responses | WireApi::Chat
+                if matches!(
+                    auth,
+                    Some(CodexAuth {
+                        mode: AuthMode::ChatGPT,
+                        ..
+                    })
+                ) =>
+            {
+                "https://chatgpt.com/backend-api/codex"
+            }
+            WireApi::Responses | WireApi::Chat => "https://api.openai.com/v1",
         };
         let query_string = self.get_query_string();
         let base_url = self
@@ -159,6 +188,14 @@ impl ModelProviderInfo {
         match self.wire_api {
             WireApi::Responses => format!("{base_url}/responses{query_string}"),
             WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
+            WireApi::AnthropicMessages => format!("{base_url}/messages{query_string}"),
+            WireApi::Gemini => {
+                // `alt=sse` switches the endpoint from a JSON array to SSE.
+                let separator = if query_string.is_empty() { '?' } else { '&' };
+                format!(
+                    "{base_url}/models/{model}:streamGenerateContent{query_string}{separator}alt=sse"
+                )
+            }
         }
     }
 
@@ -245,6 +282,16 @@ impl ModelProviderInfo {
             .map(Duration::from_millis)
             .unwrap_or(Duration::from_millis(DEFAULT_STREAM_IDLE_TIMEOUT_MS))
     }
+
+    /// Whether the provider constrains the model's output to a turn's output
+    /// schema, so that it does not need to be validated locally.
+    pub fn enforces_output_schema(&self) -> bool {
+        match self.wire_api {
+            WireApi::Responses => true,
+            WireApi::Chat => self.supports_json_schema,
+            WireApi::AnthropicMessages | WireApi::Gemini => false,
+        }
+    }
 }
 
 const DEFAULT_OLLAMA_PORT: u32 = 11434;
@@ -297,6 +344,7 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: true,
+                supports_json_schema: false,
             },
         ),
         (BUILT_IN_OSS_MODEL_PROVIDER_ID, create_oss_provider()),
@@ -341,6 +389,7 @@ pub fn create_oss_provider_with_base_url(base_url: &str) -> ModelProviderInfo {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        supports_json_schema: false,
     }
 }
 
@@ -380,6 +429,7 @@ base_url = "http://localhost:11434/v1"
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            supports_json_schema: false,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -409,6 +459,7 @@ query_params = { api-version = "2025-04-01-preview" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            supports_json_schema: false,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -441,6 +492,7 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            supports_json_schema: false,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -463,6 +515,7 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: false,
+                supports_json_schema: false,
             }
         }
 
@@ -495,6 +548,7 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            supports_json_schema: false,
         };
         assert!(named_provider.is_azure_responses_endpoint());
 
@@ -511,4 +565,46 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             );
         }
     }
+
+    #[test]
+    fn native_wire_apis_use_their_own_endpoints() {
+        let anthropic: ModelProviderInfo = toml::from_str(
+            r#"
+name = "Anthropic"
+env_key = "ANTHROPIC_API_KEY"
+wire_api = "anthropic"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(anthropic.wire_api, WireApi::AnthropicMessages);
+        assert_eq!(
+            anthropic.get_full_url(&None, "claude-sonnet-4-20250514"),
+            "https://api.anthropic.com/v1/messages"
+        );
+
+        let gemini: ModelProviderInfo = toml::from_str(
+            r#"
+name = "Gemini"
+env_key = "GEMINI_API_KEY"
+wire_api = "gemini"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(gemini.wire_api, WireApi::Gemini);
+        assert_eq!(
+            gemini.get_full_url(&None, "gemini-2.5-pro"),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+        );
+
+        let gemini_with_query = ModelProviderInfo {
+            query_params: Some(maplit::hashmap! {
+                "key".to_string() => "value".to_string(),
+            }),
+            ..gemini
+        };
+        assert_eq!(
+            gemini_with_query.get_full_url(&None, "gemini-2.5-pro"),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:streamGenerateContent?key=value&alt=sse"
+        );
+    }
 }
@@ -5,6 +5,7 @@ use serde_json::json;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
+use crate::delegate_tool::DELEGATE_TOOL;
 use crate::model_family::ModelFamily;
 use crate::plan_tool::PLAN_TOOL;
 use crate::tool_apply_patch::ApplyPatchToolType;
@@ -67,6 +68,7 @@ pub(crate) struct ToolsConfig {
     pub apply_patch_tool_type: Option<ApplyPatchToolType>,
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
+    pub include_delegate_tool: bool,
     pub experimental_unified_exec_tool: bool,
 }
 
@@ -77,6 +79,7 @@ pub(crate) struct ToolsConfigParams<'a> {
     pub(crate) include_web_search_request: bool,
     pub(crate) use_streamable_shell_tool: bool,
     pub(crate) include_view_image_tool: bool,
+    pub(crate) include_delegate_tool: bool,
     pub(crate) experimental_unified_exec_tool: bool,
 }
 
@@ -89,6 +92,7 @@ impl ToolsConfig {
             include_web_search_request,
             use_streamable_shell_tool,
             include_view_image_tool,
+            include_delegate_tool,
             experimental_unified_exec_tool,
         } = params;
         let shell_type = if *use_streamable_shell_tool {
@@ -117,6 +121,7 @@ impl ToolsConfig {
             apply_patch_tool_type,
             web_search_request: *include_web_search_request,
             include_view_image_tool: *include_view_image_tool,
+            include_delegate_tool: *include_delegate_tool,
             experimental_unified_exec_tool: *experimental_unified_exec_tool,
         }
     }
@@ -327,6 +332,61 @@ pub(crate) fn create_tools_json_for_chat_completions_api(
     Ok(tools_json)
 }
 
+/// Returns JSON values that are compatible with tool use in the Anthropic
+/// Messages API:
+/// https://docs.anthropic.com/en/docs/build-with-claude/tool-use
+pub(crate) fn create_tools_json_for_anthropic_messages_api(
+    tools: &[OpenAiTool],
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let tools_json = create_tools_json_for_chat_completions_api(tools)?
+        .into_iter()
+        .filter_map(|tool| {
+            let function = tool.get("function")?;
+            Some(json!({
+                "name": function.get("name")?,
+                "description": function.get("description")?,
+                "input_schema": function.get("parameters")?,
+            }))
+        })
+        .collect();
+    Ok(tools_json)
+}
+
+/// Returns the function declarations for the Gemini API:
+/// https://ai.google.dev/gemini-api/docs/function-calling
+///
+/// Gemini accepts an OpenAPI subset for `parameters` that rejects
+/// `additionalProperties`, so it is stripped from the schemas.
+pub(crate) fn create_tools_json_for_gemini_api(
+    tools: &[OpenAiTool],
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let tools_json = create_tools_json_for_chat_completions_api(tools)?
+        .into_iter()
+        .filter_map(|tool| {
+            let function = tool.get("function")?;
+            let mut parameters = function.get("parameters")?.clone();
+            strip_additional_properties(&mut parameters);
+            Some(json!({
+                "name": function.get("name")?,
+                "description": function.get("description")?,
+                "parameters": parameters,
+            }))
+        })
+        .collect();
+    Ok(tools_json)
+}
+
+fn strip_additional_properties(schema: &mut JsonValue) {
+    match schema {
+        JsonValue::Object(map) => {
+            map.remove("additionalProperties");
+            map.values_mut().for_each(strip_additional_properties);
+        }
+        JsonValue::Array(values) => values.iter_mut().for_each(strip_additional_properties),
+        _ => {}
+    }
+}
+
 pub(crate) fn mcp_tool_to_openai_tool(
     fully_qualified_name: String,
     tool: mcp_types::Tool,
@@ -526,6 +586,10 @@ pub(crate) fn get_openai_tools(
     if config.include_view_image_tool {
         tools.push(create_view_image_tool());
     }
+
+    if config.include_delegate_tool {
+        tools.push(DELEGATE_TOOL.clone());
+    }
     if let Some(mcp_tools) = mcp_tools {
         // Ensure deterministic ordering to maximize prompt cache hits.
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
@@ -587,6 +651,7 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             experimental_unified_exec_tool: true,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
@@ -597,6 +662,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_openai_tools_with_delegate() {
+        let model_family = find_family_for_model("codex-mini-latest")
+            .expect("codex-mini-latest should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            include_delegate_tool: true,
+            experimental_unified_exec_tool: true,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(&tools, &["unified_exec", "delegate"]);
+    }
+
     #[test]
     fn test_get_openai_tools_default_shell() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -607,6 +691,7 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             experimental_unified_exec_tool: true,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
@@ -627,6 +712,7 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             experimental_unified_exec_tool: true,
         });
         let tools = get_openai_tools(
@@ -731,6 +817,7 @@ mod tests {
             include_web_search_request: false,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             experimental_unified_exec_tool: true,
         });
 
@@ -807,6 +894,7 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             experimental_unified_exec_tool: true,
         });
 
@@ -868,6 +956,7 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             experimental_unified_exec_tool: true,
         });
 
@@ -924,6 +1013,7 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             experimental_unified_exec_tool: true,
         });
 
@@ -983,6 +1073,7 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             experimental_unified_exec_tool: true,
         });
 
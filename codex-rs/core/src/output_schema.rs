@@ -0,0 +1,95 @@
+//! Local validation of a turn's final message against its output schema.
+//!
+//! Only the Responses API, and Chat Completions providers that accept
+//! `response_format: json_schema`, constrain the model's output to the
+//! schema (see `ModelProviderInfo::enforces_output_schema`). For other
+//! providers the final message is checked here and the model is asked to fix
+//! it.
+
+use serde_json::Value;
+use tracing::warn;
+
+/// Returns why `message` does not match `schema`, or nothing when it does.
+pub(crate) fn validate_final_message(schema: &Value, message: Option<&str>) -> Vec<String> {
+    let Some(message) = message else {
+        return vec!["the turn ended without a final message".to_string()];
+    };
+    let instance = match serde_json::from_str::<Value>(message.trim()) {
+        Ok(instance) => instance,
+        Err(e) => return vec![format!("the final message is not valid JSON: {e}")],
+    };
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(e) => {
+            // The model cannot fix an invalid schema, so there is no point in
+            // asking it to.
+            warn!("output schema is not a valid JSON Schema: {e}");
+            return Vec::new();
+        }
+    };
+    validator
+        .iter_errors(&instance)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            if path.is_empty() {
+                error.to_string()
+            } else {
+                format!("{path}: {error}")
+            }
+        })
+        .collect()
+}
+
+/// The message that asks the model to fix a final message with `errors`.
+pub(crate) fn retry_prompt(errors: &[String]) -> String {
+    format!(
+        "Your final message does not match the required JSON Schema:\n- {}\n\nReply with only a JSON value that matches the schema.",
+        errors.join("\n- ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "answer": { "type": "integer" }
+            },
+            "required": ["answer"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn accepts_a_matching_message() {
+        assert!(validate_final_message(&schema(), Some(r#" {"answer": 42} "#)).is_empty());
+    }
+
+    #[test]
+    fn reports_where_a_message_does_not_match() {
+        assert_eq!(
+            validate_final_message(&schema(), Some(r#"{"answer": "42"}"#)),
+            vec![r#"/answer: "42" is not of type "integer""#.to_string()]
+        );
+        assert_eq!(
+            validate_final_message(&schema(), Some("{}")),
+            vec![r#""answer" is a required property"#.to_string()]
+        );
+    }
+
+    #[test]
+    fn rejects_missing_and_non_json_messages() {
+        assert_eq!(
+            validate_final_message(&schema(), None),
+            vec!["the turn ended without a final message".to_string()]
+        );
+        let errors = validate_final_message(&schema(), Some("The answer is 42."));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("the final message is not valid JSON"));
+    }
+}
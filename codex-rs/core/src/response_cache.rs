@@ -0,0 +1,363 @@
+//! Record/replay cache for model responses.
+//!
+//! When `response_cache` is set, [`ModelClient::stream`] looks up each
+//! request by a hash of its prompt (instructions, input, tools and output
+//! schema) and of the settings it is sent with (provider, exact model,
+//! reasoning and verbosity) in `~/.codex/response_cache/<hash>.sse`. The working
+//! directory and the durations reported by tool outputs are normalized
+//! before hashing, so a session that ran commands replays. Transcripts are
+//! stored as Responses API SSE, whatever the wire API of the provider that
+//! produced them, so a recorded file can also be used as a
+//! `CODEX_RS_SSE_FIXTURE`.
+//!
+//! Only complete responses are recorded: a stream that fails before
+//! `Completed` leaves the cache untouched.
+//!
+//! [`ModelClient::stream`]: crate::ModelClient::stream
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde_json::Value;
+use serde_json::json;
+use sha1::Digest;
+use sha1::Sha1;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_responses_api;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use codex_protocol::config_types::Verbosity as VerbosityConfig;
+
+/// Directory inside `~/.codex` that holds the recorded transcripts.
+pub const RESPONSE_CACHE_DIRNAME: &str = "response_cache";
+
+/// Placeholder substituted for the session's working directory before
+/// hashing, so transcripts recorded in one checkout replay in another.
+const CWD_PLACEHOLDER: &str = "<cwd>";
+
+/// The settings a prompt is sent with that change the response.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RequestSettings<'a> {
+    pub provider_id: &'a str,
+    /// The configured model, not its family: models of one family answer
+    /// differently.
+    pub model: &'a str,
+    pub model_family: &'a ModelFamily,
+    pub reasoning_effort: Option<ReasoningEffortConfig>,
+    pub reasoning_summary: ReasoningSummaryConfig,
+    pub verbosity: Option<VerbosityConfig>,
+}
+
+/// Path of the transcript for `prompt` in the cache under `codex_home`.
+pub(crate) fn transcript_path(
+    codex_home: &Path,
+    cwd: &Path,
+    settings: RequestSettings<'_>,
+    prompt: &Prompt,
+) -> Result<PathBuf> {
+    let key = prompt_key(cwd, settings, prompt)?;
+    Ok(codex_home
+        .join(RESPONSE_CACHE_DIRNAME)
+        .join(format!("{key}.sse")))
+}
+
+/// Hex SHA-1 of the normalized prompt and its settings. Item ids are never
+/// serialized, so only the content the model actually sees contributes to
+/// the key.
+fn prompt_key(cwd: &Path, settings: RequestSettings<'_>, prompt: &Prompt) -> Result<String> {
+    let mut request = json!({
+        "provider": settings.provider_id,
+        "model": settings.model,
+        "reasoning": {
+            "effort": settings.reasoning_effort,
+            "summary": settings.reasoning_summary,
+        },
+        "verbosity": settings.verbosity,
+        "instructions": prompt.get_full_instructions(settings.model_family),
+        "input": prompt.get_formatted_input(),
+        "tools": create_tools_json_for_responses_api(&prompt.tools)?,
+        "output_schema": prompt.output_schema,
+    });
+    if let Some(items) = request["input"].as_array_mut() {
+        items.iter_mut().for_each(normalize_tool_output);
+    }
+    let mut normalized = serde_json::to_string(&request)?;
+    let cwd = serde_json::to_string(&cwd.to_string_lossy())?;
+    let cwd = cwd.trim_matches('"');
+    if !cwd.is_empty() {
+        normalized = normalized.replace(cwd, CWD_PLACEHOLDER);
+    }
+
+    let mut hasher = Sha1::new();
+    hasher.update(normalized.as_bytes());
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Tool outputs report how long commands ran, which differs between the
+/// recording and every replay. The durations are zeroed so the requests that
+/// follow a tool call in the same turn can be found again.
+fn normalize_tool_output(item: &mut Value) {
+    if item["type"] != "function_call_output" {
+        return;
+    }
+    let Some(Value::String(output)) = item.get_mut("output") else {
+        return;
+    };
+    // Shell calls answer with `{"output": ..., "metadata": {"exit_code": ..,
+    // "duration_seconds": ..}}`.
+    if let Ok(mut exec_output) = serde_json::from_str::<Value>(output)
+        && let Some(metadata) = exec_output
+            .get_mut("metadata")
+            .and_then(Value::as_object_mut)
+    {
+        if metadata.contains_key("duration_seconds") {
+            metadata.insert("duration_seconds".to_string(), json!(0));
+        }
+        if let Some(Value::String(text)) = exec_output.get_mut("output") {
+            *text = zero_leading_duration(text, "command timed out after ", " milliseconds");
+        }
+        *output = exec_output.to_string();
+        return;
+    }
+    // `exec_command` answers with text starting with `Wall time: .. seconds`.
+    *output = zero_leading_duration(output, "Wall time: ", " seconds");
+}
+
+/// Replaces the number in a `{prefix}<number>{suffix}` header of `text`
+/// with `0`.
+fn zero_leading_duration(text: &str, prefix: &str, suffix: &str) -> String {
+    let Some(rest) = text.strip_prefix(prefix) else {
+        return text.to_string();
+    };
+    let number_len = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    if number_len == 0 || !rest[number_len..].starts_with(suffix) {
+        return text.to_string();
+    }
+    format!("{prefix}0{}", &rest[number_len..])
+}
+
+/// Forwards `stream` unchanged and writes its transcript to `path` once the
+/// response completes.
+pub(crate) fn record(mut stream: ResponseStream, path: PathBuf) -> ResponseStream {
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+    tokio::spawn(async move {
+        let mut transcript = String::new();
+        while let Some(event) = stream.rx_event.recv().await {
+            if let Ok(event) = &event {
+                if let Some(data) = sse_data(event) {
+                    let kind = data["type"].as_str().unwrap_or_default();
+                    transcript.push_str(&format!("event: {kind}\ndata: {data}\n\n"));
+                }
+                if matches!(event, ResponseEvent::Completed { .. })
+                    && let Err(e) = write_transcript(&path, &transcript).await
+                {
+                    warn!("failed to record response to {}: {e}", path.display());
+                }
+            }
+            if tx_event.send(event).await.is_err() {
+                return;
+            }
+        }
+    });
+    ResponseStream { rx_event }
+}
+
+/// Writes through a temporary file so a concurrent replay never sees a
+/// partial transcript.
+async fn write_transcript(path: &Path, transcript: &str) -> std::io::Result<()> {
+    let Some(dir) = path.parent() else {
+        return Ok(());
+    };
+    tokio::fs::create_dir_all(dir).await?;
+    let tmp = path.with_extension(format!("sse.{}.tmp", std::process::id()));
+    tokio::fs::write(&tmp, transcript).await?;
+    tokio::fs::rename(&tmp, path).await
+}
+
+/// The Responses API SSE payload that `process_sse` turns back into `event`.
+/// Rate limits come from response headers rather than the body, so they are
+/// not recorded.
+fn sse_data(event: &ResponseEvent) -> Option<Value> {
+    let data = match event {
+        ResponseEvent::Created => json!({"type": "response.created", "response": {}}),
+        ResponseEvent::OutputItemDone(item) => {
+            json!({"type": "response.output_item.done", "item": item})
+        }
+        ResponseEvent::Completed {
+            response_id,
+            token_usage,
+        } => {
+            let usage = token_usage.as_ref().map(|usage| {
+                json!({
+                    "input_tokens": usage.input_tokens,
+                    "input_tokens_details": {"cached_tokens": usage.cached_input_tokens},
+                    "output_tokens": usage.output_tokens,
+                    "output_tokens_details": {"reasoning_tokens": usage.reasoning_output_tokens},
+                    "total_tokens": usage.total_tokens,
+                })
+            });
+            json!({
+                "type": "response.completed",
+                "response": {"id": response_id, "usage": usage},
+            })
+        }
+        ResponseEvent::OutputTextDelta(delta) => {
+            json!({"type": "response.output_text.delta", "delta": delta})
+        }
+        ResponseEvent::ReasoningSummaryDelta(delta) => {
+            json!({"type": "response.reasoning_summary_text.delta", "delta": delta})
+        }
+        ResponseEvent::ReasoningContentDelta(delta) => {
+            json!({"type": "response.reasoning_text.delta", "delta": delta})
+        }
+        ResponseEvent::ReasoningSummaryPartAdded => {
+            json!({"type": "response.reasoning_summary_part.added"})
+        }
+        ResponseEvent::WebSearchCallBegin { call_id } => json!({
+            "type": "response.output_item.added",
+            "item": {"type": "web_search_call", "id": call_id},
+        }),
+        ResponseEvent::RateLimits(_) => return None,
+    };
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::find_family_for_model;
+    use codex_protocol::models::ContentItem;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use codex_protocol::models::ResponseItem;
+    use pretty_assertions::assert_eq;
+
+    fn prompt(text: &str, id: &str) -> Prompt {
+        Prompt {
+            input: vec![ResponseItem::Message {
+                id: Some(id.to_string()),
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: text.to_string(),
+                }],
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn settings(family: &ModelFamily) -> RequestSettings<'_> {
+        RequestSettings {
+            provider_id: "openai",
+            model: &family.slug,
+            model_family: family,
+            reasoning_effort: None,
+            reasoning_summary: ReasoningSummaryConfig::default(),
+            verbosity: None,
+        }
+    }
+
+    #[test]
+    fn key_ignores_item_ids_and_the_working_directory() {
+        let family = find_family_for_model("gpt-5").expect("known model");
+        let key = |cwd: &str, prompt: &Prompt| {
+            prompt_key(Path::new(cwd), settings(&family), prompt).expect("key")
+        };
+
+        assert_eq!(
+            key("/tmp/a", &prompt("read /tmp/a/src/lib.rs", "msg_1")),
+            key("/tmp/b", &prompt("read /tmp/b/src/lib.rs", "msg_2")),
+        );
+        assert_ne!(
+            key("/tmp/a", &prompt("read /tmp/a/src/lib.rs", "msg_1")),
+            key("/tmp/a", &prompt("read /tmp/a/src/main.rs", "msg_1")),
+        );
+    }
+
+    #[test]
+    fn key_depends_on_the_exact_model_provider_and_reasoning() {
+        let family = find_family_for_model("gpt-5").expect("known model");
+        let key = |settings: RequestSettings<'_>| {
+            prompt_key(Path::new("/tmp/a"), settings, &prompt("hi", "msg_1")).expect("key")
+        };
+        let base = key(settings(&family));
+
+        let other_family = find_family_for_model("o3").expect("known model");
+        assert_ne!(base, key(settings(&other_family)));
+        // Both belong to the gpt-5 family.
+        assert_ne!(
+            base,
+            key(RequestSettings {
+                model: "gpt-5-mini",
+                ..settings(&family)
+            })
+        );
+        assert_ne!(
+            base,
+            key(RequestSettings {
+                provider_id: "azure",
+                ..settings(&family)
+            })
+        );
+        assert_ne!(
+            base,
+            key(RequestSettings {
+                reasoning_effort: Some(ReasoningEffortConfig::High),
+                ..settings(&family)
+            })
+        );
+        assert_ne!(
+            base,
+            key(RequestSettings {
+                reasoning_summary: ReasoningSummaryConfig::None,
+                ..settings(&family)
+            })
+        );
+    }
+
+    #[test]
+    fn key_ignores_command_durations() {
+        let family = find_family_for_model("gpt-5").expect("known model");
+        let key = |output: &str| {
+            let prompt = Prompt {
+                input: vec![ResponseItem::FunctionCallOutput {
+                    call_id: "call_1".to_string(),
+                    output: FunctionCallOutputPayload {
+                        content: output.to_string(),
+                        success: Some(true),
+                    },
+                }],
+                ..Default::default()
+            };
+            prompt_key(Path::new("/tmp/a"), settings(&family), &prompt).expect("key")
+        };
+
+        assert_eq!(
+            key(r#"{"output":"hi\n","metadata":{"exit_code":0,"duration_seconds":0.1}}"#),
+            key(r#"{"output":"hi\n","metadata":{"exit_code":0,"duration_seconds":2.3}}"#),
+        );
+        assert_ne!(
+            key(r#"{"output":"hi\n","metadata":{"exit_code":0,"duration_seconds":0.1}}"#),
+            key(r#"{"output":"hi\n","metadata":{"exit_code":1,"duration_seconds":0.1}}"#),
+        );
+        assert_eq!(
+            key(
+                r#"{"output":"command timed out after 10012 milliseconds\n","metadata":{"exit_code":124,"duration_seconds":10.0}}"#
+            ),
+            key(
+                r#"{"output":"command timed out after 10047 milliseconds\n","metadata":{"exit_code":124,"duration_seconds":10.0}}"#
+            ),
+        );
+        assert_eq!(
+            key("Wall time: 0.512 seconds\nProcess exited with code 0\nOutput:\nhi"),
+            key("Wall time: 1.204 seconds\nProcess exited with code 0\nOutput:\nhi"),
+        );
+    }
+}
@@ -53,3 +53,83 @@ pub fn format_review_findings_block(
 
     lines.join("\n")
 }
+
+/// Prefix every line of a unified diff that exists in the new version of its
+/// file with that line number, so a reader can cite `path:line` anchors
+/// without counting hunk offsets. Removed lines get an empty gutter; file
+/// and hunk headers are kept as they are.
+pub fn format_diff_with_line_anchors(diff: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    // Line number in the new file of the next added or context line, while
+    // inside a hunk.
+    let mut next_line: Option<u32> = None;
+
+    for line in diff.lines() {
+        if line.starts_with("diff ") {
+            next_line = None;
+            lines.push(line.to_string());
+            continue;
+        }
+        if line.starts_with("@@") {
+            next_line = parse_hunk_new_start(line);
+            lines.push(line.to_string());
+            continue;
+        }
+        let Some(number) = next_line else {
+            lines.push(line.to_string());
+            continue;
+        };
+        if line.starts_with('-') || line.starts_with('\\') {
+            lines.push(format!("{:>6} {line}", ""));
+        } else {
+            lines.push(format!("{number:>6} {line}"));
+            next_line = Some(number + 1);
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// The start line of the new file in a hunk header such as
+/// `@@ -1,4 +1,6 @@ fn main() {`.
+fn parse_hunk_new_start(header: &str) -> Option<u32> {
+    let new_range = header
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix('+'))?;
+    new_range.split(',').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn diff_lines_are_anchored_to_new_line_numbers() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -10,3 +10,4 @@ fn main() {
+ let a = 1;
+-let b = 2;
++let b = 3;
++let c = 4;
+ let d = 5;
+\\ No newline at end of file";
+        assert_eq!(
+            format_diff_with_line_anchors(diff),
+            "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -10,3 +10,4 @@ fn main() {
+    10  let a = 1;
+       -let b = 2;
+    11 +let b = 3;
+    12 +let c = 4;
+    13  let d = 5;
+       \\ No newline at end of file"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Machine-readable reports of review findings for CI: SARIF 2.1.0 for code
+//! scanning dashboards and reviewdog's rdjson for inline pull request
+//! comments.
+//!
+//! Finding priorities map to severities as follows:
+//!
+//! | Priority | SARIF `level` | rdjson `severity` |
+//! | -------- | ------------- | ----------------- |
+//! | P0, P1   | `error`       | `ERROR`           |
+//! | P2       | `warning`     | `WARNING`         |
+//! | P3       | `note`        | `INFO`            |
+//!
+//! Paths are reported relative to the repository root when the finding is
+//! inside it. Relative finding paths are taken to be relative to the root.
+
+use std::path::Path;
+
+use serde_json::Value;
+use serde_json::json;
+
+use crate::protocol::ReviewFinding;
+use crate::protocol::ReviewOutputEvent;
+
+const TOOL_NAME: &str = "codex";
+const TOOL_URL: &str = "https://github.com/openai/codex";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+/// `uriBaseId` the relative artifact locations are resolved against.
+const SRCROOT: &str = "%SRCROOT%";
+
+/// The review as a SARIF 2.1.0 log with a single run. Each finding becomes
+/// a result whose rule is its priority (`P0` to `P3`); the confidence score
+/// is kept in the result's properties and as its `rank` (0 to 100).
+pub fn review_output_to_sarif(review_output: &ReviewOutputEvent, root: &Path) -> Value {
+    let mut priorities: Vec<i32> = review_output
+        .findings
+        .iter()
+        .map(|finding| clamp_priority(finding.priority))
+        .collect();
+    priorities.sort_unstable();
+    priorities.dedup();
+    let rules: Vec<Value> = priorities
+        .iter()
+        .map(|&priority| {
+            json!({
+                "id": rule_id(priority),
+                "name": format!("ReviewFindingP{priority}"),
+                "shortDescription": { "text": format!("P{priority} review finding") },
+                "defaultConfiguration": { "level": sarif_level(priority) },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = review_output
+        .findings
+        .iter()
+        .map(|finding| {
+            let (start, end) = line_range(finding);
+            let priority = clamp_priority(finding.priority);
+            json!({
+                "ruleId": rule_id(priority),
+                "ruleIndex": priorities.iter().position(|&p| p == priority),
+                "level": sarif_level(priority),
+                "message": { "text": message_text(finding) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": artifact_location(finding, root),
+                        "region": { "startLine": start, "endLine": end },
+                    },
+                }],
+                "rank": confidence_rank(finding.confidence_score),
+                "properties": {
+                    "priority": finding.priority,
+                    "confidence_score": finding.confidence_score,
+                },
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": TOOL_NAME,
+                    "informationUri": TOOL_URL,
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": {
+                SRCROOT: { "uri": directory_uri(root) },
+            },
+            "results": results,
+            "properties": {
+                "overall_correctness": review_output.overall_correctness,
+                "overall_explanation": review_output.overall_explanation,
+                "overall_confidence_score": review_output.overall_confidence_score,
+            },
+        }],
+    })
+}
+
+/// The review as a reviewdog Diagnostic Format (rdjson) result. rdjson has
+/// no field for the confidence score, so it is appended to the message.
+pub fn review_output_to_rdjson(review_output: &ReviewOutputEvent, root: &Path) -> Value {
+    let diagnostics: Vec<Value> = review_output
+        .findings
+        .iter()
+        .map(|finding| {
+            let (start, end) = line_range(finding);
+            let priority = clamp_priority(finding.priority);
+            json!({
+                "message": format!(
+                    "{}\n\nConfidence: {:.2}",
+                    message_text(finding),
+                    finding.confidence_score
+                ),
+                "location": {
+                    "path": report_path(&finding.code_location.absolute_file_path, root),
+                    "range": {
+                        "start": { "line": start },
+                        "end": { "line": end },
+                    },
+                },
+                "severity": rdjson_severity(priority),
+                "code": { "value": rule_id(priority) },
+            })
+        })
+        .collect();
+
+    json!({
+        "source": { "name": TOOL_NAME, "url": TOOL_URL },
+        "diagnostics": diagnostics,
+    })
+}
+
+/// Priorities outside P0..P3 are treated as the nearest valid one.
+fn clamp_priority(priority: i32) -> i32 {
+    priority.clamp(0, 3)
+}
+
+fn rule_id(priority: i32) -> String {
+    format!("P{priority}")
+}
+
+fn sarif_level(priority: i32) -> &'static str {
+    match priority {
+        0 | 1 => "error",
+        2 => "warning",
+        _ => "note",
+    }
+}
+
+fn rdjson_severity(priority: i32) -> &'static str {
+    match priority {
+        0 | 1 => "ERROR",
+        2 => "WARNING",
+        _ => "INFO",
+    }
+}
+
+fn confidence_rank(confidence_score: f32) -> f64 {
+    let rank = f64::from(confidence_score.clamp(0.0, 1.0)) * 100.0;
+    (rank * 10.0).round() / 10.0
+}
+
+fn message_text(finding: &ReviewFinding) -> String {
+    let body = finding.body.trim();
+    if body.is_empty() {
+        finding.title.clone()
+    } else {
+        format!("{}\n\n{body}", finding.title)
+    }
+}
+
+/// Line numbers are 1-based in both formats; the range is normalized so
+/// that it is never empty or reversed.
+fn line_range(finding: &ReviewFinding) -> (u32, u32) {
+    let range = &finding.code_location.line_range;
+    let start = range.start.max(1);
+    (start, range.end.max(start))
+}
+
+fn artifact_location(finding: &ReviewFinding, root: &Path) -> Value {
+    let path = root.join(&finding.code_location.absolute_file_path);
+    match path.strip_prefix(root) {
+        Ok(relative) => json!({
+            "uri": encode_uri_path(&slash_path(relative)),
+            "uriBaseId": SRCROOT,
+        }),
+        Err(_) => json!({ "uri": file_uri(&path) }),
+    }
+}
+
+fn report_path(path: &Path, root: &Path) -> String {
+    // Joining keeps absolute paths as they are.
+    let path = root.join(path);
+    match path.strip_prefix(root) {
+        Ok(relative) => slash_path(relative),
+        Err(_) => slash_path(&path),
+    }
+}
+
+fn directory_uri(path: &Path) -> String {
+    let uri = file_uri(path);
+    if uri.ends_with('/') {
+        uri
+    } else {
+        format!("{uri}/")
+    }
+}
+
+fn file_uri(path: &Path) -> String {
+    let path = slash_path(path);
+    if path.starts_with('/') {
+        format!("file://{}", encode_uri_path(&path))
+    } else {
+        // Windows paths such as `C:/repo`.
+        format!("file:///{}", encode_uri_path(&path))
+    }
+}
+
+fn slash_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Percent-encode everything but unreserved characters, `/` and the drive
+/// letter colon.
+fn encode_uri_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                encoded.push(char::from(byte));
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ReviewCodeLocation;
+    use crate::protocol::ReviewLineRange;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn finding(path: &str, priority: i32, start: u32, end: u32) -> ReviewFinding {
+        ReviewFinding {
+            title: format!("[P{priority}] Something is off"),
+            body: "Details.".to_string(),
+            confidence_score: 0.8,
+            priority,
+            code_location: ReviewCodeLocation {
+                absolute_file_path: PathBuf::from(path),
+                line_range: ReviewLineRange { start, end },
+            },
+        }
+    }
+
+    fn review_output(findings: Vec<ReviewFinding>) -> ReviewOutputEvent {
+        ReviewOutputEvent {
+            findings,
+            overall_correctness: "patch is incorrect".to_string(),
+            overall_explanation: "Needs work.".to_string(),
+            overall_confidence_score: 0.6,
+        }
+    }
+
+    #[test]
+    fn sarif_maps_priorities_to_levels_and_relative_regions() {
+        let output = review_output(vec![
+            finding("/repo/src/my lib.rs", 2, 10, 12),
+            finding("/repo/src/main.rs", 0, 3, 3),
+            finding("/elsewhere/notes.txt", 3, 0, 0),
+            finding("src/relative.rs", 1, 5, 7),
+        ]);
+        let sarif = review_output_to_sarif(&output, Path::new("/repo"));
+
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(
+            run["originalUriBaseIds"]["%SRCROOT%"]["uri"],
+            "file:///repo/"
+        );
+        let rules: Vec<&Value> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|rule| &rule["id"])
+            .collect();
+        assert_eq!(rules, vec!["P0", "P1", "P2", "P3"]);
+
+        let results = &run["results"];
+        assert_eq!(
+            results[0],
+            json!({
+                "ruleId": "P2",
+                "ruleIndex": 2,
+                "level": "warning",
+                "message": { "text": "[P2] Something is off\n\nDetails." },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "src/my%20lib.rs", "uriBaseId": "%SRCROOT%" },
+                        "region": { "startLine": 10, "endLine": 12 },
+                    },
+                }],
+                "rank": 80.0,
+                "properties": { "priority": 2, "confidence_score": 0.8_f32 },
+            })
+        );
+        assert_eq!(results[1]["level"], "error");
+        assert_eq!(
+            results[2]["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "file:///elsewhere/notes.txt" },
+                "region": { "startLine": 1, "endLine": 1 },
+            })
+        );
+        assert_eq!(
+            results[3]["locations"][0]["physicalLocation"]["artifactLocation"],
+            json!({ "uri": "src/relative.rs", "uriBaseId": "%SRCROOT%" })
+        );
+    }
+
+    #[test]
+    fn rdjson_reports_severity_range_and_confidence() {
+        let output = review_output(vec![
+            finding("/repo/src/main.rs", 1, 4, 6),
+            finding("src/relative.rs", 2, 1, 1),
+        ]);
+        let rdjson = review_output_to_rdjson(&output, Path::new("/repo"));
+
+        assert_eq!(
+            rdjson,
+            json!({
+                "source": { "name": "codex", "url": "https://github.com/openai/codex" },
+                "diagnostics": [{
+                    "message": "[P1] Something is off\n\nDetails.\n\nConfidence: 0.80",
+                    "location": {
+                        "path": "src/main.rs",
+                        "range": { "start": { "line": 4 }, "end": { "line": 6 } },
+                    },
+                    "severity": "ERROR",
+                    "code": { "value": "P1" },
+                }, {
+                    "message": "[P2] Something is off\n\nDetails.\n\nConfidence: 0.80",
+                    "location": {
+                        "path": "src/relative.rs",
+                        "range": { "start": { "line": 1 }, "end": { "line": 1 } },
+                    },
+                    "severity": "WARNING",
+                    "code": { "value": "P2" },
+                }],
+            })
+        );
+    }
+}
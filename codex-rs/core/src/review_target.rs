@@ -0,0 +1,210 @@
+//! Diffs of the changes a [`ReviewTarget`] covers, computed with git, and
+//! the review prompts built around them.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use thiserror::Error;
+use tokio::process::Command;
+
+use crate::git_info::get_git_repo_root;
+use crate::protocol::ReviewRequest;
+use crate::protocol::ReviewTarget;
+use crate::review_format::format_diff_with_line_anchors;
+use crate::tokenizer::Tokenizer;
+use crate::truncate::truncate_middle;
+
+/// Largest part of the diff handed to the reviewer.
+const REVIEW_DIFF_MAX_TOKENS: u64 = 40_000;
+
+#[derive(Debug, Error)]
+pub enum ReviewTargetError {
+    #[error("git {command} failed: {stderr}")]
+    Git { command: String, stderr: String },
+    #[error("`{0}` is not a commit")]
+    InvalidRevision(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A review request for `target` with the default instructions.
+pub fn review_request_for_target(target: ReviewTarget) -> ReviewRequest {
+    let (prompt, user_facing_hint) = match &target {
+        ReviewTarget::WorkingTree => (
+            "Review the current code changes (staged, unstaged, and untracked files) and provide prioritized findings.".to_string(),
+            "current changes".to_string(),
+        ),
+        ReviewTarget::Staged => (
+            "Review the staged code changes and provide prioritized findings.".to_string(),
+            "staged changes".to_string(),
+        ),
+        ReviewTarget::Commit { sha } => (
+            format!("Review the code changes introduced by commit {sha}. Provide prioritized, actionable findings."),
+            format!("commit {}", sha.chars().take(7).collect::<String>()),
+        ),
+        ReviewTarget::CommitRange { base, head } => (
+            format!("Review the code changes between {base} and {head}. Provide prioritized, actionable findings."),
+            format!("{base}..{head}"),
+        ),
+        ReviewTarget::BaseBranch { branch } => (
+            format!("Review the code changes we would merge into the base branch '{branch}'. Provide prioritized, actionable findings."),
+            format!("changes against '{branch}'"),
+        ),
+        ReviewTarget::Files { paths } => {
+            let names = paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            (
+                format!("Review the code in {names}. Provide prioritized, actionable findings."),
+                names,
+            )
+        }
+    };
+    ReviewRequest {
+        prompt,
+        user_facing_hint,
+        target: Some(target),
+    }
+}
+
+/// The unified diff of the changes `target` covers, with paths relative to
+/// the root of the repository containing `cwd`. Files are diffed against
+/// an empty file, so their full contents show up as added lines.
+pub async fn review_target_diff(
+    cwd: &Path,
+    target: &ReviewTarget,
+) -> Result<String, ReviewTargetError> {
+    let root = get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf());
+    match target {
+        ReviewTarget::WorkingTree => {
+            let mut diff = git_diff(&root, &["HEAD"]).await?;
+            let untracked = run_git(
+                &root,
+                &["ls-files", "--others", "--exclude-standard", "--full-name"],
+            )
+            .await?;
+            for path in untracked.lines().filter(|line| !line.is_empty()) {
+                diff.push_str(&diff_against_empty(&root, Path::new(path)).await?);
+            }
+            Ok(diff)
+        }
+        ReviewTarget::Staged => git_diff(&root, &["--cached"]).await,
+        ReviewTarget::Commit { sha } => {
+            let sha = resolve_commit(&root, sha).await?;
+            run_git(
+                &root,
+                &["show", "--format=", "--no-textconv", "--no-ext-diff", &sha],
+            )
+            .await
+        }
+        ReviewTarget::CommitRange { base, head } => {
+            let base = resolve_commit(&root, base).await?;
+            let head = resolve_commit(&root, head).await?;
+            git_diff(&root, &[&format!("{base}..{head}")]).await
+        }
+        ReviewTarget::BaseBranch { branch } => {
+            let branch = resolve_commit(&root, branch).await?;
+            let merge_base = run_git(&root, &["merge-base", "HEAD", &branch]).await?;
+            git_diff(&root, &[merge_base.trim()]).await
+        }
+        ReviewTarget::Files { paths } => {
+            let mut diff = String::new();
+            for path in paths {
+                let path = relative_to_root(&root, &cwd.join(path));
+                diff.push_str(&diff_against_empty(&root, &path).await?);
+            }
+            Ok(diff)
+        }
+    }
+}
+
+/// `prompt` followed by `diff`, with every line that exists after the
+/// change prefixed by its line number so findings can point at it.
+pub(crate) fn review_prompt_with_diff(
+    prompt: &str,
+    diff: &str,
+    root: &Path,
+    tokenizer: Tokenizer,
+) -> String {
+    let (diff, _) = truncate_middle(
+        &format_diff_with_line_anchors(diff),
+        REVIEW_DIFF_MAX_TOKENS,
+        tokenizer,
+    );
+    format!(
+        "{prompt}\n\nThe changes to review are below. Paths in the diff are relative to {}; join them onto it for `absolute_file_path`. Each line that exists after the change starts with its line number in the changed file; use these numbers for `line_range`.\n\n```diff\n{diff}\n```",
+        root.display()
+    )
+}
+
+/// The commit id `revision` names. Revisions come from the user, so one
+/// that looks like an option is rejected before git can take it for one.
+async fn resolve_commit(root: &Path, revision: &str) -> Result<String, ReviewTargetError> {
+    if revision.starts_with('-') {
+        return Err(ReviewTargetError::InvalidRevision(revision.to_string()));
+    }
+    let commit = format!("{revision}^{{commit}}");
+    match run_git(root, &["rev-parse", "--verify", "--quiet", &commit]).await {
+        Ok(sha) => Ok(sha.trim().to_string()),
+        Err(ReviewTargetError::Git { .. }) => {
+            Err(ReviewTargetError::InvalidRevision(revision.to_string()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn relative_to_root(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+async fn diff_against_empty(root: &Path, path: &Path) -> Result<String, ReviewTargetError> {
+    let null_device = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    let path = path.to_string_lossy();
+    let args = [
+        "diff",
+        "--no-index",
+        "--no-textconv",
+        "--no-ext-diff",
+        "--",
+        null_device,
+        path.as_ref(),
+    ];
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .await?;
+    // `--no-index` exits with 1 when the files differ.
+    if output.status.code() != Some(1) && !output.status.success() {
+        return Err(git_error(&args, &output.stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn git_diff(root: &Path, args: &[&str]) -> Result<String, ReviewTargetError> {
+    let args = [&["diff", "--no-textconv", "--no-ext-diff"][..], args].concat();
+    run_git(root, &args).await
+}
+
+async fn run_git(root: &Path, args: &[&str]) -> Result<String, ReviewTargetError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(git_error(args, &output.stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn git_error(args: &[&str], stderr: &[u8]) -> ReviewTargetError {
+    ReviewTargetError::Git {
+        command: args.join(" "),
+        stderr: String::from_utf8_lossy(stderr).trim().to_string(),
+    }
+}
@@ -0,0 +1,81 @@
+//! zstd compression of rollout files that are no longer being written to.
+//!
+//! A compressed rollout keeps its name and gains a `.zst` suffix
+//! (`rollout-…-<uuid>.jsonl.zst`). Readers go through
+//! [`read_rollout_to_string`] so they never need to know which form a
+//! session is stored in; a compressed session that is resumed is
+//! decompressed again first, since the recorder only appends plain JSONL.
+
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Suffix appended to the name of a compressed rollout file.
+pub const COMPRESSED_SUFFIX: &str = ".zst";
+
+const COMPRESSION_LEVEL: i32 = 19;
+
+pub(crate) fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "zst")
+}
+
+/// Contents of the rollout at `path`, decompressing it if necessary.
+pub(crate) fn read_rollout_bytes(path: &Path) -> io::Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    if is_compressed(path) {
+        zstd::decode_all(bytes.as_slice())
+    } else {
+        Ok(bytes)
+    }
+}
+
+pub(crate) async fn read_rollout_to_string(path: &Path) -> io::Result<String> {
+    let bytes = tokio::fs::read(path).await?;
+    let bytes = if is_compressed(path) {
+        tokio::task::spawn_blocking(move || zstd::decode_all(bytes.as_slice()))
+            .await
+            .map_err(|e| io::Error::other(format!("decompression task failed: {e}")))??
+    } else {
+        bytes
+    };
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Replace the rollout at `path` with a compressed copy and return the path
+/// of the copy. The modification time is preserved so that retention
+/// limits still see when the session was last written.
+pub(crate) fn compress_rollout(path: &Path) -> io::Result<PathBuf> {
+    let mut target = path.as_os_str().to_owned();
+    target.push(COMPRESSED_SUFFIX);
+    let target = PathBuf::from(target);
+
+    let contents = fs::read(path)?;
+    let compressed = zstd::encode_all(contents.as_slice(), COMPRESSION_LEVEL)?;
+    replace_with(path, &target, &compressed)?;
+    Ok(target)
+}
+
+/// Replace the compressed rollout at `path` with its plain JSONL contents
+/// and return the path of the decompressed file.
+pub(crate) fn decompress_rollout(path: &Path) -> io::Result<PathBuf> {
+    let target = path.with_extension("");
+    let contents = read_rollout_bytes(path)?;
+    replace_with(path, &target, &contents)?;
+    Ok(target)
+}
+
+/// Write `contents` to `target` through a temporary file, carry over the
+/// modification time of `source`, then remove `source`.
+fn replace_with(source: &Path, target: &Path, contents: &[u8]) -> io::Result<()> {
+    let modified = fs::metadata(source)?.modified()?;
+    let tmp = target.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&tmp, contents)?;
+    File::options()
+        .write(true)
+        .open(&tmp)?
+        .set_modified(modified)?;
+    fs::rename(&tmp, target)?;
+    fs::remove_file(source)
+}
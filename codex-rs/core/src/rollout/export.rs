@@ -0,0 +1,817 @@
+//! Render recorded sessions as Markdown, HTML, or a self-contained JSON bundle.
+//!
+//! A rollout is first condensed into a [`SessionTranscript`]: the user and
+//! agent messages, reasoning summaries, commands with their output, patches
+//! (converted to unified diffs), other tool calls, and the final token usage.
+//! The transcript is what the JSON bundle contains and what the Markdown and
+//! HTML renderers walk.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::Hunk;
+use codex_apply_patch::parse_patch;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellToolCallParams;
+use codex_protocol::models::WebSearchAction;
+use codex_protocol::protocol::FinalOutput;
+use codex_protocol::protocol::InitialHistory;
+use codex_protocol::protocol::InputMessageKind;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::TokenUsage;
+use codex_protocol::protocol::USER_MESSAGE_BEGIN;
+use serde::Serialize;
+use similar::ChangeTag;
+use similar::TextDiff;
+
+use super::RolloutRecorder;
+use crate::protocol::EventMsg;
+
+/// Version of the JSON bundle layout, bumped on incompatible changes.
+pub const EXPORT_BUNDLE_VERSION: u32 = 1;
+
+const REDACTED_OUTPUT: &str = "[output redacted]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExportOptions {
+    /// Replace the output of commands and tool calls with a placeholder.
+    pub redact_outputs: bool,
+    /// Rewrite absolute paths relative to the session cwd (`<cwd>`) and the
+    /// home directory (`~`).
+    pub redact_paths: bool,
+}
+
+/// A rollout condensed to what is worth reading or sharing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionTranscript {
+    pub version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<TranscriptSession>,
+    pub entries: Vec<TranscriptEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptSession {
+    pub id: String,
+    pub timestamp: String,
+    pub cwd: PathBuf,
+    pub cli_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptEntry {
+    UserMessage {
+        text: String,
+    },
+    AgentMessage {
+        text: String,
+    },
+    Reasoning {
+        text: String,
+    },
+    Command {
+        call_id: String,
+        command: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_code: Option<i32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+    },
+    Patch {
+        call_id: String,
+        diff: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+    },
+    ToolCall {
+        call_id: String,
+        name: String,
+        arguments: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+    },
+    WebSearch {
+        query: String,
+    },
+    /// Earlier turns were replaced by this summary.
+    Compacted {
+        summary: String,
+    },
+}
+
+/// Read the rollout at `path` and render it in `format`.
+pub async fn export_session(
+    path: &Path,
+    format: ExportFormat,
+    options: ExportOptions,
+) -> io::Result<String> {
+    let items = match RolloutRecorder::get_rollout_history(path).await? {
+        InitialHistory::Resumed(resumed) => resumed.history,
+        InitialHistory::New | InitialHistory::Forked(_) => Vec::new(),
+    };
+    let mut transcript = SessionTranscript::from_rollout_items(&items);
+    transcript.redact(options);
+    transcript.render(format)
+}
+
+impl SessionTranscript {
+    pub fn from_rollout_items(items: &[RolloutItem]) -> Self {
+        let mut transcript = SessionTranscript {
+            version: EXPORT_BUNDLE_VERSION,
+            session: None,
+            entries: Vec::new(),
+            token_usage: None,
+            cost_usd: None,
+        };
+        // Index of the entry awaiting the output of each call.
+        let mut pending_calls: HashMap<String, usize> = HashMap::new();
+
+        for item in items {
+            match item {
+                RolloutItem::SessionMeta(meta_line) => {
+                    if transcript.session.is_none() {
+                        let git = meta_line.git.as_ref();
+                        transcript.session = Some(TranscriptSession {
+                            id: meta_line.meta.id.to_string(),
+                            timestamp: meta_line.meta.timestamp.clone(),
+                            cwd: meta_line.meta.cwd.clone(),
+                            cli_version: meta_line.meta.cli_version.clone(),
+                            model: None,
+                            git_branch: git.and_then(|g| g.branch.clone()),
+                            git_commit: git.and_then(|g| g.commit_hash.clone()),
+                        });
+                    }
+                }
+                RolloutItem::TurnContext(ctx) => {
+                    if let Some(session) = transcript.session.as_mut() {
+                        session.model.get_or_insert_with(|| ctx.model.clone());
+                    }
+                }
+                RolloutItem::Compacted(compacted) => {
+                    transcript.entries.push(TranscriptEntry::Compacted {
+                        summary: compacted.message.clone(),
+                    });
+                }
+                RolloutItem::EventMsg(EventMsg::TokenCount(ev)) => {
+                    if let Some(info) = &ev.info {
+                        transcript.token_usage = Some(info.total_token_usage.clone());
+                        transcript.cost_usd = info.total_cost_usd;
+                    }
+                }
+                RolloutItem::EventMsg(_) | RolloutItem::Checkpoint(_) => {}
+                RolloutItem::ResponseItem(item) => {
+                    transcript.push_response_item(item, &mut pending_calls);
+                }
+            }
+        }
+        transcript
+    }
+
+    fn push_response_item(&mut self, item: &ResponseItem, pending: &mut HashMap<String, usize>) {
+        let entry = match item {
+            ResponseItem::Message { role, content, .. } => {
+                let text = message_text(role, content);
+                if text.is_empty() {
+                    return;
+                }
+                if role == "user" {
+                    TranscriptEntry::UserMessage { text }
+                } else {
+                    TranscriptEntry::AgentMessage { text }
+                }
+            }
+            ResponseItem::Reasoning { summary, .. } => {
+                let text = summary
+                    .iter()
+                    .map(|ReasoningItemReasoningSummary::SummaryText { text }| text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                if text.trim().is_empty() {
+                    return;
+                }
+                TranscriptEntry::Reasoning { text }
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action: LocalShellAction::Exec(action),
+                ..
+            } => {
+                let call_id = call_id.clone().or_else(|| id.clone()).unwrap_or_default();
+                command_entry(call_id, &action.command)
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => match name.as_str() {
+                "shell" | "container.exec" => {
+                    match serde_json::from_str::<ShellToolCallParams>(arguments) {
+                        Ok(params) => command_entry(call_id.clone(), &params.command),
+                        Err(_) => tool_call_entry(call_id, name, arguments),
+                    }
+                }
+                "apply_patch" => {
+                    match serde_json::from_str::<serde_json::Value>(arguments)
+                        .ok()
+                        .and_then(|v| v.get("input")?.as_str().map(str::to_string))
+                    {
+                        Some(patch) => patch_entry(call_id.clone(), &patch),
+                        None => tool_call_entry(call_id, name, arguments),
+                    }
+                }
+                _ => tool_call_entry(call_id, name, arguments),
+            },
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                if name == "apply_patch" {
+                    patch_entry(call_id.clone(), input)
+                } else {
+                    tool_call_entry(call_id, name, input)
+                }
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                self.attach_output(pending, call_id, function_output(output));
+                return;
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                self.attach_output(pending, call_id, (output.clone(), None));
+                return;
+            }
+            ResponseItem::WebSearchCall {
+                action: WebSearchAction::Search { query },
+                ..
+            } => TranscriptEntry::WebSearch {
+                query: query.clone(),
+            },
+            ResponseItem::WebSearchCall { .. } | ResponseItem::Other => return,
+        };
+
+        if let TranscriptEntry::Command { call_id, .. }
+        | TranscriptEntry::Patch { call_id, .. }
+        | TranscriptEntry::ToolCall { call_id, .. } = &entry
+        {
+            pending.insert(call_id.clone(), self.entries.len());
+        }
+        self.entries.push(entry);
+    }
+
+    fn attach_output(
+        &mut self,
+        pending: &mut HashMap<String, usize>,
+        call_id: &str,
+        (text, code): (String, Option<i32>),
+    ) {
+        let Some(idx) = pending.remove(call_id) else {
+            return;
+        };
+        match &mut self.entries[idx] {
+            TranscriptEntry::Command {
+                output, exit_code, ..
+            } => {
+                *output = Some(text);
+                *exit_code = code;
+            }
+            TranscriptEntry::Patch { output, .. } | TranscriptEntry::ToolCall { output, .. } => {
+                *output = Some(text);
+            }
+            _ => {}
+        }
+    }
+
+    /// Apply the redactions requested in `options`.
+    pub fn redact(&mut self, options: ExportOptions) {
+        if options.redact_outputs {
+            for entry in &mut self.entries {
+                if let TranscriptEntry::Command { output, .. }
+                | TranscriptEntry::Patch { output, .. }
+                | TranscriptEntry::ToolCall { output, .. } = entry
+                    && output.is_some()
+                {
+                    *output = Some(REDACTED_OUTPUT.to_string());
+                }
+            }
+        }
+
+        if options.redact_paths {
+            let mut prefixes: Vec<(String, &str)> = Vec::new();
+            if let Some(session) = &self.session {
+                prefixes.push((session.cwd.to_string_lossy().into_owned(), "<cwd>"));
+            }
+            if let Some(home) = dirs::home_dir() {
+                prefixes.push((home.to_string_lossy().into_owned(), "~"));
+            }
+            // Replace the most specific prefix first (cwd is usually under home).
+            prefixes.retain(|(prefix, _)| prefix.len() > 1);
+            prefixes.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+            let redact = |text: &mut String| {
+                for (prefix, replacement) in &prefixes {
+                    if text.contains(prefix.as_str()) {
+                        *text = text.replace(prefix.as_str(), replacement);
+                    }
+                }
+            };
+
+            for entry in &mut self.entries {
+                match entry {
+                    TranscriptEntry::UserMessage { text }
+                    | TranscriptEntry::AgentMessage { text }
+                    | TranscriptEntry::Reasoning { text }
+                    | TranscriptEntry::WebSearch { query: text }
+                    | TranscriptEntry::Compacted { summary: text } => redact(text),
+                    TranscriptEntry::Command {
+                        command, output, ..
+                    } => {
+                        redact(command);
+                        output.iter_mut().for_each(redact);
+                    }
+                    TranscriptEntry::Patch { diff, output, .. } => {
+                        redact(diff);
+                        output.iter_mut().for_each(redact);
+                    }
+                    TranscriptEntry::ToolCall {
+                        arguments, output, ..
+                    } => {
+                        redact(arguments);
+                        output.iter_mut().for_each(redact);
+                    }
+                }
+            }
+            if let Some(session) = self.session.as_mut() {
+                session.cwd = PathBuf::from("<cwd>");
+            }
+        }
+    }
+
+    pub fn render(&self, format: ExportFormat) -> io::Result<String> {
+        match format {
+            ExportFormat::Markdown => Ok(self.to_markdown()),
+            ExportFormat::Html => Ok(self.to_html()),
+            ExportFormat::Json => serde_json::to_string_pretty(self)
+                .map(|mut json| {
+                    json.push('\n');
+                    json
+                })
+                .map_err(io::Error::other),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let title = match &self.session {
+            Some(session) => format!("# Codex session {}\n\n", session.id),
+            None => "# Codex session\n\n".to_string(),
+        };
+        out.push_str(&title);
+        let details = self.details();
+        for (label, value) in &details {
+            let _ = writeln!(out, "- **{label}:** {value}");
+        }
+        if !details.is_empty() {
+            out.push('\n');
+        }
+
+        for entry in &self.entries {
+            match entry {
+                TranscriptEntry::UserMessage { text } => {
+                    let _ = write!(out, "## User\n\n{}\n\n", text.trim_end());
+                }
+                TranscriptEntry::AgentMessage { text } => {
+                    let _ = write!(out, "## Codex\n\n{}\n\n", text.trim_end());
+                }
+                TranscriptEntry::Reasoning { text } => {
+                    out.push_str("> **Reasoning**\n>\n");
+                    for line in text.trim_end().lines() {
+                        let _ = writeln!(out, "> {line}");
+                    }
+                    out.push('\n');
+                }
+                TranscriptEntry::Command {
+                    command,
+                    exit_code,
+                    output,
+                    ..
+                } => {
+                    out.push_str("### Command\n\n");
+                    push_fenced(&mut out, "sh", &format!("$ {command}"));
+                    if let Some(output) = output {
+                        push_fenced(&mut out, "text", output);
+                    }
+                    if let Some(code) = exit_code {
+                        let _ = write!(out, "Exit code: {code}\n\n");
+                    }
+                }
+                TranscriptEntry::Patch { diff, output, .. } => {
+                    out.push_str("### Patch\n\n");
+                    push_fenced(&mut out, "diff", diff);
+                    if let Some(output) = output {
+                        push_fenced(&mut out, "text", output);
+                    }
+                }
+                TranscriptEntry::ToolCall {
+                    name,
+                    arguments,
+                    output,
+                    ..
+                } => {
+                    let _ = write!(out, "### Tool call: `{name}`\n\n");
+                    push_fenced(&mut out, "json", arguments);
+                    if let Some(output) = output {
+                        push_fenced(&mut out, "text", output);
+                    }
+                }
+                TranscriptEntry::WebSearch { query } => {
+                    let _ = write!(out, "_Searched the web for:_ {query}\n\n");
+                }
+                TranscriptEntry::Compacted { summary } => {
+                    out.push_str("## Summary of earlier conversation\n\n");
+                    let _ = write!(out, "{}\n\n", summary.trim_end());
+                }
+            }
+        }
+
+        if let Some(totals) = self.totals() {
+            let _ = writeln!(out, "---\n\n{totals}");
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let title = match &self.session {
+            Some(session) => format!("Codex session {}", session.id),
+            None => "Codex session".to_string(),
+        };
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            escape_html(&title),
+            escape_html(&title),
+        );
+
+        let details = self.details();
+        if !details.is_empty() {
+            out.push_str("<dl>\n");
+            for (label, value) in details {
+                let _ = writeln!(
+                    out,
+                    "<dt>{}</dt><dd>{}</dd>",
+                    escape_html(label),
+                    escape_html(&value)
+                );
+            }
+            out.push_str("</dl>\n");
+        }
+
+        for entry in &self.entries {
+            match entry {
+                TranscriptEntry::UserMessage { text } => {
+                    push_html_message(&mut out, "user", "User", text);
+                }
+                TranscriptEntry::AgentMessage { text } => {
+                    push_html_message(&mut out, "agent", "Codex", text);
+                }
+                TranscriptEntry::Reasoning { text } => {
+                    push_html_message(&mut out, "reasoning", "Reasoning", text);
+                }
+                TranscriptEntry::Command {
+                    command,
+                    exit_code,
+                    output,
+                    ..
+                } => {
+                    out.push_str("<section class=\"tool\">\n<h2>Command</h2>\n");
+                    let _ = writeln!(
+                        out,
+                        "<pre class=\"command\">$ {}</pre>",
+                        escape_html(command)
+                    );
+                    if let Some(output) = output {
+                        let _ =
+                            writeln!(out, "<pre class=\"output\">{}</pre>", escape_html(output));
+                    }
+                    if let Some(code) = exit_code {
+                        let _ = writeln!(out, "<p class=\"exit\">Exit code: {code}</p>");
+                    }
+                    out.push_str("</section>\n");
+                }
+                TranscriptEntry::Patch { diff, output, .. } => {
+                    out.push_str("<section class=\"tool\">\n<h2>Patch</h2>\n<pre class=\"diff\">");
+                    for line in diff.lines() {
+                        let class = match line.chars().next() {
+                            Some('+') if !line.starts_with("+++") => "add",
+                            Some('-') if !line.starts_with("---") => "del",
+                            Some('@') => "hunk",
+                            _ => "ctx",
+                        };
+                        let _ =
+                            writeln!(out, "<span class=\"{class}\">{}</span>", escape_html(line));
+                    }
+                    out.push_str("</pre>\n");
+                    if let Some(output) = output {
+                        let _ =
+                            writeln!(out, "<pre class=\"output\">{}</pre>", escape_html(output));
+                    }
+                    out.push_str("</section>\n");
+                }
+                TranscriptEntry::ToolCall {
+                    name,
+                    arguments,
+                    output,
+                    ..
+                } => {
+                    let _ = writeln!(
+                        out,
+                        "<section class=\"tool\">\n<h2>Tool call: <code>{}</code></h2>\n<pre>{}</pre>",
+                        escape_html(name),
+                        escape_html(arguments)
+                    );
+                    if let Some(output) = output {
+                        let _ =
+                            writeln!(out, "<pre class=\"output\">{}</pre>", escape_html(output));
+                    }
+                    out.push_str("</section>\n");
+                }
+                TranscriptEntry::WebSearch { query } => {
+                    let _ = writeln!(
+                        out,
+                        "<p class=\"search\">Searched the web for: {}</p>",
+                        escape_html(query)
+                    );
+                }
+                TranscriptEntry::Compacted { summary } => {
+                    push_html_message(
+                        &mut out,
+                        "compacted",
+                        "Summary of earlier conversation",
+                        summary,
+                    );
+                }
+            }
+        }
+
+        if let Some(totals) = self.totals() {
+            let _ = writeln!(out, "<footer>{}</footer>", escape_html(&totals));
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    fn details(&self) -> Vec<(&'static str, String)> {
+        let Some(session) = &self.session else {
+            return Vec::new();
+        };
+        let mut details = vec![
+            ("Started", session.timestamp.clone()),
+            ("Directory", session.cwd.display().to_string()),
+        ];
+        if let Some(model) = &session.model {
+            details.push(("Model", model.clone()));
+        }
+        if let Some(branch) = &session.git_branch {
+            details.push(("Branch", branch.clone()));
+        }
+        if let Some(commit) = &session.git_commit {
+            details.push(("Commit", commit.clone()));
+        }
+        details.push(("Codex version", session.cli_version.clone()));
+        details
+    }
+
+    fn totals(&self) -> Option<String> {
+        let usage = self.token_usage.clone()?;
+        let mut totals = FinalOutput::from(usage).to_string();
+        if let Some(cost) = self.cost_usd {
+            let _ = write!(totals, " (estimated cost ${cost:.4})");
+        }
+        Some(totals)
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:60rem;margin:2rem auto;padding:0 1rem;line-height:1.5}\
+dl{display:grid;grid-template-columns:max-content auto;gap:.25rem 1rem}dt{font-weight:bold}dd{margin:0}\
+section{margin:1rem 0;padding:.5rem 1rem;border-radius:6px}\
+.user{background:#eef4ff}.agent{background:#f4f4f4}.reasoning{color:#555;font-style:italic}.compacted{background:#fff8e1}\
+.tool{border:1px solid #ddd}h2{font-size:1rem;margin:.25rem 0}\
+pre{white-space:pre-wrap;overflow-wrap:anywhere;background:#fafafa;padding:.5rem}\
+.message{white-space:pre-wrap}.add{color:#1a7f37}.del{color:#cf222e}.hunk{color:#8250df}\
+footer{margin-top:2rem;color:#555}";
+
+/// Text of a message, skipping the instructions and environment context
+/// Codex injects as user messages.
+fn message_text(role: &str, content: &[ContentItem]) -> String {
+    let mut parts = Vec::new();
+    for item in content {
+        match item {
+            ContentItem::InputText { text } => {
+                if !matches!(
+                    InputMessageKind::from((role, text.as_str())),
+                    InputMessageKind::Plain
+                ) {
+                    continue;
+                }
+                // Strip ide context.
+                let text = match text.find(USER_MESSAGE_BEGIN) {
+                    Some(idx) => text[idx + USER_MESSAGE_BEGIN.len()..].trim(),
+                    None => text.trim(),
+                };
+                parts.push(text.to_string());
+            }
+            ContentItem::OutputText { text } => parts.push(text.trim().to_string()),
+            ContentItem::InputImage { .. } => parts.push("[image]".to_string()),
+        }
+    }
+    parts.retain(|p| !p.is_empty());
+    parts.join("\n\n")
+}
+
+fn command_entry(call_id: String, command: &[String]) -> TranscriptEntry {
+    // `apply_patch` can also be invoked through the shell tool.
+    if let [program, patch] = command
+        && program == "apply_patch"
+    {
+        return patch_entry(call_id, patch);
+    }
+    let command = match command {
+        [shell, flag, script] if flag == "-lc" && (shell == "bash" || shell == "zsh") => {
+            script.clone()
+        }
+        _ => shlex::try_join(command.iter().map(String::as_str))
+            .unwrap_or_else(|_| command.join(" ")),
+    };
+    TranscriptEntry::Command {
+        call_id,
+        command,
+        exit_code: None,
+        output: None,
+    }
+}
+
+fn patch_entry(call_id: String, patch: &str) -> TranscriptEntry {
+    TranscriptEntry::Patch {
+        call_id,
+        diff: patch_to_unified_diff(patch),
+        output: None,
+    }
+}
+
+fn tool_call_entry(call_id: &str, name: &str, arguments: &str) -> TranscriptEntry {
+    TranscriptEntry::ToolCall {
+        call_id: call_id.to_string(),
+        name: name.to_string(),
+        arguments: arguments.to_string(),
+        output: None,
+    }
+}
+
+/// Output text and exit code of a function call. Shell calls report their
+/// output as `{"output": ..., "metadata": {"exit_code": ...}}`.
+fn function_output(payload: &FunctionCallOutputPayload) -> (String, Option<i32>) {
+    #[derive(serde::Deserialize)]
+    struct ExecOutput {
+        output: String,
+        metadata: ExecMetadata,
+    }
+    #[derive(serde::Deserialize)]
+    struct ExecMetadata {
+        exit_code: i32,
+    }
+
+    match serde_json::from_str::<ExecOutput>(&payload.content) {
+        Ok(exec) => (exec.output, Some(exec.metadata.exit_code)),
+        Err(_) => (payload.content.clone(), None),
+    }
+}
+
+/// Convert an `apply_patch` envelope into a unified diff. Patches that do
+/// not parse are returned unchanged.
+fn patch_to_unified_diff(patch: &str) -> String {
+    let Ok(hunks) = parse_patch(patch).map(|args| args.hunks) else {
+        return patch.to_string();
+    };
+    let mut out = String::new();
+    for hunk in hunks {
+        match hunk {
+            Hunk::AddFile { path, contents } => {
+                let _ = writeln!(out, "--- /dev/null\n+++ b/{}", path.display());
+                let _ = writeln!(out, "@@ -0,0 +1,{} @@", contents.lines().count());
+                for line in contents.lines() {
+                    let _ = writeln!(out, "+{line}");
+                }
+            }
+            Hunk::DeleteFile { path } => {
+                let _ = writeln!(out, "--- a/{}\n+++ /dev/null", path.display());
+            }
+            Hunk::UpdateFile {
+                path,
+                move_path,
+                chunks,
+            } => {
+                let new_path = move_path.as_ref().unwrap_or(&path);
+                let _ = writeln!(
+                    out,
+                    "--- a/{}\n+++ b/{}",
+                    path.display(),
+                    new_path.display()
+                );
+                for chunk in chunks {
+                    match &chunk.change_context {
+                        Some(context) => {
+                            let _ = writeln!(out, "@@ {context} @@");
+                        }
+                        None => out.push_str("@@\n"),
+                    }
+                    let old = join_lines(&chunk.old_lines);
+                    let new = join_lines(&chunk.new_lines);
+                    for change in TextDiff::from_lines(&old, &new).iter_all_changes() {
+                        let sign = match change.tag() {
+                            ChangeTag::Delete => '-',
+                            ChangeTag::Insert => '+',
+                            ChangeTag::Equal => ' ',
+                        };
+                        let _ = writeln!(out, "{sign}{}", change.value().trim_end_matches('\n'));
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+fn join_lines(lines: &[String]) -> String {
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+/// Append `text` as a fenced code block, using a fence longer than any
+/// backtick run inside it.
+fn push_fenced(out: &mut String, lang: &str, text: &str) {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    let _ = write!(
+        out,
+        "{fence}{lang}\n{}\n{fence}\n\n",
+        text.trim_end_matches('\n')
+    );
+}
+
+fn push_html_message(out: &mut String, class: &str, label: &str, text: &str) {
+    let _ = writeln!(
+        out,
+        "<section class=\"{class}\">\n<h2>{label}</h2>\n<div class=\"message\">{}</div>\n</section>",
+        escape_html(text.trim_end())
+    );
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,609 @@
+//! Local, incrementally-maintained index of recorded sessions.
+//!
+//! The index lives in a SQLite database at `~/.codex/session_index.sqlite`
+//! and mirrors the rollout files under `~/.codex/sessions`:
+//!
+//! * `sessions` holds one row per rollout file with the metadata used for
+//!   filtering (cwd, git branch, model, start time) and the preview shown in
+//!   listings.
+//! * `session_text` is an FTS5 table with the user and agent messages of
+//!   every session, used for full-text search.
+//!
+//! Every row remembers how many bytes of its rollout file have been indexed,
+//! so keeping the index current only ever parses lines that were appended
+//! since the last update. [`RolloutRecorder`](super::RolloutRecorder) updates
+//! the index as it writes; [`SessionIndex::sync`] picks up anything written
+//! by older versions of Codex or while the index was unavailable.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::{self};
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::Utc;
+use codex_protocol::mcp_protocol::ConversationId;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension;
+use rusqlite::params;
+use rusqlite::params_from_iter;
+use rusqlite::types::Value as SqlValue;
+use serde::Serialize;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use tracing::warn;
+
+use super::SESSIONS_SUBDIR;
+use super::compression::is_compressed;
+use super::compression::read_rollout_bytes;
+use super::list::parse_timestamp_uuid_from_filename;
+use crate::protocol::EventMsg;
+use codex_protocol::protocol::InputMessageKind;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use codex_protocol::protocol::USER_MESSAGE_BEGIN;
+
+/// Filename of the session index inside `~/.codex`.
+pub const SESSION_INDEX_FILENAME: &str = "session_index.sqlite";
+
+/// Default number of hits returned by [`SessionIndex::search`].
+pub const DEFAULT_SEARCH_LIMIT: usize = 25;
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Length of the snippet (in tokens) returned for full-text matches.
+const SNIPPET_TOKENS: i64 = 12;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS sessions (
+    path TEXT PRIMARY KEY,
+    id TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    timestamp TEXT,
+    cwd TEXT,
+    git_branch TEXT,
+    model TEXT,
+    preview TEXT,
+    indexed_bytes INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS sessions_started_at ON sessions(started_at);
+CREATE TABLE IF NOT EXISTS session_models (
+    path TEXT NOT NULL,
+    model TEXT NOT NULL,
+    PRIMARY KEY (path, model)
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS session_text USING fts5(
+    path UNINDEXED,
+    role UNINDEXED,
+    text
+);
+"#;
+
+/// Filters for [`SessionIndex::search`]. All filters are combined with AND;
+/// an empty query lists every session, newest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionQuery {
+    /// Words that must all appear in the user or agent messages. The last
+    /// word also matches as a prefix so the query can be typed incrementally.
+    pub text: Option<String>,
+    /// Sessions started in this directory or one of its subdirectories.
+    pub cwd: Option<PathBuf>,
+    /// Sessions started on this git branch.
+    pub git_branch: Option<String>,
+    /// Sessions in which this model ran at least one turn.
+    pub model: Option<String>,
+    /// Sessions started at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Sessions started before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Maximum number of hits; [`DEFAULT_SEARCH_LIMIT`] when zero.
+    pub limit: usize,
+    /// Number of hits to skip, for pagination.
+    pub offset: usize,
+}
+
+/// A session matching a [`SessionQuery`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionHit {
+    pub conversation_id: ConversationId,
+    /// Absolute path to the rollout file.
+    pub path: PathBuf,
+    /// Session start as recorded in the session metadata.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// Model of the most recent turn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// First user message of the session.
+    pub preview: String,
+    /// Excerpt of the best-matching message when searching by text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+/// Connection to the session index database.
+pub struct SessionIndex {
+    conn: Connection,
+}
+
+impl SessionIndex {
+    /// Open (creating if necessary) the index stored under `codex_home`.
+    pub fn open(codex_home: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(codex_home)?;
+        let conn = Connection::open(session_index_path(codex_home)).map_err(io::Error::other)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(io::Error::other)?;
+        // WAL lets concurrent Codex processes read while a recorder writes.
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))
+            .map_err(io::Error::other)?;
+        conn.execute_batch(SCHEMA).map_err(io::Error::other)?;
+        Ok(Self { conn })
+    }
+
+    /// Bring the index up to date with every rollout file under
+    /// `codex_home`, and forget files that no longer exist.
+    pub fn sync(&mut self, codex_home: &Path) -> io::Result<()> {
+        let root = codex_home.join(SESSIONS_SUBDIR);
+        let mut paths = Vec::new();
+        if root.exists() {
+            collect_rollout_paths(&root, &mut paths)?;
+        }
+
+        for path in &paths {
+            if let Err(e) = self.index_file(path) {
+                warn!("failed to index rollout {}: {e}", path.display());
+            }
+        }
+
+        let present: HashSet<String> = paths
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        let indexed: Vec<String> = {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT path FROM sessions")
+                .map_err(io::Error::other)?;
+            stmt.query_map([], |row| row.get(0))
+                .map_err(io::Error::other)?
+                .collect::<Result<_, _>>()
+                .map_err(io::Error::other)?
+        };
+        for path in indexed.iter().filter(|p| !present.contains(*p)) {
+            self.remove(path)?;
+        }
+        Ok(())
+    }
+
+    /// Index the lines of the rollout file at `path` that were appended
+    /// since it was last indexed. A trailing line without a newline is left
+    /// for the next update since it may still be being written.
+    pub fn index_file(&mut self, path: &Path) -> io::Result<()> {
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            return Ok(());
+        };
+        let Some((file_ts, file_id)) = parse_timestamp_uuid_from_filename(file_name) else {
+            return Ok(());
+        };
+        let key = path.to_string_lossy().into_owned();
+        let len = std::fs::metadata(path)?.len() as i64;
+
+        let indexed_bytes: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT indexed_bytes FROM sessions WHERE path = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(io::Error::other)?;
+        let start = match indexed_bytes {
+            Some(n) if n == len => return Ok(()),
+            Some(n) if n < len && !is_compressed(path) => n,
+            // The file shrank or was rewritten and must be indexed from scratch.
+            Some(_) => {
+                self.remove(&key)?;
+                0
+            }
+            None => 0,
+        };
+
+        // Compressed rollouts are never appended to, so they are always
+        // indexed in one go; `len` is then the compressed size.
+        let buf = if is_compressed(path) {
+            read_rollout_bytes(path)?
+        } else {
+            let mut file = File::open(path)?;
+            file.seek(SeekFrom::Start(start as u64))?;
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            buf
+        };
+        let consumed = buf.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        let update = parse_lines(&buf[..consumed]);
+
+        let started_at = file_ts
+            .format(STARTED_AT_FORMAT)
+            .map_err(|e| io::Error::other(format!("failed to format timestamp: {e}")))?;
+
+        let tx = self.conn.transaction().map_err(io::Error::other)?;
+        tx.execute(
+            "INSERT INTO sessions (path, id, started_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(path) DO NOTHING",
+            params![key, file_id.to_string(), started_at],
+        )
+        .map_err(io::Error::other)?;
+        if let Some(meta) = &update.meta {
+            tx.execute(
+                "UPDATE sessions SET id = ?2, timestamp = ?3, cwd = ?4, git_branch = ?5
+                 WHERE path = ?1 AND timestamp IS NULL",
+                params![
+                    key,
+                    meta.id,
+                    meta.timestamp,
+                    meta.cwd.to_string_lossy().into_owned(),
+                    meta.git_branch
+                ],
+            )
+            .map_err(io::Error::other)?;
+        }
+        if let Some(preview) = &update.preview {
+            tx.execute(
+                "UPDATE sessions SET preview = ?2 WHERE path = ?1 AND preview IS NULL",
+                params![key, preview],
+            )
+            .map_err(io::Error::other)?;
+        }
+        for model in &update.models {
+            tx.execute(
+                "INSERT OR IGNORE INTO session_models (path, model) VALUES (?1, ?2)",
+                params![key, model],
+            )
+            .map_err(io::Error::other)?;
+        }
+        if let Some(model) = update.models.last() {
+            tx.execute(
+                "UPDATE sessions SET model = ?2 WHERE path = ?1",
+                params![key, model],
+            )
+            .map_err(io::Error::other)?;
+        }
+        for (role, text) in &update.texts {
+            tx.execute(
+                "INSERT INTO session_text (path, role, text) VALUES (?1, ?2, ?3)",
+                params![key, role, text],
+            )
+            .map_err(io::Error::other)?;
+        }
+        let indexed_bytes = if is_compressed(path) {
+            len
+        } else {
+            start + consumed as i64
+        };
+        tx.execute(
+            "UPDATE sessions SET indexed_bytes = ?2 WHERE path = ?1",
+            params![key, indexed_bytes],
+        )
+        .map_err(io::Error::other)?;
+        tx.commit().map_err(io::Error::other)
+    }
+
+    /// Sessions matching `query`. Hits are ordered by relevance when
+    /// searching by text and newest first otherwise. Sessions without a user
+    /// message are never returned.
+    pub fn search(&self, query: &SessionQuery) -> io::Result<Vec<SessionHit>> {
+        let mut sql = String::new();
+        let mut values: Vec<SqlValue> = Vec::new();
+
+        let fts_query = query.text.as_deref().and_then(fts_match_expression);
+        match &fts_query {
+            Some(expr) => {
+                // Materialized so that SQLite does not flatten the FTS query
+                // into the grouped one below, where `snippet()` and `bm25()`
+                // cannot be used.
+                sql.push_str(
+                    "WITH h AS MATERIALIZED (
+                        SELECT path, snippet(session_text, 2, '', '', '…', ?) AS snippet,
+                               bm25(session_text) AS rank
+                        FROM session_text WHERE session_text MATCH ?
+                    )
+                    SELECT s.path, s.id, s.timestamp, s.cwd, s.git_branch, s.model, s.preview,
+                           h.snippet, MIN(h.rank) AS best
+                    FROM sessions s JOIN h ON h.path = s.path",
+                );
+                values.push(SqlValue::Integer(SNIPPET_TOKENS));
+                values.push(SqlValue::Text(expr.clone()));
+            }
+            None => sql.push_str(
+                "SELECT s.path, s.id, s.timestamp, s.cwd, s.git_branch, s.model, s.preview, NULL
+                 FROM sessions s",
+            ),
+        }
+
+        sql.push_str(" WHERE s.preview IS NOT NULL");
+        if let Some(cwd) = &query.cwd {
+            let cwd = cwd.to_string_lossy().trim_end_matches('/').to_string();
+            sql.push_str(" AND (s.cwd = ? OR s.cwd LIKE ? ESCAPE '\\')");
+            values.push(SqlValue::Text(cwd.clone()));
+            values.push(SqlValue::Text(format!("{}/%", escape_like(&cwd))));
+        }
+        if let Some(branch) = &query.git_branch {
+            sql.push_str(" AND s.git_branch = ?");
+            values.push(SqlValue::Text(branch.clone()));
+        }
+        if let Some(model) = &query.model {
+            sql.push_str(
+                " AND EXISTS (SELECT 1 FROM session_models m WHERE m.path = s.path AND m.model = ?)",
+            );
+            values.push(SqlValue::Text(model.clone()));
+        }
+        if let Some(since) = query.since {
+            sql.push_str(" AND s.started_at >= ?");
+            values.push(SqlValue::Text(format_started_at(since)));
+        }
+        if let Some(until) = query.until {
+            sql.push_str(" AND s.started_at < ?");
+            values.push(SqlValue::Text(format_started_at(until)));
+        }
+
+        if fts_query.is_some() {
+            // With MIN() in the select list SQLite takes the bare `snippet`
+            // column from the best-ranked match of each session.
+            sql.push_str(" GROUP BY s.path ORDER BY best, s.started_at DESC, s.id DESC");
+        } else {
+            sql.push_str(" ORDER BY s.started_at DESC, s.id DESC");
+        }
+        sql.push_str(" LIMIT ? OFFSET ?");
+        let limit = if query.limit == 0 {
+            DEFAULT_SEARCH_LIMIT
+        } else {
+            query.limit
+        };
+        values.push(SqlValue::Integer(limit as i64));
+        values.push(SqlValue::Integer(query.offset as i64));
+
+        let mut stmt = self.conn.prepare(&sql).map_err(io::Error::other)?;
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, String>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                ))
+            })
+            .map_err(io::Error::other)?;
+
+        let mut hits = Vec::new();
+        for row in rows {
+            let (path, id, timestamp, cwd, git_branch, model, preview, snippet) =
+                row.map_err(io::Error::other)?;
+            let Ok(conversation_id) = ConversationId::from_string(&id) else {
+                continue;
+            };
+            hits.push(SessionHit {
+                conversation_id,
+                path: PathBuf::from(path),
+                timestamp,
+                cwd: cwd.map(PathBuf::from),
+                git_branch,
+                model,
+                preview,
+                snippet,
+            });
+        }
+        Ok(hits)
+    }
+
+    fn remove(&self, path: &str) -> io::Result<()> {
+        for sql in [
+            "DELETE FROM sessions WHERE path = ?1",
+            "DELETE FROM session_models WHERE path = ?1",
+            "DELETE FROM session_text WHERE path = ?1",
+        ] {
+            self.conn
+                .execute(sql, params![path])
+                .map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shared handle used by the rollout writer to keep the index current
+/// without blocking the async runtime.
+#[derive(Clone)]
+pub(crate) struct SessionIndexer {
+    index: Arc<Mutex<SessionIndex>>,
+}
+
+impl SessionIndexer {
+    /// Returns `None` (after logging) if the index cannot be opened, in
+    /// which case sessions are still recorded and indexed by the next sync.
+    pub(crate) async fn open(codex_home: PathBuf) -> Option<Self> {
+        match tokio::task::spawn_blocking(move || SessionIndex::open(&codex_home)).await {
+            Ok(Ok(index)) => Some(Self {
+                index: Arc::new(Mutex::new(index)),
+            }),
+            Ok(Err(e)) => {
+                warn!("failed to open session index: {e}");
+                None
+            }
+            Err(e) => {
+                warn!("failed to open session index: {e}");
+                None
+            }
+        }
+    }
+
+    /// Index whatever was appended to `path` since the last update. Failures
+    /// are logged rather than propagated so indexing never stops recording.
+    pub(crate) async fn update(&self, path: PathBuf) {
+        let index = Arc::clone(&self.index);
+        let result = tokio::task::spawn_blocking(move || {
+            let mut index = index
+                .lock()
+                .map_err(|_| io::Error::other("session index lock poisoned"))?;
+            index.index_file(&path)
+        })
+        .await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("failed to update session index: {e}"),
+            Err(e) => warn!("failed to update session index: {e}"),
+        }
+    }
+}
+
+pub fn session_index_path(codex_home: &Path) -> PathBuf {
+    codex_home.join(SESSION_INDEX_FILENAME)
+}
+
+/// Parse a date filter given as RFC 3339 or `YYYY-MM-DD` (midnight UTC).
+pub fn parse_date_bound(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        .map_err(|_| format!("invalid time `{value}`; expected RFC 3339 or YYYY-MM-DD"))
+}
+
+/// Format of `sessions.started_at`, which sorts lexicographically.
+const STARTED_AT_FORMAT: &[FormatItem] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]Z");
+
+fn format_started_at(ts: DateTime<Utc>) -> String {
+    ts.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// What a chunk of rollout lines contributes to the index.
+#[derive(Default)]
+struct IndexUpdate {
+    meta: Option<MetaUpdate>,
+    preview: Option<String>,
+    models: Vec<String>,
+    texts: Vec<(&'static str, String)>,
+}
+
+struct MetaUpdate {
+    id: String,
+    timestamp: String,
+    cwd: PathBuf,
+    git_branch: Option<String>,
+}
+
+fn parse_lines(buf: &[u8]) -> IndexUpdate {
+    let mut update = IndexUpdate::default();
+    for line in String::from_utf8_lossy(buf).lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let Ok(rollout_line) = serde_json::from_str::<RolloutLine>(trimmed) else {
+            continue;
+        };
+        match rollout_line.item {
+            RolloutItem::SessionMeta(meta_line) => {
+                if update.meta.is_none() {
+                    update.meta = Some(MetaUpdate {
+                        id: meta_line.meta.id.to_string(),
+                        timestamp: meta_line.meta.timestamp,
+                        cwd: meta_line.meta.cwd,
+                        git_branch: meta_line.git.and_then(|git| git.branch),
+                    });
+                }
+            }
+            RolloutItem::TurnContext(ctx) => {
+                if update.models.last() != Some(&ctx.model) {
+                    update.models.push(ctx.model);
+                }
+            }
+            RolloutItem::EventMsg(EventMsg::UserMessage(ev)) => {
+                let plain = match ev.kind {
+                    Some(kind) => matches!(kind, InputMessageKind::Plain),
+                    None => matches!(
+                        InputMessageKind::from(("user", ev.message.as_str())),
+                        InputMessageKind::Plain
+                    ),
+                };
+                if !plain {
+                    continue;
+                }
+                // Strip ide context.
+                let text = match ev.message.find(USER_MESSAGE_BEGIN) {
+                    Some(idx) => ev.message[idx + USER_MESSAGE_BEGIN.len()..].trim(),
+                    None => ev.message.trim(),
+                };
+                if text.is_empty() {
+                    continue;
+                }
+                if update.preview.is_none() {
+                    update.preview = Some(text.to_string());
+                }
+                update.texts.push(("user", text.to_string()));
+            }
+            RolloutItem::EventMsg(EventMsg::AgentMessage(ev)) => {
+                update.texts.push(("agent", ev.message));
+            }
+            RolloutItem::EventMsg(_)
+            | RolloutItem::ResponseItem(_)
+            | RolloutItem::Compacted(_)
+            | RolloutItem::Checkpoint(_) => {}
+        }
+    }
+    update
+}
+
+/// Turn free text into an FTS5 query matching all of its words. Words are
+/// quoted so that FTS syntax characters in user input are taken literally.
+fn fts_match_expression(text: &str) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let (last, rest) = words.split_last()?;
+    let mut terms: Vec<String> = rest.iter().map(|w| quote_fts(w)).collect();
+    terms.push(format!("{}*", quote_fts(last)));
+    Some(terms.join(" "))
+}
+
+fn quote_fts(word: &str) -> String {
+    format!("\"{}\"", word.replace('"', "\"\""))
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Recursively collect `rollout-*.jsonl` files below `dir`, compressed or not.
+fn collect_rollout_paths(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_rollout_paths(&path, out)?;
+        } else if file_type.is_file()
+            && let Some(name) = path.file_name().and_then(|n| n.to_str())
+            && parse_timestamp_uuid_from_filename(name).is_some()
+        {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
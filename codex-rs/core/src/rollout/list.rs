@@ -14,6 +14,9 @@ use time::macros::format_description;
 use uuid::Uuid;
 
 use super::SESSIONS_SUBDIR;
+use super::compression::COMPRESSED_SUFFIX;
+use super::compression::is_compressed;
+use super::compression::read_rollout_to_string;
 use crate::protocol::EventMsg;
 use codex_protocol::protocol::RolloutItem;
 use codex_protocol::protocol::RolloutLine;
@@ -149,10 +152,6 @@ async fn traverse_directories_for_paths(
                     break 'outer;
                 }
                 let mut day_files = collect_files(day_path, |name_str, path| {
-                    if !name_str.starts_with("rollout-") || !name_str.ends_with(".jsonl") {
-                        return None;
-                    }
-
                     parse_timestamp_uuid_from_filename(name_str)
                         .map(|(ts, id)| (ts, id, name_str.to_string(), path.to_path_buf()))
                 })
@@ -269,8 +268,9 @@ where
     Ok(collected)
 }
 
-fn parse_timestamp_uuid_from_filename(name: &str) -> Option<(OffsetDateTime, Uuid)> {
-    // Expected: rollout-YYYY-MM-DDThh-mm-ss-<uuid>.jsonl
+pub(crate) fn parse_timestamp_uuid_from_filename(name: &str) -> Option<(OffsetDateTime, Uuid)> {
+    // Expected: rollout-YYYY-MM-DDThh-mm-ss-<uuid>.jsonl, optionally compressed.
+    let name = name.strip_suffix(COMPRESSED_SUFFIX).unwrap_or(name);
     let core = name.strip_prefix("rollout-")?.strip_suffix(".jsonl")?;
 
     // Scan from the right for a '-' such that the suffix parses as a UUID.
@@ -292,51 +292,71 @@ async fn read_head_and_flags(
 ) -> io::Result<(Vec<serde_json::Value>, bool, bool)> {
     use tokio::io::AsyncBufReadExt;
 
-    let file = tokio::fs::File::open(path).await?;
-    let reader = tokio::io::BufReader::new(file);
-    let mut lines = reader.lines();
-    let mut head: Vec<serde_json::Value> = Vec::new();
-    let mut saw_session_meta = false;
-    let mut saw_user_event = false;
+    let mut scan = HeadScan::default();
+    if is_compressed(path) {
+        let text = read_rollout_to_string(path).await?;
+        for line in text.lines() {
+            if scan.head.len() >= max_records {
+                break;
+            }
+            scan.push_line(line);
+        }
+    } else {
+        let file = tokio::fs::File::open(path).await?;
+        let reader = tokio::io::BufReader::new(file);
+        let mut lines = reader.lines();
+        while scan.head.len() < max_records {
+            let line_opt = lines.next_line().await?;
+            let Some(line) = line_opt else { break };
+            scan.push_line(&line);
+        }
+    }
 
-    while head.len() < max_records {
-        let line_opt = lines.next_line().await?;
-        let Some(line) = line_opt else { break };
+    Ok((scan.head, scan.saw_session_meta, scan.saw_user_event))
+}
+
+#[derive(Default)]
+struct HeadScan {
+    head: Vec<serde_json::Value>,
+    saw_session_meta: bool,
+    saw_user_event: bool,
+}
+
+impl HeadScan {
+    fn push_line(&mut self, line: &str) {
         let trimmed = line.trim();
         if trimmed.is_empty() {
-            continue;
+            return;
         }
 
         let parsed: Result<RolloutLine, _> = serde_json::from_str(trimmed);
-        let Ok(rollout_line) = parsed else { continue };
+        let Ok(rollout_line) = parsed else { return };
 
         match rollout_line.item {
             RolloutItem::SessionMeta(session_meta_line) => {
                 if let Ok(val) = serde_json::to_value(session_meta_line) {
-                    head.push(val);
-                    saw_session_meta = true;
+                    self.head.push(val);
+                    self.saw_session_meta = true;
                 }
             }
             RolloutItem::ResponseItem(item) => {
                 if let Ok(val) = serde_json::to_value(item) {
-                    head.push(val);
+                    self.head.push(val);
                 }
             }
             RolloutItem::TurnContext(_) => {
                 // Not included in `head`; skip.
             }
-            RolloutItem::Compacted(_) => {
+            RolloutItem::Compacted(_) | RolloutItem::Checkpoint(_) => {
                 // Not included in `head`; skip.
             }
             RolloutItem::EventMsg(ev) => {
                 if matches!(ev, EventMsg::UserMessage(_)) {
-                    saw_user_event = true;
+                    self.saw_user_event = true;
                 }
             }
         }
     }
-
-    Ok((head, saw_session_meta, saw_user_event))
 }
 
 /// Locate a recorded conversation rollout file by its UUID string using the existing
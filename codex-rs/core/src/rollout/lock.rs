@@ -0,0 +1,72 @@
+//! Advisory locks that mark rollout files as in use.
+//!
+//! A [`RolloutRecorder`](super::RolloutRecorder) holds an exclusive lock on
+//! the rollout it appends to for as long as the session runs, in whichever
+//! process that is. Retention and compression only touch a rollout while
+//! holding that same lock themselves, so they skip the rollouts of running
+//! sessions however long those have been idle.
+//!
+//! Locks are only taken on Unix, where they are advisory. On Windows they
+//! would stop other processes from reading the rollout, so there only the
+//! grace period in [`retention`](super::retention) protects running
+//! sessions.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Mark the rollout open as `file` as in use by this process until `file`
+/// is closed. Returns `false` if another process already holds the lock,
+/// e.g. because it is running the same session.
+#[cfg(unix)]
+pub(crate) fn lock_for_writing(file: &File) -> io::Result<bool> {
+    match file.try_lock() {
+        Ok(()) => Ok(true),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(false),
+        Err(std::fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn lock_for_writing(_file: &File) -> io::Result<bool> {
+    Ok(true)
+}
+
+/// Lock the rollout at `path` if no running session holds it. The lock is
+/// released when the returned file is dropped. Returns `None` if the
+/// rollout is in use or no longer exists.
+pub(crate) fn try_lock_unused(path: &Path) -> io::Result<Option<File>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        // Removed by a concurrent prune.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(lock_for_writing(&file)?.then_some(file))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn a_locked_rollout_is_in_use() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("rollout.jsonl");
+        let writer = File::create(&path).unwrap();
+        assert!(lock_for_writing(&writer).unwrap());
+
+        assert!(try_lock_unused(&path).unwrap().is_none());
+        drop(writer);
+        let lock = try_lock_unused(&path).unwrap();
+        assert!(lock.is_some());
+        assert!(try_lock_unused(&path).unwrap().is_none());
+        assert!(
+            try_lock_unused(&dir.path().join("missing.jsonl"))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
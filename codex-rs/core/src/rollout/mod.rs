@@ -3,11 +3,18 @@
 pub const SESSIONS_SUBDIR: &str = "sessions";
 pub const ARCHIVED_SESSIONS_SUBDIR: &str = "archived_sessions";
 
+pub(crate) mod compression;
+pub mod export;
+pub mod index;
 pub mod list;
+pub(crate) mod lock;
 pub(crate) mod policy;
 pub mod recorder;
+pub mod retention;
 
 pub use codex_protocol::protocol::SessionMeta;
+pub use index::SessionHit;
+pub use index::SessionQuery;
 pub use list::find_conversation_path_by_id_str;
 pub use recorder::RolloutRecorder;
 pub use recorder::RolloutRecorderParams;
@@ -21,11 +21,21 @@ use tracing::info;
 use tracing::warn;
 
 use super::SESSIONS_SUBDIR;
+use super::compression::decompress_rollout;
+use super::compression::is_compressed;
+use super::compression::read_rollout_to_string;
+use super::index::SessionHit;
+use super::index::SessionIndex;
+use super::index::SessionIndexer;
+use super::index::SessionQuery;
 use super::list::ConversationsPage;
 use super::list::Cursor;
 use super::list::get_conversations;
+use super::lock::lock_for_writing;
 use super::policy::is_persisted_response_item;
+use super::retention::prune_sessions;
 use crate::config::Config;
+use crate::config_types::SessionRetention;
 use crate::default_client::ORIGINATOR;
 use crate::git_info::collect_git_info;
 use codex_protocol::models::ResponseItem;
@@ -33,6 +43,7 @@ use codex_protocol::protocol::InitialHistory;
 use codex_protocol::protocol::ResumedHistory;
 use codex_protocol::protocol::RolloutItem;
 use codex_protocol::protocol::RolloutLine;
+use codex_protocol::protocol::SessionFork;
 use codex_protocol::protocol::SessionMeta;
 use codex_protocol::protocol::SessionMetaLine;
 
@@ -69,6 +80,8 @@ pub enum RolloutRecorderParams {
     Create {
         conversation_id: ConversationId,
         instructions: Option<String>,
+        /// Lineage recorded in the session metadata of a forked session.
+        fork: Option<SessionFork>,
     },
     Resume {
         path: PathBuf,
@@ -91,6 +104,19 @@ impl RolloutRecorderParams {
         Self::Create {
             conversation_id,
             instructions,
+            fork: None,
+        }
+    }
+
+    pub fn forked(
+        conversation_id: ConversationId,
+        instructions: Option<String>,
+        fork: SessionFork,
+    ) -> Self {
+        Self::Create {
+            conversation_id,
+            instructions,
+            fork: Some(fork),
         }
     }
 
@@ -109,6 +135,22 @@ impl RolloutRecorder {
         get_conversations(codex_home, page_size, cursor).await
     }
 
+    /// Search recorded conversations using the session index under the
+    /// provided Codex home directory, syncing it with the rollout files first.
+    pub async fn search_conversations(
+        codex_home: &Path,
+        query: SessionQuery,
+    ) -> std::io::Result<Vec<SessionHit>> {
+        let codex_home = codex_home.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let mut index = SessionIndex::open(&codex_home)?;
+            index.sync(&codex_home)?;
+            index.search(&query)
+        })
+        .await
+        .map_err(|e| IoError::other(format!("session search task failed: {e}")))?
+    }
+
     /// Attempt to create a new [`RolloutRecorder`]. If the sessions directory
     /// cannot be created or the rollout file cannot be opened we return the
     /// error so the caller can decide whether to disable persistence.
@@ -117,6 +159,7 @@ impl RolloutRecorder {
             RolloutRecorderParams::Create {
                 conversation_id,
                 instructions,
+                fork,
             } => {
                 let LogFileInfo {
                     file,
@@ -136,28 +179,47 @@ impl RolloutRecorder {
                 (
                     tokio::fs::File::from_std(file),
                     path,
-                    Some(SessionMeta {
-                        id: session_id,
-                        timestamp,
-                        cwd: config.cwd.clone(),
-                        originator: ORIGINATOR.value.clone(),
-                        cli_version: env!("CARGO_PKG_VERSION").to_string(),
-                        instructions,
-                    }),
+                    Some((
+                        SessionMeta {
+                            id: session_id,
+                            timestamp,
+                            cwd: config.cwd.clone(),
+                            originator: ORIGINATOR.value.clone(),
+                            cli_version: env!("CARGO_PKG_VERSION").to_string(),
+                            instructions,
+                        },
+                        fork,
+                    )),
                 )
             }
-            RolloutRecorderParams::Resume { path } => (
-                tokio::fs::OpenOptions::new()
-                    .append(true)
-                    .open(&path)
-                    .await?,
-                path,
-                None,
-            ),
+            RolloutRecorderParams::Resume { path } => {
+                // New items are appended as plain JSONL, so a rollout that
+                // was compressed by the retention policy is expanded first.
+                let path = if is_compressed(&path) {
+                    tokio::task::spawn_blocking(move || decompress_rollout(&path))
+                        .await
+                        .map_err(|e| IoError::other(format!("decompression task failed: {e}")))??
+                } else {
+                    path
+                };
+                let file = std::fs::OpenOptions::new().append(true).open(&path)?;
+                if !lock_for_writing(&file)? {
+                    warn!("{path:?} is in use by another process");
+                }
+                (tokio::fs::File::from_std(file), path, None)
+            }
         };
 
+        if meta.is_some() && !config.session_retention.is_unlimited() {
+            spawn_retention_enforcement(
+                config.codex_home.clone(),
+                config.session_retention.clone(),
+            );
+        }
+
         // Clone the cwd for the spawned task to collect git info asynchronously
         let cwd = config.cwd.clone();
+        let codex_home = config.codex_home.clone();
 
         // A reasonably-sized bounded channel. If the buffer fills up the send
         // future will yield, which is fine – we only need to ensure we do not
@@ -167,7 +229,14 @@ impl RolloutRecorder {
         // Spawn a Tokio task that owns the file handle and performs async
         // writes. Using `tokio::fs::File` keeps everything on the async I/O
         // driver instead of blocking the runtime.
-        tokio::task::spawn(rollout_writer(file, rx, meta, cwd));
+        tokio::task::spawn(rollout_writer(
+            file,
+            rx,
+            meta,
+            cwd,
+            codex_home,
+            rollout_path.clone(),
+        ));
 
         Ok(Self { tx, rollout_path })
     }
@@ -204,7 +273,7 @@ impl RolloutRecorder {
 
     pub(crate) async fn get_rollout_history(path: &Path) -> std::io::Result<InitialHistory> {
         info!("Resuming rollout from {path:?}");
-        let text = tokio::fs::read_to_string(path).await?;
+        let text = read_rollout_to_string(path).await?;
         if text.trim().is_empty() {
             return Err(IoError::other("empty session file"));
         }
@@ -246,6 +315,9 @@ impl RolloutRecorder {
                     RolloutItem::EventMsg(_ev) => {
                         items.push(RolloutItem::EventMsg(_ev));
                     }
+                    RolloutItem::Checkpoint(item) => {
+                        items.push(RolloutItem::Checkpoint(item));
+                    }
                 },
                 Err(e) => {
                     warn!("failed to parse rollout line: {v:?}, error: {e}");
@@ -293,6 +365,26 @@ impl RolloutRecorder {
     }
 }
 
+/// Apply the retention limits in the background whenever a new session
+/// starts. The new rollout is protected by being the most recent one.
+fn spawn_retention_enforcement(codex_home: PathBuf, retention: SessionRetention) {
+    tokio::task::spawn_blocking(
+        move || match prune_sessions(&codex_home, &retention, false) {
+            Ok(report) => {
+                if !report.deleted.is_empty() || !report.compressed.is_empty() {
+                    info!(
+                        "session retention deleted {} rollout(s) ({} bytes) and compressed {}",
+                        report.deleted.len(),
+                        report.deleted_bytes(),
+                        report.compressed.len()
+                    );
+                }
+            }
+            Err(e) => warn!("failed to apply session retention: {e}"),
+        },
+    );
+}
+
 struct LogFileInfo {
     /// Opened file handle to the rollout file.
     file: File,
@@ -336,6 +428,9 @@ fn create_log_file(
         .append(true)
         .create(true)
         .open(&path)?;
+    // Keeps retention from pruning or compressing the rollout while the
+    // session runs; released when the writer task drops the file.
+    lock_for_writing(&file)?;
 
     Ok(LogFileInfo {
         file,
@@ -348,17 +443,21 @@ fn create_log_file(
 async fn rollout_writer(
     file: tokio::fs::File,
     mut rx: mpsc::Receiver<RolloutCmd>,
-    mut meta: Option<SessionMeta>,
+    mut meta: Option<(SessionMeta, Option<SessionFork>)>,
     cwd: std::path::PathBuf,
+    codex_home: PathBuf,
+    rollout_path: PathBuf,
 ) -> std::io::Result<()> {
     let mut writer = JsonlWriter { file };
+    let indexer = SessionIndexer::open(codex_home).await;
 
     // If we have a meta, collect git info asynchronously and write meta first
-    if let Some(session_meta) = meta.take() {
+    if let Some((session_meta, fork)) = meta.take() {
         let git_info = collect_git_info(&cwd).await;
         let session_meta_line = SessionMetaLine {
             meta: session_meta,
             git: git_info,
+            fork,
         };
 
         // Write the SessionMeta as the first item in the file, wrapped in a rollout line
@@ -366,6 +465,9 @@ async fn rollout_writer(
             .write_rollout_item(RolloutItem::SessionMeta(session_meta_line))
             .await?;
     }
+    if let Some(indexer) = &indexer {
+        indexer.update(rollout_path.clone()).await;
+    }
 
     // Process rollout commands
     while let Some(cmd) = rx.recv().await {
@@ -376,6 +478,9 @@ async fn rollout_writer(
                         writer.write_rollout_item(item).await?;
                     }
                 }
+                if let Some(indexer) = &indexer {
+                    indexer.update(rollout_path.clone()).await;
+                }
             }
             RolloutCmd::Flush { ack } => {
                 // Ensure underlying file is flushed and then ack.
@@ -0,0 +1,401 @@
+//! Enforcement of [`SessionRetention`] limits on the rollout files under
+//! `~/.codex/sessions` (and `~/.codex/archived_sessions` unless archived
+//! sessions are kept).
+//!
+//! Sessions are ranked by when their rollout was last written, newest
+//! first. A session is deleted when it is older than `max_age_days`, when
+//! `keep_last` newer sessions are already kept, or when keeping it would
+//! push the total size of the kept sessions over `max_total_bytes`. Kept
+//! sessions that have been idle for `compress_after_days` are compressed.
+//!
+//! Rollouts that a running session holds the lock on (see
+//! [`lock`](super::lock)) are never touched, nor are rollouts written in
+//! the last few minutes, which may belong to a session that has not taken
+//! its lock yet or runs on a platform without advisory locks.
+//!
+//! Once rollouts have been deleted, the checkpoint snapshots that only they
+//! referred to are garbage collected as well.
+
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use super::ARCHIVED_SESSIONS_SUBDIR;
+use super::SESSIONS_SUBDIR;
+use super::compression::compress_rollout;
+use super::compression::is_compressed;
+use super::list::parse_timestamp_uuid_from_filename;
+use super::lock::try_lock_unused;
+use crate::checkpoint::collect_garbage;
+use crate::config_types::SessionRetention;
+
+/// Rollouts written more recently than this are considered in use, locked
+/// or not.
+const ACTIVE_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Why a session was selected for deletion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PruneReason {
+    MaxAge,
+    KeepLast,
+    MaxTotalBytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrunedSession {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub reason: PruneReason,
+}
+
+/// What [`prune_sessions`] deleted and compressed, or would have in a dry run.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct PruneReport {
+    pub deleted: Vec<PrunedSession>,
+    /// Rollouts compressed, by their path before compression.
+    pub compressed: Vec<PathBuf>,
+}
+
+impl PruneReport {
+    pub fn deleted_bytes(&self) -> u64 {
+        self.deleted.iter().map(|session| session.bytes).sum()
+    }
+}
+
+struct RolloutFile {
+    path: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+/// Apply `retention` to the sessions recorded under `codex_home`. With
+/// `dry_run`, nothing is changed and the report lists what would be.
+pub fn prune_sessions(
+    codex_home: &Path,
+    retention: &SessionRetention,
+    dry_run: bool,
+) -> io::Result<PruneReport> {
+    prune_sessions_at(codex_home, retention, dry_run, SystemTime::now())
+}
+
+fn prune_sessions_at(
+    codex_home: &Path,
+    retention: &SessionRetention,
+    dry_run: bool,
+    now: SystemTime,
+) -> io::Result<PruneReport> {
+    let mut roots = vec![codex_home.join(SESSIONS_SUBDIR)];
+    if !retention.keep_archived {
+        roots.push(codex_home.join(ARCHIVED_SESSIONS_SUBDIR));
+    }
+    let mut files = Vec::new();
+    for root in &roots {
+        if root.exists() {
+            collect_rollout_files(root, &mut files)?;
+        }
+    }
+    files.sort_by_key(|file| Reverse((file.modified, file.path.clone())));
+
+    let max_age = retention
+        .max_age_days
+        .map(|days| Duration::from_secs(days.saturating_mul(SECONDS_PER_DAY)));
+    let compress_after = retention
+        .compress_after_days
+        .map(|days| Duration::from_secs(days.saturating_mul(SECONDS_PER_DAY)));
+
+    let mut report = PruneReport::default();
+    let mut kept_count = 0usize;
+    let mut kept_bytes = 0u64;
+    for file in files {
+        let age = now.duration_since(file.modified).unwrap_or_default();
+        let reason = if age < ACTIVE_GRACE_PERIOD {
+            None
+        } else if max_age.is_some_and(|max| age > max) {
+            Some(PruneReason::MaxAge)
+        } else if retention.keep_last.is_some_and(|n| kept_count >= n) {
+            Some(PruneReason::KeepLast)
+        } else if retention
+            .max_total_bytes
+            .is_some_and(|max| kept_bytes.saturating_add(file.bytes) > max)
+        {
+            Some(PruneReason::MaxTotalBytes)
+        } else {
+            None
+        };
+
+        let compress = reason.is_none()
+            && compress_after.is_some_and(|after| age > after)
+            && !is_compressed(&file.path);
+        // Held while the rollout is deleted or compressed. A rollout that is
+        // locked by a running session is kept as it is, however long the
+        // session has been idle.
+        let lock = if reason.is_some() || compress {
+            try_lock_unused(&file.path)?
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason
+            && lock.is_some()
+        {
+            if !dry_run {
+                fs::remove_file(&file.path)?;
+                remove_empty_parents(&file.path, &roots);
+            }
+            report.deleted.push(PrunedSession {
+                path: file.path,
+                bytes: file.bytes,
+                reason,
+            });
+            continue;
+        }
+
+        kept_count += 1;
+        kept_bytes = kept_bytes.saturating_add(file.bytes);
+        if compress && lock.is_some() {
+            if !dry_run {
+                compress_rollout(&file.path)?;
+            }
+            report.compressed.push(file.path);
+        }
+    }
+
+    if !dry_run && !report.deleted.is_empty() {
+        let mut remaining = Vec::new();
+        for root in [SESSIONS_SUBDIR, ARCHIVED_SESSIONS_SUBDIR] {
+            let root = codex_home.join(root);
+            if root.exists() {
+                collect_rollout_files(&root, &mut remaining)?;
+            }
+        }
+        let remaining: Vec<PathBuf> = remaining.into_iter().map(|file| file.path).collect();
+        collect_garbage(codex_home, &remaining)?;
+    }
+    Ok(report)
+}
+
+/// Recursively collect rollout files below `dir`, compressed or not.
+fn collect_rollout_files(dir: &Path, out: &mut Vec<RolloutFile>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_rollout_files(&path, out)?;
+        } else if file_type.is_file()
+            && let Some(name) = path.file_name().and_then(|n| n.to_str())
+            && parse_timestamp_uuid_from_filename(name).is_some()
+        {
+            let metadata = entry.metadata()?;
+            out.push(RolloutFile {
+                bytes: metadata.len(),
+                modified: metadata.modified()?,
+                path,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Remove the `YYYY/MM/DD` directories left empty by a deletion, stopping
+/// at the sessions root.
+fn remove_empty_parents(path: &Path, roots: &[PathBuf]) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if roots.iter().any(|root| root == current) || fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    const DAY: Duration = Duration::from_secs(SECONDS_PER_DAY);
+
+    /// Write a rollout of `bytes` bytes that was last modified `age` ago.
+    fn write_rollout(
+        dir: &Path,
+        index: u8,
+        bytes: usize,
+        age: Duration,
+        now: SystemTime,
+    ) -> PathBuf {
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join(format!(
+            "rollout-2025-01-01T00-00-{index:02}-00000000-0000-0000-0000-0000000000{index:02}.jsonl"
+        ));
+        fs::write(&path, vec![b'x'; bytes]).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(now - age)
+            .unwrap();
+        path
+    }
+
+    fn deleted(report: &PruneReport) -> Vec<(PathBuf, PruneReason)> {
+        report
+            .deleted
+            .iter()
+            .map(|session| (session.path.clone(), session.reason))
+            .collect()
+    }
+
+    #[test]
+    fn unlimited_retention_keeps_everything() {
+        let home = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let day_dir = home.path().join("sessions/2025/01/01");
+        write_rollout(&day_dir, 1, 10, 400 * DAY, now);
+
+        let report =
+            prune_sessions_at(home.path(), &SessionRetention::default(), false, now).unwrap();
+        assert_eq!(report, PruneReport::default());
+    }
+
+    #[test]
+    fn limits_delete_oldest_sessions_first() {
+        let home = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let day_dir = home.path().join("sessions/2025/01/01");
+        let newest = write_rollout(&day_dir, 1, 10, DAY, now);
+        let second = write_rollout(&day_dir, 2, 10, 2 * DAY, now);
+        let third = write_rollout(&day_dir, 3, 10, 3 * DAY, now);
+        let ancient = write_rollout(&day_dir, 4, 10, 100 * DAY, now);
+
+        let retention = SessionRetention {
+            max_age_days: Some(30),
+            keep_last: Some(2),
+            ..Default::default()
+        };
+        let report = prune_sessions_at(home.path(), &retention, false, now).unwrap();
+
+        assert_eq!(
+            deleted(&report),
+            vec![
+                (third.clone(), PruneReason::KeepLast),
+                (ancient.clone(), PruneReason::MaxAge),
+            ]
+        );
+        assert_eq!(report.deleted_bytes(), 20);
+        assert!(newest.exists() && second.exists());
+        assert!(!third.exists() && !ancient.exists());
+    }
+
+    #[test]
+    fn total_size_limit_and_dry_run() {
+        let home = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let day_dir = home.path().join("sessions/2025/01/01");
+        write_rollout(&day_dir, 1, 60, DAY, now);
+        let older = write_rollout(&day_dir, 2, 60, 2 * DAY, now);
+
+        let retention = SessionRetention {
+            max_total_bytes: Some(100),
+            ..Default::default()
+        };
+        let report = prune_sessions_at(home.path(), &retention, true, now).unwrap();
+        assert_eq!(
+            deleted(&report),
+            vec![(older.clone(), PruneReason::MaxTotalBytes)]
+        );
+        assert!(older.exists(), "a dry run must not delete anything");
+
+        prune_sessions_at(home.path(), &retention, false, now).unwrap();
+        assert!(!older.exists());
+    }
+
+    #[test]
+    fn recent_and_archived_sessions_are_protected() {
+        let home = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let active = write_rollout(
+            &home.path().join("sessions/2025/01/01"),
+            1,
+            10,
+            Duration::ZERO,
+            now,
+        );
+        let archived_dir = home.path().join(ARCHIVED_SESSIONS_SUBDIR);
+        let archived = write_rollout(&archived_dir, 2, 10, 100 * DAY, now);
+
+        let retention = SessionRetention {
+            keep_last: Some(0),
+            max_age_days: Some(1),
+            ..Default::default()
+        };
+        let report = prune_sessions_at(home.path(), &retention, false, now).unwrap();
+        assert_eq!(report, PruneReport::default());
+        assert!(active.exists() && archived.exists());
+
+        let retention = SessionRetention {
+            keep_archived: false,
+            ..retention
+        };
+        let report = prune_sessions_at(home.path(), &retention, false, now).unwrap();
+        assert_eq!(
+            deleted(&report),
+            vec![(archived, PruneReason::MaxAge)]
+        );
+        assert!(active.exists());
+        assert!(archived_dir.exists(), "the archive root itself is kept");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rollouts_locked_by_running_sessions_are_kept() {
+        let home = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let day_dir = home.path().join("sessions/2025/01/01");
+        let idle = write_rollout(&day_dir, 1, 10, 100 * DAY, now);
+        let writer = fs::OpenOptions::new().append(true).open(&idle).unwrap();
+        assert!(crate::rollout::lock::lock_for_writing(&writer).unwrap());
+
+        let retention = SessionRetention {
+            max_age_days: Some(1),
+            compress_after_days: Some(1),
+            ..Default::default()
+        };
+        let report = prune_sessions_at(home.path(), &retention, false, now).unwrap();
+        assert_eq!(report, PruneReport::default());
+        assert!(idle.exists());
+
+        drop(writer);
+        let report = prune_sessions_at(home.path(), &retention, false, now).unwrap();
+        assert_eq!(deleted(&report), vec![(idle.clone(), PruneReason::MaxAge)]);
+        assert!(!idle.exists());
+    }
+
+    #[test]
+    fn deleting_the_last_session_of_a_day_removes_its_directories() {
+        let home = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let day_dir = home.path().join("sessions/2025/01/01");
+        write_rollout(&day_dir, 1, 10, 10 * DAY, now);
+
+        let retention = SessionRetention {
+            max_age_days: Some(1),
+            ..Default::default()
+        };
+        prune_sessions_at(home.path(), &retention, false, now).unwrap();
+        assert!(!home.path().join("sessions/2025").exists());
+        assert!(home.path().join("sessions").exists());
+    }
+}
@@ -12,6 +12,16 @@ use time::format_description::FormatItem;
 use time::macros::format_description;
 use uuid::Uuid;
 
+use codex_protocol::protocol::InitialHistory;
+
+use crate::rollout::RolloutRecorder;
+use crate::rollout::compression::compress_rollout;
+use crate::rollout::export::ExportFormat;
+use crate::rollout::export::ExportOptions;
+use crate::rollout::export::export_session;
+use crate::rollout::index::SessionIndex;
+use crate::rollout::index::SessionQuery;
+use crate::rollout::index::parse_date_bound;
 use crate::rollout::list::ConversationItem;
 use crate::rollout::list::ConversationsPage;
 use crate::rollout::list::Cursor;
@@ -443,3 +453,342 @@ async fn test_stable_ordering_same_second_pagination() {
     };
     assert_eq!(page2, expected_page2);
 }
+
+fn write_indexed_session(
+    root: &Path,
+    ts_str: &str,
+    uuid: Uuid,
+    cwd: &str,
+    branch: &str,
+    model: &str,
+    messages: &[(&str, &str)],
+) -> std::path::PathBuf {
+    let dir = root
+        .join("sessions")
+        .join(&ts_str[0..4])
+        .join(&ts_str[5..7])
+        .join(&ts_str[8..10]);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("rollout-{ts_str}-{uuid}.jsonl"));
+    let mut file = File::create(&path).unwrap();
+
+    let meta = serde_json::json!({
+        "timestamp": ts_str,
+        "type": "session_meta",
+        "payload": {
+            "id": uuid,
+            "timestamp": ts_str,
+            "instructions": null,
+            "cwd": cwd,
+            "originator": "test_originator",
+            "cli_version": "test_version",
+            "git": { "branch": branch }
+        }
+    });
+    writeln!(file, "{meta}").unwrap();
+    let turn_context = serde_json::json!({
+        "timestamp": ts_str,
+        "type": "turn_context",
+        "payload": {
+            "cwd": cwd,
+            "approval_policy": "never",
+            "sandbox_policy": { "mode": "read-only" },
+            "model": model,
+            "summary": "auto"
+        }
+    });
+    writeln!(file, "{turn_context}").unwrap();
+    for (kind, message) in messages {
+        let event = serde_json::json!({
+            "timestamp": ts_str,
+            "type": "event_msg",
+            "payload": { "type": kind, "message": message }
+        });
+        writeln!(file, "{event}").unwrap();
+    }
+    path
+}
+
+#[test]
+fn test_session_index_search_filters() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path();
+    let u1 = Uuid::from_u128(1);
+    let u2 = Uuid::from_u128(2);
+
+    let auth = write_indexed_session(
+        home,
+        "2025-01-01T12-00-00",
+        u1,
+        "/work/api",
+        "fix-auth",
+        "gpt-5",
+        &[
+            ("user_message", "Why does login fail?"),
+            ("agent_message", "The auth token refresh races with logout."),
+        ],
+    );
+    let ui = write_indexed_session(
+        home,
+        "2025-01-02T12-00-00",
+        u2,
+        "/work/web",
+        "main",
+        "o3",
+        &[("user_message", "Add a dark mode toggle")],
+    );
+
+    let mut index = SessionIndex::open(home).unwrap();
+    index.sync(home).unwrap();
+
+    let paths = |query: SessionQuery| -> Vec<std::path::PathBuf> {
+        index
+            .search(&query)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.path)
+            .collect()
+    };
+
+    assert_eq!(
+        paths(SessionQuery::default()),
+        vec![ui.clone(), auth.clone()]
+    );
+    assert_eq!(
+        paths(SessionQuery {
+            text: Some("token refr".to_string()),
+            ..Default::default()
+        }),
+        vec![auth.clone()]
+    );
+    assert_eq!(
+        paths(SessionQuery {
+            cwd: Some("/work/web".into()),
+            ..Default::default()
+        }),
+        vec![ui.clone()]
+    );
+    assert_eq!(
+        paths(SessionQuery {
+            git_branch: Some("fix-auth".to_string()),
+            ..Default::default()
+        }),
+        vec![auth]
+    );
+    assert_eq!(
+        paths(SessionQuery {
+            model: Some("o3".to_string()),
+            ..Default::default()
+        }),
+        vec![ui.clone()]
+    );
+    assert_eq!(
+        paths(SessionQuery {
+            since: Some(parse_date_bound("2025-01-02").unwrap()),
+            ..Default::default()
+        }),
+        vec![ui]
+    );
+
+    let hit = &index
+        .search(&SessionQuery {
+            text: Some("login".to_string()),
+            ..Default::default()
+        })
+        .unwrap()[0];
+    assert_eq!(hit.preview, "Why does login fail?");
+    assert_eq!(hit.git_branch.as_deref(), Some("fix-auth"));
+    assert_eq!(hit.model.as_deref(), Some("gpt-5"));
+}
+
+#[test]
+fn test_session_index_picks_up_appended_lines() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path();
+    let path = write_indexed_session(
+        home,
+        "2025-01-01T12-00-00",
+        Uuid::from_u128(1),
+        "/work",
+        "main",
+        "gpt-5",
+        &[("user_message", "first question")],
+    );
+
+    let mut index = SessionIndex::open(home).unwrap();
+    index.sync(home).unwrap();
+    let query = SessionQuery {
+        text: Some("pineapple".to_string()),
+        ..Default::default()
+    };
+    assert!(index.search(&query).unwrap().is_empty());
+
+    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    let event = serde_json::json!({
+        "timestamp": "2025-01-01T12-00-01",
+        "type": "event_msg",
+        "payload": { "type": "agent_message", "message": "pineapple pizza" }
+    });
+    writeln!(file, "{event}").unwrap();
+    index.index_file(&path).unwrap();
+
+    let hits = index.search(&query).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].preview, "first question");
+    assert_eq!(hits[0].snippet.as_deref(), Some("pineapple pizza"));
+
+    fs::remove_file(&path).unwrap();
+    index.sync(home).unwrap();
+    assert!(index.search(&SessionQuery::default()).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_compressed_rollouts_remain_readable() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path();
+    let uuid = Uuid::from_u128(9);
+    let path = write_indexed_session(
+        home,
+        "2025-01-01T12-00-00",
+        uuid,
+        "/work",
+        "main",
+        "gpt-5",
+        &[("user_message", "compress me please")],
+    );
+
+    let compressed = compress_rollout(&path).unwrap();
+    assert!(!path.exists());
+    assert!(compressed.to_string_lossy().ends_with(".jsonl.zst"));
+
+    let page = get_conversations(home, 10, None).await.unwrap();
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].path, compressed);
+
+    let InitialHistory::Resumed(resumed) = RolloutRecorder::get_rollout_history(&compressed)
+        .await
+        .unwrap()
+    else {
+        panic!("expected a resumed history");
+    };
+    assert_eq!(resumed.conversation_id.to_string(), uuid.to_string());
+
+    let mut index = SessionIndex::open(home).unwrap();
+    index.sync(home).unwrap();
+    let hits = index
+        .search(&SessionQuery {
+            text: Some("compress".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].path, compressed);
+}
+
+fn write_exportable_session(root: &Path, uuid: Uuid) -> std::path::PathBuf {
+    let ts_str = "2025-03-01T09-00-00";
+    let path = root.join(format!("rollout-{ts_str}-{uuid}.jsonl"));
+    let mut file = File::create(&path).unwrap();
+    let lines = [
+        serde_json::json!({
+            "timestamp": ts_str,
+            "type": "session_meta",
+            "payload": {
+                "id": uuid,
+                "timestamp": ts_str,
+                "instructions": null,
+                "cwd": "/work/api",
+                "originator": "test_originator",
+                "cli_version": "test_version",
+                "git": { "branch": "main" }
+            }
+        }),
+        serde_json::json!({
+            "timestamp": ts_str,
+            "type": "response_item",
+            "payload": {
+                "type": "message",
+                "role": "user",
+                "content": [{ "type": "input_text", "text": "List the files in /work/api" }]
+            }
+        }),
+        serde_json::json!({
+            "timestamp": ts_str,
+            "type": "response_item",
+            "payload": {
+                "type": "function_call",
+                "name": "shell",
+                "arguments": "{\"command\":[\"bash\",\"-lc\",\"ls /work/api\"]}",
+                "call_id": "call-1"
+            }
+        }),
+        serde_json::json!({
+            "timestamp": ts_str,
+            "type": "response_item",
+            "payload": {
+                "type": "function_call_output",
+                "call_id": "call-1",
+                "output": "{\"output\":\"Cargo.toml\\nsrc\\n\",\"metadata\":{\"exit_code\":0,\"duration_seconds\":0.1}}"
+            }
+        }),
+        serde_json::json!({
+            "timestamp": ts_str,
+            "type": "response_item",
+            "payload": {
+                "type": "message",
+                "role": "assistant",
+                "content": [{ "type": "output_text", "text": "There is a `Cargo.toml` and a `src` directory." }]
+            }
+        }),
+    ];
+    for line in lines {
+        writeln!(file, "{line}").unwrap();
+    }
+    path
+}
+
+#[tokio::test]
+async fn test_export_session_markdown() {
+    let temp = TempDir::new().unwrap();
+    let path = write_exportable_session(temp.path(), Uuid::from_u128(7));
+
+    let markdown = export_session(&path, ExportFormat::Markdown, ExportOptions::default())
+        .await
+        .unwrap();
+
+    assert!(markdown.starts_with("# Codex session 00000000-0000-0000-0000-000000000007\n"));
+    assert!(markdown.contains("- **Branch:** main"));
+    assert!(markdown.contains("## User\n\nList the files in /work/api\n"));
+    assert!(markdown.contains("```sh\n$ ls /work/api\n```"));
+    assert!(markdown.contains("```text\nCargo.toml\nsrc\n```"));
+    assert!(markdown.contains("Exit code: 0"));
+    assert!(markdown.contains("## Codex\n\nThere is a `Cargo.toml` and a `src` directory."));
+}
+
+#[tokio::test]
+async fn test_export_session_json_redacts_outputs_and_paths() {
+    let temp = TempDir::new().unwrap();
+    let path = write_exportable_session(temp.path(), Uuid::from_u128(8));
+
+    let options = ExportOptions {
+        redact_outputs: true,
+        redact_paths: true,
+    };
+    let json = export_session(&path, ExportFormat::Json, options)
+        .await
+        .unwrap();
+    let bundle: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(bundle["version"], 1);
+    assert_eq!(bundle["session"]["cwd"], "<cwd>");
+    let entries = bundle["entries"].as_array().unwrap();
+    assert_eq!(
+        entries[0],
+        serde_json::json!({ "type": "user_message", "text": "List the files in <cwd>" })
+    );
+    assert_eq!(entries[1]["type"], "command");
+    assert_eq!(entries[1]["command"], "ls <cwd>");
+    assert_eq!(entries[1]["exit_code"], 0);
+    assert_eq!(entries[1]["output"], "[output redacted]");
+    assert!(!json.contains("/work/api"));
+}
@@ -6,8 +6,10 @@ use std::path::PathBuf;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
 
+use crate::config_types::LinuxSandboxBackend;
 use crate::exec::SandboxType;
-use crate::is_safe_command::is_known_safe_command;
+use crate::is_safe_command::CommandSafetyVerdict;
+use crate::is_safe_command::analyze_command_safety;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
 
@@ -78,12 +80,17 @@ pub fn assess_patch_safety(
 /// - the user has explicitly approved the command
 /// - the command is on the "known safe" list
 /// - `DangerFullAccess` was specified and `UnlessTrusted` was not
+///
+/// Known-safe commands whose only side effect is writing to files through
+/// output redirections are treated like patches: they run in the sandbox when
+/// every target is inside a writable root and need approval otherwise.
 pub fn assess_command_safety(
     command: &[String],
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
     approved: &HashSet<Vec<String>>,
     with_escalated_permissions: bool,
+    cwd: &Path,
 ) -> SafetyCheck {
     // A command is "trusted" because either:
     // - it belongs to a set of commands we consider "safe" by default, or
@@ -94,17 +101,71 @@ pub fn assess_command_safety(
     // should be run inside a sandbox or not. (This could be something the user
     // defines as part of `execpolicy`.)
     //
-    // For example, when `is_known_safe_command(command)` returns `true`, it
-    // would probably be fine to run the command in a sandbox, but when
+    // For example, when `analyze_command_safety(command)` returns `KnownSafe`,
+    // it would probably be fine to run the command in a sandbox, but when
     // `approved.contains(command)` is `true`, the user may have approved it for
     // the session _because_ they know it needs to run outside a sandbox.
-    if is_known_safe_command(command) || approved.contains(command) {
+    if approved.contains(command) {
         return SafetyCheck::AutoApprove {
             sandbox_type: SandboxType::None,
         };
     }
 
-    assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
+    match analyze_command_safety(command) {
+        CommandSafetyVerdict::KnownSafe => SafetyCheck::AutoApprove {
+            sandbox_type: SandboxType::None,
+        },
+        CommandSafetyVerdict::WritesFiles { paths }
+            if !with_escalated_permissions && approval_policy != AskForApproval::UnlessTrusted =>
+        {
+            assess_safety_for_redirect_writes(&paths, approval_policy, sandbox_policy, cwd)
+        }
+        CommandSafetyVerdict::WritesFiles { .. } | CommandSafetyVerdict::NotKnownSafe => {
+            assess_safety_for_untrusted_command(
+                approval_policy,
+                sandbox_policy,
+                with_escalated_permissions,
+            )
+        }
+    }
+}
+
+fn assess_safety_for_redirect_writes(
+    paths: &[PathBuf],
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> SafetyCheck {
+    let outside: Vec<&PathBuf> = paths
+        .iter()
+        .filter(|path| !is_write_constrained_to_writable_paths(path.as_path(), sandbox_policy, cwd))
+        .collect();
+    if outside.is_empty() {
+        return match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None if sandbox_policy == &SandboxPolicy::DangerFullAccess => {
+                SafetyCheck::AutoApprove {
+                    sandbox_type: SandboxType::None,
+                }
+            }
+            None => SafetyCheck::AskUser,
+        };
+    }
+
+    if approval_policy == AskForApproval::Never {
+        let outside = outside
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        SafetyCheck::Reject {
+            reason: format!(
+                "writing to {outside} outside of the project; rejected by user approval settings"
+            ),
+        }
+    } else {
+        SafetyCheck::AskUser
+    }
 }
 
 pub(crate) fn assess_safety_for_untrusted_command(
@@ -176,10 +237,55 @@ pub fn get_platform_sandbox() -> Option<SandboxType> {
     }
 }
 
+/// Swaps the Landlock-based Linux sandbox for the namespace-based one when the
+/// user selected [`LinuxSandboxBackend::Namespaces`]. Other sandbox types are
+/// returned unchanged.
+pub fn apply_linux_sandbox_backend(
+    sandbox_type: SandboxType,
+    backend: LinuxSandboxBackend,
+) -> SandboxType {
+    match (sandbox_type, backend) {
+        (SandboxType::LinuxSeccomp, LinuxSandboxBackend::Namespaces) => {
+            SandboxType::LinuxNamespaces
+        }
+        (sandbox_type, _) => sandbox_type,
+    }
+}
+
 fn is_write_patch_constrained_to_writable_paths(
     action: &ApplyPatchAction,
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
+) -> bool {
+    for (path, change) in action.changes() {
+        match change {
+            ApplyPatchFileChange::Add { .. } | ApplyPatchFileChange::Delete { .. } => {
+                if !is_write_constrained_to_writable_paths(path, sandbox_policy, cwd) {
+                    return false;
+                }
+            }
+            ApplyPatchFileChange::Update { move_path, .. } => {
+                if !is_write_constrained_to_writable_paths(path, sandbox_policy, cwd) {
+                    return false;
+                }
+                if let Some(dest) = move_path
+                    && !is_write_constrained_to_writable_paths(dest, sandbox_policy, cwd)
+                {
+                    return false;
+                }
+            }
+        }
+    }
+
+    true
+}
+
+/// Determine whether `path` is inside **any** writable root. Both `path` and
+/// roots are converted to absolute, normalized forms before the prefix check.
+fn is_write_constrained_to_writable_paths(
+    path: &Path,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
 ) -> bool {
     // Early‑exit if there are no declared writable roots.
     let writable_roots = match sandbox_policy {
@@ -208,46 +314,19 @@ fn is_write_patch_constrained_to_writable_paths(
         Some(out)
     }
 
-    // Determine whether `path` is inside **any** writable root. Both `path`
-    // and roots are converted to absolute, normalized forms before the
-    // prefix check.
-    let is_path_writable = |p: &PathBuf| {
-        let abs = if p.is_absolute() {
-            p.clone()
-        } else {
-            cwd.join(p)
-        };
-        let abs = match normalize(&abs) {
-            Some(v) => v,
-            None => return false,
-        };
-
-        writable_roots
-            .iter()
-            .any(|writable_root| writable_root.is_path_writable(&abs))
+    let abs = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        cwd.join(path)
+    };
+    let abs = match normalize(&abs) {
+        Some(v) => v,
+        None => return false,
     };
 
-    for (path, change) in action.changes() {
-        match change {
-            ApplyPatchFileChange::Add { .. } | ApplyPatchFileChange::Delete { .. } => {
-                if !is_path_writable(path) {
-                    return false;
-                }
-            }
-            ApplyPatchFileChange::Update { move_path, .. } => {
-                if !is_path_writable(path) {
-                    return false;
-                }
-                if let Some(dest) = move_path
-                    && !is_path_writable(dest)
-                {
-                    return false;
-                }
-            }
-        }
-    }
-
-    true
+    writable_roots
+        .iter()
+        .any(|writable_root| writable_root.is_path_writable(&abs))
 }
 
 #[cfg(test)]
@@ -320,6 +399,7 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            Path::new("/"),
         );
 
         assert_eq!(safety_check, SafetyCheck::AskUser);
@@ -339,6 +419,7 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            Path::new("/"),
         );
 
         let expected = match get_platform_sandbox() {
@@ -347,4 +428,73 @@ mod tests {
         };
         assert_eq!(safety_check, expected);
     }
+
+    #[test]
+    fn test_redirect_writes_are_checked_against_writable_roots() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().to_path_buf();
+        let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let approved: HashSet<Vec<String>> = HashSet::new();
+        let bash = |script: &str| vec!["bash".to_string(), "-lc".to_string(), script.to_string()];
+
+        // Read-only redirects leave the command trusted.
+        assert_eq!(
+            assess_command_safety(
+                &bash("ls 2>/dev/null"),
+                AskForApproval::Never,
+                &sandbox_policy,
+                &approved,
+                false,
+                &cwd,
+            ),
+            SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::None
+            }
+        );
+
+        let inside = assess_command_safety(
+            &bash("git diff > changes.patch"),
+            AskForApproval::OnRequest,
+            &sandbox_policy,
+            &approved,
+            false,
+            &cwd,
+        );
+        let expected = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None => SafetyCheck::AskUser,
+        };
+        assert_eq!(inside, expected);
+
+        let outside = bash("git diff > ../changes.patch");
+        assert_eq!(
+            assess_command_safety(
+                &outside,
+                AskForApproval::OnRequest,
+                &sandbox_policy,
+                &approved,
+                false,
+                &cwd,
+            ),
+            SafetyCheck::AskUser
+        );
+        assert_eq!(
+            assess_command_safety(
+                &outside,
+                AskForApproval::Never,
+                &sandbox_policy,
+                &approved,
+                false,
+                &cwd,
+            ),
+            SafetyCheck::Reject {
+                reason: "writing to ../changes.patch outside of the project; rejected by user approval settings".to_string(),
+            }
+        );
+    }
 }
@@ -0,0 +1,175 @@
+//! Local token counting.
+//!
+//! Servers only report [`TokenUsage`](crate::protocol::TokenUsage) after a
+//! request completes, and some Chat Completions providers never do. Counting
+//! locally lets Codex estimate the size of a prompt before sending it and
+//! express truncation limits in tokens.
+//!
+//! OpenAI models are counted with their tiktoken BPE tables, which are
+//! embedded in the binary. Other models fall back to four bytes per token.
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+use tiktoken_rs::CoreBPE;
+use tiktoken_rs::cl100k_base_singleton;
+use tiktoken_rs::o200k_base_singleton;
+
+use crate::model_family::ModelFamily;
+
+/// Texts longer than this are estimated rather than tokenized, which bounds
+/// the cost of counting very large tool outputs.
+const MAX_TOKENIZED_BYTES: usize = 1024 * 1024;
+
+const APPROX_BYTES_PER_TOKEN: u64 = 4;
+
+/// Tokens the API spends on the framing of every input item.
+const TOKENS_PER_ITEM: u64 = 4;
+
+/// Rough cost of an image input, whose real cost depends on its size.
+const TOKENS_PER_IMAGE: u64 = 765;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Tokenizer {
+    /// `o200k_base`, used by GPT-4o and every later OpenAI model.
+    #[default]
+    O200kBase,
+    /// `cl100k_base`, used by GPT-4 and GPT-3.5.
+    Cl100kBase,
+    /// Four bytes per token, for models whose vocabulary is unknown.
+    Heuristic,
+}
+
+impl Tokenizer {
+    pub(crate) fn for_model_family(model_family: &ModelFamily) -> Self {
+        let family = model_family.family.as_str();
+        const O200K_FAMILIES: &[&str] = &[
+            "gpt-5", "gpt-4o", "gpt-4.1", "gpt-oss", "o1", "o3", "o4", "codex-",
+        ];
+        if O200K_FAMILIES
+            .iter()
+            .any(|prefix| family.starts_with(prefix))
+        {
+            Tokenizer::O200kBase
+        } else if family.starts_with("gpt-4") || family.starts_with("gpt-3.5") {
+            Tokenizer::Cl100kBase
+        } else {
+            Tokenizer::Heuristic
+        }
+    }
+
+    fn bpe(self) -> Option<&'static CoreBPE> {
+        match self {
+            Tokenizer::O200kBase => Some(o200k_base_singleton()),
+            Tokenizer::Cl100kBase => Some(cl100k_base_singleton()),
+            Tokenizer::Heuristic => None,
+        }
+    }
+
+    /// Number of tokens in `text`.
+    pub(crate) fn count(self, text: &str) -> u64 {
+        match self.bpe() {
+            Some(bpe) if text.len() <= MAX_TOKENIZED_BYTES => {
+                bpe.encode_ordinary(text).len() as u64
+            }
+            _ => (text.len() as u64).div_ceil(APPROX_BYTES_PER_TOKEN),
+        }
+    }
+
+    /// Estimated number of input tokens of a request with `instructions`
+    /// and `input`.
+    pub(crate) fn count_prompt(self, instructions: &str, input: &[ResponseItem]) -> u64 {
+        self.count(instructions) + input.iter().map(|item| self.count_item(item)).sum::<u64>()
+    }
+
+    /// Estimated number of tokens `item` occupies in the context window.
+    /// Encrypted reasoning is opaque and not counted.
+    pub(crate) fn count_item(self, item: &ResponseItem) -> u64 {
+        let content = match item {
+            ResponseItem::Message { content, .. } => content
+                .iter()
+                .map(|content| match content {
+                    ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                        self.count(text)
+                    }
+                    ContentItem::InputImage { .. } => TOKENS_PER_IMAGE,
+                })
+                .sum(),
+            ResponseItem::Reasoning { summary, .. } => summary
+                .iter()
+                .map(|ReasoningItemReasoningSummary::SummaryText { text }| self.count(text))
+                .sum(),
+            ResponseItem::FunctionCall {
+                name, arguments, ..
+            } => self.count(name) + self.count(arguments),
+            ResponseItem::FunctionCallOutput { output, .. } => self.count(&output.content),
+            ResponseItem::CustomToolCall { name, input, .. } => {
+                self.count(name) + self.count(input)
+            }
+            ResponseItem::CustomToolCallOutput { output, .. } => self.count(output),
+            ResponseItem::LocalShellCall { .. } | ResponseItem::WebSearchCall { .. } => {
+                serde_json::to_string(item)
+                    .map(|json| self.count(&json))
+                    .unwrap_or_default()
+            }
+            ResponseItem::Other => return 0,
+        };
+        content + TOKENS_PER_ITEM
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::derive_default_model_family;
+    use crate::model_family::find_family_for_model;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn picks_the_vocabulary_of_the_model_family() {
+        let tokenizer = |slug: &str| {
+            Tokenizer::for_model_family(
+                &find_family_for_model(slug).unwrap_or_else(|| derive_default_model_family(slug)),
+            )
+        };
+        assert_eq!(tokenizer("gpt-5-codex"), Tokenizer::O200kBase);
+        assert_eq!(tokenizer("o3"), Tokenizer::O200kBase);
+        assert_eq!(tokenizer("gpt-4o-mini"), Tokenizer::O200kBase);
+        assert_eq!(tokenizer("gpt-4-turbo"), Tokenizer::Cl100kBase);
+        assert_eq!(tokenizer("gpt-3.5-turbo"), Tokenizer::Cl100kBase);
+        assert_eq!(tokenizer("claude-sonnet-4"), Tokenizer::Heuristic);
+    }
+
+    #[test]
+    fn counts_tokens_with_bpe_and_heuristic() {
+        assert_eq!(Tokenizer::O200kBase.count("hello world"), 2);
+        assert_eq!(Tokenizer::Cl100kBase.count("hello world"), 2);
+        assert_eq!(Tokenizer::Heuristic.count("hello world"), 3);
+        assert_eq!(Tokenizer::O200kBase.count(""), 0);
+    }
+
+    #[test]
+    fn counts_items_with_framing_overhead() {
+        let message = ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![
+                ContentItem::InputText {
+                    text: "hello world".to_string(),
+                },
+                ContentItem::InputImage {
+                    image_url: "data:image/png;base64,AAAA".to_string(),
+                },
+            ],
+        };
+        assert_eq!(
+            Tokenizer::O200kBase.count_item(&message),
+            2 + TOKENS_PER_IMAGE + TOKENS_PER_ITEM
+        );
+        assert_eq!(Tokenizer::O200kBase.count_item(&ResponseItem::Other), 0);
+        assert_eq!(
+            Tokenizer::O200kBase.count_prompt("hello world", std::slice::from_ref(&message)),
+            2 + 2 + TOKENS_PER_IMAGE + TOKENS_PER_ITEM
+        );
+    }
+}
@@ -1,20 +1,59 @@
 //! Utilities for truncating large chunks of output while preserving a prefix
 //! and suffix on UTF-8 boundaries.
 
-/// Truncate the middle of a UTF-8 string to at most `max_bytes` bytes,
-/// preserving the beginning and the end. Returns the possibly truncated
-/// string and `Some(original_token_count)` (estimated at 4 bytes/token)
-/// if truncation occurred; otherwise returns the original string and `None`.
-pub(crate) fn truncate_middle(s: &str, max_bytes: usize) -> (String, Option<u64>) {
-    if s.len() <= max_bytes {
+use crate::tokenizer::Tokenizer;
+
+/// Truncate the middle of a UTF-8 string to at most `max_tokens` tokens (as
+/// counted by `tokenizer`), preserving the beginning and the end. Returns the
+/// possibly truncated string and `Some(original_token_count)` if truncation
+/// occurred; otherwise returns the original string and `None`.
+pub(crate) fn truncate_middle(
+    s: &str,
+    max_tokens: u64,
+    tokenizer: Tokenizer,
+) -> (String, Option<u64>) {
+    // Every token covers at least one byte.
+    if s.len() as u64 <= max_tokens {
+        return (s.to_string(), None);
+    }
+    let total_tokens = tokenizer.count(s);
+    if total_tokens <= max_tokens {
         return (s.to_string(), None);
     }
 
-    let est_tokens = (s.len() as u64).div_ceil(4);
-    if max_bytes == 0 {
-        return (format!("…{est_tokens} tokens truncated…"), Some(est_tokens));
+    let fully_truncated = format!("…{total_tokens} tokens truncated…");
+    let marker_tokens = tokenizer.count(&fully_truncated) + 1;
+    if max_tokens <= marker_tokens {
+        return (fully_truncated, Some(total_tokens));
     }
 
+    // Start from the byte budget implied by the density of `s` and shrink it
+    // until the result, marker included, fits.
+    let content_tokens = max_tokens - marker_tokens;
+    let mut max_bytes = (s.len() as u128 * u128::from(content_tokens) / u128::from(total_tokens))
+        .try_into()
+        .unwrap_or(usize::MAX);
+    loop {
+        if max_bytes == 0 {
+            return (fully_truncated, Some(total_tokens));
+        }
+        let out = truncate_middle_bytes(s, max_bytes, total_tokens, tokenizer);
+        if tokenizer.count(&out) <= max_tokens {
+            return (out, Some(total_tokens));
+        }
+        max_bytes = max_bytes * 9 / 10;
+    }
+}
+
+/// Keeps at most `max_bytes` bytes of `s`, split between a prefix and a
+/// suffix that end on line boundaries when possible, joined by a marker that
+/// reports how many of the `total_tokens` tokens were removed.
+fn truncate_middle_bytes(
+    s: &str,
+    max_bytes: usize,
+    total_tokens: u64,
+    tokenizer: Tokenizer,
+) -> String {
     fn truncate_on_boundary(input: &str, max_len: usize) -> &str {
         if input.len() <= max_len {
             return input;
@@ -50,72 +89,37 @@ pub(crate) fn truncate_middle(s: &str, max_bytes: usize) -> (String, Option<u64>
         idx
     }
 
-    let mut guess_tokens = est_tokens;
-    for _ in 0..4 {
-        let marker = format!("…{guess_tokens} tokens truncated…");
-        let marker_len = marker.len();
-        let keep_budget = max_bytes.saturating_sub(marker_len);
-        if keep_budget == 0 {
-            return (format!("…{est_tokens} tokens truncated…"), Some(est_tokens));
-        }
-
-        let left_budget = keep_budget / 2;
-        let right_budget = keep_budget - left_budget;
-        let prefix_end = pick_prefix_end(s, left_budget);
-        let mut suffix_start = pick_suffix_start(s, right_budget);
-        if suffix_start < prefix_end {
-            suffix_start = prefix_end;
-        }
-
-        let kept_content_bytes = prefix_end + (s.len() - suffix_start);
-        let truncated_content_bytes = s.len().saturating_sub(kept_content_bytes);
-        let new_tokens = (truncated_content_bytes as u64).div_ceil(4);
-
-        if new_tokens == guess_tokens {
-            let mut out = String::with_capacity(marker_len + kept_content_bytes + 1);
-            out.push_str(&s[..prefix_end]);
-            out.push_str(&marker);
-            out.push('\n');
-            out.push_str(&s[suffix_start..]);
-            return (out, Some(est_tokens));
-        }
-
-        guess_tokens = new_tokens;
-    }
-
-    let marker = format!("…{guess_tokens} tokens truncated…");
-    let marker_len = marker.len();
-    let keep_budget = max_bytes.saturating_sub(marker_len);
-    if keep_budget == 0 {
-        return (format!("…{est_tokens} tokens truncated…"), Some(est_tokens));
-    }
-
-    let left_budget = keep_budget / 2;
-    let right_budget = keep_budget - left_budget;
+    let left_budget = max_bytes / 2;
+    let right_budget = max_bytes - left_budget;
     let prefix_end = pick_prefix_end(s, left_budget);
-    let suffix_start = pick_suffix_start(s, right_budget);
+    let suffix_start = pick_suffix_start(s, right_budget).max(prefix_end);
 
-    let mut out = String::with_capacity(marker_len + prefix_end + (s.len() - suffix_start) + 1);
+    let kept_tokens = tokenizer.count(&s[..prefix_end]) + tokenizer.count(&s[suffix_start..]);
+    let removed_tokens = total_tokens.saturating_sub(kept_tokens);
+    let marker = format!("…{removed_tokens} tokens truncated…");
+    let mut out = String::with_capacity(marker.len() + prefix_end + (s.len() - suffix_start) + 1);
     out.push_str(&s[..prefix_end]);
     out.push_str(&marker);
     out.push('\n');
     out.push_str(&s[suffix_start..]);
-    (out, Some(est_tokens))
+    out
 }
 
 #[cfg(test)]
 mod tests {
     use super::truncate_middle;
+    use crate::tokenizer::Tokenizer;
 
     #[test]
     fn truncate_middle_no_newlines_fallback() {
         let s = "abcdefghijklmnopqrstuvwxyz0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ*";
-        let max_bytes = 32;
-        let (out, original) = truncate_middle(s, max_bytes);
+        let max_tokens = 10;
+        let (out, original) = truncate_middle(s, max_tokens, Tokenizer::default());
         assert!(out.starts_with("abc"));
         assert!(out.contains("tokens truncated"));
-        assert!(out.ends_with("XYZ*"));
-        assert_eq!(original, Some((s.len() as u64).div_ceil(4)));
+        assert!(out.ends_with("YZ*"));
+        assert!(Tokenizer::default().count(&out) <= max_tokens);
+        assert_eq!(original, Some(Tokenizer::default().count(s)));
     }
 
     #[test]
@@ -126,23 +130,23 @@ mod tests {
         }
         assert_eq!(s.len(), 80);
 
-        let max_bytes = 64;
-        let (out, tokens) = truncate_middle(&s, max_bytes);
-        assert!(out.starts_with("001\n002\n003\n004\n"));
+        let max_tokens = 16;
+        let (out, tokens) = truncate_middle(&s, max_tokens, Tokenizer::default());
+        assert!(out.starts_with("001\n002\n"));
         assert!(out.contains("tokens truncated"));
-        assert!(out.ends_with("017\n018\n019\n020\n"));
-        assert_eq!(tokens, Some(20));
+        assert!(out.ends_with("019\n020\n"));
+        assert_eq!(tokens, Some(Tokenizer::default().count(&s)));
     }
 
     #[test]
     fn truncate_middle_handles_utf8_content() {
         let s = "😀😀😀😀😀😀😀😀😀😀\nsecond line with ascii text\n";
-        let max_bytes = 32;
-        let (out, tokens) = truncate_middle(s, max_bytes);
+        let max_tokens = 12;
+        let (out, tokens) = truncate_middle(s, max_tokens, Tokenizer::default());
 
         assert!(out.contains("tokens truncated"));
         assert!(!out.contains('\u{fffd}'));
-        assert_eq!(tokens, Some((s.len() as u64).div_ceil(4)));
+        assert_eq!(tokens, Some(Tokenizer::default().count(s)));
     }
 
     #[test]
@@ -152,28 +156,28 @@ mod tests {
         for i in 1..=20 {
             s.push_str(&format!("{i:03}\n"));
         }
-        // Total length: 20 lines * 4 bytes per line = 80 bytes.
-        assert_eq!(s.len(), 80);
+        // Each line is two tokens: the number and the newline.
+        assert_eq!(Tokenizer::default().count(&s), 40);
 
         // Choose a cap that forces truncation while leaving room for
         // a few lines on each side after accounting for the marker.
-        let max_bytes = 64;
-        // Expect exact output: first 4 lines, marker, last 4 lines, and correct token estimate (80/4 = 20).
+        let max_tokens = 24;
+        // Expect exact output: first 4 lines, marker, last 4 lines, and the original token count.
         assert_eq!(
-            truncate_middle(&s, max_bytes),
+            truncate_middle(&s, max_tokens, Tokenizer::default()),
             (
                 r#"001
 002
 003
 004
-…12 tokens truncated…
+…24 tokens truncated…
 017
 018
 019
 020
 "#
                 .to_string(),
-                Some(20)
+                Some(40)
             )
         );
     }
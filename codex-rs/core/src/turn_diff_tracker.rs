@@ -127,6 +127,20 @@ impl TurnDiffTracker {
         }
     }
 
+    /// Every path the tracked patches touched: the current path of each file
+    /// and, for moved files, the path they were moved from.
+    pub fn touched_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .baseline_file_info
+            .values()
+            .map(|info| info.path.clone())
+            .chain(self.temp_name_to_current_path.values().cloned())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
     fn get_path_for_internal(&self, internal: &str) -> Option<PathBuf> {
         self.temp_name_to_current_path
             .get(internal)
@@ -607,6 +621,34 @@ index {left_oid}..{ZERO_OID}
         assert_eq!(diff, expected);
     }
 
+    #[test]
+    fn touched_paths_include_both_ends_of_a_move() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dest = dir.path().join("dst.txt");
+        let added = dir.path().join("added.txt");
+        fs::write(&src, "line\n").unwrap();
+
+        let mut acc = TurnDiffTracker::new();
+        acc.on_patch_begin(&HashMap::from([
+            (
+                src.clone(),
+                FileChange::Update {
+                    unified_diff: "".to_owned(),
+                    move_path: Some(dest.clone()),
+                },
+            ),
+            (
+                added.clone(),
+                FileChange::Add {
+                    content: "new\n".to_string(),
+                },
+            ),
+        ]));
+
+        assert_eq!(acc.touched_paths(), vec![added, dest, src]);
+    }
+
     #[test]
     fn accumulates_move_and_update() {
         let dir = tempdir().unwrap();
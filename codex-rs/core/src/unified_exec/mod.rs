@@ -18,6 +18,7 @@ use tokio::time::Duration;
 use tokio::time::Instant;
 
 use crate::exec_command::ExecCommandSession;
+use crate::tokenizer::Tokenizer;
 use crate::truncate::truncate_middle;
 
 mod errors;
@@ -27,12 +28,15 @@ pub(crate) use errors::UnifiedExecError;
 const DEFAULT_TIMEOUT_MS: u64 = 1_000;
 const MAX_TIMEOUT_MS: u64 = 60_000;
 const UNIFIED_EXEC_OUTPUT_MAX_BYTES: usize = 128 * 1024; // 128 KiB
+const UNIFIED_EXEC_OUTPUT_MAX_TOKENS: u64 = 32 * 1024;
 
 #[derive(Debug)]
 pub(crate) struct UnifiedExecRequest<'a> {
     pub session_id: Option<i32>,
     pub input_chunks: &'a [String],
     pub timeout_ms: Option<u64>,
+    /// Counts the tokens of the output against its cap.
+    pub tokenizer: Tokenizer,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -255,7 +259,8 @@ impl UnifiedExecSessionManager {
 
         let (output, _maybe_tokens) = truncate_middle(
             &String::from_utf8_lossy(&collected),
-            UNIFIED_EXEC_OUTPUT_MAX_BYTES,
+            UNIFIED_EXEC_OUTPUT_MAX_TOKENS,
+            request.tokenizer,
         );
         let output = if let Some(warning) = timeout_warning {
             format!("{warning}{output}")
@@ -432,6 +437,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
         let session_id = open_shell.session_id.expect("expected session_id");
@@ -444,6 +450,7 @@ mod tests {
                     "CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string(),
                 ],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
 
@@ -452,6 +459,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &["echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
         assert!(out_2.output.contains("codex"));
@@ -469,6 +477,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["/bin/bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
         let session_a = shell_a.session_id.expect("expected session id");
@@ -478,6 +487,7 @@ mod tests {
                 session_id: Some(session_a),
                 input_chunks: &["export CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string()],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
 
@@ -489,6 +499,7 @@ mod tests {
                     "$CODEX_INTERACTIVE_SHELL_VAR\n".to_string(),
                 ],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
         assert!(!out_2.output.contains("codex"));
@@ -498,6 +509,7 @@ mod tests {
                 session_id: Some(session_a),
                 input_chunks: &["echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
         assert!(out_3.output.contains("codex"));
@@ -515,6 +527,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
         let session_id = open_shell.session_id.expect("expected session id");
@@ -527,6 +540,7 @@ mod tests {
                     "CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string(),
                 ],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
 
@@ -535,6 +549,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &["sleep 5 && echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
                 timeout_ms: Some(10),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
         assert!(!out_2.output.contains("codex"));
@@ -547,6 +562,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &empty,
                 timeout_ms: Some(100),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
 
@@ -566,6 +582,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["echo".to_string(), "codex".to_string()],
                 timeout_ms: Some(120_000),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
 
@@ -587,6 +604,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["/bin/echo".to_string(), "codex".to_string()],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
 
@@ -608,6 +626,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["/bin/bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
         let session_id = open_shell.session_id.expect("expected session id");
@@ -617,6 +636,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &["exit\n".to_string()],
                 timeout_ms: Some(2_500),
+                tokenizer: Tokenizer::default(),
             })
             .await?;
 
@@ -627,6 +647,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &[],
                 timeout_ms: Some(100),
+                tokenizer: Tokenizer::default(),
             })
             .await
             .expect_err("expected unknown session error");
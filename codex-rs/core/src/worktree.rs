@@ -0,0 +1,182 @@
+//! Sessions that run in their own `git worktree` on a new branch, leaving
+//! the user's checkout untouched until the session's changes are merged.
+//!
+//! The worktree is a linked worktree of the user's repository, so
+//! [`resolve_root_git_project_for_trust`] maps it back to the main project
+//! and the project's trust settings apply inside it unchanged.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_git_tooling::CreateWorktreeOptions;
+use codex_git_tooling::GitToolingError;
+use codex_git_tooling::Worktree;
+use codex_git_tooling::commit_worktree_changes;
+use codex_git_tooling::create_worktree;
+use codex_git_tooling::merge_worktree;
+use codex_git_tooling::remove_worktree;
+use codex_protocol::config_types::WorktreeAction;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::git_info::resolve_root_git_project_for_trust;
+
+/// Directory inside `~/.codex` that holds session worktrees by default.
+pub const WORKTREES_SUBDIR: &str = "worktrees";
+
+/// Commit message used for changes left uncommitted in a worktree.
+const SESSION_COMMIT_MESSAGE: &str = "Changes from Codex session";
+
+/// A worktree created for one session.
+#[derive(Debug, Clone)]
+pub struct SessionWorktree {
+    worktree: Worktree,
+}
+
+impl SessionWorktree {
+    /// Create a worktree for a session started in `config.cwd`, as
+    /// configured by `config.worktree`, and point `config.cwd` at the
+    /// matching directory inside it.
+    pub fn enter(config: &mut Config) -> Result<Self, GitToolingError> {
+        let Some(project_root) = resolve_root_git_project_for_trust(&config.cwd) else {
+            return Err(GitToolingError::NotAGitRepository {
+                path: config.cwd.clone(),
+            });
+        };
+        let id = Uuid::new_v4().simple().to_string();
+        let id = &id[..8];
+        let project_name = project_root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "project".to_string());
+        let dir = config
+            .worktree
+            .dir
+            .clone()
+            .unwrap_or_else(|| config.codex_home.join(WORKTREES_SUBDIR));
+        let path = dir.join(format!("{project_name}-{id}"));
+        let branch = format!("{}{id}", config.worktree.branch_prefix);
+
+        let worktree = create_worktree(&CreateWorktreeOptions::new(&config.cwd, &path, &branch))?;
+        config.cwd = worktree.cwd();
+        Ok(Self { worktree })
+    }
+
+    /// Root of the worktree checkout.
+    pub fn path(&self) -> &Path {
+        self.worktree.path()
+    }
+
+    /// Working directory of the session inside the worktree.
+    pub fn cwd(&self) -> PathBuf {
+        self.worktree.cwd()
+    }
+
+    /// Branch the session's changes are made on.
+    pub fn branch(&self) -> &str {
+        self.worktree.branch()
+    }
+
+    /// Root of the checkout the session was started from.
+    pub fn origin(&self) -> &Path {
+        self.worktree.origin()
+    }
+
+    /// Apply `action` once the session has ended. A failed merge leaves the
+    /// worktree and its branch in place.
+    pub fn finish(&self, action: WorktreeAction) -> Result<(), GitToolingError> {
+        match action {
+            WorktreeAction::Merge => merge_worktree(&self.worktree, SESSION_COMMIT_MESSAGE),
+            WorktreeAction::Keep => {
+                commit_worktree_changes(&self.worktree, SESSION_COMMIT_MESSAGE)?;
+                remove_worktree(&self.worktree, false)
+            }
+            WorktreeAction::Discard => remove_worktree(&self.worktree, true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use pretty_assertions::assert_eq;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(cwd: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .status()
+            .expect("git");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn config_in(codex_home: &TempDir, cwd: &Path) -> Config {
+        Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides {
+                cwd: Some(cwd.to_path_buf()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )
+        .expect("config")
+    }
+
+    #[test]
+    fn session_runs_in_worktree_of_the_same_project() {
+        let codex_home = TempDir::new().unwrap();
+        let repo_dir = TempDir::new().unwrap();
+        let repo = repo_dir.path().join("app");
+        std::fs::create_dir_all(repo.join("crates")).unwrap();
+        git(&repo, &["init", "-q", "--initial-branch=main"]);
+        std::fs::write(repo.join("crates/lib.rs"), "fn main() {}\n").unwrap();
+        git(&repo, &["add", "."]);
+        git(
+            &repo,
+            &[
+                "-c",
+                "user.name=Test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "-m",
+                "init",
+            ],
+        );
+
+        let mut config = config_in(&codex_home, &repo.join("crates"));
+        let worktree = SessionWorktree::enter(&mut config).unwrap();
+
+        assert_eq!(config.cwd, worktree.cwd());
+        assert!(config.cwd.ends_with("crates"));
+        assert!(
+            worktree
+                .path()
+                .starts_with(codex_home.path().join(WORKTREES_SUBDIR))
+        );
+        assert!(worktree.branch().starts_with("codex/"));
+        assert!(config.cwd.join("lib.rs").exists());
+        assert_eq!(
+            resolve_root_git_project_for_trust(&config.cwd),
+            resolve_root_git_project_for_trust(&repo),
+        );
+
+        worktree.finish(WorktreeAction::Discard).unwrap();
+        assert!(!worktree.path().exists());
+    }
+
+    #[test]
+    fn entering_requires_a_git_repository() {
+        let codex_home = TempDir::new().unwrap();
+        let dir = TempDir::new().unwrap();
+        let mut config = config_in(&codex_home, dir.path());
+        let err = SessionWorktree::enter(&mut config).unwrap_err();
+        assert!(matches!(err, GitToolingError::NotAGitRepository { .. }));
+        assert_eq!(config.cwd, dir.path());
+    }
+}
@@ -0,0 +1,118 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! Verifies that commands run through the streamable and unified exec tools
+//! are recorded in the audit log.
+
+use codex_core::audit::AuditEvent;
+use codex_core::audit::AuditRecord;
+use codex_core::audit::audit_log_path;
+use codex_core::exec::SandboxType;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use wiremock::Mock;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exec_command_and_unified_exec_calls_are_audited() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    let exec_command = serde_json::json!({
+        "cmd": "echo streamed",
+        "shell": "/bin/sh",
+        "login": false,
+    })
+    .to_string();
+    let unified_exec = serde_json::json!({"input": ["echo", "unified"]}).to_string();
+    let bodies = vec![
+        sse(vec![
+            ev_function_call("call-exec-command", "exec_command", &exec_command),
+            ev_completed("r1"),
+        ]),
+        sse(vec![
+            ev_function_call("call-unified", "unified_exec", &unified_exec),
+            ev_completed("r2"),
+        ]),
+        sse(vec![ev_assistant_message("m3", "done"), ev_completed("r3")]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let test = test_codex()
+        .with_config(|config| {
+            config.approval_policy = AskForApproval::Never;
+            config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+            config.use_experimental_unified_exec_tool = true;
+        })
+        .build(&server)
+        .await
+        .unwrap();
+
+    test.codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "run them".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&test.codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let log = std::fs::read_to_string(audit_log_path(test.home.path())).unwrap();
+    let execs: Vec<(String, Vec<String>, SandboxType, i32)> = log
+        .lines()
+        .map(|line| serde_json::from_str::<AuditRecord>(line).unwrap())
+        .filter_map(|record| match record.event {
+            AuditEvent::Exec {
+                call_id,
+                command,
+                sandbox,
+                exit_code,
+                ..
+            } => Some((call_id, command, sandbox, exit_code)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        execs,
+        vec![
+            (
+                "call-exec-command".to_string(),
+                vec![
+                    "/bin/sh".to_string(),
+                    "-c".to_string(),
+                    "echo streamed".to_string()
+                ],
+                SandboxType::None,
+                0,
+            ),
+            (
+                "call-unified".to_string(),
+                vec!["echo".to_string(), "unified".to_string()],
+                SandboxType::None,
+                -1,
+            ),
+        ]
+    );
+}
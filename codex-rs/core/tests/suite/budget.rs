@@ -0,0 +1,203 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! Verifies that soft budgets pause a task and hard budgets abort it.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use codex_core::config_types::Budget;
+use codex_core::config_types::ModelPricing;
+use codex_core::cost::cost_ledger_path;
+use codex_core::protocol::BudgetExceededEvent;
+use codex_core::protocol::BudgetKind;
+use codex_core::protocol::BudgetScope;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::TurnAbortReason;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed_with_tokens;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::Request;
+use wiremock::Respond;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// Every request consumes 100k input tokens, which costs $1.00 with the
+/// pricing installed by [`build_codex`] and stays below the auto-compaction
+/// limit.
+const TOKENS_PER_REQUEST: u64 = 100_000;
+
+struct SeqResponder {
+    bodies: Vec<String>,
+    calls: AtomicUsize,
+}
+
+impl Respond for SeqResponder {
+    fn respond(&self, _: &Request) -> ResponseTemplate {
+        let n = self.calls.fetch_add(1, Ordering::SeqCst);
+        sse_response(self.bodies[n].clone())
+    }
+}
+
+async fn mount_responses(server: &MockServer, bodies: Vec<String>) {
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(SeqResponder {
+            calls: AtomicUsize::new(0),
+            bodies,
+        })
+        .mount(server)
+        .await;
+}
+
+async fn build_codex(server: &MockServer, budget: Budget) -> TestCodex {
+    test_codex()
+        .with_config(move |config| {
+            config.model_pricing.insert(
+                config.model.clone(),
+                ModelPricing {
+                    input: 10.0,
+                    cached_input: None,
+                    output: 10.0,
+                },
+            );
+            config.budget = budget;
+        })
+        .build(server)
+        .await
+        .unwrap()
+}
+
+async fn submit_text(codex: &codex_core::CodexConversation, text: &str) {
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: text.into() }],
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn soft_budget_pauses_task_until_next_message() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    mount_responses(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("call_1", "does_not_exist", "{}"),
+                ev_completed_with_tokens("r1", TOKENS_PER_REQUEST),
+            ]),
+            sse(vec![
+                ev_assistant_message("m2", "done"),
+                ev_completed_with_tokens("r2", TOKENS_PER_REQUEST),
+            ]),
+        ],
+    )
+    .await;
+
+    let TestCodex { codex, .. } = build_codex(
+        &server,
+        Budget {
+            session_soft_usd: Some(0.5),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    submit_text(&codex, "first").await;
+    let EventMsg::BudgetExceeded(event) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::BudgetExceeded(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(
+        event,
+        BudgetExceededEvent {
+            scope: BudgetScope::Session,
+            kind: BudgetKind::Soft,
+            limit_usd: 0.5,
+            spent_usd: 1.0,
+        }
+    );
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    // The tool output was not sent back to the model.
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+    // Sending another message resumes without a second warning.
+    submit_text(&codex, "continue").await;
+    let ev = wait_for_event(&codex, |ev| {
+        matches!(ev, EventMsg::BudgetExceeded(_) | EventMsg::TaskComplete(_))
+    })
+    .await;
+    assert!(matches!(ev, EventMsg::TaskComplete(_)), "got {ev:?}");
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn hard_budget_aborts_turn() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    mount_responses(
+        &server,
+        vec![sse(vec![
+            ev_assistant_message("m1", "hello"),
+            ev_completed_with_tokens("r1", TOKENS_PER_REQUEST),
+        ])],
+    )
+    .await;
+
+    let TestCodex { home, codex, .. } = build_codex(
+        &server,
+        Budget {
+            session_hard_usd: Some(0.5),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    submit_text(&codex, "first").await;
+    let EventMsg::TokenCount(token_count) = wait_for_event(
+        &codex,
+        |ev| matches!(ev, EventMsg::TokenCount(ev) if ev.info.is_some()),
+    )
+    .await
+    else {
+        unreachable!();
+    };
+    assert_eq!(token_count.info.unwrap().total_cost_usd, Some(1.0));
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    submit_text(&codex, "second").await;
+    let EventMsg::BudgetExceeded(event) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::BudgetExceeded(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(event.kind, BudgetKind::Hard);
+    let EventMsg::TurnAborted(aborted) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TurnAborted(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(aborted.reason, TurnAbortReason::BudgetExceeded);
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+    let ledger_path = cost_ledger_path(home.path(), chrono::Local::now().date_naive());
+    let ledger = std::fs::read_to_string(ledger_path).unwrap();
+    assert_eq!(ledger.lines().count(), 1);
+}
@@ -652,6 +652,7 @@ async fn azure_responses_request_includes_store_and_reasoning_ids() {
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
         requires_openai_auth: false,
+        supports_json_schema: false,
     };
 
     let codex_home = TempDir::new().unwrap();
@@ -850,7 +851,9 @@ async fn token_count_includes_rate_limits_snapshot() {
                     "total_tokens": 123
                 },
                 // Default model is gpt-5-codex in tests → 272000 context window
-                "model_context_window": 272000
+                "model_context_window": 272000,
+                // 123 input tokens at $1.25 per million
+                "total_cost_usd": 0.00015375
             },
             "rate_limits": {
                 "primary_used_percent": 12.5,
@@ -997,6 +1000,7 @@ async fn azure_overrides_assign_properties_used_for_responses_url() {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        supports_json_schema: false,
     };
 
     // Init session
@@ -1073,6 +1077,7 @@ async fn env_var_overrides_loaded_auth() {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        supports_json_schema: false,
     };
 
     // Init session
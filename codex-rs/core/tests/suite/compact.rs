@@ -3,6 +3,7 @@ use codex_core::ConversationManager;
 use codex_core::ModelProviderInfo;
 use codex_core::NewConversation;
 use codex_core::built_in_model_providers;
+use codex_core::config_types::Compaction;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
@@ -828,3 +829,237 @@ async fn auto_compact_allows_multiple_attempts_when_interleaved_with_other_turn_
         "second auto compact request should include the summarization prompt"
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn compaction_keeps_recent_turns_and_prunes_tool_outputs() {
+    non_sandbox_test!();
+
+    const FIRST_TASK: &str = "first task";
+    const SECOND_TASK: &str = "second task";
+    const SECOND_REPLY: &str = "SECOND_REPLY";
+
+    let server = start_mock_server().await;
+    let bodies = vec![
+        sse(vec![
+            ev_function_call(DUMMY_CALL_ID, DUMMY_FUNCTION_NAME, "{}"),
+            ev_completed("r1"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m2", FIRST_REPLY),
+            ev_completed("r2"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m3", SECOND_REPLY),
+            ev_completed("r3"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m4", SUMMARY_TEXT),
+            ev_completed("r4"),
+        ]),
+        sse(vec![ev_completed("r5")]),
+    ];
+    // Mocks that can only be used once are matched in the order they were
+    // mounted, so each request gets the next response.
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    config.compaction = Compaction {
+        keep_recent_turns: 1,
+        prune_tool_outputs: true,
+    };
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let NewConversation {
+        conversation: codex,
+        session_configured,
+        ..
+    } = conversation_manager.new_conversation(config).await.unwrap();
+
+    for text in [FIRST_TASK, SECOND_TASK] {
+        codex
+            .submit(Op::UserInput {
+                items: vec![InputItem::Text { text: text.into() }],
+            })
+            .await
+            .unwrap();
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    }
+    codex.submit(Op::Compact).await.unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: THIRD_USER_MSG.into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 5, "expected five requests");
+    let input_texts = |idx: usize| -> Vec<String> {
+        let body = requests[idx].body_json::<serde_json::Value>().unwrap();
+        body["input"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| match item["type"].as_str() {
+                Some("message") => item["content"][0]["text"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                Some("function_call_output") => {
+                    item["output"].as_str().unwrap_or_default().to_string()
+                }
+                _ => String::new(),
+            })
+            .collect()
+    };
+
+    // Only the first turn is summarized, and its tool output is pruned.
+    let summarize_input = input_texts(3);
+    assert_eq!(
+        summarize_input.last().map(String::as_str),
+        Some(SUMMARIZATION_PROMPT)
+    );
+    assert!(summarize_input.iter().any(|text| text == FIRST_TASK));
+    assert!(summarize_input.iter().any(|text| text == "[output pruned]"));
+    assert!(
+        !summarize_input
+            .iter()
+            .any(|text| text.contains("unsupported call") || text == SECOND_TASK),
+        "unexpected summarization input: {summarize_input:?}"
+    );
+
+    // The next turn sees the bridge for the first turn, then the second turn
+    // verbatim.
+    let after_compact = input_texts(4);
+    let bridge = after_compact
+        .iter()
+        .position(|text| text.contains(SUMMARY_TEXT))
+        .expect("bridge message");
+    assert!(after_compact[bridge].contains(FIRST_TASK));
+    assert!(!after_compact[bridge].contains(SECOND_TASK));
+    assert_eq!(
+        after_compact[bridge + 1..].to_vec(),
+        vec![
+            SECOND_TASK.to_string(),
+            SECOND_REPLY.to_string(),
+            THIRD_USER_MSG.to_string(),
+        ]
+    );
+    assert!(!after_compact.iter().any(|text| text == FIRST_REPLY));
+
+    codex.submit(Op::Shutdown).await.unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+    let text = std::fs::read_to_string(&session_configured.rollout_path).unwrap();
+    let retained_turns = text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RolloutLine>(line).ok())
+        .find_map(|line| match line.item {
+            RolloutItem::Compacted(item) => Some(item.retained_turns),
+            _ => None,
+        });
+    assert_eq!(retained_turns, Some(Some(1)));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn compacts_before_a_request_that_would_overflow_the_context_window() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    // Like many Chat Completions providers, report no usage for the turns.
+    let bodies = vec![
+        sse(vec![
+            ev_assistant_message("m1", FIRST_REPLY),
+            serde_json::json!({"type": "response.completed", "response": {"id": "r1"}}),
+        ]),
+        sse(vec![
+            ev_assistant_message("m2", SUMMARY_TEXT),
+            ev_completed("r2"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m3", SECOND_AUTO_MSG),
+            serde_json::json!({"type": "response.completed", "response": {"id": "r3"}}),
+        ]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    // The first turn stays below the limit; the second message pushes the
+    // next request over it.
+    config.model_auto_compact_token_limit = Some(3_000);
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: FIRST_AUTO_MSG.into(),
+            }],
+        })
+        .await
+        .unwrap();
+    let EventMsg::TokenCount(token_count) = wait_for_event(
+        &codex,
+        |ev| matches!(ev, EventMsg::TokenCount(token_count) if token_count.info.is_some()),
+    )
+    .await
+    else {
+        unreachable!();
+    };
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: format!("{SECOND_AUTO_MSG} {}", "lorem ".repeat(1_000)),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3, "expected a turn, a summary, then a turn");
+    let summary = std::str::from_utf8(&requests[1].body).unwrap();
+    let second = std::str::from_utf8(&requests[2].body).unwrap();
+    assert!(summary.contains("You have exceeded the maximum number of tokens"));
+    assert!(!second.contains("You have exceeded the maximum number of tokens"));
+    assert!(second.contains(SUMMARY_TEXT));
+
+    // The usage of the first response was estimated locally.
+    let usage = token_count.info.unwrap().last_token_usage;
+    assert!(usage.input_tokens > 1_000, "unexpected usage: {usage:?}");
+    assert!(usage.output_tokens > 0, "unexpected usage: {usage:?}");
+}
@@ -17,6 +17,7 @@ use codex_core::NewConversation;
 use codex_core::built_in_model_providers;
 use codex_core::codex::compact::SUMMARIZATION_PROMPT;
 use codex_core::config::Config;
+use codex_core::config_types::Compaction;
 use codex_core::protocol::ConversationPathResponseEvent;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
@@ -27,13 +28,17 @@ use core_test_support::responses::ev_assistant_message;
 use core_test_support::responses::ev_completed;
 use core_test_support::responses::mount_sse_once;
 use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
 use core_test_support::wait_for_event;
 use pretty_assertions::assert_eq;
 use serde_json::Value;
 use serde_json::json;
 use std::sync::Arc;
 use tempfile::TempDir;
+use wiremock::Mock;
 use wiremock::MockServer;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
 
 const AFTER_SECOND_RESUME: &str = "AFTER_SECOND_RESUME";
 
@@ -642,6 +647,131 @@ async fn compact_resume_after_second_compaction_preserves_history() {
     assert_eq!(expected, last_request_after_2_compacts);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+/// Scenario: a compaction that keeps the two most recent turns verbatim is
+/// resumed; the resumed history must match the live one, with a single
+/// bridge message ahead of the retained turns.
+async fn compact_resume_with_retained_turns_preserves_history() {
+    if network_disabled() {
+        println!("Skipping test because network is disabled in this sandbox");
+        return;
+    }
+
+    let server = MockServer::start().await;
+    let bodies = vec![
+        sse(vec![
+            ev_assistant_message("m1", "REPLY_1"),
+            ev_completed("r1"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m2", "REPLY_2"),
+            ev_completed("r2"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m3", "REPLY_3"),
+            ev_completed("r3"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m4", SUMMARY_TEXT),
+            ev_completed("r4"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m5", "AFTER_COMPACT_REPLY"),
+            ev_completed("r5"),
+        ]),
+        sse(vec![ev_completed("r6")]),
+    ];
+    // Mocks that can only be used once are matched in the order they were
+    // mounted, so each request gets the next response.
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let home = TempDir::new().expect("create temp dir");
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    config.compaction = Compaction {
+        keep_recent_turns: 2,
+        prune_tool_outputs: false,
+    };
+    let manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let NewConversation {
+        conversation: base, ..
+    } = manager
+        .new_conversation(config.clone())
+        .await
+        .expect("create conversation");
+
+    for text in ["TURN_1", "TURN_2", "TURN_3"] {
+        user_turn(&base, text).await;
+    }
+    compact_conversation(&base).await;
+    user_turn(&base, "AFTER_COMPACT").await;
+    let base_path = fetch_conversation_path(&base, "base conversation").await;
+
+    let resumed = resume_conversation(&manager, &config, base_path).await;
+    user_turn(&resumed, "AFTER_RESUME").await;
+
+    let requests = gather_request_bodies(&server).await;
+    assert_eq!(requests.len(), 6, "expected six requests");
+    let input_texts = |request: &Value| -> Vec<String> {
+        request["input"]
+            .as_array()
+            .expect("input should be an array")
+            .iter()
+            .map(|item| {
+                item["content"][0]["text"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect()
+    };
+    let after_compact = input_texts(&requests[4]);
+    let after_resume = input_texts(&requests[5]);
+
+    // The live history: the bridge for the first turn, then the last two
+    // turns verbatim.
+    let bridge = after_compact
+        .iter()
+        .position(|text| text.contains(SUMMARY_TEXT))
+        .expect("bridge message");
+    assert!(after_compact[bridge].contains("TURN_1"));
+    assert_eq!(
+        after_compact[bridge + 1..].to_vec(),
+        vec!["TURN_2", "REPLY_2", "TURN_3", "REPLY_3", "AFTER_COMPACT"]
+    );
+
+    // The resumed history continues it with nothing summarized twice.
+    assert_eq!(
+        after_resume,
+        [
+            after_compact,
+            vec![
+                "AFTER_COMPACT_REPLY".to_string(),
+                "AFTER_RESUME".to_string()
+            ],
+        ]
+        .concat()
+    );
+    assert_eq!(
+        after_resume
+            .iter()
+            .filter(|text| text.contains(SUMMARY_TEXT))
+            .count(),
+        1
+    );
+}
+
 fn normalize_line_endings(value: &mut Value) {
     match value {
         Value::String(text) => {
@@ -830,7 +960,7 @@ async fn fork_conversation(
     nth_user_message: usize,
 ) -> Arc<CodexConversation> {
     let NewConversation { conversation, .. } = manager
-        .fork_conversation(nth_user_message, config.clone(), path)
+        .fork_conversation(nth_user_message, None, config.clone(), path)
         .await
         .expect("fork conversation");
     conversation
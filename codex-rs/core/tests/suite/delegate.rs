@@ -0,0 +1,252 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! Verifies that the `delegate` tool runs a child conversation and returns
+//! its final message to the parent.
+
+use codex_core::audit::AuditEvent;
+use codex_core::audit::AuditRecord;
+use codex_core::audit::audit_log_path;
+use codex_core::protocol::ApprovalSource;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_completed_with_tokens;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use wiremock::Mock;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+const CHILD_REPLY: &str = "the test fails because the fixture is stale";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn delegate_returns_the_child_final_message() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    let arguments = serde_json::json!({
+        "task": "investigate the failing test",
+        "sandbox": "read-only",
+    })
+    .to_string();
+    let bodies = vec![
+        sse(vec![
+            ev_function_call("call-1", "delegate", &arguments),
+            ev_completed("r1"),
+        ]),
+        // The child conversation shares the parent's provider.
+        sse(vec![
+            ev_assistant_message("m2", CHILD_REPLY),
+            ev_completed("r2"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m3", "fixed the fixture"),
+            ev_completed("r3"),
+        ]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let test = test_codex()
+        .with_config(|config| config.include_delegate_tool = true)
+        .build(&server)
+        .await
+        .unwrap();
+    test.codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "fix the failing test".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let EventMsg::DelegateBegin(begin) =
+        wait_for_event(&test.codex, |ev| matches!(ev, EventMsg::DelegateBegin(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(begin.call_id, "call-1");
+    assert_eq!(begin.task, "investigate the failing test");
+    let EventMsg::DelegateEnd(end) =
+        wait_for_event(&test.codex, |ev| matches!(ev, EventMsg::DelegateEnd(_))).await
+    else {
+        unreachable!();
+    };
+    assert!(end.success);
+    assert_eq!(end.last_agent_message.as_deref(), Some(CHILD_REPLY));
+    wait_for_event(&test.codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    let tool_names = |body: &Value| -> Vec<String> {
+        body["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|tool| tool["name"].as_str().map(str::to_string))
+            .collect()
+    };
+
+    let parent = requests[0].body_json::<Value>().unwrap();
+    assert!(tool_names(&parent).contains(&"delegate".to_string()));
+
+    // The child gets the task, the requested sandbox and no way to delegate.
+    let child = requests[1].body_json::<Value>().unwrap();
+    assert!(!tool_names(&child).contains(&"delegate".to_string()));
+    let child_text = child["input"].to_string();
+    assert!(child_text.contains("investigate the failing test"));
+    assert!(child_text.contains("<sandbox_mode>read-only</sandbox_mode>"));
+
+    let last = requests[2].body_json::<Value>().unwrap();
+    let output = last["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["type"] == "function_call_output")
+        .expect("delegate output");
+    assert_eq!(output["call_id"], "call-1");
+    assert_eq!(output["output"], CHILD_REPLY);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn delegate_asks_the_parent_for_approval_and_adds_its_usage() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    let delegate_arguments = serde_json::json!({ "task": "create the marker file" }).to_string();
+    let shell_arguments = serde_json::json!({ "command": ["touch", "delegated.txt"] }).to_string();
+    let bodies = vec![
+        sse(vec![
+            ev_function_call("call-1", "delegate", &delegate_arguments),
+            ev_completed_with_tokens("r1", 100),
+        ]),
+        sse(vec![
+            ev_function_call("call-2", "shell", &shell_arguments),
+            ev_completed_with_tokens("r2", 1_000),
+        ]),
+        sse(vec![
+            ev_assistant_message("m3", CHILD_REPLY),
+            ev_completed_with_tokens("r3", 2_000),
+        ]),
+        sse(vec![
+            ev_assistant_message("m4", "done"),
+            ev_completed_with_tokens("r4", 10),
+        ]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let test = test_codex()
+        .with_config(|config| {
+            config.include_delegate_tool = true;
+            config.approval_policy = AskForApproval::UnlessTrusted;
+        })
+        .build(&server)
+        .await
+        .unwrap();
+    test.codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "delegate the marker file".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    // The child's approval request reaches the parent's client directly.
+    let mut approval_requests = Vec::new();
+    let mut total_tokens = None;
+    loop {
+        let event =
+            tokio::time::timeout(std::time::Duration::from_secs(10), test.codex.next_event())
+                .await
+                .expect("timeout waiting for event")
+                .expect("stream ended unexpectedly");
+        match event.msg {
+            EventMsg::ExecApprovalRequest(request) => {
+                approval_requests.push((request.call_id, request.command));
+                test.codex
+                    .submit(Op::ExecApproval {
+                        id: event.id,
+                        decision: ReviewDecision::Denied,
+                        source: ApprovalSource::Tui,
+                    })
+                    .await
+                    .unwrap();
+            }
+            EventMsg::TokenCount(token_count) => {
+                total_tokens = token_count
+                    .info
+                    .map(|info| info.total_token_usage.total_tokens);
+            }
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+
+    assert_eq!(
+        approval_requests,
+        vec![(
+            "call-2".to_string(),
+            vec!["touch".to_string(), "delegated.txt".to_string()]
+        )]
+    );
+    // The child's usage is added to the parent's.
+    assert_eq!(total_tokens, Some(100 + 1_000 + 2_000 + 10));
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 4);
+    let child = requests[2].body_json::<Value>().unwrap();
+    let output = child["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["type"] == "function_call_output")
+        .expect("shell output");
+    assert_eq!(output["call_id"], "call-2");
+    assert_eq!(output["output"], "exec command rejected by user");
+
+    // The child records the decision with the source the parent's client gave.
+    let log = std::fs::read_to_string(audit_log_path(test.home.path())).unwrap();
+    let approvals: Vec<(String, ApprovalSource)> = log
+        .lines()
+        .map(|line| serde_json::from_str::<AuditRecord>(line).unwrap())
+        .filter_map(|record| match record.event {
+            AuditEvent::Approval {
+                call_id, source, ..
+            } => Some((call_id, source)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        approvals,
+        vec![
+            ("call-2".to_string(), ApprovalSource::Tui),
+            ("call-2".to_string(), ApprovalSource::Tui),
+        ]
+    );
+}
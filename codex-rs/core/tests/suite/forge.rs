@@ -0,0 +1,496 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! Verifies the GitHub and GitLab forges against a mock REST API, and that
+//! `Op::OpenPullRequest` and `Op::AddressReviewComments` push the branch,
+//! open a pull request and feed its review comments back to the agent.
+
+use std::path::Path;
+use std::process::Command;
+
+use codex_core::forge::Forge;
+use codex_core::forge::ForgeError;
+use codex_core::forge::GitHub;
+use codex_core::forge::GitLab;
+use codex_core::forge::NewPullRequest;
+use codex_core::forge::PullRequest;
+use codex_core::forge::ReviewComment;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::Op;
+use codex_core::protocol::PullRequestOpenedEvent;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::body_partial_json;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::matchers::query_param;
+
+/// Never set, so requests go out without credentials.
+const TOKEN_ENV_VAR: &str = "CODEX_TEST_FORGE_TOKEN";
+
+fn git(cwd: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?} failed");
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+fn new_pull_request() -> NewPullRequest {
+    NewPullRequest {
+        title: "Add widget".to_string(),
+        body: "Adds a widget.".to_string(),
+        head: "feature".to_string(),
+        base: "main".to_string(),
+        draft: true,
+    }
+}
+
+/// A repository in `cwd` whose `feature` branch is one commit ahead of
+/// `main`, with an `origin` on GitHub that pushes to a local bare
+/// repository instead.
+fn init_feature_branch(cwd: &Path, bare: &Path) {
+    git(bare, &["init", "-q", "--bare"]);
+    git(cwd, &["init", "-q", "-b", "main"]);
+    git(cwd, &["config", "user.name", "Test"]);
+    git(cwd, &["config", "user.email", "test@example.com"]);
+    std::fs::write(cwd.join("README.md"), "readme\n").unwrap();
+    git(cwd, &["add", "README.md"]);
+    git(cwd, &["commit", "-q", "-m", "init"]);
+    git(cwd, &["checkout", "-q", "-b", "feature"]);
+    std::fs::write(cwd.join("widget.txt"), "widget\n").unwrap();
+    git(cwd, &["add", "widget.txt"]);
+    git(cwd, &["commit", "-q", "-m", "Add widget"]);
+    git(
+        cwd,
+        &[
+            "remote",
+            "add",
+            "origin",
+            "https://github.com/acme/widgets.git",
+        ],
+    );
+    git(
+        cwd,
+        &["config", "remote.origin.pushurl", bare.to_str().unwrap()],
+    );
+}
+
+async fn mount_model_reply(server: &MockServer, text: &str) {
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", text),
+            ev_completed("r1"),
+        ])))
+        .up_to_n_times(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn github_opens_pull_request_and_follows_comment_pages() {
+    non_sandbox_test!();
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/repos/acme/widgets/pulls"))
+        .and(body_partial_json(json!({
+            "title": "Add widget",
+            "head": "feature",
+            "base": "main",
+            "draft": true,
+        })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "number": 7,
+            "html_url": "https://github.com/acme/widgets/pull/7",
+            "title": "Add widget",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/repos/acme/widgets/pulls"))
+        .and(query_param("head", "acme:feature"))
+        .and(query_param("state", "open"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "number": 7,
+            "html_url": "https://github.com/acme/widgets/pull/7",
+            "title": "Add widget",
+        }])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/repos/acme/widgets/pulls/7/comments"))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "user": {"login": "carol"},
+            "body": "And here.",
+            "path": "src/widget.rs",
+            "line": 20,
+            "created_at": "2025-01-03T00:00:00Z",
+        }])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/repos/acme/widgets/pulls/7/comments"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header(
+                    "link",
+                    format!(
+                        "<{}/repos/acme/widgets/pulls/7/comments?per_page=100&page=2>; rel=\"next\"",
+                        server.uri()
+                    )
+                    .as_str(),
+                )
+                .set_body_json(json!([{
+                    "user": {"login": "alice"},
+                    "body": "Rename this.",
+                    "path": "src/widget.rs",
+                    "line": null,
+                    "original_line": 12,
+                    "created_at": "2025-01-02T00:00:00Z",
+                }])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/repos/acme/widgets/issues/7/comments"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "user": {"login": "bob"},
+            "body": "Needs a test.",
+            "created_at": "2025-01-01T00:00:00Z",
+        }])))
+        .mount(&server)
+        .await;
+
+    let forge = GitHub::new(
+        server.uri(),
+        "acme/widgets".to_string(),
+        TOKEN_ENV_VAR.to_string(),
+    );
+    let pull_request = forge.open_pull_request(&new_pull_request()).await.unwrap();
+    let expected = PullRequest {
+        number: 7,
+        url: "https://github.com/acme/widgets/pull/7".to_string(),
+        title: "Add widget".to_string(),
+    };
+    assert_eq!(pull_request, expected);
+    assert_eq!(
+        forge.find_pull_request("feature").await.unwrap(),
+        Some(expected)
+    );
+    assert_eq!(
+        forge.review_comments(7).await.unwrap(),
+        vec![
+            ReviewComment {
+                author: "bob".to_string(),
+                body: "Needs a test.".to_string(),
+                path: None,
+                line: None,
+            },
+            ReviewComment {
+                author: "alice".to_string(),
+                body: "Rename this.".to_string(),
+                path: Some("src/widget.rs".to_string()),
+                line: Some(12),
+            },
+            ReviewComment {
+                author: "carol".to_string(),
+                body: "And here.".to_string(),
+                path: Some("src/widget.rs".to_string()),
+                line: Some(20),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn gitlab_opens_merge_request_and_reads_every_page_of_notes() {
+    non_sandbox_test!();
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/projects/acme%2Fwidgets/merge_requests"))
+        .and(body_partial_json(json!({
+            "title": "Draft: Add widget",
+            "description": "Adds a widget.",
+            "source_branch": "feature",
+            "target_branch": "main",
+        })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "iid": 3,
+            "web_url": "https://gitlab.com/acme/widgets/-/merge_requests/3",
+            "title": "Draft: Add widget",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/projects/acme%2Fwidgets/merge_requests/3/notes"))
+        .and(query_param("page", "1"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("x-next-page", "2")
+                .set_body_json(json!([{
+                    "author": {"username": "alice"},
+                    "body": "Rename this.",
+                    "system": false,
+                    "position": {"new_path": "src/widget.rs", "new_line": 4},
+                }])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/projects/acme%2Fwidgets/merge_requests/3/notes"))
+        .and(query_param("page", "2"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("x-next-page", "")
+                .set_body_json(json!([
+                    {
+                        "author": {"username": "alice"},
+                        "body": "added 1 commit",
+                        "system": true,
+                    },
+                    {
+                        "author": {"username": "bob"},
+                        "body": "Looks good otherwise.",
+                        "system": false,
+                    },
+                ])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let forge = GitLab::new(
+        server.uri(),
+        "acme/widgets".to_string(),
+        TOKEN_ENV_VAR.to_string(),
+    );
+    let merge_request = forge.open_pull_request(&new_pull_request()).await.unwrap();
+    assert_eq!(merge_request.number, 3);
+    assert_eq!(
+        forge.review_comments(3).await.unwrap(),
+        vec![
+            ReviewComment {
+                author: "alice".to_string(),
+                body: "Rename this.".to_string(),
+                path: Some("src/widget.rs".to_string()),
+                line: Some(4),
+            },
+            ReviewComment {
+                author: "bob".to_string(),
+                body: "Looks good otherwise.".to_string(),
+                path: None,
+                line: None,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unauthorized_response_names_the_token_variable() {
+    non_sandbox_test!();
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/repos/acme/widgets/pulls"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&server)
+        .await;
+
+    let forge = GitHub::new(
+        server.uri(),
+        "acme/widgets".to_string(),
+        TOKEN_ENV_VAR.to_string(),
+    );
+    let err = forge
+        .open_pull_request(&new_pull_request())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, ForgeError::Unauthorized { env_var } if env_var == TOKEN_ENV_VAR),
+        "{err:?}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn open_pull_request_pushes_branch_with_generated_description() {
+    non_sandbox_test!();
+    let server = start_mock_server().await;
+    mount_model_reply(&server, "# Add widget\n\nAdds a widget file.").await;
+    Mock::given(method("POST"))
+        .and(path("/repos/acme/widgets/pulls"))
+        .and(body_partial_json(json!({
+            "title": "Add widget",
+            "body": "Adds a widget file.",
+            "head": "feature",
+            "base": "main",
+            "draft": false,
+        })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "number": 7,
+            "html_url": "https://github.com/acme/widgets/pull/7",
+            "title": "Add widget",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let bare = TempDir::new().unwrap();
+    let bare_path = bare.path().to_path_buf();
+    let api_base_url = server.uri();
+    let test = test_codex()
+        .with_config(move |config| {
+            init_feature_branch(&config.cwd, &bare_path);
+            config.forge.api_base_url = Some(api_base_url);
+            config.forge.token_env_var = Some(TOKEN_ENV_VAR.to_string());
+        })
+        .build(&server)
+        .await
+        .unwrap();
+    let codex = &test.codex;
+
+    codex
+        .submit(Op::OpenPullRequest {
+            base: Some("main".to_string()),
+            draft: false,
+        })
+        .await
+        .unwrap();
+    let EventMsg::PullRequestOpened(event) = wait_for_event(codex, |ev| {
+        matches!(ev, EventMsg::PullRequestOpened(_) | EventMsg::Error(_))
+    })
+    .await
+    else {
+        panic!("expected the pull request to open");
+    };
+    assert_eq!(
+        event,
+        PullRequestOpenedEvent {
+            number: 7,
+            url: "https://github.com/acme/widgets/pull/7".to_string(),
+            title: "Add widget".to_string(),
+            branch: "feature".to_string(),
+        }
+    );
+
+    // The branch was pushed and the model described its commits and diff.
+    assert_eq!(
+        git(bare.path(), &["rev-parse", "refs/heads/feature"]),
+        git(test.cwd.path(), &["rev-parse", "HEAD"])
+    );
+    let requests = server.received_requests().await.unwrap();
+    let model_request = requests
+        .iter()
+        .find(|request| request.url.path() == "/v1/responses")
+        .unwrap();
+    let model_request = String::from_utf8_lossy(&model_request.body);
+    assert!(model_request.contains("- Add widget"), "{model_request}");
+    assert!(model_request.contains("+widget"), "{model_request}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn review_comments_start_a_turn() {
+    non_sandbox_test!();
+    let server = start_mock_server().await;
+    mount_model_reply(&server, "renamed it").await;
+    Mock::given(method("GET"))
+        .and(path("/repos/acme/widgets/pulls"))
+        .and(query_param("head", "acme:feature"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "number": 7,
+            "html_url": "https://github.com/acme/widgets/pull/7",
+            "title": "Add widget",
+        }])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/repos/acme/widgets/pulls/7/comments"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "user": {"login": "alice"},
+            "body": "Rename widget.txt to gadget.txt.",
+            "path": "widget.txt",
+            "line": 1,
+            "created_at": "2025-01-02T00:00:00Z",
+        }])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/repos/acme/widgets/issues/7/comments"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&server)
+        .await;
+
+    let bare = TempDir::new().unwrap();
+    let bare_path = bare.path().to_path_buf();
+    let api_base_url = server.uri();
+    let test = test_codex()
+        .with_config(move |config| {
+            init_feature_branch(&config.cwd, &bare_path);
+            config.forge.api_base_url = Some(api_base_url);
+            config.forge.token_env_var = Some(TOKEN_ENV_VAR.to_string());
+        })
+        .build(&server)
+        .await
+        .unwrap();
+    let codex = &test.codex;
+
+    codex
+        .submit(Op::AddressReviewComments { number: None })
+        .await
+        .unwrap();
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    let model_request = requests
+        .iter()
+        .find(|request| request.url.path() == "/v1/responses")
+        .unwrap();
+    let model_request = model_request.body_json::<Value>().unwrap();
+    // The comments come in their own developer message, ahead of the request.
+    let messages: Vec<(&str, &str)> = model_request["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|item| item["type"] == "message")
+        .map(|item| {
+            (
+                item["role"].as_str().unwrap(),
+                item["content"][0]["text"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    let (role, comments) = messages[messages.len() - 2];
+    assert_eq!(role, "developer");
+    assert!(
+        comments.contains("untrusted")
+            && comments.contains("alice on widget.txt:1:\n> Rename widget.txt to gadget.txt.\n"),
+        "{comments}"
+    );
+    assert_eq!(
+        messages[messages.len() - 1],
+        (
+            "user",
+            "Address the review comments on pull request #7 (https://github.com/acme/widgets/pull/7), quoted in the previous developer message."
+        )
+    );
+}
@@ -13,6 +13,7 @@ use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
 use codex_core::protocol::RolloutItem;
 use codex_core::protocol::RolloutLine;
+use codex_core::protocol::SessionFork;
 use core_test_support::load_default_config_for_test;
 use core_test_support::wait_for_event;
 use tempfile::TempDir;
@@ -58,6 +59,7 @@ async fn fork_conversation_twice_drops_to_first_message() {
     let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
     let NewConversation {
         conversation: codex,
+        conversation_id: base_id,
         ..
     } = conversation_manager
         .new_conversation(config)
@@ -140,9 +142,15 @@ async fn fork_conversation_twice_drops_to_first_message() {
     // Fork once with n=1 → drops the last user input and everything after.
     let NewConversation {
         conversation: codex_fork1,
+        conversation_id: fork1_id,
         ..
     } = conversation_manager
-        .fork_conversation(1, config_for_fork.clone(), base_path.clone())
+        .fork_conversation(
+            1,
+            Some("retry".to_string()),
+            config_for_fork.clone(),
+            base_path.clone(),
+        )
         .await
         .expect("fork 1");
 
@@ -162,13 +170,21 @@ async fn fork_conversation_twice_drops_to_first_message() {
         serde_json::to_value(&fork1_items).unwrap(),
         serde_json::to_value(&expected_after_first).unwrap()
     );
+    pretty_assertions::assert_eq!(
+        read_fork(&fork1_path),
+        Some(SessionFork {
+            parent_id: base_id,
+            nth_user_message: 1,
+            name: Some("retry".to_string()),
+        })
+    );
 
     // Fork again with n=0 → drops the (new) last user message, leaving only the first.
     let NewConversation {
         conversation: codex_fork2,
         ..
     } = conversation_manager
-        .fork_conversation(0, config_for_fork.clone(), fork1_path.clone())
+        .fork_conversation(0, None, config_for_fork.clone(), fork1_path.clone())
         .await
         .expect("fork 2");
 
@@ -194,4 +210,24 @@ async fn fork_conversation_twice_drops_to_first_message() {
         serde_json::to_value(&fork2_items).unwrap(),
         serde_json::to_value(&expected_after_second).unwrap()
     );
+    pretty_assertions::assert_eq!(
+        read_fork(&fork2_path),
+        Some(SessionFork {
+            parent_id: fork1_id,
+            nth_user_message: 0,
+            name: None,
+        })
+    );
+}
+
+/// Lineage recorded in the first `SessionMeta` line of a rollout.
+#[expect(clippy::expect_used)]
+fn read_fork(path: &std::path::Path) -> Option<SessionFork> {
+    let text = std::fs::read_to_string(path).expect("read rollout file");
+    let first = text.lines().next().expect("session meta line");
+    let line: RolloutLine = serde_json::from_str(first).expect("rollout line");
+    match line.item {
+        RolloutItem::SessionMeta(meta) => meta.fork,
+        _ => panic!("first rollout line must be SessionMeta"),
+    }
 }
@@ -0,0 +1,221 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! Verifies that configured hooks can veto and rewrite tool calls and
+//! prompts, and that their output reaches the model.
+
+use codex_core::config_types::HookCommand;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use wiremock::Mock;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn hook(script: &str) -> HookCommand {
+    HookCommand {
+        command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+        timeout_ms: None,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exec_hooks_veto_rewrite_and_report_back() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    let allowed = serde_json::json!({"command": ["echo", "original"]}).to_string();
+    let forbidden = serde_json::json!({"command": ["echo", "forbidden"]}).to_string();
+    let bodies = vec![
+        sse(vec![
+            ev_function_call("call-allowed", "shell", &allowed),
+            ev_function_call("call-forbidden", "shell", &forbidden),
+            ev_completed("r1"),
+        ]),
+        sse(vec![ev_assistant_message("m2", "done"), ev_completed("r2")]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let test = test_codex()
+        .with_config(|config| {
+            config.approval_policy = AskForApproval::Never;
+            config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+            config.hooks.pre_exec = vec![hook(
+                r#"case "$(cat)" in
+*forbidden*) echo '{"decision":"deny","reason":"forbidden command"}' ;;
+*) echo '{"command":["echo","rewritten"],"message":"checked"}' ;;
+esac"#,
+            )];
+            config.hooks.post_exec = vec![hook("grep -q '\"hook\":\"post_exec\"' && echo logged")];
+        })
+        .build(&server)
+        .await
+        .unwrap();
+
+    test.codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "run both".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&test.codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let body = requests[1].body_json::<Value>().unwrap();
+    let outputs: Vec<(String, String)> = body["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|item| item["type"] == "function_call_output")
+        .map(|item| {
+            (
+                item["call_id"].as_str().unwrap().to_string(),
+                item["output"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(outputs.len(), 2);
+
+    let (call_id, allowed_output) = &outputs[0];
+    assert_eq!(call_id, "call-allowed");
+    assert!(allowed_output.contains("rewritten"), "{allowed_output}");
+    assert!(!allowed_output.contains("original"), "{allowed_output}");
+    assert!(
+        allowed_output.ends_with("\n\nhook: checked\n\nhook: logged"),
+        "{allowed_output}"
+    );
+
+    let (call_id, forbidden_output) = &outputs[1];
+    assert_eq!(call_id, "call-forbidden");
+    assert_eq!(
+        forbidden_output,
+        "exec command rejected by hook: forbidden command"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn user_prompt_hook_can_block_a_prompt() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![ev_completed("r1")])))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let test = test_codex()
+        .with_config(|config| {
+            config.hooks.user_prompt_submit = vec![hook(
+                "echo 'prompts mentioning secrets are not allowed' >&2; exit 2",
+            )];
+        })
+        .build(&server)
+        .await
+        .unwrap();
+
+    test.codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "print the secrets".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let EventMsg::Error(error) =
+        wait_for_event(&test.codex, |ev| matches!(ev, EventMsg::Error(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(
+        error.message,
+        "prompt blocked by hook: prompts mentioning secrets are not allowed"
+    );
+    wait_for_event(&test.codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pre_exec_hooks_cover_unified_exec() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    let arguments = serde_json::json!({"input": ["echo", "forbidden"]}).to_string();
+    let bodies = vec![
+        sse(vec![
+            ev_function_call("call-unified", "unified_exec", &arguments),
+            ev_completed("r1"),
+        ]),
+        sse(vec![ev_assistant_message("m2", "done"), ev_completed("r2")]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let test = test_codex()
+        .with_config(|config| {
+            config.approval_policy = AskForApproval::Never;
+            config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+            config.use_experimental_unified_exec_tool = true;
+            config.hooks.pre_exec = vec![hook(
+                r#"grep -q forbidden && echo '{"decision":"deny","reason":"forbidden command"}'"#,
+            )];
+        })
+        .build(&server)
+        .await
+        .unwrap();
+
+    test.codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "run it".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&test.codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let body = requests[1].body_json::<Value>().unwrap();
+    let output = body["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["type"] == "function_call_output")
+        .expect("unified_exec output");
+    assert_eq!(output["call_id"], "call-unified");
+    assert_eq!(
+        output["output"],
+        "exec command rejected by hook: forbidden command"
+    );
+}
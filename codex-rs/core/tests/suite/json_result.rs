@@ -1,5 +1,6 @@
 #![cfg(not(target_os = "windows"))]
 
+use codex_core::WireApi;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
@@ -15,7 +16,14 @@ use pretty_assertions::assert_eq;
 use responses::ev_assistant_message;
 use responses::ev_completed;
 use responses::sse;
+use responses::sse_response;
 use responses::start_mock_server;
+use serde_json::Value;
+use serde_json::json;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
 
 const SCHEMA: &str = r#"
 {
@@ -95,3 +103,164 @@ async fn codex_returns_json_result() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// A Chat Completions stream whose final message is `text`.
+fn chat_sse(text: &str) -> String {
+    format!(
+        "data: {}\n\ndata: {}\n\ndata: [DONE]\n\n",
+        json!({"choices": [{"delta": {"content": text}}]}),
+        json!({"choices": [{"delta": {}, "finish_reason": "stop"}]}),
+    )
+}
+
+/// Runs a turn with `SCHEMA` against a Chat Completions provider that replies
+/// with `replies` in order, and returns the request bodies and the events.
+async fn run_chat_turn(
+    replies: &[&str],
+    supports_json_schema: bool,
+    max_retries: u32,
+) -> anyhow::Result<(Vec<Value>, Vec<EventMsg>)> {
+    let server = MockServer::start().await;
+    for reply in replies {
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(sse_response(chat_sse(reply)))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let TestCodex { codex, cwd, .. } = test_codex()
+        .with_config(move |config| {
+            config.model_provider.wire_api = WireApi::Chat;
+            config.model_provider.supports_json_schema = supports_json_schema;
+            config.output_schema_max_retries = max_retries;
+        })
+        .build(&server)
+        .await?;
+    codex
+        .submit(Op::UserTurn {
+            items: vec![InputItem::Text {
+                text: "hello world".into(),
+            }],
+            final_output_json_schema: Some(serde_json::from_str(SCHEMA)?),
+            cwd: cwd.path().to_path_buf(),
+            approval_policy: AskForApproval::Never,
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            model: "gpt-5".to_string(),
+            effort: None,
+            summary: ReasoningSummary::Auto,
+        })
+        .await?;
+
+    let mut events = Vec::new();
+    loop {
+        let event = wait_for_event(&codex, |_| true).await;
+        let done = matches!(event, EventMsg::TaskComplete(_));
+        events.push(event);
+        if done {
+            break;
+        }
+    }
+
+    let bodies = server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(wiremock::Request::body_json::<Value>)
+        .collect::<Result<_, _>>()?;
+    Ok((bodies, events))
+}
+
+const VALID_REPLY: &str = r#"{"explanation": "e", "final_answer": "a"}"#;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn chat_provider_with_json_schema_support_gets_response_format() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let (bodies, _) = run_chat_turn(&[VALID_REPLY], true, 2).await?;
+
+    assert_eq!(bodies.len(), 1);
+    assert_eq!(
+        bodies[0]["response_format"],
+        json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "codex_output_schema",
+                "strict": true,
+                "schema": serde_json::from_str::<Value>(SCHEMA)?,
+            },
+        })
+    );
+    let system = bodies[0]["messages"][0]["content"]
+        .as_str()
+        .unwrap_or_default();
+    assert!(!system.contains("JSON Schema"));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn chat_provider_output_is_validated_and_retried() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let (bodies, events) = run_chat_turn(&["not json", VALID_REPLY], false, 2).await?;
+
+    assert_eq!(bodies.len(), 2);
+    assert!(bodies[0].get("response_format").is_none());
+    let system = bodies[0]["messages"][0]["content"]
+        .as_str()
+        .unwrap_or_default();
+    assert!(system.contains("matches this JSON Schema"), "{system}");
+
+    let messages = bodies[1]["messages"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let retry = messages
+        .last()
+        .and_then(|message| message["content"].as_str())
+        .unwrap_or_default();
+    assert!(
+        retry.starts_with("Your final message does not match the required JSON Schema:\n- the final message is not valid JSON"),
+        "{retry}"
+    );
+
+    let Some(EventMsg::TaskComplete(complete)) = events.last() else {
+        anyhow::bail!("expected the task to complete");
+    };
+    assert_eq!(complete.last_agent_message.as_deref(), Some(VALID_REPLY));
+    assert!(
+        !events
+            .iter()
+            .any(|event| matches!(event, EventMsg::OutputSchemaMismatch(_)))
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn chat_provider_output_mismatch_is_reported_after_retries() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let (bodies, events) = run_chat_turn(
+        &[r#"{"explanation": "e"}"#, r#"{"explanation": "e"}"#],
+        false,
+        1,
+    )
+    .await?;
+
+    assert_eq!(bodies.len(), 2);
+    let mismatch = events.iter().find_map(|event| match event {
+        EventMsg::OutputSchemaMismatch(mismatch) => Some(mismatch),
+        _ => None,
+    });
+    let Some(mismatch) = mismatch else {
+        anyhow::bail!("expected an output schema mismatch");
+    };
+    assert_eq!(mismatch.attempts, 2);
+    assert_eq!(
+        mismatch.errors,
+        vec![r#""final_answer" is a required property"#.to_string()]
+    );
+    Ok(())
+}
@@ -1,19 +1,29 @@
 // Aggregates all former standalone integration tests as modules.
 
+mod audit;
+mod budget;
 mod cli_stream;
 mod client;
 mod compact;
 mod compact_resume_fork;
+mod delegate;
 mod exec;
 mod exec_stream_events;
+mod forge;
 mod fork_conversation;
+mod hooks;
 mod json_result;
 mod live_cli;
+mod model_fallback;
 mod model_overrides;
+mod native_wire_apis;
+mod parallel_tool_calls;
 mod prompt_caching;
+mod response_cache;
 mod review;
 mod rollout_list_find;
 mod seatbelt;
 mod stream_error_allows_next_turn;
 mod stream_no_completed;
+mod undo;
 mod user_notification;
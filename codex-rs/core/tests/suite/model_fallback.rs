@@ -0,0 +1,124 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! Verifies that a turn switches to the next configured model fallback when
+//! the primary provider keeps returning rate-limit errors.
+
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::config_types::ModelFallback;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::RolloutItem;
+use codex_core::protocol::RolloutLine;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use wiremock::Mock;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn rate_limited_turn_switches_to_fallback_model() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(ResponseTemplate::new(429))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/backup/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "hello from the fallback"),
+            ev_completed("r1"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let primary = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let backup = ModelProviderInfo {
+        base_url: Some(format!("{}/backup/v1", server.uri())),
+        ..primary.clone()
+    };
+
+    let TestCodex {
+        home: _home,
+        codex,
+        session_configured,
+        ..
+    } = test_codex()
+        .with_config(move |config| {
+            config.model_provider = primary;
+            config.model_fallbacks = vec![ModelFallback {
+                model: "gpt-4.1".to_string(),
+                model_provider_id: "backup".to_string(),
+                model_provider: backup,
+            }];
+        })
+        .build(&server)
+        .await
+        .unwrap();
+    let primary_model = session_configured.model.clone();
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let EventMsg::BackgroundEvent(event) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::BackgroundEvent(_))).await
+    else {
+        unreachable!();
+    };
+    assert!(
+        event.message.ends_with("switching to gpt-4.1 via backup"),
+        "unexpected background event: {}",
+        event.message
+    );
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    let models = requests
+        .iter()
+        .map(|request| {
+            let body = request.body_json::<serde_json::Value>().unwrap();
+            body["model"].as_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(models, vec![primary_model.clone(), "gpt-4.1".to_string()]);
+
+    // The rollout records the model that actually served each request.
+    codex.submit(Op::Shutdown).await.unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+    let text = std::fs::read_to_string(&session_configured.rollout_path).unwrap();
+    let turn_models = text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RolloutLine>(line).ok())
+        .filter_map(|line| match line.item {
+            RolloutItem::TurnContext(item) => Some(item.model),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(turn_models, vec![primary_model, "gpt-4.1".to_string()]);
+}
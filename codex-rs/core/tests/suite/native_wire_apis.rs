@@ -0,0 +1,182 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! End-to-end tests for the Anthropic Messages and Gemini wire APIs, replaying
+//! the recorded SSE fixtures in `tests/fixtures` from a mock server.
+
+use std::sync::Arc;
+
+use codex_core::ContentItem;
+use codex_core::ModelClient;
+use codex_core::ModelProviderInfo;
+use codex_core::Prompt;
+use codex_core::ResponseEvent;
+use codex_core::ResponseItem;
+use codex_core::WireApi;
+use codex_protocol::mcp_protocol::ConversationId;
+use core_test_support::load_default_config_for_test;
+use core_test_support::non_sandbox_test;
+use futures::StreamExt;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::matchers::query_param;
+
+fn recorded_fixture(name: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_to_string(path).expect("read fixture")
+}
+
+async fn stream_items(server: &MockServer, wire_api: WireApi, model: &str) -> Vec<ResponseItem> {
+    let provider = ModelProviderInfo {
+        name: "mock".into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        env_key: None,
+        env_key_instructions: None,
+        wire_api,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(5_000),
+        requires_openai_auth: false,
+        supports_json_schema: false,
+    };
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model = model.to_string();
+    config.model_family = codex_core::model_family::derive_default_model_family(model);
+    config.model_provider_id = provider.name.clone();
+    config.model_provider = provider.clone();
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+
+    let client = ModelClient::new(
+        Arc::new(config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+    );
+
+    let mut prompt = Prompt::default();
+    prompt.input = vec![ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: "list the files".to_string(),
+        }],
+    }];
+
+    let mut stream = client.stream(&prompt).await.unwrap();
+    let mut items = Vec::new();
+    let mut completed = false;
+    while let Some(event) = stream.next().await {
+        match event.unwrap() {
+            ResponseEvent::OutputItemDone(item) => items.push(item),
+            ResponseEvent::Completed { .. } => completed = true,
+            _ => {}
+        }
+    }
+    assert!(completed, "stream ended without Completed");
+    items
+}
+
+fn assert_listing_turn(items: &[ResponseItem]) {
+    let [
+        ResponseItem::Reasoning {
+            encrypted_content: Some(_),
+            ..
+        },
+        ResponseItem::Message { content, .. },
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        },
+    ] = items
+    else {
+        panic!("expected reasoning, message and function call, got {items:?}");
+    };
+    assert_eq!(
+        content,
+        &vec![ContentItem::OutputText {
+            text: "Let me list the files.".to_string()
+        }]
+    );
+    assert_eq!(name, "shell");
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(arguments).unwrap(),
+        serde_json::json!({"command": ["ls", "-la"]})
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn anthropic_messages_stream_maps_recorded_events() {
+    non_sandbox_test!();
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            recorded_fixture("anthropic_messages.sse"),
+            "text/event-stream",
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let items = stream_items(
+        &server,
+        WireApi::AnthropicMessages,
+        "claude-sonnet-4-20250514",
+    )
+    .await;
+    assert_listing_turn(&items);
+
+    let requests = server.received_requests().await.unwrap();
+    let body = requests[0].body_json::<serde_json::Value>().unwrap();
+    assert_eq!(body["model"], "claude-sonnet-4-20250514");
+    assert_eq!(body["stream"], true);
+    assert!(body["system"].is_string());
+    assert_eq!(
+        body["messages"][0]["content"][0],
+        serde_json::json!({"type": "text", "text": "list the files"})
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn gemini_stream_maps_recorded_events() {
+    non_sandbox_test!();
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/models/gemini-2.5-pro:streamGenerateContent"))
+        .and(query_param("alt", "sse"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(recorded_fixture("gemini.sse"), "text/event-stream"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let items = stream_items(&server, WireApi::Gemini, "gemini-2.5-pro").await;
+    assert_listing_turn(&items);
+
+    let requests = server.received_requests().await.unwrap();
+    let body = requests[0].body_json::<serde_json::Value>().unwrap();
+    assert!(body["systemInstruction"]["parts"][0]["text"].is_string());
+    assert_eq!(
+        body["contents"][0],
+        serde_json::json!({"role": "user", "parts": [{"text": "list the files"}]})
+    );
+}
@@ -0,0 +1,156 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! Verifies that safe tool calls run concurrently, that their outputs keep
+//! the order of the calls, and that requests advertise `parallel_tool_calls`
+//! only when it is enabled.
+
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use wiremock::Mock;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn shell_call(call_id: &str, file: &str) -> Value {
+    let arguments = serde_json::json!({"command": ["cat", file]}).to_string();
+    ev_function_call(call_id, "shell", &arguments)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn safe_calls_run_concurrently_in_order() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    let bodies = vec![
+        sse(vec![
+            shell_call("call-a", "a.txt"),
+            shell_call("call-b", "b.txt"),
+            ev_completed("r1"),
+        ]),
+        sse(vec![ev_assistant_message("m2", "done"), ev_completed("r2")]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let test = test_codex()
+        .with_config(|config| {
+            config.approval_policy = AskForApproval::Never;
+            config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+            config.parallel_tool_calls = Some(true);
+        })
+        .build(&server)
+        .await
+        .unwrap();
+    // Named pipes keep each `cat` running until both commands have started,
+    // so running the calls one after the other would never get past the first.
+    let pipes = [
+        (test.cwd.path().join("a.txt"), "alpha"),
+        (test.cwd.path().join("b.txt"), "beta"),
+    ];
+    for (pipe, _) in &pipes {
+        let status = std::process::Command::new("mkfifo")
+            .arg(pipe)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    test.codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "read both files".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let mut order = Vec::new();
+    loop {
+        let event = wait_for_event(&test.codex, |_| true).await;
+        match event {
+            EventMsg::ExecCommandBegin(begin) => {
+                order.push(format!("begin {}", begin.call_id));
+                if order.len() == 2 {
+                    for (pipe, contents) in pipes.clone() {
+                        tokio::task::spawn_blocking(move || std::fs::write(pipe, contents));
+                    }
+                }
+            }
+            EventMsg::ExecCommandEnd(end) => order.push(format!("end {}", end.call_id)),
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(&order[..2], &["begin call-a", "begin call-b"]);
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let first = requests[0].body_json::<Value>().unwrap();
+    assert_eq!(first["parallel_tool_calls"], true);
+
+    let second = requests[1].body_json::<Value>().unwrap();
+    let outputs: Vec<(String, String)> = second["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|item| item["type"] == "function_call_output")
+        .map(|item| {
+            (
+                item["call_id"].as_str().unwrap().to_string(),
+                item["output"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(outputs[0].0, "call-a");
+    assert!(outputs[0].1.contains("alpha"), "{outputs:?}");
+    assert_eq!(outputs[1].0, "call-b");
+    assert!(outputs[1].1.contains("beta"), "{outputs:?}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn parallel_tool_calls_are_off_by_default() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![ev_completed("r1")])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let test = test_codex().build(&server).await.unwrap();
+    test.codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&test.codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    let body = requests[0].body_json::<Value>().unwrap();
+    assert_eq!(body["parallel_tool_calls"], false);
+}
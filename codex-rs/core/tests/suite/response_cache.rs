@@ -0,0 +1,209 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! Verifies that responses recorded in one session replay in another without
+//! reaching the model.
+
+use std::path::PathBuf;
+
+use codex_core::config_types::ResponseCacheMode;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::response_cache::RESPONSE_CACHE_DIRNAME;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+async fn build_codex(
+    server: &MockServer,
+    codex_home: PathBuf,
+    mode: ResponseCacheMode,
+) -> TestCodex {
+    test_codex()
+        .with_config(move |config| {
+            config.codex_home = codex_home;
+            config.response_cache = Some(mode);
+            config.approval_policy = AskForApproval::Never;
+            config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+        })
+        .build(server)
+        .await
+        .unwrap()
+}
+
+async fn submit_text(codex: &codex_core::CodexConversation, text: &str) {
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: text.into() }],
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn recorded_response_replays_offline() {
+    non_sandbox_test!();
+
+    let codex_home = TempDir::new().unwrap();
+
+    let server = start_mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "recorded hello"),
+            ev_completed("r1"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recording = build_codex(
+        &server,
+        codex_home.path().to_path_buf(),
+        ResponseCacheMode::Auto,
+    )
+    .await;
+    submit_text(&recording.codex, "hello").await;
+    wait_for_event(&recording.codex, |ev| {
+        matches!(ev, EventMsg::TaskComplete(_))
+    })
+    .await;
+    let recorded = std::fs::read_dir(codex_home.path().join(RESPONSE_CACHE_DIRNAME))
+        .unwrap()
+        .count();
+    assert_eq!(recorded, 1);
+
+    // The replaying session runs in a different working directory against a
+    // server that answers nothing.
+    let offline = start_mock_server().await;
+    let replaying = build_codex(
+        &offline,
+        codex_home.path().to_path_buf(),
+        ResponseCacheMode::Replay,
+    )
+    .await;
+    submit_text(&replaying.codex, "hello").await;
+    let EventMsg::AgentMessage(message) = wait_for_event(&replaying.codex, |ev| {
+        matches!(ev, EventMsg::AgentMessage(_))
+    })
+    .await
+    else {
+        unreachable!();
+    };
+    assert_eq!(message.message, "recorded hello");
+    wait_for_event(&replaying.codex, |ev| {
+        matches!(ev, EventMsg::TaskComplete(_))
+    })
+    .await;
+
+    // A prompt that was never recorded fails instead of reaching the model.
+    submit_text(&replaying.codex, "something new").await;
+    let EventMsg::Error(error) =
+        wait_for_event(&replaying.codex, |ev| matches!(ev, EventMsg::Error(_))).await
+    else {
+        unreachable!();
+    };
+    assert!(
+        error.message.contains("no recorded response"),
+        "unexpected error: {}",
+        error.message
+    );
+    assert_eq!(offline.received_requests().await.unwrap().len(), 0);
+}
+
+async fn wait_for_message(codex: &codex_core::CodexConversation) -> String {
+    let EventMsg::AgentMessage(message) =
+        wait_for_event(codex, |ev| matches!(ev, EventMsg::AgentMessage(_))).await
+    else {
+        unreachable!();
+    };
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    message.message
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn multi_turn_session_with_shell_call_replays_offline() {
+    non_sandbox_test!();
+
+    let codex_home = TempDir::new().unwrap();
+
+    // The command takes as long as the `delay` file in the working directory
+    // says, so the recording and the replay report different durations for
+    // the same output.
+    let arguments = serde_json::json!({
+        "command": ["sh", "-c", "sleep $(cat delay); echo cached"],
+    })
+    .to_string();
+    let server = start_mock_server().await;
+    for body in [
+        sse(vec![
+            ev_function_call("call_1", "shell", &arguments),
+            ev_completed("r1"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m1", "ran it"),
+            ev_completed("r2"),
+        ]),
+        sse(vec![ev_assistant_message("m2", "bye"), ev_completed("r3")]),
+    ] {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let recording = build_codex(
+        &server,
+        codex_home.path().to_path_buf(),
+        ResponseCacheMode::Auto,
+    )
+    .await;
+    std::fs::write(recording.cwd.path().join("delay"), "0").unwrap();
+    submit_text(&recording.codex, "run it").await;
+    assert_eq!(wait_for_message(&recording.codex).await, "ran it");
+    submit_text(&recording.codex, "thanks").await;
+    assert_eq!(wait_for_message(&recording.codex).await, "bye");
+    let recorded = std::fs::read_dir(codex_home.path().join(RESPONSE_CACHE_DIRNAME))
+        .unwrap()
+        .count();
+    assert_eq!(recorded, 3);
+
+    let offline = start_mock_server().await;
+    let replaying = build_codex(
+        &offline,
+        codex_home.path().to_path_buf(),
+        ResponseCacheMode::Replay,
+    )
+    .await;
+    std::fs::write(replaying.cwd.path().join("delay"), "0.3").unwrap();
+    submit_text(&replaying.codex, "run it").await;
+    let EventMsg::ExecCommandEnd(end) = wait_for_event(&replaying.codex, |ev| {
+        matches!(ev, EventMsg::ExecCommandEnd(_))
+    })
+    .await
+    else {
+        unreachable!();
+    };
+    assert_eq!(end.stdout, "cached\n");
+    assert_eq!(wait_for_message(&replaying.codex).await, "ran it");
+    submit_text(&replaying.codex, "thanks").await;
+    assert_eq!(wait_for_message(&replaying.codex).await, "bye");
+    assert_eq!(offline.received_requests().await.unwrap().len(), 0);
+}
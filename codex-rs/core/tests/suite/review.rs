@@ -18,6 +18,7 @@ use codex_core::protocol::ReviewFinding;
 use codex_core::protocol::ReviewLineRange;
 use codex_core::protocol::ReviewOutputEvent;
 use codex_core::protocol::ReviewRequest;
+use codex_core::protocol::ReviewTarget;
 use codex_core::protocol::RolloutItem;
 use codex_core::protocol::RolloutLine;
 use core_test_support::load_default_config_for_test;
@@ -83,6 +84,7 @@ async fn review_op_emits_lifecycle_and_review_output() {
             review_request: ReviewRequest {
                 prompt: "Please review my changes".to_string(),
                 user_facing_hint: "my changes".to_string(),
+                target: None,
             },
         })
         .await
@@ -185,6 +187,7 @@ async fn review_op_with_plain_text_emits_review_fallback() {
             review_request: ReviewRequest {
                 prompt: "Plain text review".to_string(),
                 user_facing_hint: "plain text review".to_string(),
+                target: None,
             },
         })
         .await
@@ -254,6 +257,7 @@ async fn review_does_not_emit_agent_message_on_structured_output() {
             review_request: ReviewRequest {
                 prompt: "check structured".to_string(),
                 user_facing_hint: "check structured".to_string(),
+                target: None,
             },
         })
         .await
@@ -308,6 +312,7 @@ async fn review_uses_custom_review_model_from_config() {
             review_request: ReviewRequest {
                 prompt: "use custom model".to_string(),
                 user_facing_hint: "use custom model".to_string(),
+                target: None,
             },
         })
         .await
@@ -424,6 +429,7 @@ async fn review_input_isolated_from_parent_history() {
             review_request: ReviewRequest {
                 prompt: review_prompt.clone(),
                 user_facing_hint: review_prompt.clone(),
+                target: None,
             },
         })
         .await
@@ -537,6 +543,7 @@ async fn review_history_does_not_leak_into_parent_session() {
             review_request: ReviewRequest {
                 prompt: "Start a review".to_string(),
                 user_facing_hint: "Start a review".to_string(),
+                target: None,
             },
         })
         .await
@@ -598,6 +605,163 @@ async fn review_history_does_not_leak_into_parent_session() {
     server.verify().await;
 }
 
+/// Verify that a review with a target hands the reviewer the target's diff,
+/// computed by core, with line number anchors for the changed file.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn review_target_diff_is_sent_with_line_anchors() {
+    non_sandbox_test!();
+
+    let sse_raw = r#"[
+        {"type":"response.output_item.done", "item":{
+            "type":"message", "role":"assistant",
+            "content":[{"type":"output_text","text":"{\"findings\":[],\"overall_correctness\":\"good\",\"overall_explanation\":\"ok\",\"overall_confidence_score\":0.5}"}]
+        }},
+        {"type":"response.completed", "response": {"id": "__ID__"}}
+    ]"#;
+    let server = start_responses_server_with_sse(sse_raw, 1).await;
+    let codex_home = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    let repo_path = repo.path().to_path_buf();
+    git(&repo_path, &["init", "-q"]);
+    std::fs::write(repo_path.join("notes.txt"), "one\n").unwrap();
+    git(&repo_path, &["add", "notes.txt"]);
+    git(&repo_path, &["commit", "-q", "-m", "first"]);
+    std::fs::write(repo_path.join("notes.txt"), "one\ntwo\n").unwrap();
+    git(&repo_path, &["commit", "-q", "-am", "second"]);
+    let codex = new_conversation_for_server(&server, &codex_home, |config| {
+        config.cwd = repo_path;
+    })
+    .await;
+
+    codex
+        .submit(Op::Review {
+            review_request: ReviewRequest {
+                prompt: "Review the last commit".to_string(),
+                user_facing_hint: "HEAD~1..HEAD".to_string(),
+                target: Some(ReviewTarget::CommitRange {
+                    base: "HEAD~1".to_string(),
+                    head: "HEAD".to_string(),
+                }),
+            },
+        })
+        .await
+        .unwrap();
+    let _complete = wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    let body = requests[0].body_json::<serde_json::Value>().unwrap();
+    let text = body["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|msg| msg["content"][0]["text"].as_str())
+        .find(|text| text.contains("Review the last commit"))
+        .expect("review prompt in request");
+    assert!(text.contains("+++ b/notes.txt"), "{text}");
+    assert!(text.contains("     1  one\n     2 +two"), "{text}");
+
+    server.verify().await;
+}
+
+/// Verify that a review target without changes is rejected before the
+/// reviewer is started.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn review_target_without_changes_reports_error() {
+    non_sandbox_test!();
+
+    let server = start_responses_server_with_sse("[]", 0).await;
+    let codex_home = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    let repo_path = repo.path().to_path_buf();
+    git(&repo_path, &["init", "-q"]);
+    let codex = new_conversation_for_server(&server, &codex_home, |config| {
+        config.cwd = repo_path;
+    })
+    .await;
+
+    codex
+        .submit(Op::Review {
+            review_request: ReviewRequest {
+                prompt: "Review the staged changes".to_string(),
+                user_facing_hint: "staged changes".to_string(),
+                target: Some(ReviewTarget::Staged),
+            },
+        })
+        .await
+        .unwrap();
+    let EventMsg::Error(error) = wait_for_event(&codex, |ev| {
+        matches!(ev, EventMsg::Error(_) | EventMsg::EnteredReviewMode(_))
+    })
+    .await
+    else {
+        panic!("expected an error before entering review mode");
+    };
+    assert_eq!(
+        error.message,
+        "There are no changes to review in staged changes."
+    );
+
+    server.verify().await;
+}
+
+/// Verify that a revision that git would take for an option is rejected
+/// instead of being passed on.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn review_target_rejects_option_like_revisions() {
+    non_sandbox_test!();
+
+    let server = start_responses_server_with_sse("[]", 0).await;
+    let codex_home = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    let repo_path = repo.path().to_path_buf();
+    git(&repo_path, &["init", "-q"]);
+    std::fs::write(repo_path.join("notes.txt"), "one\n").unwrap();
+    git(&repo_path, &["add", "notes.txt"]);
+    git(&repo_path, &["commit", "-q", "-m", "first"]);
+    let output = repo_path.join("leaked.txt");
+    let codex = new_conversation_for_server(&server, &codex_home, |config| {
+        config.cwd = repo_path.clone();
+    })
+    .await;
+
+    let sha = format!("--output={}", output.display());
+    codex
+        .submit(Op::Review {
+            review_request: ReviewRequest {
+                prompt: "Review the commit".to_string(),
+                user_facing_hint: "commit".to_string(),
+                target: Some(ReviewTarget::Commit { sha: sha.clone() }),
+            },
+        })
+        .await
+        .unwrap();
+    let EventMsg::Error(error) = wait_for_event(&codex, |ev| {
+        matches!(ev, EventMsg::Error(_) | EventMsg::EnteredReviewMode(_))
+    })
+    .await
+    else {
+        panic!("expected an error before entering review mode");
+    };
+    assert_eq!(
+        error.message,
+        format!("Failed to compute the changes to review: `{sha}` is not a commit")
+    );
+    assert!(!output.exists());
+
+    server.verify().await;
+}
+
+#[expect(clippy::expect_used)]
+fn git(cwd: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(cwd)
+        .status()
+        .expect("run git");
+    assert!(status.success(), "git {args:?} failed");
+}
+
 /// Start a mock Responses API server and mount the given SSE stream body.
 async fn start_responses_server_with_sse(sse_raw: &str, expected_requests: usize) -> MockServer {
     let server = MockServer::start().await;
@@ -0,0 +1,189 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! Verifies that checkpoints taken before each turn let `Op::Undo` and
+//! `Op::RestoreCheckpoint` roll back the workspace, with and without git.
+
+use std::path::Path;
+use std::process::Command;
+
+use codex_core::CodexConversation;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::CheckpointRestoredEvent;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// A `shell` call that writes `text` to `notes.txt`.
+fn write_notes(call_id: &str, text: &str) -> serde_json::Value {
+    let script = format!("printf '{text}\\n' > notes.txt");
+    let arguments = serde_json::json!({"command": ["sh", "-c", script]}).to_string();
+    ev_function_call(call_id, "shell", &arguments)
+}
+
+/// Two turns: the first creates `notes.txt`, the second edits it.
+async fn mount_two_write_turns(server: &MockServer) {
+    let bodies = vec![
+        sse(vec![write_notes("call-add", "first"), ev_completed("r1")]),
+        sse(vec![
+            ev_assistant_message("m1", "added"),
+            ev_completed("r2"),
+        ]),
+        sse(vec![write_notes("call-edit", "second"), ev_completed("r3")]),
+        sse(vec![
+            ev_assistant_message("m2", "edited"),
+            ev_completed("r4"),
+        ]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(server)
+            .await;
+    }
+}
+
+async fn build_codex(server: &MockServer, init_git: bool) -> TestCodex {
+    test_codex()
+        .with_config(move |config| {
+            config.approval_policy = AskForApproval::Never;
+            config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+            config.checkpoints.enabled = true;
+            if init_git {
+                git(&config.cwd, &["init", "-q"]);
+                std::fs::write(config.cwd.join("README.md"), "readme\n").unwrap();
+                git(&config.cwd, &["add", "README.md"]);
+                git(
+                    &config.cwd,
+                    &[
+                        "-c",
+                        "user.name=Test",
+                        "-c",
+                        "user.email=test@example.com",
+                        "commit",
+                        "-q",
+                        "-m",
+                        "init",
+                    ],
+                );
+            }
+        })
+        .build(server)
+        .await
+        .unwrap()
+}
+
+fn git(cwd: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+async fn run_turn(codex: &CodexConversation, text: &str) {
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: text.into() }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+}
+
+async fn submit_and_wait_for_restore(codex: &CodexConversation, op: Op) -> EventMsg {
+    codex.submit(op).await.unwrap();
+    wait_for_event(codex, |ev| {
+        matches!(ev, EventMsg::CheckpointRestored(_) | EventMsg::Error(_))
+    })
+    .await
+}
+
+async fn assert_undo_rolls_back_turns(init_git: bool) {
+    let server = start_mock_server().await;
+    mount_two_write_turns(&server).await;
+    let test = build_codex(&server, init_git).await;
+    let notes = test.cwd.path().join("notes.txt");
+
+    run_turn(&test.codex, "add notes").await;
+    run_turn(&test.codex, "edit notes").await;
+    assert_eq!(std::fs::read_to_string(&notes).unwrap(), "second\n");
+
+    let EventMsg::CheckpointRestored(CheckpointRestoredEvent { turns_undone, .. }) =
+        submit_and_wait_for_restore(&test.codex, Op::Undo { turns: 1 }).await
+    else {
+        panic!("expected CheckpointRestored");
+    };
+    assert_eq!(turns_undone, 1);
+    assert_eq!(std::fs::read_to_string(&notes).unwrap(), "first\n");
+
+    let EventMsg::CheckpointRestored(CheckpointRestoredEvent { turns_undone, .. }) =
+        submit_and_wait_for_restore(&test.codex, Op::Undo { turns: 1 }).await
+    else {
+        panic!("expected CheckpointRestored");
+    };
+    assert_eq!(turns_undone, 1);
+    assert!(!notes.exists());
+
+    let EventMsg::Error(error) =
+        submit_and_wait_for_restore(&test.codex, Op::Undo { turns: 1 }).await
+    else {
+        panic!("expected an error once every checkpoint is used");
+    };
+    assert!(
+        error.message.contains("only 0 checkpoint(s) available"),
+        "{}",
+        error.message
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn undo_restores_snapshot_outside_git() {
+    non_sandbox_test!();
+    assert_undo_rolls_back_turns(false).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn undo_restores_ghost_commit_in_git_repo() {
+    non_sandbox_test!();
+    assert_undo_rolls_back_turns(true).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn restore_checkpoint_rejects_unknown_id() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+    let test = build_codex(&server, false).await;
+
+    let EventMsg::Error(error) = submit_and_wait_for_restore(
+        &test.codex,
+        Op::RestoreCheckpoint {
+            id: "does-not-exist".to_string(),
+        },
+    )
+    .await
+    else {
+        panic!("expected an error for an unknown checkpoint");
+    };
+    assert_eq!(error.message, "Unknown checkpoint does-not-exist.");
+}
@@ -48,6 +48,11 @@ pub struct Cli {
     #[clap(long = "cd", short = 'C', value_name = "DIR")]
     pub cwd: Option<PathBuf>,
 
+    /// Run the session in a new git worktree on its own branch, then merge,
+    /// keep, or discard it (see `worktree.on_exit`).
+    #[arg(long = "worktree", default_value_t = false)]
+    pub worktree: bool,
+
     /// Allow running Codex outside a Git repository.
     #[arg(long = "skip-git-repo-check", default_value_t = false)]
     pub skip_git_repo_check: bool,
@@ -8,6 +8,9 @@ use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::BudgetKind;
+use codex_core::protocol::DelegateBeginEvent;
+use codex_core::protocol::DelegateEndEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -192,13 +195,35 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             }
             EventMsg::TokenCount(ev) => {
                 if let Some(usage_info) = ev.info {
+                    let cost = usage_info
+                        .total_cost_usd
+                        .map(|cost| format!(" (~${cost:.4})"))
+                        .unwrap_or_default();
+                    let estimated = if usage_info.estimated {
+                        " (estimated)"
+                    } else {
+                        ""
+                    };
                     ts_println!(
                         self,
-                        "tokens used: {}",
+                        "tokens used: {}{estimated}{cost}",
                         format_with_separators(usage_info.total_token_usage.blended_total())
                     );
                 }
             }
+            EventMsg::BudgetExceeded(ev) => match ev.kind {
+                BudgetKind::Soft => {
+                    ts_println!(self, "{}", ev.to_string().style(self.dimmed));
+                }
+                BudgetKind::Hard => {
+                    let prefix = "ERROR:".style(self.red);
+                    ts_println!(self, "{prefix} {ev}");
+                }
+            },
+            EventMsg::OutputSchemaMismatch(ev) => {
+                let prefix = "ERROR:".style(self.red);
+                ts_println!(self, "{prefix} {ev}");
+            }
             EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }) => {
                 if !self.answer_started {
                     ts_println!(self, "{}\n", "codex".style(self.italic).style(self.magenta));
@@ -299,6 +324,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 aggregated_output,
                 duration,
                 exit_code,
+                sandbox_violations,
                 ..
             }) => {
                 let exec_command = self.call_id_to_command.remove(&call_id);
@@ -328,6 +354,9 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     }
                 }
                 println!("{}", truncated_output.style(self.dimmed));
+                for violation in sandbox_violations {
+                    println!("{} {violation}", "sandbox blocked".style(self.red));
+                }
             }
             EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
                 call_id: _,
@@ -571,6 +600,33 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     }
                 }
             }
+            EventMsg::DelegateBegin(DelegateBeginEvent { task, model, .. }) => {
+                ts_println!(
+                    self,
+                    "{} {}",
+                    "delegate".style(self.magenta),
+                    format!("({model})").style(self.dimmed)
+                );
+                println!("{}", task.style(self.italic));
+            }
+            EventMsg::DelegateEvent(_) => {
+                // The child's own events are summarized by `DelegateEnd`.
+            }
+            EventMsg::DelegateEnd(DelegateEndEvent {
+                last_agent_message,
+                success,
+                ..
+            }) => {
+                let title = if success {
+                    "delegate finished".style(self.green)
+                } else {
+                    "delegate failed".style(self.red)
+                };
+                ts_println!(self, "{title}");
+                if let Some(message) = last_agent_message {
+                    println!("{}", message.style(self.dimmed));
+                }
+            }
             EventMsg::GetHistoryEntryResponse(_) => {
                 // Currently ignored in exec output.
             }
@@ -590,12 +646,43 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 TurnAbortReason::ReviewEnded => {
                     ts_println!(self, "task aborted: review ended");
                 }
+                TurnAbortReason::BudgetExceeded => {
+                    ts_println!(self, "task aborted: budget exceeded");
+                    return CodexStatus::InitiateShutdown;
+                }
             },
             EventMsg::ShutdownComplete => return CodexStatus::Shutdown,
             EventMsg::ConversationPath(_) => {}
             EventMsg::UserMessage(_) => {}
             EventMsg::EnteredReviewMode(_) => {}
             EventMsg::ExitedReviewMode(_) => {}
+            EventMsg::CheckpointRestored(ev) => {
+                ts_println!(
+                    self,
+                    "restored checkpoint {} ({} turn(s) undone)",
+                    ev.checkpoint_id,
+                    ev.turns_undone
+                );
+            }
+            EventMsg::TurnCommitted(ev) => {
+                let short_sha: String = ev.sha.chars().take(8).collect();
+                let subject = ev.message.lines().next().unwrap_or_default();
+                ts_println!(
+                    self,
+                    "{} {short_sha} {subject}",
+                    "committed".style(self.magenta)
+                );
+            }
+            EventMsg::PullRequestOpened(ev) => {
+                ts_println!(
+                    self,
+                    "{} #{} {} ({})",
+                    "pull request".style(self.magenta),
+                    ev.number,
+                    ev.title,
+                    ev.url
+                );
+            }
         }
         CodexStatus::Running
     }
@@ -21,8 +21,12 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
 use codex_core::protocol::TaskCompleteEvent;
+use codex_core::protocol::TurnAbortReason;
+use codex_core::protocol::TurnAbortedEvent;
+use codex_core::worktree::SessionWorktree;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
+use codex_protocol::config_types::WorktreeAction;
 use event_processor_with_human_output::EventProcessorWithHumanOutput;
 use event_processor_with_json_output::EventProcessorWithJsonOutput;
 use serde_json::Value;
@@ -46,6 +50,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         full_auto,
         dangerously_bypass_approvals_and_sandbox,
         cwd,
+        worktree,
         skip_git_repo_check,
         color,
         last_message_file,
@@ -178,7 +183,21 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     };
 
-    let config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    let mut config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    let worktree = if worktree || config.worktree.enabled {
+        match SessionWorktree::enter(&mut config) {
+            Ok(worktree) => Some(WorktreeGuard {
+                worktree,
+                on_exit: config.worktree.on_exit,
+            }),
+            Err(e) => {
+                eprintln!("Failed to create a git worktree for the session: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
     let mut event_processor: Box<dyn EventProcessor> = if json_mode {
         Box::new(EventProcessorWithJsonOutput::new(last_message_file.clone()))
     } else {
@@ -208,6 +227,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
 
     if !skip_git_repo_check && get_git_repo_root(&default_cwd).is_none() {
         eprintln!("Not inside a trusted directory and --skip-git-repo-check was not specified.");
+        drop(worktree);
         std::process::exit(1);
     }
 
@@ -316,7 +336,16 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     info!("Sent prompt with event ID: {initial_prompt_task_id}");
 
     // Run the loop until the task is complete.
+    let mut budget_exceeded = false;
+    let mut output_schema_mismatch = false;
     while let Some(event) = rx.recv().await {
+        match &event.msg {
+            EventMsg::TurnAborted(TurnAbortedEvent {
+                reason: TurnAbortReason::BudgetExceeded,
+            }) => budget_exceeded = true,
+            EventMsg::OutputSchemaMismatch(_) => output_schema_mismatch = true,
+            _ => {}
+        }
         let shutdown: CodexStatus = event_processor.process_event(event);
         match shutdown {
             CodexStatus::Running => continue,
@@ -329,9 +358,72 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     }
 
+    drop(worktree);
+
+    if budget_exceeded || output_schema_mismatch {
+        std::process::exit(1);
+    }
+
     Ok(())
 }
 
+/// The session's worktree, finished when dropped so that it is merged, kept
+/// or discarded however `run_main` returns. `std::process::exit` skips
+/// destructors, so drop it before exiting.
+struct WorktreeGuard {
+    worktree: SessionWorktree,
+    on_exit: Option<WorktreeAction>,
+}
+
+impl Drop for WorktreeGuard {
+    fn drop(&mut self) {
+        finish_worktree(&self.worktree, self.on_exit);
+    }
+}
+
+/// Apply `on_exit` to the session's worktree, asking on the terminal when no
+/// action is configured. Without a terminal the branch is kept.
+fn finish_worktree(worktree: &SessionWorktree, on_exit: Option<WorktreeAction>) {
+    let branch = worktree.branch();
+    let action = on_exit
+        .or_else(|| prompt_worktree_action(branch))
+        .unwrap_or(WorktreeAction::Keep);
+    match worktree.finish(action) {
+        Ok(()) => match action {
+            WorktreeAction::Merge => eprintln!(
+                "Merged branch {branch} into {}.",
+                worktree.origin().display()
+            ),
+            WorktreeAction::Keep => eprintln!("Kept the session's changes on branch {branch}."),
+            WorktreeAction::Discard => eprintln!("Discarded branch {branch}."),
+        },
+        Err(e) => eprintln!(
+            "Failed to {action} the worktree at {}: {e}",
+            worktree.path().display()
+        ),
+    }
+}
+
+fn prompt_worktree_action(branch: &str) -> Option<WorktreeAction> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return None;
+    }
+    loop {
+        eprint!("The session ran on branch {branch}. [m]erge, [k]eep branch, or [d]iscard? ");
+        let mut answer = String::new();
+        match std::io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => {}
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "m" | "merge" => return Some(WorktreeAction::Merge),
+            "k" | "keep" => return Some(WorktreeAction::Keep),
+            "d" | "discard" => return Some(WorktreeAction::Discard),
+            _ => {}
+        }
+    }
+}
+
 async fn resolve_resume_path(
     config: &Config,
     args: &crate::cli::ResumeArgs,
@@ -0,0 +1,130 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::expect_used, clippy::unwrap_used)]
+
+//! Verifies that `auto_commit` commits exactly the files a turn patched,
+//! with the model's commit message and the configured trailer, and records
+//! the commit in the rollout. Patches only apply through a binary that can
+//! act as `apply_patch`, so this runs `codex-exec` end to end.
+
+use std::path::Path;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::RolloutItem;
+use codex_core::protocol::RolloutLine;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_apply_patch_function_call;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+const ADD_NOTES: &str = "*** Begin Patch\n*** Add File: notes.txt\n+first\n*** End Patch";
+const TRAILER: &str = "Co-authored-by: Codex <codex@example.com>";
+
+fn git(cwd: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?} failed");
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn turn_changes_are_committed_with_generated_message() {
+    non_sandbox_test!();
+    let server = start_mock_server().await;
+    let bodies = vec![
+        sse(vec![
+            ev_apply_patch_function_call("call-add", ADD_NOTES),
+            ev_completed("r1"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m1", "added notes"),
+            ev_completed("r2"),
+        ]),
+        // The commit message request.
+        sse(vec![
+            ev_assistant_message("m2", "docs: add notes file"),
+            ev_completed("r3"),
+        ]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let home = TempDir::new().unwrap();
+    std::fs::write(
+        home.path().join("config.toml"),
+        format!("[auto_commit]\nenabled = true\ntrailer = \"{TRAILER}\"\n"),
+    )
+    .unwrap();
+    let repo = TempDir::new().unwrap();
+    let cwd = repo.path();
+    git(cwd, &["init", "-q"]);
+    git(cwd, &["config", "user.name", "Test"]);
+    git(cwd, &["config", "user.email", "test@example.com"]);
+    std::fs::write(cwd.join("README.md"), "readme\n").unwrap();
+    git(cwd, &["add", "README.md"]);
+    git(cwd, &["commit", "-q", "-m", "init"]);
+    // Work of the user that must stay out of the agent's commit.
+    std::fs::write(cwd.join("README.md"), "edited by the user\n").unwrap();
+    git(cwd, &["add", "README.md"]);
+
+    Command::cargo_bin("codex-exec")
+        .unwrap()
+        .current_dir(cwd)
+        .env("CODEX_HOME", home.path())
+        .env("OPENAI_API_KEY", "dummy")
+        .env("OPENAI_BASE_URL", format!("{}/v1", server.uri()))
+        .arg("-s")
+        .arg("danger-full-access")
+        .arg("add notes")
+        .assert()
+        .success();
+
+    let message = git(cwd, &["log", "-1", "--format=%B"]);
+    assert_eq!(message, format!("docs: add notes file\n\n{TRAILER}"));
+    assert_eq!(
+        git(cwd, &["show", "--name-only", "--format=", "HEAD"]),
+        "notes.txt"
+    );
+    assert_eq!(git(cwd, &["diff", "--cached", "--name-only"]), "README.md");
+
+    // The model saw the diff of the turn when writing the message.
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    let commit_request = String::from_utf8_lossy(&requests[2].body).into_owned();
+    assert!(commit_request.contains("+first"), "{commit_request}");
+
+    let sha = git(cwd, &["rev-parse", "HEAD"]);
+    let rollout = walkdir::WalkDir::new(home.path().join("sessions"))
+        .into_iter()
+        .filter_map(Result::ok)
+        .find(|entry| entry.file_type().is_file())
+        .expect("rollout file");
+    let recorded: Vec<String> = std::fs::read_to_string(rollout.path())
+        .unwrap()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RolloutLine>(line).ok())
+        .filter_map(|line| match line.item {
+            RolloutItem::EventMsg(EventMsg::TurnCommitted(ev)) => Some(ev.sha),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(recorded, vec![sha]);
+}
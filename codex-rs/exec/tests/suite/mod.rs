@@ -1,6 +1,8 @@
 // Aggregates all former standalone integration tests as modules.
 mod apply_patch;
+mod auto_commit;
 mod common;
 mod output_schema;
 mod resume;
 mod sandbox;
+mod worktree;
@@ -10,8 +10,21 @@ use std::process::ExitStatus;
 use tokio::fs::create_dir_all;
 use tokio::process::Child;
 
+/// The sandbox implementations of the platform; each test runs under all of
+/// them.
+#[cfg(target_os = "macos")]
+type SandboxBackend = ();
+#[cfg(target_os = "macos")]
+const SANDBOX_BACKENDS: [SandboxBackend; 1] = [()];
+#[cfg(target_os = "linux")]
+type SandboxBackend = codex_core::config_types::LinuxSandboxBackend;
+#[cfg(target_os = "linux")]
+const SANDBOX_BACKENDS: [SandboxBackend; 2] =
+    [SandboxBackend::Landlock, SandboxBackend::Namespaces];
+
 #[cfg(target_os = "macos")]
 async fn spawn_command_under_sandbox(
+    _backend: SandboxBackend,
     command: Vec<String>,
     command_cwd: PathBuf,
     sandbox_policy: &SandboxPolicy,
@@ -33,6 +46,7 @@ async fn spawn_command_under_sandbox(
 
 #[cfg(target_os = "linux")]
 async fn spawn_command_under_sandbox(
+    backend: SandboxBackend,
     command: Vec<String>,
     command_cwd: PathBuf,
     sandbox_policy: &SandboxPolicy,
@@ -44,6 +58,7 @@ async fn spawn_command_under_sandbox(
     let codex_linux_sandbox_exe = assert_cmd::cargo::cargo_bin("codex-exec");
     spawn_command_under_linux_sandbox(
         codex_linux_sandbox_exe,
+        backend,
         command,
         command_cwd,
         sandbox_policy,
@@ -89,27 +104,40 @@ if __name__ == '__main__':
 
     let command_cwd = std::env::current_dir().expect("should be able to get current dir");
     let sandbox_cwd = command_cwd.clone();
-    let mut child = spawn_command_under_sandbox(
-        vec![
-            "python3".to_string(),
-            "-c".to_string(),
-            python_code.to_string(),
-        ],
-        command_cwd,
-        &policy,
-        sandbox_cwd.as_path(),
-        StdioPolicy::Inherit,
-        HashMap::new(),
-    )
-    .await
-    .expect("should be able to spawn python under sandbox");
+    for backend in SANDBOX_BACKENDS {
+        let mut child = spawn_command_under_sandbox(
+            backend,
+            vec![
+                "python3".to_string(),
+                "-c".to_string(),
+                python_code.to_string(),
+            ],
+            command_cwd.clone(),
+            &policy,
+            sandbox_cwd.as_path(),
+            StdioPolicy::Inherit,
+            HashMap::new(),
+        )
+        .await
+        .expect("should be able to spawn python under sandbox");
 
-    let status = child.wait().await.expect("should wait for child process");
-    assert!(status.success(), "python exited with {status:?}");
+        let status = child.wait().await.expect("should wait for child process");
+        assert!(
+            status.success(),
+            "python exited with {status:?} under {backend:?}"
+        );
+    }
 }
 
 #[tokio::test]
 async fn sandbox_distinguishes_command_and_policy_cwds() {
+    for backend in SANDBOX_BACKENDS {
+        assert_sandbox_distinguishes_command_and_policy_cwds(backend).await;
+    }
+}
+
+#[expect(clippy::expect_used)]
+async fn assert_sandbox_distinguishes_command_and_policy_cwds(backend: SandboxBackend) {
     let temp = tempfile::tempdir().expect("should be able to create temp dir");
     let sandbox_root = temp.path().join("sandbox");
     let command_root = temp.path().join("command");
@@ -134,6 +162,7 @@ async fn sandbox_distinguishes_command_and_policy_cwds() {
 
     // Attempt to write inside the command cwd, which is outside of the sandbox policy cwd.
     let mut child = spawn_command_under_sandbox(
+        backend,
         vec![
             "bash".to_string(),
             "-lc".to_string(),
@@ -154,7 +183,7 @@ async fn sandbox_distinguishes_command_and_policy_cwds() {
         .expect("should wait for forbidden command");
     assert!(
         !status.success(),
-        "sandbox unexpectedly allowed writing to command cwd: {status:?}"
+        "sandbox unexpectedly allowed writing to command cwd under {backend:?}: {status:?}"
     );
     let forbidden_exists = tokio::fs::try_exists(&disallowed_path)
         .await
@@ -166,6 +195,7 @@ async fn sandbox_distinguishes_command_and_policy_cwds() {
 
     // Writing to the sandbox policy cwd after changing directories into it should succeed.
     let mut child = spawn_command_under_sandbox(
+        backend,
         vec![
             "/usr/bin/touch".to_string(),
             canonical_allowed_path.to_string_lossy().into_owned(),
@@ -182,7 +212,7 @@ async fn sandbox_distinguishes_command_and_policy_cwds() {
     let status = child.wait().await.expect("should wait for allowed command");
     assert!(
         status.success(),
-        "sandbox blocked allowed write: {status:?}"
+        "sandbox blocked allowed write under {backend:?}: {status:?}"
     );
     let allowed_exists = tokio::fs::try_exists(&canonical_allowed_path)
         .await
@@ -265,13 +295,20 @@ fn unix_sock_body() {
 
 #[tokio::test]
 async fn allow_unix_socketpair_recvfrom() {
-    run_code_under_sandbox(
-        "allow_unix_socketpair_recvfrom",
-        &SandboxPolicy::ReadOnly,
-        || async { unix_sock_body() },
-    )
-    .await
-    .expect("should be able to reexec");
+    for backend in SANDBOX_BACKENDS {
+        let status = run_code_under_sandbox(
+            "allow_unix_socketpair_recvfrom",
+            backend,
+            &SandboxPolicy::ReadOnly,
+            || async { unix_sock_body() },
+        )
+        .await
+        .expect("should be able to reexec");
+        if status.is_none() {
+            // We are the re-executed child and already ran the body.
+            break;
+        }
+    }
 }
 
 const IN_SANDBOX_ENV_VAR: &str = "IN_SANDBOX";
@@ -279,6 +316,7 @@ const IN_SANDBOX_ENV_VAR: &str = "IN_SANDBOX";
 #[expect(clippy::expect_used)]
 pub async fn run_code_under_sandbox<F, Fut>(
     test_selector: &str,
+    backend: SandboxBackend,
     policy: &SandboxPolicy,
     child_body: F,
 ) -> io::Result<Option<ExitStatus>>
@@ -301,6 +339,7 @@ where
         let command_cwd = std::env::current_dir().expect("should be able to get current dir");
         let sandbox_cwd = command_cwd.clone();
         let mut child = spawn_command_under_sandbox(
+            backend,
             cmds,
             command_cwd,
             policy,
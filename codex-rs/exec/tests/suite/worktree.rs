@@ -0,0 +1,150 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::expect_used, clippy::unwrap_used)]
+
+use std::path::Path;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_apply_patch_function_call;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+const ADD_NOTES: &str =
+    "*** Begin Patch\n*** Add File: notes.md\n+from the worktree\n*** End Patch";
+
+fn git(cwd: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?} failed");
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+fn init_repo(repo: &Path) {
+    git(repo, &["init", "-q", "--initial-branch=main"]);
+    git(repo, &["config", "user.name", "Test"]);
+    git(repo, &["config", "user.email", "test@example.com"]);
+    std::fs::write(repo.join("README.md"), "readme\n").unwrap();
+    git(repo, &["add", "README.md"]);
+    git(repo, &["commit", "-q", "-m", "init"]);
+}
+
+/// Run `codex exec --worktree` in `repo` for one turn that adds `notes.md`.
+async fn run_in_worktree(repo: &Path, home: &Path, on_exit: Option<&str>) {
+    let server = start_mock_server().await;
+    let bodies = [
+        sse(vec![
+            ev_apply_patch_function_call("call-add", ADD_NOTES),
+            ev_completed("r1"),
+        ]),
+        sse(vec![ev_completed("r2")]),
+    ];
+    for body in bodies {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+
+    let mut cmd = Command::cargo_bin("codex-exec").unwrap();
+    cmd.current_dir(repo)
+        .env("CODEX_HOME", home)
+        .env("OPENAI_API_KEY", "dummy")
+        .env("OPENAI_BASE_URL", format!("{}/v1", server.uri()))
+        .arg("--worktree")
+        .arg("-s")
+        .arg("danger-full-access");
+    if let Some(on_exit) = on_exit {
+        cmd.arg("-c").arg(format!("worktree.on_exit=\"{on_exit}\""));
+    }
+    cmd.arg("add notes").assert().success();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn worktree_changes_are_merged_back() {
+    non_sandbox_test!();
+    let home = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    init_repo(repo.path());
+
+    run_in_worktree(repo.path(), home.path(), Some("merge")).await;
+
+    assert_eq!(
+        std::fs::read_to_string(repo.path().join("notes.md")).unwrap(),
+        "from the worktree\n"
+    );
+    assert_eq!(git(repo.path(), &["status", "--porcelain"]), "");
+    assert_eq!(git(repo.path(), &["branch", "--list", "codex/*"]), "");
+    assert_eq!(
+        git(repo.path(), &["worktree", "list", "--porcelain"])
+            .lines()
+            .filter(|line| line.starts_with("worktree "))
+            .count(),
+        1
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn worktree_branch_is_kept_without_a_terminal() {
+    non_sandbox_test!();
+    let home = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    init_repo(repo.path());
+
+    run_in_worktree(repo.path(), home.path(), None).await;
+
+    assert!(!repo.path().join("notes.md").exists());
+    let branch = git(
+        repo.path(),
+        &["branch", "--list", "codex/*", "--format=%(refname:short)"],
+    );
+    assert!(branch.starts_with("codex/"), "{branch}");
+    assert_eq!(
+        git(repo.path(), &["show", &format!("{branch}:notes.md")]),
+        "from the worktree"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn worktree_is_finished_when_the_session_fails_to_start() {
+    non_sandbox_test!();
+    let home = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    init_repo(repo.path());
+
+    // No Ollama server listens there, so the run fails after entering the
+    // worktree.
+    Command::cargo_bin("codex-exec")
+        .unwrap()
+        .current_dir(repo.path())
+        .env("CODEX_HOME", home.path())
+        .env("CODEX_OSS_BASE_URL", "http://127.0.0.1:9/v1")
+        .arg("--worktree")
+        .arg("--oss")
+        .arg("-c")
+        .arg("worktree.on_exit=\"discard\"")
+        .arg("add notes")
+        .assert()
+        .failure();
+
+    assert_eq!(git(repo.path(), &["branch", "--list", "codex/*"]), "");
+    assert_eq!(
+        git(repo.path(), &["worktree", "list", "--porcelain"])
+            .lines()
+            .filter(|line| line.starts_with("worktree "))
+            .count(),
+        1
+    );
+}
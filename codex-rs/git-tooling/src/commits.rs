@@ -0,0 +1,264 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::GitToolingError;
+use crate::operations::commit_identity_env;
+use crate::operations::ensure_git_repository;
+use crate::operations::normalize_relative_path;
+use crate::operations::resolve_head;
+use crate::operations::resolve_repository_root;
+use crate::operations::run_git_for_status;
+use crate::operations::run_git_for_stdout;
+
+/// Options to control committing a set of files.
+pub struct CommitPathsOptions<'a> {
+    /// Directory inside the repository to commit to.
+    pub repo_path: &'a Path,
+    /// Files to commit, absolute or relative to `repo_path`. Files outside
+    /// the repository and ignored files are skipped.
+    pub paths: &'a [PathBuf],
+    /// Full commit message.
+    pub message: &'a str,
+}
+
+impl<'a> CommitPathsOptions<'a> {
+    pub fn new(repo_path: &'a Path, paths: &'a [PathBuf], message: &'a str) -> Self {
+        Self {
+            repo_path,
+            paths,
+            message,
+        }
+    }
+}
+
+/// Paths of `options.paths` that would be committed, relative to the
+/// repository root.
+pub fn committable_paths(
+    options: &CommitPathsOptions<'_>,
+) -> Result<Vec<PathBuf>, GitToolingError> {
+    ensure_git_repository(options.repo_path)?;
+    let root = resolve_repository_root(options.repo_path)?;
+    let mut relative = Vec::new();
+    for path in options.paths {
+        let Some(path) = relative_to_root(&root, &options.repo_path.join(path)) else {
+            continue;
+        };
+        let Ok(path) = normalize_relative_path(&path) else {
+            continue;
+        };
+        if relative.contains(&path) || is_ignored(&root, &path)? {
+            continue;
+        }
+        // A file that was created and removed again has nothing to commit.
+        if !root.join(&path).exists() && !is_tracked(&root, &path)? {
+            continue;
+        }
+        relative.push(path);
+    }
+    Ok(relative)
+}
+
+/// Stage and commit exactly `options.paths` on the current branch. Other
+/// changes, including changes already staged, stay out of the commit.
+/// Repository hooks run as usual, so a failing `pre-commit` or `commit-msg`
+/// hook fails the commit. Returns the new commit, or `None` when the paths
+/// have no changes.
+pub fn commit_paths(options: &CommitPathsOptions<'_>) -> Result<Option<String>, GitToolingError> {
+    let paths = committable_paths(options)?;
+    if paths.is_empty() {
+        return Ok(None);
+    }
+    let root = resolve_repository_root(options.repo_path)?;
+
+    let mut add_args = vec![
+        OsString::from("add"),
+        OsString::from("--all"),
+        OsString::from("--"),
+    ];
+    add_args.extend(paths.iter().map(|path| OsString::from(path.as_os_str())));
+    run_git_for_status(root.as_path(), add_args, None)?;
+
+    let mut diff_args = vec![
+        OsString::from("diff"),
+        OsString::from("--cached"),
+        OsString::from("--name-only"),
+        OsString::from("--"),
+    ];
+    diff_args.extend(paths.iter().map(|path| OsString::from(path.as_os_str())));
+    if run_git_for_stdout(root.as_path(), diff_args, None)?.is_empty() {
+        return Ok(None);
+    }
+
+    let mut commit_args = vec![
+        OsString::from("commit"),
+        OsString::from("--quiet"),
+        OsString::from("-m"),
+        OsString::from(options.message),
+        OsString::from("--"),
+    ];
+    commit_args.extend(paths.iter().map(|path| OsString::from(path.as_os_str())));
+    let env = commit_identity_env(root.as_path());
+    run_git_for_status(root.as_path(), commit_args, Some(env.as_slice()))?;
+    resolve_head(root.as_path())
+}
+
+/// `path` relative to `root`, also when one of them goes through a symlink
+/// or `path` no longer exists.
+fn relative_to_root(root: &Path, path: &Path) -> Option<PathBuf> {
+    if let Ok(relative) = path.strip_prefix(root) {
+        return Some(relative.to_path_buf());
+    }
+    let root = root.canonicalize().ok()?;
+    let parent = path.parent()?.canonicalize().ok()?;
+    let relative = parent.strip_prefix(&root).ok()?;
+    Some(relative.join(path.file_name()?))
+}
+
+fn is_ignored(root: &Path, path: &Path) -> Result<bool, GitToolingError> {
+    match run_git_for_status(
+        root,
+        vec![
+            OsString::from("check-ignore"),
+            OsString::from("--quiet"),
+            OsString::from("--"),
+            OsString::from(path.as_os_str()),
+        ],
+        None,
+    ) {
+        Ok(()) => Ok(true),
+        Err(GitToolingError::GitCommand { status, .. }) if status.code() == Some(1) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+fn is_tracked(root: &Path, path: &Path) -> Result<bool, GitToolingError> {
+    let listed = run_git_for_stdout(
+        root,
+        vec![
+            OsString::from("ls-files"),
+            OsString::from("--"),
+            OsString::from(path.as_os_str()),
+        ],
+        None,
+    )?;
+    Ok(!listed.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::process::Command;
+
+    /// Runs a git command in the test repository and asserts success.
+    fn run_git_in(repo_path: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .current_dir(repo_path)
+            .args(args)
+            .status()
+            .expect("git command");
+        assert!(status.success(), "git command failed: {args:?}");
+    }
+
+    /// Runs a git command and returns its trimmed stdout output.
+    fn run_git_stdout(repo_path: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(args)
+            .output()
+            .expect("git command");
+        assert!(output.status.success(), "git command failed: {args:?}");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Initializes a repository with one commit on `main`.
+    fn init_test_repo(repo: &Path) {
+        run_git_in(repo, &["init", "--initial-branch=main"]);
+        run_git_in(repo, &["config", "core.autocrlf", "false"]);
+        run_git_in(repo, &["config", "user.name", "Tester"]);
+        run_git_in(repo, &["config", "user.email", "test@example.com"]);
+        std::fs::write(repo.join(".gitignore"), "target/\n").expect("write gitignore");
+        std::fs::write(repo.join("tracked.txt"), "initial\n").expect("write file");
+        std::fs::write(repo.join("gone.txt"), "initial\n").expect("write file");
+        run_git_in(repo, &["add", "."]);
+        run_git_in(repo, &["commit", "-q", "-m", "initial"]);
+    }
+
+    #[test]
+    /// Only the listed files end up in the commit, including deletions.
+    fn commit_paths_commits_only_listed_files() -> Result<(), GitToolingError> {
+        let temp = tempfile::tempdir()?;
+        let repo = temp.path();
+        init_test_repo(repo);
+
+        std::fs::write(repo.join("tracked.txt"), "changed\n")?;
+        std::fs::write(repo.join("new.txt"), "new\n")?;
+        std::fs::remove_file(repo.join("gone.txt"))?;
+        std::fs::write(repo.join("other.txt"), "user work\n")?;
+        run_git_in(repo, &["add", "other.txt"]);
+        std::fs::create_dir_all(repo.join("target"))?;
+        std::fs::write(repo.join("target/out.txt"), "build\n")?;
+
+        let paths = vec![
+            repo.join("tracked.txt"),
+            PathBuf::from("new.txt"),
+            repo.join("gone.txt"),
+            repo.join("target/out.txt"),
+            temp.path().join("../outside.txt"),
+        ];
+        let sha =
+            commit_paths(&CommitPathsOptions::new(repo, &paths, "feat: update"))?.expect("commit");
+
+        assert_eq!(sha, run_git_stdout(repo, &["rev-parse", "HEAD"]));
+        assert_eq!(
+            run_git_stdout(repo, &["show", "--name-status", "--format=%s", "HEAD"]),
+            "feat: update\n\nD\tgone.txt\nA\tnew.txt\nM\ttracked.txt"
+        );
+        // The user's staged file is still staged, but not committed.
+        assert_eq!(
+            run_git_stdout(repo, &["diff", "--cached", "--name-only"]),
+            "other.txt"
+        );
+        Ok(())
+    }
+
+    #[test]
+    /// Nothing is committed when the listed files are unchanged.
+    fn commit_paths_without_changes_returns_none() -> Result<(), GitToolingError> {
+        let temp = tempfile::tempdir()?;
+        let repo = temp.path();
+        init_test_repo(repo);
+        let head = run_git_stdout(repo, &["rev-parse", "HEAD"]);
+
+        let paths = vec![repo.join("tracked.txt"), repo.join("never-existed.txt")];
+        assert_eq!(
+            commit_paths(&CommitPathsOptions::new(repo, &paths, "chore: nothing"))?,
+            None
+        );
+        assert_eq!(run_git_stdout(repo, &["rev-parse", "HEAD"]), head);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    /// A failing `pre-commit` hook fails the commit.
+    fn commit_paths_runs_hooks() -> Result<(), GitToolingError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir()?;
+        let repo = temp.path();
+        init_test_repo(repo);
+        let hook = repo.join(".git/hooks/pre-commit");
+        std::fs::write(&hook, "#!/bin/sh\necho rejected >&2\nexit 1\n")?;
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))?;
+
+        std::fs::write(repo.join("tracked.txt"), "changed\n")?;
+        let paths = vec![repo.join("tracked.txt")];
+        let err = commit_paths(&CommitPathsOptions::new(repo, &paths, "feat: blocked"))
+            .expect_err("hook rejects the commit");
+        assert!(matches!(err, GitToolingError::GitCommand { .. }));
+        assert_eq!(run_git_stdout(repo, &["log", "--format=%s"]), "initial");
+        Ok(())
+    }
+}
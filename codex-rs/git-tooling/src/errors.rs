@@ -5,7 +5,7 @@ use std::string::FromUtf8Error;
 use thiserror::Error;
 use walkdir::Error as WalkdirError;
 
-/// Errors returned while managing git worktree snapshots.
+/// Errors returned while managing git worktree snapshots and worktrees.
 #[derive(Debug, Error)]
 pub enum GitToolingError {
     #[error("git command `{command}` failed with status {status}: {stderr}")]
@@ -22,6 +22,8 @@ pub enum GitToolingError {
     },
     #[error("{path:?} is not a git repository")]
     NotAGitRepository { path: PathBuf },
+    #[error("{path:?} has no commits yet")]
+    UnbornHead { path: PathBuf },
     #[error("path {path:?} must be relative to the repository root")]
     NonRelativePath { path: PathBuf },
     #[error("path {path:?} escapes the repository root")]
@@ -1,16 +1,27 @@
 use std::fmt;
 
+mod commits;
 mod errors;
 mod ghost_commits;
 mod operations;
 mod platform;
+mod worktrees;
 
+pub use commits::CommitPathsOptions;
+pub use commits::commit_paths;
+pub use commits::committable_paths;
 pub use errors::GitToolingError;
 pub use ghost_commits::CreateGhostCommitOptions;
 pub use ghost_commits::create_ghost_commit;
 pub use ghost_commits::restore_ghost_commit;
 pub use ghost_commits::restore_to_commit;
 pub use platform::create_symlink;
+pub use worktrees::CreateWorktreeOptions;
+pub use worktrees::Worktree;
+pub use worktrees::commit_worktree_changes;
+pub use worktrees::create_worktree;
+pub use worktrees::merge_worktree;
+pub use worktrees::remove_worktree;
 
 /// Details of a ghost commit created from a repository state.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -130,6 +130,30 @@ fn non_empty_path(path: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Environment for `git commit` in `path`: empty when git has a user
+/// identity configured, otherwise a fallback identity so the commit can
+/// still be created.
+pub(crate) fn commit_identity_env(path: &Path) -> Vec<(OsString, OsString)> {
+    if run_git_for_stdout(path, ["config", "user.email"], None).is_ok() {
+        return Vec::new();
+    }
+    vec![
+        (OsString::from("GIT_AUTHOR_NAME"), OsString::from("Codex")),
+        (
+            OsString::from("GIT_AUTHOR_EMAIL"),
+            OsString::from("codex@codex.local"),
+        ),
+        (
+            OsString::from("GIT_COMMITTER_NAME"),
+            OsString::from("Codex"),
+        ),
+        (
+            OsString::from("GIT_COMMITTER_EMAIL"),
+            OsString::from("codex@codex.local"),
+        ),
+    ]
+}
+
 pub(crate) fn run_git_for_status<I, S>(
     dir: &Path,
     args: I,
@@ -0,0 +1,345 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::GitToolingError;
+use crate::operations::commit_identity_env;
+use crate::operations::ensure_git_repository;
+use crate::operations::repo_subdir;
+use crate::operations::resolve_head;
+use crate::operations::resolve_repository_root;
+use crate::operations::run_git_for_status;
+use crate::operations::run_git_for_stdout;
+
+/// Options to control worktree creation.
+pub struct CreateWorktreeOptions<'a> {
+    /// Directory inside the checkout the worktree branches off.
+    pub repo_path: &'a Path,
+    /// Where the new worktree is checked out. Must not exist yet.
+    pub worktree_path: &'a Path,
+    /// Name of the branch created for the worktree.
+    pub branch: &'a str,
+}
+
+impl<'a> CreateWorktreeOptions<'a> {
+    pub fn new(repo_path: &'a Path, worktree_path: &'a Path, branch: &'a str) -> Self {
+        Self {
+            repo_path,
+            worktree_path,
+            branch,
+        }
+    }
+}
+
+/// A linked git worktree checked out on its own branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worktree {
+    path: PathBuf,
+    branch: String,
+    base: String,
+    origin: PathBuf,
+    subdir: Option<PathBuf>,
+}
+
+impl Worktree {
+    /// Root of the worktree checkout.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Branch the worktree is checked out on.
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
+
+    /// Commit the branch started from.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// Root of the checkout the worktree was created from.
+    pub fn origin(&self) -> &Path {
+        &self.origin
+    }
+
+    /// Directory inside the worktree that corresponds to the `repo_path`
+    /// the worktree was created from.
+    pub fn cwd(&self) -> PathBuf {
+        match &self.subdir {
+            Some(subdir) => self.path.join(subdir),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// Check out `HEAD` of the checkout containing `repo_path` into a new
+/// worktree on a new branch.
+pub fn create_worktree(options: &CreateWorktreeOptions<'_>) -> Result<Worktree, GitToolingError> {
+    ensure_git_repository(options.repo_path)?;
+
+    let origin = resolve_repository_root(options.repo_path)?;
+    let subdir = repo_subdir(origin.as_path(), options.repo_path);
+    let Some(base) = resolve_head(origin.as_path())? else {
+        return Err(GitToolingError::UnbornHead { path: origin });
+    };
+    if let Some(parent) = options.worktree_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    run_git_for_status(
+        origin.as_path(),
+        vec![
+            OsString::from("worktree"),
+            OsString::from("add"),
+            OsString::from("-b"),
+            OsString::from(options.branch),
+            OsString::from(options.worktree_path.as_os_str()),
+            OsString::from(&base),
+        ],
+        None,
+    )?;
+
+    Ok(Worktree {
+        path: options.worktree_path.to_path_buf(),
+        branch: options.branch.to_string(),
+        base,
+        origin,
+        subdir,
+    })
+}
+
+/// Commit every change in the worktree, including untracked files, to its
+/// branch. Returns the new commit, or `None` when there was nothing to
+/// commit. The user's git identity is used when one is configured.
+pub fn commit_worktree_changes(
+    worktree: &Worktree,
+    message: &str,
+) -> Result<Option<String>, GitToolingError> {
+    let path = worktree.path();
+    run_git_for_status(path, ["add", "--all"], None)?;
+    let staged = run_git_for_stdout(path, ["diff", "--cached", "--name-only"], None)?;
+    if staged.is_empty() {
+        return Ok(None);
+    }
+
+    let env = commit_identity_env(path);
+    run_git_for_status(
+        path,
+        vec![
+            OsString::from("commit"),
+            OsString::from("--quiet"),
+            OsString::from("-m"),
+            OsString::from(message),
+        ],
+        Some(env.as_slice()),
+    )?;
+    resolve_head(path)
+}
+
+/// Merge the worktree branch into the checkout it was created from, then
+/// remove the worktree and its branch. Uncommitted changes in the worktree
+/// are committed first. When the merge fails, it is aborted and both the
+/// worktree and the branch are left in place.
+pub fn merge_worktree(worktree: &Worktree, message: &str) -> Result<(), GitToolingError> {
+    commit_worktree_changes(worktree, message)?;
+    let origin = worktree.origin();
+    if let Err(err) = run_git_for_status(
+        origin,
+        vec![
+            OsString::from("merge"),
+            OsString::from("--no-edit"),
+            OsString::from(worktree.branch()),
+        ],
+        None,
+    ) {
+        // Nothing to abort when the merge refused to start, e.g. because of
+        // local changes in the checkout.
+        let _ = run_git_for_status(origin, ["merge", "--abort"], None);
+        return Err(err);
+    }
+    remove_worktree(worktree, true)
+}
+
+/// Remove the worktree checkout, keeping its branch unless `delete_branch`
+/// is set. Uncommitted changes in the worktree are lost; use
+/// [`commit_worktree_changes`] first to keep them on the branch.
+pub fn remove_worktree(worktree: &Worktree, delete_branch: bool) -> Result<(), GitToolingError> {
+    let origin = worktree.origin();
+    run_git_for_status(
+        origin,
+        vec![
+            OsString::from("worktree"),
+            OsString::from("remove"),
+            OsString::from("--force"),
+            OsString::from(worktree.path().as_os_str()),
+        ],
+        None,
+    )?;
+    if delete_branch {
+        run_git_for_status(
+            origin,
+            vec![
+                OsString::from("branch"),
+                OsString::from("-D"),
+                OsString::from(worktree.branch()),
+            ],
+            None,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::process::Command;
+
+    /// Runs a git command in the test repository and asserts success.
+    fn run_git_in(repo_path: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .current_dir(repo_path)
+            .args(args)
+            .status()
+            .expect("git command");
+        assert!(status.success(), "git command failed: {args:?}");
+    }
+
+    /// Runs a git command and returns its trimmed stdout output.
+    fn run_git_stdout(repo_path: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(args)
+            .output()
+            .expect("git command");
+        assert!(output.status.success(), "git command failed: {args:?}");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Initializes a repository with one commit on `main` and a `src` directory.
+    fn init_test_repo(repo: &Path) {
+        run_git_in(repo, &["init", "--initial-branch=main"]);
+        run_git_in(repo, &["config", "core.autocrlf", "false"]);
+        run_git_in(repo, &["config", "user.name", "Tester"]);
+        run_git_in(repo, &["config", "user.email", "test@example.com"]);
+        std::fs::create_dir_all(repo.join("src")).expect("create src");
+        std::fs::write(repo.join("src/lib.txt"), "initial\n").expect("write file");
+        run_git_in(repo, &["add", "."]);
+        run_git_in(repo, &["commit", "-q", "-m", "initial"]);
+    }
+
+    fn create_in(repo: &Path, worktrees: &Path, branch: &str) -> Result<Worktree, GitToolingError> {
+        let path = worktrees.join(branch.replace('/', "-"));
+        create_worktree(&CreateWorktreeOptions::new(
+            &repo.join("src"),
+            &path,
+            branch,
+        ))
+    }
+
+    #[test]
+    /// A worktree starts at HEAD on its own branch and keeps the subdirectory.
+    fn create_worktree_checks_out_new_branch() -> Result<(), GitToolingError> {
+        let temp = tempfile::tempdir()?;
+        let repo = temp.path().join("repo");
+        std::fs::create_dir_all(&repo)?;
+        init_test_repo(&repo);
+
+        let worktree = create_in(&repo, &temp.path().join("worktrees"), "codex/one")?;
+
+        assert_eq!(worktree.cwd(), worktree.path().join("src"));
+        assert_eq!(
+            std::fs::read_to_string(worktree.cwd().join("lib.txt"))?,
+            "initial\n"
+        );
+        assert_eq!(
+            run_git_stdout(worktree.path(), &["branch", "--show-current"]),
+            "codex/one"
+        );
+        assert_eq!(
+            worktree.base(),
+            run_git_stdout(&repo, &["rev-parse", "HEAD"])
+        );
+        Ok(())
+    }
+
+    #[test]
+    /// A repository without commits cannot be branched into a worktree.
+    fn create_worktree_requires_a_commit() -> Result<(), GitToolingError> {
+        let temp = tempfile::tempdir()?;
+        let repo = temp.path().join("repo");
+        std::fs::create_dir_all(&repo)?;
+        run_git_in(&repo, &["init", "--initial-branch=main"]);
+
+        let err = create_worktree(&CreateWorktreeOptions::new(
+            &repo,
+            &temp.path().join("wt"),
+            "codex/one",
+        ))
+        .unwrap_err();
+        assert!(matches!(err, GitToolingError::UnbornHead { .. }));
+        Ok(())
+    }
+
+    #[test]
+    /// Merging commits pending changes, merges them and cleans up.
+    fn merge_worktree_brings_changes_back() -> Result<(), GitToolingError> {
+        let temp = tempfile::tempdir()?;
+        let repo = temp.path().join("repo");
+        std::fs::create_dir_all(&repo)?;
+        init_test_repo(&repo);
+        let worktree = create_in(&repo, &temp.path().join("worktrees"), "codex/merge")?;
+
+        std::fs::write(worktree.cwd().join("lib.txt"), "changed\n")?;
+        std::fs::write(worktree.cwd().join("new.txt"), "new\n")?;
+        merge_worktree(&worktree, "codex session")?;
+
+        assert_eq!(
+            std::fs::read_to_string(repo.join("src/lib.txt"))?,
+            "changed\n"
+        );
+        assert_eq!(std::fs::read_to_string(repo.join("src/new.txt"))?, "new\n");
+        assert!(!worktree.path().exists());
+        assert_eq!(
+            run_git_stdout(&repo, &["branch", "--list", "codex/merge"]),
+            ""
+        );
+        Ok(())
+    }
+
+    #[test]
+    /// Keeping commits the changes to the branch; discarding drops both.
+    fn keep_and_discard_worktree() -> Result<(), GitToolingError> {
+        let temp = tempfile::tempdir()?;
+        let repo = temp.path().join("repo");
+        std::fs::create_dir_all(&repo)?;
+        init_test_repo(&repo);
+        let worktrees = temp.path().join("worktrees");
+
+        let kept = create_in(&repo, &worktrees, "codex/keep")?;
+        std::fs::write(kept.cwd().join("lib.txt"), "kept\n")?;
+        let commit = commit_worktree_changes(&kept, "codex session")?;
+        assert!(commit.is_some());
+        assert_eq!(commit_worktree_changes(&kept, "codex session")?, None);
+        remove_worktree(&kept, false)?;
+        assert!(!kept.path().exists());
+        assert_eq!(
+            run_git_stdout(&repo, &["show", "codex/keep:src/lib.txt"]),
+            "kept"
+        );
+        assert_eq!(
+            std::fs::read_to_string(repo.join("src/lib.txt"))?,
+            "initial\n"
+        );
+
+        let discarded = create_in(&repo, &worktrees, "codex/discard")?;
+        std::fs::write(discarded.cwd().join("lib.txt"), "discarded\n")?;
+        remove_worktree(&discarded, true)?;
+        assert!(!discarded.path().exists());
+        assert_eq!(
+            run_git_stdout(&repo, &["branch", "--list", "codex/discard"]),
+            ""
+        );
+        Ok(())
+    }
+}
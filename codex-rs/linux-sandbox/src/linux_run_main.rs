@@ -1,11 +1,19 @@
 use clap::Parser;
+use codex_core::landlock::CODEX_SANDBOX_VIOLATION_REPORT_ENV_VAR;
 use std::ffi::CString;
 use std::path::PathBuf;
 
 use crate::landlock::apply_sandbox_policy_to_current_thread;
+use crate::namespaces::run_command_in_namespaces;
+use crate::violations::run_command_with_violation_report;
 
 #[derive(Debug, Parser)]
 pub struct LandlockCommand {
+    /// Isolate the command in fresh user, mount and PID namespaces instead of
+    /// restricting it with Landlock.
+    #[arg(long = "namespaces")]
+    pub use_namespaces: bool,
+
     /// It is possible that the cwd used in the context of the sandbox policy
     /// is different from the cwd of the process to spawn.
     pub sandbox_policy_cwd: PathBuf,
@@ -19,19 +27,51 @@ pub struct LandlockCommand {
 
 pub fn run_main() -> ! {
     let LandlockCommand {
+        use_namespaces,
         sandbox_policy_cwd,
         sandbox_policy,
         command,
     } = LandlockCommand::parse();
 
+    if command.is_empty() {
+        panic!("No command specified to execute.");
+    }
+
+    // The report location is meant for us, not for the sandboxed command.
+    let violation_report = std::env::var_os(CODEX_SANDBOX_VIOLATION_REPORT_ENV_VAR);
+    // SAFETY: the helper is still single-threaded at this point.
+    unsafe {
+        std::env::remove_var(CODEX_SANDBOX_VIOLATION_REPORT_ENV_VAR);
+    }
+
+    if use_namespaces {
+        if violation_report.is_some() {
+            panic!(
+                "sandbox violation reports are only supported by the Landlock backend, not with --namespaces"
+            );
+        }
+        run_command_in_namespaces(&sandbox_policy, &sandbox_policy_cwd, command);
+    }
+
+    if let Some(report_path) = violation_report {
+        run_command_with_violation_report(
+            &sandbox_policy,
+            &sandbox_policy_cwd,
+            command,
+            &PathBuf::from(report_path),
+        );
+    }
+
     if let Err(e) = apply_sandbox_policy_to_current_thread(&sandbox_policy, &sandbox_policy_cwd) {
         panic!("error running landlock: {e:?}");
     }
 
-    if command.is_empty() {
-        panic!("No command specified to execute.");
-    }
+    exec_command(command)
+}
 
+/// Replaces the current process image with `command`, resolving `command[0]`
+/// against `PATH`.
+pub(crate) fn exec_command(command: Vec<String>) -> ! {
     #[expect(clippy::expect_used)]
     let c_command =
         CString::new(command[0].as_str()).expect("Failed to convert command to CString");
@@ -52,3 +92,40 @@ pub fn run_main() -> ! {
     let err = std::io::Error::last_os_error();
     panic!("Failed to execvp {}: {err}", command[0].as_str());
 }
+
+/// Waits for the sandboxed child and exits with the same status.
+pub(crate) fn wait_for_child(child_pid: libc::pid_t) -> ! {
+    exit_like_child(reap_child(child_pid))
+}
+
+/// Waits for `child_pid` to terminate and returns its wait status.
+pub(crate) fn reap_child(child_pid: libc::pid_t) -> libc::c_int {
+    let mut status: libc::c_int = 0;
+    loop {
+        if unsafe { libc::waitpid(child_pid, &mut status, 0) } != -1 {
+            return status;
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            panic!("waitpid failed: {err}");
+        }
+    }
+}
+
+/// Exits with the same status as a child that terminated with wait status
+/// `status`. If the child was killed by a signal, the signal is re-raised so
+/// the caller observes the same termination reason as if the command had been
+/// exec'd directly.
+pub(crate) fn exit_like_child(status: libc::c_int) -> ! {
+    if libc::WIFEXITED(status) {
+        std::process::exit(libc::WEXITSTATUS(status));
+    }
+    if libc::WIFSIGNALED(status) {
+        let signal = libc::WTERMSIG(status);
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::kill(libc::getpid(), signal);
+        }
+    }
+    std::process::exit(1);
+}
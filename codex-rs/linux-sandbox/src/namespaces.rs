@@ -0,0 +1,393 @@
+use std::ffi::CString;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::WritableRoot;
+
+use crate::landlock::install_network_seccomp_filter_on_current_thread;
+use crate::linux_run_main::exec_command;
+use crate::linux_run_main::wait_for_child;
+
+/// Directory that is covered with a scratch tmpfs and then used as the
+/// temporary root while the sandboxed root is assembled. Since the mount
+/// namespace is private, the host's `/tmp` is unaffected.
+const STAGING_DIR: &str = "/tmp";
+
+/// Location of the sandboxed root, relative to the staging tmpfs.
+const NEW_ROOT: &str = "/newroot";
+
+/// Location of the original root, relative to the staging tmpfs.
+const OLD_ROOT: &str = "/oldroot";
+
+/// Runs `command` inside fresh user, mount and PID namespaces (plus a network
+/// namespace unless the policy grants network access), bubblewrap-style.
+///
+/// The calling process stays outside the new PID namespace and acts as a
+/// supervisor that mirrors the exit status of the sandboxed child. The child
+/// is PID 1 of its namespace, so when it exits, or when the supervisor is
+/// killed (e.g., because the command timed out), the kernel kills every
+/// process that was spawned inside the sandbox.
+pub(crate) fn run_command_in_namespaces(
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    command: Vec<String>,
+) -> ! {
+    let command_cwd = match std::env::current_dir() {
+        Ok(cwd) => cwd,
+        Err(e) => panic!("failed to determine current directory: {e}"),
+    };
+
+    if let Err(e) = unshare_namespaces(sandbox_policy) {
+        panic!("error creating sandbox namespaces: {e}");
+    }
+
+    // Only the supervisor keeps the write end open, so the child can tell
+    // whether the supervisor is still alive.
+    let mut liveness_pipe = [0; 2];
+    if unsafe { libc::pipe2(liveness_pipe.as_mut_ptr(), libc::O_CLOEXEC) } == -1 {
+        panic!("pipe2 failed: {}", io::Error::last_os_error());
+    }
+    let [liveness_read, liveness_write] = liveness_pipe;
+
+    match unsafe { libc::fork() } {
+        -1 => panic!("fork failed: {}", io::Error::last_os_error()),
+        0 => {
+            unsafe { libc::close(liveness_write) };
+            if let Err(e) = prepare_sandboxed_child(
+                sandbox_policy,
+                sandbox_policy_cwd,
+                &command_cwd,
+                liveness_read,
+            ) {
+                panic!("error setting up namespace sandbox: {e}");
+            }
+            exec_command(command)
+        }
+        child_pid => {
+            unsafe { libc::close(liveness_read) };
+            wait_for_child(child_pid)
+        }
+    }
+}
+
+fn unshare_namespaces(sandbox_policy: &SandboxPolicy) -> io::Result<()> {
+    let uid = unsafe { libc::geteuid() };
+    let gid = unsafe { libc::getegid() };
+
+    let mut flags = libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWPID;
+    if !sandbox_policy.has_full_network_access() {
+        // A fresh network namespace only has a loopback device, which is down.
+        flags |= libc::CLONE_NEWNET;
+    }
+    if unsafe { libc::unshare(flags) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Map the invoking user onto itself so that files keep their apparent
+    // ownership inside the sandbox. `setgroups` must be denied before an
+    // unprivileged process may write `gid_map`.
+    match std::fs::write("/proc/self/setgroups", "deny") {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    std::fs::write("/proc/self/uid_map", format!("{uid} {uid} 1"))?;
+    std::fs::write("/proc/self/gid_map", format!("{gid} {gid} 1"))?;
+    Ok(())
+}
+
+fn prepare_sandboxed_child(
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    command_cwd: &Path,
+    liveness_fd: libc::c_int,
+) -> io::Result<()> {
+    // Die with the supervisor so that killing it tears down the whole PID
+    // namespace.
+    if unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // The supervisor may have died before the death signal was armed. It
+    // lives outside our PID namespace, where `getppid()` reports 0, so check
+    // the pipe whose write end only the supervisor holds instead.
+    if supervisor_exited(liveness_fd)? {
+        std::process::exit(1);
+    }
+    unsafe { libc::close(liveness_fd) };
+
+    if sandbox_policy.has_full_disk_write_access() {
+        // Keep the host filesystem as-is, but still provide a `/proc` that
+        // matches the new PID namespace.
+        mount_proc(Path::new("/proc"))?;
+    } else {
+        let writable_roots = sandbox_policy.get_writable_roots_with_cwd(sandbox_policy_cwd);
+        assemble_sandboxed_root(&writable_roots, command_cwd)?;
+    }
+    std::env::set_current_dir(command_cwd)?;
+
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    if !sandbox_policy.has_full_network_access() {
+        install_network_seccomp_filter_on_current_thread().map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// Whether the write end of the liveness pipe read through `fd` is closed,
+/// i.e. whether the supervisor has exited.
+fn supervisor_exited(fd: libc::c_int) -> io::Result<bool> {
+    let mut poll_fd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    loop {
+        if unsafe { libc::poll(&mut poll_fd, 1, 0) } != -1 {
+            return Ok(poll_fd.revents & libc::POLLHUP != 0);
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// Replaces the root of the current mount namespace with a read-only view of
+/// the host filesystem in which only `writable_roots` are writable, `/tmp` and
+/// `/dev/shm` are empty private tmpfs mounts, and `/proc` belongs to the new
+/// PID namespace. A `command_cwd` hidden by the private `/tmp` is bound back
+/// read-only so the command can still start in it.
+fn assemble_sandboxed_root(writable_roots: &[WritableRoot], command_cwd: &Path) -> io::Result<()> {
+    // Ensure none of the mounts below propagate back to the host.
+    mount(
+        None,
+        Path::new("/"),
+        None,
+        libc::MS_REC | libc::MS_PRIVATE,
+        None,
+    )?;
+
+    // Move to a scratch tmpfs root and keep the original root reachable at
+    // `OLD_ROOT` so that writable roots can be bound from it.
+    let staging = Path::new(STAGING_DIR);
+    mount_tmpfs(staging, "0755")?;
+    let new_root = Path::new(NEW_ROOT);
+    let old_root = Path::new(OLD_ROOT);
+    std::fs::create_dir(rebase(staging, new_root))?;
+    std::fs::create_dir(rebase(staging, old_root))?;
+    pivot_root(staging, &rebase(staging, old_root))?;
+    std::env::set_current_dir("/")?;
+
+    bind_mount(old_root, new_root)?;
+    remount_read_only_recursively(new_root)?;
+
+    mount_tmpfs(&rebase(new_root, Path::new("/tmp")), "1777")?;
+    let dev_shm = rebase(new_root, Path::new("/dev/shm"));
+    if dev_shm.is_dir() {
+        mount_tmpfs(&dev_shm, "1777")?;
+    }
+
+    for WritableRoot {
+        root,
+        read_only_subpaths,
+    } in writable_roots
+    {
+        // The private /tmp is already writable.
+        if root == Path::new("/tmp") || !rebase(old_root, root).exists() {
+            continue;
+        }
+        let target = rebase(new_root, root);
+        // Roots that live under the private /tmp must be recreated first.
+        std::fs::create_dir_all(&target)?;
+        bind_mount(&rebase(old_root, root), &target)?;
+
+        for subpath in read_only_subpaths {
+            let target = rebase(new_root, subpath);
+            if target.exists() {
+                bind_mount(&target, &target)?;
+                remount_read_only_recursively(&target)?;
+            }
+        }
+    }
+
+    let cwd_target = rebase(new_root, command_cwd);
+    if !cwd_target.exists() && rebase(old_root, command_cwd).exists() {
+        std::fs::create_dir_all(&cwd_target)?;
+        bind_mount(&rebase(old_root, command_cwd), &cwd_target)?;
+        remount_read_only_recursively(&cwd_target)?;
+    }
+
+    mount_proc(&rebase(new_root, Path::new("/proc")))?;
+
+    // Switch to the new root and lazily detach the staging tmpfs, which also
+    // makes the original root unreachable.
+    std::env::set_current_dir(new_root)?;
+    pivot_root(Path::new("."), Path::new("."))?;
+    umount_detach(Path::new("."))?;
+    std::env::set_current_dir("/")?;
+    Ok(())
+}
+
+/// Remounts `root` and every mount below it read-only, preserving the flags
+/// that the kernel refuses to clear inside a user namespace.
+fn remount_read_only_recursively(root: &Path) -> io::Result<()> {
+    // The original procfs is still mounted under `OLD_ROOT`; mount points are
+    // reported relative to the current (staging) root.
+    let mountinfo = std::fs::read_to_string(rebase(
+        Path::new(OLD_ROOT),
+        Path::new("/proc/self/mountinfo"),
+    ))?;
+    for mount_point in parse_mount_points(&mountinfo) {
+        if mount_point.starts_with(root) {
+            remount_read_only(&mount_point)?;
+        }
+    }
+    Ok(())
+}
+
+fn remount_read_only(path: &Path) -> io::Result<()> {
+    let c_path = to_cstring(path)?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } == -1 {
+        let err = io::Error::last_os_error();
+        // Mount points that vanished or that we cannot even inspect (e.g.,
+        // shadowed by another mount) cannot be written through either.
+        if matches!(err.raw_os_error(), Some(libc::ENOENT | libc::EACCES)) {
+            return Ok(());
+        }
+        return Err(err);
+    }
+
+    let mut flags = libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY;
+    for (st_flag, ms_flag) in [
+        (libc::ST_NOSUID, libc::MS_NOSUID),
+        (libc::ST_NODEV, libc::MS_NODEV),
+        (libc::ST_NOEXEC, libc::MS_NOEXEC),
+        (libc::ST_NOATIME, libc::MS_NOATIME),
+        (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+        (libc::ST_RELATIME, libc::MS_RELATIME),
+    ] {
+        if stat.f_flag & st_flag != 0 {
+            flags |= ms_flag;
+        }
+    }
+    mount(None, path, None, flags, None)
+}
+
+/// Extracts the mount point column from `/proc/self/mountinfo`, undoing the
+/// kernel's octal escaping of whitespace and backslashes.
+fn parse_mount_points(mountinfo: &str) -> Vec<PathBuf> {
+    mountinfo
+        .lines()
+        .filter_map(|line| line.split(' ').nth(4))
+        .map(|escaped| {
+            let mut bytes = Vec::with_capacity(escaped.len());
+            let raw = escaped.as_bytes();
+            let mut i = 0;
+            while i < raw.len() {
+                if raw[i] == b'\\'
+                    && i + 3 < raw.len()
+                    && let Ok(byte) = u8::from_str_radix(&escaped[i + 1..i + 4], 8)
+                {
+                    bytes.push(byte);
+                    i += 4;
+                } else {
+                    bytes.push(raw[i]);
+                    i += 1;
+                }
+            }
+            PathBuf::from(std::ffi::OsStr::from_bytes(&bytes))
+        })
+        .collect()
+}
+
+/// Returns `path` (which must be absolute) re-rooted under `base`.
+fn rebase(base: &Path, path: &Path) -> PathBuf {
+    base.join(path.strip_prefix("/").unwrap_or(path))
+}
+
+fn bind_mount(source: &Path, target: &Path) -> io::Result<()> {
+    mount(
+        Some(source),
+        target,
+        None,
+        libc::MS_BIND | libc::MS_REC,
+        None,
+    )
+}
+
+fn mount_tmpfs(target: &Path, mode: &str) -> io::Result<()> {
+    mount(
+        Some(Path::new("tmpfs")),
+        target,
+        Some("tmpfs"),
+        libc::MS_NOSUID | libc::MS_NODEV,
+        Some(&format!("mode={mode}")),
+    )
+}
+
+fn mount_proc(target: &Path) -> io::Result<()> {
+    mount(
+        Some(Path::new("proc")),
+        target,
+        Some("proc"),
+        libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+        None,
+    )
+}
+
+fn mount(
+    source: Option<&Path>,
+    target: &Path,
+    fstype: Option<&str>,
+    flags: libc::c_ulong,
+    data: Option<&str>,
+) -> io::Result<()> {
+    let source = source.map(to_cstring).transpose()?;
+    let target_c = to_cstring(target)?;
+    let fstype = fstype.map(CString::new).transpose()?;
+    let data = data.map(CString::new).transpose()?;
+    let rc = unsafe {
+        libc::mount(
+            source.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            target_c.as_ptr(),
+            fstype.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            flags,
+            data.as_ref()
+                .map_or(std::ptr::null(), |s| s.as_ptr().cast::<libc::c_void>()),
+        )
+    };
+    if rc == -1 {
+        let err = io::Error::last_os_error();
+        return Err(io::Error::new(
+            err.kind(),
+            format!("mount {}: {err}", target.display()),
+        ));
+    }
+    Ok(())
+}
+
+fn pivot_root(new_root: &Path, put_old: &Path) -> io::Result<()> {
+    let new_root = to_cstring(new_root)?;
+    let put_old = to_cstring(put_old)?;
+    if unsafe { libc::syscall(libc::SYS_pivot_root, new_root.as_ptr(), put_old.as_ptr()) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn umount_detach(target: &Path) -> io::Result<()> {
+    let target = to_cstring(target)?;
+    if unsafe { libc::umount2(target.as_ptr(), libc::MNT_DETACH) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn to_cstring(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)
+}
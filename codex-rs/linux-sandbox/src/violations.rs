@@ -0,0 +1,917 @@
+//! Reporting of the operations blocked by the Landlock/seccomp sandbox.
+//!
+//! Instead of exec'ing the command directly, the helper forks. The child
+//! applies the sandbox and installs an additional seccomp filter that hands
+//! network syscalls and syscalls that may write to the filesystem to the
+//! parent through a seccomp user-notification listener. The parent acts as a
+//! supervisor: it denies network syscalls and records every denial.
+//! Filesystem writes are always passed on to the kernel, so Landlock alone
+//! enforces the filesystem policy. The supervisor only resolves the target
+//! path itself and reports the writes that Landlock is going to reject; a
+//! mismatch between its resolution and the kernel's can at worst produce a
+//! wrong report, never a wrong denial.
+//!
+//! Once the command exits, the supervisor writes the report as a JSON list of
+//! [`SandboxViolation`]s and exits with the same status as the command.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::error::SandboxErr;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::SandboxViolation;
+use seccompiler::BpfProgram;
+use seccompiler::SeccompAction;
+use seccompiler::SeccompCmpArgLen;
+use seccompiler::SeccompCmpOp;
+use seccompiler::SeccompCondition;
+use seccompiler::SeccompFilter;
+use seccompiler::SeccompRule;
+
+use crate::landlock::apply_filesystem_policy_to_current_thread;
+use crate::landlock::install_network_seccomp_filter_on_current_thread;
+use crate::landlock::landlock_writable_roots;
+use crate::landlock::network_seccomp_rules;
+use crate::landlock::seccomp_target_arch;
+use crate::linux_run_main::exec_command;
+use crate::linux_run_main::exit_like_child;
+use crate::linux_run_main::reap_child;
+use crate::linux_run_main::wait_for_child;
+
+/// seccompiler has no notion of `SECCOMP_RET_USER_NOTIF`, so matching rules
+/// return `SECCOMP_RET_TRACE` with this marker, which is then patched in the
+/// compiled program.
+const NOTIFY_MARKER: u32 = 0x5ec;
+
+/// Upper bound on the number of distinct violations kept in a report.
+const MAX_REPORTED_VIOLATIONS: usize = 64;
+
+/// Matches `PATH_MAX`.
+const MAX_PATH_LEN: usize = 4096;
+
+// The user notification ioctls from `<linux/seccomp.h>`, which libc does not
+// export: `SECCOMP_IOWR(0, struct seccomp_notif)`,
+// `SECCOMP_IOWR(1, struct seccomp_notif_resp)` and `SECCOMP_IOW(2, __u64)`,
+// encoded with the asm-generic `_IOC` layout that x86_64 and aarch64 use.
+const SECCOMP_IOCTL_NOTIF_RECV: libc::Ioctl =
+    seccomp_ioc(IOC_READ | IOC_WRITE, 0, size_of::<libc::seccomp_notif>());
+const SECCOMP_IOCTL_NOTIF_SEND: libc::Ioctl = seccomp_ioc(
+    IOC_READ | IOC_WRITE,
+    1,
+    size_of::<libc::seccomp_notif_resp>(),
+);
+const SECCOMP_IOCTL_NOTIF_ID_VALID: libc::Ioctl = seccomp_ioc(IOC_WRITE, 2, size_of::<u64>());
+
+const IOC_WRITE: u32 = 1;
+const IOC_READ: u32 = 2;
+
+/// `_IOC(dir, SECCOMP_IOC_MAGIC, nr, size)` with `SECCOMP_IOC_MAGIC` `'!'`.
+const fn seccomp_ioc(dir: u32, nr: u32, size: usize) -> libc::Ioctl {
+    ((dir << 30) | ((size as u32) << 16) | ((b'!' as u32) << 8) | nr) as libc::Ioctl
+}
+
+/// Runs `command` under the Landlock/seccomp sandbox and writes the
+/// violations observed while it ran to `report_path`.
+///
+/// Falls back to running the command without a report when the kernel does
+/// not support seccomp user notifications.
+pub(crate) fn run_command_with_violation_report(
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    command: Vec<String>,
+    report_path: &Path,
+) -> ! {
+    let monitor = ViolationMonitor::new(sandbox_policy, sandbox_policy_cwd);
+
+    // The child sends the number of its listener fd over this socket and
+    // waits for an acknowledgement before it execs the command.
+    let mut sockets = [-1; 2];
+    if unsafe {
+        libc::socketpair(
+            libc::AF_UNIX,
+            libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
+            0,
+            sockets.as_mut_ptr(),
+        )
+    } == -1
+    {
+        panic!("socketpair failed: {}", io::Error::last_os_error());
+    }
+    let (supervisor_socket, child_socket) = unsafe {
+        (
+            OwnedFd::from_raw_fd(sockets[0]),
+            OwnedFd::from_raw_fd(sockets[1]),
+        )
+    };
+
+    let supervisor_pid = unsafe { libc::getpid() };
+    match unsafe { libc::fork() } {
+        -1 => panic!("fork failed: {}", io::Error::last_os_error()),
+        0 => {
+            drop(supervisor_socket);
+            if let Err(e) = prepare_monitored_child(
+                sandbox_policy,
+                sandbox_policy_cwd,
+                &monitor,
+                supervisor_pid,
+            ) {
+                panic!("error running landlock: {e:?}");
+            }
+            let listener_fd = match install_notifying_seccomp_filter(&monitor) {
+                Ok(fd) => fd,
+                Err(_) => {
+                    // Keep the sandbox intact and just go without a report.
+                    if !sandbox_policy.has_full_network_access()
+                        && let Err(e) = install_network_seccomp_filter_on_current_thread()
+                    {
+                        panic!("error running landlock: {e:?}");
+                    }
+                    -1
+                }
+            };
+            send_fd_number(&child_socket, listener_fd);
+            wait_for_ack(&child_socket);
+            drop(child_socket);
+            exec_command(command)
+        }
+        child_pid => {
+            drop(child_socket);
+            let listener = receive_listener(&supervisor_socket, child_pid);
+            send_ack(&supervisor_socket);
+            drop(supervisor_socket);
+
+            let Some((listener, pidfd)) = listener else {
+                wait_for_child(child_pid)
+            };
+            let violations = monitor.supervise(&listener, &pidfd);
+            // Reap the child first: a zombie still counts as a user of the
+            // filter.
+            let status = reap_child(child_pid);
+            if listener_has_users(&listener) {
+                monitor.serve_in_background(&listener);
+            }
+            if let Err(e) = write_report(report_path, &violations) {
+                tracing::warn!("failed to write sandbox violation report: {e}");
+            }
+            exit_like_child(status)
+        }
+    }
+}
+
+fn prepare_monitored_child(
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    monitor: &ViolationMonitor,
+    supervisor_pid: libc::pid_t,
+) -> codex_core::error::Result<()> {
+    // Die with the supervisor; a killed supervisor (e.g., on timeout) would
+    // otherwise leave the command running unsupervised.
+    if unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) } == -1 {
+        return Err(io::Error::last_os_error().into());
+    }
+    if unsafe { libc::getppid() } != supervisor_pid {
+        std::process::exit(1);
+    }
+
+    apply_filesystem_policy_to_current_thread(sandbox_policy, sandbox_policy_cwd)?;
+
+    // Required for installing a seccomp filter without CAP_SYS_ADMIN. Landlock
+    // already sets it unless the policy grants full disk write access.
+    if monitor.monitors_anything()
+        && unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } == -1
+    {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Installs a seccomp filter that turns every monitored syscall into a user
+/// notification and returns the listener fd.
+fn install_notifying_seccomp_filter(monitor: &ViolationMonitor) -> io::Result<libc::c_int> {
+    if !monitor.monitors_anything() {
+        return Ok(-1);
+    }
+
+    let mut rules = BTreeMap::new();
+    if monitor.network {
+        rules.extend(network_seccomp_rules().map_err(io::Error::other)?);
+    }
+    if monitor.writable_roots.is_some() {
+        rules.extend(filesystem_write_seccomp_rules().map_err(io::Error::other)?);
+    }
+
+    let filter = SeccompFilter::new(
+        rules,
+        SeccompAction::Allow,
+        SeccompAction::Trace(NOTIFY_MARKER),
+        seccomp_target_arch(),
+    )
+    .map_err(io::Error::other)?;
+    let mut prog: BpfProgram = filter.try_into().map_err(io::Error::other)?;
+    for insn in prog.iter_mut() {
+        if u32::from(insn.code) == libc::BPF_RET | libc::BPF_K
+            && insn.k == libc::SECCOMP_RET_TRACE | NOTIFY_MARKER
+        {
+            insn.k = libc::SECCOMP_RET_USER_NOTIF;
+        }
+    }
+
+    let fprog = libc::sock_fprog {
+        len: prog.len() as libc::c_ushort,
+        filter: prog.as_mut_ptr().cast::<libc::sock_filter>(),
+    };
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+            &fprog as *const libc::sock_fprog,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(fd as libc::c_int)
+}
+
+/// Rules matching the syscalls that create, modify or remove files. `open`
+/// and `openat` only match when called with a flag that implies writing.
+fn filesystem_write_seccomp_rules() -> Result<BTreeMap<i64, Vec<SeccompRule>>, SandboxErr> {
+    let write_flag_rules = |flags_arg: u8| -> Result<Vec<SeccompRule>, SandboxErr> {
+        [libc::O_WRONLY, libc::O_RDWR, libc::O_CREAT, libc::O_TRUNC]
+            .into_iter()
+            .map(|flag| {
+                Ok(SeccompRule::new(vec![SeccompCondition::new(
+                    flags_arg,
+                    SeccompCmpArgLen::Dword,
+                    SeccompCmpOp::MaskedEq(flag as u64),
+                    flag as u64,
+                )?])?)
+            })
+            .collect()
+    };
+
+    let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
+    rules.insert(libc::SYS_openat, write_flag_rules(2)?);
+    for nr in [
+        libc::SYS_mkdirat,
+        libc::SYS_mknodat,
+        libc::SYS_unlinkat,
+        libc::SYS_renameat2,
+        libc::SYS_symlinkat,
+        libc::SYS_linkat,
+        libc::SYS_truncate,
+    ] {
+        rules.insert(nr, vec![]);
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        rules.insert(libc::SYS_open, write_flag_rules(1)?);
+        for nr in [
+            libc::SYS_creat,
+            libc::SYS_mkdir,
+            libc::SYS_mknod,
+            libc::SYS_rmdir,
+            libc::SYS_unlink,
+            libc::SYS_rename,
+            libc::SYS_renameat,
+            libc::SYS_symlink,
+            libc::SYS_link,
+        ] {
+            rules.insert(nr, vec![]);
+        }
+    }
+    Ok(rules)
+}
+
+fn send_fd_number(socket: &OwnedFd, fd: libc::c_int) {
+    let bytes = fd.to_ne_bytes();
+    unsafe {
+        libc::write(
+            socket.as_raw_fd(),
+            bytes.as_ptr().cast::<libc::c_void>(),
+            bytes.len(),
+        );
+    }
+}
+
+fn wait_for_ack(socket: &OwnedFd) {
+    let mut ack = [0u8; 1];
+    unsafe {
+        libc::read(
+            socket.as_raw_fd(),
+            ack.as_mut_ptr().cast::<libc::c_void>(),
+            ack.len(),
+        );
+    }
+}
+
+fn send_ack(socket: &OwnedFd) {
+    let ack = [1u8; 1];
+    unsafe {
+        libc::write(
+            socket.as_raw_fd(),
+            ack.as_ptr().cast::<libc::c_void>(),
+            ack.len(),
+        );
+    }
+}
+
+/// Receives the number of the child's listener fd and duplicates it into the
+/// supervisor. Returns the listener together with a pidfd for the child.
+fn receive_listener(socket: &OwnedFd, child_pid: libc::pid_t) -> Option<(OwnedFd, OwnedFd)> {
+    let mut bytes = [0u8; size_of::<libc::c_int>()];
+    let n = unsafe {
+        libc::read(
+            socket.as_raw_fd(),
+            bytes.as_mut_ptr().cast::<libc::c_void>(),
+            bytes.len(),
+        )
+    };
+    if n != bytes.len() as isize {
+        return None;
+    }
+    let child_fd = libc::c_int::from_ne_bytes(bytes);
+    if child_fd < 0 {
+        return None;
+    }
+
+    let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, child_pid, 0) };
+    if pidfd < 0 {
+        return None;
+    }
+    let pidfd = unsafe { OwnedFd::from_raw_fd(pidfd as libc::c_int) };
+    let listener = unsafe { libc::syscall(libc::SYS_pidfd_getfd, pidfd.as_raw_fd(), child_fd, 0) };
+    if listener < 0 {
+        return None;
+    }
+    let listener = unsafe { OwnedFd::from_raw_fd(listener as libc::c_int) };
+    Some((listener, pidfd))
+}
+
+fn write_report(report_path: &Path, violations: &[SandboxViolation]) -> io::Result<()> {
+    let json = serde_json::to_vec(violations).map_err(io::Error::other)?;
+    std::fs::write(report_path, json)
+}
+
+/// Outcome of a monitored syscall.
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    /// Let the kernel (and Landlock) handle the syscall and report the
+    /// violation Landlock is expected to reject it with, if any.
+    Continue(Option<SandboxViolation>),
+    /// Fail the syscall with the given errno and report the violation, if
+    /// any.
+    Deny(libc::c_int, Option<SandboxViolation>),
+}
+
+impl Verdict {
+    fn into_violation(self) -> Option<SandboxViolation> {
+        match self {
+            Verdict::Continue(violation) | Verdict::Deny(_, violation) => violation,
+        }
+    }
+}
+
+/// A path argument of a syscall that writes to the filesystem.
+struct WriteTarget {
+    /// Directory fd that a relative `path` is resolved against.
+    dirfd: libc::c_int,
+    /// Address of the path in the memory of the calling process.
+    path_ptr: u64,
+    /// Whether the syscall follows a symlink in the last path component.
+    follow_symlinks: bool,
+    access: WriteAccess,
+}
+
+/// How a syscall writes to one of its path arguments. This determines when
+/// the kernel fails the syscall before the sandbox is even consulted (e.g.,
+/// `mkdir` on an existing directory fails with `EEXIST`), in which case the
+/// supervisor must not turn the error into a violation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WriteAccess {
+    /// Creates the path; fails if it already exists.
+    Create,
+    /// Modifies or removes the path; fails if it does not exist.
+    Modify,
+    /// Writes the path whether or not it exists.
+    CreateOrModify,
+}
+
+struct ViolationMonitor {
+    network: bool,
+    /// Canonicalized writable roots, or `None` if the policy allows writing
+    /// everywhere.
+    writable_roots: Option<Vec<PathBuf>>,
+}
+
+impl ViolationMonitor {
+    fn new(sandbox_policy: &SandboxPolicy, sandbox_policy_cwd: &Path) -> Self {
+        let writable_roots = (!sandbox_policy.has_full_disk_write_access()).then(|| {
+            landlock_writable_roots(sandbox_policy, sandbox_policy_cwd)
+                .into_iter()
+                .map(|root| root.canonicalize().unwrap_or(root))
+                .collect()
+        });
+        Self {
+            network: !sandbox_policy.has_full_network_access(),
+            writable_roots,
+        }
+    }
+
+    fn monitors_anything(&self) -> bool {
+        self.network || self.writable_roots.is_some()
+    }
+
+    /// Answers notifications until the child exits and returns the distinct
+    /// violations, in the order they were first observed.
+    fn supervise(&self, listener: &OwnedFd, pidfd: &OwnedFd) -> Vec<SandboxViolation> {
+        let mut violations = Vec::new();
+        loop {
+            let mut fds = [
+                libc::pollfd {
+                    fd: listener.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+                libc::pollfd {
+                    fd: pidfd.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+            ];
+            if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } == -1 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                break;
+            }
+            if fds[0].revents & libc::POLLIN != 0 {
+                if let Some(violation) = self
+                    .handle_notification(listener)
+                    .and_then(Verdict::into_violation)
+                    && violations.len() < MAX_REPORTED_VIOLATIONS
+                    && !violations.contains(&violation)
+                {
+                    violations.push(violation);
+                }
+                continue;
+            }
+            if fds[1].revents != 0 || fds[0].revents & libc::POLLHUP != 0 {
+                break;
+            }
+        }
+        violations
+    }
+
+    /// Processes that the command left running in the background are still
+    /// subject to the filter, and their monitored syscalls would fail with
+    /// `ENOSYS` once the listener is closed. Keep answering them from a child
+    /// that stays in the command's session and process group, so it is
+    /// signalled along with them, and that exits as soon as the last
+    /// filtered process is gone.
+    fn serve_in_background(&self, listener: &OwnedFd) {
+        if unsafe { libc::fork() } != 0 {
+            return;
+        }
+        // Do not hold on to the caller's output pipes, whose readers wait for
+        // every writer to go away.
+        if let Ok(dev_null) = File::options().read(true).write(true).open("/dev/null") {
+            for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+                unsafe {
+                    libc::dup2(dev_null.as_raw_fd(), fd);
+                }
+            }
+        }
+        loop {
+            let mut fds = [libc::pollfd {
+                fd: listener.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            }];
+            if unsafe { libc::poll(fds.as_mut_ptr(), 1, -1) } == -1 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                break;
+            }
+            if fds[0].revents & libc::POLLIN != 0 {
+                self.handle_notification(listener);
+            } else if fds[0].revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+                break;
+            }
+        }
+        std::process::exit(0);
+    }
+
+    /// Receives and answers a single notification. Returns `None` if the
+    /// calling process went away in the meantime.
+    fn handle_notification(&self, listener: &OwnedFd) -> Option<Verdict> {
+        let mut request: libc::seccomp_notif = unsafe { std::mem::zeroed() };
+        if unsafe { libc::ioctl(listener.as_raw_fd(), SECCOMP_IOCTL_NOTIF_RECV, &mut request) }
+            == -1
+        {
+            return None;
+        }
+
+        let verdict = self.decide(listener, &request);
+
+        let mut response: libc::seccomp_notif_resp = unsafe { std::mem::zeroed() };
+        response.id = request.id;
+        match &verdict {
+            Verdict::Continue(_) => response.flags = libc::SECCOMP_USER_NOTIF_FLAG_CONTINUE as u32,
+            Verdict::Deny(errno, _) => response.error = -errno,
+        }
+        unsafe {
+            libc::ioctl(
+                listener.as_raw_fd(),
+                SECCOMP_IOCTL_NOTIF_SEND,
+                &mut response,
+            );
+        }
+        Some(verdict)
+    }
+
+    fn decide(&self, listener: &OwnedFd, request: &libc::seccomp_notif) -> Verdict {
+        let nr = i64::from(request.data.nr);
+        let args = &request.data.args;
+
+        if let Some((syscall, targets)) = write_targets(nr, args) {
+            // Landlock decides whether the write goes through; we only
+            // predict its decision for the report.
+            let Some(writable_roots) = &self.writable_roots else {
+                return Verdict::Continue(None);
+            };
+            for target in targets {
+                let Some(path) = read_target_path(request.pid, &target) else {
+                    continue;
+                };
+                // Make sure the pid was not recycled while we read its memory.
+                if !notification_is_valid(listener, request.id) {
+                    return Verdict::Continue(None);
+                }
+                if kernel_consults_sandbox(&path, &target)
+                    && !is_write_allowed(&path, target.follow_symlinks, writable_roots)
+                {
+                    return Verdict::Continue(Some(SandboxViolation::FileWrite {
+                        syscall: syscall.to_string(),
+                        path,
+                    }));
+                }
+            }
+            return Verdict::Continue(None);
+        }
+
+        let violation = match nr {
+            // Shells and runtimes probe their stdio with these on startup, so
+            // reporting them would only be noise.
+            libc::SYS_getpeername
+            | libc::SYS_getsockname
+            | libc::SYS_getsockopt
+            | libc::SYS_setsockopt => None,
+            libc::SYS_ptrace => Some(SandboxViolation::Syscall {
+                syscall: "ptrace".to_string(),
+            }),
+            _ => Some(SandboxViolation::Network {
+                syscall: syscall_name(nr).to_string(),
+            }),
+        };
+        Verdict::Deny(libc::EPERM, violation)
+    }
+}
+
+fn listener_has_users(listener: &OwnedFd) -> bool {
+    let mut fds = [libc::pollfd {
+        fd: listener.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    }];
+    let ready = unsafe { libc::poll(fds.as_mut_ptr(), 1, 0) };
+    ready == 0 || (ready > 0 && fds[0].revents & libc::POLLHUP == 0)
+}
+
+fn notification_is_valid(listener: &OwnedFd, id: u64) -> bool {
+    let mut id = id;
+    unsafe { libc::ioctl(listener.as_raw_fd(), SECCOMP_IOCTL_NOTIF_ID_VALID, &mut id) == 0 }
+}
+
+/// Returns the name of a monitored syscall and the paths it writes to.
+fn write_targets(nr: i64, args: &[u64; 6]) -> Option<(&'static str, Vec<WriteTarget>)> {
+    use WriteAccess::*;
+
+    let target = |dirfd: u64, path_ptr: u64, follow_symlinks: bool, access| WriteTarget {
+        dirfd: dirfd as libc::c_int,
+        path_ptr,
+        follow_symlinks,
+        access,
+    };
+    let open_target = |dirfd: u64, path_ptr: u64, flags: u64| {
+        let flags = flags as libc::c_int;
+        let access = if flags & libc::O_CREAT == 0 {
+            Modify
+        } else if flags & libc::O_EXCL != 0 {
+            Create
+        } else {
+            CreateOrModify
+        };
+        let follow_symlinks = flags & libc::O_NOFOLLOW == 0 && access != Create;
+        target(dirfd, path_ptr, follow_symlinks, access)
+    };
+    let cwd = libc::AT_FDCWD as u64;
+
+    let targets = match nr {
+        libc::SYS_openat => ("openat", vec![open_target(args[0], args[1], args[2])]),
+        libc::SYS_mkdirat => ("mkdirat", vec![target(args[0], args[1], false, Create)]),
+        libc::SYS_mknodat => ("mknodat", vec![target(args[0], args[1], false, Create)]),
+        libc::SYS_unlinkat => ("unlinkat", vec![target(args[0], args[1], false, Modify)]),
+        libc::SYS_renameat2 => (
+            "renameat2",
+            vec![
+                target(args[0], args[1], false, Modify),
+                target(args[2], args[3], false, CreateOrModify),
+            ],
+        ),
+        libc::SYS_symlinkat => ("symlinkat", vec![target(args[1], args[2], false, Create)]),
+        libc::SYS_linkat => ("linkat", vec![target(args[2], args[3], false, Create)]),
+        libc::SYS_truncate => ("truncate", vec![target(cwd, args[0], true, Modify)]),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_open => ("open", vec![open_target(cwd, args[0], args[1])]),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_creat => ("creat", vec![target(cwd, args[0], true, CreateOrModify)]),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_mkdir => ("mkdir", vec![target(cwd, args[0], false, Create)]),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_mknod => ("mknod", vec![target(cwd, args[0], false, Create)]),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_rmdir => ("rmdir", vec![target(cwd, args[0], false, Modify)]),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_unlink => ("unlink", vec![target(cwd, args[0], false, Modify)]),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_rename => (
+            "rename",
+            vec![
+                target(cwd, args[0], false, Modify),
+                target(cwd, args[1], false, CreateOrModify),
+            ],
+        ),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_renameat => (
+            "renameat",
+            vec![
+                target(args[0], args[1], false, Modify),
+                target(args[2], args[3], false, CreateOrModify),
+            ],
+        ),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_symlink => ("symlink", vec![target(cwd, args[1], false, Create)]),
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_link => ("link", vec![target(cwd, args[1], false, Create)]),
+        _ => return None,
+    };
+    Some(targets)
+}
+
+/// Whether the syscall gets as far as the sandbox checks, as opposed to
+/// failing early because `path` does (not) exist.
+fn kernel_consults_sandbox(path: &Path, target: &WriteTarget) -> bool {
+    let exists = if target.follow_symlinks {
+        path.exists()
+    } else {
+        path.symlink_metadata().is_ok()
+    };
+    match target.access {
+        WriteAccess::Create => !exists,
+        WriteAccess::Modify => exists,
+        WriteAccess::CreateOrModify => true,
+    }
+}
+
+fn syscall_name(nr: i64) -> &'static str {
+    match nr {
+        libc::SYS_connect => "connect",
+        libc::SYS_accept => "accept",
+        libc::SYS_accept4 => "accept4",
+        libc::SYS_bind => "bind",
+        libc::SYS_listen => "listen",
+        libc::SYS_shutdown => "shutdown",
+        libc::SYS_sendto => "sendto",
+        libc::SYS_sendmsg => "sendmsg",
+        libc::SYS_sendmmsg => "sendmmsg",
+        libc::SYS_recvmsg => "recvmsg",
+        libc::SYS_recvmmsg => "recvmmsg",
+        libc::SYS_socket => "socket",
+        libc::SYS_socketpair => "socketpair",
+        _ => "unknown",
+    }
+}
+
+/// Reads the path argument of a syscall from the memory of process `pid` and
+/// makes it absolute.
+fn read_target_path(pid: u32, target: &WriteTarget) -> Option<PathBuf> {
+    let mem = File::open(format!("/proc/{pid}/mem")).ok()?;
+    let mut bytes = Vec::new();
+    let mut addr = target.path_ptr;
+    while bytes.len() < MAX_PATH_LEN {
+        // Never read across a page boundary, which may be unmapped.
+        let mut chunk = vec![0u8; 4096 - (addr % 4096) as usize];
+        let n = mem.read_at(&mut chunk, addr).ok()?;
+        if n == 0 {
+            return None;
+        }
+        if let Some(nul) = chunk[..n].iter().position(|b| *b == 0) {
+            bytes.extend_from_slice(&chunk[..nul]);
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..n]);
+        addr += n as u64;
+    }
+
+    let path = PathBuf::from(std::ffi::OsStr::from_bytes(&bytes));
+    if path.is_absolute() {
+        return Some(path);
+    }
+    let base = if target.dirfd == libc::AT_FDCWD {
+        std::fs::read_link(format!("/proc/{pid}/cwd")).ok()?
+    } else {
+        std::fs::read_link(format!("/proc/{pid}/fd/{}", target.dirfd)).ok()?
+    };
+    Some(base.join(path))
+}
+
+/// Mirrors the Landlock rules: writes are allowed beneath the writable roots.
+/// Paths under `/dev` and `/proc` are left to Landlock since they cannot be
+/// resolved reliably from the supervisor (e.g., `/dev/stdout`).
+fn is_write_allowed(path: &Path, follow_symlinks: bool, writable_roots: &[PathBuf]) -> bool {
+    if path.starts_with("/dev") || path.starts_with("/proc") {
+        return true;
+    }
+    let Some(resolved) = resolve(path, follow_symlinks) else {
+        return true;
+    };
+    writable_roots.iter().any(|root| resolved.starts_with(root))
+}
+
+/// Resolves symlinks in `path`. The last component is only resolved when
+/// `follow_symlinks` is set and it exists.
+fn resolve(path: &Path, follow_symlinks: bool) -> Option<PathBuf> {
+    if follow_symlinks && let Ok(resolved) = path.canonicalize() {
+        return Some(resolved);
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent.canonicalize().ok().map(|p| p.join(name)),
+        _ => path.canonicalize().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    fn target(follow_symlinks: bool, access: WriteAccess) -> WriteTarget {
+        WriteTarget {
+            dirfd: libc::AT_FDCWD,
+            path_ptr: 0,
+            follow_symlinks,
+            access,
+        }
+    }
+
+    #[test]
+    fn notification_ioctls_match_the_kernel_headers() {
+        assert_eq!(SECCOMP_IOCTL_NOTIF_RECV as u32, 0xc050_2100);
+        assert_eq!(SECCOMP_IOCTL_NOTIF_SEND as u32, 0xc018_2101);
+        assert_eq!(SECCOMP_IOCTL_NOTIF_ID_VALID as u32, 0x4008_2102);
+    }
+
+    /// `(path_ptr, follow_symlinks, access)` of each write target.
+    type TargetSummary = Vec<(u64, bool, WriteAccess)>;
+
+    fn summary(nr: i64, args: [u64; 6]) -> Option<(&'static str, TargetSummary)> {
+        write_targets(nr, &args).map(|(syscall, targets)| {
+            let targets = targets
+                .into_iter()
+                .map(|t| (t.path_ptr, t.follow_symlinks, t.access))
+                .collect();
+            (syscall, targets)
+        })
+    }
+
+    #[test]
+    fn open_flags_determine_write_access() {
+        let cwd = libc::AT_FDCWD as u64;
+        let open = |flags: libc::c_int| summary(libc::SYS_openat, [cwd, 7, flags as u64, 0, 0, 0]);
+
+        assert_eq!(
+            open(libc::O_WRONLY),
+            Some(("openat", vec![(7, true, WriteAccess::Modify)]))
+        );
+        assert_eq!(
+            open(libc::O_WRONLY | libc::O_CREAT),
+            Some(("openat", vec![(7, true, WriteAccess::CreateOrModify)]))
+        );
+        assert_eq!(
+            open(libc::O_RDWR | libc::O_CREAT | libc::O_NOFOLLOW),
+            Some(("openat", vec![(7, false, WriteAccess::CreateOrModify)]))
+        );
+        // `O_EXCL` never follows a symlink in the last component.
+        assert_eq!(
+            open(libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL),
+            Some(("openat", vec![(7, false, WriteAccess::Create)]))
+        );
+    }
+
+    #[test]
+    fn rename_writes_both_paths() {
+        let cwd = libc::AT_FDCWD as u64;
+        assert_eq!(
+            summary(libc::SYS_renameat2, [cwd, 1, cwd, 2, 0, 0]),
+            Some((
+                "renameat2",
+                vec![
+                    (1, false, WriteAccess::Modify),
+                    (2, false, WriteAccess::CreateOrModify),
+                ]
+            ))
+        );
+        assert_eq!(
+            summary(libc::SYS_linkat, [cwd, 1, cwd, 2, 0, 0]),
+            Some(("linkat", vec![(2, false, WriteAccess::Create)]))
+        );
+        assert_eq!(summary(libc::SYS_read, [0; 6]), None);
+    }
+
+    #[test]
+    fn kernel_consults_sandbox_only_when_existence_checks_pass() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let existing = dir.path().join("existing");
+        std::fs::write(&existing, "")?;
+        let missing = dir.path().join("missing");
+        let dangling = dir.path().join("dangling");
+        symlink(&missing, &dangling)?;
+
+        assert!(!kernel_consults_sandbox(
+            &existing,
+            &target(false, WriteAccess::Create)
+        ));
+        assert!(kernel_consults_sandbox(
+            &missing,
+            &target(false, WriteAccess::Create)
+        ));
+        assert!(kernel_consults_sandbox(
+            &existing,
+            &target(true, WriteAccess::Modify)
+        ));
+        assert!(!kernel_consults_sandbox(
+            &missing,
+            &target(true, WriteAccess::Modify)
+        ));
+        assert!(kernel_consults_sandbox(
+            &missing,
+            &target(true, WriteAccess::CreateOrModify)
+        ));
+        // A dangling symlink exists as a link, but not as its target.
+        assert!(!kernel_consults_sandbox(
+            &dangling,
+            &target(true, WriteAccess::Modify)
+        ));
+        assert!(kernel_consults_sandbox(
+            &dangling,
+            &target(false, WriteAccess::Modify)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn writes_are_resolved_against_writable_roots() -> io::Result<()> {
+        let root = tempfile::tempdir()?;
+        let outside = tempfile::tempdir()?;
+        let roots = vec![root.path().canonicalize()?];
+        let escape = root.path().join("escape");
+        std::fs::write(outside.path().join("file"), "")?;
+        symlink(outside.path().join("file"), &escape)?;
+
+        assert!(is_write_allowed(&root.path().join("new"), true, &roots));
+        assert!(!is_write_allowed(&outside.path().join("new"), true, &roots));
+        // Writing through the link lands outside; removing the link itself
+        // does not.
+        assert!(!is_write_allowed(&escape, true, &roots));
+        assert!(is_write_allowed(&escape, false, &roots));
+        // Left to Landlock.
+        assert!(is_write_allowed(Path::new("/dev/null"), true, &roots));
+        assert!(is_write_allowed(
+            &outside.path().join("missing/dir/file"),
+            true,
+            &roots
+        ));
+        Ok(())
+    }
+}
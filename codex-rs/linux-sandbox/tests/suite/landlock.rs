@@ -3,6 +3,7 @@ use codex_core::config_types::ShellEnvironmentPolicy;
 use codex_core::error::CodexErr;
 use codex_core::error::SandboxErr;
 use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
 use codex_core::exec::SandboxType;
 use codex_core::exec::process_exec_tool_call;
 use codex_core::exec_env::create_env;
@@ -28,13 +29,21 @@ const NETWORK_TIMEOUT_MS: u64 = 2_000;
 #[cfg(target_arch = "aarch64")]
 const NETWORK_TIMEOUT_MS: u64 = 10_000;
 
+/// Every Linux sandbox backend; each test below runs under all of them.
+const SANDBOX_TYPES: [SandboxType; 2] = [SandboxType::LinuxSeccomp, SandboxType::LinuxNamespaces];
+
 fn create_env_from_core_vars() -> HashMap<String, String> {
     let policy = ShellEnvironmentPolicy::default();
     create_env(&policy)
 }
 
-#[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
-async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
+#[expect(clippy::expect_used)]
+async fn exec_cmd(
+    sandbox_type: SandboxType,
+    cmd: &[&str],
+    writable_roots: &[PathBuf],
+    timeout_ms: u64,
+) -> Result<ExecToolCallOutput, CodexErr> {
     let cwd = std::env::current_dir().expect("cwd should exist");
     let sandbox_cwd = cwd.clone();
     let params = ExecParams {
@@ -57,84 +66,126 @@ async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
     };
     let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
     let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
-    let res = process_exec_tool_call(
+    process_exec_tool_call(
         params,
-        SandboxType::LinuxSeccomp,
+        sandbox_type,
         &sandbox_policy,
         sandbox_cwd.as_path(),
         &codex_linux_sandbox_exe,
         None,
     )
     .await
-    .unwrap();
+}
+
+#[expect(clippy::print_stdout, clippy::unwrap_used)]
+async fn run_cmd(
+    sandbox_type: SandboxType,
+    cmd: &[&str],
+    writable_roots: &[PathBuf],
+    timeout_ms: u64,
+) {
+    let res = exec_cmd(sandbox_type, cmd, writable_roots, timeout_ms)
+        .await
+        .unwrap();
 
     if res.exit_code != 0 {
         println!("stdout:\n{}", res.stdout.text);
         println!("stderr:\n{}", res.stderr.text);
-        panic!("exit code: {}", res.exit_code);
+        panic!("{sandbox_type:?} exit code: {}", res.exit_code);
     }
 }
 
 #[tokio::test]
 async fn test_root_read() {
-    run_cmd(&["ls", "-l", "/bin"], &[], SHORT_TIMEOUT_MS).await;
+    for sandbox_type in SANDBOX_TYPES {
+        run_cmd(sandbox_type, &["ls", "-l", "/bin"], &[], SHORT_TIMEOUT_MS).await;
+    }
 }
 
 #[tokio::test]
-#[should_panic]
 async fn test_root_write() {
-    let tmpfile = NamedTempFile::new().unwrap();
+    // Outside of `/tmp`, which the namespaces backend replaces with a private
+    // tmpfs.
+    let tmpfile = NamedTempFile::new_in(env!("CARGO_TARGET_TMPDIR")).unwrap();
     let tmpfile_path = tmpfile.path().to_string_lossy();
-    run_cmd(
-        &["bash", "-lc", &format!("echo blah > {tmpfile_path}")],
-        &[],
-        SHORT_TIMEOUT_MS,
-    )
-    .await;
+    for sandbox_type in SANDBOX_TYPES {
+        let output = match exec_cmd(
+            sandbox_type,
+            &["bash", "-lc", &format!("echo blah > {tmpfile_path}")],
+            &[],
+            LONG_TIMEOUT_MS,
+        )
+        .await
+        {
+            Ok(output) => output,
+            Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => *output,
+            Err(e) => panic!("{sandbox_type:?}: unexpected exec error: {e:?}"),
+        };
+        assert_ne!(output.exit_code, 0, "{sandbox_type:?} allowed the write");
+        assert_eq!(std::fs::read_to_string(tmpfile.path()).unwrap(), "");
+    }
 }
 
 #[tokio::test]
 async fn test_dev_null_write() {
-    run_cmd(
-        &["bash", "-lc", "echo blah > /dev/null"],
-        &[],
-        // We have seen timeouts when running this test in CI on GitHub,
-        // so we are using a generous timeout until we can diagnose further.
-        LONG_TIMEOUT_MS,
-    )
-    .await;
+    for sandbox_type in SANDBOX_TYPES {
+        run_cmd(
+            sandbox_type,
+            &["bash", "-lc", "echo blah > /dev/null"],
+            &[],
+            // We have seen timeouts when running this test in CI on GitHub,
+            // so we are using a generous timeout until we can diagnose further.
+            LONG_TIMEOUT_MS,
+        )
+        .await;
+    }
 }
 
 #[tokio::test]
 async fn test_writable_root() {
     let tmpdir = tempfile::tempdir().unwrap();
     let file_path = tmpdir.path().join("test");
-    run_cmd(
-        &[
-            "bash",
-            "-lc",
-            &format!("echo blah > {}", file_path.to_string_lossy()),
-        ],
-        &[tmpdir.path().to_path_buf()],
-        // We have seen timeouts when running this test in CI on GitHub,
-        // so we are using a generous timeout until we can diagnose further.
-        LONG_TIMEOUT_MS,
-    )
-    .await;
+    for sandbox_type in SANDBOX_TYPES {
+        run_cmd(
+            sandbox_type,
+            &[
+                "bash",
+                "-lc",
+                &format!("echo blah > {}", file_path.to_string_lossy()),
+            ],
+            &[tmpdir.path().to_path_buf()],
+            // We have seen timeouts when running this test in CI on GitHub,
+            // so we are using a generous timeout until we can diagnose further.
+            LONG_TIMEOUT_MS,
+        )
+        .await;
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "blah\n");
+    }
 }
 
 #[tokio::test]
-#[should_panic(expected = "Sandbox(Timeout")]
 async fn test_timeout() {
-    run_cmd(&["sleep", "2"], &[], 50).await;
+    for sandbox_type in SANDBOX_TYPES {
+        let result = exec_cmd(sandbox_type, &["sleep", "2"], &[], 50).await;
+        assert!(
+            matches!(result, Err(CodexErr::Sandbox(SandboxErr::Timeout { .. }))),
+            "{sandbox_type:?}: expected timeout, got {result:?}"
+        );
+    }
 }
 
-/// Helper that runs `cmd` under the Linux sandbox and asserts that the command
-/// does NOT succeed (i.e. returns a non‑zero exit code) **unless** the binary
-/// is missing in which case we silently treat it as an accepted skip so the
-/// suite remains green on leaner CI images.
-#[expect(clippy::expect_used)]
+/// Helper that runs `cmd` under every Linux sandbox backend and asserts that
+/// the command does NOT succeed (i.e. returns a non‑zero exit code) **unless**
+/// the binary is missing in which case we silently treat it as an accepted
+/// skip so the suite remains green on leaner CI images.
 async fn assert_network_blocked(cmd: &[&str]) {
+    for sandbox_type in SANDBOX_TYPES {
+        assert_network_blocked_with(sandbox_type, cmd).await;
+    }
+}
+
+#[expect(clippy::expect_used)]
+async fn assert_network_blocked_with(sandbox_type: SandboxType, cmd: &[&str]) {
     let cwd = std::env::current_dir().expect("cwd should exist");
     let sandbox_cwd = cwd.clone();
     let params = ExecParams {
@@ -153,7 +204,7 @@ async fn assert_network_blocked(cmd: &[&str]) {
     let codex_linux_sandbox_exe: Option<PathBuf> = Some(PathBuf::from(sandbox_program));
     let result = process_exec_tool_call(
         params,
-        SandboxType::LinuxSeccomp,
+        sandbox_type,
         &sandbox_policy,
         sandbox_cwd.as_path(),
         &codex_linux_sandbox_exe,
@@ -179,7 +230,7 @@ async fn assert_network_blocked(cmd: &[&str]) {
 
     if output.exit_code == 0 {
         panic!(
-            "Network sandbox FAILED - {cmd:?} exited 0\nstdout:\n{}\nstderr:\n{}",
+            "Network sandbox FAILED - {cmd:?} exited 0 under {sandbox_type:?}\nstdout:\n{}\nstderr:\n{}",
             output.stdout.text, output.stderr.text
         );
     }
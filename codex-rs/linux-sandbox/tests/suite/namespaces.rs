@@ -0,0 +1,178 @@
+#![cfg(target_os = "linux")]
+use codex_core::config_types::ShellEnvironmentPolicy;
+use codex_core::error::CodexErr;
+use codex_core::error::SandboxErr;
+use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
+use codex_core::exec::SandboxType;
+use codex_core::exec::process_exec_tool_call;
+use codex_core::exec_env::create_env;
+use codex_core::landlock::CODEX_SANDBOX_VIOLATION_REPORT_ENV_VAR;
+use codex_core::protocol::SandboxPolicy;
+use std::path::PathBuf;
+
+// At least on GitHub CI, the arm64 tests appear to need longer timeouts.
+
+#[cfg(not(target_arch = "aarch64"))]
+const TIMEOUT_MS: u64 = 2_000;
+#[cfg(target_arch = "aarch64")]
+const TIMEOUT_MS: u64 = 10_000;
+
+/// Runs `cmd` with the namespace backend and returns its output, treating a
+/// sandbox denial as a regular (non-zero) result.
+#[expect(clippy::expect_used)]
+async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) -> ExecToolCallOutput {
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let sandbox_cwd = cwd.clone();
+    let params = ExecParams {
+        command: cmd.iter().copied().map(str::to_owned).collect(),
+        cwd,
+        timeout_ms: Some(timeout_ms),
+        env: create_env(&ShellEnvironmentPolicy::default()),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: writable_roots.to_vec(),
+        network_access: false,
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxNamespaces,
+        &sandbox_policy,
+        sandbox_cwd.as_path(),
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await;
+
+    match result {
+        Ok(output) => output,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => *output,
+        Err(e) => panic!("unexpected exec error: {e:?}"),
+    }
+}
+
+/// A directory outside of the sandbox cwd and outside of `/tmp`, which is
+/// replaced by a private tmpfs inside the sandbox.
+#[expect(clippy::expect_used)]
+fn host_tempdir() -> tempfile::TempDir {
+    tempfile::tempdir_in(env!("CARGO_TARGET_TMPDIR")).expect("create tempdir")
+}
+
+#[tokio::test]
+async fn namespaces_writable_root_under_tmp_is_visible() {
+    let tmpdir = tempfile::tempdir().unwrap_or_else(|e| panic!("create tempdir: {e}"));
+    let file_path = tmpdir.path().join("test");
+    let output = run_cmd(
+        &[
+            "bash",
+            "-c",
+            &format!("echo blah > {}", file_path.to_string_lossy()),
+        ],
+        &[tmpdir.path().to_path_buf()],
+        TIMEOUT_MS,
+    )
+    .await;
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+    assert!(file_path.exists());
+}
+
+#[tokio::test]
+async fn namespaces_private_tmp() {
+    let marker = tempfile::NamedTempFile::new().unwrap_or_else(|e| panic!("tempfile: {e}"));
+    let marker_path = marker.path().to_string_lossy().to_string();
+    let output = run_cmd(
+        &[
+            "bash",
+            "-c",
+            &format!("test ! -e {marker_path} && touch /tmp/scratch && test -e /tmp/scratch"),
+        ],
+        &[],
+        TIMEOUT_MS,
+    )
+    .await;
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+    assert!(!std::path::Path::new("/tmp/scratch").exists());
+}
+
+#[tokio::test]
+async fn namespaces_private_proc() {
+    // The command is PID 1 of its own PID namespace.
+    let output = run_cmd(&["bash", "-c", "echo $$"], &[], TIMEOUT_MS).await;
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+    assert_eq!(output.stdout.text.trim(), "1");
+
+    let output = run_cmd(
+        &["bash", "-c", "ls /proc | grep -c '^[0-9]'"],
+        &[],
+        TIMEOUT_MS,
+    )
+    .await;
+    let visible_pids: u32 = output.stdout.text.trim().parse().unwrap_or(u32::MAX);
+    assert!(visible_pids <= 3, "saw {visible_pids} processes in /proc");
+}
+
+#[tokio::test]
+async fn namespaces_timeout_kills_process_tree() {
+    let tmpdir = host_tempdir();
+    let marker = tmpdir.path().join("survivor");
+    let result = process_exec_tool_call(
+        ExecParams {
+            command: vec![
+                "bash".to_string(),
+                "-c".to_string(),
+                format!("(sleep 1 && touch {}) & sleep 5", marker.to_string_lossy()),
+            ],
+            cwd: tmpdir.path().to_path_buf(),
+            timeout_ms: Some(200),
+            env: create_env(&ShellEnvironmentPolicy::default()),
+            with_escalated_permissions: None,
+            justification: None,
+        },
+        SandboxType::LinuxNamespaces,
+        &SandboxPolicy::new_workspace_write_policy(),
+        tmpdir.path(),
+        &Some(PathBuf::from(env!("CARGO_BIN_EXE_codex-linux-sandbox"))),
+        None,
+    )
+    .await;
+    assert!(
+        matches!(result, Err(CodexErr::Sandbox(SandboxErr::Timeout { .. }))),
+        "expected timeout, got {result:?}"
+    );
+
+    // Give an orphaned background job time to (incorrectly) run.
+    tokio::time::sleep(std::time::Duration::from_millis(1_500)).await;
+    assert!(!marker.exists(), "background process outlived the sandbox");
+}
+
+#[test]
+fn namespaces_refuse_violation_reports() {
+    let tmpdir = host_tempdir();
+    let policy =
+        serde_json::to_string(&SandboxPolicy::new_read_only_policy()).expect("serialize policy");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_codex-linux-sandbox"))
+        .arg("--namespaces")
+        .arg(tmpdir.path())
+        .arg(policy)
+        .args(["--", "true"])
+        .env(
+            CODEX_SANDBOX_VIOLATION_REPORT_ENV_VAR,
+            tmpdir.path().join("report.json"),
+        )
+        .output()
+        .expect("run codex-linux-sandbox");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("only supported by the Landlock backend"),
+        "unexpected stderr: {stderr}"
+    );
+}
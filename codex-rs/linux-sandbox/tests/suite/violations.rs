@@ -0,0 +1,148 @@
+#![cfg(target_os = "linux")]
+use codex_core::config_types::ShellEnvironmentPolicy;
+use codex_core::error::CodexErr;
+use codex_core::error::SandboxErr;
+use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
+use codex_core::exec::SandboxType;
+use codex_core::exec::process_exec_tool_call;
+use codex_core::exec_env::create_env;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::SandboxViolation;
+use std::path::Path;
+use std::path::PathBuf;
+
+// At least on GitHub CI, the arm64 tests appear to need longer timeouts.
+
+#[cfg(not(target_arch = "aarch64"))]
+const TIMEOUT_MS: u64 = 2_000;
+#[cfg(target_arch = "aarch64")]
+const TIMEOUT_MS: u64 = 10_000;
+
+/// Runs `cmd` under Landlock with `writable_root` as the only writable root,
+/// treating a sandbox denial as a regular (non-zero) result.
+#[expect(clippy::expect_used)]
+async fn run_cmd(cmd: &str, writable_root: &Path) -> ExecToolCallOutput {
+    let params = ExecParams {
+        command: vec!["bash".to_string(), "-c".to_string(), cmd.to_string()],
+        cwd: writable_root.to_path_buf(),
+        timeout_ms: Some(TIMEOUT_MS),
+        env: create_env(&ShellEnvironmentPolicy::default()),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        network_access: false,
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        &writable_root.canonicalize().expect("canonicalize"),
+        &Some(PathBuf::from(sandbox_program)),
+        None,
+    )
+    .await;
+
+    match result {
+        Ok(output) => output,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => *output,
+        Err(e) => panic!("unexpected exec error: {e:?}"),
+    }
+}
+
+#[expect(clippy::expect_used)]
+fn tempdir() -> tempfile::TempDir {
+    tempfile::tempdir().expect("create tempdir")
+}
+
+#[tokio::test]
+async fn reports_write_outside_writable_roots() {
+    let workspace = tempdir();
+    let outside = tempdir();
+    let forbidden = outside
+        .path()
+        .canonicalize()
+        .unwrap_or_else(|e| panic!("canonicalize: {e}"))
+        .join("forbidden.txt");
+
+    let output = run_cmd(
+        &format!("echo blah > {}", forbidden.to_string_lossy()),
+        workspace.path(),
+    )
+    .await;
+
+    assert_ne!(output.exit_code, 0);
+    assert!(!forbidden.exists());
+    assert_eq!(
+        output.sandbox_violations,
+        vec![SandboxViolation::FileWrite {
+            syscall: "openat".to_string(),
+            path: forbidden,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn reports_blocked_network_access() {
+    let workspace = tempdir();
+    let output = run_cmd("echo hi > /dev/tcp/127.0.0.1/80", workspace.path()).await;
+
+    assert_ne!(output.exit_code, 0);
+    assert!(
+        output
+            .sandbox_violations
+            .iter()
+            .any(|v| matches!(v, SandboxViolation::Network { .. })),
+        "violations: {:?}",
+        output.sandbox_violations
+    );
+}
+
+#[tokio::test]
+async fn allowed_writes_are_not_reported() {
+    let workspace = tempdir();
+    let output = run_cmd(
+        "mkdir sub && echo blah > sub/file && mv sub/file sub/moved && rm -r sub && echo hi > /dev/null",
+        workspace.path(),
+    )
+    .await;
+
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+    assert_eq!(output.sandbox_violations, Vec::new());
+}
+
+#[tokio::test]
+async fn background_processes_keep_writing_after_command_exits() {
+    let workspace = tempdir();
+    let late = workspace.path().join("late.txt");
+    let output = run_cmd(
+        &format!(
+            "(sleep 0.5 && echo done > {}) > /dev/null 2>&1 &",
+            late.to_string_lossy()
+        ),
+        workspace.path(),
+    )
+    .await;
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1_500)).await;
+    assert_eq!(
+        std::fs::read_to_string(&late).ok().as_deref(),
+        Some("done\n")
+    );
+}
+
+#[tokio::test]
+async fn existing_directories_are_not_reported() {
+    let workspace = tempdir();
+    // `mkdir -p` relies on `EEXIST` for directories outside of the workspace.
+    let output = run_cmd("mkdir -p /usr/bin && rm -f /nonexistent", workspace.path()).await;
+
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+    assert_eq!(output.sandbox_violations, Vec::new());
+}
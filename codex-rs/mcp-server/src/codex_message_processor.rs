@@ -9,7 +9,9 @@ use codex_core::ConversationManager;
 use codex_core::Cursor as RolloutCursor;
 use codex_core::NewConversation;
 use codex_core::RolloutRecorder;
-use codex_core::SessionMeta;
+use codex_core::SessionHit;
+use codex_core::SessionQuery;
+use codex_core::apply_linux_sandbox_backend;
 use codex_core::auth::CLIENT_ID;
 use codex_core::auth::get_auth_file;
 use codex_core::auth::login_with_api_key;
@@ -26,16 +28,20 @@ use codex_core::exec::ExecParams;
 use codex_core::exec_env::create_env;
 use codex_core::get_platform_sandbox;
 use codex_core::git_info::git_diff_to_remote;
+use codex_core::parse_date_bound;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::ApprovalSource;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::InputItem as CoreInputItem;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
+use codex_core::worktree::SessionWorktree;
 use codex_login::ServerOptions as LoginServerOptions;
 use codex_login::ShutdownHandle;
 use codex_login::run_login_server;
+use codex_protocol::config_types::WorktreeAction;
 use codex_protocol::mcp_protocol::APPLY_PATCH_APPROVAL_METHOD;
 use codex_protocol::mcp_protocol::AddConversationListenerParams;
 use codex_protocol::mcp_protocol::AddConversationSubscriptionResponse;
@@ -45,13 +51,19 @@ use codex_protocol::mcp_protocol::ArchiveConversationParams;
 use codex_protocol::mcp_protocol::ArchiveConversationResponse;
 use codex_protocol::mcp_protocol::AuthStatusChangeNotification;
 use codex_protocol::mcp_protocol::ClientRequest;
+use codex_protocol::mcp_protocol::ConversationFilter;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::mcp_protocol::ConversationSummary;
+use codex_protocol::mcp_protocol::ConversationWorktree;
 use codex_protocol::mcp_protocol::EXEC_COMMAND_APPROVAL_METHOD;
 use codex_protocol::mcp_protocol::ExecArbitraryCommandResponse;
 use codex_protocol::mcp_protocol::ExecCommandApprovalParams;
 use codex_protocol::mcp_protocol::ExecCommandApprovalResponse;
 use codex_protocol::mcp_protocol::ExecOneOffCommandParams;
+use codex_protocol::mcp_protocol::FinishWorktreeParams;
+use codex_protocol::mcp_protocol::FinishWorktreeResponse;
+use codex_protocol::mcp_protocol::ForkConversationParams;
+use codex_protocol::mcp_protocol::ForkConversationResponse;
 use codex_protocol::mcp_protocol::GetUserAgentResponse;
 use codex_protocol::mcp_protocol::GetUserSavedConfigResponse;
 use codex_protocol::mcp_protocol::GitDiffToRemoteResponse;
@@ -76,11 +88,14 @@ use codex_protocol::mcp_protocol::SendUserTurnResponse;
 use codex_protocol::mcp_protocol::ServerNotification;
 use codex_protocol::mcp_protocol::SetDefaultModelParams;
 use codex_protocol::mcp_protocol::SetDefaultModelResponse;
+use codex_protocol::mcp_protocol::UndoConversationParams;
+use codex_protocol::mcp_protocol::UndoConversationResponse;
 use codex_protocol::mcp_protocol::UserInfoResponse;
 use codex_protocol::mcp_protocol::UserSavedConfig;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::ResponseItem;
 use codex_protocol::protocol::InputMessageKind;
+use codex_protocol::protocol::SessionMetaLine;
 use codex_protocol::protocol::USER_MESSAGE_BEGIN;
 use mcp_types::JSONRPCErrorError;
 use mcp_types::RequestId;
@@ -122,6 +137,16 @@ pub(crate) struct CodexMessageProcessor {
     active_login: Arc<Mutex<Option<ActiveLogin>>>,
     // Queue of pending interrupt requests per conversation. We reply when TurnAborted arrives.
     pending_interrupts: Arc<Mutex<HashMap<ConversationId, Vec<RequestId>>>>,
+    // Worktrees of conversations started with `worktree`, until finished.
+    worktrees: HashMap<ConversationId, PendingWorktree>,
+}
+
+/// A conversation's worktree that the client has not finished yet.
+struct PendingWorktree {
+    worktree: SessionWorktree,
+    /// Applied if the client disconnects first: `worktree.on_exit`, or
+    /// `keep` so that no work is lost.
+    on_disconnect: WorktreeAction,
 }
 
 impl CodexMessageProcessor {
@@ -141,6 +166,7 @@ impl CodexMessageProcessor {
             conversation_listeners: HashMap::new(),
             active_login: Arc::new(Mutex::new(None)),
             pending_interrupts: Arc::new(Mutex::new(HashMap::new())),
+            worktrees: HashMap::new(),
         }
     }
 
@@ -158,6 +184,9 @@ impl CodexMessageProcessor {
             ClientRequest::ResumeConversation { request_id, params } => {
                 self.handle_resume_conversation(request_id, params).await;
             }
+            ClientRequest::ForkConversation { request_id, params } => {
+                self.handle_fork_conversation(request_id, params).await;
+            }
             ClientRequest::ArchiveConversation { request_id, params } => {
                 self.archive_conversation(request_id, params).await;
             }
@@ -170,6 +199,12 @@ impl CodexMessageProcessor {
             ClientRequest::InterruptConversation { request_id, params } => {
                 self.interrupt_conversation(request_id, params).await;
             }
+            ClientRequest::UndoConversation { request_id, params } => {
+                self.undo_conversation(request_id, params).await;
+            }
+            ClientRequest::FinishWorktree { request_id, params } => {
+                self.finish_worktree(request_id, params).await;
+            }
             ClientRequest::AddConversationListener { request_id, params } => {
                 self.add_conversation_listener(request_id, params).await;
             }
@@ -583,7 +618,11 @@ impl CodexMessageProcessor {
             codex_core::protocol::SandboxPolicy::DangerFullAccess => {
                 codex_core::exec::SandboxType::None
             }
-            _ => get_platform_sandbox().unwrap_or(codex_core::exec::SandboxType::None),
+            _ => get_platform_sandbox()
+                .map(|sandbox_type| {
+                    apply_linux_sandbox_backend(sandbox_type, self.config.linux_sandbox_backend)
+                })
+                .unwrap_or(codex_core::exec::SandboxType::None),
         };
         tracing::debug!("Sandbox type: {sandbox_type:?}");
         let codex_linux_sandbox_exe = self.config.codex_linux_sandbox_exe.clone();
@@ -622,20 +661,63 @@ impl CodexMessageProcessor {
         });
     }
 
-    async fn process_new_conversation(&self, request_id: RequestId, params: NewConversationParams) {
-        let config = match derive_config_from_params(params, self.codex_linux_sandbox_exe.clone()) {
-            Ok(config) => config,
-            Err(err) => {
-                let error = JSONRPCErrorError {
-                    code: INVALID_REQUEST_ERROR_CODE,
-                    message: format!("error deriving config: {err}"),
-                    data: None,
-                };
-                self.outgoing.send_error(request_id, error).await;
-                return;
+    async fn process_new_conversation(
+        &mut self,
+        request_id: RequestId,
+        params: NewConversationParams,
+    ) {
+        let mut config =
+            match derive_config_from_params(params, self.codex_linux_sandbox_exe.clone()) {
+                Ok(config) => config,
+                Err(err) => {
+                    let error = JSONRPCErrorError {
+                        code: INVALID_REQUEST_ERROR_CODE,
+                        message: format!("error deriving config: {err}"),
+                        data: None,
+                    };
+                    self.outgoing.send_error(request_id, error).await;
+                    return;
+                }
+            };
+
+        let worktree = if config.worktree.enabled {
+            let entered = tokio::task::spawn_blocking(move || {
+                let worktree = SessionWorktree::enter(&mut config).map_err(|err| err.to_string());
+                (config, worktree)
+            })
+            .await;
+            let worktree = match entered {
+                Ok((entered_config, worktree)) => {
+                    config = entered_config;
+                    worktree
+                }
+                Err(err) => {
+                    let error = JSONRPCErrorError {
+                        code: INTERNAL_ERROR_CODE,
+                        message: format!("worktree task failed: {err}"),
+                        data: None,
+                    };
+                    self.outgoing.send_error(request_id, error).await;
+                    return;
+                }
+            };
+            match worktree {
+                Ok(worktree) => Some(worktree),
+                Err(err) => {
+                    let error = JSONRPCErrorError {
+                        code: INVALID_REQUEST_ERROR_CODE,
+                        message: format!("error creating worktree: {err}"),
+                        data: None,
+                    };
+                    self.outgoing.send_error(request_id, error).await;
+                    return;
+                }
             }
+        } else {
+            None
         };
 
+        let on_disconnect = config.worktree.on_exit.unwrap_or(WorktreeAction::Keep);
         match self.conversation_manager.new_conversation(config).await {
             Ok(conversation_id) => {
                 let NewConversation {
@@ -648,10 +730,31 @@ impl CodexMessageProcessor {
                     model: session_configured.model,
                     reasoning_effort: session_configured.reasoning_effort,
                     rollout_path: session_configured.rollout_path,
+                    worktree: worktree.as_ref().map(|worktree| ConversationWorktree {
+                        cwd: worktree.cwd(),
+                        branch: worktree.branch().to_string(),
+                    }),
                 };
+                if let Some(worktree) = worktree {
+                    self.worktrees.insert(
+                        conversation_id,
+                        PendingWorktree {
+                            worktree,
+                            on_disconnect,
+                        },
+                    );
+                }
                 self.outgoing.send_response(request_id, response).await;
             }
             Err(err) => {
+                if let Some(worktree) = worktree
+                    && let Err(err) = finish_in_background(&worktree, WorktreeAction::Discard).await
+                {
+                    warn!(
+                        "failed to remove worktree {}: {err}",
+                        worktree.path().display()
+                    );
+                }
                 let error = JSONRPCErrorError {
                     code: INTERNAL_ERROR_CODE,
                     message: format!("error creating conversation: {err}"),
@@ -668,6 +771,11 @@ impl CodexMessageProcessor {
         params: ListConversationsParams,
     ) {
         let page_size = params.page_size.unwrap_or(25);
+        if let Some(filter) = params.filter {
+            self.search_conversations(request_id, filter, page_size, params.cursor)
+                .await;
+            return;
+        }
         // Decode the optional cursor string to a Cursor via serde (Cursor implements Deserialize from string)
         let cursor_obj: Option<RolloutCursor> = match params.cursor {
             Some(s) => serde_json::from_str::<RolloutCursor>(&format!("\"{s}\"")).ok(),
@@ -713,24 +821,36 @@ impl CodexMessageProcessor {
         self.outgoing.send_response(request_id, response).await;
     }
 
-    async fn handle_resume_conversation(
+    /// Serve a filtered `listConversations` request from the session index.
+    /// The cursor is the number of matches already returned.
+    async fn search_conversations(
         &self,
         request_id: RequestId,
-        params: ResumeConversationParams,
+        filter: ConversationFilter,
+        page_size: usize,
+        cursor: Option<String>,
     ) {
-        // Derive a Config using the same logic as new conversation, honoring overrides if provided.
-        let config = match params.overrides {
-            Some(overrides) => {
-                derive_config_from_params(overrides, self.codex_linux_sandbox_exe.clone())
+        let query = match session_query_from_filter(filter, page_size, cursor.as_deref()) {
+            Ok(query) => query,
+            Err(message) => {
+                let error = JSONRPCErrorError {
+                    code: INVALID_REQUEST_ERROR_CODE,
+                    message,
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+                return;
             }
-            None => Ok(self.config.as_ref().clone()),
         };
-        let config = match config {
-            Ok(cfg) => cfg,
+        let offset = query.offset;
+
+        let hits = match RolloutRecorder::search_conversations(&self.config.codex_home, query).await
+        {
+            Ok(hits) => hits,
             Err(err) => {
                 let error = JSONRPCErrorError {
-                    code: INVALID_REQUEST_ERROR_CODE,
-                    message: format!("error deriving config: {err}"),
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("failed to search conversations: {err}"),
                     data: None,
                 };
                 self.outgoing.send_error(request_id, error).await;
@@ -738,6 +858,25 @@ impl CodexMessageProcessor {
             }
         };
 
+        let next_cursor = (hits.len() == page_size).then(|| (offset + hits.len()).to_string());
+        let items = hits.into_iter().map(session_hit_to_summary).collect();
+        let response = ListConversationsResponse { items, next_cursor };
+        self.outgoing.send_response(request_id, response).await;
+    }
+
+    async fn handle_resume_conversation(
+        &self,
+        request_id: RequestId,
+        params: ResumeConversationParams,
+    ) {
+        let config = match self.config_with_overrides(params.overrides) {
+            Ok(cfg) => cfg,
+            Err(error) => {
+                self.outgoing.send_error(request_id, error).await;
+                return;
+            }
+        };
+
         match self
             .conversation_manager
             .resume_conversation_from_rollout(
@@ -757,24 +896,12 @@ impl CodexMessageProcessor {
                     msg: EventMsg::SessionConfigured(session_configured.clone()),
                 };
                 self.outgoing.send_event_as_notification(&event, None).await;
-                let initial_messages = session_configured.initial_messages.map(|msgs| {
-                    msgs.into_iter()
-                        .filter(|event| {
-                            // Don't send non-plain user messages (like user instructions
-                            // or environment context) back so they don't get rendered.
-                            if let EventMsg::UserMessage(user_message) = event {
-                                return matches!(user_message.kind, Some(InputMessageKind::Plain));
-                            }
-                            true
-                        })
-                        .collect()
-                });
 
                 // Reply with conversation id + model and initial messages (when present)
                 let response = codex_protocol::mcp_protocol::ResumeConversationResponse {
                     conversation_id,
                     model: session_configured.model.clone(),
-                    initial_messages,
+                    initial_messages: plain_initial_messages(session_configured.initial_messages),
                 };
                 self.outgoing.send_response(request_id, response).await;
             }
@@ -789,6 +916,78 @@ impl CodexMessageProcessor {
         }
     }
 
+    async fn handle_fork_conversation(
+        &self,
+        request_id: RequestId,
+        params: ForkConversationParams,
+    ) {
+        let ForkConversationParams {
+            path,
+            nth_user_message,
+            name,
+            overrides,
+        } = params;
+        let config = match self.config_with_overrides(overrides) {
+            Ok(cfg) => cfg,
+            Err(error) => {
+                self.outgoing.send_error(request_id, error).await;
+                return;
+            }
+        };
+
+        match self
+            .conversation_manager
+            .fork_conversation(nth_user_message, name, config, path)
+            .await
+        {
+            Ok(NewConversation {
+                conversation_id,
+                session_configured,
+                ..
+            }) => {
+                let event = Event {
+                    id: "".to_string(),
+                    msg: EventMsg::SessionConfigured(session_configured.clone()),
+                };
+                self.outgoing.send_event_as_notification(&event, None).await;
+
+                let response = ForkConversationResponse {
+                    conversation_id,
+                    model: session_configured.model.clone(),
+                    initial_messages: plain_initial_messages(session_configured.initial_messages),
+                };
+                self.outgoing.send_response(request_id, response).await;
+            }
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("error forking conversation: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
+    /// Derive a Config using the same logic as new conversation, honoring
+    /// overrides if provided.
+    fn config_with_overrides(
+        &self,
+        overrides: Option<NewConversationParams>,
+    ) -> Result<Config, JSONRPCErrorError> {
+        let config = match overrides {
+            Some(overrides) => {
+                derive_config_from_params(overrides, self.codex_linux_sandbox_exe.clone())
+            }
+            None => Ok(self.config.as_ref().clone()),
+        };
+        config.map_err(|err| JSONRPCErrorError {
+            code: INVALID_REQUEST_ERROR_CODE,
+            message: format!("error deriving config: {err}"),
+            data: None,
+        })
+    }
+
     async fn archive_conversation(&self, request_id: RequestId, params: ArchiveConversationParams) {
         let ArchiveConversationParams {
             conversation_id,
@@ -852,48 +1051,7 @@ impl CodexMessageProcessor {
             .await;
         if let Some(conversation) = removed_conversation {
             info!("conversation {conversation_id} was active; shutting down");
-            let conversation_clone = conversation.clone();
-            let notify = Arc::new(tokio::sync::Notify::new());
-            let notify_clone = notify.clone();
-
-            // Establish the listener for ShutdownComplete before submitting
-            // Shutdown so it is not missed.
-            let is_shutdown = tokio::spawn(async move {
-                loop {
-                    select! {
-                        _ = notify_clone.notified() => {
-                            break;
-                        }
-                        event = conversation_clone.next_event() => {
-                            if let Ok(event) = event && matches!(event.msg, EventMsg::ShutdownComplete) {
-                                break;
-                            }
-                        }
-                    }
-                }
-            });
-
-            // Request shutdown.
-            match conversation.submit(Op::Shutdown).await {
-                Ok(_) => {
-                    // Successfully submitted Shutdown; wait before proceeding.
-                    select! {
-                        _ = is_shutdown => {
-                            // Normal shutdown: proceed with archive.
-                        }
-                        _ = tokio::time::sleep(Duration::from_secs(10)) => {
-                            warn!("conversation {conversation_id} shutdown timed out; proceeding with archive");
-                            notify.notify_one();
-                        }
-                    }
-                }
-                Err(err) => {
-                    error!("failed to submit Shutdown to conversation {conversation_id}: {err}");
-                    notify.notify_one();
-                    // Perhaps we lost a shutdown race, so let's continue to
-                    // clean up the .jsonl file.
-                }
-            }
+            shutdown_conversation(conversation_id, conversation).await;
         }
 
         // Move the .jsonl file to the archived sessions subdir.
@@ -1048,6 +1206,108 @@ impl CodexMessageProcessor {
         let _ = conversation.submit(Op::Interrupt).await;
     }
 
+    async fn undo_conversation(&self, request_id: RequestId, params: UndoConversationParams) {
+        let UndoConversationParams {
+            conversation_id,
+            turns,
+            checkpoint_id,
+        } = params;
+        let Ok(conversation) = self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+        else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+
+        let op = match checkpoint_id {
+            Some(id) => Op::RestoreCheckpoint { id },
+            None => Op::Undo {
+                turns: turns.unwrap_or(1),
+            },
+        };
+        let _ = conversation.submit(op).await;
+
+        self.outgoing
+            .send_response(request_id, UndoConversationResponse {})
+            .await;
+    }
+
+    async fn finish_worktree(&mut self, request_id: RequestId, params: FinishWorktreeParams) {
+        let FinishWorktreeParams {
+            conversation_id,
+            action,
+        } = params;
+        let Some(pending) = self.worktrees.remove(&conversation_id) else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation has no worktree: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+
+        // Every action removes the worktree checkout, so a conversation
+        // still running in it is shut down first.
+        if let Some(conversation) = self
+            .conversation_manager
+            .remove_conversation(&conversation_id)
+            .await
+        {
+            info!(
+                "conversation {conversation_id} is active; shutting down before finishing its worktree"
+            );
+            shutdown_conversation(conversation_id, conversation).await;
+        }
+
+        match finish_in_background(&pending.worktree, action).await {
+            Ok(()) => {
+                self.outgoing
+                    .send_response(request_id, FinishWorktreeResponse {})
+                    .await;
+            }
+            Err(err) => {
+                // The worktree is left in place, so the request can be retried,
+                // e.g. with `keep` after a failed merge.
+                self.worktrees.insert(conversation_id, pending);
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("error finishing worktree: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
+    /// Called once the client has disconnected: shut down the conversations
+    /// whose worktree was never finished and apply their `on_disconnect`
+    /// action.
+    pub(crate) async fn shutdown(&mut self) {
+        for (conversation_id, pending) in std::mem::take(&mut self.worktrees) {
+            if let Some(conversation) = self
+                .conversation_manager
+                .remove_conversation(&conversation_id)
+                .await
+            {
+                shutdown_conversation(conversation_id, conversation).await;
+            }
+            if let Err(err) = finish_in_background(&pending.worktree, pending.on_disconnect).await {
+                warn!(
+                    "failed to finish worktree {} of conversation {conversation_id}: {err}",
+                    pending.worktree.path().display()
+                );
+            }
+        }
+    }
+
     async fn add_conversation_listener(
         &mut self,
         request_id: RequestId,
@@ -1257,6 +1517,7 @@ fn derive_config_from_params(
         base_instructions,
         include_plan_tool,
         include_apply_patch_tool,
+        worktree,
     } = params;
     let overrides = ConfigOverrides {
         model,
@@ -1281,7 +1542,69 @@ fn derive_config_from_params(
         .map(|(k, v)| (k, json_to_toml(v)))
         .collect();
 
-    Config::load_with_cli_overrides(cli_overrides, overrides)
+    let mut config = Config::load_with_cli_overrides(cli_overrides, overrides)?;
+    if let Some(enabled) = worktree {
+        config.worktree.enabled = enabled;
+    }
+    Ok(config)
+}
+
+/// Shut `conversation` down and wait, for up to ten seconds, until it has.
+async fn shutdown_conversation(
+    conversation_id: ConversationId,
+    conversation: Arc<CodexConversation>,
+) {
+    let conversation_clone = conversation.clone();
+    let notify = Arc::new(tokio::sync::Notify::new());
+    let notify_clone = notify.clone();
+
+    // Establish the listener for ShutdownComplete before submitting
+    // Shutdown so it is not missed.
+    let is_shutdown = tokio::spawn(async move {
+        loop {
+            select! {
+                _ = notify_clone.notified() => {
+                    break;
+                }
+                event = conversation_clone.next_event() => {
+                    if let Ok(event) = event && matches!(event.msg, EventMsg::ShutdownComplete) {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // Request shutdown.
+    match conversation.submit(Op::Shutdown).await {
+        Ok(_) => {
+            // Successfully submitted Shutdown; wait before proceeding.
+            select! {
+                _ = is_shutdown => {}
+                _ = tokio::time::sleep(Duration::from_secs(10)) => {
+                    warn!("conversation {conversation_id} shutdown timed out; proceeding anyway");
+                    notify.notify_one();
+                }
+            }
+        }
+        Err(err) => {
+            // Perhaps we lost a shutdown race, so let's continue.
+            error!("failed to submit Shutdown to conversation {conversation_id}: {err}");
+            notify.notify_one();
+        }
+    }
+}
+
+/// Apply `action` to `worktree` on the blocking pool, since it runs git.
+async fn finish_in_background(
+    worktree: &SessionWorktree,
+    action: WorktreeAction,
+) -> Result<(), String> {
+    let worktree = worktree.clone();
+    tokio::task::spawn_blocking(move || worktree.finish(action))
+        .await
+        .map_err(|err| format!("worktree task failed: {err}"))?
+        .map_err(|err| err.to_string())
 }
 
 async fn on_patch_approval_response(
@@ -1298,6 +1621,7 @@ async fn on_patch_approval_response(
                 .submit(Op::PatchApproval {
                     id: event_id.clone(),
                     decision: ReviewDecision::Denied,
+                    source: ApprovalSource::AppServer,
                 })
                 .await
             {
@@ -1319,6 +1643,7 @@ async fn on_patch_approval_response(
         .submit(Op::PatchApproval {
             id: event_id,
             decision: response.decision,
+            source: ApprovalSource::AppServer,
         })
         .await
     {
@@ -1355,6 +1680,7 @@ async fn on_exec_approval_response(
         .submit(Op::ExecApproval {
             id: event_id,
             decision: response.decision,
+            source: ApprovalSource::AppServer,
         })
         .await
     {
@@ -1362,12 +1688,64 @@ async fn on_exec_approval_response(
     }
 }
 
+fn session_query_from_filter(
+    filter: ConversationFilter,
+    page_size: usize,
+    cursor: Option<&str>,
+) -> Result<SessionQuery, String> {
+    let offset = match cursor {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .map_err(|_| format!("invalid cursor: {cursor}"))?,
+        None => 0,
+    };
+    Ok(SessionQuery {
+        text: filter.text,
+        cwd: filter.cwd,
+        git_branch: filter.git_branch,
+        model: filter.model,
+        since: filter.since.as_deref().map(parse_date_bound).transpose()?,
+        until: filter.until.as_deref().map(parse_date_bound).transpose()?,
+        limit: page_size,
+        offset,
+    })
+}
+
+/// Don't send non-plain user messages (like user instructions or environment
+/// context) back so they don't get rendered.
+fn plain_initial_messages(messages: Option<Vec<EventMsg>>) -> Option<Vec<EventMsg>> {
+    messages.map(|msgs| {
+        msgs.into_iter()
+            .filter(|event| {
+                if let EventMsg::UserMessage(user_message) = event {
+                    return matches!(user_message.kind, Some(InputMessageKind::Plain));
+                }
+                true
+            })
+            .collect()
+    })
+}
+
+fn session_hit_to_summary(hit: SessionHit) -> ConversationSummary {
+    ConversationSummary {
+        conversation_id: hit.conversation_id,
+        path: hit.path,
+        preview: hit.preview,
+        timestamp: hit.timestamp.filter(|ts| !ts.is_empty()),
+        fork: None,
+    }
+}
+
 fn extract_conversation_summary(
     path: PathBuf,
     head: &[serde_json::Value],
 ) -> Option<ConversationSummary> {
-    let session_meta = match head.first() {
-        Some(first_line) => serde_json::from_value::<SessionMeta>(first_line.clone()).ok()?,
+    let SessionMetaLine {
+        meta: session_meta,
+        fork,
+        ..
+    } = match head.first() {
+        Some(first_line) => serde_json::from_value::<SessionMetaLine>(first_line.clone()).ok()?,
         None => return None,
     };
 
@@ -1405,6 +1783,7 @@ fn extract_conversation_summary(
         timestamp,
         path,
         preview: preview.to_string(),
+        fork,
     })
 }
 
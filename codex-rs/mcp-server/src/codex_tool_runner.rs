@@ -276,12 +276,20 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::WebSearchEnd(_)
                     | EventMsg::GetHistoryEntryResponse(_)
                     | EventMsg::PlanUpdate(_)
+                    | EventMsg::DelegateBegin(_)
+                    | EventMsg::DelegateEvent(_)
+                    | EventMsg::DelegateEnd(_)
                     | EventMsg::TurnAborted(_)
+                    | EventMsg::BudgetExceeded(_)
+                    | EventMsg::OutputSchemaMismatch(_)
                     | EventMsg::ConversationPath(_)
                     | EventMsg::UserMessage(_)
                     | EventMsg::ShutdownComplete
                     | EventMsg::EnteredReviewMode(_)
-                    | EventMsg::ExitedReviewMode(_) => {
+                    | EventMsg::ExitedReviewMode(_)
+                    | EventMsg::CheckpointRestored(_)
+                    | EventMsg::TurnCommitted(_)
+                    | EventMsg::PullRequestOpened(_) => {
                         // For now, we do not do anything extra for these
                         // events. Note that
                         // send(codex_event_to_notification(&event)) above has
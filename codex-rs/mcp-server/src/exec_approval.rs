@@ -2,6 +2,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use codex_core::CodexConversation;
+use codex_core::protocol::ApprovalSource;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
 use mcp_types::ElicitRequest;
@@ -141,6 +142,7 @@ async fn on_exec_approval_response(
         .submit(Op::ExecApproval {
             id: event_id,
             decision: response.decision,
+            source: ApprovalSource::McpElicitation,
         })
         .await
     {
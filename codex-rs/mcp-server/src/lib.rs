@@ -115,6 +115,8 @@ pub async fn run_main(
                 }
             }
 
+            // stdin was closed: the client is gone.
+            processor.shutdown().await;
             info!("processor task exited (channel closed)");
         }
     });
@@ -74,6 +74,11 @@ impl MessageProcessor {
         }
     }
 
+    /// Release what the client left behind once it has disconnected.
+    pub(crate) async fn shutdown(&mut self) {
+        self.codex_message_processor.shutdown().await;
+    }
+
     pub(crate) async fn process_request(&mut self, request: JSONRPCRequest) {
         if let Ok(request_json) = serde_json::to_value(request.clone())
             && let Ok(codex_request) = serde_json::from_value::<ClientRequest>(request_json)
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use codex_core::CodexConversation;
+use codex_core::protocol::ApprovalSource;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
@@ -122,6 +123,7 @@ pub(crate) async fn on_patch_approval_response(
                 .submit(Op::PatchApproval {
                     id: event_id.clone(),
                     decision: ReviewDecision::Denied,
+                    source: ApprovalSource::McpElicitation,
                 })
                 .await
             {
@@ -142,6 +144,7 @@ pub(crate) async fn on_patch_approval_response(
         .submit(Op::PatchApproval {
             id: event_id,
             decision: response.decision,
+            source: ApprovalSource::McpElicitation,
         })
         .await
     {
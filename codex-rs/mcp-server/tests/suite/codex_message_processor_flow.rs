@@ -92,6 +92,7 @@ async fn test_codex_jsonrpc_conversation_flow() {
         model,
         reasoning_effort: _,
         rollout_path: _,
+        worktree: _,
     } = new_conv_resp;
     assert_eq!(model, "mock-model");
 
@@ -1,11 +1,17 @@
 use std::fs;
 use std::path::Path;
 
+use codex_protocol::mcp_protocol::ConversationFilter;
+use codex_protocol::mcp_protocol::ConversationSummary;
+use codex_protocol::mcp_protocol::ForkConversationParams;
+use codex_protocol::mcp_protocol::ForkConversationResponse;
 use codex_protocol::mcp_protocol::ListConversationsParams;
 use codex_protocol::mcp_protocol::ListConversationsResponse;
 use codex_protocol::mcp_protocol::NewConversationParams; // reused for overrides shape
 use codex_protocol::mcp_protocol::ResumeConversationParams;
 use codex_protocol::mcp_protocol::ResumeConversationResponse;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::SessionFork;
 use mcp_test_support::McpProcess;
 use mcp_test_support::to_response;
 use mcp_types::JSONRPCNotification;
@@ -55,6 +61,7 @@ async fn test_list_and_resume_conversations() {
         .send_list_conversations_request(ListConversationsParams {
             page_size: Some(2),
             cursor: None,
+            filter: None,
         })
         .await
         .expect("send listConversations");
@@ -80,6 +87,7 @@ async fn test_list_and_resume_conversations() {
         .send_list_conversations_request(ListConversationsParams {
             page_size: Some(2),
             cursor: next_cursor,
+            filter: None,
         })
         .await
         .expect("send listConversations page 2");
@@ -145,6 +153,191 @@ async fn test_list_and_resume_conversations() {
     assert!(!conversation_id.to_string().is_empty());
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_list_conversations_with_filter() {
+    let codex_home = TempDir::new().expect("create temp dir");
+    create_fake_rollout(
+        codex_home.path(),
+        "2025-01-02T12-00-00",
+        "2025-01-02T12:00:00Z",
+        "Fix the auth token refresh bug",
+    );
+    create_fake_rollout(
+        codex_home.path(),
+        "2025-01-01T12-00-00",
+        "2025-01-01T12:00:00Z",
+        "Add a dark mode toggle",
+    );
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timeout")
+        .expect("init failed");
+
+    let req_id = mcp
+        .send_list_conversations_request(ListConversationsParams {
+            page_size: Some(10),
+            cursor: None,
+            filter: Some(ConversationFilter {
+                text: Some("auth bug".to_string()),
+                ..Default::default()
+            }),
+        })
+        .await
+        .expect("send listConversations");
+    let resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(req_id)),
+    )
+    .await
+    .expect("listConversations timeout")
+    .expect("listConversations resp");
+    let ListConversationsResponse { items, next_cursor } =
+        to_response::<ListConversationsResponse>(resp).expect("deserialize response");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].preview, "Fix the auth token refresh bug");
+    assert_eq!(next_cursor, None);
+
+    let req_id = mcp
+        .send_list_conversations_request(ListConversationsParams {
+            page_size: Some(10),
+            cursor: None,
+            filter: Some(ConversationFilter {
+                until: Some("2025-01-02".to_string()),
+                ..Default::default()
+            }),
+        })
+        .await
+        .expect("send listConversations");
+    let resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(req_id)),
+    )
+    .await
+    .expect("listConversations timeout")
+    .expect("listConversations resp");
+    let ListConversationsResponse { items, .. } =
+        to_response::<ListConversationsResponse>(resp).expect("deserialize response");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].preview, "Add a dark mode toggle");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_fork_conversation_records_lineage() {
+    let codex_home = TempDir::new().expect("create temp dir");
+    create_fake_rollout(
+        codex_home.path(),
+        "2025-01-01T12-00-00",
+        "2025-01-01T12:00:00Z",
+        "First question",
+    );
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timeout")
+        .expect("init failed");
+
+    let parent = list_all(&mut mcp).await.remove(0);
+    append_user_message(&parent.path, "Second question");
+
+    let fork_req_id = mcp
+        .send_fork_conversation_request(ForkConversationParams {
+            path: parent.path.clone(),
+            nth_user_message: 1,
+            name: Some("retry".to_string()),
+            overrides: None,
+        })
+        .await
+        .expect("send forkConversation");
+    let fork_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(fork_req_id)),
+    )
+    .await
+    .expect("forkConversation timeout")
+    .expect("forkConversation resp");
+    let ForkConversationResponse {
+        conversation_id,
+        initial_messages,
+        ..
+    } = to_response::<ForkConversationResponse>(fork_resp)
+        .expect("deserialize forkConversation response");
+    assert_ne!(conversation_id, parent.conversation_id);
+    let user_messages: Vec<String> = initial_messages
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|msg| match msg {
+            EventMsg::UserMessage(user_message) => Some(user_message.message),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(user_messages, vec!["First question".to_string()]);
+
+    // The session metadata is written by the recorder in the background.
+    let fork = timeout(DEFAULT_READ_TIMEOUT, async {
+        loop {
+            if let Some(fork) = list_all(&mut mcp)
+                .await
+                .into_iter()
+                .find(|item| item.conversation_id == conversation_id)
+            {
+                break fork;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("forked conversation is listed");
+    assert_eq!(
+        fork.fork,
+        Some(SessionFork {
+            parent_id: parent.conversation_id,
+            nth_user_message: 1,
+            name: Some("retry".to_string()),
+        })
+    );
+}
+
+#[expect(clippy::expect_used)]
+async fn list_all(mcp: &mut McpProcess) -> Vec<ConversationSummary> {
+    let req_id = mcp
+        .send_list_conversations_request(ListConversationsParams::default())
+        .await
+        .expect("send listConversations");
+    let resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(req_id)),
+    )
+    .await
+    .expect("listConversations timeout")
+    .expect("listConversations resp");
+    to_response::<ListConversationsResponse>(resp)
+        .expect("deserialize response")
+        .items
+}
+
+#[expect(clippy::expect_used)]
+fn append_user_message(path: &Path, text: &str) {
+    let line = json!({
+        "timestamp": "2025-01-01T12:01:00Z",
+        "type": "response_item",
+        "payload": {
+            "type": "message",
+            "role": "user",
+            "content": [{"type": "input_text", "text": text}]
+        }
+    });
+    let mut contents = fs::read_to_string(path).expect("read rollout file");
+    contents.push_str(&format!("{line}\n"));
+    fs::write(path, contents).expect("write rollout file");
+}
+
 fn create_fake_rollout(codex_home: &Path, filename_ts: &str, meta_rfc3339: &str, preview: &str) {
     let uuid = Uuid::new_v4();
     // sessions/YYYY/MM/DD/ derived from filename_ts (YYYY-MM-DDThh-mm-ss)
@@ -12,3 +12,4 @@ mod send_message;
 mod set_default_model;
 mod user_agent;
 mod user_info;
+mod worktree;
@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::process::Command;
+
+use codex_protocol::config_types::WorktreeAction;
+use codex_protocol::mcp_protocol::ConversationWorktree;
+use codex_protocol::mcp_protocol::FinishWorktreeParams;
+use codex_protocol::mcp_protocol::FinishWorktreeResponse;
+use codex_protocol::mcp_protocol::NewConversationParams;
+use codex_protocol::mcp_protocol::NewConversationResponse;
+use mcp_test_support::McpProcess;
+use mcp_test_support::to_response;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn worktree_conversation_is_merged_on_finish() {
+    let codex_home = TempDir::new().expect("create temp dir");
+    std::fs::write(
+        codex_home.path().join("config.toml"),
+        "model = \"mock-model\"\napproval_policy = \"never\"\n",
+    )
+    .expect("write config.toml");
+    let repo = TempDir::new().expect("create repo dir");
+    init_repo(repo.path());
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("initialize timeout")
+        .expect("initialize request");
+
+    let new_request_id = mcp
+        .send_new_conversation_request(NewConversationParams {
+            cwd: Some(repo.path().to_string_lossy().into_owned()),
+            worktree: Some(true),
+            ..Default::default()
+        })
+        .await
+        .expect("send newConversation");
+    let new_response: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(new_request_id)),
+    )
+    .await
+    .expect("newConversation timeout")
+    .expect("newConversation response");
+    let NewConversationResponse {
+        conversation_id,
+        worktree,
+        ..
+    } = to_response::<NewConversationResponse>(new_response)
+        .expect("deserialize newConversation response");
+    let ConversationWorktree { cwd, branch } = worktree.expect("conversation worktree");
+    assert!(branch.starts_with("codex/"), "{branch}");
+    assert!(cwd.join("README.md").exists());
+    assert!(cwd.starts_with(codex_home.path().join("worktrees")));
+
+    std::fs::write(cwd.join("notes.md"), "from the worktree\n").expect("write notes");
+
+    let finish_request_id = mcp
+        .send_finish_worktree_request(FinishWorktreeParams {
+            conversation_id,
+            action: WorktreeAction::Merge,
+        })
+        .await
+        .expect("send finishWorktree");
+    let finish_response: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(finish_request_id)),
+    )
+    .await
+    .expect("finishWorktree timeout")
+    .expect("finishWorktree response");
+    let _: FinishWorktreeResponse = to_response::<FinishWorktreeResponse>(finish_response)
+        .expect("deserialize finishWorktree response");
+
+    assert_eq!(
+        std::fs::read_to_string(repo.path().join("notes.md")).expect("read notes"),
+        "from the worktree\n"
+    );
+    assert!(!cwd.exists());
+
+    // The worktree is gone, so finishing it again is rejected.
+    let again_request_id = mcp
+        .send_finish_worktree_request(FinishWorktreeParams {
+            conversation_id,
+            action: WorktreeAction::Discard,
+        })
+        .await
+        .expect("send finishWorktree");
+    let error = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_error_message(RequestId::Integer(again_request_id)),
+    )
+    .await
+    .expect("finishWorktree timeout")
+    .expect("finishWorktree error");
+    assert!(
+        error.error.message.contains("has no worktree"),
+        "{}",
+        error.error.message
+    );
+}
+
+#[expect(clippy::expect_used)]
+fn init_repo(repo: &Path) {
+    for args in [
+        &["init", "-q", "--initial-branch=main"][..],
+        &["config", "user.name", "Test"],
+        &["config", "user.email", "test@example.com"],
+    ] {
+        git(repo, args);
+    }
+    std::fs::write(repo.join("README.md"), "readme\n").expect("write readme");
+    git(repo, &["add", "README.md"]);
+    git(repo, &["commit", "-q", "-m", "init"]);
+}
+
+#[expect(clippy::expect_used)]
+fn git(cwd: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .status()
+        .expect("run git");
+    assert!(status.success(), "git {args:?} failed");
+}
@@ -24,12 +24,15 @@ pub fn generate_ts(out_dir: &Path, prettier: Option<&Path>) -> Result<()> {
     codex_protocol::mcp_protocol::NewConversationResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::ListConversationsResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::ResumeConversationResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::ForkConversationResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::ArchiveConversationResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::AddConversationSubscriptionResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::RemoveConversationSubscriptionResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::SendUserMessageResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::SendUserTurnResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::InterruptConversationResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::UndoConversationResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::FinishWorktreeResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::GitDiffToRemoteResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::LoginApiKeyParams::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::LoginApiKeyResponse::export_all_to(out_dir)?;
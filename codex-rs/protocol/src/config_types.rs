@@ -59,3 +59,16 @@ pub enum SandboxMode {
     #[serde(rename = "danger-full-access")]
     DangerFullAccess,
 }
+
+/// What happens to the git worktree a session ran in once it ends.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Serialize, Display, TS)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum WorktreeAction {
+    /// Merge the worktree branch into the original checkout and delete it.
+    Merge,
+    /// Commit the changes to the worktree branch and keep the branch.
+    Keep,
+    /// Delete the worktree and its branch.
+    Discard,
+}
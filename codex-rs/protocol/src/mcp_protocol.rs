@@ -6,11 +6,13 @@ use crate::config_types::ReasoningEffort;
 use crate::config_types::ReasoningSummary;
 use crate::config_types::SandboxMode;
 use crate::config_types::Verbosity;
+use crate::config_types::WorktreeAction;
 use crate::protocol::AskForApproval;
 use crate::protocol::EventMsg;
 use crate::protocol::FileChange;
 use crate::protocol::ReviewDecision;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SessionFork;
 use crate::protocol::TurnAbortReason;
 use mcp_types::RequestId;
 use serde::Deserialize;
@@ -109,6 +111,13 @@ pub enum ClientRequest {
         request_id: RequestId,
         params: ResumeConversationParams,
     },
+    /// Start a new conversation from a recorded one, keeping its history up
+    /// to a user message. The new session records where it branched off.
+    ForkConversation {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: ForkConversationParams,
+    },
     ArchiveConversation {
         #[serde(rename = "id")]
         request_id: RequestId,
@@ -129,6 +138,21 @@ pub enum ClientRequest {
         request_id: RequestId,
         params: InterruptConversationParams,
     },
+    /// Restore the conversation's workspace to an earlier checkpoint. The
+    /// outcome is delivered as a `checkpoint_restored` or `error` event.
+    UndoConversation {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: UndoConversationParams,
+    },
+    /// Merge, keep or discard the git worktree of a conversation started
+    /// with `worktree` set. A conversation that is still running is shut
+    /// down first.
+    FinishWorktree {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: FinishWorktreeParams,
+    },
     AddConversationListener {
         #[serde(rename = "id")]
         request_id: RequestId,
@@ -233,6 +257,12 @@ pub struct NewConversationParams {
     /// Whether to include the apply patch tool in the conversation.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub include_apply_patch_tool: Option<bool>,
+
+    /// Run the conversation in its own git worktree on a new branch.
+    /// Defaults to `worktree.enabled` from config.toml. Ignored when resuming
+    /// or forking a conversation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -244,6 +274,18 @@ pub struct NewConversationResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_effort: Option<ReasoningEffort>,
     pub rollout_path: PathBuf,
+    /// Set when the conversation runs in its own git worktree.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<ConversationWorktree>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationWorktree {
+    /// Working directory of the conversation inside the worktree.
+    pub cwd: PathBuf,
+    /// Branch the conversation's changes are made on.
+    pub branch: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
@@ -255,6 +297,15 @@ pub struct ResumeConversationResponse {
     pub initial_messages: Option<Vec<EventMsg>>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkConversationResponse {
+    pub conversation_id: ConversationId,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_messages: Option<Vec<EventMsg>>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct ListConversationsParams {
@@ -264,6 +315,35 @@ pub struct ListConversationsParams {
     /// Opaque pagination cursor returned by a previous call.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cursor: Option<String>,
+    /// Optional filter; when set, conversations are looked up in the local
+    /// session index instead of being listed from the sessions directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<ConversationFilter>,
+}
+
+/// Criteria for [`ListConversationsParams::filter`]. All criteria that are
+/// set must match.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationFilter {
+    /// Words that must all appear in the user or agent messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Conversations started in this directory or one of its subdirectories.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Conversations started on this git branch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// Conversations in which this model ran at least one turn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Conversations started at or after this time (RFC 3339 or YYYY-MM-DD).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// Conversations started before this time (RFC 3339 or YYYY-MM-DD).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -275,6 +355,9 @@ pub struct ConversationSummary {
     /// RFC3339 timestamp string for the session start, if available.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<String>,
+    /// Where this conversation branched off another one, if it is a fork.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fork: Option<SessionFork>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -297,6 +380,22 @@ pub struct ResumeConversationParams {
     pub overrides: Option<NewConversationParams>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkConversationParams {
+    /// Absolute path to the rollout JSONL file to fork.
+    pub path: PathBuf,
+    /// Keep the history before this user message (0-based) and drop the
+    /// message itself and everything after it.
+    pub nth_user_message: usize,
+    /// Optional name for the new branch, shown next to it in session lists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Optional overrides to apply when spawning the forked session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<NewConversationParams>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct AddConversationSubscriptionResponse {
@@ -513,6 +612,8 @@ pub struct Tools {
     pub web_search: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub view_image: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delegate: Option<bool>,
 }
 
 /// MCP representation of a [`codex_core::config_types::SandboxWorkspaceWrite`].
@@ -566,6 +667,33 @@ pub struct InterruptConversationResponse {
     pub abort_reason: TurnAbortReason,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoConversationParams {
+    pub conversation_id: ConversationId,
+    /// Number of turns to undo. Defaults to 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turns: Option<usize>,
+    /// Restore this checkpoint instead of counting turns back.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoConversationResponse {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishWorktreeParams {
+    pub conversation_id: ConversationId,
+    pub action: WorktreeAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishWorktreeResponse {}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct SendUserMessageResponse {}
@@ -720,6 +848,7 @@ mod tests {
                 base_instructions: None,
                 include_plan_tool: None,
                 include_apply_patch_tool: None,
+                worktree: None,
             },
         };
         assert_eq!(
@@ -133,6 +133,9 @@ pub enum Op {
         id: String,
         /// The user's decision in response to the request.
         decision: ReviewDecision,
+        /// The client through which the decision was made.
+        #[serde(default)]
+        source: ApprovalSource,
     },
 
     /// Approve a code patch
@@ -141,6 +144,9 @@ pub enum Op {
         id: String,
         /// The user's decision in response to the request.
         decision: ReviewDecision,
+        /// The client through which the decision was made.
+        #[serde(default)]
+        source: ApprovalSource,
     },
 
     /// Append an entry to the persistent cross-session message history.
@@ -174,6 +180,31 @@ pub enum Op {
     /// Request a code review from the agent.
     Review { review_request: ReviewRequest },
 
+    /// Restore the workspace to the checkpoint taken before the `turns`-th
+    /// most recent turn, undoing the changes made since then.
+    /// Reply is delivered via `EventMsg::CheckpointRestored`.
+    Undo { turns: usize },
+
+    /// Restore the workspace to the checkpoint with the given id, as recorded
+    /// in a `RolloutItem::Checkpoint`.
+    /// Reply is delivered via `EventMsg::CheckpointRestored`.
+    RestoreCheckpoint { id: String },
+
+    /// Push the current branch and open a pull request for it on the forge
+    /// hosting the repository, with a description written by the agent.
+    /// `base` defaults to the remote's default branch.
+    /// Reply is delivered via `EventMsg::PullRequestOpened`.
+    OpenPullRequest {
+        base: Option<String>,
+        #[serde(default)]
+        draft: bool,
+    },
+
+    /// Fetch the review comments on a pull request and start a turn that
+    /// addresses them. `number` defaults to the open pull request for the
+    /// current branch.
+    AddressReviewComments { number: Option<u64> },
+
     /// Request to shut down codex instance.
     Shutdown,
 }
@@ -507,8 +538,28 @@ pub enum EventMsg {
 
     PlanUpdate(UpdatePlanArgs),
 
+    /// The agent handed a task to a child conversation through the
+    /// `delegate` tool.
+    DelegateBegin(DelegateBeginEvent),
+
+    /// An event of a delegated child conversation, nested under the call id
+    /// of the `delegate` tool call that started it.
+    DelegateEvent(DelegateNestedEvent),
+
+    /// A delegated child conversation finished.
+    DelegateEnd(DelegateEndEvent),
+
     TurnAborted(TurnAbortedEvent),
 
+    /// Notification that the estimated cost of the session or of the day
+    /// crossed a configured budget. A soft budget pauses the task before its
+    /// next model request; a hard budget aborts it.
+    BudgetExceeded(BudgetExceededEvent),
+
+    /// The final message of a turn still did not match the turn's output
+    /// schema after the model was asked to fix it.
+    OutputSchemaMismatch(OutputSchemaMismatchEvent),
+
     /// Notification that the agent is shutting down.
     ShutdownComplete,
 
@@ -519,6 +570,44 @@ pub enum EventMsg {
 
     /// Exited review mode with an optional final result to apply.
     ExitedReviewMode(ExitedReviewModeEvent),
+
+    /// The workspace was restored to a checkpoint in response to `Op::Undo`
+    /// or `Op::RestoreCheckpoint`.
+    CheckpointRestored(CheckpointRestoredEvent),
+
+    /// The files changed by a turn were committed because `auto_commit` is
+    /// enabled. Recorded in the rollout.
+    TurnCommitted(TurnCommittedEvent),
+
+    /// A pull request was opened in response to `Op::OpenPullRequest`.
+    PullRequestOpened(PullRequestOpenedEvent),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct CheckpointRestoredEvent {
+    pub checkpoint_id: String,
+    /// Number of turns whose changes to the workspace were undone.
+    pub turns_undone: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct TurnCommittedEvent {
+    /// Id of the new commit.
+    pub sha: String,
+    /// Full commit message, including the configured trailer.
+    pub message: String,
+    /// Committed files, relative to the repository root.
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct PullRequestOpenedEvent {
+    /// Pull request number (merge request iid on GitLab).
+    pub number: u64,
+    pub url: String,
+    pub title: String,
+    /// Branch the pull request was opened from.
+    pub branch: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -538,12 +627,39 @@ pub struct TaskCompleteEvent {
     pub last_agent_message: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct DelegateBeginEvent {
+    /// Identifier of the `delegate` tool call.
+    pub call_id: String,
+    /// The task handed to the child conversation.
+    pub task: String,
+    /// Model the child conversation runs with.
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct DelegateNestedEvent {
+    /// Identifier of the `delegate` tool call.
+    pub call_id: String,
+    pub msg: Box<EventMsg>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct DelegateEndEvent {
+    /// Identifier of the `delegate` tool call.
+    pub call_id: String,
+    /// The child's final message, returned to the parent as the tool output.
+    pub last_agent_message: Option<String>,
+    /// Whether the child completed its task without errors or interruptions.
+    pub success: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct TaskStartedEvent {
     pub model_context_window: Option<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default, TS)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq, TS)]
 pub struct TokenUsage {
     pub input_tokens: u64,
     pub cached_input_tokens: u64,
@@ -557,6 +673,14 @@ pub struct TokenUsageInfo {
     pub total_token_usage: TokenUsage,
     pub last_token_usage: TokenUsage,
     pub model_context_window: Option<u64>,
+    /// Estimated cost in USD of the session so far, counting only the
+    /// requests made to models with known pricing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
+    /// Whether some of the usage was estimated locally because the provider
+    /// did not report it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub estimated: bool,
 }
 
 impl TokenUsageInfo {
@@ -575,6 +699,8 @@ impl TokenUsageInfo {
                 total_token_usage: TokenUsage::default(),
                 last_token_usage: TokenUsage::default(),
                 model_context_window,
+                total_cost_usd: None,
+                estimated: false,
             },
         };
         if let Some(last) = last {
@@ -587,6 +713,10 @@ impl TokenUsageInfo {
         self.total_token_usage.add_assign(last);
         self.last_token_usage = last.clone();
     }
+
+    pub fn add_cost(&mut self, cost_usd: f64) {
+        *self.total_cost_usd.get_or_insert(0.0) += cost_usd;
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -930,6 +1060,21 @@ pub struct SessionMetaLine {
     pub meta: SessionMeta,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub git: Option<GitInfo>,
+    /// Set when the session was forked from another one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fork: Option<SessionFork>,
+}
+
+/// Where a forked session branched off its parent.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct SessionFork {
+    pub parent_id: ConversationId,
+    /// Number of the parent's user messages the fork kept; the fork
+    /// diverges at the parent's user message with this (0-based) index.
+    pub nth_user_message: usize,
+    /// Name given to the branch when it was forked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
@@ -940,11 +1085,19 @@ pub enum RolloutItem {
     Compacted(CompactedItem),
     TurnContext(TurnContextItem),
     EventMsg(EventMsg),
+    Checkpoint(CheckpointItem),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, TS)]
 pub struct CompactedItem {
     pub message: String,
+    /// Number of most recent user turns kept verbatim after the summary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retained_turns: Option<usize>,
+    /// The history the compaction left behind, restored as-is on resume.
+    /// Rollouts written before it was recorded are rebuilt from `message`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_history: Option<Vec<ResponseItem>>,
 }
 
 impl From<CompactedItem> for ResponseItem {
@@ -970,6 +1123,30 @@ pub struct TurnContextItem {
     pub summary: ReasoningSummaryConfig,
 }
 
+/// Snapshot of the workspace taken before a turn made any changes, or once
+/// it completed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+pub struct CheckpointItem {
+    /// Ghost commit id, or snapshot id in `~/.codex/checkpoints`.
+    pub id: String,
+    pub kind: CheckpointKind,
+    /// Submission id of the turn the checkpoint was taken for.
+    pub turn_id: String,
+    /// True for the snapshot of the workspace a completed turn left behind.
+    /// Only snapshots taken before a turn are undo targets.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub after_turn: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointKind {
+    /// An unreferenced commit of the working tree of a git repository.
+    GhostCommit,
+    /// A copy of the working directory in the content-addressed store.
+    Snapshot,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RolloutLine {
     pub timestamp: String,
@@ -995,6 +1172,28 @@ pub struct GitInfo {
 pub struct ReviewRequest {
     pub prompt: String,
     pub user_facing_hint: String,
+    /// Changes to review. When set, core computes their diff with git and
+    /// hands it to the reviewer along with `prompt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<ReviewTarget>,
+}
+
+/// Changes covered by a review, relative to the session's working directory.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReviewTarget {
+    /// Staged, unstaged and untracked changes against `HEAD`.
+    WorkingTree,
+    /// Changes staged in the index.
+    Staged,
+    /// The changes introduced by a single commit.
+    Commit { sha: String },
+    /// The changes between two revisions, as in `git diff base..head`.
+    CommitRange { base: String, head: String },
+    /// The changes on `HEAD` since it diverged from `branch`.
+    BaseBranch { branch: String },
+    /// The full contents of the given files.
+    Files { paths: Vec<PathBuf> },
 }
 
 /// Structured review result produced by a child review session.
@@ -1070,6 +1269,34 @@ pub struct ExecCommandEndEvent {
     pub duration: Duration,
     /// Formatted output from the command, as seen by the model.
     pub formatted_output: String,
+    /// Operations that the sandbox blocked while the command was running.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sandbox_violations: Vec<SandboxViolation>,
+}
+
+/// An operation that was blocked by the sandbox, as reported by the sandbox
+/// helper.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SandboxViolation {
+    /// Write access to a path outside of the writable roots.
+    FileWrite { syscall: String, path: PathBuf },
+    /// Outbound network access.
+    Network { syscall: String },
+    /// Any other syscall denied by the sandbox, e.g., `ptrace`.
+    Syscall { syscall: String },
+}
+
+impl fmt::Display for SandboxViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SandboxViolation::FileWrite { syscall, path } => {
+                write!(f, "write to {} ({syscall})", path.display())
+            }
+            SandboxViolation::Network { syscall } => write!(f, "network access ({syscall})"),
+            SandboxViolation::Syscall { syscall } => write!(f, "{syscall}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
@@ -1128,6 +1355,72 @@ pub struct StreamErrorEvent {
     pub message: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct BudgetExceededEvent {
+    pub scope: BudgetScope,
+    pub kind: BudgetKind,
+    /// The configured limit in USD.
+    pub limit_usd: f64,
+    /// Estimated spend in USD within `scope`.
+    pub spent_usd: f64,
+}
+
+impl fmt::Display for BudgetExceededEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scope = match self.scope {
+            BudgetScope::Session => "Session",
+            BudgetScope::Day => "Daily",
+        };
+        let action = match self.kind {
+            BudgetKind::Soft => "pausing; send a message to continue",
+            BudgetKind::Hard => "aborting the turn",
+        };
+        write!(
+            f,
+            "{scope} budget reached: spent ${:.2} of the ${:.2} {} limit; {action}.",
+            self.spent_usd, self.limit_usd, self.kind
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct OutputSchemaMismatchEvent {
+    /// How many final messages the model produced for the turn.
+    pub attempts: u32,
+    /// Why the last of them did not match the schema.
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for OutputSchemaMismatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Final message did not match the output schema after {} attempt{}: {}",
+            self.attempts,
+            if self.attempts == 1 { "" } else { "s" },
+            self.errors.join("; ")
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, Display, TS)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum BudgetScope {
+    Session,
+    Day,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Display, TS)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum BudgetKind {
+    /// Crossing the limit pauses the task; the user may continue.
+    Soft,
+    /// Crossing the limit aborts the task and refuses new ones.
+    Hard,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct PatchApplyBeginEvent {
     /// Identifier so this can be paired with the PatchApplyEnd event.
@@ -1225,6 +1518,25 @@ pub enum ReviewDecision {
     Abort,
 }
 
+/// Where a [`ReviewDecision`] came from. Recorded in the audit log so it is
+/// possible to tell who approved an action.
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalSource {
+    /// The interactive terminal UI.
+    Tui,
+
+    /// An MCP client answering an elicitation request from the `codex` tool.
+    McpElicitation,
+
+    /// A client of the MCP server's conversation (app-server) API.
+    AppServer,
+
+    /// The client did not say.
+    #[default]
+    Unknown,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
 #[serde(rename_all = "snake_case")]
 pub enum FileChange {
@@ -1259,6 +1571,7 @@ pub enum TurnAbortReason {
     Interrupted,
     Replaced,
     ReviewEnded,
+    BudgetExceeded,
 }
 
 #[cfg(test)]
@@ -306,7 +306,9 @@ impl App {
         }
     }
 
-    /// Thin wrapper around ConversationManager::fork_conversation.
+    /// Thin wrapper around ConversationManager::fork_conversation. Forks
+    /// made by backtracking are unnamed; branch names can only be given by
+    /// clients through the `forkConversation` request.
     async fn perform_fork(
         &self,
         path: PathBuf,
@@ -314,7 +316,7 @@ impl App {
         cfg: codex_core::config::Config,
     ) -> codex_core::error::Result<codex_core::NewConversation> {
         self.server
-            .fork_conversation(nth_user_message, cfg, path)
+            .fork_conversation(nth_user_message, None, cfg, path)
             .await
     }
 
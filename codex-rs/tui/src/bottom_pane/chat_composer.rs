@@ -1306,8 +1306,13 @@ impl WidgetRef for ChatComposer {
                     hint.push("   ".into());
                     hint.push(
                         Span::from(format!(
-                            "{} tokens used",
-                            format_si_suffix(token_usage.blended_total())
+                            "{} tokens used{}",
+                            format_si_suffix(token_usage.blended_total()),
+                            if token_usage_info.estimated {
+                                " (estimated)"
+                            } else {
+                                ""
+                            }
                         ))
                         .style(Style::default().add_modifier(Modifier::DIM)),
                     );
@@ -15,6 +15,10 @@ use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::BudgetExceededEvent;
+use codex_core::protocol::BudgetKind;
+use codex_core::protocol::DelegateBeginEvent;
+use codex_core::protocol::DelegateEndEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -32,6 +36,7 @@ use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::ReviewRequest;
+use codex_core::protocol::ReviewTarget;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TokenUsage;
@@ -41,6 +46,7 @@ use codex_core::protocol::TurnDiffEvent;
 use codex_core::protocol::UserMessageEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
+use codex_core::review_target::review_request_for_target;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::parse_command::ParsedCommand;
 use crossterm::event::KeyCode;
@@ -104,13 +110,6 @@ use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_file_search::FileMatch;
-use codex_git_tooling::CreateGhostCommitOptions;
-use codex_git_tooling::GhostCommit;
-use codex_git_tooling::GitToolingError;
-use codex_git_tooling::create_ghost_commit;
-use codex_git_tooling::restore_ghost_commit;
-
-const MAX_TRACKED_GHOST_COMMITS: usize = 20;
 
 // Track information about an in-flight exec command.
 struct RunningCommand {
@@ -214,9 +213,6 @@ pub(crate) struct ChatWidget {
     pending_notification: Option<Notification>,
     // Simple review mode flag; used to adjust layout and banners.
     is_review_mode: bool,
-    // List of ghost commits corresponding to each turn.
-    ghost_snapshots: Vec<GhostCommit>,
-    ghost_snapshots_disabled: bool,
 }
 
 struct UserMessage {
@@ -404,7 +400,7 @@ impl ChatWidget {
         // Finalize, log a gentle prompt, and clear running state.
         self.finalize_turn();
 
-        if reason != TurnAbortReason::ReviewEnded {
+        if reason == TurnAbortReason::Interrupted {
             self.add_to_history(history_cell::new_error_event(
                 "Conversation interrupted - tell the model what to do differently".to_owned(),
             ));
@@ -427,6 +423,40 @@ impl ChatWidget {
         self.request_redraw();
     }
 
+    fn on_budget_exceeded(&mut self, ev: BudgetExceededEvent) {
+        let message = ev.to_string();
+        match ev.kind {
+            BudgetKind::Soft => self.add_to_history(history_cell::new_warning_event(message)),
+            BudgetKind::Hard => self.add_to_history(history_cell::new_error_event(message)),
+        }
+        self.request_redraw();
+    }
+
+    fn on_delegate_begin(&mut self, ev: DelegateBeginEvent) {
+        self.add_to_history(history_cell::new_info_event(
+            format!("Delegating: {}", ev.task),
+            Some(ev.model),
+        ));
+        self.request_redraw();
+    }
+
+    fn on_delegate_end(&mut self, ev: DelegateEndEvent) {
+        let message = ev
+            .last_agent_message
+            .unwrap_or_else(|| "(no final message)".to_string());
+        if ev.success {
+            self.add_to_history(history_cell::new_info_event(
+                format!("Delegate finished: {message}"),
+                None,
+            ));
+        } else {
+            self.add_to_history(history_cell::new_warning_event(format!(
+                "Delegate failed: {message}"
+            )));
+        }
+        self.request_redraw();
+    }
+
     fn on_plan_update(&mut self, update: codex_core::plan_tool::UpdatePlanArgs) {
         self.add_to_history(history_cell::new_plan_update(update));
     }
@@ -626,6 +656,9 @@ impl ChatWidget {
                 self.flush_active_exec_cell();
             }
         }
+        if !ev.sandbox_violations.is_empty() {
+            self.add_to_history(history_cell::new_sandbox_violations(&ev.sandbox_violations));
+        }
     }
 
     pub(crate) fn handle_patch_apply_end_now(
@@ -809,8 +842,6 @@ impl ChatWidget {
             suppress_session_configured_redraw: false,
             pending_notification: None,
             is_review_mode: false,
-            ghost_snapshots: Vec::new(),
-            ghost_snapshots_disabled: true,
         }
     }
 
@@ -870,8 +901,6 @@ impl ChatWidget {
             suppress_session_configured_redraw: true,
             pending_notification: None,
             is_review_mode: false,
-            ghost_snapshots: Vec::new(),
-            ghost_snapshots_disabled: true,
         }
     }
 
@@ -1005,7 +1034,16 @@ impl ChatWidget {
                 self.app_event_tx.send(AppEvent::ExitRequest);
             }
             SlashCommand::Undo => {
-                self.undo_last_snapshot();
+                self.submit_op(Op::Undo { turns: 1 });
+            }
+            SlashCommand::Pr => {
+                self.submit_op(Op::OpenPullRequest {
+                    base: None,
+                    draft: false,
+                });
+            }
+            SlashCommand::PrComments => {
+                self.submit_op(Op::AddressReviewComments { number: None });
             }
             SlashCommand::Diff => {
                 self.add_diff_in_progress();
@@ -1121,8 +1159,6 @@ impl ChatWidget {
             return;
         }
 
-        self.capture_ghost_snapshot();
-
         let mut items: Vec<InputItem> = Vec::new();
 
         if !text.is_empty() {
@@ -1154,57 +1190,6 @@ impl ChatWidget {
         }
     }
 
-    fn capture_ghost_snapshot(&mut self) {
-        if self.ghost_snapshots_disabled {
-            return;
-        }
-
-        let options = CreateGhostCommitOptions::new(&self.config.cwd);
-        match create_ghost_commit(&options) {
-            Ok(commit) => {
-                self.ghost_snapshots.push(commit);
-                if self.ghost_snapshots.len() > MAX_TRACKED_GHOST_COMMITS {
-                    self.ghost_snapshots.remove(0);
-                }
-            }
-            Err(err) => {
-                self.ghost_snapshots_disabled = true;
-                let (message, hint) = match &err {
-                    GitToolingError::NotAGitRepository { .. } => (
-                        "Snapshots disabled: current directory is not a Git repository."
-                            .to_string(),
-                        None,
-                    ),
-                    _ => (
-                        format!("Snapshots disabled after error: {err}"),
-                        Some(
-                            "Restart Codex after resolving the issue to re-enable snapshots."
-                                .to_string(),
-                        ),
-                    ),
-                };
-                self.add_info_message(message, hint);
-                tracing::warn!("failed to create ghost snapshot: {err}");
-            }
-        }
-    }
-
-    fn undo_last_snapshot(&mut self) {
-        let Some(commit) = self.ghost_snapshots.pop() else {
-            self.add_info_message("No snapshot available to undo.".to_string(), None);
-            return;
-        };
-
-        if let Err(err) = restore_ghost_commit(&self.config.cwd, &commit) {
-            self.add_error_message(format!("Failed to restore snapshot: {err}"));
-            self.ghost_snapshots.push(commit);
-            return;
-        }
-
-        let short_id: String = commit.id().chars().take(8).collect();
-        self.add_info_message(format!("Restored workspace to snapshot {short_id}"), None);
-    }
-
     /// Replay a subset of initial events into the UI to seed the transcript when
     /// resuming an existing session. This approximates the live event flow and
     /// is intentionally conservative: only safe-to-replay items are rendered to
@@ -1272,11 +1257,19 @@ impl ChatWidget {
                 TurnAbortReason::Replaced => {
                     self.on_error("Turn aborted: replaced by a new task".to_owned())
                 }
-                TurnAbortReason::ReviewEnded => {
+                TurnAbortReason::ReviewEnded | TurnAbortReason::BudgetExceeded => {
                     self.on_interrupted_turn(ev.reason);
                 }
             },
+            EventMsg::BudgetExceeded(ev) => self.on_budget_exceeded(ev),
+            EventMsg::OutputSchemaMismatch(ev) => {
+                self.add_to_history(history_cell::new_error_event(ev.to_string()));
+                self.request_redraw();
+            }
             EventMsg::PlanUpdate(update) => self.on_plan_update(update),
+            EventMsg::DelegateBegin(ev) => self.on_delegate_begin(ev),
+            EventMsg::DelegateEvent(_) => {}
+            EventMsg::DelegateEnd(ev) => self.on_delegate_end(ev),
             EventMsg::ExecApprovalRequest(ev) => {
                 // For replayed events, synthesize an empty id (these should not occur).
                 self.on_exec_approval_request(id.unwrap_or_default(), ev)
@@ -1315,6 +1308,21 @@ impl ChatWidget {
                 self.on_entered_review_mode(review_request)
             }
             EventMsg::ExitedReviewMode(review) => self.on_exited_review_mode(review),
+            EventMsg::CheckpointRestored(ev) => {
+                let short_id: String = ev.checkpoint_id.chars().take(8).collect();
+                self.add_info_message(format!("Restored workspace to snapshot {short_id}"), None);
+            }
+            EventMsg::TurnCommitted(ev) => {
+                let short_sha: String = ev.sha.chars().take(8).collect();
+                let subject = ev.message.lines().next().unwrap_or_default();
+                self.add_info_message(format!("Committed {short_sha} {subject}"), None);
+            }
+            EventMsg::PullRequestOpened(ev) => {
+                self.add_info_message(
+                    format!("Opened pull request #{} {}", ev.number, ev.title),
+                    Some(ev.url),
+                );
+            }
         }
     }
 
@@ -1450,6 +1458,8 @@ impl ChatWidget {
         self.add_to_history(history_cell::new_status_output(
             &self.config,
             usage_ref,
+            self.token_info.as_ref().and_then(|ti| ti.total_cost_usd),
+            self.token_info.as_ref().is_some_and(|ti| ti.estimated),
             &self.conversation_id,
             self.rate_limit_snapshot.as_ref(),
         ));
@@ -1674,16 +1684,11 @@ impl ChatWidget {
             name: "Review uncommitted changes".to_string(),
             description: None,
             is_current: false,
-            actions: vec![Box::new(
-                move |tx: &AppEventSender| {
-                    tx.send(AppEvent::CodexOp(Op::Review {
-                        review_request: ReviewRequest {
-                            prompt: "Review the current code changes (staged, unstaged, and untracked files) and provide prioritized findings.".to_string(),
-                            user_facing_hint: "current changes".to_string(),
-                        },
-                    }));
-                },
-            )],
+            actions: vec![Box::new(move |tx: &AppEventSender| {
+                tx.send(AppEvent::CodexOp(Op::Review {
+                    review_request: review_request_for_target(ReviewTarget::WorkingTree),
+                }));
+            })],
             dismiss_on_select: true,
             search_value: None,
         });
@@ -1751,12 +1756,9 @@ impl ChatWidget {
                 is_current: false,
                 actions: vec![Box::new(move |tx3: &AppEventSender| {
                     tx3.send(AppEvent::CodexOp(Op::Review {
-                        review_request: ReviewRequest {
-                            prompt: format!(
-                                "Review the code changes against the base branch '{branch}'. Start by finding the merge diff between the current branch and {branch}'s upstream e.g. (`git merge-base HEAD \"$(git rev-parse --abbrev-ref \"{branch}@{{upstream}}\")\"`), then run `git diff` against that SHA to see what changes we would merge into the {branch} branch. Provide prioritized, actionable findings."
-                            ),
-                            user_facing_hint: format!("changes against '{branch}'"),
-                        },
+                        review_request: review_request_for_target(ReviewTarget::BaseBranch {
+                            branch: branch.clone(),
+                        }),
                     }));
                 })],
                 dismiss_on_select: true,
@@ -1797,6 +1799,7 @@ impl ChatWidget {
                         review_request: ReviewRequest {
                             prompt,
                             user_facing_hint: hint,
+                            target: Some(ReviewTarget::Commit { sha: sha.clone() }),
                         },
                     }));
                 })],
@@ -1830,6 +1833,7 @@ impl ChatWidget {
                     review_request: ReviewRequest {
                         prompt: trimmed.clone(),
                         user_facing_hint: trimmed,
+                        target: None,
                     },
                 }));
             }),
@@ -2018,6 +2022,7 @@ pub(crate) fn show_review_commit_picker_with_entries(
                     review_request: ReviewRequest {
                         prompt,
                         user_facing_hint: hint,
+                        target: Some(ReviewTarget::Commit { sha: sha.clone() }),
                     },
                 }));
             })],
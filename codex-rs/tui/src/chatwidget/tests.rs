@@ -30,6 +30,7 @@ use codex_core::protocol::ReviewFinding;
 use codex_core::protocol::ReviewLineRange;
 use codex_core::protocol::ReviewOutputEvent;
 use codex_core::protocol::ReviewRequest;
+use codex_core::protocol::SandboxViolation;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TaskStartedEvent;
@@ -201,6 +202,7 @@ fn entered_review_mode_uses_request_hint() {
         msg: EventMsg::EnteredReviewMode(ReviewRequest {
             prompt: "Review the latest changes".to_string(),
             user_facing_hint: "feature branch".to_string(),
+            target: None,
         }),
     });
 
@@ -220,6 +222,7 @@ fn entered_review_mode_defaults_to_current_changes_banner() {
         msg: EventMsg::EnteredReviewMode(ReviewRequest {
             prompt: "Review the current changes".to_string(),
             user_facing_hint: "current changes".to_string(),
+            target: None,
         }),
     });
 
@@ -335,8 +338,6 @@ fn make_chatwidget_manual() -> (
         suppress_session_configured_redraw: false,
         pending_notification: None,
         is_review_mode: false,
-        ghost_snapshots: Vec::new(),
-        ghost_snapshots_disabled: false,
     };
     (widget, rx, op_rx)
 }
@@ -545,6 +546,7 @@ fn end_exec(chat: &mut ChatWidget, call_id: &str, stdout: &str, stderr: &str, ex
             exit_code,
             duration: std::time::Duration::from_millis(5),
             formatted_output: aggregated,
+            sandbox_violations: Vec::new(),
         }),
     });
 }
@@ -680,6 +682,37 @@ fn exec_history_cell_shows_working_then_failed() {
     assert!(blob.to_lowercase().contains("bloop"), "expected error text");
 }
 
+#[test]
+fn exec_end_shows_sandbox_violations() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+
+    begin_exec(&mut chat, "call-3", "touch /etc/blocked");
+    chat.handle_codex_event(Event {
+        id: "call-3".into(),
+        msg: EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+            call_id: "call-3".into(),
+            stdout: String::new(),
+            stderr: "Permission denied".into(),
+            aggregated_output: "Permission denied".into(),
+            exit_code: 1,
+            duration: std::time::Duration::from_millis(5),
+            formatted_output: "Permission denied".into(),
+            sandbox_violations: vec![SandboxViolation::FileWrite {
+                syscall: "openat".into(),
+                path: PathBuf::from("/etc/blocked"),
+            }],
+        }),
+    });
+
+    let cells = drain_insert_history(&mut rx);
+    assert_eq!(cells.len(), 2, "expected exec cell and violations cell");
+    let blob = lines_to_single_string(&cells[1]);
+    assert!(
+        blob.contains("sandbox blocked write to /etc/blocked (openat)"),
+        "expected violation to be shown: {blob:?}"
+    );
+}
+
 /// Selecting the custom prompt option from the review popup sends
 /// OpenReviewCustomPrompt to the app event channel.
 #[test]
@@ -1623,7 +1656,7 @@ fn apply_patch_approval_sends_op_with_submission_id() {
     // Expect a CodexOp with PatchApproval carrying the submission id, not call id
     let mut found = false;
     while let Ok(app_ev) = rx.try_recv() {
-        if let AppEvent::CodexOp(Op::PatchApproval { id, decision }) = app_ev {
+        if let AppEvent::CodexOp(Op::PatchApproval { id, decision, .. }) = app_ev {
             assert_eq!(id, "sub-123");
             assert!(matches!(
                 decision,
@@ -1673,7 +1706,7 @@ fn apply_patch_full_flow_integration_like() {
         .try_recv()
         .expect("expected op forwarded to codex channel");
     match forwarded {
-        Op::PatchApproval { id, decision } => {
+        Op::PatchApproval { id, decision, .. } => {
             assert_eq!(id, "sub-xyz");
             assert!(matches!(
                 decision,
@@ -2038,6 +2071,7 @@ fn chatwidget_exec_and_status_layout_vt100_snapshot() {
             exit_code: 0,
             duration: std::time::Duration::from_millis(16000),
             formatted_output: String::new(),
+            sandbox_violations: Vec::new(),
         }),
     });
     chat.handle_codex_event(Event {
@@ -26,6 +26,7 @@ use codex_core::protocol::FileChange;
 use codex_core::protocol::McpInvocation;
 use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::SandboxViolation;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
@@ -1078,9 +1079,21 @@ pub(crate) fn new_warning_event(message: String) -> PlainHistoryCell {
     }
 }
 
+#[allow(clippy::disallowed_methods)]
+pub(crate) fn new_sandbox_violations(violations: &[SandboxViolation]) -> PlainHistoryCell {
+    PlainHistoryCell {
+        lines: violations
+            .iter()
+            .map(|violation| vec![format!("⚠ sandbox blocked {violation}").yellow()].into())
+            .collect(),
+    }
+}
+
 pub(crate) fn new_status_output(
     config: &Config,
     usage: &TokenUsage,
+    total_cost_usd: Option<f64>,
+    usage_estimated: bool,
     session_id: &Option<ConversationId>,
     rate_limits: Option<&RateLimitSnapshot>,
 ) -> PlainHistoryCell {
@@ -1244,10 +1257,21 @@ pub(crate) fn new_status_output(
         format_with_separators(usage.output_tokens).into(),
     ]));
     // Total: <total>
-    lines.push(Line::from(vec![
+    let mut total_line_spans: Vec<Span<'static>> = vec![
         "  • Total: ".into(),
         format_with_separators(usage.blended_total()).into(),
-    ]));
+    ];
+    if usage_estimated {
+        total_line_spans.push(" (estimated)".dim());
+    }
+    lines.push(Line::from(total_line_spans));
+    // Estimated Cost: $<cost>
+    if let Some(cost) = total_cost_usd {
+        lines.push(Line::from(vec![
+            "  • Estimated Cost: ".into(),
+            format!("${cost:.4}").into(),
+        ]));
+    }
 
     if is_chatgpt_auth {
         lines.push("".into());
@@ -7,6 +7,8 @@ use codex_core::ConversationItem;
 use codex_core::ConversationsPage;
 use codex_core::Cursor;
 use codex_core::RolloutRecorder;
+use codex_core::SessionHit;
+use codex_core::SessionQuery;
 use color_eyre::eyre::Result;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -36,9 +38,11 @@ pub enum ResumeSelection {
     Exit,
 }
 
-/// Interactive session picker that lists recorded rollout files with simple
-/// search and pagination. Shows the first user input as the preview, relative
-/// time (e.g., "5 seconds ago"), and the absolute path.
+/// Interactive session picker that lists recorded rollout files with
+/// pagination and full-text search across all sessions. Shows the first user
+/// input as the preview, relative time (e.g., "5 seconds ago"), and the
+/// absolute path. Sessions forked from another session on the same page are
+/// listed under it as a tree, labelled with their branch name.
 pub async fn run_resume_picker(tui: &mut Tui, codex_home: &Path) -> Result<ResumeSelection> {
     let alt = AltScreenGuard::enter(tui);
     let mut state = PickerState::new(codex_home.to_path_buf(), alt.tui.frame_requester());
@@ -112,6 +116,12 @@ struct Row {
     path: PathBuf,
     preview: String,
     ts: Option<DateTime<Utc>>,
+    id: Option<String>,
+    /// Session this one was forked from, if any.
+    parent_id: Option<String>,
+    branch: Option<String>,
+    /// Nesting level in the branch tree; 0 for sessions shown at the top level.
+    depth: usize,
 }
 
 impl PickerState {
@@ -171,7 +181,7 @@ impl PickerState {
             }
             KeyCode::Backspace => {
                 self.query.pop();
-                self.apply_filter();
+                self.apply_filter().await;
             }
             KeyCode::Char(c) => {
                 // basic text input for search
@@ -181,7 +191,7 @@ impl PickerState {
                     && !key.modifiers.contains(crossterm::event::KeyModifiers::ALT)
                 {
                     self.query.push(c);
-                    self.apply_filter();
+                    self.apply_filter().await;
                 }
             }
             _ => {}
@@ -228,23 +238,39 @@ impl PickerState {
         let page = RolloutRecorder::list_conversations(&self.codex_home, PAGE_SIZE, anchor).await?;
         self.pagination.next_cursor = page.next_cursor.clone();
         self.all_rows = to_rows(page);
-        self.apply_filter();
+        self.apply_filter().await;
         // reset selection on new page
         self.selected = 0;
         Ok(())
     }
 
-    fn apply_filter(&mut self) {
-        if self.query.is_empty() {
+    /// Show the rows matching the query. Non-empty queries search every
+    /// recorded session through the session index, falling back to the
+    /// previews of the current page if the index is unavailable.
+    async fn apply_filter(&mut self) {
+        if self.query.trim().is_empty() {
             self.filtered_rows = self.all_rows.clone();
         } else {
-            let q = self.query.to_lowercase();
-            self.filtered_rows = self
-                .all_rows
-                .iter()
-                .filter(|r| r.preview.to_lowercase().contains(&q))
-                .cloned()
-                .collect();
+            let query = SessionQuery {
+                text: Some(self.query.clone()),
+                limit: PAGE_SIZE,
+                ..Default::default()
+            };
+            match RolloutRecorder::search_conversations(&self.codex_home, query).await {
+                Ok(hits) => {
+                    self.filtered_rows = hits.iter().map(hit_to_row).collect();
+                }
+                Err(err) => {
+                    tracing::warn!("session search failed: {err}");
+                    let q = self.query.to_lowercase();
+                    self.filtered_rows = self
+                        .all_rows
+                        .iter()
+                        .filter(|r| r.preview.to_lowercase().contains(&q))
+                        .cloned()
+                        .collect();
+                }
+            }
         }
         if self.selected >= self.filtered_rows.len() {
             self.selected = self.filtered_rows.len().saturating_sub(1);
@@ -254,7 +280,49 @@ impl PickerState {
 }
 
 fn to_rows(page: ConversationsPage) -> Vec<Row> {
-    page.items.into_iter().map(|it| head_to_row(&it)).collect()
+    let rows = page.items.iter().map(head_to_row).collect();
+    arrange_as_tree(rows)
+}
+
+/// Move every forked session directly below its parent, keeping the backend
+/// order among siblings. Forks whose parent is not in `rows` stay at the top
+/// level.
+fn arrange_as_tree(rows: Vec<Row>) -> Vec<Row> {
+    let parent_index = |row: &Row| {
+        let parent_id = row.parent_id.as_deref()?;
+        rows.iter()
+            .position(|candidate| candidate.id.as_deref() == Some(parent_id))
+    };
+    let parents: Vec<Option<usize>> = rows.iter().map(parent_index).collect();
+
+    let mut ordered = Vec::with_capacity(rows.len());
+    let mut visited = vec![false; rows.len()];
+    let mut stack: Vec<(usize, usize)> = (0..rows.len())
+        .rev()
+        .filter(|&i| parents[i].is_none())
+        .map(|i| (i, 0))
+        .collect();
+    while let Some((index, depth)) = stack.pop() {
+        if std::mem::replace(&mut visited[index], true) {
+            continue;
+        }
+        ordered.push(Row {
+            depth,
+            ..rows[index].clone()
+        });
+        for child in (0..rows.len()).rev() {
+            if parents[child] == Some(index) {
+                stack.push((child, depth + 1));
+            }
+        }
+    }
+    // Rows caught in a parent cycle are unreachable from any root.
+    for (index, row) in rows.iter().enumerate() {
+        if !visited[index] {
+            ordered.push(row.clone());
+        }
+    }
+    ordered
 }
 
 fn head_to_row(item: &ConversationItem) -> Row {
@@ -271,10 +339,36 @@ fn head_to_row(item: &ConversationItem) -> Row {
         .filter(|s| !s.is_empty())
         .unwrap_or_else(|| String::from("(no message yet)"));
 
+    let meta = item.head.first();
+    let meta_str =
+        |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).map(ToString::to_string);
+    let fork = meta.and_then(|m| m.get("fork"));
+
     Row {
         path: item.path.clone(),
         preview,
         ts,
+        id: meta_str(meta.and_then(|m| m.get("id"))),
+        parent_id: meta_str(fork.and_then(|f| f.get("parent_id"))),
+        branch: meta_str(fork.and_then(|f| f.get("name"))),
+        depth: 0,
+    }
+}
+
+fn hit_to_row(hit: &SessionHit) -> Row {
+    let ts = hit
+        .timestamp
+        .as_deref()
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|parsed| parsed.with_timezone(&Utc));
+    Row {
+        path: hit.path.clone(),
+        preview: hit.preview.clone(),
+        ts,
+        id: Some(hit.conversation_id.to_string()),
+        parent_id: None,
+        branch: None,
+        depth: 0,
     }
 }
 
@@ -337,7 +431,7 @@ fn draw_picker(tui: &mut Tui, state: &PickerState) -> std::io::Result<()> {
 
         // Search line
         let q = if state.query.is_empty() {
-            "Type to search".dim().to_string()
+            "Type to search all sessions".dim().to_string()
         } else {
             format!("Search: {}", state.query)
         };
@@ -385,10 +479,23 @@ fn render_list(frame: &mut crate::custom_terminal::Frame, area: Rect, state: &Pi
             .map(human_time_ago)
             .unwrap_or_else(|| "".to_string())
             .dim();
-        let max_cols = area.width.saturating_sub(6) as usize;
+        let mut spans = vec![marker, ts, "  ".into()];
+        let mut used_cols = 6;
+        if row.depth > 0 {
+            let guide = format!("{}└ ", "  ".repeat(row.depth - 1));
+            used_cols += guide.chars().count();
+            spans.push(guide.dim());
+        }
+        if let Some(branch) = &row.branch {
+            used_cols += branch.chars().count() + 2;
+            spans.push(format!("[{branch}]").cyan());
+            spans.push(" ".into());
+        }
+        let max_cols = (area.width as usize).saturating_sub(used_cols);
         let preview = truncate_text(&row.preview, max_cols);
+        spans.push(preview.into());
 
-        let line: Line = vec![marker, ts, "  ".into(), preview.into()].into();
+        let line: Line = spans.into();
         let rect = Rect::new(area.x, y, area.width, 1);
         frame.render_widget_ref(line, rect);
         y = y.saturating_add(1);
@@ -494,4 +601,58 @@ mod tests {
         assert!(rows[0].preview.contains('A'));
         assert!(rows[1].preview.contains('B'));
     }
+
+    fn session_head(id: &str, fork: Option<(&str, &str)>, text: &str) -> Vec<serde_json::Value> {
+        let mut head = head_with_ts_and_user_text("2025-01-01T00:00:00Z", &[text]);
+        head[0]["id"] = json!(id);
+        if let Some((parent_id, name)) = fork {
+            head[0]["fork"] = json!({
+                "parent_id": parent_id,
+                "nth_user_message": 1,
+                "name": name,
+            });
+        }
+        head
+    }
+
+    #[test]
+    fn to_rows_nests_forks_under_their_parent() {
+        // Newest first: both forks were written after their parent.
+        let items = vec![
+            ("fork-b", Some(("root", "retry")), "B"),
+            ("other", None, "other"),
+            ("fork-a", Some(("root", "alt")), "A"),
+            ("nested", Some(("fork-a", "deeper")), "nested"),
+            ("root", None, "root"),
+            ("orphan", Some(("missing", "lost")), "orphan"),
+        ]
+        .into_iter()
+        .map(|(id, fork, text)| ConversationItem {
+            path: PathBuf::from(format!("/tmp/{id}.jsonl")),
+            head: session_head(id, fork, text),
+        })
+        .collect();
+        let rows = to_rows(ConversationsPage {
+            items,
+            next_cursor: None,
+            num_scanned_files: 0,
+            reached_scan_cap: false,
+        });
+
+        let tree: Vec<(&str, usize, Option<&str>)> = rows
+            .iter()
+            .map(|row| (row.id.as_deref().unwrap(), row.depth, row.branch.as_deref()))
+            .collect();
+        assert_eq!(
+            tree,
+            vec![
+                ("other", 0, None),
+                ("root", 0, None),
+                ("fork-b", 1, Some("retry")),
+                ("fork-a", 1, Some("alt")),
+                ("nested", 2, Some("deeper")),
+                ("orphan", 0, Some("lost")),
+            ]
+        );
+    }
 }
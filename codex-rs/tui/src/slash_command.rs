@@ -19,6 +19,8 @@ pub enum SlashCommand {
     Init,
     Compact,
     Undo,
+    Pr,
+    PrComments,
     Diff,
     Mention,
     Status,
@@ -38,6 +40,8 @@ impl SlashCommand {
             SlashCommand::Compact => "summarize conversation to prevent hitting the context limit",
             SlashCommand::Review => "review my current changes and find issues",
             SlashCommand::Undo => "restore the workspace to the last Codex snapshot",
+            SlashCommand::Pr => "push this branch and open a pull request",
+            SlashCommand::PrComments => "address the review comments on this branch's pull request",
             SlashCommand::Quit => "exit Codex",
             SlashCommand::Diff => "show git diff (including untracked files)",
             SlashCommand::Mention => "mention a file",
@@ -64,6 +68,8 @@ impl SlashCommand {
             | SlashCommand::Init
             | SlashCommand::Compact
             | SlashCommand::Undo
+            | SlashCommand::Pr
+            | SlashCommand::PrComments
             | SlashCommand::Model
             | SlashCommand::Approvals
             | SlashCommand::Review